@@ -0,0 +1,62 @@
+import { Program } from "@coral-xyz/anchor";
+import { Counter } from "../target/types/counter";
+
+export interface Version {
+  major: number;
+  minor: number;
+  patch: number;
+}
+
+export function formatVersion(v: Version): string {
+  return `${v.major}.${v.minor}.${v.patch}`;
+}
+
+// The version this client was generated for: the VERSION constant the
+// program published in its IDL.
+export function expectedVersion(program: Program<Counter>): Version {
+  const constant = program.idl.constants.find((c) => c.name === "VERSION");
+  const [major, minor, patch] = JSON.parse(constant.value)
+    .split(".")
+    .map(Number);
+  return { major, minor, patch };
+}
+
+// Asks the deployed program for its version. This only simulates the
+// transaction, so it costs nothing and needs no signature.
+export async function deployedVersion(
+  program: Program<Counter>
+): Promise<Version> {
+  return await program.methods.getVersion().view();
+}
+
+// Semantic versioning: a client works with a deployed program that has the
+// same major version and at least the client's minor version (newer minor
+// versions only add things). Before 1.0.0, every minor version may break
+// things, so the minor version has to match exactly.
+export function isCompatible(deployed: Version, expected: Version): boolean {
+  if (deployed.major !== expected.major) {
+    return false;
+  }
+  if (expected.major === 0) {
+    return deployed.minor === expected.minor;
+  }
+  return deployed.minor >= expected.minor;
+}
+
+// Compares the deployed program's version with this client's and warns if
+// they're incompatible. Returns the deployed version.
+export async function checkProgramVersion(
+  program: Program<Counter>,
+  warn: (message: string) => void = console.warn
+): Promise<Version> {
+  const deployed = await deployedVersion(program);
+  const expected = expectedVersion(program);
+  if (!isCompatible(deployed, expected)) {
+    warn(
+      `counter program at ${program.programId.toBase58()} is version ` +
+        `${formatVersion(deployed)}, but this client was built for ` +
+        `${formatVersion(expected)}`
+    );
+  }
+  return deployed;
+}
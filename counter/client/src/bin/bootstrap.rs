@@ -0,0 +1,139 @@
+// Sets up a localnet or devnet environment for integration tests, so
+// nobody has to do it by hand:
+//
+//     anchor deploy --provider.cluster devnet
+//     cargo run -p counter-client --bin bootstrap -- \
+//         --cluster devnet --counters 5 > manifest.json
+//
+// Deploying is left to "anchor deploy": the tool only checks the program
+// is there. Then it airdrops to --keypair if it's low on SOL, creates the
+// Config (with --keypair as admin unless --admin is given; --keypair must
+// be the upgrade authority), and creates --counters demo counters owned by
+// --keypair. Counters have no on-chain names, so each one's label
+// ("demo-0", "demo-1", ...) is only in the manifest.
+//
+// The manifest is JSON on stdout; progress goes to stderr. Running it
+// again reuses the Config and adds new counters.
+use anyhow::{bail, Context};
+use clap::Parser;
+use counter_client::{Cluster, CounterClient};
+use serde::Serialize;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+};
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    // Pays for everything and owns the demo counters.
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    // The Config's admin. Defaults to --keypair.
+    #[arg(long)]
+    admin: Option<Pubkey>,
+    #[arg(long, default_value_t = 3)]
+    counters: usize,
+    // Airdrop when --keypair has less than this many SOL.
+    #[arg(long, default_value_t = 2)]
+    min_sol: u64,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    url: String,
+    program_id: String,
+    authority: String,
+    config: String,
+    counters: Vec<DemoCounter>,
+}
+
+#[derive(Serialize)]
+struct DemoCounter {
+    label: String,
+    address: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    if args.cluster == Cluster::Mainnet {
+        bail!("bootstrap is for localnet and devnet only");
+    }
+    let url = args.url.as_deref().unwrap_or(args.cluster.default_url());
+    let client = CounterClient::new(url, args.cluster.program_id());
+    let keypair_path = expand_home(&args.keypair);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("reading keypair {keypair_path}: {err}"))?;
+
+    let program = client
+        .rpc
+        .get_account(&client.program_id)
+        .with_context(|| format!("program {} isn't deployed", client.program_id))?;
+    if !program.executable {
+        bail!("{} isn't a program", client.program_id);
+    }
+
+    let balance = client.rpc.get_balance(&payer.pubkey())?;
+    if balance < args.min_sol * LAMPORTS_PER_SOL {
+        let signature = client
+            .rpc
+            .request_airdrop(&payer.pubkey(), args.min_sol * LAMPORTS_PER_SOL)?;
+        client
+            .rpc
+            .poll_for_signature_confirmation(&signature, 1)
+            .context("waiting for the airdrop")?;
+        eprintln!("airdropped {} SOL: {signature}", args.min_sol);
+    }
+
+    let config = client.config_address();
+    if client.rpc.get_account(&config).is_ok() {
+        eprintln!("config {config} already exists");
+    } else {
+        let admin = args.admin.unwrap_or(payer.pubkey());
+        let signature = client.send(
+            &payer,
+            &[client.initialize_config_ix(&payer.pubkey(), admin)],
+        )?;
+        eprintln!("created config {config}: {signature}");
+    }
+
+    let mut counters = Vec::with_capacity(args.counters);
+    for i in 0..args.counters {
+        let counter = Keypair::new();
+        let signature = client.send_with_signers(
+            &payer,
+            &[&counter],
+            &[client.initialize_ix(&counter.pubkey(), &payer.pubkey())],
+        )?;
+        eprintln!("created counter {}: {signature}", counter.pubkey());
+        counters.push(DemoCounter {
+            label: format!("demo-{i}"),
+            address: counter.pubkey().to_string(),
+        });
+    }
+
+    let manifest = Manifest {
+        url: url.to_string(),
+        program_id: client.program_id.to_string(),
+        authority: payer.pubkey().to_string(),
+        config: config.to_string(),
+        counters,
+    };
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    Ok(())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
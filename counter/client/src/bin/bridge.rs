@@ -0,0 +1,297 @@
+// Republishes the program's events to NATS JetStream (see bridge.rs):
+//
+//     nats stream add COUNTER --subjects 'counter.events.>'
+//     cargo run -p counter-client --bin bridge -- \
+//         --cluster mainnet --nats 127.0.0.1:4222
+//
+// Every poll it lists the program's transactions since --checkpoint,
+// oldest first, and publishes the events in their logs, waiting for
+// JetStream to acknowledge each one. An event that can't be decoded, or
+// isn't acknowledged after --max-attempts tries, goes to
+// --dead-letter-subject with the reason. The checkpoint moves past a
+// transaction once all its events are acknowledged or dead-lettered; if
+// even dead-lettering fails, the bridge exits and the next run resumes
+// from the last checkpoint.
+//
+// Without a checkpoint it starts at the program's latest transaction:
+// there's no backfill of older events.
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    path::PathBuf,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use counter_client::{
+    bridge::{decode_event, hpub, parse_ack, parse_msg_line, Checkpoint, Decoded, Envelope},
+    simulate::event_data,
+    Cluster, CounterClient,
+};
+use serde_json::{json, Value};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    // The NATS server, as host:port.
+    #[arg(long, default_value = "127.0.0.1:4222")]
+    nats: String,
+    // Events go to "<prefix>.<event>", e.g. counter.events.fee_paid.
+    #[arg(long, default_value = "counter.events")]
+    subject_prefix: String,
+    // Defaults to "<prefix>.dead_letter".
+    #[arg(long)]
+    dead_letter_subject: Option<String>,
+    #[arg(long, default_value_t = 5)]
+    max_attempts: u32,
+
+    #[arg(long, default_value = "bridge-checkpoint.json")]
+    checkpoint: PathBuf,
+    #[arg(long, default_value_t = 5)]
+    interval_secs: u64,
+    // Publish what's new once and exit, instead of polling forever.
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+    let dead_letter = args
+        .dead_letter_subject
+        .clone()
+        .unwrap_or_else(|| format!("{}.dead_letter", args.subject_prefix));
+    let mut nats = Nats::connect(&args.nats)?;
+
+    let mut checkpoint: Option<Checkpoint> = match fs::read(&args.checkpoint) {
+        Ok(bytes) => Some(
+            serde_json::from_slice(&bytes)
+                .with_context(|| format!("parsing {}", args.checkpoint.display()))?,
+        ),
+        Err(_) => None,
+    };
+
+    loop {
+        for (signature, slot) in new_transactions(&client, checkpoint.as_ref())? {
+            if checkpoint.is_some() {
+                let logs = transaction_logs(&client, &signature)?;
+                publish_events(&mut nats, &args, &dead_letter, &signature, slot, &logs)?;
+            }
+            let next = Checkpoint { signature, slot };
+            fs::write(&args.checkpoint, serde_json::to_vec_pretty(&next)?)?;
+            checkpoint = Some(next);
+        }
+        if args.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(args.interval_secs));
+    }
+}
+
+// Publishes one transaction's events, dead-lettering those that can't be.
+// Fails only if dead-lettering does too.
+fn publish_events(
+    nats: &mut Nats,
+    args: &Args,
+    dead_letter: &str,
+    signature: &str,
+    slot: u64,
+    logs: &[String],
+) -> anyhow::Result<()> {
+    for (index, data) in event_data(logs).iter().enumerate() {
+        let (event, fields) = match decode_event(data) {
+            Decoded::Event(event, fields) => (event, fields),
+            Decoded::Malformed(event) => {
+                let envelope = Envelope::new(signature, slot, index, event, json!({}));
+                let reason = "malformed event".to_string();
+                nats.dead_letter(dead_letter, &envelope, reason)?;
+                continue;
+            }
+            Decoded::Other => continue,
+        };
+        let envelope = Envelope::new(signature, slot, index, event, fields);
+        let subject = format!("{}.{event}", args.subject_prefix);
+        let payload = serde_json::to_value(&envelope)?;
+        let mut attempt = 1;
+        while let Err(err) = nats.publish(&subject, &envelope.id, &payload) {
+            tracing::warn!(id = envelope.id, attempt, error = %err, "publish failed");
+            if attempt >= args.max_attempts {
+                nats.dead_letter(dead_letter, &envelope, format!("{err:#}"))?;
+                break;
+            }
+            thread::sleep(Duration::from_millis(200 << attempt.min(6)));
+            attempt += 1;
+        }
+    }
+    Ok(())
+}
+
+// The program's successful transactions after "since", oldest first. With
+// no checkpoint, only the latest one, to start from.
+fn new_transactions(
+    client: &CounterClient,
+    since: Option<&Checkpoint>,
+) -> anyhow::Result<Vec<(String, u64)>> {
+    let until = since
+        .map(|checkpoint| Signature::from_str(&checkpoint.signature))
+        .transpose()?;
+    let mut found = Vec::new();
+    let mut before = None;
+    loop {
+        let page = client.rpc.get_signatures_for_address_with_config(
+            &client.program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(if since.is_some() { 1000 } else { 1 }),
+                commitment: Some(client.rpc.commitment()),
+            },
+        )?;
+        let Some(last) = page.last() else { break };
+        before = Some(Signature::from_str(&last.signature)?);
+        let full = page.len() == 1000;
+        found.extend(page.into_iter().filter(|status| status.err.is_none()));
+        if since.is_none() || !full {
+            break;
+        }
+    }
+    Ok(found
+        .into_iter()
+        .rev()
+        .map(|status| (status.signature, status.slot))
+        .collect())
+}
+
+fn transaction_logs(client: &CounterClient, signature: &str) -> anyhow::Result<Vec<String>> {
+    let transaction = client.rpc.get_transaction_with_config(
+        &Signature::from_str(signature)?,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(client.rpc.commitment()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let logs = transaction.transaction.meta.map(|meta| meta.log_messages);
+    Ok(match logs {
+        Some(OptionSerializer::Some(logs)) => logs,
+        _ => Vec::new(),
+    })
+}
+
+// A connection to a NATS server, publishing to JetStream.
+struct Nats {
+    address: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    inbox: String,
+    next_reply: u64,
+}
+
+impl Nats {
+    fn connect(address: &str) -> anyhow::Result<Nats> {
+        let stream = TcpStream::connect(address)
+            .with_context(|| format!("connecting to NATS at {address}"))?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut nats = Nats {
+            address: address.to_string(),
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            inbox: format!("_INBOX.bridge-{}", std::process::id()),
+            next_reply: 0,
+        };
+        // The server greets with INFO first.
+        nats.read_line()?;
+        let connect = json!({ "verbose": false, "headers": true, "no_responders": true });
+        write!(
+            nats.writer,
+            "CONNECT {connect}\r\nSUB {}.* 1\r\n",
+            nats.inbox
+        )?;
+        Ok(nats)
+    }
+
+    // Publishes and waits for JetStream's acknowledgement. After a failure
+    // it reconnects, in case that's what broke.
+    fn publish(&mut self, subject: &str, msg_id: &str, payload: &Value) -> anyhow::Result<u64> {
+        let result = self.try_publish(subject, msg_id, payload);
+        if result.is_err() {
+            if let Ok(fresh) = Nats::connect(&self.address) {
+                *self = fresh;
+            }
+        }
+        result
+    }
+
+    // Publishes "envelope" to the dead-letter subject, with why it's
+    // there.
+    fn dead_letter(
+        &mut self,
+        subject: &str,
+        envelope: &Envelope,
+        reason: String,
+    ) -> anyhow::Result<()> {
+        tracing::warn!(id = envelope.id, reason, "dead-lettering event");
+        let payload = json!({ "envelope": envelope, "reason": reason });
+        self.publish(subject, &envelope.id, &payload)
+            .with_context(|| format!("dead-lettering {} to {subject}", envelope.id))?;
+        Ok(())
+    }
+
+    fn try_publish(&mut self, subject: &str, msg_id: &str, payload: &Value) -> anyhow::Result<u64> {
+        self.next_reply += 1;
+        let reply = format!("{}.{}", self.inbox, self.next_reply);
+        self.writer.write_all(&hpub(
+            subject,
+            &reply,
+            msg_id,
+            payload.to_string().as_bytes(),
+        ))?;
+        loop {
+            let line = self.read_line()?;
+            if line == "PING" {
+                self.writer.write_all(b"PONG\r\n")?;
+                continue;
+            }
+            if let Some(error) = line.strip_prefix("-ERR") {
+                bail!("NATS error:{error}");
+            }
+            let Some(msg) = parse_msg_line(&line) else {
+                continue;
+            };
+            let mut body = vec![0; msg.total_len + 2];
+            self.reader.read_exact(&mut body)?;
+            // Acknowledgements of earlier, abandoned attempts are skipped.
+            if msg.subject != reply {
+                continue;
+            }
+            let headers = String::from_utf8_lossy(&body[..msg.header_len]);
+            return parse_ack(&headers, &body[msg.header_len..msg.total_len])
+                .map_err(|err| anyhow::anyhow!(err));
+        }
+    }
+
+    fn read_line(&mut self) -> anyhow::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("NATS closed the connection");
+        }
+        Ok(line.trim_end().to_string())
+    }
+}
@@ -0,0 +1,674 @@
+// A command line for everyday work with counters:
+//
+//     cargo run -p counter-client --bin counter-cli -- --cluster devnet show <ADDRESS>
+//     cargo run -p counter-client --bin counter-cli -- --cluster devnet increment <ADDRESS>
+//     cargo run -p counter-client --bin counter-cli -- --cluster devnet simulate increment <ADDRESS>
+//
+// Commands that send a transaction can be put behind "simulate", which
+// runs the same transaction through simulateTransaction instead (see
+// simulate.rs): it prints the logs, events and compute units, and the
+// count the counter would have, without sending anything or paying fees.
+//
+// Settings can come from a profile in ~/.config/counter-cli/config.toml
+// (see profile.rs) instead of flags: "--profile mainnet show". With a
+// default counter in the profile, the address can be left out too.
+//
+// "show" and "list" save what they read to a local cache (see cache.rs),
+// and "--offline show" answers from it without a connection. "watch" keeps
+// the cache current as counters change.
+//
+// Latency-critical sends can go through Jito's block engine with a tip:
+// "--jito --jito-tip 50000 increment".
+//
+// For scripts, "--output json" prints results and errors as JSON, and the
+// exit code tells failures apart (see output.rs).
+//
+// Keypairs can be kept encrypted in ~/.config/counter-cli/keys (see
+// keystore.rs) and used as "--keypair keystore:<NAME>", in a profile too:
+//
+//     cargo run -p counter-client --bin counter-cli -- keys import ops ~/.config/solana/ops.json
+//     cargo run -p counter-client --bin counter-cli -- keys list
+//
+// The passphrase is prompted for, or read from COUNTER_CLI_PASSPHRASE.
+//
+// Counters whose authority is a Squads multisig vault are changed through
+// proposals (see squads.rs). "multisig propose" builds the admin
+// instruction with the vault as authority and proposes it, signed by
+// --keypair as a member:
+//
+//     cargo run -p counter-client --bin counter-cli -- --cluster mainnet \
+//         multisig --multisig <MULTISIG> propose withdraw-vault <COUNTER> 1000000
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anchor_lang::Discriminator;
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand};
+use counter::Counter;
+use counter_client::{
+    cache::Cache,
+    jito::{self, Jito},
+    keystore::{EncryptedKey, Keystore, ITERATIONS, PASSPHRASE_ENV},
+    output::{ErrorReport, Output},
+    profile::{ConfigFile, Profile},
+    simulate::{describe_event, event_data},
+    squads, Cluster, CounterClient,
+};
+use serde_json::{json, Value};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    pubsub_client::PubsubClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentLevel,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair},
+    signer::Signer,
+};
+
+#[derive(Parser)]
+struct Args {
+    // A profile from the config file. Flags below override its settings.
+    #[arg(long)]
+    profile: Option<String>,
+    // Defaults to ~/.config/counter-cli/config.toml.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[arg(long, value_enum)]
+    cluster: Option<Cluster>,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+    // Overrides the cluster's program id, e.g. for a local deploy.
+    #[arg(long)]
+    program_id: Option<Pubkey>,
+    // Pays for (and signs) transactions: a keypair file, or
+    // "keystore:<NAME>". Defaults to ~/.config/solana/id.json.
+    #[arg(long)]
+    keypair: Option<String>,
+    // processed, confirmed (the default) or finalized.
+    #[arg(long)]
+    commitment: Option<CommitmentLevel>,
+    // Sends transactions as Jito bundles with a tip, falling back to the
+    // RPC node (see jito.rs). Mainnet only.
+    #[arg(long)]
+    jito: bool,
+    #[arg(long, default_value = jito::MAINNET_BLOCK_ENGINE)]
+    jito_url: String,
+    // Lamports tipped per bundle.
+    #[arg(long, default_value_t = 10_000)]
+    jito_tip: u64,
+    // Answers "show" and "list" from the local cache, without RPC calls.
+    #[arg(long)]
+    offline: bool,
+    // "json" prints one JSON value per run, errors included (see
+    // output.rs).
+    #[arg(long, value_enum, default_value = "text")]
+    output: Output,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    // Prints a counter.
+    Show {
+        counter: Option<Pubkey>,
+    },
+    // Lists the program's counters.
+    List {
+        #[arg(long)]
+        include_archived: bool,
+    },
+    // Keeps the local cache current by subscribing to the program's
+    // account changes, printing each one, until interrupted.
+    Watch,
+    #[command(flatten)]
+    Send(Action),
+    // Runs an action through simulateTransaction instead of sending it.
+    Simulate {
+        #[command(subcommand)]
+        action: Action,
+    },
+    // Works with counters owned by a Squads multisig vault.
+    Multisig {
+        #[arg(long)]
+        multisig: Pubkey,
+        // Which of the multisig's vaults owns the counters.
+        #[arg(long, default_value_t = 0)]
+        vault_index: u8,
+        #[command(subcommand)]
+        command: Multisig,
+    },
+    // Manages the encrypted keystore.
+    Keys {
+        #[command(subcommand)]
+        command: Keys,
+    },
+}
+
+#[derive(Subcommand)]
+enum Multisig {
+    // Proposes an admin action as a new multisig transaction, and
+    // approves it as --keypair.
+    Propose {
+        // Shown in the Squads app next to the transaction.
+        #[arg(long)]
+        memo: Option<String>,
+        #[command(subcommand)]
+        action: AdminAction,
+    },
+}
+
+// Commands that change a counter's settings. The authority signing them is
+// the multisig's vault.
+#[derive(Subcommand)]
+enum AdminAction {
+    // Charges a fee per increment (see set_increment_fee). The first time,
+    // the vault pays the rent of the counter's fees account, so it needs
+    // some SOL.
+    SetIncrementFee {
+        counter: Option<Pubkey>,
+        fee_lamports: u64,
+        #[arg(long, default_value_t = 0)]
+        referral_bps: u16,
+    },
+    // Withdraws lamports, such as collected fees, from a counter's vault.
+    WithdrawVault {
+        counter: Option<Pubkey>,
+        lamports: u64,
+    },
+}
+
+impl AdminAction {
+    fn build(
+        &self,
+        client: &CounterClient,
+        authority: &Pubkey,
+        default: Option<Pubkey>,
+    ) -> anyhow::Result<Instruction> {
+        Ok(match self {
+            AdminAction::SetIncrementFee {
+                counter,
+                fee_lamports,
+                referral_bps,
+            } => client.set_increment_fee_ix(
+                &pick(*counter, default)?,
+                authority,
+                *fee_lamports,
+                *referral_bps,
+            ),
+            AdminAction::WithdrawVault { counter, lamports } => {
+                client.withdraw_vault_ix(&pick(*counter, default)?, authority, *lamports)
+            }
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum Keys {
+    // Encrypts a keypair file into the keystore. The file itself is left
+    // alone: delete it once the import is checked.
+    Import { name: String, keypair: String },
+    // Decrypts a key into a plaintext keypair file, readable only by its
+    // owner.
+    Export { name: String, keypair: String },
+    // Lists the keystore's keys and their public keys.
+    List,
+}
+
+// Commands that send a transaction.
+#[derive(Subcommand)]
+enum Action {
+    // Adds 1 to a counter.
+    Increment { counter: Option<Pubkey> },
+}
+
+impl Action {
+    // The instructions to send, and the counter they change.
+    fn build(
+        &self,
+        client: &CounterClient,
+        default: Option<Pubkey>,
+    ) -> anyhow::Result<(Vec<Instruction>, Pubkey)> {
+        match self {
+            Action::Increment { counter } => {
+                let counter = pick(*counter, default)?;
+                Ok((vec![client.increment_ix(&counter)], counter))
+            }
+        }
+    }
+}
+
+fn main() -> process::ExitCode {
+    let args = Args::parse();
+    // Only warnings by default: stdout is for results.
+    counter_client::logging::init("warn");
+    match run(&args) {
+        Ok(()) => process::ExitCode::SUCCESS,
+        Err(err) => {
+            let report = ErrorReport::from_error(&err);
+            match args.output {
+                Output::Text => eprintln!("error: {err:#}"),
+                Output::Json => println!("{}", json!({ "error": report })),
+            }
+            process::ExitCode::from(report.exit_code() as u8)
+        }
+    }
+}
+
+fn run(args: &Args) -> anyhow::Result<()> {
+    // Keys commands don't talk to a cluster, so they need no profile.
+    if let Command::Keys { command } = &args.command {
+        return keys(command, args.output);
+    }
+    let profile = load_config(args.config.clone())?
+        .select(args.profile.as_deref())?
+        .merge(Profile {
+            cluster: args.cluster,
+            url: args.url.clone(),
+            keypair: args.keypair.clone(),
+            commitment: args.commitment,
+            program_id: args.program_id,
+            counter: None,
+        });
+    let settings = profile.settings()?;
+    let client =
+        CounterClient::new_with_commitment(&settings.url, settings.program_id, settings.commitment);
+    let cache_path = PathBuf::from(expand_home(&format!(
+        "~/.cache/counter-cli/{}.json",
+        settings.program_id
+    )));
+    // A broken cache only matters when it's all there is.
+    let mut cache = match Cache::load(&cache_path) {
+        Ok(cache) => cache,
+        Err(err) if !args.offline => {
+            tracing::warn!(error = %err, "ignoring the cache");
+            Cache::default()
+        }
+        Err(err) => return Err(err.into()),
+    };
+    // Prints "value" for --output json, or "text" otherwise.
+    let emit = |value: Value, text: String| match args.output {
+        Output::Text => print!("{text}"),
+        Output::Json => println!("{value}"),
+    };
+
+    match &args.command {
+        Command::Show { counter } => {
+            let address = pick(*counter, settings.counter)?;
+            let (state, slot) = if args.offline {
+                cache
+                    .get(&address)
+                    .with_context(|| format!("{address} isn't in the cache"))?
+            } else {
+                let (state, slot) = client.fetch_counter(&address)?;
+                cache.put_counter(&address, slot, &state);
+                save_cache(&cache, &cache_path);
+                (state, slot)
+            };
+            let read = if args.offline { "cached" } else { "read" };
+            emit(
+                json!({
+                    "address": address.to_string(),
+                    "count": state.count,
+                    "authority": state.authority.to_string(),
+                    "flags": state.flags,
+                    "finalized": state.finalized,
+                    "deny_cpi": state.deny_cpi,
+                    "lifetime_count": state.lifetime_count,
+                    "role_weighted": state.role_weighted,
+                    "slot": slot,
+                    "cached": args.offline,
+                }),
+                format!(
+                    "count:     {}\nlifetime:  {}\nauthority: {}\nflags:     {:#010b}\n\
+                     finalized: {}\ndeny_cpi:  {}\nby role:   {}\n({read} at slot {slot})\n",
+                    state.count,
+                    state.lifetime_count,
+                    state.authority,
+                    state.flags,
+                    state.finalized,
+                    state.deny_cpi,
+                    state.role_weighted
+                ),
+            );
+        }
+        Command::List { include_archived } => {
+            let counters: Vec<(Pubkey, Counter)> = if args.offline {
+                cache
+                    .list(*include_archived)
+                    .into_iter()
+                    .map(|(address, state, _)| (address, state))
+                    .collect()
+            } else {
+                // The accounts are read at this slot or later.
+                let slot = client.rpc.get_slot()?;
+                let counters = client.list_counters(*include_archived)?;
+                for (address, state) in &counters {
+                    cache.put_counter(address, slot, state);
+                }
+                save_cache(&cache, &cache_path);
+                counters
+            };
+            emit(
+                counters
+                    .iter()
+                    .map(|(address, state)| {
+                        json!({ "address": address.to_string(), "count": state.count })
+                    })
+                    .collect(),
+                counters
+                    .iter()
+                    .map(|(address, state)| format!("{address} {}\n", state.count))
+                    .collect(),
+            );
+        }
+        Command::Send(action) => {
+            let payer = keypair(&settings.keypair)?;
+            let (instructions, counter) = action.build(&client, settings.counter)?;
+            let signature = if args.jito {
+                if args.jito_tip < jito::MIN_TIP_LAMPORTS {
+                    bail!("--jito-tip must be at least {}", jito::MIN_TIP_LAMPORTS);
+                }
+                let jito = Jito {
+                    url: args.jito_url.clone(),
+                    tip_lamports: args.jito_tip,
+                };
+                client.send_with_jito(&payer, &instructions, &jito)?
+            } else {
+                client.send(&payer, &instructions)?
+            };
+            let (state, _) = client.fetch_counter(&counter)?;
+            emit(
+                json!({
+                    "signature": signature.to_string(),
+                    "counter": counter.to_string(),
+                    "count": state.count,
+                }),
+                format!("{signature}\ncount: {}\n", state.count),
+            );
+        }
+        Command::Keys { .. } => unreachable!("handled above"),
+        Command::Watch => {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    0,
+                    &Counter::DISCRIMINATOR,
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(settings.commitment),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let (_subscription, updates) = PubsubClient::program_subscribe(
+                &websocket_url(&settings.url),
+                &settings.program_id,
+                Some(config),
+            )?;
+            for update in updates {
+                let slot = update.context.slot;
+                let address: Pubkey = update.value.pubkey.parse()?;
+                let Some(account) = update.value.account.decode::<Account>() else {
+                    continue;
+                };
+                // A closed account comes through emptied.
+                let count = if account.lamports == 0 {
+                    cache.remove(&address, slot);
+                    None
+                } else if cache.put(&address, slot, &account.data) {
+                    cache.get(&address).map(|(state, _)| state.count)
+                } else {
+                    continue;
+                };
+                save_cache(&cache, &cache_path);
+                emit(
+                    json!({ "address": address.to_string(), "slot": slot, "count": count }),
+                    match count {
+                        Some(count) => format!("{slot} {address} {count}\n"),
+                        None => format!("{slot} {address} closed\n"),
+                    },
+                );
+            }
+            bail!("the subscription ended");
+        }
+        Command::Multisig {
+            multisig,
+            vault_index,
+            command: Multisig::Propose { memo, action },
+        } => {
+            let member = keypair(&settings.keypair)?;
+            let data = client
+                .rpc
+                .get_account_data(multisig)
+                .with_context(|| format!("reading multisig {multisig}"))?;
+            let index = squads::transaction_index(&data)
+                .with_context(|| format!("{multisig} isn't a Squads multisig"))?
+                + 1;
+            let vault = squads::vault_address(multisig, *vault_index);
+            let instruction = action.build(&client, &vault, settings.counter)?;
+            let signature = client.send(
+                &member,
+                &squads::propose_ixs(
+                    multisig,
+                    *vault_index,
+                    index,
+                    &member.pubkey(),
+                    &[instruction],
+                    memo.clone(),
+                ),
+            )?;
+            emit(
+                json!({
+                    "signature": signature.to_string(),
+                    "transaction_index": index,
+                    "proposal": squads::proposal_address(multisig, index).to_string(),
+                    "vault": vault.to_string(),
+                }),
+                format!(
+                    "{signature}
+proposed transaction {index} from vault {vault}
+"
+                ),
+            );
+        }
+        Command::Simulate { action } => {
+            // Nothing is signed, so only the payer's address is needed.
+            let payer = keypair(&settings.keypair)?.pubkey();
+            let (instructions, counter) = action.build(&client, settings.counter)?;
+            let simulation = client.simulate(&payer, &instructions, &counter)?;
+            let events: Vec<String> = event_data(&simulation.logs)
+                .iter()
+                .filter_map(|data| describe_event(data))
+                .collect();
+            let error = simulation
+                .err
+                .as_ref()
+                .map(|err| ErrorReport::from_transaction_error(err, &simulation.logs));
+            let count_after = simulation.counter_after.as_ref().map(|state| state.count);
+
+            let mut text = String::new();
+            for line in &simulation.logs {
+                text += &format!("  {line}\n");
+            }
+            for event in &events {
+                text += &format!("event: {event}\n");
+            }
+            if let Some(units) = simulation.units_consumed {
+                text += &format!("compute units: {units}\n");
+            }
+            text += &match (&error, count_after) {
+                (Some(error), _) => format!("would fail: {}\n", error.message),
+                (None, Some(count)) => format!("count would be: {count}\n"),
+                (None, None) => "would succeed\n".to_string(),
+            };
+            emit(
+                json!({
+                    "counter": counter.to_string(),
+                    "error": error,
+                    "count_after": count_after,
+                    "units_consumed": simulation.units_consumed,
+                    "events": events,
+                    "logs": simulation.logs,
+                }),
+                text,
+            );
+        }
+    }
+    Ok(())
+}
+
+// Saving the cache is best effort: the command already has its answer.
+fn save_cache(cache: &Cache, path: &Path) {
+    if let Err(err) = cache.save(path) {
+        tracing::warn!(error = %err, path = %path.display(), "couldn't save the cache");
+    }
+}
+
+// The RPC node's websocket endpoint: same host, ws(s) scheme, and for a
+// local validator the port after the RPC one.
+fn websocket_url(url: &str) -> String {
+    let url = url
+        .replacen("https://", "wss://", 1)
+        .replacen("http://", "ws://", 1);
+    url.replacen(":8899", ":8900", 1)
+}
+
+// The counter given on the command line, or else the profile's.
+fn pick(counter: Option<Pubkey>, default: Option<Pubkey>) -> anyhow::Result<Pubkey> {
+    counter
+        .or(default)
+        .context("no counter given, and the profile has no default counter")
+}
+
+// Reads the config file. A missing file is only an error if it was asked
+// for with --config.
+fn load_config(path: Option<PathBuf>) -> anyhow::Result<ConfigFile> {
+    let explicit = path.is_some();
+    let path =
+        path.unwrap_or_else(|| PathBuf::from(expand_home("~/.config/counter-cli/config.toml")));
+    match fs::read_to_string(&path) {
+        Ok(text) => ConfigFile::parse(&text).with_context(|| format!("reading {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !explicit => Ok(ConfigFile::default()),
+        Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+fn keys(command: &Keys, output: Output) -> anyhow::Result<()> {
+    let keystore = keystore();
+    match command {
+        Keys::Import {
+            name,
+            keypair: path,
+        } => {
+            let path = expand_home(path);
+            let keypair = read_keypair_file(&path)
+                .map_err(|err| anyhow::anyhow!("{err}"))
+                .with_context(|| format!("reading keypair {path}"))?;
+            let passphrase = passphrase(&format!("New passphrase for {name}: "), true)?;
+            keystore.save(
+                name,
+                &EncryptedKey::encrypt(&keypair, &passphrase, ITERATIONS),
+            )?;
+            match output {
+                Output::Text => println!("imported {name}: {}", keypair.pubkey()),
+                Output::Json => println!(
+                    "{}",
+                    json!({ "name": name, "pubkey": keypair.pubkey().to_string() })
+                ),
+            }
+        }
+        Keys::Export {
+            name,
+            keypair: path,
+        } => {
+            let path = expand_home(path);
+            if fs::metadata(&path).is_ok() {
+                bail!("{path} already exists");
+            }
+            let keypair = unlock(&keystore, name)?;
+            // Created with mode 0600.
+            write_keypair_file(&keypair, &path)
+                .map_err(|err| anyhow::anyhow!("{err}"))
+                .with_context(|| format!("writing {path}"))?;
+            match output {
+                Output::Text => println!("exported {name} to {path}"),
+                Output::Json => println!("{}", json!({ "name": name, "path": path })),
+            }
+        }
+        Keys::List => {
+            let keys = keystore.list()?;
+            match output {
+                Output::Text => {
+                    for (name, pubkey) in &keys {
+                        println!("{name} {pubkey}");
+                    }
+                }
+                Output::Json => {
+                    let keys: Vec<Value> = keys
+                        .iter()
+                        .map(|(name, pubkey)| json!({ "name": name, "pubkey": pubkey.to_string() }))
+                        .collect();
+                    println!("{}", Value::from(keys));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn keystore() -> Keystore {
+    Keystore::new(expand_home("~/.config/counter-cli/keys"))
+}
+
+// Decrypts a key from the keystore.
+fn unlock(keystore: &Keystore, name: &str) -> anyhow::Result<Keypair> {
+    let key = keystore.load(name)?;
+    let passphrase = passphrase(&format!("Passphrase for {name}: "), false)?;
+    Ok(key.decrypt(&passphrase)?)
+}
+
+// COUNTER_CLI_PASSPHRASE if it's set, or else asks on the terminal;
+// "confirm" asks twice, for new passphrases.
+fn passphrase(prompt: &str, confirm: bool) -> anyhow::Result<String> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password(prompt)?;
+    if confirm && rpassword::prompt_password("Again: ")? != passphrase {
+        bail!("the passphrases don't match");
+    }
+    if passphrase.is_empty() {
+        bail!("the passphrase can't be empty");
+    }
+    Ok(passphrase)
+}
+
+// A keypair file, or "keystore:<NAME>".
+fn keypair(path: &str) -> anyhow::Result<Keypair> {
+    if let Some(name) = path.strip_prefix("keystore:") {
+        return unlock(&keystore(), name);
+    }
+    let path = expand_home(path);
+    read_keypair_file(&path)
+        .map_err(|err| anyhow::anyhow!("{err}"))
+        .with_context(|| format!("reading keypair {path}"))
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
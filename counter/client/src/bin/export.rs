@@ -0,0 +1,182 @@
+// Snapshots the program's accounts to files, for loading into BI tools:
+//
+//     cargo run -p counter-client --bin export -- \
+//         --cluster mainnet --format csv --out-dir export/
+//
+// Writes one file per account kind (see export.rs), e.g. export/counter.csv.
+// --kind picks which kinds (all by default), and --owner keeps only the
+// accounts owned by one key: counters by their authority, leases by their
+// renter, and so on. Kinds with no owner are skipped when it's set.
+//
+// A single getProgramAccounts call for a big program can time out or hit
+// the RPC node's response limit. So each kind is fetched in two steps:
+// first just the addresses (with a zero-length data slice, so the
+// response stays small), then the accounts, --page-size at a time, with
+// getMultipleAccounts. --requests-per-second keeps it under public RPC
+// rate limits.
+//
+// Output is CSV or JSON Lines, which every BI tool we've tried loads
+// directly. There's no Parquet writer: it would pull in arrow, which is
+// far heavier than anything else this crate depends on. Counters have no
+// labels to filter by either; --owner is the only filter.
+use std::{
+    fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use clap::Parser;
+use counter_client::{
+    export::{to_csv, to_json_lines, Kind, Row},
+    Cluster, CounterClient,
+};
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Format {
+    Csv,
+    // JSON Lines: one object per line.
+    Json,
+}
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    #[arg(long = "kind", value_enum)]
+    kinds: Vec<Kind>,
+    #[arg(long)]
+    owner: Option<Pubkey>,
+
+    #[arg(long, value_enum, default_value = "csv")]
+    format: Format,
+    #[arg(long, default_value = "export")]
+    out_dir: PathBuf,
+
+    // Accounts per getMultipleAccounts call. 100 is the RPC maximum.
+    #[arg(long, default_value_t = 100)]
+    page_size: usize,
+    #[arg(long, default_value_t = 5.0)]
+    requests_per_second: f64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+    let kinds = if args.kinds.is_empty() {
+        Kind::ALL.to_vec()
+    } else {
+        args.kinds.clone()
+    };
+    let mut limiter = RateLimiter::new(args.requests_per_second);
+    fs::create_dir_all(&args.out_dir)
+        .with_context(|| format!("creating {}", args.out_dir.display()))?;
+
+    for kind in kinds {
+        let filters = match (args.owner, kind.owner_offset()) {
+            (None, _) => vec![discriminator_filter(kind)],
+            (Some(owner), Some(offset)) => vec![
+                discriminator_filter(kind),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, owner.as_ref())),
+            ],
+            (Some(_), None) => continue,
+        };
+
+        limiter.wait();
+        let addresses = addresses(&client, filters)
+            .with_context(|| format!("listing {} accounts", kind.name()))?;
+
+        let mut rows: Vec<Row> = Vec::with_capacity(addresses.len());
+        for page in addresses.chunks(args.page_size.max(1)) {
+            limiter.wait();
+            let accounts = client
+                .rpc
+                .get_multiple_accounts(page)
+                .with_context(|| format!("fetching {} accounts", kind.name()))?;
+            // An account can be closed between the two steps; skip it.
+            for (address, account) in page.iter().zip(accounts) {
+                if let Some(account) = account {
+                    rows.push(kind.decode(address, &account.data)?);
+                }
+            }
+        }
+
+        let (contents, extension) = match args.format {
+            Format::Csv => (to_csv(&rows), "csv"),
+            Format::Json => (to_json_lines(&rows), "jsonl"),
+        };
+        let path = args.out_dir.join(format!("{}.{extension}", kind.name()));
+        fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))?;
+        println!(
+            "{}: {} accounts -> {}",
+            kind.name(),
+            rows.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+fn discriminator_filter(kind: Kind) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &kind.discriminator()))
+}
+
+// The addresses of the program's accounts matching "filters", without
+// their data.
+fn addresses(client: &CounterClient, filters: Vec<RpcFilterType>) -> anyhow::Result<Vec<Pubkey>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            data_slice: Some(UiDataSliceConfig {
+                offset: 0,
+                length: 0,
+            }),
+            commitment: Some(client.rpc.commitment()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = client
+        .rpc
+        .get_program_accounts_with_config(&client.program_id, config)?;
+    Ok(accounts.into_iter().map(|(address, _)| address).collect())
+}
+
+// Spaces RPC calls at least 1 / requests_per_second apart.
+struct RateLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second.max(f64::MIN_POSITIVE)),
+            last: None,
+        }
+    }
+
+    fn wait(&mut self) {
+        if let Some(last) = self.last {
+            if let Some(left) = self.interval.checked_sub(last.elapsed()) {
+                thread::sleep(left);
+            }
+        }
+        self.last = Some(Instant::now());
+    }
+}
@@ -0,0 +1,128 @@
+// Keeps counters' oracle-style feeds (see programs/counter-feed) up to
+// date:
+//
+//     cargo run -p counter-client --bin feed-crank -- \
+//         --cluster localnet --counter <ADDRESS> --counter <ADDRESS>
+//
+// Every --interval-secs it reads the counters and their feeds in one
+// getMultipleAccounts call, creates any feed that doesn't exist yet, and
+// updates those that are due (see feed::update_due): the count changed, or
+// --heartbeat-secs passed without an update.
+//
+// Updates go out FEEDS_PER_TRANSACTION at a time, signed by --keypair,
+// which pays the fees. update_feed is permissionless, so several cranks
+// can run for the same feeds; a batch that loses a race fails with
+// AlreadyUpdated and is tried again next round.
+use std::{
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anchor_lang::AccountDeserialize;
+use anyhow::bail;
+use clap::Parser;
+use counter::Counter;
+use counter_client::{
+    feed::{decode_feed, feed_address, initialize_feed_ix, update_due, update_feed_ix},
+    Cluster, CounterClient,
+};
+use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file, signer::Signer};
+
+// Each update passes two accounts, so this keeps well inside a
+// transaction's size limit.
+const FEEDS_PER_TRANSACTION: usize = 10;
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    // A counter whose feed to keep. Repeat for more.
+    #[arg(long = "counter", required = true)]
+    counters: Vec<Pubkey>,
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    #[arg(long, default_value_t = 10)]
+    interval_secs: u64,
+    #[arg(long, default_value_t = 300)]
+    heartbeat_secs: u64,
+    // Run one round and exit, instead of polling forever.
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+    if args.cluster.program_id() != counter::ID {
+        // The feed program checks counters against the counter program it
+        // was built with (see declare_id! in programs/counter/src/lib.rs).
+        bail!("counter-feed is only deployed alongside the localnet counter program");
+    }
+    let keypair_path = expand_home(&args.keypair);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("reading keypair {keypair_path}: {err}"))?;
+
+    // Counters first, then their feeds, in one call.
+    let mut addresses = args.counters.clone();
+    addresses.extend(args.counters.iter().map(feed_address));
+
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        match client.rpc.get_multiple_accounts(&addresses) {
+            Ok(accounts) => {
+                let (counters, feeds) = accounts.split_at(args.counters.len());
+                let mut instructions = Vec::new();
+                for ((address, counter), feed) in args.counters.iter().zip(counters).zip(feeds) {
+                    let Some(counter) = counter
+                        .as_ref()
+                        .filter(|account| account.owner == client.program_id)
+                        .and_then(|account| {
+                            Counter::try_deserialize(&mut account.data.as_slice()).ok()
+                        })
+                    else {
+                        tracing::warn!(counter = %address, "not a counter, skipping");
+                        continue;
+                    };
+                    match feed {
+                        None => instructions.push(initialize_feed_ix(address, &payer.pubkey())),
+                        Some(account) => match decode_feed(&account.data) {
+                            Some(feed)
+                                if update_due(&feed, counter.count, now, args.heartbeat_secs) =>
+                            {
+                                instructions.push(update_feed_ix(address))
+                            }
+                            Some(_) => {}
+                            None => tracing::warn!(counter = %address, "feed account isn't a Feed"),
+                        },
+                    }
+                }
+                for batch in instructions.chunks(FEEDS_PER_TRANSACTION) {
+                    match client.send(&payer, batch) {
+                        Ok(signature) => println!("updated {} feeds: {signature}", batch.len()),
+                        Err(err) => tracing::warn!(error = %err, "couldn't update the feeds"),
+                    }
+                }
+            }
+            Err(err) => tracing::warn!(error = %err, "couldn't read the counters"),
+        }
+        if args.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(args.interval_secs));
+    }
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
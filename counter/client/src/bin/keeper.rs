@@ -0,0 +1,229 @@
+// Sends the program's permissionless maintenance instructions when they
+// come due (see keeper.rs):
+//
+//     cargo run -p counter-client --bin keeper -- --cluster devnet
+//
+// Every --interval-secs it finds the work with getProgramAccounts:
+//
+//   - finalize, for every counter whose Deadline has passed and that isn't
+//     finalized yet
+//   - forfeit_commit, for every Commitment whose round is over (revealed
+//     ones are closed, so any left are forfeits)
+//   - reap, for every reapable counter that changed since its ReapPolicy
+//     last looked or has been idle long enough
+//
+// Every counter it fetches along the way also has its checksum checked
+// (see programs/counter/src/checksum.rs), and one that doesn't match is
+// logged as a warning: something other than the program changed it.
+//
+// and sends it --batch-size instructions per transaction, signed by
+// --keypair, which pays the fees and each FinalResult's rent.
+//
+// Each transaction bids a priority fee: --priority-fee micro-lamports per
+// compute unit if given, otherwise the --fee-percentile of what recently
+// landed writing the same accounts, never more than --max-priority-fee.
+//
+// Like feed-crank, several keepers can run at once: a batch that loses a
+// race fails (the result already exists, or the commitment is gone) and
+// whatever's left in it is found again next round.
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anchor_lang::{AccountDeserialize, Space};
+use clap::Parser;
+use counter::{checksum_matches, CommitRound, Commitment, Counter, Deadline, ReapPolicy};
+use counter_client::{
+    keeper::{
+        commitment_round, finalize_due, forfeit_due, priority_fee, program_accounts, reap_due,
+        with_priority_fee,
+    },
+    Cluster, CounterClient,
+};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+};
+
+// getMultipleAccounts takes at most this many addresses per call.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    #[arg(long, default_value_t = 60)]
+    interval_secs: u64,
+    // Instructions per transaction. finalize passes five accounts and
+    // forfeit_commit four, so this keeps well inside the size limit.
+    #[arg(long, default_value_t = 5)]
+    batch_size: usize,
+    // A fixed priority fee, in micro-lamports per compute unit, instead of
+    // one based on recent fees.
+    #[arg(long)]
+    priority_fee: Option<u64>,
+    #[arg(long, default_value_t = 50)]
+    fee_percentile: u8,
+    #[arg(long, default_value_t = 100_000)]
+    max_priority_fee: u64,
+    // Run one round and exit, instead of polling forever.
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+    let keypair_path = expand_home(&args.keypair);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("reading keypair {keypair_path}: {err}"))?;
+
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        match find_work(&client, &payer.pubkey(), now) {
+            Ok(instructions) => {
+                for batch in instructions.chunks(args.batch_size.max(1)) {
+                    send(&client, &payer, batch, &args);
+                }
+            }
+            Err(err) => tracing::warn!(error = %err, "couldn't look for work"),
+        }
+        if args.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(args.interval_secs));
+    }
+}
+
+fn find_work(client: &CounterClient, payer: &Pubkey, now: i64) -> anyhow::Result<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+
+    // Deadlines that have passed, then the counters they belong to, to
+    // leave out the ones already finalized.
+    let passed: Vec<(Pubkey, Deadline)> =
+        program_accounts::<Deadline>(client, 8 + Deadline::INIT_SPACE)?
+            .into_iter()
+            .filter(|(_, deadline)| now >= deadline.end_time)
+            .collect();
+    for chunk in passed.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let addresses: Vec<Pubkey> = chunk.iter().map(|(_, deadline)| deadline.counter).collect();
+        let counters = client.rpc.get_multiple_accounts(&addresses)?;
+        for ((_, deadline), account) in chunk.iter().zip(counters) {
+            let Some(account) = account.filter(|account| account.owner == client.program_id) else {
+                tracing::warn!(counter = %deadline.counter, "deadline without a counter, skipping");
+                continue;
+            };
+            check_checksum(&deadline.counter, &account.data);
+            let Ok(counter) = Counter::try_deserialize(&mut account.data.as_slice()) else {
+                tracing::warn!(counter = %deadline.counter, "deadline without a counter, skipping");
+                continue;
+            };
+            if finalize_due(deadline, &counter, now) {
+                instructions.push(client.finalize_ix(&deadline.counter, payer));
+            }
+        }
+    }
+
+    // Each counter's current round, then every commitment still open.
+    let rounds: HashMap<Pubkey, CommitRound> =
+        program_accounts::<CommitRound>(client, 8 + CommitRound::INIT_SPACE)?
+            .into_iter()
+            .map(|(_, round)| (round.counter, round))
+            .collect();
+    for (address, commitment) in program_accounts::<Commitment>(client, 8 + Commitment::INIT_SPACE)?
+    {
+        let found = rounds.iter().find_map(|(counter, current)| {
+            commitment_round(client, &address, counter, &commitment, current)
+                .map(|round| (counter, current, round))
+        });
+        match found {
+            Some((counter, current, round)) if forfeit_due(current, round, now) => {
+                instructions.push(client.forfeit_commit_ix(counter, &commitment.participant, round))
+            }
+            Some(_) => {}
+            None => tracing::warn!(commitment = %address, "commitment of no known round"),
+        }
+    }
+
+    // Reapable counters, each fetched with its vault.
+    let epoch = client.rpc.get_epoch_info()?.epoch;
+    let policies = program_accounts::<ReapPolicy>(client, 8 + ReapPolicy::INIT_SPACE)?;
+    for chunk in policies.chunks(MAX_MULTIPLE_ACCOUNTS / 2) {
+        let addresses: Vec<Pubkey> = chunk
+            .iter()
+            .flat_map(|(_, policy)| [policy.counter, client.vault_address(&policy.counter)])
+            .collect();
+        let accounts = client.rpc.get_multiple_accounts(&addresses)?;
+        for ((_, policy), pair) in chunk.iter().zip(accounts.chunks(2)) {
+            let Some(counter) = &pair[0] else {
+                tracing::warn!(counter = %policy.counter, "reap policy without a counter, skipping");
+                continue;
+            };
+            check_checksum(&policy.counter, &counter.data);
+            let vault_lamports = pair[1].as_ref().map_or(0, |vault| vault.lamports);
+            if reap_due(policy, &counter.data, vault_lamports, epoch) {
+                instructions.push(client.reap_ix(&policy.counter, &policy.beneficiary));
+            }
+        }
+    }
+
+    tracing::info!(instructions = instructions.len(), "found work");
+    Ok(instructions)
+}
+
+// Warns if "data", a counter's, doesn't match its checksum.
+fn check_checksum(counter: &Pubkey, data: &[u8]) {
+    if !checksum_matches(data) {
+        tracing::warn!(counter = %counter, "checksum doesn't match, changed outside the program");
+    }
+}
+
+fn send(client: &CounterClient, payer: &Keypair, batch: &[Instruction], args: &Args) {
+    let fee = match args.priority_fee {
+        Some(fee) => fee,
+        None => {
+            let writable: Vec<Pubkey> = batch
+                .iter()
+                .flat_map(|instruction| &instruction.accounts)
+                .filter(|meta| meta.is_writable)
+                .map(|meta| meta.pubkey)
+                .collect();
+            match client.rpc.get_recent_prioritization_fees(&writable) {
+                Ok(recent) => {
+                    let recent: Vec<u64> =
+                        recent.iter().map(|fee| fee.prioritization_fee).collect();
+                    priority_fee(&recent, args.fee_percentile, args.max_priority_fee)
+                }
+                Err(err) => {
+                    tracing::warn!(error = %err, "couldn't read recent fees, bidding none");
+                    0
+                }
+            }
+        }
+    };
+    match client.send(payer, &with_priority_fee(batch, fee)) {
+        Ok(signature) => println!("sent {} instructions at {fee}: {signature}", batch.len()),
+        Err(err) => tracing::warn!(error = %err, "couldn't send a batch"),
+    }
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
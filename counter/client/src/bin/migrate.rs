@@ -0,0 +1,148 @@
+// Finds counters still in an older account layout and, if asked, migrates
+// them with migrate_counter (see programs/counter/src/migrate.rs):
+//
+//     cargo run -p counter-client --bin migrate -- --cluster devnet
+//     cargo run -p counter-client --bin migrate -- --cluster devnet --send
+//
+// The layouts come from COUNTER_LAYOUTS in the program crate this tool was
+// built with, so build it from the version being deployed. Without --send
+// it's a dry run: it reads every account with the Counter discriminator
+// and prints how many are in each layout, plus any whose size matches none
+// of them. With --send it migrates every outdated counter, --batch-size
+// per transaction, paid for by --keypair. Migrated counters are the
+// current size afterwards, so an interrupted run can simply be rerun.
+//
+// The first, count-only counters have no owner, so migrate_counter refuses
+// them. They're migrated only when --owner is given, with
+// migrate_ownerless_counter: --keypair must then be the program's upgrade
+// authority, and --owner becomes each one's authority.
+use anchor_lang::Discriminator;
+use clap::Parser;
+use counter::{counter_layout, Counter, COUNTER_LAYOUTS, OWNERLESS_LAYOUT};
+use counter_client::{Cluster, CounterClient};
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file, signer::Signer};
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    // Send migrate_counter transactions, instead of only reporting.
+    #[arg(long)]
+    send: bool,
+    // Pays the extra rent of migrated counters.
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    // Migrations per transaction.
+    #[arg(long, default_value_t = 10)]
+    batch_size: usize,
+    // Also migrate count-only counters, giving them this authority.
+    #[arg(long)]
+    owner: Option<Pubkey>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+
+    // Unlike list_counters there's no size filter: finding the other sizes
+    // is the point.
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            &Counter::DISCRIMINATOR,
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(client.rpc.commitment()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = client
+        .rpc
+        .get_program_accounts_with_config(&client.program_id, config)?;
+
+    let current = COUNTER_LAYOUTS.len() - 1;
+    let mut per_layout = [0usize; COUNTER_LAYOUTS.len()];
+    let mut outdated: Vec<Pubkey> = Vec::new();
+    let mut ownerless: Vec<Pubkey> = Vec::new();
+    for (address, account) in &accounts {
+        match counter_layout(&account.data) {
+            Some(layout) => {
+                per_layout[layout] += 1;
+                if layout == OWNERLESS_LAYOUT {
+                    ownerless.push(*address);
+                } else if layout < current {
+                    outdated.push(*address);
+                }
+            }
+            None => println!(
+                "{address}: {} bytes, matches no known layout",
+                account.data.len()
+            ),
+        }
+    }
+    for (layout, (size, count)) in COUNTER_LAYOUTS.iter().zip(per_layout).enumerate() {
+        let note = if layout == current { " (current)" } else { "" };
+        println!("layout {layout}, {size} bytes{note}: {count} counters");
+    }
+
+    if !args.send {
+        println!(
+            "{} counters need migrating; rerun with --send to migrate them",
+            outdated.len()
+        );
+        println!(
+            "{} count-only counters need an owner; rerun with --send --owner to migrate them",
+            ownerless.len()
+        );
+        return Ok(());
+    }
+    let keypair_path = expand_home(&args.keypair);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("reading keypair {keypair_path}: {err}"))?;
+    for batch in outdated.chunks(args.batch_size.max(1)) {
+        let migrations: Vec<_> = batch
+            .iter()
+            .map(|counter| client.migrate_counter_ix(counter, &payer.pubkey()))
+            .collect();
+        let signature = client.send(&payer, &migrations)?;
+        println!("migrated {} counters: {signature}", batch.len());
+    }
+    let Some(owner) = args.owner else {
+        if !ownerless.is_empty() {
+            println!("skipped {} count-only counters: no --owner", ownerless.len());
+        }
+        return Ok(());
+    };
+    for batch in ownerless.chunks(args.batch_size.max(1)) {
+        let migrations: Vec<_> = batch
+            .iter()
+            .map(|counter| client.migrate_ownerless_counter_ix(counter, &payer.pubkey(), owner))
+            .collect();
+        let signature = client.send(&payer, &migrations)?;
+        println!(
+            "migrated {} count-only counters to {owner}: {signature}",
+            batch.len()
+        );
+    }
+    Ok(())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
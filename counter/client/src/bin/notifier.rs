@@ -0,0 +1,117 @@
+// Posts to Discord or Telegram when watched counters hit milestones, set
+// off their alarm, change authority or stall (see notify.rs for the
+// config file):
+//
+//     cargo run -p counter-client --bin notifier -- \
+//         --cluster mainnet --config notifier.toml
+//
+// Every --interval-secs it reads all the watched counters in one
+// getMultipleAccounts call and compares them with the previous read. The
+// first read only sets the baseline, so restarting doesn't repeat old
+// news. A message that fails to post is logged and dropped: notices are
+// for people, and a late one is worse than none.
+use std::{
+    fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{bail, Context};
+use clap::Parser;
+use counter::Counter;
+use counter_client::{
+    notify::{Channel, NotifierConfig, Seen},
+    Cluster, CounterClient,
+};
+use serde_json::json;
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    #[arg(long, default_value = "notifier.toml")]
+    config: PathBuf,
+    #[arg(long, default_value_t = 30)]
+    interval_secs: u64,
+    // Print messages instead of posting them.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let text = fs::read_to_string(&args.config)
+        .with_context(|| format!("reading {}", args.config.display()))?;
+    let config = NotifierConfig::parse(&text)?;
+    if config.counters.is_empty() {
+        bail!("no counters to watch in {}", args.config.display());
+    }
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+    let http = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let addresses: Vec<_> = config.counters.iter().map(|w| w.address).collect();
+
+    let mut seen: Vec<Option<Seen>> = vec![None; config.counters.len()];
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        match client.rpc.get_multiple_accounts(&addresses) {
+            Ok(accounts) => {
+                for ((watched, account), seen) in
+                    config.counters.iter().zip(accounts).zip(&mut seen)
+                {
+                    let Some(counter) = account
+                        .filter(|account| account.owner == client.program_id)
+                        .and_then(|account| {
+                            Counter::try_deserialize(&mut account.data.as_slice()).ok()
+                        })
+                    else {
+                        tracing::warn!(counter = %watched.address, "not a counter, skipping");
+                        continue;
+                    };
+                    let Some(seen) = seen else {
+                        *seen = Some(Seen::new(&counter, now));
+                        continue;
+                    };
+                    for notice in seen.observe(watched, &counter, now) {
+                        let text = notice.render(&config.templates, watched, counter.count);
+                        for channel in config.route(watched, notice.kind()) {
+                            if args.dry_run {
+                                println!("{text}");
+                            } else if let Err(err) = post(&http, channel, &text) {
+                                tracing::warn!(error = %err, text, "couldn't post");
+                            }
+                        }
+                    }
+                }
+            }
+            Err(err) => tracing::warn!(error = %err, "couldn't read the counters"),
+        }
+        thread::sleep(Duration::from_secs(args.interval_secs));
+    }
+}
+
+fn post(http: &reqwest::blocking::Client, channel: &Channel, text: &str) -> anyhow::Result<()> {
+    let request = match channel {
+        Channel::Discord { webhook, .. } => http.post(webhook).json(&json!({ "content": text })),
+        Channel::Telegram {
+            bot_token, chat_id, ..
+        } => http
+            .post(format!(
+                "https://api.telegram.org/bot{bot_token}/sendMessage"
+            ))
+            .json(&json!({ "chat_id": chat_id, "text": text })),
+    };
+    request.send()?.error_for_status()?;
+    Ok(())
+}
@@ -0,0 +1,131 @@
+// Mirrors a counter from one cluster onto another, e.g. a mainnet counter
+// onto a devnet one, so analytics can be staged against production values:
+//
+//     cargo run -p counter-client --bin relayer -- \
+//         --source-cluster mainnet --source-counter <ADDRESS> \
+//         --mirror-cluster devnet --mirror-counter <ADDRESS>
+//
+// Every poll it reads both counters, works out how far behind the mirror is
+// (see relay.rs), and sends that many increments, signed by --keypair.
+// Progress is saved to --checkpoint so restarts don't replay anything
+// twice. If the counts stop adding up (drift), it exits with an error
+// rather than guess; delete the checkpoint to start over from the current
+// values.
+use std::{fs, path::PathBuf, thread, time::Duration};
+
+use anyhow::Context;
+use clap::Parser;
+use counter_client::{
+    relay::{plan, Checkpoint},
+    Cluster, CounterClient,
+};
+use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file};
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    source_cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    source_url: Option<String>,
+    #[arg(long)]
+    source_counter: Pubkey,
+
+    #[arg(long, value_enum)]
+    mirror_cluster: Cluster,
+    #[arg(long)]
+    mirror_url: Option<String>,
+    #[arg(long)]
+    mirror_counter: Pubkey,
+
+    // Pays for (and signs) the mirror's increments. Normally the mirror
+    // counter's authority.
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    #[arg(long, default_value = "relayer-checkpoint.json")]
+    checkpoint: PathBuf,
+    #[arg(long, default_value_t = 10)]
+    interval_secs: u64,
+    // Increments per transaction.
+    #[arg(long, default_value_t = 20)]
+    batch_size: u64,
+    // Catch up once and exit, instead of polling forever.
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let source = CounterClient::new(
+        args.source_url
+            .as_deref()
+            .unwrap_or(args.source_cluster.default_url()),
+        args.source_cluster.program_id(),
+    );
+    let mirror = CounterClient::new(
+        args.mirror_url
+            .as_deref()
+            .unwrap_or(args.mirror_cluster.default_url()),
+        args.mirror_cluster.program_id(),
+    );
+    let keypair_path = expand_home(&args.keypair);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("reading keypair {keypair_path}: {err}"))?;
+
+    let mut checkpoint = match fs::read(&args.checkpoint) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .with_context(|| format!("parsing {}", args.checkpoint.display()))?,
+        Err(_) => {
+            let (counter, _) = mirror.fetch_counter(&args.mirror_counter)?;
+            Checkpoint::fresh(counter.count)
+        }
+    };
+
+    loop {
+        let (source_counter, slot) = source.fetch_counter(&args.source_counter)?;
+        let (mirror_counter, _) = mirror.fetch_counter(&args.mirror_counter)?;
+        let mut behind = plan(&checkpoint, source_counter.count, mirror_counter.count)
+            .context("drift detected")?;
+
+        // Increments that already landed (see relay::plan) count as
+        // replayed.
+        checkpoint.advance(source_counter.count - checkpoint.source_count - behind);
+        while behind > 0 {
+            let batch = behind.min(args.batch_size);
+            let increments: Vec<_> = (0..batch)
+                .map(|_| mirror.increment_ix(&args.mirror_counter))
+                .collect();
+            let signature = mirror.send(&payer, &increments)?;
+            println!("replayed {batch} increments: {signature}");
+
+            behind -= batch;
+            checkpoint.advance(batch);
+            checkpoint.source_slot = slot;
+            save(&args.checkpoint, &checkpoint)?;
+        }
+        checkpoint.source_slot = slot;
+        save(&args.checkpoint, &checkpoint)?;
+
+        if args.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(args.interval_secs));
+    }
+}
+
+// Writes to a temporary file and renames it over the old one, so a crash
+// mid-write never leaves a half-written checkpoint.
+fn save(path: &PathBuf, checkpoint: &Checkpoint) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(checkpoint)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
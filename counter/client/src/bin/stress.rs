@@ -0,0 +1,230 @@
+// Load-tests increments against a test cluster and reports throughput,
+// confirmation latency and why transactions failed:
+//
+//     cargo run -p counter-client --bin stress -- --cluster devnet \
+//         --counter <ADDRESS> --counter <ADDRESS> --tps 50 --duration-secs 30
+//
+// Sends plain "increment" transactions at --tps, spread round-robin over
+// the --counter addresses and over --payers fresh keypairs. Each payer is
+// funded from --keypair at the start and swept back at the end.
+// Transactions skip preflight, so the node doesn't simulate each one
+// before accepting it, and program errors (a ONCE_PER_SLOT counter hit
+// twice in a slot, say) show up in the report by error code.
+//
+// Confirmations are polled every --poll-ms, so latencies are only that
+// precise. Transactions still unconfirmed --timeout-secs after sending
+// stop are counted as "timed out".
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use counter_client::{
+    load::{failure_cause, Report},
+    Cluster, CounterClient,
+};
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature},
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+
+// Fee for a transaction with one signature.
+const FEE: u64 = 5_000;
+
+#[derive(Parser)]
+struct Args {
+    #[arg(long, value_enum)]
+    cluster: Cluster,
+    // Overrides the cluster's public RPC endpoint.
+    #[arg(long)]
+    url: Option<String>,
+
+    #[arg(long = "counter", required = true)]
+    counters: Vec<Pubkey>,
+    // Funds the payers.
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    #[arg(long, default_value_t = 8)]
+    payers: usize,
+    // Lamports given to each payer; enough for 10,000 transactions.
+    #[arg(long, default_value_t = 10_000 * FEE)]
+    lamports_per_payer: u64,
+
+    #[arg(long, default_value_t = 10.0)]
+    tps: f64,
+    #[arg(long, default_value_t = 10)]
+    duration_secs: u64,
+    #[arg(long, default_value_t = 200)]
+    poll_ms: u64,
+    #[arg(long, default_value_t = 60)]
+    timeout_secs: u64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    counter_client::logging::init("info");
+    let client = CounterClient::new(
+        args.url.as_deref().unwrap_or(args.cluster.default_url()),
+        args.cluster.program_id(),
+    );
+    let keypair_path = expand_home(&args.keypair);
+    let funder = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("reading keypair {keypair_path}: {err}"))?;
+
+    let payers: Vec<Keypair> = (0..args.payers.max(1)).map(|_| Keypair::new()).collect();
+    let funding: Vec<_> = payers
+        .iter()
+        .map(|payer| {
+            system_instruction::transfer(&funder.pubkey(), &payer.pubkey(), args.lamports_per_payer)
+        })
+        .collect();
+    for batch in funding.chunks(10) {
+        client.send(&funder, batch)?;
+    }
+    eprintln!("funded {} payers", payers.len());
+
+    let mut report = Report::default();
+    let mut pending: HashMap<Signature, (Instant, Pubkey)> = HashMap::new();
+    let interval = Duration::from_secs_f64(1.0 / args.tps.max(f64::MIN_POSITIVE));
+    let config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let mut blockhash = client.rpc.get_latest_blockhash()?;
+    let mut blockhash_at = start;
+    let mut polled_at = start;
+    let mut seq: u64 = 0;
+    while start.elapsed() < Duration::from_secs(args.duration_secs) {
+        // Paced from the start rather than from the last send, so slow
+        // sends don't lower the rate.
+        let next = start + interval.mul_f64(seq as f64);
+        if let Some(wait) = next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        if blockhash_at.elapsed() > Duration::from_secs(1) {
+            blockhash = client.rpc.get_latest_blockhash()?;
+            blockhash_at = Instant::now();
+        }
+        let payer = &payers[seq as usize % payers.len()];
+        let counter = &args.counters[(seq as usize / payers.len()) % args.counters.len()];
+        let transaction = increment(&client, payer, counter, seq, blockhash);
+        report.sent += 1;
+        match client
+            .rpc
+            .send_transaction_with_config(&transaction, config)
+        {
+            Ok(signature) => {
+                pending.insert(signature, (Instant::now(), *counter));
+            }
+            Err(err) => report.failed(format!("send: {}", err.kind)),
+        }
+        seq += 1;
+
+        if polled_at.elapsed() >= Duration::from_millis(args.poll_ms) {
+            poll(&client, &mut pending, &mut report)?;
+            polled_at = Instant::now();
+        }
+    }
+    report.elapsed = start.elapsed();
+
+    let deadline = Instant::now() + Duration::from_secs(args.timeout_secs);
+    while !pending.is_empty() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(args.poll_ms));
+        poll(&client, &mut pending, &mut report)?;
+    }
+    for _ in pending.drain() {
+        report.failed("timed out");
+    }
+
+    for payer in &payers {
+        let balance = client.rpc.get_balance(&payer.pubkey())?;
+        if balance > FEE {
+            let sweep =
+                system_instruction::transfer(&payer.pubkey(), &funder.pubkey(), balance - FEE);
+            client.send(payer, &[sweep])?;
+        }
+    }
+
+    print!("{report}");
+    Ok(())
+}
+
+// An increment of "counter" paid by "payer". The compute unit limit only
+// varies so that no two transactions are identical: same instructions,
+// payer and blockhash would mean the same signature, and the cluster would
+// drop the copy.
+fn increment(
+    client: &CounterClient,
+    payer: &Keypair,
+    counter: &Pubkey,
+    seq: u64,
+    blockhash: Hash,
+) -> Transaction {
+    let limit = 200_000 - (seq % 100_000) as u32;
+    Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(limit),
+            client.increment_ix(counter),
+        ],
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash,
+    )
+}
+
+// Moves every pending transaction with a confirmed status into the report.
+fn poll(
+    client: &CounterClient,
+    pending: &mut HashMap<Signature, (Instant, Pubkey)>,
+    report: &mut Report,
+) -> anyhow::Result<()> {
+    let signatures: Vec<Signature> = pending.keys().copied().collect();
+    // getSignatureStatuses takes at most 256 signatures.
+    for chunk in signatures.chunks(256) {
+        let statuses = client.rpc.get_signature_statuses(chunk)?.value;
+        for (signature, status) in chunk.iter().zip(statuses) {
+            let Some(status) = status else { continue };
+            if !status.satisfies_commitment(client.rpc.commitment()) {
+                continue;
+            }
+            let (sent_at, counter) = pending.remove(signature).expect("signature is pending");
+            let latency = sent_at.elapsed();
+            let _span = tracing::debug_span!(
+                "increment",
+                %signature,
+                slot = status.slot,
+                %counter
+            )
+            .entered();
+            match status.err {
+                None => {
+                    tracing::debug!(latency_ms = latency.as_millis() as u64, "confirmed");
+                    report.confirmed(latency);
+                }
+                Some(err) => {
+                    let cause = failure_cause(&err);
+                    tracing::debug!(%cause, "failed");
+                    report.failed(cause);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
@@ -0,0 +1,219 @@
+// The message handling behind src/bin/bridge.rs, kept free of network
+// calls so it can be tested on its own (see tests/bridge.rs).
+//
+// The bridge republishes the program's events to NATS JetStream, so
+// backend services can subscribe to "counter.events.>" without knowing
+// anything about Solana. Each event becomes one JSON Envelope on a subject
+// named after the event: "<prefix>.counter_incremented" and so on.
+//
+// Delivery is at least once. The bridge only moves its checkpoint past a
+// transaction once JetStream has acknowledged all of its events, so after
+// a crash it publishes some again. Every envelope carries a Nats-Msg-Id
+// header made from the transaction signature and the event's position, so
+// JetStream drops those repeats within its duplicate window. An event the
+// bridge can't decode or can't get acknowledged goes to the dead-letter
+// subject instead, with the reason, rather than holding up the rest.
+//
+// Only the NATS text protocol is spoken here, over a plain TCP socket.
+// Kafka's protocol is binary and versioned per request type, and there's
+// no Kafka client among this crate's dependencies; a Kafka sink would
+// consume the same Envelopes.
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use counter::{
+    AlarmTriggered, BadgeEarned, CounterDecremented, CounterFinalized, CounterIncremented,
+    CounterReset, FeePaid,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// One event, as published.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    // "<signature>:<index>", unique per event; also the Nats-Msg-Id.
+    pub id: String,
+    pub signature: String,
+    pub slot: u64,
+    // The event's position among the transaction's events.
+    pub index: usize,
+    // "counter_incremented", "fee_paid", ...
+    pub event: String,
+    pub data: Value,
+}
+
+impl Envelope {
+    pub fn new(signature: &str, slot: u64, index: usize, event: &str, data: Value) -> Self {
+        Envelope {
+            id: format!("{signature}:{index}"),
+            signature: signature.to_string(),
+            slot,
+            index,
+            event: event.to_string(),
+            data,
+        }
+    }
+}
+
+// What's in a "Program data:" log line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decoded {
+    // One of the events the bridge publishes: its subject name and fields.
+    Event(&'static str, Value),
+    // It has the discriminator of one of them, but its fields don't parse:
+    // the program and this bridge disagree on the layout.
+    Malformed(&'static str),
+    // Some other event, possibly another program's. Skipped.
+    Other,
+}
+
+pub fn decode_event(data: &[u8]) -> Decoded {
+    let Some((discriminator, mut fields)) = data.split_at_checked(8) else {
+        return Decoded::Other;
+    };
+    let fields = &mut fields;
+    macro_rules! decode {
+        ($name:literal, $event:ty, |$e:ident| $json:expr) => {
+            match <$event>::deserialize(fields) {
+                Ok($e) => Decoded::Event($name, $json),
+                Err(_) => Decoded::Malformed($name),
+            }
+        };
+    }
+    match discriminator {
+        d if d == CounterIncremented::DISCRIMINATOR => {
+            decode!("counter_incremented", CounterIncremented, |e| json!({
+                "counter": e.counter.to_string(),
+                "count": e.count,
+                "event_seq": e.event_seq,
+            }))
+        }
+        d if d == CounterDecremented::DISCRIMINATOR => {
+            decode!("counter_decremented", CounterDecremented, |e| json!({
+                "counter": e.counter.to_string(),
+                "count": e.count,
+                "event_seq": e.event_seq,
+            }))
+        }
+        d if d == CounterReset::DISCRIMINATOR => {
+            decode!("counter_reset", CounterReset, |e| json!({
+                "counter": e.counter.to_string(),
+                "previous": e.previous,
+                "lifetime_count": e.lifetime_count,
+                "event_seq": e.event_seq,
+            }))
+        }
+        d if d == AlarmTriggered::DISCRIMINATOR => {
+            decode!("alarm_triggered", AlarmTriggered, |e| json!({
+                "counter": e.counter.to_string(),
+                "count": e.count,
+                "threshold": e.threshold,
+                "event_seq": e.event_seq,
+            }))
+        }
+        d if d == FeePaid::DISCRIMINATOR => decode!("fee_paid", FeePaid, |e| json!({
+            "counter": e.counter.to_string(),
+            "payer": e.payer.to_string(),
+            "fee": e.fee,
+            "referrer": e.referrer.map(|referrer| referrer.to_string()),
+            "referrer_share": e.referrer_share,
+            "event_seq": e.event_seq,
+        })),
+        d if d == CounterFinalized::DISCRIMINATOR => {
+            decode!("counter_finalized", CounterFinalized, |e| json!({
+                "counter": e.counter.to_string(),
+                "count": e.count,
+                "slot": e.slot,
+                "event_seq": e.event_seq,
+            }))
+        }
+        d if d == BadgeEarned::DISCRIMINATOR => decode!("badge_earned", BadgeEarned, |e| json!({
+            "counter": e.counter.to_string(),
+            "owner": e.owner.to_string(),
+            "tier": e.tier,
+            "earned_at": e.earned_at,
+        })),
+        _ => Decoded::Other,
+    }
+}
+
+// An HPUB command: "payload" on "subject" with a Nats-Msg-Id header, the
+// acknowledgement to be sent to "reply".
+pub fn hpub(subject: &str, reply: &str, msg_id: &str, payload: &[u8]) -> Vec<u8> {
+    let headers = format!("NATS/1.0\r\nNats-Msg-Id: {msg_id}\r\n\r\n");
+    let mut command = format!(
+        "HPUB {subject} {reply} {} {}\r\n",
+        headers.len(),
+        headers.len() + payload.len()
+    )
+    .into_bytes();
+    command.extend_from_slice(headers.as_bytes());
+    command.extend_from_slice(payload);
+    command.extend_from_slice(b"\r\n");
+    command
+}
+
+// A MSG or HMSG line from the server: the subject, and how many bytes
+// follow (headers first, for HMSG).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsgLine {
+    pub subject: String,
+    pub header_len: usize,
+    pub total_len: usize,
+}
+
+// "MSG <subject> <sid> [reply] <len>" or
+// "HMSG <subject> <sid> [reply] <header len> <total len>".
+pub fn parse_msg_line(line: &str) -> Option<MsgLine> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let number = |part: &str| part.parse::<usize>().ok();
+    match parts.as_slice() {
+        ["MSG", subject, _sid, .., len] if parts.len() <= 5 => Some(MsgLine {
+            subject: subject.to_string(),
+            header_len: 0,
+            total_len: number(len)?,
+        }),
+        ["HMSG", subject, _sid, .., header_len, total_len] if parts.len() <= 6 => Some(MsgLine {
+            subject: subject.to_string(),
+            header_len: number(header_len)?,
+            total_len: number(total_len)?,
+        }),
+        _ => None,
+    }
+}
+
+// What a JetStream publish got back: the stream sequence the message was
+// stored at, or why it wasn't. "headers" is the HMSG header block, which
+// carries the status when no stream listens on the subject.
+pub fn parse_ack(headers: &str, payload: &[u8]) -> Result<u64, String> {
+    let status = headers.lines().next().unwrap_or_default();
+    if let Some(code) = status.strip_prefix("NATS/1.0 ") {
+        return Err(match code.split_whitespace().next() {
+            Some("503") => "no JetStream stream listens on this subject".to_string(),
+            _ => format!("status {}", code.trim()),
+        });
+    }
+    #[derive(Deserialize)]
+    struct Ack {
+        seq: Option<u64>,
+        error: Option<AckError>,
+    }
+    #[derive(Deserialize)]
+    struct AckError {
+        description: String,
+    }
+    let ack: Ack = serde_json::from_slice(payload)
+        .map_err(|err| format!("unexpected acknowledgement: {err}"))?;
+    match (ack.error, ack.seq) {
+        (Some(error), _) => Err(error.description),
+        (None, Some(seq)) => Ok(seq),
+        (None, None) => Err("acknowledgement without a sequence".to_string()),
+    }
+}
+
+// How far the bridge got: every event up to and including this
+// transaction is published. Saved after every transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub signature: String,
+    // For humans reading the file.
+    pub slot: u64,
+}
@@ -0,0 +1,124 @@
+// A local copy of the counters counter-cli has seen, so "show" and "list"
+// can answer from disk with "--offline": instantly, and without a
+// connection.
+//
+// Every counter the CLI fetches is written to the cache, together with the
+// slot it was read at, and "counter-cli watch" keeps it current from a
+// programSubscribe stream. A write only replaces an entry read at the
+// same slot or later, so a slow fetch can't overwrite what a newer update
+// already brought in. Entries are raw account data: one the current
+// program can't decode any more (after a layout change) is a miss rather
+// than an error.
+//
+// There's one file per program id, ~/.cache/counter-cli/<PROGRAM_ID>.json.
+// It's saved by writing a new file and renaming it over the old one, so a
+// reader never sees half a file; with two writers at once the last one
+// wins, which for a cache only means a lost entry. Events aren't cached:
+// the CLI never reads past events (the bridge in bridge.rs is for that).
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use counter::Counter;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid cache file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cache {
+    // By address.
+    pub counters: BTreeMap<String, Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub slot: u64,
+    // The account data, base64.
+    pub data: String,
+}
+
+impl Cache {
+    // A missing file is an empty cache.
+    pub fn load(path: &Path) -> Result<Cache, CacheError> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Cache::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CacheError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temp, serde_json::to_vec(self)?)?;
+        fs::rename(temp, path)?;
+        Ok(())
+    }
+
+    // Stores a counter's account data as read at "slot", unless the cache
+    // already has it from a later slot. Returns whether it was stored.
+    pub fn put(&mut self, address: &Pubkey, slot: u64, data: &[u8]) -> bool {
+        let key = address.to_string();
+        if self
+            .counters
+            .get(&key)
+            .is_some_and(|entry| entry.slot > slot)
+        {
+            return false;
+        }
+        let data = STANDARD.encode(data);
+        self.counters.insert(key, Entry { slot, data });
+        true
+    }
+
+    pub fn put_counter(&mut self, address: &Pubkey, slot: u64, counter: &Counter) -> bool {
+        let mut data = Vec::new();
+        counter
+            .try_serialize(&mut data)
+            .expect("serializing to a Vec can't fail");
+        self.put(address, slot, &data)
+    }
+
+    // Forgets a counter that was closed at "slot".
+    pub fn remove(&mut self, address: &Pubkey, slot: u64) {
+        let key = address.to_string();
+        if self
+            .counters
+            .get(&key)
+            .is_some_and(|entry| entry.slot <= slot)
+        {
+            self.counters.remove(&key);
+        }
+    }
+
+    // A counter and the slot it was read at.
+    pub fn get(&self, address: &Pubkey) -> Option<(Counter, u64)> {
+        let entry = self.counters.get(&address.to_string())?;
+        Some((decode(entry)?, entry.slot))
+    }
+
+    // Every cached counter, like CounterClient::list_counters.
+    pub fn list(&self, include_archived: bool) -> Vec<(Pubkey, Counter, u64)> {
+        self.counters
+            .iter()
+            .filter_map(|(address, entry)| {
+                Some((address.parse().ok()?, decode(entry)?, entry.slot))
+            })
+            .filter(|(_, counter, _)| include_archived || !counter.has_flag(Counter::ARCHIVED))
+            .collect()
+    }
+}
+
+fn decode(entry: &Entry) -> Option<Counter> {
+    let data = STANDARD.decode(&entry.data).ok()?;
+    Counter::try_deserialize(&mut data.as_slice()).ok()
+}
@@ -0,0 +1,93 @@
+// The off-chain half of ConfidentialCounter (see confidential.rs in the
+// program): encrypting deltas, decrypting totals and proving thresholds.
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use solana_zk_token_sdk::{
+    encryption::{
+        elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey, ElGamalSecretKey},
+        pedersen::Pedersen,
+    },
+    instruction::{CiphertextCommitmentEqualityProofData, RangeProofU64Data, ZkProofData},
+    zk_token_proof_instruction::{ContextStateInfo, ProofInstruction},
+    zk_token_proof_program,
+    zk_token_proof_state::ProofContextState,
+};
+
+// Encrypts a delta for increment_confidential. Every call uses fresh
+// randomness, so two encryptions of the same delta look unrelated.
+pub fn encrypt_delta(pubkey: &ElGamalPubkey, delta: u64) -> [u8; 64] {
+    pubkey.encrypt(delta).to_bytes()
+}
+
+// Decrypts a ConfidentialCounter's encrypted_count. Decryption has to
+// search for the value, which is only practical for totals below 2^32;
+// returns None above that, or if the bytes aren't a ciphertext.
+pub fn decrypt_count(secret: &ElGamalSecretKey, encrypted_count: &[u8; 64]) -> Option<u64> {
+    ElGamalCiphertext::from_bytes(encrypted_count)?.decrypt_u32(secret)
+}
+
+// The two proofs prove_threshold needs (see confidential.rs in the
+// program).
+pub struct ThresholdProofs {
+    pub equality: CiphertextCommitmentEqualityProofData,
+    pub range: RangeProofU64Data,
+}
+
+// Builds proofs that the counter's count is at least "min". Returns None
+// if it isn't (no valid proof exists) or the count can't be decrypted.
+pub fn threshold_proofs(
+    keypair: &ElGamalKeypair,
+    encrypted_count: &[u8; 64],
+    min: u64,
+) -> Option<ThresholdProofs> {
+    let count = ElGamalCiphertext::from_bytes(encrypted_count)?;
+    let remainder = count.decrypt_u32(keypair.secret())?.checked_sub(min)?;
+    // The program subtracts "min" the same way, so it ends up with the
+    // exact same ciphertext.
+    let remainder_ciphertext = count.subtract_amount(min);
+    let (commitment, opening) = Pedersen::new(remainder);
+
+    Some(ThresholdProofs {
+        equality: CiphertextCommitmentEqualityProofData::new(
+            keypair,
+            &remainder_ciphertext,
+            &commitment,
+            &opening,
+            remainder,
+        )
+        .ok()?,
+        range: RangeProofU64Data::new(&commitment, remainder, &opening).ok()?,
+    })
+}
+
+// Instructions that create a context state account at "context_account"
+// (a new keypair, which must sign) and have the proof program verify
+// "proof" into it. Send each proof in its own transaction; they're large.
+pub fn verify_into_context<T, U>(
+    payer: &Pubkey,
+    context_account: &Pubkey,
+    rent_lamports: u64,
+    proof_instruction: ProofInstruction,
+    proof: &T,
+) -> Vec<Instruction>
+where
+    T: bytemuck::Pod + ZkProofData<U>,
+    U: bytemuck::Pod,
+{
+    let space = std::mem::size_of::<ProofContextState<U>>();
+    vec![
+        system_instruction::create_account(
+            payer,
+            context_account,
+            rent_lamports,
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        proof_instruction.encode_verify_proof(
+            Some(ContextStateInfo {
+                context_state_account: context_account,
+                context_state_authority: payer,
+            }),
+            proof,
+        ),
+    ]
+}
@@ -0,0 +1,239 @@
+// The decoding and formatting behind src/bin/export.rs, kept free of RPC
+// calls so it can be tested on its own (see tests/export.rs).
+//
+// Each account type the export knows is a Kind. A Kind knows its
+// discriminator (so getProgramAccounts can ask for just that type), where
+// its owner's key sits (for filtering by owner), and how to turn an
+// account into a Row: named columns in a fixed order, one file per Kind.
+use std::fmt::Write;
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use counter::{
+    checksum_matches, decode_tag, Badge, Contribution, Counter, FinalResult, Lease, ReferrerStats,
+    Replica, StatsBucket,
+};
+use serde_json::{json, Map, Value};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::Result;
+
+// Column name and value, in column order.
+pub type Row = Vec<(&'static str, Value)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Kind {
+    Counter,
+    Replica,
+    Lease,
+    Contribution,
+    Badge,
+    ReferrerStats,
+    StatsBucket,
+    FinalResult,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 8] = [
+        Kind::Counter,
+        Kind::Replica,
+        Kind::Lease,
+        Kind::Contribution,
+        Kind::Badge,
+        Kind::ReferrerStats,
+        Kind::StatsBucket,
+        Kind::FinalResult,
+    ];
+
+    // Used for file names.
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Replica => "replica",
+            Kind::Lease => "lease",
+            Kind::Contribution => "contribution",
+            Kind::Badge => "badge",
+            Kind::ReferrerStats => "referrer_stats",
+            Kind::StatsBucket => "stats_bucket",
+            Kind::FinalResult => "final_result",
+        }
+    }
+
+    pub fn discriminator(self) -> [u8; 8] {
+        match self {
+            Kind::Counter => Counter::DISCRIMINATOR,
+            Kind::Replica => Replica::DISCRIMINATOR,
+            Kind::Lease => Lease::DISCRIMINATOR,
+            Kind::Contribution => Contribution::DISCRIMINATOR,
+            Kind::Badge => Badge::DISCRIMINATOR,
+            Kind::ReferrerStats => ReferrerStats::DISCRIMINATOR,
+            Kind::StatsBucket => StatsBucket::DISCRIMINATOR,
+            Kind::FinalResult => FinalResult::DISCRIMINATOR,
+        }
+    }
+
+    // The byte offset of the key that "owns" an account of this kind, for
+    // a memcmp filter: the authority of a counter, the renter of a lease,
+    // and so on. None for kinds that belong to no one in particular.
+    pub fn owner_offset(self) -> Option<usize> {
+        // Every offset skips the 8-byte discriminator; most also skip a
+        // leading "counter" key.
+        match self {
+            Kind::Counter => Some(8 + 8),
+            Kind::Lease | Kind::Contribution | Kind::Badge | Kind::ReferrerStats => Some(8 + 32),
+            Kind::Replica | Kind::StatsBucket | Kind::FinalResult => None,
+        }
+    }
+
+    // Decodes an account of this kind. "try_deserialize" checks the
+    // discriminator first, so an account of the wrong kind is an error.
+    pub fn decode(self, address: &Pubkey, mut data: &[u8]) -> Result<Row> {
+        let data = &mut data;
+        let mut row: Row = vec![("address", json!(address.to_string()))];
+        row.extend(match self {
+            Kind::Counter => {
+                let c = Counter::try_deserialize(data)?;
+                vec![
+                    ("count", json!(c.count)),
+                    ("authority", json!(c.authority.to_string())),
+                    ("flags", json!(c.flags)),
+                    ("archived", json!(c.has_flag(Counter::ARCHIVED))),
+                    ("last_increment_slot", json!(c.last_increment_slot)),
+                    ("alarm_threshold", json!(c.alarm_threshold)),
+                    ("event_seq", json!(c.event_seq)),
+                    ("finalized", json!(c.finalized)),
+                    ("deny_cpi", json!(c.deny_cpi)),
+                    ("lifetime_count", json!(c.lifetime_count)),
+                    ("role_weighted", json!(c.role_weighted)),
+                    (
+                        "tags",
+                        json!(c.tags().map(decode_tag).collect::<Vec<_>>().join(",")),
+                    ),
+                    ("max_count", json!(c.max_count)),
+                    ("overflow", json!(c.overflow.to_string())),
+                    // As a string: JSON numbers can't hold a u128.
+                    ("cumulative_count", json!(c.cumulative_count.to_string())),
+                    ("twac_slot", json!(c.twac_slot)),
+                    ("delta_feed_recorded", json!(c.delta_feed.recorded)),
+                    ("frozen", json!(c.frozen)),
+                    ("group", json!(c.group.to_string())),
+                    ("gifted_count", json!(c.gifted_count)),
+                    // False if the counter was changed other than by the
+                    // program (see programs/counter/src/checksum.rs).
+                    ("checksum_matches", json!(checksum_matches(data))),
+                ]
+            }
+            Kind::Replica => {
+                let r = Replica::try_deserialize(data)?;
+                vec![
+                    ("root", json!(r.root.to_string())),
+                    ("id", json!(r.id)),
+                    ("count", json!(r.count)),
+                    ("merged", json!(r.merged)),
+                ]
+            }
+            Kind::Lease => {
+                let l = Lease::try_deserialize(data)?;
+                vec![
+                    ("counter", json!(l.counter.to_string())),
+                    ("renter", json!(l.renter.to_string())),
+                    ("expires_at", json!(l.expires_at)),
+                    ("price", json!(l.price)),
+                ]
+            }
+            Kind::Contribution => {
+                let c = Contribution::try_deserialize(data)?;
+                vec![
+                    ("counter", json!(c.counter.to_string())),
+                    ("contributor", json!(c.contributor.to_string())),
+                    ("count", json!(c.count)),
+                ]
+            }
+            Kind::Badge => {
+                let b = Badge::try_deserialize(data)?;
+                vec![
+                    ("counter", json!(b.counter.to_string())),
+                    ("owner", json!(b.owner.to_string())),
+                    ("tier", json!(b.tier)),
+                    ("earned_at", json!(b.earned_at)),
+                ]
+            }
+            Kind::ReferrerStats => {
+                let s = ReferrerStats::try_deserialize(data)?;
+                vec![
+                    ("counter", json!(s.counter.to_string())),
+                    ("referrer", json!(s.referrer.to_string())),
+                    ("increments", json!(s.increments)),
+                    ("earned", json!(s.earned)),
+                ]
+            }
+            Kind::StatsBucket => {
+                let s = StatsBucket::try_deserialize(data)?;
+                vec![
+                    ("counter", json!(s.counter.to_string())),
+                    ("unix_hour", json!(s.unix_hour)),
+                    ("increments", json!(s.increments)),
+                    ("unique_actors", json!(s.unique_actors)),
+                    ("fee_revenue", json!(s.fee_revenue)),
+                ]
+            }
+            Kind::FinalResult => {
+                let r = FinalResult::try_deserialize(data)?;
+                vec![
+                    ("counter", json!(r.counter.to_string())),
+                    ("count", json!(r.count)),
+                    ("slot", json!(r.slot)),
+                    ("finalized_at", json!(r.finalized_at)),
+                ]
+            }
+        });
+        Ok(row)
+    }
+}
+
+// Rows as CSV, with a header line. Every row of one Kind has the same
+// columns, so the header comes from the first.
+pub fn to_csv(rows: &[Row]) -> String {
+    let mut out = String::new();
+    let Some(first) = rows.first() else {
+        return out;
+    };
+    let header: Vec<_> = first.iter().map(|(name, _)| csv_field(name)).collect();
+    out.push_str(&header.join(","));
+    out.push('\n');
+    for row in rows {
+        let fields: Vec<_> = row
+            .iter()
+            .map(|(_, value)| match value {
+                Value::String(s) => csv_field(s),
+                other => other.to_string(),
+            })
+            .collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+// Rows as JSON Lines: one JSON object per line.
+pub fn to_json_lines(rows: &[Row]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let object: Map<String, Value> = row
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        // Writing to a String can't fail.
+        let _ = writeln!(out, "{}", Value::Object(object));
+    }
+    out
+}
+
+// Quotes a field if it contains a comma, quote or line break, doubling
+// any quotes inside (RFC 4180).
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
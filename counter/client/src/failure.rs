@@ -0,0 +1,241 @@
+// Turning a failed transaction back into the error the program returned.
+//
+// On-chain, a CounterError becomes a bare number: "custom program error
+// 0x1784" is 6020, the 21st variant (Anchor numbers them from 6000). The
+// number is all the transaction error carries, so Failure::decode maps it
+// back to the CounterError it came from, with the index of the instruction
+// that failed. Errors from Anchor itself (account checks and the like,
+// numbered below 6000) have no enum here; their name and message are read
+// from the "AnchorError" line Anchor logs instead, when logs are at hand.
+use counter::CounterError;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+// Every CounterError, in order, so COUNTER_ERRORS[n] has number 6000 + n.
+// A new variant must be added here too (tests/failure.rs checks it is).
+pub const COUNTER_ERRORS: [CounterError; 85] = [
+    CounterError::Overflow,
+    CounterError::IndexOutOfBounds,
+    CounterError::NotUpgradeAuthority,
+    CounterError::DuplicateIdempotencyKey,
+    CounterError::Unauthorized,
+    CounterError::AlreadyCountedThisSlot,
+    CounterError::ReplicaRootMismatch,
+    CounterError::InvalidWormholeMessage,
+    CounterError::UnknownEmitter,
+    CounterError::StaleWormholeMessage,
+    CounterError::TreeLogRequired,
+    CounterError::InvalidCiphertext,
+    CounterError::InvalidThresholdProof,
+    CounterError::ConflictingModes,
+    CounterError::MintRequired,
+    CounterError::Underflow,
+    CounterError::MirrorMintRequired,
+    CounterError::Archived,
+    CounterError::InvalidLease,
+    CounterError::LeaseActive,
+    CounterError::FeeRequired,
+    CounterError::InvalidFee,
+    CounterError::ReferrerAccountsMissing,
+    CounterError::TierNotReached,
+    CounterError::VoteWeightRequired,
+    CounterError::NotVoteWeighted,
+    CounterError::InvalidLockup,
+    CounterError::LockupActive,
+    CounterError::NoVoteWeight,
+    CounterError::RevealRequired,
+    CounterError::NotCommitReveal,
+    CounterError::InvalidRound,
+    CounterError::WrongPhase,
+    CounterError::CommitMismatch,
+    CounterError::Finalized,
+    CounterError::TooEarlyToFinalize,
+    CounterError::ReplicaOvermerged,
+    CounterError::MirrorSupplyMismatch,
+    CounterError::MirrorMintMissing,
+    CounterError::VaultBelowRent,
+    CounterError::CpiDenied,
+    CounterError::CallerNotAllowed,
+    CounterError::NotMigratable,
+    CounterError::ResetMirrored,
+    CounterError::LifetimeBelowCount,
+    CounterError::RoleRequired,
+    CounterError::NotRoleWeighted,
+    CounterError::InvalidTemplateName,
+    CounterError::FeesAccountMissing,
+    CounterError::UnsupportedArgsVersion,
+    CounterError::InvalidReapPolicy,
+    CounterError::CounterNotIdle,
+    CounterError::VaultNotEmpty,
+    CounterError::SponsorQuotaReached,
+    CounterError::TreasuryTooLow,
+    CounterError::InvalidGift,
+    CounterError::GiftExpired,
+    CounterError::GiftNotExpired,
+    CounterError::InvalidTag,
+    CounterError::UnknownTag,
+    CounterError::TagRegistryFull,
+    CounterError::SoftCapReached,
+    CounterError::InvalidSoftCap,
+    CounterError::NotTwac,
+    CounterError::InvalidFreezePolicy,
+    CounterError::FreezeVotingOff,
+    CounterError::AlreadyVoted,
+    CounterError::Frozen,
+    CounterError::NotFrozen,
+    CounterError::InvalidFeeSchedule,
+    CounterError::InvalidSweep,
+    CounterError::InvalidAuthority,
+    CounterError::ChecksumMismatch,
+    CounterError::GroupRequired,
+    CounterError::GroupPaused,
+    CounterError::WrongGroup,
+    CounterError::NotTreeLogged,
+    CounterError::NotTokenMirrored,
+    CounterError::NotFeeRequired,
+    CounterError::RevealTooLarge,
+    CounterError::OwnerlessCounter,
+    CounterError::GiftedCounter,
+    CounterError::LeaderboardEntryLive,
+    CounterError::NotOnLeaderboard,
+    CounterError::SelfReferral,
+];
+
+// The first CounterError number.
+pub const COUNTER_ERROR_OFFSET: u32 = anchor_lang::error::ERROR_CODE_OFFSET;
+
+// The CounterError with number "code", if there is one.
+pub fn counter_error(code: u32) -> Option<CounterError> {
+    let index = code.checked_sub(COUNTER_ERROR_OFFSET)?;
+    COUNTER_ERRORS.get(usize::try_from(index).ok()?).copied()
+}
+
+// Why a transaction failed.
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Failure {
+    // The counter program rejected it.
+    #[error("instruction {instruction} failed: {error} ({})", error.name())]
+    Counter {
+        instruction: u8,
+        error: CounterError,
+    },
+    // Anchor rejected it before our code ran, e.g. a wrong account.
+    #[error("instruction {instruction} failed: {message} ({name})")]
+    Anchor {
+        instruction: u8,
+        code: u32,
+        name: String,
+        message: String,
+    },
+    // Some other program, or the runtime, rejected an instruction.
+    #[error("instruction {instruction} failed: {error}")]
+    Instruction {
+        instruction: u8,
+        error: InstructionError,
+    },
+    // The transaction as a whole was rejected (fees, blockhash, ...).
+    #[error(transparent)]
+    Transaction(TransactionError),
+}
+
+impl Failure {
+    // "logs" may be empty; they're only needed for Anchor's own errors.
+    pub fn decode(err: &TransactionError, logs: &[String]) -> Failure {
+        let TransactionError::InstructionError(instruction, ix_err) = err else {
+            return Failure::Transaction(err.clone());
+        };
+        let instruction = *instruction;
+        if let InstructionError::Custom(code) = ix_err {
+            if let Some(error) = counter_error(*code) {
+                return Failure::Counter { instruction, error };
+            }
+            if let Some(logged) = anchor_error(logs).filter(|logged| logged.number == *code) {
+                return Failure::Anchor {
+                    instruction,
+                    code: *code,
+                    name: logged.name,
+                    message: logged.message,
+                };
+            }
+        }
+        Failure::Instruction {
+            instruction,
+            error: ix_err.clone(),
+        }
+    }
+
+    // The program error number, if it was one.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            Failure::Counter { error, .. } => Some(u32::from(*error)),
+            Failure::Anchor { code, .. } => Some(*code),
+            Failure::Instruction {
+                error: InstructionError::Custom(code),
+                ..
+            } => Some(*code),
+            _ => None,
+        }
+    }
+
+    // The error's name, like "FeeRequired", if it's known.
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Failure::Counter { error, .. } => Some(error.name()),
+            Failure::Anchor { name, .. } => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    // Which instruction failed, if it was one instruction.
+    pub fn instruction(&self) -> Option<u8> {
+        match self {
+            Failure::Counter { instruction, .. }
+            | Failure::Anchor { instruction, .. }
+            | Failure::Instruction { instruction, .. } => Some(*instruction),
+            Failure::Transaction(_) => None,
+        }
+    }
+}
+
+// An Anchor error as the program logged it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedError {
+    pub name: String,
+    pub number: u32,
+    pub message: String,
+}
+
+// Finds the error Anchor logged, from a line like
+// "Program log: AnchorError occurred. Error Code: FeeRequired. Error
+// Number: 6020. Error Message: ...". Errors raised by account constraints
+// start differently but end the same way.
+pub fn anchor_error(logs: &[String]) -> Option<LoggedError> {
+    logs.iter().rev().find_map(|line| {
+        if !line.contains("AnchorError") {
+            return None;
+        }
+        let (_, rest) = line.split_once("Error Code: ")?;
+        let (name, rest) = rest.split_once(". Error Number: ")?;
+        let (number, message) = rest.split_once(". Error Message: ")?;
+        Some(LoggedError {
+            name: name.to_string(),
+            number: number.parse().ok()?,
+            message: message.strip_suffix('.').unwrap_or(message).to_string(),
+        })
+    })
+}
+
+// The logs of a transaction that failed preflight simulation, which is how
+// most failures reach a client.
+pub fn preflight_logs(err: &ClientError) -> &[String] {
+    match &err.kind {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.logs.as_deref().unwrap_or_default(),
+        _ => &[],
+    }
+}
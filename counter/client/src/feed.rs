@@ -0,0 +1,55 @@
+// Instructions for the counter-feed program (programs/counter-feed) and
+// the rule src/bin/feed-crank.rs uses to decide when a feed needs one.
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use counter_feed::{Feed, FEED_SEED};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_program};
+
+// A counter's Feed PDA.
+pub fn feed_address(counter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[FEED_SEED, counter.as_ref()], &counter_feed::ID).0
+}
+
+pub fn initialize_feed_ix(counter: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: counter_feed::ID,
+        accounts: counter_feed::accounts::InitializeFeed {
+            feed: feed_address(counter),
+            counter: *counter,
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: counter_feed::instruction::InitializeFeed {}.data(),
+    }
+}
+
+pub fn update_feed_ix(counter: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: counter_feed::ID,
+        accounts: counter_feed::accounts::UpdateFeed {
+            feed: feed_address(counter),
+            counter: *counter,
+        }
+        .to_account_metas(None),
+        data: counter_feed::instruction::UpdateFeed {}.data(),
+    }
+}
+
+// Decodes a Feed account's data, or None if it isn't one.
+pub fn decode_feed(data: &[u8]) -> Option<Feed> {
+    if data.len() != Feed::SPACE || data[..8] != Feed::DISCRIMINATOR {
+        return None;
+    }
+    Some(bytemuck::pod_read_unaligned(&data[8..]))
+}
+
+// Whether a feed should be updated: the usual oracle rule of publishing on
+// any change, and at least every "heartbeat" seconds even without one, so
+// consumers can tell a quiet counter from a dead crank. "now" is Unix time.
+// A finalized feed is never due: its value is final and never goes stale.
+pub fn update_due(feed: &Feed, count: u64, now: i64, heartbeat: u64) -> bool {
+    if feed.finalized != 0 {
+        return false;
+    }
+    feed.value != count || now.saturating_sub(feed.timestamp) >= heartbeat as i64
+}
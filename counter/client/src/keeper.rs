@@ -0,0 +1,116 @@
+// What src/bin/keeper.rs looks for and how it pays for it.
+//
+// Some of the program's instructions exist to be sent by anyone once a
+// deadline passes: finalize, after a counter's end time (see
+// programs/counter/src/finalize.rs), forfeit_commit, after a
+// commit-reveal round (see commit_reveal.rs), and reap, for reapable
+// counters that changed or went idle (see reap.rs). Nobody in particular is
+// waiting to send them, so without a keeper they don't get sent. The
+// rules for when each is due live here, next to the account discovery,
+// so they can be tested without a cluster.
+use anchor_lang::{AccountDeserialize, Discriminator};
+use counter::{CommitRound, Commitment, Counter, Deadline, ReapPolicy};
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, hash::hash, instruction::Instruction, pubkey::Pubkey,
+};
+
+use crate::{CounterClient, Result};
+
+// Every account of type T the program owns, found with getProgramAccounts
+// filtered on T's discriminator and "space" (its size, discriminator
+// included), so the RPC node only sends back those.
+pub fn program_accounts<T: AccountDeserialize + Discriminator>(
+    client: &CounterClient,
+    space: usize,
+) -> Result<Vec<(Pubkey, T)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(space as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &T::DISCRIMINATOR)),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            commitment: Some(client.rpc.commitment()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = client
+        .rpc
+        .get_program_accounts_with_config(&client.program_id, config)?;
+    accounts
+        .into_iter()
+        .map(|(address, account)| Ok((address, T::try_deserialize(&mut account.data.as_slice())?)))
+        .collect()
+}
+
+// Whether finalize can be sent for "counter": its end time has come and it
+// isn't finalized already. "now" is Unix time.
+pub fn finalize_due(deadline: &Deadline, counter: &Counter, now: i64) -> bool {
+    !counter.finalized && now >= deadline.end_time
+}
+
+// The round "commitment" was made in, found by rederiving its address for
+// each of the counter's rounds so far. Commitments don't store their round
+// or counter; both are only in the seeds.
+pub fn commitment_round(
+    client: &CounterClient,
+    address: &Pubkey,
+    counter: &Pubkey,
+    commitment: &Commitment,
+    current: &CommitRound,
+) -> Option<u64> {
+    (1..=current.round).rev().find(|&round| {
+        client.commitment_address(counter, &commitment.participant, round) == *address
+    })
+}
+
+// Whether a commitment made in "round" can be forfeited: the same rule as
+// forfeit_commit. Any commitment still around after its round is over was
+// never revealed, since revealing closes it.
+pub fn forfeit_due(current: &CommitRound, round: u64, now: i64) -> bool {
+    round < current.round || current.is_over(now)
+}
+
+// Whether reap is worth sending for a reapable counter, given its data and
+// its vault's balance: either the counter changed since the policy last
+// looked, and reap restarts the wait, or it has been idle long enough and
+// reap closes it. The program won't reap a counter whose vault holds
+// anything, so those are left alone until they change.
+pub fn reap_due(policy: &ReapPolicy, counter_data: &[u8], vault_lamports: u64, epoch: u64) -> bool {
+    if hash(counter_data).to_bytes() != policy.seen {
+        return true;
+    }
+    vault_lamports == 0 && epoch >= policy.seen_epoch.saturating_add(policy.idle_epochs)
+}
+
+// The priority fee to bid, in micro-lamports per compute unit: the
+// "percentile"th of the recent fees paid to write the same accounts
+// (getRecentPrioritizationFees), capped at "max". Nothing here is urgent,
+// so there's no point outbidding the going rate; the cap keeps a fee
+// spike from draining the keeper.
+pub fn priority_fee(recent: &[u64], percentile: u8, max: u64) -> u64 {
+    if recent.is_empty() {
+        return 0;
+    }
+    let mut fees = recent.to_vec();
+    fees.sort_unstable();
+    let index = (fees.len() - 1) * usize::from(percentile.min(100)) / 100;
+    fees[index].min(max)
+}
+
+// "instructions" with a compute unit price of "micro_lamports" in front,
+// or as they are for 0.
+pub fn with_priority_fee(instructions: &[Instruction], micro_lamports: u64) -> Vec<Instruction> {
+    let mut all = Vec::with_capacity(instructions.len() + 1);
+    if micro_lamports > 0 {
+        all.push(ComputeBudgetInstruction::set_compute_unit_price(
+            micro_lamports,
+        ));
+    }
+    all.extend_from_slice(instructions);
+    all
+}
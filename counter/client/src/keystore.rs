@@ -0,0 +1,186 @@
+// Passphrase-encrypted keypairs for counter-cli, so authority keys don't
+// sit in plaintext JSON files (see "counter-cli keys").
+//
+// Each key is one JSON file in the keystore directory
+// (~/.config/counter-cli/keys by default), named after the key. The public
+// key is stored in the clear, so "keys list" works without a passphrase.
+// The secret key is encrypted with AES-256-GCM-SIV under a key derived
+// from the passphrase with PBKDF2-HMAC-SHA256 and a random salt. GCM-SIV
+// authenticates what it decrypts, so a wrong passphrase is an error rather
+// than a garbage key.
+//
+// scrypt or age would make guessing passphrases more expensive than
+// PBKDF2 does; neither is a dependency of this crate yet, while everything
+// used here already is (via the Solana crates). "kdf" in the file leaves
+// room to switch.
+use std::{fs, io, path::PathBuf};
+
+use aes_gcm_siv::{
+    aead::{Aead, NewAead},
+    Aes256GcmSiv, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::Hmac;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+pub const KDF: &str = "pbkdf2-hmac-sha256";
+// Unlocks keys without a prompt, for scripts.
+pub const PASSPHRASE_ENV: &str = "COUNTER_CLI_PASSPHRASE";
+// OWASP's recommendation for PBKDF2-HMAC-SHA256.
+pub const ITERATIONS: u32 = 600_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeystoreError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid key file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("wrong passphrase, or the key file was changed")]
+    WrongPassphrase,
+    #[error("invalid key file: {0}")]
+    Corrupt(&'static str),
+    #[error("no key named {0:?}")]
+    NotFound(String),
+    #[error("a key named {0:?} already exists")]
+    Exists(String),
+    #[error("invalid key name {0:?}: use letters, digits, '-' and '_'")]
+    InvalidName(String),
+}
+
+// One key file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedKey {
+    pub pubkey: String,
+    pub kdf: String,
+    pub iterations: u32,
+    // base64
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl EncryptedKey {
+    pub fn encrypt(keypair: &Keypair, passphrase: &str, iterations: u32) -> EncryptedKey {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let cipher = cipher(passphrase, &salt, iterations);
+        let ciphertext = cipher
+            .encrypt(&Nonce::from(nonce), keypair.to_bytes().as_ref())
+            .expect("encrypting to a Vec can't fail");
+        EncryptedKey {
+            pubkey: keypair.pubkey().to_string(),
+            kdf: KDF.to_string(),
+            iterations,
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        }
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair, KeystoreError> {
+        if self.kdf != KDF {
+            return Err(KeystoreError::Corrupt("unknown kdf"));
+        }
+        let decode = |field: &str| {
+            STANDARD
+                .decode(field)
+                .map_err(|_| KeystoreError::Corrupt("invalid base64"))
+        };
+        let nonce: [u8; 12] = decode(&self.nonce)?
+            .try_into()
+            .map_err(|_| KeystoreError::Corrupt("invalid nonce"))?;
+        let cipher = cipher(passphrase, &decode(&self.salt)?, self.iterations);
+        let secret = cipher
+            .decrypt(&Nonce::from(nonce), decode(&self.ciphertext)?.as_ref())
+            .map_err(|_| KeystoreError::WrongPassphrase)?;
+        let keypair =
+            Keypair::from_bytes(&secret).map_err(|_| KeystoreError::Corrupt("invalid keypair"))?;
+        if keypair.pubkey().to_string() != self.pubkey {
+            return Err(KeystoreError::Corrupt("public key doesn't match"));
+        }
+        Ok(keypair)
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256GcmSiv {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, iterations, &mut key);
+    Aes256GcmSiv::new(&Key::from(key))
+}
+
+// A directory of key files.
+pub struct Keystore {
+    pub dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Keystore { dir: dir.into() }
+    }
+
+    // Names become file names, so they can't contain paths.
+    fn path(&self, name: &str) -> Result<PathBuf, KeystoreError> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(KeystoreError::InvalidName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+
+    // Refuses to overwrite an existing key: that could lose the only copy.
+    pub fn save(&self, name: &str, key: &EncryptedKey) -> Result<(), KeystoreError> {
+        let path = self.path(name)?;
+        fs::create_dir_all(&self.dir)?;
+        if path.exists() {
+            return Err(KeystoreError::Exists(name.to_string()));
+        }
+        fs::write(path, serde_json::to_vec_pretty(key)?)?;
+        Ok(())
+    }
+
+    pub fn load(&self, name: &str) -> Result<EncryptedKey, KeystoreError> {
+        match fs::read(self.path(name)?) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(KeystoreError::NotFound(name.to_string()))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Every key's name and public key, sorted by name.
+    pub fn list(&self) -> Result<Vec<(String, Pubkey)>, KeystoreError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut keys = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            let pubkey = self
+                .load(name)?
+                .pubkey
+                .parse()
+                .map_err(|_| KeystoreError::Corrupt("invalid public key"))?;
+            keys.push((name.to_string(), pubkey));
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
@@ -0,0 +1,1156 @@
+// ============================================================================
+// COUNTER CLIENT - talking to the counter program from Rust
+// ============================================================================
+
+// The programs in programs/ run on-chain. This crate is the other side: code
+// that runs on an ordinary computer, reads counter accounts over RPC, and
+// sends transactions. The binaries in src/bin/ are built on top of it.
+//
+// It depends on the program crate itself (with "no-entrypoint"), so account
+// layouts and instruction encodings always match the program they were
+// compiled with instead of being copied by hand.
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, Space, ToAccountMetas};
+use counter::Counter;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    commitment_config::CommitmentConfig,
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_program,
+    transaction::Transaction,
+};
+
+pub mod bridge;
+pub mod cache;
+pub mod confidential;
+pub mod export;
+pub mod failure;
+pub mod feed;
+pub mod jito;
+pub mod keeper;
+pub mod keystore;
+pub mod load;
+pub mod logging;
+pub mod notify;
+pub mod output;
+pub mod profile;
+pub mod relay;
+pub mod simulate;
+pub mod squads;
+
+// The program's address on each cluster. These match the declare_id!s in
+// programs/counter/src/lib.rs; the program crate only knows the one it was
+// built for, but tools like the relayer talk to several clusters at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cluster {
+    Localnet,
+    Devnet,
+    Mainnet,
+}
+
+impl Cluster {
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            Cluster::Localnet => pubkey!("2LUoJnKc5maGZYcyqMcGk2WwdKwTygxwRP7uqBUu6h6y"),
+            Cluster::Devnet => pubkey!("DyjzWj2QdUAzMjdstmUgDZUDF6oZKoiv4Ym3bMX95iU5"),
+            Cluster::Mainnet => pubkey!("EC2qL9qXo65QZ4HbNwrnDK3E2Y3GTLttwYJrwnP6i5cC"),
+        }
+    }
+
+    // The public RPC endpoint, used unless a tool is given its own URL.
+    pub fn default_url(self) -> &'static str {
+        match self {
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    // Boxed because RPC errors are large, and every Result in this crate
+    // would otherwise be as big as one.
+    #[error(transparent)]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("account {0} not found")]
+    NotFound(Pubkey),
+    #[error("account {address} is owned by {owner}, not the counter program {program_id}")]
+    WrongOwner {
+        address: Pubkey,
+        owner: Pubkey,
+        program_id: Pubkey,
+    },
+    #[error("could not decode account: {0}")]
+    Decode(#[from] anchor_lang::error::Error),
+    // The transaction was sent but rejected (see failure.rs).
+    #[error("transaction failed: {0}")]
+    Failed(failure::Failure),
+}
+
+impl From<solana_client::client_error::ClientError> for Error {
+    fn from(err: solana_client::client_error::ClientError) -> Self {
+        match err.get_transaction_error() {
+            Some(tx_err) => Error::Failed(failure::Failure::decode(
+                &tx_err,
+                failure::preflight_logs(&err),
+            )),
+            None => Error::Rpc(Box::new(err)),
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+// An RPC connection to the counter program on one cluster.
+pub struct CounterClient {
+    pub rpc: RpcClient,
+    pub program_id: Pubkey,
+}
+
+impl CounterClient {
+    pub fn new(url: impl ToString, program_id: Pubkey) -> Self {
+        CounterClient::new_with_commitment(url, program_id, CommitmentConfig::confirmed())
+    }
+
+    pub fn new_with_commitment(
+        url: impl ToString,
+        program_id: Pubkey,
+        commitment: CommitmentConfig,
+    ) -> Self {
+        let rpc = RpcClient::new_with_commitment(url.to_string(), commitment);
+        CounterClient { rpc, program_id }
+    }
+
+    // Fetches and decodes a Counter, together with the slot the RPC node
+    // read it at.
+    #[tracing::instrument(level = "debug", skip(self), fields(counter = %address, slot))]
+    pub fn fetch_counter(&self, address: &Pubkey) -> Result<(Counter, u64)> {
+        let response = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())?;
+        tracing::Span::current().record("slot", response.context.slot);
+        let account = response.value.ok_or(Error::NotFound(*address))?;
+        if account.owner != self.program_id {
+            return Err(Error::WrongOwner {
+                address: *address,
+                owner: account.owner,
+                program_id: self.program_id,
+            });
+        }
+        // "try_deserialize" checks the discriminator before decoding, the
+        // same check the program does for Account<'info, Counter>.
+        let counter = Counter::try_deserialize(&mut account.data.as_slice())?;
+        tracing::debug!(count = counter.count, "fetched counter");
+        Ok((counter, response.context.slot))
+    }
+
+    // The counter's TWAC accumulator (see programs/counter/src/twac.rs) as
+    // of the slot the RPC node read it at, or None if it doesn't keep one.
+    // Two of these, some slots apart, give the average count in between
+    // (see TwacObservation::average_since).
+    pub fn fetch_twac(&self, address: &Pubkey) -> Result<Option<counter::TwacObservation>> {
+        let (counter, slot) = self.fetch_counter(address)?;
+        Ok(counter.is_twac().then(|| counter.observe(slot)))
+    }
+
+    // The changes to a counter's count since a previous poll saw
+    // "recorded" entries in its delta feed (see
+    // programs/counter/src/deltas.rs), with the new "recorded" to pass next
+    // time. None if the counter doesn't keep a feed.
+    pub fn fetch_deltas_since(
+        &self,
+        address: &Pubkey,
+        recorded: u64,
+    ) -> Result<Option<(counter::DeltasSince, u64)>> {
+        let (counter, _) = self.fetch_counter(address)?;
+        let feed = counter.delta_feed;
+        Ok(feed.is_on().then(|| (feed.since(recorded), feed.recorded)))
+    }
+
+    // Every Counter the program owns, skipping archived ones unless
+    // "include_archived" is set.
+    //
+    // getProgramAccounts returns every account of the program, so the
+    // filters make the RPC node only send back ones with the Counter
+    // discriminator and size.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn list_counters(&self, include_archived: bool) -> Result<Vec<(Pubkey, Counter)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize((8 + Counter::INIT_SPACE) as u64),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Counter::DISCRIMINATOR)),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                commitment: Some(self.rpc.commitment()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(&self.program_id, config)?;
+
+        let mut counters = Vec::with_capacity(accounts.len());
+        for (address, account) in accounts {
+            let counter = Counter::try_deserialize(&mut account.data.as_slice())?;
+            if include_archived || !counter.has_flag(Counter::ARCHIVED) {
+                counters.push((address, counter));
+            }
+        }
+        tracing::debug!(found = counters.len(), "listed counters");
+        Ok(counters)
+    }
+
+    // Every Counter tagged "tag" (see programs/counter/src/tags.rs).
+    //
+    // A memcmp filter matches at one offset, and the tag could be in any
+    // of a counter's slots, so this asks once per slot (see tag_filters).
+    // A counter's tags are distinct, so none comes back twice.
+    #[tracing::instrument(level = "debug", skip(self), fields(tag = %counter::decode_tag(tag)))]
+    pub fn fetch_by_tag(&self, tag: &[u8; counter::TAG_LEN]) -> Result<Vec<(Pubkey, Counter)>> {
+        let mut counters = Vec::new();
+        for slot in 0..counter::MAX_COUNTER_TAGS {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(tag_filters(tag, slot)),
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(self.rpc.commitment()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            for (address, account) in self
+                .rpc
+                .get_program_accounts_with_config(&self.program_id, config)?
+            {
+                let counter = Counter::try_deserialize(&mut account.data.as_slice())?;
+                counters.push((address, counter));
+            }
+        }
+        tracing::debug!(found = counters.len(), "fetched tagged counters");
+        Ok(counters)
+    }
+
+    // The TagRegistry PDA.
+    pub fn tag_registry_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[counter::TAG_REGISTRY_SEED], &self.program_id).0
+    }
+
+    // Fetches the registered tags, in the order they were added.
+    pub fn fetch_tags(&self) -> Result<Vec<[u8; counter::TAG_LEN]>> {
+        let address = self.tag_registry_address();
+        let account = self
+            .rpc
+            .get_account_with_commitment(&address, self.rpc.commitment())?
+            .value
+            .ok_or(Error::NotFound(address))?;
+        if account.owner != self.program_id {
+            return Err(Error::WrongOwner {
+                address,
+                owner: account.owner,
+                program_id: self.program_id,
+            });
+        }
+        let registry = counter::TagRegistry::try_deserialize(&mut account.data.as_slice())?;
+        Ok(registry.tags)
+    }
+
+    // Builds an "initialize" instruction creating a counter at "counter",
+    // owned by "user". Both must sign.
+    pub fn initialize_ix(&self, counter: &Pubkey, user: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::Initialize {
+                counter: *counter,
+                user: *user,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Initialize {}.data(),
+        }
+    }
+
+    // Like initialize_ix, but the counter is "authority"'s and "payer"
+    // pays its rent. "counter" and "payer" sign; "authority" doesn't.
+    pub fn initialize_funded_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        payer: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::InitializeFunded {
+                counter: *counter,
+                authority: *authority,
+                payer: *payer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::InitializeFunded {}.data(),
+        }
+    }
+
+    // Builds a "verify_checksum" instruction, which fails if "counter" was
+    // changed other than by the program. Nothing signs; simulate it to
+    // check a counter without paying. counter::checksum_matches does the
+    // same check on account data already fetched.
+    pub fn verify_checksum_ix(&self, counter: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::ReadCounter { counter: *counter }.to_account_metas(None),
+            data: counter::instruction::VerifyChecksum {}.data(),
+        }
+    }
+
+    // The program-wide Config PDA (see programs/counter/src/config.rs).
+    pub fn config_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[counter::CONFIG_SEED], &self.program_id).0
+    }
+
+    // Builds an "initialize_config" instruction. "authority" must be the
+    // program's upgrade authority.
+    pub fn initialize_config_ix(&self, authority: &Pubkey, admin: Pubkey) -> Instruction {
+        let program_data =
+            Pubkey::find_program_address(&[self.program_id.as_ref()], &bpf_loader_upgradeable::ID)
+                .0;
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::InitializeConfig {
+                config: self.config_address(),
+                authority: *authority,
+                program: self.program_id,
+                program_data,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::InitializeConfig { admin }.data(),
+        }
+    }
+
+    // The Template PDA named "name" (see programs/counter/src/templates.rs).
+    pub fn template_address(&self, name: &str) -> Pubkey {
+        Pubkey::find_program_address(&[counter::TEMPLATE_SEED, name.as_bytes()], &self.program_id).0
+    }
+
+    // Builds a "set_template" instruction. "admin" must be the Config's
+    // admin, and pays for the template the first time.
+    pub fn set_template_ix(
+        &self,
+        admin: &Pubkey,
+        name: &str,
+        settings: counter::TemplateSettings,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::SetTemplate {
+                config: self.config_address(),
+                template: self.template_address(name),
+                admin: *admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::SetTemplate {
+                args: counter::TemplateArgs {
+                    version: counter::TemplateArgs::VERSION,
+                    name: name.to_string(),
+                    settings,
+                },
+            }
+            .data(),
+        }
+    }
+
+    // Builds an "initialize_from_template" instruction, like initialize_ix
+    // but with the settings of the template "name". "with_fees" passes the
+    // counter's fees account, which templates with a fee need.
+    pub fn initialize_from_template_ix(
+        &self,
+        counter: &Pubkey,
+        user: &Pubkey,
+        name: &str,
+        with_fees: bool,
+    ) -> Instruction {
+        let mut accounts = counter::accounts::InitializeFromTemplate {
+            counter: *counter,
+            template: self.template_address(name),
+            fees: with_fees.then(|| self.counter_pda(counter::FEES_SEED, counter)),
+            user: *user,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        // Anchor passes a missing optional account as the program's own
+        // address, and only knows the one it was built for.
+        if !with_fees {
+            accounts[2].pubkey = self.program_id;
+        }
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: counter::instruction::InitializeFromTemplate {}.data(),
+        }
+    }
+
+    // Builds an "initialize_sponsored" instruction: initialize_ix with the
+    // rent paid by the program's treasury, within "user"'s quota. "user"
+    // needn't hold any SOL, so someone else can pay the transaction fee.
+    pub fn initialize_sponsored_ix(&self, counter: &Pubkey, user: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::InitializeSponsored {
+                sponsorship: Pubkey::find_program_address(
+                    &[counter::SPONSORSHIP_SEED],
+                    &self.program_id,
+                )
+                .0,
+                treasury: self.treasury_address(),
+                sponsored_wallet: Pubkey::find_program_address(
+                    &[counter::SPONSORED_WALLET_SEED, user.as_ref()],
+                    &self.program_id,
+                )
+                .0,
+                counter: *counter,
+                user: *user,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::InitializeSponsored {}.data(),
+        }
+    }
+
+    // The program's treasury, which pays for sponsored counters. Fund it
+    // with a plain transfer.
+    pub fn treasury_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[counter::TREASURY_SEED], &self.program_id).0
+    }
+
+    // Builds a "create_gift" instruction: a new counter starting at
+    // "count", which "recipient" can claim within "duration" seconds.
+    // "creator" pays the rent, and gets it back when the gift is claimed
+    // or expires.
+    pub fn create_gift_ix(
+        &self,
+        counter: &Pubkey,
+        creator: &Pubkey,
+        count: u64,
+        recipient: Pubkey,
+        duration: i64,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::CreateGift {
+                counter: *counter,
+                gift: self.counter_pda(counter::GIFT_SEED, counter),
+                creator: *creator,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::CreateGift {
+                args: counter::GiftArgs {
+                    version: counter::GiftArgs::VERSION,
+                    count,
+                    recipient,
+                    duration,
+                },
+            }
+            .data(),
+        }
+    }
+
+    // Builds a "claim_gift" instruction, signed by the gift's recipient.
+    pub fn claim_gift_ix(
+        &self,
+        counter: &Pubkey,
+        recipient: &Pubkey,
+        creator: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::ClaimGift {
+                counter: *counter,
+                gift: self.counter_pda(counter::GIFT_SEED, counter),
+                access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                recipient: *recipient,
+                creator: *creator,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::ClaimGift {}.data(),
+        }
+    }
+
+    // Builds a "reclaim_gift" instruction for an expired gift. Anyone can
+    // send it.
+    pub fn reclaim_gift_ix(&self, counter: &Pubkey, creator: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::ReclaimGift {
+                counter: *counter,
+                gift: self.counter_pda(counter::GIFT_SEED, counter),
+                vault: self.counter_pda(counter::VAULT_SEED, counter),
+                creator: *creator,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::ReclaimGift {}.data(),
+        }
+    }
+
+    // The Leaderboard PDA (see programs/counter/src/leaderboard.rs).
+    pub fn leaderboard_address(&self) -> Pubkey {
+        Pubkey::find_program_address(&[counter::LEADERBOARD_SEED], &self.program_id).0
+    }
+
+    // Fetches the Leaderboard's entries, highest count first.
+    pub fn fetch_leaderboard(&self) -> Result<Vec<counter::LeaderboardEntry>> {
+        let address = self.leaderboard_address();
+        let account = self
+            .rpc
+            .get_account_with_commitment(&address, self.rpc.commitment())?
+            .value
+            .ok_or(Error::NotFound(address))?;
+        if account.owner != self.program_id {
+            return Err(Error::WrongOwner {
+                address,
+                owner: account.owner,
+                program_id: self.program_id,
+            });
+        }
+        let leaderboard = decode_leaderboard(&account.data).ok_or_else(|| {
+            Error::Decode(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into())
+        })?;
+        Ok(leaderboard.ranked().to_vec())
+    }
+
+    // Builds an "initialize_leaderboard" instruction. It only needs
+    // sending once, by anyone; "payer" pays the rent.
+    pub fn initialize_leaderboard_ix(&self, payer: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::InitializeLeaderboard {
+                leaderboard: self.leaderboard_address(),
+                payer: *payer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::InitializeLeaderboard {}.data(),
+        }
+    }
+
+    // Builds an "update_leaderboard" instruction, submitting a counter's
+    // current count.
+    pub fn update_leaderboard_ix(&self, counter: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::UpdateLeaderboard {
+                leaderboard: self.leaderboard_address(),
+                counter: *counter,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::UpdateLeaderboard {}.data(),
+        }
+    }
+
+    // Builds an "evict_leaderboard_entry" instruction, for a counter on the
+    // board that's since been closed or was gifted.
+    pub fn evict_leaderboard_entry_ix(&self, counter: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::EvictLeaderboardEntry {
+                leaderboard: self.leaderboard_address(),
+                counter: *counter,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::EvictLeaderboardEntry {}.data(),
+        }
+    }
+
+    // Builds a "register_tag" instruction. "admin" is the Config's, and
+    // pays for the TagRegistry the first time.
+    pub fn register_tag_ix(&self, admin: &Pubkey, tag: [u8; counter::TAG_LEN]) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::RegisterTag {
+                config: self.config_address(),
+                tag_registry: self.tag_registry_address(),
+                admin: *admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::RegisterTag { tag }.data(),
+        }
+    }
+
+    // Like initialize_ix, with up to MAX_COUNTER_TAGS registered tags.
+    pub fn initialize_tagged_ix(
+        &self,
+        counter: &Pubkey,
+        user: &Pubkey,
+        tags: Vec<[u8; counter::TAG_LEN]>,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::InitializeTagged {
+                counter: *counter,
+                tag_registry: self.tag_registry_address(),
+                user: *user,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::InitializeTagged { tags }.data(),
+        }
+    }
+
+    // Like increment_ix, with the Leaderboard passed along so the new
+    // count goes on it.
+    pub fn increment_ranked_ix(&self, counter: &Pubkey) -> Instruction {
+        let mut ix = self.increment_ix(counter);
+        ix.accounts
+            .push(AccountMeta::new(self.leaderboard_address(), false));
+        ix
+    }
+
+    // Builds an "increment" instruction for a counter.
+    pub fn increment_ix(&self, counter: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::Increment { counter: *counter }.to_account_metas(None),
+            data: counter::instruction::Increment {}.data(),
+        }
+    }
+
+    // Builds a "set_increment_fee" instruction. "authority" is the
+    // counter's, and pays for its fees and access log accounts the first
+    // time.
+    pub fn set_increment_fee_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        fee_lamports: u64,
+        referral_bps: u16,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::SetIncrementFee {
+                guard: counter::accounts::AuthorityGuard {
+                    counter: *counter,
+                    access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                    authority: *authority,
+                    system_program: system_program::ID,
+                },
+                fees: self.counter_pda(counter::FEES_SEED, counter),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::SetIncrementFee {
+                args: counter::IncrementFeeArgs {
+                    version: counter::IncrementFeeArgs::VERSION,
+                    fee_lamports,
+                    referral_bps,
+                },
+            }
+            .data(),
+        }
+    }
+
+    // Builds a "set_fee_schedule" instruction, charging by the day's volume
+    // (see programs/counter/src/fees.rs). "authority" is as in
+    // set_increment_fee_ix, and also pays for the schedule the first time.
+    pub fn set_fee_schedule_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        tiers: Vec<counter::FeeTier>,
+        referral_bps: u16,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::SetFeeSchedule {
+                guard: counter::accounts::AuthorityGuard {
+                    counter: *counter,
+                    access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                    authority: *authority,
+                    system_program: system_program::ID,
+                },
+                fees: self.counter_pda(counter::FEES_SEED, counter),
+                fee_schedule: self.counter_pda(counter::FEE_SCHEDULE_SEED, counter),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::SetFeeSchedule {
+                args: counter::FeeScheduleArgs {
+                    version: counter::FeeScheduleArgs::VERSION,
+                    tiers,
+                    referral_bps,
+                },
+            }
+            .data(),
+        }
+    }
+
+    // Builds a "sweep_fees" instruction, emptying the vaults of
+    // "counters" (all "authority"'s) into the treasury at once. Mind the
+    // transaction size: each counter adds two accounts.
+    pub fn sweep_fees_ix(&self, authority: &Pubkey, counters: &[Pubkey]) -> Instruction {
+        let mut accounts = counter::accounts::SweepFees {
+            treasury: self.treasury_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        for counter in counters {
+            accounts.push(AccountMeta::new(*counter, false));
+            accounts.push(AccountMeta::new(
+                self.counter_pda(counter::VAULT_SEED, counter),
+                false,
+            ));
+        }
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: counter::instruction::SweepFees {}.data(),
+        }
+    }
+
+    // Builds a "withdraw_vault" instruction, sending "amount" lamports of
+    // the counter's vault (where fees go) to its authority.
+    pub fn withdraw_vault_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::WithdrawVault {
+                guard: counter::accounts::AuthorityGuard {
+                    counter: *counter,
+                    access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                    authority: *authority,
+                    system_program: system_program::ID,
+                },
+                vault: self.counter_pda(counter::VAULT_SEED, counter),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::WithdrawVault { amount }.data(),
+        }
+    }
+
+    // Builds an "initialize_group" instruction creating a Group at "group"
+    // (see programs/counter/src/group.rs), run by "authority". Both sign.
+    pub fn initialize_group_ix(&self, group: &Pubkey, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::InitializeGroup {
+                group: *group,
+                authority: *authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::InitializeGroup {}.data(),
+        }
+    }
+
+    // Builds an "update_group" instruction, changing the rules of every
+    // counter in "group" at once. "authority" is the group's.
+    pub fn update_group_ix(
+        &self,
+        group: &Pubkey,
+        authority: &Pubkey,
+        paused: bool,
+        fee_lamports: u64,
+        deny_cpi: bool,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::UpdateGroup {
+                group: *group,
+                authority: *authority,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::UpdateGroup {
+                args: counter::GroupArgs {
+                    version: counter::GroupArgs::VERSION,
+                    paused,
+                    fee_lamports,
+                    deny_cpi,
+                },
+            }
+            .data(),
+        }
+    }
+
+    // Builds a "join_group" instruction. "authority" must own the counter
+    // and run the group, and pays for the access log the first time.
+    pub fn join_group_ix(
+        &self,
+        counter: &Pubkey,
+        group: &Pubkey,
+        authority: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::JoinGroup {
+                guard: counter::accounts::AuthorityGuard {
+                    counter: *counter,
+                    access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                    authority: *authority,
+                    system_program: system_program::ID,
+                },
+                group: *group,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::JoinGroup {}.data(),
+        }
+    }
+
+    // Builds an "increment_grouped" instruction, the only way to increment
+    // a counter in a group. "payer" signs and pays the group's fee, if any.
+    pub fn increment_grouped_ix(
+        &self,
+        counter: &Pubkey,
+        group: &Pubkey,
+        payer: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::IncrementGrouped {
+                payment: counter::accounts::FeePayment {
+                    counter: *counter,
+                    vault: self.vault_address(counter),
+                    payer: *payer,
+                    system_program: system_program::ID,
+                },
+                group: *group,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::IncrementGrouped {}.data(),
+        }
+    }
+
+    // Builds a "set_soft_cap" instruction. "authority" is whoever
+    // controls the counter's settings (see lease.rs), and pays for its
+    // access log the first time. A "max_count" of 0 takes the cap off.
+    pub fn set_soft_cap_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        max_count: u64,
+        overflow: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::UpdateCounter {
+                counter: *counter,
+                lease: self.counter_pda(counter::LEASE_SEED, counter),
+                access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                authority: *authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::SetSoftCap {
+                args: counter::SoftCapArgs {
+                    version: counter::SoftCapArgs::VERSION,
+                    max_count,
+                    overflow,
+                },
+            }
+            .data(),
+        }
+    }
+
+    // Builds a "set_twac" instruction, turning a counter's time-weighted
+    // average on or off. "authority" is as in set_soft_cap_ix.
+    pub fn set_twac_ix(&self, counter: &Pubkey, authority: &Pubkey, enabled: bool) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::UpdateCounter {
+                counter: *counter,
+                lease: self.counter_pda(counter::LEASE_SEED, counter),
+                access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                authority: *authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::SetTwac { enabled }.data(),
+        }
+    }
+
+    // Builds a "set_delta_feed" instruction, turning a counter's delta feed
+    // on or off. "authority" is as in set_soft_cap_ix.
+    pub fn set_delta_feed_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        enabled: bool,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::UpdateCounter {
+                counter: *counter,
+                lease: self.counter_pda(counter::LEASE_SEED, counter),
+                access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                authority: *authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::SetDeltaFeed { enabled }.data(),
+        }
+    }
+
+    // Builds a "vote_freeze" instruction (see programs/counter/src/freeze.rs).
+    // "voter" must hold the counter's badge for "tier".
+    pub fn vote_freeze_ix(&self, counter: &Pubkey, voter: &Pubkey, tier: u8) -> Instruction {
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &self.program_id).0;
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::VoteFreeze {
+                counter: *counter,
+                freeze_policy: pda(&[counter::FREEZE_POLICY_SEED]),
+                freeze_tally: self.counter_pda(counter::FREEZE_TALLY_SEED, counter),
+                freeze_vote: pda(&[counter::FREEZE_VOTE_SEED, counter.as_ref(), voter.as_ref()]),
+                badge: pda(&[
+                    counter::BADGE_SEED,
+                    counter.as_ref(),
+                    voter.as_ref(),
+                    &[tier],
+                ]),
+                voter: *voter,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::VoteFreeze {}.data(),
+        }
+    }
+
+    // Builds an "unfreeze" instruction, for the owner once they have
+    // reviewed a counter its contributors froze.
+    pub fn unfreeze_ix(&self, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::Unfreeze {
+                guard: counter::accounts::AuthorityGuard {
+                    counter: *counter,
+                    access_log: self.counter_pda(counter::ACCESS_LOG_SEED, counter),
+                    authority: *authority,
+                    system_program: system_program::ID,
+                },
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Unfreeze {}.data(),
+        }
+    }
+
+    // Builds an "increment_spilling" instruction for a counter with a soft
+    // cap, whose overflow counter is "overflow".
+    pub fn increment_spilling_ix(&self, counter: &Pubkey, overflow: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::IncrementSpilling {
+                counter: *counter,
+                overflow: *overflow,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::IncrementSpilling {}.data(),
+        }
+    }
+
+    // A PDA of the program seeded with "seed" and the counter's address.
+    fn counter_pda(&self, seed: &[u8], counter: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[seed, counter.as_ref()], &self.program_id).0
+    }
+
+    // Builds a "migrate_counter" instruction, which grows an old counter to
+    // the current layout. "payer" pays the extra rent.
+    pub fn migrate_counter_ix(&self, counter: &Pubkey, payer: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::MigrateCounter {
+                counter: *counter,
+                payer: *payer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::MigrateCounter {}.data(),
+        }
+    }
+
+    // Builds a "migrate_ownerless_counter" instruction, which migrates a
+    // count-only counter and makes "owner" its authority. "authority" must
+    // be the program's upgrade authority, and pays the extra rent.
+    pub fn migrate_ownerless_counter_ix(
+        &self,
+        counter: &Pubkey,
+        authority: &Pubkey,
+        owner: Pubkey,
+    ) -> Instruction {
+        let program_data =
+            Pubkey::find_program_address(&[self.program_id.as_ref()], &bpf_loader_upgradeable::ID)
+                .0;
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::MigrateOwnerlessCounter {
+                counter: *counter,
+                authority: *authority,
+                program: self.program_id,
+                program_data,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::MigrateOwnerlessCounter { owner }.data(),
+        }
+    }
+
+    // Builds a "reap" instruction for a reapable counter (see
+    // programs/counter/src/reap.rs). Anyone can send it; the rent goes to
+    // "beneficiary", which must be the one in the counter's ReapPolicy.
+    pub fn reap_ix(&self, counter: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::Reap {
+                counter: *counter,
+                reap_policy: self.counter_pda(counter::REAP_POLICY_SEED, counter),
+                vault: self.counter_pda(counter::VAULT_SEED, counter),
+                beneficiary: *beneficiary,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Reap {}.data(),
+        }
+    }
+
+    // Builds a "finalize" instruction for a counter whose end time has
+    // passed. Anyone can send it; "payer" pays for the FinalResult account.
+    pub fn finalize_ix(&self, counter: &Pubkey, payer: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::Finalize {
+                counter: *counter,
+                deadline: self.counter_pda(counter::DEADLINE_SEED, counter),
+                result: self.counter_pda(counter::RESULT_SEED, counter),
+                payer: *payer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Finalize {}.data(),
+        }
+    }
+
+    // Builds a "forfeit_commit" instruction, sending the deposit of an
+    // unrevealed commitment made in "round" to the counter's vault. Anyone
+    // can send it once the round is over.
+    pub fn forfeit_commit_ix(
+        &self,
+        counter: &Pubkey,
+        participant: &Pubkey,
+        round: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: counter::accounts::ForfeitCommit {
+                counter: *counter,
+                commit_round: self.counter_pda(counter::COMMIT_ROUND_SEED, counter),
+                commitment: self.commitment_address(counter, participant, round),
+                vault: self.counter_pda(counter::VAULT_SEED, counter),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::ForfeitCommit { round }.data(),
+        }
+    }
+
+    // A counter's vault, where its fees and lease payments go (see
+    // programs/counter/src/lease.rs).
+    pub fn vault_address(&self, counter: &Pubkey) -> Pubkey {
+        self.counter_pda(counter::VAULT_SEED, counter)
+    }
+
+    // A participant's Commitment PDA for one round of a counter's
+    // commit-reveal (see programs/counter/src/commit_reveal.rs).
+    pub fn commitment_address(&self, counter: &Pubkey, participant: &Pubkey, round: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                counter::COMMITMENT_SEED,
+                counter.as_ref(),
+                &round.to_le_bytes(),
+                participant.as_ref(),
+            ],
+            &self.program_id,
+        )
+        .0
+    }
+
+    // Signs the instructions into one transaction paid for by "payer",
+    // sends it, and waits for it to be confirmed.
+    pub fn send(&self, payer: &Keypair, instructions: &[Instruction]) -> Result<Signature> {
+        self.send_with_signers(payer, &[], instructions)
+    }
+
+    // Like "send", for instructions that need more signers than the payer,
+    // such as a new account's keypair.
+    #[tracing::instrument(
+        skip_all,
+        fields(payer = %payer.pubkey(), instructions = instructions.len(), signature)
+    )]
+    pub fn send_with_signers(
+        &self,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        instructions: &[Instruction],
+    ) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        // Recorded before sending, so a failure is logged with it too.
+        tracing::Span::current().record(
+            "signature",
+            tracing::field::display(transaction.signatures[0]),
+        );
+        match self.rpc.send_and_confirm_transaction(&transaction) {
+            Ok(signature) => {
+                tracing::info!("transaction confirmed");
+                Ok(signature)
+            }
+            Err(err) => {
+                let err = Error::from(err);
+                tracing::warn!(error = %err, "transaction failed");
+                Err(err)
+            }
+        }
+    }
+}
+
+// Decodes a Leaderboard account's data, or None if it isn't one.
+pub fn decode_leaderboard(data: &[u8]) -> Option<counter::Leaderboard> {
+    if data.len() != counter::Leaderboard::SPACE || data[..8] != counter::Leaderboard::DISCRIMINATOR
+    {
+        return None;
+    }
+    Some(bytemuck::pod_read_unaligned(&data[8..]))
+}
+
+// getProgramAccounts filters matching current Counters with "tag" in tag
+// slot "slot". Counters from before tags were added (see migrate.rs) are
+// shorter, so the size filter leaves them out.
+pub fn tag_filters(tag: &[u8; counter::TAG_LEN], slot: usize) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::DataSize((8 + Counter::INIT_SPACE) as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Counter::DISCRIMINATOR)),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            counter::TAGS_OFFSET + slot * counter::TAG_LEN,
+            tag,
+        )),
+    ]
+}
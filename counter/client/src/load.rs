@@ -0,0 +1,87 @@
+// The bookkeeping behind src/bin/stress.rs, kept free of RPC calls so it
+// can be tested on its own (see tests/load.rs).
+//
+// The stress tool sends increments as fast as it's told and polls for
+// their confirmations. Every transaction ends in a Report as either a
+// latency (sent to confirmed) or a failure cause, and the Report prints
+// the totals at the end.
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+use crate::failure::Failure;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub sent: u64,
+    // Sent to confirmed, one per confirmed transaction.
+    pub latencies: Vec<Duration>,
+    // How many transactions failed, by cause.
+    pub failures: BTreeMap<String, u64>,
+    // How long the sending took, for the achieved rate.
+    pub elapsed: Duration,
+}
+
+impl Report {
+    pub fn confirmed(&mut self, latency: Duration) {
+        self.latencies.push(latency);
+    }
+
+    pub fn failed(&mut self, cause: impl Into<String>) {
+        *self.failures.entry(cause.into()).or_default() += 1;
+    }
+
+    // The latency "p" percent of confirmations were at or under, or None
+    // before any were confirmed.
+    pub fn percentile(&self, p: u32) -> Option<Duration> {
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+        let rank = (sorted.len() * p.min(100) as usize).div_ceil(100);
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+
+    // Sent transactions per second.
+    pub fn rate(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.sent as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failed: u64 = self.failures.values().sum();
+        writeln!(
+            f,
+            "sent {} in {:.1}s ({:.1} tps)",
+            self.sent,
+            self.elapsed.as_secs_f64(),
+            self.rate()
+        )?;
+        writeln!(f, "confirmed {}, failed {failed}", self.latencies.len())?;
+        for p in [50, 90, 99] {
+            if let Some(latency) = self.percentile(p) {
+                writeln!(f, "  p{p} latency {}ms", latency.as_millis())?;
+            }
+        }
+        for (cause, count) in &self.failures {
+            writeln!(f, "  {count} x {cause}")?;
+        }
+        Ok(())
+    }
+}
+
+// A short name for why a transaction failed: the CounterError's name
+// when it's one of ours (see failure.rs), otherwise the error's variant.
+pub fn failure_cause(err: &TransactionError) -> String {
+    match Failure::decode(err, &[]) {
+        Failure::Counter { error, .. } => error.name(),
+        Failure::Instruction {
+            error: InstructionError::Custom(code),
+            ..
+        } => format!("program error {code}"),
+        Failure::Instruction { error, .. } => format!("{error:?}"),
+        _ => format!("{err:?}"),
+    }
+}
@@ -0,0 +1,26 @@
+// Where the client's diagnostics go.
+//
+// The client is instrumented with "tracing": CounterClient's RPC calls run
+// in spans carrying the counter, slot and transaction signature, and log
+// events inside them. Something has to collect those. The binaries call
+// init, which prints them to stderr through env_logger ("tracing" hands
+// its spans and events to the "log" crate when no tracing subscriber is
+// installed). RUST_LOG picks the level, e.g.
+//
+//     RUST_LOG=counter_client=debug cargo run -p counter-client --bin relayer -- ...
+//
+// RUST_LOG=debug also shows the Solana RPC client's own logging.
+//
+// A service that wants traces in an OpenTelemetry collector can install a
+// tracing-opentelemetry subscriber with an OTLP exporter at startup
+// instead of calling init; the spans are plain "tracing" and need nothing
+// else. Those crates aren't dependencies of this one, so the tools here
+// don't offer it themselves.
+use env_logger::Env;
+
+// Starts printing log records at "default_level" and above, unless
+// RUST_LOG says otherwise. Calling it twice does nothing.
+pub fn init(default_level: &str) {
+    let _ =
+        env_logger::Builder::from_env(Env::default().default_filter_or(default_level)).try_init();
+}
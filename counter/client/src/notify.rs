@@ -0,0 +1,289 @@
+// The rules behind src/bin/notifier.rs, kept free of network calls so
+// they can be tested on its own (see tests/notify.rs).
+//
+// The notifier polls a list of counters and posts to Discord or Telegram
+// when something happens to one of them. It's configured in TOML:
+//
+//     [channels.ops]
+//     kind = "discord"
+//     webhook = "https://discord.com/api/webhooks/..."
+//
+//     [channels.community]
+//     kind = "telegram"
+//     bot_token = "..."
+//     chat_id = "-1001234567890"
+//     notices = ["milestone"]
+//
+//     [[counters]]
+//     address = "<ADDRESS>"
+//     name = "Main counter"
+//     milestones = [1000, 5000]
+//     every = 10000
+//     stall_secs = 3600
+//     channels = ["ops", "community"]
+//
+//     [templates]
+//     milestone = "🎉 {name} just passed {milestone}!"
+//
+// A counter's notices go to each channel in its "channels" whose
+// "notices" includes the notice's kind (every kind if "notices" is left
+// out). The kinds are:
+//
+//   - "milestone": the count passed one of "milestones", or a multiple of
+//     "every";
+//   - "alarm": the counter's alarm went off (see set_alarm_threshold);
+//   - "authority": the authority changed, e.g. a lease or a handover;
+//   - "stall": the count hasn't changed for "stall_secs". Sent once per
+//     stall.
+//
+// Templates fill in {name}, {address} and {count}, plus {milestone},
+// {threshold}, {old} and {new}, or {minutes}, depending on the kind.
+use std::collections::BTreeMap;
+
+use counter::Counter;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("invalid notifier config: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("no channel named {0:?}")]
+    UnknownChannel(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifierConfig {
+    pub channels: BTreeMap<String, Channel>,
+    pub counters: Vec<Watched>,
+    #[serde(default)]
+    pub templates: Templates,
+}
+
+impl NotifierConfig {
+    pub fn parse(text: &str) -> Result<Self, NotifyError> {
+        let config: NotifierConfig = toml::from_str(text)?;
+        for watched in &config.counters {
+            for channel in &watched.channels {
+                if !config.channels.contains_key(channel) {
+                    return Err(NotifyError::UnknownChannel(channel.clone()));
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    // The channels a counter's notice of "kind" goes to.
+    pub fn route<'a>(&'a self, watched: &'a Watched, kind: Kind) -> Vec<&'a Channel> {
+        watched
+            .channels
+            .iter()
+            .filter_map(|name| self.channels.get(name))
+            .filter(|channel| channel.notices().is_none_or(|kinds| kinds.contains(&kind)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Channel {
+    Discord {
+        webhook: String,
+        notices: Option<Vec<Kind>>,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+        notices: Option<Vec<Kind>>,
+    },
+}
+
+impl Channel {
+    fn notices(&self) -> Option<&Vec<Kind>> {
+        match self {
+            Channel::Discord { notices, .. } | Channel::Telegram { notices, .. } => {
+                notices.as_ref()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Watched {
+    #[serde(deserialize_with = "pubkey")]
+    pub address: Pubkey,
+    // Defaults to the address.
+    pub name: Option<String>,
+    #[serde(default)]
+    pub milestones: Vec<u64>,
+    pub every: Option<u64>,
+    pub stall_secs: Option<u64>,
+    pub channels: Vec<String>,
+}
+
+impl Watched {
+    pub fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.address.to_string())
+    }
+
+    // The highest milestone in (before, after], if any.
+    pub fn milestone_passed(&self, before: u64, after: u64) -> Option<u64> {
+        let listed = self
+            .milestones
+            .iter()
+            .copied()
+            .filter(|&m| before < m && m <= after)
+            .max();
+        let multiple = self
+            .every
+            .filter(|&every| every > 0)
+            .map(|every| after - after % every)
+            .filter(|&m| m > before && m > 0);
+        listed.max(multiple)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Milestone,
+    Alarm,
+    Authority,
+    Stall,
+}
+
+// Any left out keep their defaults.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Templates {
+    pub milestone: String,
+    pub alarm: String,
+    pub authority: String,
+    pub stall: String,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Templates {
+            milestone: "{name} passed {milestone} (count {count})".to_string(),
+            alarm: "{name} reached its alarm threshold of {threshold} (count {count})".to_string(),
+            authority: "{name} changed authority from {old} to {new}".to_string(),
+            stall: "{name} hasn't changed in {minutes} minutes (count {count})".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Notice {
+    Milestone { milestone: u64 },
+    Alarm { threshold: u64 },
+    Authority { old: Pubkey, new: Pubkey },
+    Stall { secs: u64 },
+}
+
+impl Notice {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Notice::Milestone { .. } => Kind::Milestone,
+            Notice::Alarm { .. } => Kind::Alarm,
+            Notice::Authority { .. } => Kind::Authority,
+            Notice::Stall { .. } => Kind::Stall,
+        }
+    }
+
+    // The message, from the template for this kind.
+    pub fn render(&self, templates: &Templates, watched: &Watched, count: u64) -> String {
+        let (template, vars) = match self {
+            Notice::Milestone { milestone } => (
+                &templates.milestone,
+                vec![("milestone", milestone.to_string())],
+            ),
+            Notice::Alarm { threshold } => {
+                (&templates.alarm, vec![("threshold", threshold.to_string())])
+            }
+            Notice::Authority { old, new } => (
+                &templates.authority,
+                vec![("old", old.to_string()), ("new", new.to_string())],
+            ),
+            Notice::Stall { secs } => {
+                (&templates.stall, vec![("minutes", (secs / 60).to_string())])
+            }
+        };
+        let mut text = template
+            .replace("{name}", &watched.name())
+            .replace("{address}", &watched.address.to_string())
+            .replace("{count}", &count.to_string());
+        for (var, value) in vars {
+            text = text.replace(&format!("{{{var}}}"), &value);
+        }
+        text
+    }
+}
+
+// What the notifier last saw of one counter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Seen {
+    pub count: u64,
+    pub authority: Pubkey,
+    pub alarm_triggered: bool,
+    // When the count last changed, in seconds (any clock).
+    pub changed_at: u64,
+    pub stall_sent: bool,
+}
+
+impl Seen {
+    pub fn new(counter: &Counter, now: u64) -> Self {
+        Seen {
+            count: counter.count,
+            authority: counter.authority,
+            alarm_triggered: counter.has_flag(Counter::ALARM_TRIGGERED),
+            changed_at: now,
+            stall_sent: false,
+        }
+    }
+
+    // Compares the counter as it is "now" with what was seen before,
+    // returning the notices that calls for and remembering the new state.
+    pub fn observe(&mut self, watched: &Watched, counter: &Counter, now: u64) -> Vec<Notice> {
+        let mut notices = Vec::new();
+        if let Some(milestone) = watched.milestone_passed(self.count, counter.count) {
+            notices.push(Notice::Milestone { milestone });
+        }
+        let alarm_triggered = counter.has_flag(Counter::ALARM_TRIGGERED);
+        if alarm_triggered && !self.alarm_triggered {
+            notices.push(Notice::Alarm {
+                threshold: counter.alarm_threshold,
+            });
+        }
+        if counter.authority != self.authority {
+            notices.push(Notice::Authority {
+                old: self.authority,
+                new: counter.authority,
+            });
+        }
+        if counter.count != self.count {
+            self.changed_at = now;
+            self.stall_sent = false;
+        } else if let Some(stall_secs) = watched.stall_secs {
+            let idle = now.saturating_sub(self.changed_at);
+            if idle >= stall_secs && !self.stall_sent {
+                notices.push(Notice::Stall { secs: idle });
+                self.stall_sent = true;
+            }
+        }
+        self.count = counter.count;
+        self.authority = counter.authority;
+        self.alarm_triggered = alarm_triggered;
+        notices
+    }
+}
+
+fn pubkey<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,110 @@
+// Machine-readable results for counter-cli, for CI jobs and bots driving
+// it with "--output json".
+//
+// With JSON output every command prints exactly one JSON value on stdout,
+// including when it fails: then it's {"error": ErrorReport}. The process
+// exit code says what kind of failure it was (see ExitCode), so scripts
+// can branch on it without parsing anything.
+//
+// Field names are part of the interface: add new ones freely, but don't
+// rename or remove existing ones.
+use serde::Serialize;
+use solana_client::client_error::ClientError;
+use solana_sdk::transaction::TransactionError;
+
+use crate::{failure::Failure, keystore::KeystoreError, profile::ProfileError, Error};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Output {
+    Text,
+    Json,
+}
+
+// Process exit codes. clap itself exits with 2 on bad arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExitCode {
+    Ok = 0,
+    Other = 1,
+    // The config file, profile or keystore is wrong.
+    Config = 3,
+    // An account doesn't exist or isn't a counter.
+    NotFound = 4,
+    // The RPC node couldn't be reached or refused the request.
+    Rpc = 5,
+    // The transaction reached the program and was rejected.
+    TransactionFailed = 6,
+}
+
+// What went wrong, as JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ErrorReport {
+    // "config", "not_found", "rpc", "transaction" or "other".
+    pub kind: &'static str,
+    pub message: String,
+    // For program errors: the error number (6000 and up for CounterError)
+    // and its name, like "FeeRequired".
+    pub code: Option<u32>,
+    pub name: Option<String>,
+    // Which instruction of the transaction failed.
+    pub instruction: Option<u8>,
+}
+
+impl ErrorReport {
+    pub fn from_error(err: &anyhow::Error) -> ErrorReport {
+        let mut report = ErrorReport {
+            kind: "other",
+            message: format!("{err:#}"),
+            code: None,
+            name: None,
+            instruction: None,
+        };
+        if err.downcast_ref::<ProfileError>().is_some()
+            || err.downcast_ref::<KeystoreError>().is_some()
+        {
+            report.kind = "config";
+        } else if let Some(err) = err.downcast_ref::<Error>() {
+            match err {
+                Error::NotFound(_) | Error::WrongOwner { .. } | Error::Decode(_) => {
+                    report.kind = "not_found";
+                }
+                Error::Rpc(_) => report.kind = "rpc",
+                Error::Failed(failure) => report.add_failure(failure),
+            }
+        } else if err.downcast_ref::<ClientError>().is_some() {
+            report.kind = "rpc";
+        }
+        report
+    }
+
+    // A failed simulation, which has no Rust error to start from.
+    pub fn from_transaction_error(err: &TransactionError, logs: &[String]) -> ErrorReport {
+        let failure = Failure::decode(err, logs);
+        let mut report = ErrorReport {
+            kind: "other",
+            message: failure.to_string(),
+            code: None,
+            name: None,
+            instruction: None,
+        };
+        report.add_failure(&failure);
+        report
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        match self.kind {
+            "config" => ExitCode::Config,
+            "not_found" => ExitCode::NotFound,
+            "rpc" => ExitCode::Rpc,
+            "transaction" => ExitCode::TransactionFailed,
+            _ => ExitCode::Other,
+        }
+    }
+
+    fn add_failure(&mut self, failure: &Failure) {
+        self.kind = "transaction";
+        self.code = failure.code();
+        self.name = failure.name();
+        self.instruction = failure.instruction();
+    }
+}
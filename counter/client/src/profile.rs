@@ -0,0 +1,146 @@
+// Named settings for counter-cli, so switching between clusters is one
+// "--profile devnet" instead of a flag per setting.
+//
+// Profiles live in ~/.config/counter-cli/config.toml:
+//
+//     default = "devnet"
+//
+//     [profiles.devnet]
+//     cluster = "devnet"
+//     keypair = "~/.config/solana/devnet.json"
+//     counter = "<ADDRESS>"
+//
+//     [profiles.mainnet]
+//     cluster = "mainnet"
+//     url = "https://my-rpc.example.com"
+//     commitment = "finalized"
+//
+// Every setting is optional. A flag given on the command line wins over
+// the profile, and the profile wins over the built-in defaults. "cluster"
+// supplies the program id and RPC URL unless "program_id" and "url" are
+// set themselves.
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde::{Deserialize, Deserializer};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+};
+
+use crate::Cluster;
+
+pub const DEFAULT_KEYPAIR: &str = "~/.config/solana/id.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("invalid config file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("no profile named {0:?}")]
+    Unknown(String),
+    #[error("no cluster: pass --cluster, or --url and --program-id")]
+    NoCluster,
+}
+
+// The whole config file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    // The profile used when --profile isn't given.
+    pub default: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ConfigFile {
+    pub fn parse(text: &str) -> Result<Self, ProfileError> {
+        Ok(toml::from_str(text)?)
+    }
+
+    // The profile called "name", or the default one if there's no name.
+    // No name and no default means no settings from the file at all.
+    pub fn select(&self, name: Option<&str>) -> Result<Profile, ProfileError> {
+        match name.or(self.default.as_deref()) {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| ProfileError::Unknown(name.to_string())),
+            None => Ok(Profile::default()),
+        }
+    }
+}
+
+// One profile's settings, or the command line's: unset ones are None.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub cluster: Option<Cluster>,
+    pub url: Option<String>,
+    pub keypair: Option<String>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub commitment: Option<CommitmentLevel>,
+    #[serde(default, deserialize_with = "from_str")]
+    pub program_id: Option<Pubkey>,
+    // Used by commands that take a counter when none is given.
+    #[serde(default, deserialize_with = "from_str")]
+    pub counter: Option<Pubkey>,
+}
+
+// Everything counter-cli needs, with defaults filled in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub url: String,
+    pub program_id: Pubkey,
+    pub keypair: String,
+    pub commitment: CommitmentConfig,
+    pub counter: Option<Pubkey>,
+}
+
+impl Profile {
+    // This profile with every setting "overrides" has replaced.
+    pub fn merge(self, overrides: Profile) -> Profile {
+        Profile {
+            cluster: overrides.cluster.or(self.cluster),
+            url: overrides.url.or(self.url),
+            keypair: overrides.keypair.or(self.keypair),
+            commitment: overrides.commitment.or(self.commitment),
+            program_id: overrides.program_id.or(self.program_id),
+            counter: overrides.counter.or(self.counter),
+        }
+    }
+
+    pub fn settings(&self) -> Result<Settings, ProfileError> {
+        let url = match (&self.url, self.cluster) {
+            (Some(url), _) => url.clone(),
+            (None, Some(cluster)) => cluster.default_url().to_string(),
+            (None, None) => return Err(ProfileError::NoCluster),
+        };
+        let program_id = self
+            .program_id
+            .or(self.cluster.map(Cluster::program_id))
+            .ok_or(ProfileError::NoCluster)?;
+        Ok(Settings {
+            url,
+            program_id,
+            keypair: self
+                .keypair
+                .clone()
+                .unwrap_or_else(|| DEFAULT_KEYPAIR.to_string()),
+            commitment: CommitmentConfig {
+                commitment: self.commitment.unwrap_or(CommitmentLevel::Confirmed),
+            },
+            counter: self.counter,
+        })
+    }
+}
+
+// Reads a value written as text, like a base58 address.
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map(Some).map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,79 @@
+// The decision logic behind src/bin/relayer.rs, kept free of RPC calls so
+// it can be tested on its own (see tests/relay.rs).
+//
+// The relayer keeps a mirror counter on one cluster following a source
+// counter on another. It can't copy the value over - counters only go up
+// by increments - so it replays the difference as increments instead.
+use serde::{Deserialize, Serialize};
+
+// What the relayer has replayed so far: at "source_count" on the source,
+// the mirror was at "mirror_count". Saved to disk after every confirmed
+// batch, so a restarted relayer picks up where it left off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub source_count: u64,
+    pub mirror_count: u64,
+    // The slot the source was read at, for humans reading the file.
+    pub source_slot: u64,
+}
+
+impl Checkpoint {
+    // Starting point when there's no checkpoint yet: pretend the mirror
+    // was in sync when the source was at the mirror's value, so the first
+    // run catches the mirror up to the source.
+    pub fn fresh(mirror_count: u64) -> Self {
+        Checkpoint {
+            source_count: mirror_count,
+            mirror_count,
+            source_slot: 0,
+        }
+    }
+
+    // Records "replayed" more increments landing on the mirror.
+    pub fn advance(&mut self, replayed: u64) {
+        self.source_count += replayed;
+        self.mirror_count += replayed;
+    }
+}
+
+// The values don't add up any more, so the relayer stops instead of
+// guessing. Restarting with a fresh checkpoint accepts the current values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Drift {
+    #[error("source went back from {checkpoint} to {now} (was it closed and recreated?)")]
+    SourceWentBack { checkpoint: u64, now: u64 },
+    #[error("mirror went back from {checkpoint} to {now}")]
+    MirrorWentBack { checkpoint: u64, now: u64 },
+    #[error(
+        "mirror is at {actual} but should be at most {expected}; something else is incrementing it"
+    )]
+    MirrorAhead { expected: u64, actual: u64 },
+}
+
+// Works out how many increments the mirror needs to catch up.
+//
+// The mirror is allowed to be anywhere between the checkpoint and where it
+// should be: increments that landed just before a crash, before the
+// checkpoint was saved, aren't replayed twice.
+pub fn plan(checkpoint: &Checkpoint, source: u64, mirror: u64) -> Result<u64, Drift> {
+    if source < checkpoint.source_count {
+        return Err(Drift::SourceWentBack {
+            checkpoint: checkpoint.source_count,
+            now: source,
+        });
+    }
+    if mirror < checkpoint.mirror_count {
+        return Err(Drift::MirrorWentBack {
+            checkpoint: checkpoint.mirror_count,
+            now: mirror,
+        });
+    }
+    let expected = checkpoint.mirror_count + (source - checkpoint.source_count);
+    if mirror > expected {
+        return Err(Drift::MirrorAhead {
+            expected,
+            actual: mirror,
+        });
+    }
+    Ok(expected - mirror)
+}
@@ -0,0 +1,144 @@
+// Dry runs: sending a transaction through simulateTransaction instead of
+// for real, so the CLI can show what it would do without paying a fee
+// (see "counter-cli simulate").
+//
+// The RPC node runs the transaction against current state and returns its
+// logs, the compute units it used, and, if asked, accounts as they'd be
+// afterwards. Nothing is saved and nothing needs signing: signature
+// checks are off and the node fills in a fresh blockhash.
+//
+// Events come back inside the logs. "emit!" writes each one as a
+// "Program data: <base64>" line: the event's 8-byte discriminator, then
+// its fields. describe_event turns the ones a counter's owner cares about
+// into text.
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use counter::{
+    AlarmTriggered, BadgeEarned, Counter, CounterDecremented, CounterFinalized, CounterIncremented,
+    CounterReset, FeePaid,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, transaction::Transaction,
+    transaction::TransactionError,
+};
+
+use crate::{CounterClient, Result};
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+// What a transaction would do.
+pub struct Simulation {
+    // Why it would fail, or None if it would succeed.
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    // The counter as it would be afterwards.
+    pub counter_after: Option<Counter>,
+}
+
+impl CounterClient {
+    // Simulates "instructions" as a transaction paid for by "payer", and
+    // reads back "counter" as it would be afterwards.
+    pub fn simulate(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        counter: &Pubkey,
+    ) -> Result<Simulation> {
+        let transaction = Transaction::new_with_payer(instructions, Some(payer));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.rpc.commitment()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: vec![counter.to_string()],
+            }),
+            ..Default::default()
+        };
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(&transaction, config)?
+            .value;
+
+        let counter_after = result
+            .accounts
+            .and_then(|accounts| accounts.into_iter().next().flatten())
+            .and_then(|account| account.decode::<solana_sdk::account::Account>())
+            .and_then(|account| Counter::try_deserialize(&mut account.data.as_slice()).ok());
+        Ok(Simulation {
+            err: result.err,
+            logs: result.logs.unwrap_or_default(),
+            units_consumed: result.units_consumed,
+            counter_after,
+        })
+    }
+}
+
+// The data of every event in "logs", in order.
+pub fn event_data(logs: &[String]) -> Vec<Vec<u8>> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix(PROGRAM_DATA))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .collect()
+}
+
+// One line about an event, or None if it isn't one of ours.
+pub fn describe_event(data: &[u8]) -> Option<String> {
+    let (discriminator, mut fields) = data.split_at_checked(8)?;
+    let fields = &mut fields;
+    let text = match discriminator {
+        d if d == CounterIncremented::DISCRIMINATOR => {
+            let e = CounterIncremented::deserialize(fields).ok()?;
+            format!(
+                "CounterIncremented: count {} (event {})",
+                e.count, e.event_seq
+            )
+        }
+        d if d == CounterDecremented::DISCRIMINATOR => {
+            let e = CounterDecremented::deserialize(fields).ok()?;
+            format!(
+                "CounterDecremented: count {} (event {})",
+                e.count, e.event_seq
+            )
+        }
+        d if d == CounterReset::DISCRIMINATOR => {
+            let e = CounterReset::deserialize(fields).ok()?;
+            format!(
+                "CounterReset: count {} to 0, {} in its lifetime (event {})",
+                e.previous, e.lifetime_count, e.event_seq
+            )
+        }
+        d if d == AlarmTriggered::DISCRIMINATOR => {
+            let e = AlarmTriggered::deserialize(fields).ok()?;
+            format!(
+                "AlarmTriggered: threshold {} reached at count {} (event {})",
+                e.threshold, e.count, e.event_seq
+            )
+        }
+        d if d == FeePaid::DISCRIMINATOR => {
+            let e = FeePaid::deserialize(fields).ok()?;
+            format!(
+                "FeePaid: {} lamports by {}, {} to the referrer (event {})",
+                e.fee, e.payer, e.referrer_share, e.event_seq
+            )
+        }
+        d if d == CounterFinalized::DISCRIMINATOR => {
+            let e = CounterFinalized::deserialize(fields).ok()?;
+            format!(
+                "CounterFinalized: count {} in slot {} (event {})",
+                e.count, e.slot, e.event_seq
+            )
+        }
+        d if d == BadgeEarned::DISCRIMINATOR => {
+            let e = BadgeEarned::deserialize(fields).ok()?;
+            format!("BadgeEarned: tier {} for {}", e.tier, e.owner)
+        }
+        _ => return None,
+    };
+    Some(text)
+}
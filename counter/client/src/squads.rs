@@ -0,0 +1,193 @@
+// Squads v4 proposals, behind "counter-cli multisig propose", for counters
+// whose authority is a Squads vault rather than a keypair.
+//
+// A vault can't sign a transaction itself: a member proposes it, enough
+// members approve, and the Squads program executes it, signing for the
+// vault. Proposing takes three Squads instructions in one transaction:
+//
+//   - vault_transaction_create stores the instructions to run, compiled
+//     into a message whose fee payer is the vault;
+//   - proposal_create opens voting on it;
+//   - proposal_approve casts the proposer's own vote.
+//
+// Transactions are numbered per multisig: the next one is the multisig's
+// transaction_index plus one, and its accounts are PDAs of that number.
+// Approving the rest and executing is left to the Squads app (or its CLI):
+// those steps don't depend on what the transaction does.
+//
+// Squads isn't a dependency, so its instructions are built by hand here
+// from its IDL: Anchor discriminators and Borsh arguments, except for the
+// stored message, which Squads serializes its own way (see
+// transaction_message). Kept free of RPC calls so it can be tested on its
+// own (see tests/squads.rs).
+use solana_sdk::{
+    hash::hashv,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub const SQUADS_PROGRAM_ID: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+const SEED_TRANSACTION: &[u8] = b"transaction";
+const SEED_PROPOSAL: &[u8] = b"proposal";
+
+// Where transaction_index sits in a Multisig account: after the
+// discriminator, create_key, config_authority, threshold and time_lock.
+const TRANSACTION_INDEX_OFFSET: usize = 8 + 32 + 32 + 2 + 4;
+
+// The number of the multisig's latest transaction, from its account data.
+pub fn transaction_index(multisig_data: &[u8]) -> Option<u64> {
+    let bytes = multisig_data.get(TRANSACTION_INDEX_OFFSET..TRANSACTION_INDEX_OFFSET + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+pub fn vault_address(multisig: &Pubkey, vault_index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), SEED_VAULT, &[vault_index]],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn transaction_address(multisig: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX,
+            multisig.as_ref(),
+            SEED_TRANSACTION,
+            &index.to_le_bytes(),
+        ],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn proposal_address(multisig: &Pubkey, index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX,
+            multisig.as_ref(),
+            SEED_TRANSACTION,
+            &index.to_le_bytes(),
+            SEED_PROPOSAL,
+        ],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+// "instructions" compiled for the vault to run, in Squads' format: like a
+// legacy message, but with one-byte lengths (two for instruction data), no
+// blockhash, and an (empty) list of lookup tables.
+pub fn transaction_message(vault: &Pubkey, instructions: &[Instruction]) -> Vec<u8> {
+    let message = Message::new(instructions, Some(vault));
+    let header = message.header;
+    let signers = header.num_required_signatures;
+    let unsigned = message.account_keys.len() as u8 - signers;
+
+    let mut data = vec![
+        signers,
+        signers - header.num_readonly_signed_accounts,
+        unsigned - header.num_readonly_unsigned_accounts,
+        message.account_keys.len() as u8,
+    ];
+    for key in &message.account_keys {
+        data.extend_from_slice(key.as_ref());
+    }
+    data.push(message.instructions.len() as u8);
+    for instruction in &message.instructions {
+        data.push(instruction.program_id_index);
+        data.push(instruction.accounts.len() as u8);
+        data.extend_from_slice(&instruction.accounts);
+        data.extend_from_slice(&(instruction.data.len() as u16).to_le_bytes());
+        data.extend_from_slice(&instruction.data);
+    }
+    // No address lookup tables.
+    data.push(0);
+    data
+}
+
+// The three instructions proposing "instructions" as the multisig's
+// transaction number "index", from its vault "vault_index". "member" must
+// be a member allowed to propose and vote, and pays the rent of the new
+// accounts.
+pub fn propose_ixs(
+    multisig: &Pubkey,
+    vault_index: u8,
+    index: u64,
+    member: &Pubkey,
+    instructions: &[Instruction],
+    memo: Option<String>,
+) -> Vec<Instruction> {
+    let vault = vault_address(multisig, vault_index);
+    let proposal = proposal_address(multisig, index);
+
+    let mut create_args = vec![vault_index, 0];
+    borsh_bytes(&mut create_args, &transaction_message(&vault, instructions));
+    borsh_memo(&mut create_args, memo.as_deref());
+    let create = Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(transaction_address(multisig, index), false),
+            AccountMeta::new_readonly(*member, true),
+            AccountMeta::new(*member, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: [discriminator("vault_transaction_create"), create_args].concat(),
+    };
+
+    let mut proposal_args = index.to_le_bytes().to_vec();
+    // Not a draft: open for voting straight away.
+    proposal_args.push(0);
+    let open = Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(*member, true),
+            AccountMeta::new(*member, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: [discriminator("proposal_create"), proposal_args].concat(),
+    };
+
+    let mut approve_args = Vec::new();
+    borsh_memo(&mut approve_args, None);
+    let approve = Instruction {
+        program_id: SQUADS_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(*member, true),
+            AccountMeta::new(proposal, false),
+        ],
+        data: [discriminator("proposal_approve"), approve_args].concat(),
+    };
+
+    vec![create, open, approve]
+}
+
+// An Anchor instruction discriminator.
+fn discriminator(name: &str) -> Vec<u8> {
+    hashv(&[b"global:", name.as_bytes()]).to_bytes()[..8].to_vec()
+}
+
+fn borsh_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(bytes);
+}
+
+fn borsh_memo(data: &mut Vec<u8>, memo: Option<&str>) {
+    match memo {
+        Some(memo) => {
+            data.push(1);
+            borsh_bytes(data, memo.as_bytes());
+        }
+        None => data.push(0),
+    }
+}
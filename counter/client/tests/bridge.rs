@@ -0,0 +1,91 @@
+use anchor_lang::{AnchorSerialize, Discriminator, Event};
+use counter::{CounterIncremented, FeePaid, Version};
+use counter_client::bridge::{
+    decode_event, hpub, parse_ack, parse_msg_line, Decoded, Envelope, MsgLine,
+};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn decodes_events_to_json() {
+    let counter = Pubkey::new_unique();
+    let event = CounterIncremented {
+        counter,
+        count: 7,
+        event_seq: 3,
+        version: Version::CURRENT,
+    };
+    assert_eq!(
+        decode_event(&event.data()),
+        Decoded::Event(
+            "counter_incremented",
+            json!({ "counter": counter.to_string(), "count": 7, "event_seq": 3 })
+        )
+    );
+}
+
+#[test]
+fn flags_events_that_dont_parse() {
+    let mut data = FeePaid::DISCRIMINATOR.to_vec();
+    data.extend(Pubkey::new_unique().try_to_vec().unwrap());
+    assert_eq!(decode_event(&data), Decoded::Malformed("fee_paid"));
+    assert_eq!(decode_event(&[1, 2, 3, 4, 5, 6, 7, 8, 9]), Decoded::Other);
+    assert_eq!(decode_event(&[1, 2]), Decoded::Other);
+}
+
+#[test]
+fn ids_are_unique_per_event() {
+    let envelope = Envelope::new("5sig", 10, 2, "fee_paid", json!({}));
+    assert_eq!(envelope.id, "5sig:2");
+}
+
+#[test]
+fn frames_hpub_with_a_message_id() {
+    let command = hpub("counter.events.fee_paid", "_INBOX.1", "5sig:0", b"{}");
+    let headers = "NATS/1.0\r\nNats-Msg-Id: 5sig:0\r\n\r\n";
+    assert_eq!(
+        String::from_utf8(command).unwrap(),
+        format!(
+            "HPUB counter.events.fee_paid _INBOX.1 {} {}\r\n{headers}{{}}\r\n",
+            headers.len(),
+            headers.len() + 2
+        )
+    );
+}
+
+#[test]
+fn parses_msg_lines() {
+    assert_eq!(
+        parse_msg_line("MSG _INBOX.1 1 42"),
+        Some(MsgLine {
+            subject: "_INBOX.1".to_string(),
+            header_len: 0,
+            total_len: 42
+        })
+    );
+    assert_eq!(
+        parse_msg_line("HMSG _INBOX.2 1 reply 16 16"),
+        Some(MsgLine {
+            subject: "_INBOX.2".to_string(),
+            header_len: 16,
+            total_len: 16
+        })
+    );
+    assert_eq!(parse_msg_line("+OK"), None);
+    assert_eq!(parse_msg_line("MSG a 1 b c 5"), None);
+}
+
+#[test]
+fn parses_acknowledgements() {
+    assert_eq!(parse_ack("", br#"{"stream":"COUNTER","seq":12}"#), Ok(12));
+    assert_eq!(
+        parse_ack(
+            "",
+            br#"{"error":{"code":503,"description":"stream is full"}}"#
+        ),
+        Err("stream is full".to_string())
+    );
+    assert!(parse_ack("NATS/1.0 503\r\n\r\n", b"")
+        .unwrap_err()
+        .contains("no JetStream stream"));
+}
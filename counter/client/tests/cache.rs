@@ -0,0 +1,75 @@
+use anchor_lang::AccountSerialize;
+use counter::Counter;
+use counter_client::cache::Cache;
+use solana_sdk::pubkey::Pubkey;
+
+fn counter(count: u64) -> Counter {
+    Counter {
+        count,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn keeps_the_newest_read() {
+    let mut cache = Cache::default();
+    let address = Pubkey::new_unique();
+    assert!(cache.put_counter(&address, 10, &counter(5)));
+    assert!(!cache.put_counter(&address, 9, &counter(4)));
+    assert_eq!(cache.get(&address).unwrap().0.count, 5);
+
+    assert!(cache.put_counter(&address, 10, &counter(6)));
+    assert_eq!(
+        cache.get(&address).map(|(c, slot)| (c.count, slot)),
+        Some((6, 10))
+    );
+}
+
+#[test]
+fn forgets_closed_counters_unless_seen_again_later() {
+    let mut cache = Cache::default();
+    let address = Pubkey::new_unique();
+    cache.put_counter(&address, 10, &counter(5));
+    cache.remove(&address, 9);
+    assert!(cache.get(&address).is_some());
+    cache.remove(&address, 11);
+    assert!(cache.get(&address).is_none());
+}
+
+#[test]
+fn lists_without_archived_counters() {
+    let mut cache = Cache::default();
+    let (live, archived) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut state = counter(3);
+    cache.put_counter(&live, 1, &state);
+    state.set_flag(Counter::ARCHIVED, true);
+    cache.put_counter(&archived, 1, &state);
+
+    let listed: Vec<Pubkey> = cache.list(false).into_iter().map(|(a, _, _)| a).collect();
+    assert_eq!(listed, vec![live]);
+    assert_eq!(cache.list(true).len(), 2);
+}
+
+#[test]
+fn undecodable_entries_are_misses() {
+    let mut cache = Cache::default();
+    let address = Pubkey::new_unique();
+    let mut data = Vec::new();
+    counter(1).try_serialize(&mut data).unwrap();
+    // An older, shorter layout.
+    cache.put(&address, 1, &data[..20]);
+    assert!(cache.get(&address).is_none());
+    assert!(cache.list(true).is_empty());
+}
+
+#[test]
+fn saves_and_loads() {
+    let path = std::env::temp_dir().join(format!("counter-cache-{}.json", std::process::id()));
+    assert_eq!(Cache::load(&path).unwrap(), Cache::default());
+
+    let mut cache = Cache::default();
+    cache.put_counter(&Pubkey::new_unique(), 1, &counter(2));
+    cache.save(&path).unwrap();
+    assert_eq!(Cache::load(&path).unwrap(), cache);
+    std::fs::remove_file(path).unwrap();
+}
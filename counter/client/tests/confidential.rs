@@ -0,0 +1,79 @@
+use counter::{check_threshold_proofs, ConfidentialCounter};
+use counter_client::confidential::{decrypt_count, encrypt_delta, threshold_proofs};
+use solana_zk_token_sdk::{encryption::elgamal::ElGamalKeypair, instruction::ZkProofData};
+
+#[test]
+fn encrypted_deltas_add_up() {
+    let keypair = ElGamalKeypair::new_rand();
+    let mut counter = ConfidentialCounter {
+        authority: Default::default(),
+        elgamal_pubkey: keypair.pubkey().to_bytes(),
+        encrypted_count: [0; 64],
+        increments: 0,
+    };
+    assert_eq!(
+        decrypt_count(keypair.secret(), &counter.encrypted_count),
+        Some(0)
+    );
+
+    for delta in [1, 0, 1, 5] {
+        counter
+            .add_encrypted(encrypt_delta(keypair.pubkey(), delta))
+            .unwrap();
+    }
+
+    assert_eq!(counter.increments, 4);
+    assert_eq!(
+        decrypt_count(keypair.secret(), &counter.encrypted_count),
+        Some(7)
+    );
+
+    // Someone else's key can't read it.
+    let other = ElGamalKeypair::new_rand();
+    assert_ne!(
+        decrypt_count(other.secret(), &counter.encrypted_count),
+        Some(7)
+    );
+}
+
+fn counter_at(keypair: &ElGamalKeypair, value: u64) -> ConfidentialCounter {
+    let mut counter = ConfidentialCounter {
+        authority: Default::default(),
+        elgamal_pubkey: keypair.pubkey().to_bytes(),
+        encrypted_count: [0; 64],
+        increments: 0,
+    };
+    counter
+        .add_encrypted(encrypt_delta(keypair.pubkey(), value))
+        .unwrap();
+    counter
+}
+
+#[test]
+fn threshold_proofs_verify_and_match_the_counter() {
+    let keypair = ElGamalKeypair::new_rand();
+    let counter = counter_at(&keypair, 12);
+
+    let proofs = threshold_proofs(&keypair, &counter.encrypted_count, 10).unwrap();
+    // What the proof program checks...
+    proofs.equality.verify_proof().unwrap();
+    proofs.range.verify_proof().unwrap();
+    // ...and what prove_threshold checks on top.
+    let equality = *proofs.equality.context_data();
+    let range = *proofs.range.context_data();
+    check_threshold_proofs(&counter, 10, &equality, &range).unwrap();
+
+    // The same proofs say nothing about other thresholds or counters.
+    assert!(check_threshold_proofs(&counter, 11, &equality, &range).is_err());
+    let other = counter_at(&keypair, 12);
+    assert!(check_threshold_proofs(&other, 10, &equality, &range).is_err());
+}
+
+#[test]
+fn no_threshold_proof_above_the_count() {
+    let keypair = ElGamalKeypair::new_rand();
+    let counter = counter_at(&keypair, 12);
+
+    assert!(threshold_proofs(&keypair, &counter.encrypted_count, 12).is_some());
+    assert!(threshold_proofs(&keypair, &counter.encrypted_count, 13).is_none());
+}
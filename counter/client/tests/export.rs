@@ -0,0 +1,126 @@
+use anchor_lang::AccountSerialize;
+use counter::{encode_tag, Badge, Counter, DeltaFeed, TAG_LEN};
+use counter_client::export::{to_csv, to_json_lines, Kind, Row};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+fn serialize(account: &impl AccountSerialize) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn counter(count: u64) -> Counter {
+    Counter {
+        count,
+        authority: Pubkey::new_unique(),
+        flags: Counter::ARCHIVED,
+        last_increment_slot: 7,
+        alarm_threshold: 0,
+        event_seq: 3,
+        finalized: false,
+        deny_cpi: true,
+        lifetime_count: count + 5,
+        role_weighted: true,
+        tags: [
+            encode_tag("games").unwrap(),
+            encode_tag("dao").unwrap(),
+            [0; TAG_LEN],
+            [0; TAG_LEN],
+        ],
+        max_count: 0,
+        overflow: Pubkey::default(),
+        cumulative_count: u128::from(u64::MAX) * 3,
+        twac_slot: 9,
+        delta_feed: DeltaFeed::default(),
+        frozen: false,
+        checksum: 0,
+        group: Pubkey::default(),
+        gifted_count: 0,
+    }
+}
+
+#[test]
+fn decodes_a_counter() {
+    let address = Pubkey::new_unique();
+    let c = counter(42);
+    let row = Kind::Counter.decode(&address, &serialize(&c)).unwrap();
+
+    let get = |name| row.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+    assert_eq!(get("address"), json!(address.to_string()));
+    assert_eq!(get("count"), json!(42));
+    assert_eq!(get("authority"), json!(c.authority.to_string()));
+    assert_eq!(get("archived"), json!(true));
+    assert_eq!(get("deny_cpi"), json!(true));
+    assert_eq!(get("lifetime_count"), json!(47));
+    assert_eq!(get("role_weighted"), json!(true));
+    assert_eq!(get("tags"), json!("games,dao"));
+    assert_eq!(get("cumulative_count"), json!("55340232221128654845"));
+    assert_eq!(get("checksum_matches"), json!(true));
+}
+
+#[test]
+fn flags_counters_changed_outside_the_program() {
+    let mut data = serialize(&counter(42));
+    data[8] ^= 1;
+    let row = Kind::Counter.decode(&Pubkey::new_unique(), &data).unwrap();
+    let get = |name| row.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+    assert_eq!(get("count"), json!(43));
+    assert_eq!(get("checksum_matches"), json!(false));
+}
+
+#[test]
+fn refuses_the_wrong_kind() {
+    let data = serialize(&counter(1));
+    assert!(Kind::Badge.decode(&Pubkey::new_unique(), &data).is_err());
+}
+
+#[test]
+fn owner_offset_points_at_the_owner() {
+    let c = counter(1);
+    let offset = Kind::Counter.owner_offset().unwrap();
+    assert_eq!(&serialize(&c)[offset..offset + 32], c.authority.as_ref());
+
+    let badge = Badge {
+        counter: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        tier: 1,
+        earned_at: 0,
+        bump: 255,
+    };
+    let offset = Kind::Badge.owner_offset().unwrap();
+    assert_eq!(
+        &serialize(&badge)[offset..offset + 32],
+        badge.owner.as_ref()
+    );
+}
+
+#[test]
+fn csv_quotes_only_when_needed() {
+    let rows: Vec<Row> = vec![
+        vec![("name", json!("plain")), ("count", json!(1))],
+        vec![("name", json!("a, \"b\"")), ("count", json!(2))],
+    ];
+    assert_eq!(to_csv(&rows), "name,count\nplain,1\n\"a, \"\"b\"\"\",2\n");
+    assert_eq!(to_csv(&[]), "");
+}
+
+#[test]
+fn json_lines_keep_one_object_per_line() {
+    let rows: Vec<Row> = vec![
+        vec![("name", json!("x")), ("count", json!(1))],
+        vec![("name", json!("y")), ("count", json!(2))],
+    ];
+    let out = to_json_lines(&rows);
+    let lines: Vec<serde_json::Value> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            json!({"name": "x", "count": 1}),
+            json!({"name": "y", "count": 2})
+        ]
+    );
+}
@@ -0,0 +1,188 @@
+use counter::CounterError;
+use counter_client::failure::{
+    anchor_error, counter_error, Failure, LoggedError, COUNTER_ERRORS, COUNTER_ERROR_OFFSET,
+};
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+fn logs(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn counter_errors_are_listed_in_order() {
+    for (index, error) in COUNTER_ERRORS.iter().enumerate() {
+        assert_eq!(u32::from(*error), COUNTER_ERROR_OFFSET + index as u32);
+        assert_eq!(
+            counter_error(u32::from(*error)).map(u32::from),
+            Some(u32::from(*error))
+        );
+    }
+    assert!(counter_error(COUNTER_ERROR_OFFSET - 1).is_none());
+    assert!(counter_error(COUNTER_ERROR_OFFSET + COUNTER_ERRORS.len() as u32).is_none());
+}
+
+// Doesn't compile when CounterError gains a variant, as a reminder to add
+// it to COUNTER_ERRORS.
+#[test]
+fn counter_errors_are_all_listed() {
+    let listed = |error: CounterError| match error {
+        CounterError::Overflow
+        | CounterError::IndexOutOfBounds
+        | CounterError::NotUpgradeAuthority
+        | CounterError::DuplicateIdempotencyKey
+        | CounterError::Unauthorized
+        | CounterError::AlreadyCountedThisSlot
+        | CounterError::ReplicaRootMismatch
+        | CounterError::InvalidWormholeMessage
+        | CounterError::UnknownEmitter
+        | CounterError::StaleWormholeMessage
+        | CounterError::TreeLogRequired
+        | CounterError::InvalidCiphertext
+        | CounterError::InvalidThresholdProof
+        | CounterError::ConflictingModes
+        | CounterError::MintRequired
+        | CounterError::Underflow
+        | CounterError::MirrorMintRequired
+        | CounterError::Archived
+        | CounterError::InvalidLease
+        | CounterError::LeaseActive
+        | CounterError::FeeRequired
+        | CounterError::InvalidFee
+        | CounterError::ReferrerAccountsMissing
+        | CounterError::TierNotReached
+        | CounterError::VoteWeightRequired
+        | CounterError::NotVoteWeighted
+        | CounterError::InvalidLockup
+        | CounterError::LockupActive
+        | CounterError::NoVoteWeight
+        | CounterError::RevealRequired
+        | CounterError::NotCommitReveal
+        | CounterError::InvalidRound
+        | CounterError::WrongPhase
+        | CounterError::CommitMismatch
+        | CounterError::Finalized
+        | CounterError::TooEarlyToFinalize
+        | CounterError::ReplicaOvermerged
+        | CounterError::MirrorSupplyMismatch
+        | CounterError::MirrorMintMissing
+        | CounterError::VaultBelowRent
+        | CounterError::CpiDenied
+        | CounterError::CallerNotAllowed
+        | CounterError::NotMigratable
+        | CounterError::ResetMirrored
+        | CounterError::LifetimeBelowCount
+        | CounterError::RoleRequired
+        | CounterError::NotRoleWeighted
+        | CounterError::InvalidTemplateName
+        | CounterError::FeesAccountMissing
+        | CounterError::UnsupportedArgsVersion
+        | CounterError::InvalidReapPolicy
+        | CounterError::CounterNotIdle
+        | CounterError::VaultNotEmpty
+        | CounterError::SponsorQuotaReached
+        | CounterError::TreasuryTooLow
+        | CounterError::InvalidGift
+        | CounterError::GiftExpired
+        | CounterError::GiftNotExpired
+        | CounterError::InvalidTag
+        | CounterError::UnknownTag
+        | CounterError::TagRegistryFull
+        | CounterError::SoftCapReached
+        | CounterError::InvalidSoftCap
+        | CounterError::NotTwac
+        | CounterError::InvalidFreezePolicy
+        | CounterError::FreezeVotingOff
+        | CounterError::AlreadyVoted
+        | CounterError::Frozen
+        | CounterError::NotFrozen
+        | CounterError::InvalidFeeSchedule
+        | CounterError::InvalidSweep
+        | CounterError::InvalidAuthority
+        | CounterError::ChecksumMismatch
+        | CounterError::GroupRequired
+        | CounterError::GroupPaused
+        | CounterError::WrongGroup
+        | CounterError::NotTreeLogged
+        | CounterError::NotTokenMirrored
+        | CounterError::NotFeeRequired
+        | CounterError::RevealTooLarge
+        | CounterError::OwnerlessCounter
+        | CounterError::GiftedCounter
+        | CounterError::LeaderboardEntryLive
+        | CounterError::NotOnLeaderboard
+        | CounterError::SelfReferral => {}
+    };
+    COUNTER_ERRORS.into_iter().for_each(listed);
+}
+
+#[test]
+fn decodes_counter_errors_without_logs() {
+    let err = TransactionError::InstructionError(2, InstructionError::Custom(6020));
+    let failure = Failure::decode(&err, &[]);
+    assert!(matches!(
+        failure,
+        Failure::Counter {
+            instruction: 2,
+            error: CounterError::FeeRequired
+        }
+    ));
+    assert_eq!(failure.code(), Some(6020));
+    assert_eq!(failure.name().as_deref(), Some("FeeRequired"));
+    assert_eq!(
+        failure.to_string(),
+        "instruction 2 failed: This counter's increments must go through increment_paid \
+         (FeeRequired)"
+    );
+}
+
+#[test]
+fn decodes_anchor_errors_from_logs() {
+    let err = TransactionError::InstructionError(0, InstructionError::Custom(3012));
+    let logs = logs(&[
+        "Program log: AnchorError caused by account: counter. Error Code: \
+         AccountNotInitialized. Error Number: 3012. Error Message: The program expected \
+         this account to be already initialized.",
+    ]);
+    let failure = Failure::decode(&err, &logs);
+    assert_eq!(failure.code(), Some(3012));
+    assert_eq!(failure.name().as_deref(), Some("AccountNotInitialized"));
+    assert_eq!(failure.instruction(), Some(0));
+
+    // Without the logs only the number is known.
+    let failure = Failure::decode(&err, &[]);
+    assert!(matches!(failure, Failure::Instruction { .. }));
+    assert_eq!((failure.code(), failure.name()), (Some(3012), None));
+}
+
+#[test]
+fn other_failures_pass_through() {
+    let failure = Failure::decode(&TransactionError::BlockhashNotFound, &[]);
+    assert!(matches!(
+        failure,
+        Failure::Transaction(TransactionError::BlockhashNotFound)
+    ));
+    assert_eq!(failure.instruction(), None);
+
+    let err = TransactionError::InstructionError(1, InstructionError::InsufficientFunds);
+    assert_eq!(Failure::decode(&err, &[]).instruction(), Some(1));
+}
+
+#[test]
+fn reads_anchor_error_lines() {
+    let logs = logs(&[
+        "Program log: Instruction: Increment",
+        "Program log: AnchorError thrown in programs/counter/src/lib.rs:980. \
+         Error Code: FeeRequired. Error Number: 6020. \
+         Error Message: This counter's increments must go through increment_paid.",
+        "Program failed: custom program error: 0x1784",
+    ]);
+    assert_eq!(
+        anchor_error(&logs),
+        Some(LoggedError {
+            name: "FeeRequired".to_string(),
+            number: 6020,
+            message: "This counter's increments must go through increment_paid".to_string(),
+        })
+    );
+    assert_eq!(anchor_error(&self::logs(&["Program log: hello"])), None);
+}
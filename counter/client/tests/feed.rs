@@ -0,0 +1,40 @@
+use anchor_lang::Discriminator;
+use counter_client::feed::{decode_feed, update_due};
+use counter_feed::Feed;
+
+fn feed(value: u64, timestamp: i64) -> Feed {
+    Feed {
+        value,
+        timestamp,
+        round: 1,
+        ..bytemuck::Zeroable::zeroed()
+    }
+}
+
+#[test]
+fn updates_on_change_or_heartbeat() {
+    let feed = feed(5, 1_000);
+    assert!(!update_due(&feed, 5, 1_299, 300));
+    assert!(update_due(&feed, 5, 1_300, 300));
+    assert!(update_due(&feed, 6, 1_001, 300));
+}
+
+#[test]
+fn finalized_feeds_are_never_due() {
+    let feed = Feed {
+        finalized: 1,
+        ..feed(5, 1_000)
+    };
+    assert!(!update_due(&feed, 5, 1_000_000, 300));
+}
+
+#[test]
+fn decodes_only_feed_accounts() {
+    let feed = feed(5, 1_000);
+    let mut data = Feed::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&feed));
+    assert_eq!(decode_feed(&data).map(|feed| feed.value), Some(5));
+    assert!(decode_feed(&data[1..]).is_none());
+    data[0] ^= 1;
+    assert!(decode_feed(&data).is_none());
+}
@@ -0,0 +1,36 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use counter_client::jito::{bundle_params, tip_ix, TIP_ACCOUNTS};
+use solana_sdk::{
+    hash::Hash, pubkey::Pubkey, signature::Keypair, signer::Signer, system_instruction,
+    system_program, transaction::Transaction,
+};
+
+#[test]
+fn tips_one_of_the_tip_accounts() {
+    let payer = Pubkey::new_unique();
+    for _ in 0..20 {
+        let tip = tip_ix(&payer, 10_000);
+        assert_eq!(tip.program_id, system_program::ID);
+        assert_eq!(tip.accounts[0].pubkey, payer);
+        assert!(TIP_ACCOUNTS.contains(&tip.accounts[1].pubkey));
+        let expected = system_instruction::transfer(&payer, &tip.accounts[1].pubkey, 10_000);
+        assert_eq!(tip.data, expected.data);
+    }
+}
+
+#[test]
+fn encodes_bundles_as_base64() {
+    let payer = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[tip_ix(&payer.pubkey(), 1_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        Hash::default(),
+    );
+    let params = bundle_params(std::slice::from_ref(&transaction));
+
+    assert_eq!(params[1]["encoding"], "base64");
+    let encoded = params[0][0].as_str().unwrap();
+    let decoded: Transaction = bincode::deserialize(&STANDARD.decode(encoded).unwrap()).unwrap();
+    assert_eq!(decoded, transaction);
+}
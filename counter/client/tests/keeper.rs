@@ -0,0 +1,112 @@
+use counter::{CommitRound, Commitment, Counter, Deadline, ReapPolicy};
+use counter_client::{
+    keeper::{
+        commitment_round, finalize_due, forfeit_due, priority_fee, reap_due, with_priority_fee,
+    },
+    CounterClient,
+};
+use solana_sdk::{compute_budget, hash::hash, pubkey::Pubkey};
+
+fn round(round: u64, reveal_deadline: i64) -> CommitRound {
+    CommitRound {
+        counter: Pubkey::new_unique(),
+        round,
+        commit_deadline: reveal_deadline - 100,
+        reveal_deadline,
+        deposit: 1_000,
+        max_value: 10,
+        commits: 0,
+        reveals: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn finalizes_once_the_end_time_comes() {
+    let deadline = Deadline {
+        counter: Pubkey::new_unique(),
+        end_time: 1_000,
+        bump: 0,
+    };
+    let mut counter = Counter::default();
+    assert!(!finalize_due(&deadline, &counter, 999));
+    assert!(finalize_due(&deadline, &counter, 1_000));
+    counter.finalized = true;
+    assert!(!finalize_due(&deadline, &counter, 1_000));
+}
+
+#[test]
+fn forfeits_commitments_of_rounds_that_are_over() {
+    // No RPC calls: the client is only used to derive addresses.
+    let client = CounterClient::new("http://127.0.0.1:8899", counter::ID);
+    let counter = Pubkey::new_unique();
+    let current = round(3, 1_000);
+    let commitment = Commitment {
+        participant: Pubkey::new_unique(),
+        hash: [0; 32],
+        bump: 0,
+    };
+
+    let earlier = client.commitment_address(&counter, &commitment.participant, 2);
+    assert_eq!(
+        commitment_round(&client, &earlier, &counter, &commitment, &current),
+        Some(2)
+    );
+    assert!(forfeit_due(&current, 2, 0));
+
+    let latest = client.commitment_address(&counter, &commitment.participant, 3);
+    assert_eq!(
+        commitment_round(&client, &latest, &counter, &commitment, &current),
+        Some(3)
+    );
+    assert!(!forfeit_due(&current, 3, 999));
+    assert!(forfeit_due(&current, 3, 1_000));
+
+    let other = client.commitment_address(&Pubkey::new_unique(), &commitment.participant, 3);
+    assert_eq!(
+        commitment_round(&client, &other, &counter, &commitment, &current),
+        None
+    );
+}
+
+#[test]
+fn bids_a_capped_percentile_of_recent_fees() {
+    let recent = [500, 0, 100, 300, 200];
+    assert_eq!(priority_fee(&recent, 0, u64::MAX), 0);
+    assert_eq!(priority_fee(&recent, 50, u64::MAX), 200);
+    assert_eq!(priority_fee(&recent, 100, u64::MAX), 500);
+    assert_eq!(priority_fee(&recent, 100, 400), 400);
+    assert_eq!(priority_fee(&[], 50, 400), 0);
+
+    let client = CounterClient::new("http://127.0.0.1:8899", counter::ID);
+    let finalize = client.finalize_ix(&Pubkey::new_unique(), &Pubkey::new_unique());
+    assert_eq!(
+        with_priority_fee(std::slice::from_ref(&finalize), 0),
+        vec![finalize.clone()]
+    );
+    let paid = with_priority_fee(std::slice::from_ref(&finalize), 200);
+    assert_eq!(paid.len(), 2);
+    assert_eq!(paid[0].program_id, compute_budget::ID);
+    assert_eq!(paid[1], finalize);
+}
+
+#[test]
+fn reaps_counters_that_changed_or_went_idle() {
+    let data = [1, 2, 3];
+    let mut policy = ReapPolicy {
+        counter: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        idle_epochs: 2,
+        seen: hash(&data).to_bytes(),
+        seen_epoch: 10,
+        bump: 0,
+    };
+    assert!(!reap_due(&policy, &data, 0, 11));
+    assert!(reap_due(&policy, &data, 0, 12));
+    // A funded vault keeps an idle counter around...
+    assert!(!reap_due(&policy, &data, 1, 12));
+    // ...but a change is still worth recording.
+    assert!(reap_due(&policy, &[1, 2, 4], 1, 11));
+    policy.seen = [0; 32];
+    assert!(reap_due(&policy, &data, 0, 10));
+}
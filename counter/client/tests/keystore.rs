@@ -0,0 +1,95 @@
+use std::{fs, path::PathBuf};
+
+use counter_client::keystore::{EncryptedKey, Keystore, KeystoreError};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+// Real keys use ITERATIONS; a few keep the tests fast.
+const ITERATIONS: u32 = 10;
+
+// An empty directory of its own for each test.
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("counter-keystore-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn decrypts_with_the_right_passphrase() {
+    let keypair = Keypair::new();
+    let key = EncryptedKey::encrypt(&keypair, "hunter2", ITERATIONS);
+    assert_eq!(key.pubkey, keypair.pubkey().to_string());
+    assert_eq!(
+        key.decrypt("hunter2").unwrap().to_bytes(),
+        keypair.to_bytes()
+    );
+}
+
+#[test]
+fn rejects_the_wrong_passphrase() {
+    let key = EncryptedKey::encrypt(&Keypair::new(), "hunter2", ITERATIONS);
+    assert!(matches!(
+        key.decrypt("hunter3"),
+        Err(KeystoreError::WrongPassphrase)
+    ));
+}
+
+#[test]
+fn rejects_a_changed_file() {
+    let mut key = EncryptedKey::encrypt(&Keypair::new(), "hunter2", ITERATIONS);
+    key.pubkey = Keypair::new().pubkey().to_string();
+    assert!(matches!(
+        key.decrypt("hunter2"),
+        Err(KeystoreError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn saves_loads_and_lists_keys() {
+    let keystore = Keystore::new(temp_dir("list"));
+    assert!(keystore.list().unwrap().is_empty());
+
+    let (ops, admin) = (Keypair::new(), Keypair::new());
+    let ops_key = EncryptedKey::encrypt(&ops, "a", ITERATIONS);
+    keystore.save("ops", &ops_key).unwrap();
+    keystore
+        .save("admin", &EncryptedKey::encrypt(&admin, "b", ITERATIONS))
+        .unwrap();
+
+    assert_eq!(keystore.load("ops").unwrap(), ops_key);
+    assert_eq!(
+        keystore.list().unwrap(),
+        vec![
+            ("admin".to_string(), admin.pubkey()),
+            ("ops".to_string(), ops.pubkey())
+        ]
+    );
+    fs::remove_dir_all(&keystore.dir).unwrap();
+}
+
+#[test]
+fn never_overwrites_a_key() {
+    let keystore = Keystore::new(temp_dir("overwrite"));
+    let key = EncryptedKey::encrypt(&Keypair::new(), "a", ITERATIONS);
+    keystore.save("ops", &key).unwrap();
+    assert!(matches!(
+        keystore.save("ops", &key),
+        Err(KeystoreError::Exists(_))
+    ));
+    fs::remove_dir_all(&keystore.dir).unwrap();
+}
+
+#[test]
+fn rejects_names_that_are_paths() {
+    let keystore = Keystore::new(temp_dir("names"));
+    let key = EncryptedKey::encrypt(&Keypair::new(), "a", ITERATIONS);
+    for name in ["", "../ops", "a/b"] {
+        assert!(matches!(
+            keystore.save(name, &key),
+            Err(KeystoreError::InvalidName(_))
+        ));
+    }
+    assert!(matches!(
+        keystore.load("missing"),
+        Err(KeystoreError::NotFound(_))
+    ));
+}
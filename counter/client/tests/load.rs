@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use counter_client::load::{failure_cause, Report};
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn percentiles_pick_the_nearest_rank() {
+    let mut report = Report::default();
+    assert_eq!(report.percentile(50), None);
+    for latency in [400, 100, 300, 200] {
+        report.confirmed(ms(latency));
+    }
+    assert_eq!(report.percentile(50), Some(ms(200)));
+    assert_eq!(report.percentile(90), Some(ms(400)));
+    assert_eq!(report.percentile(0), Some(ms(100)));
+}
+
+#[test]
+fn failures_are_counted_by_cause() {
+    let mut report = Report::default();
+    let once_per_slot = TransactionError::InstructionError(1, InstructionError::Custom(6005));
+    report.failed(failure_cause(&once_per_slot));
+    report.failed(failure_cause(&once_per_slot));
+    report.failed(failure_cause(&TransactionError::BlockhashNotFound));
+
+    assert_eq!(report.failures["AlreadyCountedThisSlot"], 2);
+    assert_eq!(report.failures["BlockhashNotFound"], 1);
+
+    let unknown = TransactionError::InstructionError(0, InstructionError::Custom(42));
+    assert_eq!(failure_cause(&unknown), "program error 42");
+}
+
+#[test]
+fn rate_is_sent_per_second() {
+    let report = Report {
+        sent: 50,
+        elapsed: Duration::from_secs(10),
+        ..Default::default()
+    };
+    assert_eq!(report.rate(), 5.0);
+    assert_eq!(Report::default().rate(), 0.0);
+}
@@ -0,0 +1,120 @@
+use counter::Counter;
+use counter_client::notify::{Channel, Kind, Notice, NotifierConfig, NotifyError, Seen};
+use solana_sdk::pubkey::Pubkey;
+
+const CONFIG: &str = r#"
+[channels.ops]
+kind = "discord"
+webhook = "https://discord.example/hook"
+
+[channels.community]
+kind = "telegram"
+bot_token = "token"
+chat_id = "-100"
+notices = ["milestone"]
+
+[[counters]]
+address = "11111111111111111111111111111111"
+name = "Main"
+milestones = [150]
+every = 1000
+stall_secs = 600
+channels = ["ops", "community"]
+
+[templates]
+milestone = "{name} passed {milestone}!"
+"#;
+
+fn counter(count: u64) -> Counter {
+    Counter {
+        count,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn routes_by_notice_kind() {
+    let config = NotifierConfig::parse(CONFIG).unwrap();
+    let watched = &config.counters[0];
+    assert_eq!(config.route(watched, Kind::Milestone).len(), 2);
+    let alarm = config.route(watched, Kind::Alarm);
+    assert!(matches!(alarm[..], [Channel::Discord { .. }]));
+}
+
+#[test]
+fn rejects_unknown_channels() {
+    let config = CONFIG.replace(r#"["ops", "community"]"#, r#"["ops", "nope"]"#);
+    assert!(matches!(
+        NotifierConfig::parse(&config),
+        Err(NotifyError::UnknownChannel(name)) if name == "nope"
+    ));
+}
+
+#[test]
+fn finds_the_highest_milestone_passed() {
+    let config = NotifierConfig::parse(CONFIG).unwrap();
+    let watched = &config.counters[0];
+    assert_eq!(watched.milestone_passed(100, 149), None);
+    assert_eq!(watched.milestone_passed(100, 150), Some(150));
+    assert_eq!(watched.milestone_passed(150, 999), None);
+    assert_eq!(watched.milestone_passed(900, 2500), Some(2000));
+    assert_eq!(watched.milestone_passed(0, 0), None);
+}
+
+#[test]
+fn notices_changes_since_the_last_read() {
+    let config = NotifierConfig::parse(CONFIG).unwrap();
+    let watched = &config.counters[0];
+    let mut seen = Seen::new(&counter(140), 0);
+
+    let mut next = counter(151);
+    next.alarm_threshold = 145;
+    next.set_flag(Counter::ALARM_TRIGGERED, true);
+    next.authority = Pubkey::new_unique();
+    let notices = seen.observe(watched, &next, 10);
+    assert_eq!(
+        notices,
+        vec![
+            Notice::Milestone { milestone: 150 },
+            Notice::Alarm { threshold: 145 },
+            Notice::Authority {
+                old: Pubkey::default(),
+                new: next.authority
+            },
+        ]
+    );
+    // Nothing new, nothing sent.
+    assert!(seen.observe(watched, &next, 20).is_empty());
+}
+
+#[test]
+fn notices_a_stall_once() {
+    let config = NotifierConfig::parse(CONFIG).unwrap();
+    let watched = &config.counters[0];
+    let mut seen = Seen::new(&counter(5), 0);
+    assert!(seen.observe(watched, &counter(5), 599).is_empty());
+    assert_eq!(
+        seen.observe(watched, &counter(5), 600),
+        vec![Notice::Stall { secs: 600 }]
+    );
+    assert!(seen.observe(watched, &counter(5), 900).is_empty());
+    // Moving again resets it.
+    assert!(seen.observe(watched, &counter(6), 1000).is_empty());
+    assert_eq!(seen.observe(watched, &counter(6), 1600).len(), 1);
+}
+
+#[test]
+fn renders_templates() {
+    let config = NotifierConfig::parse(CONFIG).unwrap();
+    let watched = &config.counters[0];
+    let milestone = Notice::Milestone { milestone: 150 };
+    assert_eq!(
+        milestone.render(&config.templates, watched, 151),
+        "Main passed 150!"
+    );
+    // Templates left out keep their defaults.
+    assert_eq!(
+        Notice::Stall { secs: 720 }.render(&config.templates, watched, 9),
+        "Main hasn't changed in 12 minutes (count 9)"
+    );
+}
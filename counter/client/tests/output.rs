@@ -0,0 +1,52 @@
+use counter_client::{
+    output::{ErrorReport, ExitCode},
+    profile::ProfileError,
+    Error,
+};
+use solana_sdk::{instruction::InstructionError, pubkey::Pubkey, transaction::TransactionError};
+
+#[test]
+fn program_errors_carry_code_name_and_instruction() {
+    let err = TransactionError::InstructionError(1, InstructionError::Custom(6020));
+    let report = ErrorReport::from_transaction_error(&err, &[]);
+    assert_eq!(report.kind, "transaction");
+    assert_eq!(report.code, Some(6020));
+    assert_eq!(report.name.as_deref(), Some("FeeRequired"));
+    assert_eq!(report.instruction, Some(1));
+    assert_eq!(report.exit_code(), ExitCode::TransactionFailed);
+}
+
+#[test]
+fn exit_codes_tell_failures_apart() {
+    let not_found = anyhow::Error::new(Error::NotFound(Pubkey::default()));
+    assert_eq!(
+        ErrorReport::from_error(&not_found).exit_code(),
+        ExitCode::NotFound
+    );
+
+    let config = anyhow::Error::new(ProfileError::NoCluster).context("loading settings");
+    assert_eq!(
+        ErrorReport::from_error(&config).exit_code(),
+        ExitCode::Config
+    );
+
+    let other = anyhow::anyhow!("something else");
+    let report = ErrorReport::from_error(&other);
+    assert_eq!(report.exit_code(), ExitCode::Other);
+    assert_eq!(report.message, "something else");
+}
+
+#[test]
+fn reports_serialize_with_stable_names() {
+    let report = ErrorReport::from_error(&anyhow::anyhow!("oops"));
+    assert_eq!(
+        serde_json::to_value(&report).unwrap(),
+        serde_json::json!({
+            "kind": "other",
+            "message": "oops",
+            "code": null,
+            "name": null,
+            "instruction": null,
+        })
+    );
+}
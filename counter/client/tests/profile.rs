@@ -0,0 +1,98 @@
+use counter_client::{
+    profile::{ConfigFile, Profile, ProfileError, DEFAULT_KEYPAIR},
+    Cluster,
+};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+};
+
+const CONFIG: &str = r#"
+default = "dev"
+
+[profiles.dev]
+cluster = "devnet"
+keypair = "~/dev.json"
+counter = "11111111111111111111111111111111"
+
+[profiles.main]
+cluster = "mainnet"
+url = "https://rpc.example.com"
+commitment = "finalized"
+"#;
+
+#[test]
+fn parses_profiles() {
+    let config = ConfigFile::parse(CONFIG).unwrap();
+    assert_eq!(config.default.as_deref(), Some("dev"));
+    assert_eq!(
+        config.profiles["main"],
+        Profile {
+            cluster: Some(Cluster::Mainnet),
+            url: Some("https://rpc.example.com".to_string()),
+            commitment: Some(CommitmentLevel::Finalized),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn selects_the_default_unless_named() {
+    let config = ConfigFile::parse(CONFIG).unwrap();
+    assert_eq!(config.select(None).unwrap().cluster, Some(Cluster::Devnet));
+    assert_eq!(
+        config.select(Some("main")).unwrap().cluster,
+        Some(Cluster::Mainnet)
+    );
+    assert!(matches!(
+        config.select(Some("staging")),
+        Err(ProfileError::Unknown(name)) if name == "staging"
+    ));
+    // No file, no profile: nothing selected, and that's fine.
+    assert_eq!(
+        ConfigFile::default().select(None).unwrap(),
+        Profile::default()
+    );
+}
+
+#[test]
+fn flags_override_the_profile() {
+    let config = ConfigFile::parse(CONFIG).unwrap();
+    let settings = config
+        .select(None)
+        .unwrap()
+        .merge(Profile {
+            url: Some("http://localhost:8899".to_string()),
+            ..Default::default()
+        })
+        .settings()
+        .unwrap();
+
+    assert_eq!(settings.url, "http://localhost:8899");
+    assert_eq!(settings.program_id, Cluster::Devnet.program_id());
+    assert_eq!(settings.keypair, "~/dev.json");
+    assert_eq!(settings.commitment, CommitmentConfig::confirmed());
+    assert_eq!(settings.counter, Some(Pubkey::default()));
+}
+
+#[test]
+fn needs_a_cluster_or_both_url_and_program_id() {
+    assert!(matches!(
+        Profile::default().settings(),
+        Err(ProfileError::NoCluster)
+    ));
+    let settings = Profile {
+        url: Some("http://localhost:8899".to_string()),
+        program_id: Some(Pubkey::default()),
+        ..Default::default()
+    }
+    .settings()
+    .unwrap();
+    assert_eq!(settings.keypair, DEFAULT_KEYPAIR);
+}
+
+#[test]
+fn rejects_typos() {
+    assert!(ConfigFile::parse("[profiles.dev]\nclustr = \"devnet\"\n").is_err());
+    assert!(ConfigFile::parse("[profiles.dev]\ncounter = \"not an address\"\n").is_err());
+}
@@ -0,0 +1,58 @@
+use counter_client::relay::{plan, Checkpoint, Drift};
+
+fn checkpoint(source_count: u64, mirror_count: u64) -> Checkpoint {
+    Checkpoint {
+        source_count,
+        mirror_count,
+        source_slot: 0,
+    }
+}
+
+#[test]
+fn fresh_checkpoint_catches_the_mirror_up_to_the_source() {
+    assert_eq!(plan(&Checkpoint::fresh(3), 10, 3), Ok(7));
+    assert_eq!(plan(&Checkpoint::fresh(10), 10, 10), Ok(0));
+}
+
+#[test]
+fn replays_only_what_is_missing() {
+    // The mirror started 100 ahead of the source; that offset is kept.
+    let cp = checkpoint(5, 105);
+    assert_eq!(plan(&cp, 9, 105), Ok(4));
+    // Two of those landed before the last checkpoint was saved.
+    assert_eq!(plan(&cp, 9, 107), Ok(2));
+}
+
+#[test]
+fn advance_keeps_the_offset() {
+    let mut cp = checkpoint(5, 105);
+    cp.advance(4);
+    assert_eq!(cp, checkpoint(9, 109));
+    assert_eq!(plan(&cp, 9, 109), Ok(0));
+}
+
+#[test]
+fn reports_drift() {
+    let cp = checkpoint(5, 5);
+    assert_eq!(
+        plan(&cp, 4, 5),
+        Err(Drift::SourceWentBack {
+            checkpoint: 5,
+            now: 4
+        })
+    );
+    assert_eq!(
+        plan(&cp, 5, 4),
+        Err(Drift::MirrorWentBack {
+            checkpoint: 5,
+            now: 4
+        })
+    );
+    assert_eq!(
+        plan(&cp, 6, 8),
+        Err(Drift::MirrorAhead {
+            expected: 6,
+            actual: 8
+        })
+    );
+}
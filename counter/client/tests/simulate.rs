@@ -0,0 +1,66 @@
+use anchor_lang::{Discriminator, Event};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use counter::{CounterIncremented, FeePaid, Version};
+use counter_client::simulate::{describe_event, event_data};
+use solana_sdk::pubkey::Pubkey;
+
+fn incremented(count: u64) -> CounterIncremented {
+    CounterIncremented {
+        counter: Pubkey::new_unique(),
+        count,
+        event_seq: 4,
+        version: Version::CURRENT,
+    }
+}
+
+// A log line as "emit!" writes it.
+fn log_line(event: &impl Event) -> String {
+    format!("Program data: {}", STANDARD.encode(event.data()))
+}
+
+#[test]
+fn finds_events_among_other_logs() {
+    let logs = vec![
+        "Program log: Instruction: Increment".to_string(),
+        log_line(&incremented(7)),
+        "Program data: not base64!".to_string(),
+        "Program log: count 7".to_string(),
+    ];
+    let events = event_data(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        describe_event(&events[0]).as_deref(),
+        Some("CounterIncremented: count 7 (event 4)")
+    );
+}
+
+#[test]
+fn describes_fees() {
+    let payer = Pubkey::new_unique();
+    let event = FeePaid {
+        counter: Pubkey::new_unique(),
+        payer,
+        fee: 1000,
+        referrer: None,
+        referrer_share: 0,
+        event_seq: 1,
+        version: Version::CURRENT,
+    };
+    assert_eq!(
+        describe_event(&event.data()),
+        Some(format!(
+            "FeePaid: 1000 lamports by {payer}, 0 to the referrer (event 1)"
+        ))
+    );
+}
+
+#[test]
+fn ignores_unknown_and_short_data() {
+    assert_eq!(describe_event(&[0; 16]), None);
+    assert_eq!(
+        describe_event(&CounterIncremented::DISCRIMINATOR[..4]),
+        None
+    );
+    // Right discriminator, fields cut short.
+    assert_eq!(describe_event(&incremented(1).data()[..12]), None);
+}
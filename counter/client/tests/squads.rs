@@ -0,0 +1,88 @@
+use counter_client::{
+    squads::{
+        proposal_address, propose_ixs, transaction_address, transaction_index, transaction_message,
+        vault_address, SQUADS_PROGRAM_ID,
+    },
+    CounterClient,
+};
+use solana_sdk::{pubkey::Pubkey, system_program};
+
+fn client() -> CounterClient {
+    CounterClient::new("http://localhost:8899", counter::ID)
+}
+
+#[test]
+fn reads_the_transaction_index() {
+    let mut data = vec![0u8; 120];
+    data[78..86].copy_from_slice(&41u64.to_le_bytes());
+    assert_eq!(transaction_index(&data), Some(41));
+    assert_eq!(transaction_index(&data[..80]), None);
+}
+
+#[test]
+fn addresses_depend_on_the_index() {
+    let multisig = Pubkey::new_unique();
+    assert_ne!(vault_address(&multisig, 0), vault_address(&multisig, 1));
+    assert_ne!(
+        transaction_address(&multisig, 1),
+        transaction_address(&multisig, 2)
+    );
+    assert_ne!(
+        transaction_address(&multisig, 1),
+        proposal_address(&multisig, 1)
+    );
+}
+
+#[test]
+fn compiles_the_message_for_the_vault() {
+    let vault = vault_address(&Pubkey::new_unique(), 0);
+    let counter = Pubkey::new_unique();
+    let withdraw = client().withdraw_vault_ix(&counter, &vault, 5);
+    let message = transaction_message(&vault, std::slice::from_ref(&withdraw));
+
+    // The vault signs and pays; the counter, its access log and its vault
+    // are the other writable accounts.
+    assert_eq!(message[..4], [1, 1, 3, 6]);
+    let keys: Vec<&[u8]> = message[4..4 + 6 * 32].chunks(32).collect();
+    assert_eq!(keys[0], vault.as_ref());
+    assert!(keys.contains(&counter.as_ref()));
+    assert!(keys.contains(&system_program::ID.as_ref()));
+    assert!(keys.contains(&counter::ID.as_ref()));
+
+    let rest = &message[4 + 6 * 32..];
+    // One instruction with 5 accounts, then its data with a u16 length,
+    // then no lookup tables.
+    assert_eq!(rest[0], 1);
+    assert_eq!(keys[rest[1] as usize], counter::ID.as_ref());
+    assert_eq!(rest[2], 5);
+    let data = &rest[3 + 5..];
+    assert_eq!(
+        u16::from_le_bytes([data[0], data[1]]) as usize,
+        withdraw.data.len()
+    );
+    assert_eq!(&data[2..2 + withdraw.data.len()], &withdraw.data[..]);
+    assert_eq!(data[2 + withdraw.data.len()..], [0]);
+}
+
+#[test]
+fn proposes_creates_and_approves() {
+    let multisig = Pubkey::new_unique();
+    let member = Pubkey::new_unique();
+    let vault = vault_address(&multisig, 0);
+    let withdraw = client().withdraw_vault_ix(&Pubkey::new_unique(), &vault, 5);
+    let ixs = propose_ixs(&multisig, 0, 7, &member, &[withdraw], None);
+
+    assert_eq!(ixs.len(), 3);
+    assert!(ixs.iter().all(|ix| ix.program_id == SQUADS_PROGRAM_ID));
+    assert_eq!(ixs[0].accounts[1].pubkey, transaction_address(&multisig, 7));
+    assert_eq!(ixs[1].accounts[1].pubkey, proposal_address(&multisig, 7));
+    assert_eq!(ixs[2].accounts[2].pubkey, proposal_address(&multisig, 7));
+    // proposal_create's arguments: the index, then "draft".
+    assert_eq!(ixs[1].data[8..], [7, 0, 0, 0, 0, 0, 0, 0, 0]);
+    for ix in &ixs {
+        assert!(ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == member && meta.is_signer));
+    }
+}
@@ -0,0 +1,20 @@
+// ============================================================================
+// CLOCK
+// ============================================================================
+
+// What programs read from the Clock sysvar. Only work inside a
+// program (or anything else that provides the sysvar).
+use anchor_lang::prelude::*;
+
+// Unix time, in seconds.
+pub fn now() -> Result<i64> {
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+pub fn slot() -> Result<u64> {
+    Ok(Clock::get()?.slot)
+}
+
+pub fn epoch() -> Result<u64> {
+    Ok(Clock::get()?.epoch)
+}
@@ -0,0 +1,17 @@
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+// "#[error_code]" also generates "From<CommonError> for Error", so a
+// program's handlers can "?" these like their own errors. "offset = 9000"
+// keeps the numbers clear of every program's own errors, which Anchor
+// numbers from 6000.
+use anchor_lang::prelude::*;
+
+#[error_code(offset = 9000)]
+pub enum CommonError {
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Division by zero")]
+    DivisionByZero,
+}
@@ -0,0 +1,19 @@
+// ============================================================================
+// NAUTILUS COMMON - helpers every program in the workspace can use
+// ============================================================================
+
+// Small pieces several programs were each writing for themselves: reading
+// the clock, splitting an amount proportionally without overflowing,
+// numbering events, and the seeds of the singleton PDAs most programs
+// have. None of it needs a running program, so it's all tested as plain
+// Rust (see tests/).
+//
+// It's a library, not a program: no declare_id!, no instructions. Errors
+// it returns are CommonError, numbered from 9000 so they can't be
+// mistaken for a program's own errors (6000 up).
+pub mod clock;
+pub mod error;
+pub use error::*;
+pub mod math;
+pub mod seeds;
+pub mod seq;
@@ -0,0 +1,29 @@
+// ============================================================================
+// MATH
+// ============================================================================
+
+// "a * b / c" comes up wherever an amount is split proportionally: fee
+// shares, vote weights, vesting, market payouts. Done in u64, "a * b"
+// overflows long before the result would, so both helpers multiply in
+// u128 and only come back to u64 for the result. Both round down.
+use anchor_lang::prelude::*;
+
+use crate::CommonError;
+
+// "value * numerator / denominator". Fails if the result doesn't fit in a
+// u64 or the denominator is 0.
+pub fn mul_div(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    require!(denominator != 0, CommonError::DivisionByZero);
+    let result = u128::from(value) * u128::from(numerator) / u128::from(denominator);
+    Ok(u64::try_from(result).map_err(|_| CommonError::Overflow)?)
+}
+
+// The "part / whole" portion of "value". "part" is capped at "whole", so
+// the result is never more than "value" and can't fail; a "whole" of 0
+// gives 0.
+pub fn portion(value: u64, part: u64, whole: u64) -> u64 {
+    if whole == 0 {
+        return 0;
+    }
+    (u128::from(value) * u128::from(part.min(whole)) / u128::from(whole)) as u64
+}
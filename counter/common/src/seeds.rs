@@ -0,0 +1,14 @@
+// ============================================================================
+// SEEDS
+// ============================================================================
+
+// Seeds for the PDAs that programs in the workspace have one of, under the
+// same name everywhere. Each program's PDAs are its own (the program id is
+// part of the address), so sharing a seed never means sharing an account.
+
+// The program-wide settings account: [CONFIG_SEED].
+pub const CONFIG_SEED: &[u8] = b"config";
+
+// An account that holds SOL on behalf of another, and signs for it:
+// [VAULT_SEED, owner].
+pub const VAULT_SEED: &[u8] = b"vault";
@@ -0,0 +1,15 @@
+// ============================================================================
+// EVENT SEQUENCE NUMBERS
+// ============================================================================
+
+// Events about an account carry "event_seq", the account's own event
+// number: 1 for the first, then 2, 3, ... with no gaps, so a listener that
+// sees 5 after 3 knows it missed one. The account stores the last number
+// it handed out.
+
+// Numbers the next event. "wrapping_add" can't fail; a u64 of events will
+// never run out anyway.
+pub fn next(seq: &mut u64) -> u64 {
+    *seq = seq.wrapping_add(1);
+    *seq
+}
@@ -0,0 +1,31 @@
+use anchor_lang::error::Error;
+use nautilus_common::math::{mul_div, portion};
+use nautilus_common::CommonError;
+
+#[test]
+fn mul_div_multiplies_past_u64_before_dividing() {
+    assert_eq!(mul_div(10, 3, 4).unwrap(), 7);
+    assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    assert_eq!(
+        mul_div(u64::MAX, 2, 1).err(),
+        Some(Error::from(CommonError::Overflow))
+    );
+    assert_eq!(
+        mul_div(1, 1, 0).err(),
+        Some(Error::from(CommonError::DivisionByZero))
+    );
+}
+
+#[test]
+fn portion_never_exceeds_the_value() {
+    assert_eq!(portion(1_000, 2_500, 10_000), 250);
+    assert_eq!(portion(u64::MAX, 1, 2), u64::MAX / 2);
+    assert_eq!(portion(1_000, 20_000, 10_000), 1_000);
+    assert_eq!(portion(1_000, 1, 0), 0);
+}
+
+#[test]
+fn errors_are_numbered_clear_of_program_errors() {
+    assert_eq!(u32::from(CommonError::Overflow), 9000);
+    assert_eq!(u32::from(CommonError::DivisionByZero), 9001);
+}
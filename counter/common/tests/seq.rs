@@ -0,0 +1,12 @@
+use nautilus_common::seq;
+
+#[test]
+fn numbers_events_from_one_without_gaps() {
+    let mut last = 0;
+    assert_eq!(seq::next(&mut last), 1);
+    assert_eq!(seq::next(&mut last), 2);
+    assert_eq!(last, 2);
+
+    last = u64::MAX;
+    assert_eq!(seq::next(&mut last), 0);
+}
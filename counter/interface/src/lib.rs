@@ -0,0 +1,125 @@
+// ============================================================================
+// COUNTABLE INTERFACE - what every counter implementation answers
+// ============================================================================
+
+// The workspace has more than one program that counts: programs/counter,
+// and programs/counter-native, which does the same without Anchor. More
+// may follow (compressed, confidential, ...). A program or client that
+// only needs to create a counter, add to it and read it shouldn't have to
+// care which one it talks to.
+//
+// So those three instructions are fixed here, once: their discriminators,
+// their accounts in order, and what they return. Any program that answers
+// them the same way is a drop-in replacement, and integrators build their
+// instructions with this crate and the program id of whichever
+// implementation they picked.
+//
+// The discriminators are the ones Anchor gives instructions named
+// "initialize", "increment" and "get_count" (the first 8 bytes of
+// sha256("global:<name>")), so an Anchor program implements the interface
+// just by having those instructions with these accounts. Programs without
+// Anchor can implement Countable and route through process (see below).
+//
+// It depends only on solana-program, not Anchor, so any program can use
+// it.
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::{get_return_data, invoke},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub const INITIALIZE: [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
+pub const INCREMENT: [u8; 8] = [11, 18, 104, 9, 104, 174, 59, 33];
+pub const GET_COUNT: [u8; 8] = [224, 158, 79, 100, 65, 72, 197, 132];
+
+// None of the instructions take arguments: the data is just the
+// discriminator.
+//
+// Accounts: [counter (signer, writable), payer (signer, writable),
+// system_program]. Creates the counter at a fresh keypair's address, owned
+// by "payer".
+pub fn initialize(program_id: &Pubkey, counter: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &INITIALIZE,
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+// Accounts: [counter (writable)]. Adds 1.
+pub fn increment(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &INCREMENT,
+        vec![AccountMeta::new(*counter, false)],
+    )
+}
+
+// Accounts: [counter]. Sets the count as return data: 8 bytes, little
+// endian.
+pub fn get_count(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &GET_COUNT,
+        vec![AccountMeta::new_readonly(*counter, false)],
+    )
+}
+
+// For programs reading a counter through CPI: calls get_count on
+// "program" and decodes what it returns. "accounts" must include the
+// counter and the program.
+pub fn read_count(
+    program: &Pubkey,
+    counter: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> Result<u64, ProgramError> {
+    invoke(&get_count(program, counter.key), accounts)?;
+    // Return data from some other program (or none at all) means the
+    // program didn't answer get_count.
+    match get_return_data() {
+        Some((from, data)) if from == *program => decode_count(&data),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+pub fn decode_count(data: &[u8]) -> Result<u64, ProgramError> {
+    let bytes: [u8; 8] = data
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// The interface, for programs written without Anchor. Each method gets
+// the accounts listed above, in that order.
+pub trait Countable {
+    fn initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult;
+    fn increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult;
+    // Returns the count; process sets it as return data.
+    fn get_count(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<u64, ProgramError>;
+}
+
+// Routes an interface instruction to T's implementation. None if "data"
+// isn't one, so the program can go on to its own instructions.
+pub fn process<T: Countable>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> Option<ProgramResult> {
+    let discriminator: [u8; 8] = data.get(..8)?.try_into().ok()?;
+    Some(match discriminator {
+        INITIALIZE => T::initialize(program_id, accounts),
+        INCREMENT => T::increment(program_id, accounts),
+        GET_COUNT => T::get_count(program_id, accounts).map(|count| {
+            solana_program::program::set_return_data(&count.to_le_bytes());
+        }),
+        _ => return None,
+    })
+}
@@ -0,0 +1,73 @@
+// The interface must match what the counter programs actually answer:
+// the Anchor program's discriminators and account lists, and the routing
+// a program without Anchor gets from process.
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use countable_interface::{process, Countable, GET_COUNT, INCREMENT, INITIALIZE};
+use counter::{accounts, instruction};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, system_program,
+};
+
+#[test]
+fn the_anchor_program_implements_the_interface() {
+    assert_eq!(INITIALIZE, instruction::Initialize::DISCRIMINATOR);
+    assert_eq!(INCREMENT, instruction::Increment::DISCRIMINATOR);
+    assert_eq!(GET_COUNT, instruction::GetCount::DISCRIMINATOR);
+
+    let (counter, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let initialize = countable_interface::initialize(&counter::ID, &counter, &payer);
+    assert_eq!(initialize.data, instruction::Initialize {}.data());
+    assert_eq!(
+        initialize.accounts,
+        accounts::Initialize {
+            counter,
+            user: payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None)
+    );
+    assert_eq!(
+        countable_interface::increment(&counter::ID, &counter).accounts,
+        accounts::Increment { counter }.to_account_metas(None)
+    );
+    assert_eq!(
+        countable_interface::get_count(&counter::ID, &counter).accounts,
+        accounts::ReadCounter { counter }.to_account_metas(None)
+    );
+}
+
+// Tells the handlers apart by their results.
+struct Fake;
+
+impl Countable for Fake {
+    fn initialize(_: &Pubkey, _: &[AccountInfo]) -> ProgramResult {
+        Err(ProgramError::Custom(1))
+    }
+
+    fn increment(_: &Pubkey, _: &[AccountInfo]) -> ProgramResult {
+        Err(ProgramError::Custom(2))
+    }
+
+    fn get_count(_: &Pubkey, _: &[AccountInfo]) -> Result<u64, ProgramError> {
+        Err(ProgramError::Custom(3))
+    }
+}
+
+#[test]
+fn process_routes_only_interface_instructions() {
+    let program = Pubkey::new_unique();
+    let route = |data: &[u8]| process::<Fake>(&program, &[], data);
+    assert_eq!(route(&INITIALIZE), Some(Err(ProgramError::Custom(1))));
+    assert_eq!(route(&INCREMENT), Some(Err(ProgramError::Custom(2))));
+    assert_eq!(route(&GET_COUNT), Some(Err(ProgramError::Custom(3))));
+
+    // Anything else is left to the program.
+    assert_eq!(route(&[0; 8]), None);
+    assert_eq!(route(&INCREMENT[..7]), None);
+    assert_eq!(
+        countable_interface::decode_count(&7u64.to_le_bytes()),
+        Ok(7)
+    );
+    assert!(countable_interface::decode_count(&[7]).is_err());
+}
@@ -0,0 +1,436 @@
+// ============================================================================
+// COUNTER ESCROW - payouts that wait on a counter
+// ============================================================================
+
+// A payer puts SOL or tokens into escrow for a beneficiary, on one
+// condition: a counter reaches a target count by a deadline. "Pay the
+// artist once the drop sells 1,000", "release the grant when the campaign
+// hits 50 signups by Friday".
+//
+// Each escrow is a PDA of the payer, the counter and an id the payer picks
+// (so one payer can have several on the same counter). SOL sits in the
+// Escrow account itself; tokens sit in the escrow's associated token
+// account.
+//
+//   - release: once the count is at or above the target, and the deadline
+//     hasn't passed, sends everything to the beneficiary.
+//   - refund: once the deadline has passed without a release, sends
+//     everything back to the payer.
+//
+// Both can be called by anyone, since neither lets the caller choose where
+// the funds go. Either way the escrow is closed and its rent goes back to
+// the payer.
+//
+// The count is only looked at when someone calls release, so a counter
+// that reaches its target and then goes down again (burn.rs and
+// token_mirror.rs in the counter program can lower it) has to be released
+// while it's up. In practice the beneficiary calls release as soon as it
+// can.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use counter::Counter;
+use nautilus_common::clock;
+
+declare_id!("GFtfyqJYKdX1W8fma8SMjC29JZeJmbs4ocKQegUVD4R8");
+
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+#[program]
+pub mod counter_escrow {
+    use super::*;
+
+    // Escrows "terms.amount" lamports.
+    pub fn create_escrow(ctx: Context<CreateEscrow>, id: u64, terms: Terms) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.payer = ctx.accounts.payer.key();
+        escrow.counter = ctx.accounts.counter.key();
+        escrow.mint = Pubkey::default();
+        escrow.id = id;
+        escrow.bump = ctx.bumps.escrow;
+        escrow.set_terms(terms, clock::now()?)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: escrow.to_account_info(),
+                },
+            ),
+            terms.amount,
+        )
+    }
+
+    // Escrows "terms.amount" of "mint".
+    pub fn create_token_escrow(
+        ctx: Context<CreateTokenEscrow>,
+        id: u64,
+        terms: Terms,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.payer = ctx.accounts.payer.key();
+        escrow.counter = ctx.accounts.counter.key();
+        escrow.mint = ctx.accounts.mint.key();
+        escrow.id = id;
+        escrow.bump = ctx.bumps.escrow;
+        escrow.set_terms(terms, clock::now()?)?;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.payer_tokens.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            terms.amount,
+            ctx.accounts.mint.decimals,
+        )
+    }
+
+    // Sends an SOL escrow to the beneficiary. "close = payer" on the
+    // escrow then returns its rent.
+    pub fn release(ctx: Context<Release>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        escrow.check_release(ctx.accounts.counter.count, clock::now()?)?;
+        escrow.sub_lamports(escrow.amount)?;
+        ctx.accounts.beneficiary.add_lamports(escrow.amount)?;
+        Ok(())
+    }
+
+    // Sends a token escrow to the beneficiary.
+    pub fn release_tokens(ctx: Context<ReleaseTokens>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        escrow.check_release(ctx.accounts.counter.count, clock::now()?)?;
+        pay_out_tokens(
+            escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.beneficiary_tokens,
+            &ctx.accounts.payer,
+            &ctx.accounts.token_program,
+        )
+    }
+
+    // Returns an SOL escrow to the payer. Closing it does all the work.
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        ctx.accounts.escrow.check_refund(clock::now()?)
+    }
+
+    // Returns a token escrow to the payer.
+    pub fn refund_tokens(ctx: Context<RefundTokens>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        escrow.check_refund(clock::now()?)?;
+        pay_out_tokens(
+            escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault,
+            &ctx.accounts.payer_tokens,
+            &ctx.accounts.payer,
+            &ctx.accounts.token_program,
+        )
+    }
+}
+
+// What the payer agrees to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Terms {
+    pub beneficiary: Pubkey,
+    // Lamports, or the token's smallest unit.
+    pub amount: u64,
+    // The count that releases the escrow.
+    pub target: u64,
+    // Unix time, in seconds. The last moment release works; refund works
+    // after it.
+    pub deadline: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub payer: Pubkey,
+    pub beneficiary: Pubkey,
+    pub counter: Pubkey,
+    // Pubkey::default() for SOL.
+    pub mint: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+    pub target: u64,
+    pub deadline: i64,
+    pub bump: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // Neither release nor refund can be called yet.
+    Pending,
+    Release,
+    Refund,
+}
+
+impl Escrow {
+    pub fn set_terms(&mut self, terms: Terms, now: i64) -> Result<()> {
+        require!(
+            terms.amount > 0 && terms.deadline > now,
+            EscrowError::InvalidTerms
+        );
+        self.beneficiary = terms.beneficiary;
+        self.amount = terms.amount;
+        self.target = terms.target;
+        self.deadline = terms.deadline;
+        Ok(())
+    }
+
+    pub fn outcome(&self, count: u64, now: i64) -> Outcome {
+        if now > self.deadline {
+            Outcome::Refund
+        } else if count >= self.target {
+            Outcome::Release
+        } else {
+            Outcome::Pending
+        }
+    }
+
+    pub fn check_release(&self, count: u64, now: i64) -> Result<()> {
+        match self.outcome(count, now) {
+            Outcome::Release => Ok(()),
+            Outcome::Pending => err!(EscrowError::TargetNotReached),
+            Outcome::Refund => err!(EscrowError::DeadlinePassed),
+        }
+    }
+
+    // Refunds don't depend on the count: past the deadline, release is
+    // no longer possible.
+    pub fn check_refund(&self, now: i64) -> Result<()> {
+        require!(now > self.deadline, EscrowError::DeadlineNotPassed);
+        Ok(())
+    }
+}
+
+// Empties the escrow's token account into "to" and closes it, returning
+// its rent to the payer. The token account belongs to the escrow PDA, so
+// the PDA signs.
+fn pay_out_tokens<'info>(
+    escrow: &Account<'info, Escrow>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    payer: &UncheckedAccount<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let id = escrow.id.to_le_bytes();
+    let seeds: &[&[u8]] = &[
+        ESCROW_SEED,
+        escrow.payer.as_ref(),
+        escrow.counter.as_ref(),
+        &id,
+        &[escrow.bump],
+    ];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            &[seeds],
+        ),
+        vault.amount,
+        mint.decimals,
+    )?;
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: vault.to_account_info(),
+            destination: payer.to_account_info(),
+            authority: escrow.to_account_info(),
+        },
+        &[seeds],
+    ))
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateEscrow<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [ESCROW_SEED, payer.key().as_ref(), counter.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub counter: Account<'info, Counter>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateTokenEscrow<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [ESCROW_SEED, payer.key().as_ref(), counter.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub counter: Account<'info, Counter>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = payer,
+        token::token_program = token_program
+    )]
+    pub payer_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Release<'info> {
+    #[account(
+        mut,
+        has_one = counter,
+        has_one = beneficiary,
+        has_one = payer,
+        constraint = escrow.mint == Pubkey::default() @ EscrowError::WrongAsset,
+        close = payer
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub counter: Account<'info, Counter>,
+
+    /// CHECK: the escrow's beneficiary (has_one above).
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+
+    /// CHECK: the escrow's payer (has_one above), who gets its rent back.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseTokens<'info> {
+    #[account(mut, has_one = counter, has_one = mint, has_one = payer, close = payer)]
+    pub escrow: Account<'info, Escrow>,
+
+    pub counter: Account<'info, Counter>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = escrow.beneficiary,
+        token::token_program = token_program
+    )]
+    pub beneficiary_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: the escrow's payer (has_one above), who gets the rent back.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    #[account(
+        mut,
+        has_one = payer,
+        constraint = escrow.mint == Pubkey::default() @ EscrowError::WrongAsset,
+        close = payer
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// CHECK: the escrow's payer (has_one above).
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundTokens<'info> {
+    #[account(mut, has_one = mint, has_one = payer, close = payer)]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = escrow.payer,
+        token::token_program = token_program
+    )]
+    pub payer_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: the escrow's payer (has_one above), who gets the rent back.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("Escrow needs an amount and a deadline in the future")]
+    InvalidTerms,
+    #[msg("Counter hasn't reached the escrow's target")]
+    TargetNotReached,
+    #[msg("Escrow's deadline has passed")]
+    DeadlinePassed,
+    #[msg("Escrow's deadline hasn't passed yet")]
+    DeadlineNotPassed,
+    #[msg("Escrow holds a different asset")]
+    WrongAsset,
+}
@@ -0,0 +1,67 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use counter_escrow::{Escrow, EscrowError, Outcome, Terms};
+
+fn escrow(target: u64, deadline: i64) -> Escrow {
+    Escrow {
+        payer: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        counter: Pubkey::new_unique(),
+        mint: Pubkey::default(),
+        id: 0,
+        amount: 1_000,
+        target,
+        deadline,
+        bump: 255,
+    }
+}
+
+#[test]
+fn releases_only_at_the_target_before_the_deadline() {
+    let escrow = escrow(100, 1_000);
+    assert_eq!(escrow.outcome(99, 1_000), Outcome::Pending);
+    assert_eq!(escrow.outcome(100, 1_000), Outcome::Release);
+    assert_eq!(escrow.outcome(100, 1_001), Outcome::Refund);
+
+    assert_eq!(
+        escrow.check_release(99, 500).err(),
+        Some(Error::from(EscrowError::TargetNotReached))
+    );
+    assert_eq!(
+        escrow.check_release(100, 1_001).err(),
+        Some(Error::from(EscrowError::DeadlinePassed))
+    );
+    assert!(escrow.check_release(150, 500).is_ok());
+}
+
+#[test]
+fn refunds_only_after_the_deadline() {
+    let escrow = escrow(100, 1_000);
+    assert_eq!(
+        escrow.check_refund(1_000).err(),
+        Some(Error::from(EscrowError::DeadlineNotPassed))
+    );
+    assert!(escrow.check_refund(1_001).is_ok());
+}
+
+#[test]
+fn rejects_empty_or_expired_terms() {
+    let mut escrow = escrow(0, 0);
+    let terms = Terms {
+        beneficiary: Pubkey::new_unique(),
+        amount: 5,
+        target: 10,
+        deadline: 200,
+    };
+    let invalid = Some(Error::from(EscrowError::InvalidTerms));
+    assert_eq!(
+        escrow.set_terms(Terms { amount: 0, ..terms }, 100).err(),
+        invalid
+    );
+    assert_eq!(escrow.set_terms(terms, 200).err(), invalid);
+    escrow.set_terms(terms, 100).unwrap();
+    assert_eq!(
+        (escrow.amount, escrow.target, escrow.deadline),
+        (5, 10, 200)
+    );
+}
@@ -0,0 +1,148 @@
+// ============================================================================
+// COUNTER FEED - a counter's value, shaped like an oracle price feed
+// ============================================================================
+
+// DeFi programs already know how to read oracles: an account with a value,
+// the slot and time it was published, and a rule for how old a value they
+// accept. This program gives a counter such an account, so those programs
+// can consume counter data without learning the counter program's layout
+// or its modes.
+//
+// Setup:
+//   1. Call initialize_feed with a counter. This creates the counter's Feed
+//      PDA (seeds: "feed" and the counter's address).
+//   2. Run a crank (see client/src/bin/feed-crank.rs) that calls
+//      update_feed every so often.
+//
+// update_feed is open to anyone: it copies the value straight from the
+// counter account, which Anchor checks is owned by the counter program, so
+// there's nothing a crank could lie about. All it can do is be late, which
+// is what "slot" and "timestamp" are for.
+//
+// Consumers read the Feed with AccountLoader<Feed> (or bytemuck, at offset
+// 8) and call value_no_older_than, the same way they'd reject a stale
+// price. The layout below is fixed: new fields go at the end, into the
+// reserved bytes.
+use anchor_lang::prelude::*;
+use counter::Counter;
+
+declare_id!("37YSdLLSo3tpATh8SFb9GSwJqyvCB5ouszHd2mrnfpk2");
+
+pub const FEED_SEED: &[u8] = b"feed";
+
+#[program]
+pub mod counter_feed {
+    use super::*;
+
+    // Creates a counter's Feed, holding its current value. Anyone can pay
+    // for it.
+    pub fn initialize_feed(ctx: Context<InitializeFeed>) -> Result<()> {
+        let mut feed = ctx.accounts.feed.load_init()?;
+        feed.counter = ctx.accounts.counter.key();
+        feed.bump = ctx.bumps.feed;
+        feed.publish(&ctx.accounts.counter, &Clock::get()?)
+    }
+
+    // Copies the counter's current value into its Feed. Fails if the feed
+    // was already updated in this slot, so cranks racing each other don't
+    // pay for rounds that say nothing new.
+    pub fn update_feed(ctx: Context<UpdateFeed>) -> Result<()> {
+        let mut feed = ctx.accounts.feed.load_mut()?;
+        feed.publish(&ctx.accounts.counter, &Clock::get()?)
+    }
+}
+
+// Byte offsets, after the 8-byte discriminator:
+//   0  counter     Pubkey
+//   32 value       u64
+//   40 slot        u64
+//   48 timestamp   i64
+//   56 round       u64
+//   64 finalized   u8
+//   65 bump        u8
+//   66 reserved    [u8; 62]
+#[account(zero_copy)]
+pub struct Feed {
+    pub counter: Pubkey,
+    // The counter's count when the feed was last updated.
+    pub value: u64,
+    // When that was: the slot and Unix time, in seconds.
+    pub slot: u64,
+    pub timestamp: i64,
+    // Updates so far, starting at 1 with initialize_feed.
+    pub round: u64,
+    // 1 once the counter is finalized (see finalize.rs in the counter
+    // program). Its value can't change after that, so consumers can stop
+    // caring how old it is.
+    pub finalized: u8,
+    pub bump: u8,
+    pub reserved: [u8; 62],
+}
+
+impl Feed {
+    pub const SPACE: usize = 8 + std::mem::size_of::<Feed>();
+
+    // Records the counter's value as of "clock".
+    pub fn publish(&mut self, counter: &Counter, clock: &Clock) -> Result<()> {
+        require!(
+            self.round == 0 || clock.slot > self.slot,
+            FeedError::AlreadyUpdated
+        );
+        self.value = counter.count;
+        self.slot = clock.slot;
+        self.timestamp = clock.unix_timestamp;
+        self.round += 1;
+        self.finalized = counter.finalized as u8;
+        Ok(())
+    }
+
+    // The value, unless it was published more than "max_age" seconds
+    // before "clock". A finalized counter's value is never stale.
+    pub fn value_no_older_than(&self, clock: &Clock, max_age: u64) -> Result<u64> {
+        let age = clock.unix_timestamp.saturating_sub(self.timestamp);
+        require!(
+            self.finalized != 0 || age <= max_age as i64,
+            FeedError::StaleFeed
+        );
+        Ok(self.value)
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeed<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Feed::SPACE,
+        seeds = [FEED_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub feed: AccountLoader<'info, Feed>,
+
+    pub counter: Account<'info, Counter>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeed<'info> {
+    #[account(
+        mut,
+        seeds = [FEED_SEED, counter.key().as_ref()],
+        bump = feed.load()?.bump
+    )]
+    pub feed: AccountLoader<'info, Feed>,
+
+    pub counter: Account<'info, Counter>,
+}
+
+#[error_code]
+pub enum FeedError {
+    #[msg("Feed was already updated in this slot")]
+    AlreadyUpdated,
+    #[msg("Feed value is older than the caller accepts")]
+    StaleFeed,
+}
@@ -0,0 +1,71 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Clock;
+use anchor_lang::Discriminator;
+use counter::Counter;
+use counter_feed::{Feed, FeedError};
+
+fn clock(slot: u64, unix_timestamp: i64) -> Clock {
+    Clock {
+        slot,
+        unix_timestamp,
+        ..Default::default()
+    }
+}
+
+fn counter(count: u64) -> Counter {
+    Counter {
+        count,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn layout_matches_the_documented_offsets() {
+    assert_eq!(Feed::SPACE, 8 + 128);
+    let feed = Feed {
+        value: 7,
+        round: 1,
+        finalized: 1,
+        ..bytemuck::Zeroable::zeroed()
+    };
+    let bytes = bytemuck::bytes_of(&feed);
+    assert_eq!(bytes[32..40], 7u64.to_le_bytes());
+    assert_eq!(bytes[56..64], 1u64.to_le_bytes());
+    assert_eq!(bytes[64], 1);
+    assert_ne!(Feed::DISCRIMINATOR, [0; 8]);
+}
+
+#[test]
+fn publishes_once_per_slot() {
+    let mut feed: Feed = bytemuck::Zeroable::zeroed();
+    feed.publish(&counter(3), &clock(10, 100)).unwrap();
+    assert_eq!((feed.value, feed.slot, feed.round), (3, 10, 1));
+
+    assert_eq!(
+        feed.publish(&counter(4), &clock(10, 100)).err(),
+        Some(Error::from(FeedError::AlreadyUpdated))
+    );
+    feed.publish(&counter(4), &clock(11, 101)).unwrap();
+    assert_eq!((feed.value, feed.timestamp, feed.round), (4, 101, 2));
+}
+
+#[test]
+fn rejects_stale_values_until_finalized() {
+    let mut feed: Feed = bytemuck::Zeroable::zeroed();
+    feed.publish(&counter(3), &clock(10, 100)).unwrap();
+    assert_eq!(feed.value_no_older_than(&clock(20, 160), 60).unwrap(), 3);
+    assert_eq!(
+        feed.value_no_older_than(&clock(20, 161), 60).err(),
+        Some(Error::from(FeedError::StaleFeed))
+    );
+
+    let finalized = Counter {
+        finalized: true,
+        ..counter(5)
+    };
+    feed.publish(&finalized, &clock(11, 101)).unwrap();
+    assert_eq!(
+        feed.value_no_older_than(&clock(99, 1_000_000), 60).unwrap(),
+        5
+    );
+}
@@ -0,0 +1,182 @@
+// ============================================================================
+// COUNTER HOOK - count a token's transfers
+// ============================================================================
+
+// Token-2022 mints can name a "transfer hook": a program the Token-2022
+// program calls (CPIs into) on every transfer of that mint, after moving
+// the tokens. If the hook fails, the whole transfer fails.
+//
+// This program is such a hook. Each transfer of a linked mint increments
+// a counter in the counter program, so the counter always equals the
+// number of transfers made.
+//
+// Setup:
+//   1. Create a Token-2022 mint with the TransferHook extension pointing at
+//      this program.
+//   2. Call initialize_extra_account_meta_list with the mint and a counter.
+//
+// Step 2 is how the hook gets extra accounts. Token-2022 only knows about
+// the accounts a transfer needs (source, mint, destination, owner), but
+// we also need the counter and the counter program. The SPL transfer hook
+// interface solves this with the "ExtraAccountMetaList": a PDA of the hook
+// program, per mint, listing the extra accounts to pass. Wallets and
+// Token-2022 read it and append those accounts to every transfer.
+//
+// The counter's own modes still apply: a once-per-slot counter makes a
+// second transfer in the same slot fail, and a tree-logged counter
+// rejects every transfer, since those increments must be logged.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_lang::InstructionData;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_hook::{TransferHook, TransferHookAccount},
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::{
+    Account as TokenAccountState, Mint as MintState,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use counter::program::Counter as CounterProgram;
+use counter::Counter;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("Hu9Js7yYvGzxMxT9QzW2VMrdvBQpLR11pDUvaVzaqNcP");
+
+// Fixed by the transfer hook interface; Token-2022 derives the list's
+// address from it.
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
+#[program]
+pub mod counter_hook {
+    use super::*;
+
+    // Links a mint to a counter by writing the mint's ExtraAccountMetaList.
+    // Only the mint's transfer hook authority (set when the mint was
+    // created) can do this.
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let metas = extra_account_metas(ctx.accounts.counter.key());
+        let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &metas)?;
+        Ok(())
+    }
+
+    // Called by Token-2022 on every transfer. "interface" makes Anchor use
+    // the transfer hook interface's discriminator for this instruction
+    // instead of one derived from its name, since Token-2022 is the caller.
+    #[interface(spl_transfer_hook_interface::execute)]
+    pub fn transfer_hook(ctx: Context<TransferHookExecute>, _amount: u64) -> Result<()> {
+        // The execute instruction can be called by anyone, not just
+        // Token-2022, which would let them increment without transferring.
+        // Token-2022 sets "transferring" on the source account for the
+        // duration of the hook call, so check for that.
+        let source = ctx.accounts.source_token.to_account_info();
+        let data = source.try_borrow_data()?;
+        let source = StateWithExtensions::<TokenAccountState>::unpack(&data)?;
+        let transferring = bool::from(source.get_extension::<TransferHookAccount>()?.transferring);
+        require!(transferring, HookError::NotTransferring);
+
+        // The counter crate's generated "instruction" and "accounts" types
+        // build the increment instruction for us. (Its "cpi" feature
+        // doesn't compile on Anchor 0.30 because get_version takes no
+        // accounts.)
+        let increment = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Increment {
+                counter: ctx.accounts.counter.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Increment {}.data(),
+        };
+        invoke(
+            &increment,
+            &[
+                ctx.accounts.counter.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+        )?;
+        // Anchor writes "mut" accounts back when the instruction ends. Our
+        // copy of the counter predates the increment, so reload it or the
+        // write-back would undo the increment.
+        ctx.accounts.counter.reload()?;
+        Ok(())
+    }
+}
+
+// The extra accounts every transfer of a linked mint passes to the hook,
+// after the interface's five (source, mint, destination, owner, and the
+// ExtraAccountMetaList itself).
+pub fn extra_account_metas(counter: Pubkey) -> Vec<ExtraAccountMeta> {
+    vec![
+        // Index 5: the counter program, needed to CPI into it.
+        ExtraAccountMeta::new_with_pubkey(&counter::ID, false, false).unwrap(),
+        // Index 6: the counter, writable so it can be incremented.
+        ExtraAccountMeta::new_with_pubkey(&counter, false, true).unwrap(),
+    ]
+}
+
+// The authority allowed to change the mint's transfer hook, or None if
+// it has none (or the mint has no TransferHook extension).
+fn transfer_hook_authority(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(hook) = mint.get_extension::<TransferHook>() else {
+        return Ok(None);
+    };
+    Ok(Option::<Pubkey>::from(hook.authority))
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas(counter.key()).len())?,
+        seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: written by ExtraAccountMetaList::init, not Anchor.
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = transfer_hook_authority(&mint.to_account_info())? == Some(payer.key())
+            @ HookError::NotHookAuthority
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub counter: Account<'info, Counter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// The order of the first five accounts is fixed by the interface.
+#[derive(Accounts)]
+pub struct TransferHookExecute<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: the source's owner or delegate; Token-2022 already checked it.
+    pub owner: UncheckedAccount<'info>,
+    #[account(seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()], bump)]
+    /// CHECK: Token-2022 resolved the accounts below from it.
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+}
+
+#[error_code]
+pub enum HookError {
+    #[msg("Only the mint's transfer hook authority can link it to a counter")]
+    NotHookAuthority,
+    #[msg("The hook can only be called by Token-2022 during a transfer")]
+    NotTransferring,
+}
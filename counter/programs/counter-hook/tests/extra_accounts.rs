@@ -0,0 +1,32 @@
+// Token-2022 builds the hook's accounts from the ExtraAccountMetaList, so
+// check the list resolves to the counter program and the linked counter.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use counter_hook::extra_account_metas;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+use spl_type_length_value::state::TlvStateBorrowed;
+
+#[test]
+fn extra_accounts_resolve_to_the_linked_counter() {
+    let counter = Pubkey::new_unique();
+    let metas = extra_account_metas(counter);
+
+    let mut data = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &metas).unwrap();
+
+    let state = TlvStateBorrowed::unpack(&data).unwrap();
+    let stored = ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&state).unwrap();
+    let resolved: Vec<AccountMeta> = stored
+        .data()
+        .iter()
+        .map(|meta| AccountMeta::try_from(meta).unwrap())
+        .collect();
+    assert_eq!(
+        resolved,
+        [
+            AccountMeta::new_readonly(counter::ID, false),
+            AccountMeta::new(counter, false),
+        ]
+    );
+}
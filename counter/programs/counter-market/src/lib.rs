@@ -0,0 +1,369 @@
+// ============================================================================
+// COUNTER MARKET - betting on where a counter ends up
+// ============================================================================
+
+// A market asks one question about a counter: will its final count be
+// above "threshold"? Users stake SOL on YES or NO until "close_time", and
+// get that many shares (one per lamport) of the side they picked. Shares
+// are ordinary tokens, so they can be traded before the market resolves.
+//
+// The answer comes from the counter program's finalization (see
+// finalize.rs there): once the counter is finalized, anyone can call
+// resolve, which reads its FinalResult PDA. The winning side then splits
+// the whole pool pro rata: redeem burns a holder's winning shares and pays
+//
+//   shares * (yes_staked + no_staked) / winning side's shares
+//
+// Rounding is always down, so the pool never pays out more than it holds;
+// the dust stays in the market account. If nobody staked on the winning
+// side the market is void, and every share redeems for the lamport it
+// cost.
+//
+// The market should close before the counter's end time: stake refuses a
+// finalized counter, but nothing stops the count from moving in the
+// meantime, and a late close only gives stakers more to go on.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Burn, Mint, MintTo, TokenAccount, TokenInterface};
+use counter::{Counter, FinalResult, RESULT_SEED};
+use nautilus_common::{clock, math, CommonError};
+
+declare_id!("A7iT6RjHzkN3CGSYH3a3NAH2Q9MDBeerxkx2MwbaSko9");
+
+pub const MARKET_SEED: &[u8] = b"market";
+pub const YES_MINT_SEED: &[u8] = b"yes_mint";
+pub const NO_MINT_SEED: &[u8] = b"no_mint";
+
+#[program]
+pub mod counter_market {
+    use super::*;
+
+    // Opens a market on "counter". "id" lets a creator open several on
+    // the same counter.
+    pub fn create_market(
+        ctx: Context<CreateMarket>,
+        id: u64,
+        threshold: u64,
+        close_time: i64,
+    ) -> Result<()> {
+        require!(close_time > clock::now()?, MarketError::InvalidMarket);
+        require!(
+            !ctx.accounts.counter.finalized,
+            MarketError::CounterFinalized
+        );
+
+        let market = &mut ctx.accounts.market;
+        market.counter = ctx.accounts.counter.key();
+        market.creator = ctx.accounts.creator.key();
+        market.id = id;
+        market.threshold = threshold;
+        market.close_time = close_time;
+        market.yes_mint = ctx.accounts.yes_mint.key();
+        market.no_mint = ctx.accounts.no_mint.key();
+        market.yes_staked = 0;
+        market.no_staked = 0;
+        market.resolution = Resolution::Open;
+        market.bump = ctx.bumps.market;
+        Ok(())
+    }
+
+    // Puts "lamports" on "side" and mints the staker as many shares.
+    pub fn stake(ctx: Context<Stake>, side: Side, lamports: u64) -> Result<()> {
+        require!(lamports > 0, MarketError::InvalidStake);
+        require!(
+            !ctx.accounts.counter.finalized,
+            MarketError::CounterFinalized
+        );
+        let market = &mut ctx.accounts.market;
+        market.record_stake(side, lamports, clock::now()?)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.staker.to_account_info(),
+                    to: market.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        // The mints belong to the market PDA, so it signs.
+        let id = market.id.to_le_bytes();
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.staker_shares.to_account_info(),
+                    authority: market.to_account_info(),
+                },
+                &[&[
+                    MARKET_SEED,
+                    market.counter.as_ref(),
+                    market.creator.as_ref(),
+                    &id,
+                    &[market.bump],
+                ]],
+            ),
+            lamports,
+        )
+    }
+
+    // Settles the market from the counter's final result. Anyone can call
+    // it, once.
+    pub fn resolve(ctx: Context<Resolve>) -> Result<()> {
+        let count = ctx.accounts.result.count;
+        ctx.accounts.market.resolve(count)
+    }
+
+    // Burns all of the holder's "side" shares and pays out their part of
+    // the pool.
+    pub fn redeem(ctx: Context<Redeem>, side: Side) -> Result<()> {
+        let shares = ctx.accounts.holder_shares.amount;
+        let market = &ctx.accounts.market;
+        let payout = market.payout(side, shares)?;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.holder_shares.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+        market.sub_lamports(payout)?;
+        ctx.accounts.holder.add_lamports(payout)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Yes,
+    No,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum Resolution {
+    Open,
+    // The final count was above the threshold.
+    Yes,
+    No,
+    // Nobody staked on the side that won.
+    Void,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Market {
+    pub counter: Pubkey,
+    pub creator: Pubkey,
+    pub id: u64,
+    pub threshold: u64,
+    // Unix time, in seconds. Staking ends then.
+    pub close_time: i64,
+    pub yes_mint: Pubkey,
+    pub no_mint: Pubkey,
+    // Lamports staked on each side, which is also each side's share
+    // supply until redeeming starts.
+    pub yes_staked: u64,
+    pub no_staked: u64,
+    pub resolution: Resolution,
+    pub bump: u8,
+}
+
+impl Market {
+    pub fn share_mint(&self, side: Side) -> Pubkey {
+        match side {
+            Side::Yes => self.yes_mint,
+            Side::No => self.no_mint,
+        }
+    }
+
+    pub fn record_stake(&mut self, side: Side, lamports: u64, now: i64) -> Result<()> {
+        require!(now < self.close_time, MarketError::MarketClosed);
+        let staked = match side {
+            Side::Yes => &mut self.yes_staked,
+            Side::No => &mut self.no_staked,
+        };
+        *staked = staked
+            .checked_add(lamports)
+            .ok_or(MarketError::InvalidStake)?;
+        Ok(())
+    }
+
+    pub fn resolve(&mut self, final_count: u64) -> Result<()> {
+        require!(
+            self.resolution == Resolution::Open,
+            MarketError::AlreadyResolved
+        );
+        let (resolution, winners) = if final_count > self.threshold {
+            (Resolution::Yes, self.yes_staked)
+        } else {
+            (Resolution::No, self.no_staked)
+        };
+        self.resolution = if winners == 0 {
+            Resolution::Void
+        } else {
+            resolution
+        };
+        Ok(())
+    }
+
+    // What "shares" of "side" redeem for.
+    pub fn payout(&self, side: Side, shares: u64) -> Result<u64> {
+        let winners = match (self.resolution, side) {
+            (Resolution::Open, _) => return err!(MarketError::NotResolved),
+            (Resolution::Void, _) => return Ok(shares),
+            (Resolution::Yes, Side::Yes) => self.yes_staked,
+            (Resolution::No, Side::No) => self.no_staked,
+            _ => return err!(MarketError::LosingSide),
+        };
+        let pool = self
+            .yes_staked
+            .checked_add(self.no_staked)
+            .ok_or(CommonError::Overflow)?;
+        math::mul_div(shares, pool, winners)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [MARKET_SEED, counter.key().as_ref(), creator.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    pub counter: Account<'info, Counter>,
+
+    // Shares have SOL's 9 decimals, since each one is a staked lamport.
+    #[account(
+        init,
+        payer = creator,
+        seeds = [YES_MINT_SEED, market.key().as_ref()],
+        bump,
+        mint::decimals = 9,
+        mint::authority = market,
+        mint::token_program = token_program
+    )]
+    pub yes_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [NO_MINT_SEED, market.key().as_ref()],
+        bump,
+        mint::decimals = 9,
+        mint::authority = market,
+        mint::token_program = token_program
+    )]
+    pub no_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(side: Side)]
+pub struct Stake<'info> {
+    #[account(mut, has_one = counter)]
+    pub market: Account<'info, Market>,
+
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        address = market.share_mint(side),
+        mint::token_program = token_program
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = staker,
+        associated_token::mint = share_mint,
+        associated_token::authority = staker,
+        associated_token::token_program = token_program
+    )]
+    pub staker_shares: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Resolve<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    // Only exists once the counter is finalized. "seeds::program" derives
+    // the address under the counter program, which owns it.
+    #[account(
+        seeds = [RESULT_SEED, market.counter.as_ref()],
+        bump = result.bump,
+        seeds::program = counter::ID
+    )]
+    pub result: Account<'info, FinalResult>,
+}
+
+#[derive(Accounts)]
+#[instruction(side: Side)]
+pub struct Redeem<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        address = market.share_mint(side),
+        mint::token_program = token_program
+    )]
+    pub share_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub holder_shares: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum MarketError {
+    #[msg("Market's close time must be in the future")]
+    InvalidMarket,
+    #[msg("Counter is already finalized")]
+    CounterFinalized,
+    #[msg("Stake must be positive")]
+    InvalidStake,
+    #[msg("Market is closed for staking")]
+    MarketClosed,
+    #[msg("Market is already resolved")]
+    AlreadyResolved,
+    #[msg("Market isn't resolved yet")]
+    NotResolved,
+    #[msg("These shares are on the losing side")]
+    LosingSide,
+}
@@ -0,0 +1,61 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use counter_market::{Market, MarketError, Resolution, Side};
+
+fn market(yes_staked: u64, no_staked: u64) -> Market {
+    Market {
+        counter: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        id: 0,
+        threshold: 100,
+        close_time: 1_000,
+        yes_mint: Pubkey::new_unique(),
+        no_mint: Pubkey::new_unique(),
+        yes_staked,
+        no_staked,
+        resolution: Resolution::Open,
+        bump: 255,
+    }
+}
+
+#[test]
+fn stakes_only_before_close() {
+    let mut market = market(0, 0);
+    market.record_stake(Side::Yes, 5, 999).unwrap();
+    market.record_stake(Side::No, 7, 999).unwrap();
+    assert_eq!((market.yes_staked, market.no_staked), (5, 7));
+    assert_eq!(
+        market.record_stake(Side::Yes, 5, 1_000).err(),
+        Some(Error::from(MarketError::MarketClosed))
+    );
+}
+
+#[test]
+fn winners_split_the_pool_pro_rata() {
+    let mut market = market(300, 700);
+    assert_eq!(
+        market.payout(Side::Yes, 100).err(),
+        Some(Error::from(MarketError::NotResolved))
+    );
+    // "Above" the threshold: exactly at it is a NO.
+    market.resolve(101).unwrap();
+    assert_eq!(market.resolution, Resolution::Yes);
+    assert_eq!(market.payout(Side::Yes, 300).unwrap(), 1_000);
+    assert_eq!(market.payout(Side::Yes, 1).unwrap(), 3);
+    assert_eq!(
+        market.payout(Side::No, 700).err(),
+        Some(Error::from(MarketError::LosingSide))
+    );
+    assert_eq!(
+        market.resolve(101).err(),
+        Some(Error::from(MarketError::AlreadyResolved))
+    );
+}
+
+#[test]
+fn markets_without_winners_are_void() {
+    let mut market = market(0, 700);
+    market.resolve(500).unwrap();
+    assert_eq!(market.resolution, Resolution::Void);
+    assert_eq!(market.payout(Side::No, 700).unwrap(), 700);
+}
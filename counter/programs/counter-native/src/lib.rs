@@ -0,0 +1,217 @@
+// ============================================================================
+// SOLANA COUNTER PROGRAM - without Anchor
+// ============================================================================
+
+// This is the same program as programs/counter, written against the plain
+// "solana-program" library. Everything Anchor's macros generated for us -
+// instruction routing, account checks, (de)serialization - is written out by
+// hand below.
+//
+// It exists to measure how many compute units Anchor adds (see
+// tests/litesvm/native.ts), so it deliberately stays wire-compatible with
+// the Anchor program:
+//   - the same 8-byte instruction discriminators, so the same client code
+//     can build transactions for either program. Its three instructions
+//     are the Countable interface's (see interface/), so it routes them
+//     through that crate
+//   - the same account layout as the Anchor Counter, so accounts look
+//     identical to explorers and indexers. That includes its checksum,
+//     which every write here recomputes the same way (see seal)
+use countable_interface::Countable;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    hash::hashv,
+    log::sol_log_64,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+solana_program::declare_id!("3Toq9X9ME3pAGaAPdNFcspH4fnGXivgqpsyzztjgTr97");
+
+// Anchor picks these as the first 8 bytes of sha256("global:<name>") and
+// sha256("account:<name>"). tests/layout.rs checks they still match.
+pub use countable_interface::{
+    GET_COUNT as GET_COUNT_DISCRIMINATOR, INCREMENT as INCREMENT_DISCRIMINATOR,
+    INITIALIZE as INITIALIZE_DISCRIMINATOR,
+};
+pub const COUNTER_DISCRIMINATOR: [u8; 8] = [255, 176, 4, 245, 188, 253, 124, 25];
+
+// The Anchor program's Counter: discriminator, then the fields in order.
+// Only the ones this program writes get named offsets.
+//
+//   0..8    discriminator
+//   8..16   count (u64, little endian)
+//   16..48  authority (Pubkey)
+//   48      flags (u8)
+//   49..57  last_increment_slot (u64)
+//   57..65  alarm_threshold (u64)
+//   65..73  event_seq (u64)
+//   73      finalized (bool)
+//   74      deny_cpi (bool)
+//   75..83  lifetime_count (u64, little endian)
+//   83      role_weighted (bool)
+//   84..148 tags ([[u8; 16]; 4])
+//   148..156 max_count (u64)
+//   156..188 overflow (Pubkey)
+//   188..204 cumulative_count (u128)
+//   204..212 twac_slot (u64)
+//   212..364 delta_feed (three u64s, then 16 (u32, i32) entries)
+//   364     frozen (bool)
+//   365..373 checksum (first 8 bytes of sha256 of 8..365 and 373..413)
+//   373..405 group (Pubkey)
+//   405..413 gifted_count (u64)
+pub const COUNTER_SPACE: usize = 413;
+const COUNT: std::ops::Range<usize> = 8..16;
+const AUTHORITY: std::ops::Range<usize> = 16..48;
+const LIFETIME_COUNT: std::ops::Range<usize> = 75..83;
+const CHECKSUM: std::ops::Range<usize> = 365..373;
+
+// Same numbers as CounterError::Overflow and logging::LogEvent in the Anchor
+// program, so errors and logs decode the same way for both.
+pub const OVERFLOW_ERROR: u32 = 6000;
+const LOG_INITIALIZED: u64 = 1;
+const LOG_INCREMENTED: u64 = 2;
+
+// "entrypoint!" generates the function the Solana runtime calls first. It
+// turns the raw input bytes into a program id, a list of accounts, and the
+// instruction data, then calls process_instruction.
+//
+// Other programs that depend on this crate (like our tests) enable
+// "no-entrypoint" so two entrypoints don't end up in one binary.
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+// Routes the instruction to its handler based on the first 8 bytes.
+// This is what "#[program]" generates in the Anchor version.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    countable_interface::process::<NativeCounter>(program_id, accounts, data)
+        .unwrap_or(Err(ProgramError::InvalidInstructionData))
+}
+
+// The handlers below, as the interface's Countable.
+pub struct NativeCounter;
+
+impl Countable for NativeCounter {
+    fn initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        initialize(program_id, accounts)
+    }
+
+    fn increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        increment(program_id, accounts)
+    }
+
+    fn get_count(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<u64, ProgramError> {
+        get_count(program_id, accounts)
+    }
+}
+
+// Accounts: [counter (signer, writable), user (signer, writable), system_program]
+fn initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let counter = next_account_info(accounts)?;
+    let user = next_account_info(accounts)?;
+    let system = next_account_info(accounts)?;
+
+    // "#[account(mut)]" and "Signer<'info>" in the Anchor version.
+    if !counter.is_signer || !user.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !counter.is_writable || !user.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // "Program<'info, System>"
+    if *system.key != system_program::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // "#[account(init, payer = user, space = ...)]": ask the System
+    // Program to create the account, funded by the user and owned by us.
+    let lamports = Rent::get()?.minimum_balance(COUNTER_SPACE);
+    invoke(
+        &system_instruction::create_account(
+            user.key,
+            counter.key,
+            lamports,
+            COUNTER_SPACE as u64,
+            program_id,
+        ),
+        &[user.clone(), counter.clone(), system.clone()],
+    )?;
+
+    // Write the discriminator and authority. Everything else (count,
+    // flags, ...) starts at 0, which new accounts already are.
+    let mut data = counter.try_borrow_mut_data()?;
+    data[..8].copy_from_slice(&COUNTER_DISCRIMINATOR);
+    data[AUTHORITY].copy_from_slice(user.key.as_ref());
+    seal(&mut data);
+
+    sol_log_64(LOG_INITIALIZED, 0, 0, 0, 0);
+    Ok(())
+}
+
+// Accounts: [counter (writable)]
+fn increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let counter = next_account_info(&mut accounts.iter())?;
+
+    // "Account<'info, Counter>" checks: owned by us, right type, writable.
+    if counter.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !counter.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut data = counter.try_borrow_mut_data()?;
+    if data.len() < COUNTER_SPACE || data[..8] != COUNTER_DISCRIMINATOR {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Read the u64 straight out of the account bytes ("le" = little
+    // endian, the byte order Borsh uses), add 1, and write it back.
+    //
+    // This program has no instructions for turning on the Anchor
+    // program's optional modes (flags), joining a group or setting an
+    // alarm threshold, so its counters never have any and there's nothing
+    // else to check.
+    let count = u64::from_le_bytes(data[COUNT].try_into().unwrap())
+        .checked_add(1)
+        .ok_or(ProgramError::Custom(OVERFLOW_ERROR))?;
+    // Counter::add in the Anchor version: the lifetime count goes up too.
+    let lifetime_count = u64::from_le_bytes(data[LIFETIME_COUNT].try_into().unwrap())
+        .checked_add(1)
+        .ok_or(ProgramError::Custom(OVERFLOW_ERROR))?;
+    data[COUNT].copy_from_slice(&count.to_le_bytes());
+    data[LIFETIME_COUNT].copy_from_slice(&lifetime_count.to_le_bytes());
+    seal(&mut data);
+
+    sol_log_64(LOG_INCREMENTED, count, 0, 0, 0);
+    Ok(())
+}
+
+// What the Anchor Counter's try_serialize does on every write: store the
+// checksum of the fields on either side of it (see checksum.rs there).
+fn seal(data: &mut [u8]) {
+    let checksum = hashv(&[&data[8..CHECKSUM.start], &data[CHECKSUM.end..]]).to_bytes();
+    data[CHECKSUM].copy_from_slice(&checksum[..8]);
+}
+
+// Accounts: [counter]
+fn get_count(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<u64, ProgramError> {
+    let counter = next_account_info(&mut accounts.iter())?;
+    if counter.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let data = counter.try_borrow_data()?;
+    if data.len() < COUNTER_SPACE || data[..8] != COUNTER_DISCRIMINATOR {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(u64::from_le_bytes(data[COUNT].try_into().unwrap()))
+}
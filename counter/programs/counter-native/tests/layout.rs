@@ -0,0 +1,46 @@
+// The native program must stay byte-compatible with the Anchor one. These
+// tests fail if either side changes its discriminators or account layout.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::{AccountSerialize, Discriminator, Space};
+use counter::{instruction, Counter, CounterError};
+use counter_native::{
+    COUNTER_DISCRIMINATOR, COUNTER_SPACE, GET_COUNT_DISCRIMINATOR, INCREMENT_DISCRIMINATOR,
+    INITIALIZE_DISCRIMINATOR, OVERFLOW_ERROR,
+};
+
+#[test]
+fn instruction_discriminators_match_anchor() {
+    assert_eq!(INITIALIZE_DISCRIMINATOR, instruction::Initialize::DISCRIMINATOR);
+    assert_eq!(INCREMENT_DISCRIMINATOR, instruction::Increment::DISCRIMINATOR);
+    assert_eq!(GET_COUNT_DISCRIMINATOR, instruction::GetCount::DISCRIMINATOR);
+}
+
+#[test]
+fn counter_layout_matches_anchor() {
+    let authority = Pubkey::new_unique();
+    let mut bytes = Vec::new();
+    Counter {
+        count: 0x0102_0304_0506_0708,
+        authority,
+        lifetime_count: 0x1112_1314_1516_1718,
+        ..Default::default()
+    }
+    .try_serialize(&mut bytes)
+    .unwrap();
+
+    assert_eq!(bytes.len(), COUNTER_SPACE);
+    assert_eq!(COUNTER_SPACE, 8 + Counter::INIT_SPACE);
+    assert_eq!(bytes[..8], COUNTER_DISCRIMINATOR);
+    assert_eq!(bytes[8..16], 0x0102_0304_0506_0708u64.to_le_bytes());
+    assert_eq!(bytes[16..48], authority.to_bytes());
+    assert_eq!(bytes[75..83], 0x1112_1314_1516_1718u64.to_le_bytes());
+    // What seal writes.
+    let fields = [&bytes[8..365], &bytes[373..]];
+    assert_eq!(bytes[365..373], hashv(&fields).to_bytes()[..8]);
+}
+
+#[test]
+fn overflow_error_code_matches_anchor() {
+    assert_eq!(OVERFLOW_ERROR, u32::from(CounterError::Overflow));
+}
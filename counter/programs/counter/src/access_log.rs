@@ -0,0 +1,130 @@
+// ============================================================================
+// ACCESS LOG - an on-chain record of what the authority changed
+// ============================================================================
+
+// Events (see events.rs) only live in transaction logs, which RPC nodes
+// drop after a while. For settings changes - who turned a mode on, and
+// when - that's not good enough for an audit.
+//
+// Each counter gets an AccessLog PDA, created the first time its authority
+// changes something. Every admin instruction appends an entry: who signed,
+// what they did, and when. It's a "ring buffer": once all 64 entries are
+// used, the next one overwrites the oldest, so the account never grows.
+//
+// It's zero-copy (see bank.rs), since it's a few KB and each write only
+// touches one entry.
+use std::cell::RefMut;
+
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+pub const ACCESS_LOG_SEED: &[u8] = b"access_log";
+pub const ACCESS_LOG_ENTRIES: usize = 64;
+
+// What an entry records. Like LogEvent, the numbers are fixed, so entries
+// written by older versions of the program still read correctly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    SetOncePerSlot = 1,
+    SetAlarmThreshold = 2,
+    AcknowledgeAlarm = 3,
+    SetTreeLogged = 4,
+    InitializeCounterMint = 5,
+    SetBurnMint = 6,
+    Archive = 7,
+    Unarchive = 8,
+    LeaseAuthority = 9,
+    SetIncrementFee = 10,
+    SetVoteWeighted = 11,
+    SetCommitReveal = 12,
+    OpenCommitRound = 13,
+    SetEndTime = 14,
+    SetDenyCpi = 15,
+    AllowProgram = 16,
+    DisallowProgram = 17,
+    ExemptFromFees = 18,
+    RevokeFeeExemption = 19,
+    ResetCount = 20,
+    SetRoleWeighted = 21,
+    SetRole = 22,
+    SetSoftCap = 23,
+    SetTwac = 24,
+    SetDeltaFeed = 25,
+    Unfreeze = 26,
+    SetFeeSchedule = 27,
+    JoinGroup = 28,
+    LeaveGroup = 29,
+    WithdrawVault = 30,
+    ClaimGift = 31,
+}
+
+/// The last ACCESS_LOG_ENTRIES settings changes of a counter, in a ring.
+#[account(zero_copy)]
+pub struct AccessLog {
+    pub counter: Pubkey,
+    /// Entries written ever. The next one goes in entries[total % 64].
+    pub total: u64,
+    pub entries: [AccessLogEntry; ACCESS_LOG_ENTRIES],
+}
+
+// "#[zero_copy]" is "#[account(zero_copy)]" for structs that are only
+// ever part of an account, so they get no discriminator.
+#[zero_copy]
+pub struct AccessLogEntry {
+    /// Who made the change.
+    pub actor: Pubkey,
+    /// When, in unix seconds.
+    pub timestamp: i64,
+    /// An AdminAction.
+    pub action: u8,
+    // Zero-copy structs can't have gaps the compiler fills in by itself,
+    // so the padding up to a multiple of 8 bytes is spelled out.
+    pub padding: [u8; 7],
+}
+
+impl AccessLog {
+    pub const SPACE: usize = 8 + std::mem::size_of::<AccessLog>();
+
+    pub fn append(&mut self, actor: Pubkey, action: AdminAction, timestamp: i64) {
+        let index = (self.total % ACCESS_LOG_ENTRIES as u64) as usize;
+        self.entries[index] = AccessLogEntry {
+            actor,
+            timestamp,
+            action: action as u8,
+            padding: [0; 7],
+        };
+        self.total += 1;
+    }
+
+    // The entries from oldest to newest.
+    pub fn entries(&self) -> impl Iterator<Item = &AccessLogEntry> {
+        let len = self.total.min(ACCESS_LOG_ENTRIES as u64) as usize;
+        let start = (self.total as usize).wrapping_sub(len);
+        (start..start + len).map(|i| &self.entries[i % ACCESS_LOG_ENTRIES])
+    }
+}
+
+// Appends an entry to a counter's log. The log is "init_if_needed" in
+// every admin accounts struct, so the first call finds it blank.
+pub fn record(
+    access_log: &AccountLoader<AccessLog>,
+    counter: Pubkey,
+    actor: Pubkey,
+    action: AdminAction,
+) -> Result<()> {
+    let mut log = load_or_init(access_log)?;
+    log.counter = counter;
+    log.append(actor, action, clock::now()?);
+    Ok(())
+}
+
+// A zero-copy account that was just created has to be opened with
+// "load_init"; after that, with "load_mut".
+fn load_or_init<'a>(access_log: &'a AccountLoader<AccessLog>) -> Result<RefMut<'a, AccessLog>> {
+    let blank = access_log.as_ref().try_borrow_data()?[..8] == [0; 8];
+    if blank {
+        access_log.load_init()
+    } else {
+        access_log.load_mut()
+    }
+}
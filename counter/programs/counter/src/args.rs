@@ -0,0 +1,33 @@
+// ============================================================================
+// ARGS - versioned instruction arguments
+// ============================================================================
+
+// Some instructions keep gaining arguments: a lease may get a grace period,
+// a fee a cap. Added as one more positional argument, each of those changes
+// the instruction's data and breaks every client built before it.
+//
+// So those instructions take one struct instead (LeaseArgs,
+// IncrementFeeArgs, ...), which starts with a "version" byte. The rules for
+// changing one:
+//
+//   - A new version only adds fields at the end, and bumps the struct's
+//     VERSION. Nothing is removed, reordered or resized.
+//   - The struct then decodes by hand, reading the new fields only when
+//     "version" says they were sent and defaulting them otherwise, so what
+//     older clients send keeps decoding.
+//   - Handlers call check_args_version first. Anchor ignores whatever data
+//     is left after the arguments, so without it a program older than the
+//     client would silently drop the fields it doesn't know about.
+use anchor_lang::prelude::*;
+
+use crate::CounterError;
+
+// Fails unless "version" is one this build knows, 1 through "current". 0
+// is never valid, so zeroed data isn't taken for arguments.
+pub fn check_args_version(version: u8, current: u8) -> Result<()> {
+    require!(
+        (1..=current).contains(&version),
+        CounterError::UnsupportedArgsVersion
+    );
+    Ok(())
+}
@@ -0,0 +1,199 @@
+// ============================================================================
+// BADGES - on-chain achievements for a counter's contributors
+// ============================================================================
+
+// increment_contributed is an increment that also keeps score of who made
+// it, in a Contribution PDA per (counter, contributor). When a
+// contributor's score reaches one of the TIERS (10, 100, 1000), the
+// moment is written down, and they can claim a Badge PDA for that tier
+// with claim_badge. A badge can only exist if the tier was reached, so
+// front-ends can show it as proof.
+//
+// Claiming is a separate instruction so increments don't each have to
+// pass in (and pay for) badge accounts that are almost never needed.
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::stats::{self, current_hour_seed, StatsBucket, STATS_SEED};
+use crate::{AlarmTriggered, BadgeEarned, Counter, CounterError, CounterIncremented, Version};
+
+pub const CONTRIBUTION_SEED: &[u8] = b"contribution";
+pub const BADGE_SEED: &[u8] = b"badge";
+
+// The contribution counts that earn a badge. Tier 0 is the first.
+pub const TIERS: [u64; 3] = [10, 100, 1000];
+
+/// One contributor's increments of one counter, and the tiers they reached.
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    pub counter: Pubkey,
+    pub contributor: Pubkey,
+    /// Increments this contributor made through increment_contributed.
+    pub count: u64,
+    /// When each tier was reached (unix seconds), or 0 if it wasn't yet.
+    pub reached_at: [i64; TIERS.len()],
+    pub bump: u8,
+}
+
+impl Contribution {
+    // Adds one contribution, recording "now" for a tier it reaches.
+    pub fn add(&mut self, now: i64) -> Result<()> {
+        self.count = self.count.checked_add(1).ok_or(CounterError::Overflow)?;
+        if let Some(tier) = TIERS.iter().position(|&t| t == self.count) {
+            self.reached_at[tier] = now;
+        }
+        Ok(())
+    }
+}
+
+/// Held by a contributor who reached "tier" on a counter.
+#[account]
+#[derive(InitSpace)]
+pub struct Badge {
+    pub counter: Pubkey,
+    pub owner: Pubkey,
+    /// The index of the tier in TIERS, from 0.
+    pub tier: u8,
+    /// When the tier was reached (not when the badge was claimed), in unix
+    /// seconds.
+    pub earned_at: i64,
+    pub bump: u8,
+}
+
+pub fn process_increment_contributed(ctx: Context<IncrementContributed>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.check_modes()?;
+    let before = counter.count;
+    counter.increment()?;
+    debug_assert_invariants!(counter);
+
+    let contribution = &mut ctx.accounts.contribution;
+    contribution.counter = counter.key();
+    contribution.contributor = ctx.accounts.contributor.key();
+    contribution.bump = ctx.bumps.contribution;
+    contribution.add(clock::now()?)?;
+
+    stats::record_increment(
+        &mut ctx.accounts.stats_bucket,
+        ctx.bumps.stats_bucket,
+        counter.key(),
+        &ctx.accounts.contributor.key(),
+        0,
+    )?;
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+pub fn process_claim_badge(ctx: Context<ClaimBadge>, tier: u8) -> Result<()> {
+    let earned_at = *ctx
+        .accounts
+        .contribution
+        .reached_at
+        .get(usize::from(tier))
+        .ok_or(CounterError::TierNotReached)?;
+    require!(earned_at != 0, CounterError::TierNotReached);
+
+    let badge = &mut ctx.accounts.badge;
+    badge.counter = ctx.accounts.contribution.counter;
+    badge.owner = ctx.accounts.contributor.key();
+    badge.tier = tier;
+    badge.earned_at = earned_at;
+    badge.bump = ctx.bumps.badge;
+
+    emit!(BadgeEarned {
+        counter: badge.counter,
+        owner: badge.owner,
+        tier,
+        earned_at,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct IncrementContributed<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + Contribution::INIT_SPACE,
+        seeds = [CONTRIBUTION_SEED, counter.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    // This hour's stats (see stats.rs).
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + StatsBucket::INIT_SPACE,
+        seeds = [
+            STATS_SEED,
+            counter.key().as_ref(),
+            &current_hour_seed()
+        ],
+        bump
+    )]
+    pub stats_bucket: Account<'info, StatsBucket>,
+
+    // Signs, so nobody can run up someone else's score.
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct ClaimBadge<'info> {
+    #[account(
+        seeds = [
+            CONTRIBUTION_SEED,
+            contribution.counter.as_ref(),
+            contributor.key().as_ref()
+        ],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    // "init" (not "init_if_needed"): each badge can only be claimed once.
+    #[account(
+        init,
+        payer = contributor,
+        space = 8 + Badge::INIT_SPACE,
+        seeds = [
+            BADGE_SEED,
+            contribution.counter.as_ref(),
+            contributor.key().as_ref(),
+            &[tier]
+        ],
+        bump
+    )]
+    pub badge: Account<'info, Badge>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
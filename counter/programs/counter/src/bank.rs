@@ -0,0 +1,96 @@
+// ============================================================================
+// COUNTER BANK - 1,024 counters in a single account
+// ============================================================================
+
+// A normal "#[account]" like Counter is stored with Borsh: every time an
+// instruction runs, Anchor copies ALL the account's bytes into a Rust struct
+// (deserialize), and copies the whole struct back when it's done (serialize).
+// For 16 bytes that's nothing. For 8 KB it's a lot of wasted compute.
+//
+// A "zero-copy" account skips all of that. The struct is laid out in memory
+// exactly like the bytes stored on-chain, so the program can read and write
+// the account's data in place. Incrementing slot 7 only touches the 8 bytes
+// of slot 7.
+use anchor_lang::prelude::*;
+
+use crate::logging::{log_slot, LogEvent};
+use crate::{BankIncremented, CounterError, Version};
+
+// Number of counters in a bank.
+pub const BANK_SLOTS: usize = 1024;
+
+// "#[account(zero_copy)]" makes this a zero-copy account. It also adds
+// "#[repr(C)]", which tells Rust to lay the fields out in memory in exactly
+// the order they're written (Rust may reorder fields otherwise).
+//
+// Zero-copy structs can only contain plain fixed-size data: integers, fixed
+// arrays, Pubkeys. No Vec or String, because those point to memory
+// elsewhere instead of storing their contents inline.
+#[account(zero_copy)]
+pub struct CounterBank {
+    // "[u64; BANK_SLOTS]" = a fixed-size array of 1,024 u64s (8,192 bytes).
+    /// BANK_SLOTS counts, each incremented on its own by increment_index.
+    pub slots: [u64; BANK_SLOTS],
+}
+
+impl CounterBank {
+    // Account size: 8-byte discriminator + the struct itself.
+    // "std::mem::size_of" asks the compiler how many bytes the struct uses,
+    // which for zero-copy structs is exactly what's stored on-chain.
+    pub const SPACE: usize = 8 + std::mem::size_of::<CounterBank>();
+
+    // Adds 1 to one slot and returns its new value.
+    pub fn increment(&mut self, index: u16) -> Result<u64> {
+        // "get_mut" returns None instead of crashing if the index is past
+        // the end of the array.
+        let slot = self
+            .slots
+            .get_mut(usize::from(index))
+            .ok_or(CounterError::IndexOutOfBounds)?;
+        *slot = slot.checked_add(1).ok_or(CounterError::Overflow)?;
+        Ok(*slot)
+    }
+}
+
+// Creates a bank with every slot at 0.
+pub fn process_initialize_bank(ctx: Context<InitializeBank>) -> Result<()> {
+    // "load_init" gives write access to a zero-copy account that was just
+    // created. The new account's bytes are already all zeros, so there's
+    // nothing to set.
+    ctx.accounts.bank.load_init()?;
+    Ok(())
+}
+
+// Adds 1 to the slot at "index".
+pub fn process_increment_index(ctx: Context<IncrementIndex>, index: u16) -> Result<()> {
+    // "load_mut" borrows the account data in place - no copying.
+    let mut bank = ctx.accounts.bank.load_mut()?;
+    let count = bank.increment(index)?;
+    log_slot(LogEvent::BankIncremented, index, count);
+    emit!(BankIncremented {
+        bank: ctx.accounts.bank.key(),
+        index,
+        count,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeBank<'info> {
+    // Zero-copy accounts use "AccountLoader" instead of "Account". It
+    // checks the owner and discriminator like Account does, but leaves the
+    // data where it is until we call load/load_mut/load_init.
+    #[account(init, payer = user, space = CounterBank::SPACE)]
+    pub bank: AccountLoader<'info, CounterBank>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementIndex<'info> {
+    // Like Increment: anyone can increment any slot.
+    #[account(mut)]
+    pub bank: AccountLoader<'info, CounterBank>,
+}
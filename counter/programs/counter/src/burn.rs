@@ -0,0 +1,137 @@
+// ============================================================================
+// BURN TO DECREMENT - spend tokens to bring a counter down
+// ============================================================================
+
+// The authority picks a mint for a counter, and anyone holding that token
+// can burn some of it to subtract the same amount from the count. Burned
+// tokens are gone for good, so the mint's supply caps how far the count
+// can ever be brought down - a quota that's used up as it's spent.
+//
+// A mirrored counter (see token_mirror.rs) can only use its own mint here,
+// so its supply keeps matching the count.
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, Mint, TokenAccount, TokenInterface};
+
+use crate::access_log::AdminAction;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{Counter, CounterDecremented, CounterError, Version, COUNTER_MINT_SEED};
+
+pub const BURN_MINT_SEED: &[u8] = b"burn_mint";
+
+/// Which mint decrement_by_burn accepts for a counter.
+#[account]
+#[derive(InitSpace)]
+pub struct BurnMint {
+    pub counter: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+// Sets (or changes) the counter's burn mint.
+pub fn process_set_burn_mint(ctx: Context<SetBurnMint>) -> Result<()> {
+    let counter = &ctx.accounts.guard.counter;
+    let mint = ctx.accounts.mint.key();
+    check_mirror_mint(counter, mint)?;
+
+    let burn_mint = &mut ctx.accounts.burn_mint;
+    burn_mint.counter = counter.key();
+    burn_mint.mint = mint;
+    burn_mint.bump = ctx.bumps.burn_mint;
+
+    ctx.accounts.guard.log(AdminAction::SetBurnMint)
+}
+
+// Burns "amount" of the caller's tokens and subtracts "amount" from the
+// counter.
+pub fn process_decrement_by_burn(ctx: Context<DecrementByBurn>, amount: u64) -> Result<()> {
+    // The counter may have been mirrored after its burn mint was set.
+    check_mirror_mint(&ctx.accounts.counter, ctx.accounts.mint.key())?;
+    let counter = &mut ctx.accounts.counter;
+    counter.begin_change()?;
+    counter.subtract(amount)?;
+    debug_assert_invariants!(counter);
+
+    // "amount" is in the mint's smallest unit, so with a mint that has
+    // decimals, one count is a fraction of a token. Use a 0-decimal mint
+    // for 1 token = 1 count.
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.holder_tokens.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    log_count(LogEvent::Decremented, counter.count);
+    emit!(CounterDecremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// A mirrored counter may only burn its own mint.
+fn check_mirror_mint(counter: &Account<Counter>, mint: Pubkey) -> Result<()> {
+    if counter.has_flag(Counter::TOKEN_MIRRORED) {
+        let (mirror_mint, _) =
+            Pubkey::find_program_address(&[COUNTER_MINT_SEED, counter.key().as_ref()], &crate::ID);
+        require_keys_eq!(mint, mirror_mint, CounterError::MirrorMintRequired);
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetBurnMint<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + BurnMint::INIT_SPACE,
+        seeds = [BURN_MINT_SEED, guard.counter.key().as_ref()],
+        bump
+    )]
+    pub burn_mint: Account<'info, BurnMint>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DecrementByBurn<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        seeds = [BURN_MINT_SEED, counter.key().as_ref()],
+        bump = burn_mint.bump,
+        has_one = mint
+    )]
+    pub burn_mint: Account<'info, BurnMint>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Tokens are burned from the holder's associated token account, the
+    // one wallets show.
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program
+    )]
+    pub holder_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
@@ -0,0 +1,116 @@
+// ============================================================================
+// CHECKSUM - noticing counters changed other than by their instructions
+// ============================================================================
+
+// A counter's bytes should only change through this program's
+// instructions. A buggy migration (see migrate.rs), a realloc that keeps
+// the wrong bytes or a build that writes the wrong layout could change them
+// some other way, and nothing would notice: the result still decodes, just
+// wrong.
+//
+// So a counter carries a checksum of its other fields: the first 8 bytes
+// of their SHA-256 hash. It stays where it was added, at CHECKSUM; fields
+// added since come after it, and are covered too. Every write of a Counter
+// goes through try_serialize below (Account's exit, sponsor.rs, migrate.rs), which
+// recomputes it, so a checksum that doesn't match means something else
+// wrote the account. verify_checksum checks one counter on chain; an
+// indexer or the keeper can check every change it sees with
+// checksum_matches. The next write recomputes it, so the time to check is
+// when the change is seen.
+//
+// SHA-256 rather than a CRC32 or xxHash: the runtime has a syscall for it,
+// which costs fewer compute units than either computed in the program, and
+// reap.rs already hashes counters with it. It catches accidents, not
+// attacks; only this program can write its accounts anyway.
+//
+// Computing it means writing out what "#[account]" would generate (see
+// Counter in lib.rs), with the checksum added in try_serialize.
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::{AccountDeserialize, AccountSerialize, Discriminator, Owner};
+
+use crate::{Counter, CounterError, ReadCounter, COUNTER_LAYOUTS};
+
+// The first of COUNTER_LAYOUTS with the checksum.
+pub const CHECKSUM_LAYOUT: usize = 13;
+
+// Where the checksum sits in a counter's data: the last 8 bytes of
+// CHECKSUM_LAYOUT.
+pub const CHECKSUM: std::ops::Range<usize> =
+    COUNTER_LAYOUTS[CHECKSUM_LAYOUT] - 8..COUNTER_LAYOUTS[CHECKSUM_LAYOUT];
+
+// The checksum of a counter's data: every field but the checksum itself.
+fn checksum(data: &[u8]) -> [u8; 8] {
+    let fields = [&data[8..CHECKSUM.start], &data[CHECKSUM.end..]];
+    hashv(&fields).to_bytes()[..8].try_into().unwrap()
+}
+
+// Whether a counter account's data, discriminator included, holds the
+// checksum of its fields. False for anything that isn't a counter in the
+// current layout (see COUNTER_LAYOUTS).
+pub fn checksum_matches(data: &[u8]) -> bool {
+    if data.len() != 8 + Counter::INIT_SPACE || !data.starts_with(&Counter::DISCRIMINATOR) {
+        return false;
+    }
+    checksum(data) == data[CHECKSUM]
+}
+
+pub fn process_verify_checksum(ctx: Context<ReadCounter>) -> Result<()> {
+    let counter = ctx.accounts.counter.to_account_info();
+    require!(
+        checksum_matches(&counter.try_borrow_data()?),
+        CounterError::ChecksumMismatch
+    );
+    Ok(())
+}
+
+impl AccountSerialize for Counter {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut data = Vec::with_capacity(8 + Counter::INIT_SPACE);
+        data.extend_from_slice(&Counter::DISCRIMINATOR);
+        if AnchorSerialize::serialize(self, &mut data).is_err() {
+            return Err(ErrorCode::AccountDidNotSerialize.into());
+        }
+        let sum = checksum(&data);
+        data[CHECKSUM].copy_from_slice(&sum);
+        if writer.write_all(&data).is_err() {
+            return Err(ErrorCode::AccountDidNotSerialize.into());
+        }
+        Ok(())
+    }
+}
+
+// Unchanged from "#[account]": loading doesn't check the checksum, so a
+// counter that fails verify_checksum still works.
+impl AccountDeserialize for Counter {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Counter::DISCRIMINATOR.len() {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+        if buf[..8] != Counter::DISCRIMINATOR {
+            return Err(
+                error!(ErrorCode::AccountDiscriminatorMismatch).with_account_name("Counter")
+            );
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl Discriminator for Counter {
+    // The first 8 bytes of sha256("account:Counter"), as "#[account]"
+    // picks them (tests/checksum.rs checks).
+    const DISCRIMINATOR: [u8; 8] = [255, 176, 4, 245, 188, 253, 124, 25];
+}
+
+impl Owner for Counter {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
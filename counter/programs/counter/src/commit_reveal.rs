@@ -0,0 +1,369 @@
+// ============================================================================
+// COMMIT-REVEAL - contributions that can't be gamed at the last second
+// ============================================================================
+
+// On a competitive counter, whoever moves last can see everyone else's
+// moves first. With commit-reveal, nobody sees anything until it's too
+// late to change their own contribution:
+//
+//   1. Commit phase (until "commit_deadline"): each participant sends
+//      commit(hash), where hash = sha256(participant, value, salt). The
+//      hash gives nothing away, but pins the value down.
+//   2. Reveal phase (until "reveal_deadline"): each participant sends
+//      reveal(value, salt). If it matches their hash, "value" is added to
+//      the count, as long as it's no more than the round's "max_value":
+//      otherwise one reveal could take the count anywhere.
+//
+// A commit comes with a deposit (set per round). Revealing refunds it.
+// Anyone who doesn't reveal in time forfeits it: after the reveal
+// deadline, anyone can call forfeit_commit, which sends it to the
+// counter's vault (see lease.rs). Without that, a participant could commit
+// several values and only reveal the one that suits them.
+//
+// set_commit_reveal (lib.rs) turns the mode on; while it's on, reveal is
+// the only way to increment. open_commit_round starts each round.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{self, Transfer};
+use nautilus_common::clock;
+
+use crate::access_log::AdminAction;
+use crate::args::check_args_version;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    AlarmTriggered, Counter, CounterError, CounterIncremented, Route, Version, VAULT_SEED,
+};
+
+pub const COMMIT_ROUND_SEED: &[u8] = b"commit_round";
+pub const COMMITMENT_SEED: &[u8] = b"commitment";
+
+/// A counter's current round. Reused by each new round.
+#[account]
+#[derive(InitSpace)]
+pub struct CommitRound {
+    pub counter: Pubkey,
+    /// 1 for the first round, then 2, 3, ... Part of each commitment's
+    /// seeds, so commitments left over from a round don't block the next.
+    pub round: u64,
+    /// Commits are accepted before this, in unix seconds.
+    pub commit_deadline: i64,
+    /// Reveals are accepted from "commit_deadline" until this, in unix
+    /// seconds.
+    pub reveal_deadline: i64,
+    /// What each commit puts down, in lamports.
+    pub deposit: u64,
+    /// The most one reveal adds to the count.
+    pub max_value: u64,
+    /// Commits and reveals this round, counts.
+    pub commits: u64,
+    pub reveals: u64,
+    pub bump: u8,
+}
+
+impl CommitRound {
+    pub fn check_commit_phase(&self, now: i64) -> Result<()> {
+        require!(now < self.commit_deadline, CounterError::WrongPhase);
+        Ok(())
+    }
+
+    pub fn check_reveal_phase(&self, now: i64) -> Result<()> {
+        require!(
+            self.commit_deadline <= now && now < self.reveal_deadline,
+            CounterError::WrongPhase
+        );
+        Ok(())
+    }
+
+    pub fn is_over(&self, now: i64) -> bool {
+        now >= self.reveal_deadline
+    }
+
+    pub fn check_value(&self, value: u64) -> Result<()> {
+        require!(value <= self.max_value, CounterError::RevealTooLarge);
+        Ok(())
+    }
+}
+
+// open_commit_round's arguments (see args.rs).
+#[derive(AnchorSerialize, Clone)]
+pub struct CommitRoundArgs {
+    pub version: u8,
+    pub commit_deadline: i64,
+    pub reveal_deadline: i64,
+    // Lamports each commit puts down, refunded on reveal.
+    pub deposit: u64,
+    // Version 2: the most one reveal adds. Version 1 rounds get 1, so each
+    // reveal counts as one increment.
+    pub max_value: u64,
+}
+
+impl CommitRoundArgs {
+    pub const VERSION: u8 = 2;
+}
+
+impl AnchorDeserialize for CommitRoundArgs {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let version = u8::deserialize_reader(reader)?;
+        let commit_deadline = i64::deserialize_reader(reader)?;
+        let reveal_deadline = i64::deserialize_reader(reader)?;
+        let deposit = u64::deserialize_reader(reader)?;
+        let max_value = if version >= 2 {
+            u64::deserialize_reader(reader)?
+        } else {
+            1
+        };
+        Ok(Self {
+            version,
+            commit_deadline,
+            reveal_deadline,
+            deposit,
+            max_value,
+        })
+    }
+}
+
+/// One participant's commit in one round. Holds the deposit until it's
+/// revealed or forfeited, and is closed either way.
+#[account]
+#[derive(InitSpace)]
+pub struct Commitment {
+    pub participant: Pubkey,
+    /// The hash of what will be revealed (see commitment_hash).
+    pub hash: [u8; 32],
+    pub bump: u8,
+}
+
+// What commit expects: sha256 of the participant, the value (8 bytes,
+// little-endian) and the salt. The participant is included so nobody can
+// copy someone else's commit and reveal it once they have.
+pub fn commitment_hash(participant: &Pubkey, value: u64, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[participant.as_ref(), &value.to_le_bytes(), salt]).to_bytes()
+}
+
+pub fn process_open_commit_round(
+    ctx: Context<OpenCommitRound>,
+    args: CommitRoundArgs,
+) -> Result<()> {
+    check_args_version(args.version, CommitRoundArgs::VERSION)?;
+    let CommitRoundArgs {
+        commit_deadline,
+        reveal_deadline,
+        deposit,
+        max_value,
+        ..
+    } = args;
+    let now = clock::now()?;
+    let counter = &ctx.accounts.guard.counter;
+    require!(
+        counter.has_flag(Counter::COMMIT_REVEAL),
+        CounterError::NotCommitReveal
+    );
+    require!(
+        now < commit_deadline && commit_deadline < reveal_deadline && max_value > 0,
+        CounterError::InvalidRound
+    );
+    let round = &mut ctx.accounts.commit_round;
+    // A fresh round account is all zeros, so it reads as long over.
+    require!(round.is_over(now), CounterError::WrongPhase);
+
+    round.counter = counter.key();
+    round.round += 1;
+    round.commit_deadline = commit_deadline;
+    round.reveal_deadline = reveal_deadline;
+    round.deposit = deposit;
+    round.max_value = max_value;
+    round.commits = 0;
+    round.reveals = 0;
+    round.bump = ctx.bumps.commit_round;
+
+    ctx.accounts.guard.log(AdminAction::OpenCommitRound)
+}
+
+pub fn process_commit(ctx: Context<Commit>, hash: [u8; 32]) -> Result<()> {
+    let round = &mut ctx.accounts.commit_round;
+    round.check_commit_phase(clock::now()?)?;
+    round.commits += 1;
+
+    let commitment = &mut ctx.accounts.commitment;
+    commitment.participant = ctx.accounts.participant.key();
+    commitment.hash = hash;
+    commitment.bump = ctx.bumps.commitment;
+
+    if round.deposit > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.participant.to_account_info(),
+                    to: commitment.to_account_info(),
+                },
+            ),
+            round.deposit,
+        )?;
+    }
+    Ok(())
+}
+
+// Adds "value" to the count if it matches the commit. Anchor closes the
+// commitment afterwards, refunding the deposit and rent.
+pub fn process_reveal(ctx: Context<Reveal>, value: u64, salt: [u8; 32]) -> Result<()> {
+    let round = &mut ctx.accounts.commit_round;
+    round.check_reveal_phase(clock::now()?)?;
+    require!(
+        commitment_hash(&ctx.accounts.participant.key(), value, &salt)
+            == ctx.accounts.commitment.hash,
+        CounterError::CommitMismatch
+    );
+    // A value over the maximum fails the reveal, so the deposit stays put
+    // and is forfeited once the round is over.
+    round.check_value(value)?;
+    round.reveals += 1;
+
+    let counter = &mut ctx.accounts.counter;
+    // The mode can have been turned off since the commit.
+    counter.check_route(Route::CommitReveal)?;
+    let before = counter.count;
+    counter.add(value)?;
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+// Once the commit's round is over, sends an unrevealed commitment's
+// lamports to the vault. Anchor does the closing.
+pub fn process_forfeit_commit(ctx: Context<ForfeitCommit>, round: u64) -> Result<()> {
+    let current = &ctx.accounts.commit_round;
+    require!(
+        round < current.round || current.is_over(clock::now()?),
+        CounterError::WrongPhase
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct OpenCommitRound<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + CommitRound::INIT_SPACE,
+        seeds = [COMMIT_ROUND_SEED, guard.counter.key().as_ref()],
+        bump
+    )]
+    pub commit_round: Account<'info, CommitRound>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Commit<'info> {
+    // Otherwise the deposit goes down on a commit that can never be
+    // revealed.
+    #[account(constraint = counter.has_flag(Counter::COMMIT_REVEAL) @ CounterError::NotCommitReveal)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        seeds = [COMMIT_ROUND_SEED, counter.key().as_ref()],
+        bump = commit_round.bump
+    )]
+    pub commit_round: Account<'info, CommitRound>,
+
+    // "init": one commit per participant per round.
+    #[account(
+        init,
+        payer = participant,
+        space = 8 + Commitment::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            counter.key().as_ref(),
+            &commit_round.round.to_le_bytes(),
+            participant.key().as_ref()
+        ],
+        bump
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Reveal<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        seeds = [COMMIT_ROUND_SEED, counter.key().as_ref()],
+        bump = commit_round.bump
+    )]
+    pub commit_round: Account<'info, CommitRound>,
+
+    #[account(
+        mut,
+        close = participant,
+        seeds = [
+            COMMITMENT_SEED,
+            counter.key().as_ref(),
+            &commit_round.round.to_le_bytes(),
+            participant.key().as_ref()
+        ],
+        bump = commitment.bump
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round: u64)]
+pub struct ForfeitCommit<'info> {
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        seeds = [COMMIT_ROUND_SEED, counter.key().as_ref()],
+        bump = commit_round.bump
+    )]
+    pub commit_round: Account<'info, CommitRound>,
+
+    // "round" picks the round the commit was made in: a commitment from an
+    // earlier round can be forfeited too, since that round is over.
+    #[account(
+        mut,
+        close = vault,
+        seeds = [
+            COMMITMENT_SEED,
+            counter.key().as_ref(),
+            &round.to_le_bytes(),
+            commitment.participant.as_ref()
+        ],
+        bump = commitment.bump
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    #[account(mut, seeds = [VAULT_SEED, counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+}
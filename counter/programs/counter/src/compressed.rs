@@ -0,0 +1,482 @@
+// ============================================================================
+// COMPRESSED COUNTERS - per-user counters that pay no rent
+// ============================================================================
+
+// Every Counter is an account, and every account holds rent: fine for a
+// few counters, a lot for millions of per-user ones. Light Protocol's "ZK
+// compression" keeps state out of accounts instead. A compressed account
+// is a leaf (its hash) in a Merkle tree that Light's programs keep
+// on-chain, and its data travels in each transaction that uses it: the
+// client fetches it, with a "validity proof" (a Groth16 proof that its
+// hash is in the tree), from a Light RPC node. The Light system program
+// checks the proof, spends the old leaf and appends the new one.
+//
+//   - create_compressed_counter: a CompressedCounter at 0, at an address
+//     derived from its authority and an id, the way a PDA's is
+//   - increment_compressed_counter: spends the counter's current state and
+//     writes it back with count + 1
+//
+// A compressed counter is only an authority and a count: the modes, fees
+// and history of the account-based Counter don't apply to it. It's the
+// cheap path for counters that only count.
+//
+// As with Wormhole (see wormhole.rs), we talk to the Light system program
+// directly, building its invoke_cpi instruction by hand instead of through
+// an SDK crate. The layouts below are light-system-program 1.x's and must
+// not change.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    program::invoke_signed,
+};
+
+use crate::program::Counter as CounterProgram;
+use crate::{CompressedCounterIncremented, CounterError, Version};
+
+// Light's programs have the same addresses on every cluster, the local
+// "light test-validator" included, so unlike Wormhole's they aren't picked
+// by feature.
+pub const LIGHT_SYSTEM_PROGRAM_ID: Pubkey = pubkey!("SySTEM1eSU2p4BGQfQpimFEWWSC1XDFeun3Nqzz3rT7");
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey =
+    pubkey!("compr6CUsB5m2jS4Y3831ztGSTnDpnKJTKS95d64XVq");
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+// The seed of a program's "CPI authority": the PDA the Light system program
+// expects to sign for the compressed accounts that program owns. The Light
+// system program has one of its own, under the same seed, which signs for
+// it towards the account compression program.
+pub const LIGHT_CPI_AUTHORITY_SEED: &[u8] = b"cpi_authority";
+pub const COMPRESSED_COUNTER_SEED: &[u8] = b"compressed_counter";
+
+// Anchor's discriminator for the Light system program's invoke_cpi:
+// sha256("global:invoke_cpi"), first 8 bytes.
+const INVOKE_CPI_DISCRIMINATOR: [u8; 8] = [49, 212, 191, 129, 39, 194, 43, 196];
+
+// ----------------------------------------------------------------------------
+// The counter
+// ----------------------------------------------------------------------------
+
+/// A counter kept as Light compressed state (see compressed.rs). Clients
+/// pass it in, with a proof that it's the current state.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressedCounter {
+    /// Created it. Its address is derived from this and an id.
+    pub authority: Pubkey,
+    pub count: u64,
+}
+
+impl CompressedCounter {
+    // sha256("account:CompressedCounter"), first 8 bytes, as Anchor would
+    // pick for an account: Light's indexers use it to tell our compressed
+    // accounts apart.
+    pub const DISCRIMINATOR: [u8; 8] = [195, 63, 73, 191, 132, 87, 106, 237];
+
+    // What the address is derived from, like a PDA's seeds: our program,
+    // then ours for the counter. Light turns it into the address with the
+    // tree's key (see derive_address).
+    pub fn address_seed(authority: &Pubkey, id: u64) -> [u8; 32] {
+        hash_to_field(&[
+            crate::ID.as_ref(),
+            COMPRESSED_COUNTER_SEED,
+            authority.as_ref(),
+            &id.to_le_bytes(),
+        ])
+    }
+
+    // The hash that stands for the data in the counter's leaf. Light takes
+    // whatever the owning program computes, so this is ours to pick.
+    pub fn data_hash(&self) -> Result<[u8; 32]> {
+        Ok(hash_to_field(&[&self.try_to_vec()?]))
+    }
+
+    fn compressed_account(&self, address: [u8; 32]) -> Result<CompressedAccount> {
+        Ok(CompressedAccount {
+            owner: crate::ID,
+            lamports: 0,
+            address: Some(address),
+            data: Some(CompressedAccountData {
+                discriminator: CompressedCounter::DISCRIMINATOR,
+                data: self.try_to_vec()?,
+                data_hash: self.data_hash()?,
+            }),
+        })
+    }
+}
+
+// Light's trees hash with Poseidon over the BN254 curve's scalar field, so
+// every 32-byte value that goes into one has to be smaller than the field's
+// modulus. Zeroing the top byte of a keccak hash guarantees that; it's what
+// Light's own hashv_to_bn254_field_size_be does.
+fn hash_to_field(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hash = keccak::hashv(parts).to_bytes();
+    hash[0] = 0;
+    hash
+}
+
+// The address the Light system program gives a new compressed account with
+// this seed in this address tree: hash_to_bn254_field_size_be(tree, seed)
+// in Light's terms, which appends a bump of 255 (always accepted with the
+// top byte zeroed). It checks our output's address against it.
+pub fn derive_address(address_tree: &Pubkey, seed: &[u8; 32]) -> [u8; 32] {
+    hash_to_field(&[address_tree.as_ref(), seed, &[u8::MAX]])
+}
+
+// ----------------------------------------------------------------------------
+// Light's types
+// ----------------------------------------------------------------------------
+
+// Where Light's instructions take trees and queues, they take the index of
+// the account in "remaining_accounts" instead of its address: each appears
+// once, however many compressed accounts use it.
+
+/// A Groth16 proof from a Light RPC node: the compressed accounts being
+/// spent are in their state trees, and the addresses being created aren't
+/// in their address trees yet.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressedProof {
+    pub a: [u8; 32],
+    pub b: [u8; 64],
+    pub c: [u8; 32],
+}
+
+/// The address tree a new compressed counter's address goes into.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedAddressTreeInfo {
+    /// The tree's index in the remaining accounts.
+    pub address_merkle_tree_account_index: u8,
+    /// Its queue's index in the remaining accounts.
+    pub address_queue_account_index: u8,
+    /// Which of the tree's recent roots the proof is against, as the RPC
+    /// node reports it.
+    pub root_index: u16,
+}
+
+/// A leaf that's still in its tree's queue, not yet in the tree.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueIndex {
+    pub queue_id: u8,
+    pub index: u16,
+}
+
+/// Where a compressed account's current state is.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedMerkleContext {
+    /// The state tree's index in the remaining accounts.
+    pub merkle_tree_pubkey_index: u8,
+    /// Its nullifier queue's index in the remaining accounts.
+    pub nullifier_queue_pubkey_index: u8,
+    pub leaf_index: u32,
+    pub queue_index: Option<QueueIndex>,
+}
+
+/// What increment_compressed_counter needs to find a counter and write it
+/// back, as a Light RPC node reports it with the counter.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressedCounterMeta {
+    pub address: [u8; 32],
+    pub merkle_context: PackedMerkleContext,
+    /// Which of the state tree's recent roots the proof is against.
+    pub root_index: u16,
+    /// The state tree the new state goes into, by its index in the
+    /// remaining accounts.
+    pub output_tree_index: u8,
+}
+
+// The rest only goes into invoke_cpi's data, in Light's field order.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CompressedAccountData {
+    pub discriminator: [u8; 8],
+    pub data: Vec<u8>,
+    pub data_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CompressedAccount {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub address: Option<[u8; 32]>,
+    pub data: Option<CompressedAccountData>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NewAddressParamsPacked {
+    pub seed: [u8; 32],
+    pub address_queue_account_index: u8,
+    pub address_merkle_tree_account_index: u8,
+    pub address_merkle_tree_root_index: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PackedCompressedAccountWithMerkleContext {
+    pub compressed_account: CompressedAccount,
+    pub merkle_context: PackedMerkleContext,
+    pub root_index: u16,
+    // Unused by Light 1.x; always false.
+    pub read_only: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutputCompressedAccountWithPackedContext {
+    pub compressed_account: CompressedAccount,
+    pub merkle_tree_index: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressedCpiContext {
+    pub set_context: bool,
+    pub first_set_context: bool,
+    pub cpi_context_account_index: u8,
+}
+
+// invoke_cpi's one argument (Light's InstructionDataInvokeCpi), which it
+// takes Borsh-encoded, as bytes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LightInvokeCpi {
+    pub proof: Option<CompressedProof>,
+    pub new_address_params: Vec<NewAddressParamsPacked>,
+    pub input_compressed_accounts_with_merkle_context:
+        Vec<PackedCompressedAccountWithMerkleContext>,
+    pub output_compressed_accounts: Vec<OutputCompressedAccountWithPackedContext>,
+    pub relay_fee: Option<u64>,
+    pub compress_or_decompress_lamports: Option<u64>,
+    pub is_compress: bool,
+    pub cpi_context: Option<CompressedCpiContext>,
+}
+
+impl LightInvokeCpi {
+    // No lamports move and nothing is batched with other programs' CPIs,
+    // so only the proof and the accounts are ever set.
+    fn new(proof: CompressedProof) -> Self {
+        LightInvokeCpi {
+            proof: Some(proof),
+            new_address_params: Vec::new(),
+            input_compressed_accounts_with_merkle_context: Vec::new(),
+            output_compressed_accounts: Vec::new(),
+            relay_fee: None,
+            compress_or_decompress_lamports: None,
+            is_compress: false,
+            cpi_context: None,
+        }
+    }
+
+    // invoke_cpi's instruction data: its discriminator, then these bytes
+    // as a Vec<u8> (length first).
+    pub fn data(&self) -> Result<Vec<u8>> {
+        let inputs = self.try_to_vec()?;
+        let mut data = INVOKE_CPI_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&(inputs.len() as u32).to_le_bytes());
+        data.extend_from_slice(&inputs);
+        Ok(data)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Instructions
+// ----------------------------------------------------------------------------
+
+// Creates a counter at 0, owned by "payer". "id" tells apart the counters
+// one authority creates; the address tree refuses an address twice, so
+// each (authority, id) only ever has one.
+pub fn process_create_compressed_counter<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateCompressedCounter<'info>>,
+    proof: CompressedProof,
+    address_tree: PackedAddressTreeInfo,
+    output_tree_index: u8,
+    id: u64,
+) -> Result<()> {
+    let light = &ctx.accounts.light;
+    let tree = ctx
+        .remaining_accounts
+        .get(address_tree.address_merkle_tree_account_index as usize)
+        .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+    let counter = CompressedCounter {
+        authority: light.payer.key(),
+        count: 0,
+    };
+    let seed = CompressedCounter::address_seed(&counter.authority, id);
+    let address = derive_address(tree.key, &seed);
+
+    let mut inputs = LightInvokeCpi::new(proof);
+    inputs.new_address_params.push(NewAddressParamsPacked {
+        seed,
+        address_queue_account_index: address_tree.address_queue_account_index,
+        address_merkle_tree_account_index: address_tree.address_merkle_tree_account_index,
+        address_merkle_tree_root_index: address_tree.root_index,
+    });
+    inputs
+        .output_compressed_accounts
+        .push(OutputCompressedAccountWithPackedContext {
+            compressed_account: counter.compressed_account(address)?,
+            merkle_tree_index: output_tree_index,
+        });
+    light.invoke(
+        &inputs,
+        ctx.remaining_accounts,
+        ctx.bumps.light.cpi_authority,
+    )
+}
+
+// Adds 1. Anyone can, as with increment. "counter" is its current state:
+// if it isn't, its hash isn't the leaf the proof is for, and the Light
+// system program fails the call.
+pub fn process_increment_compressed_counter<'info>(
+    ctx: Context<'_, '_, 'info, 'info, IncrementCompressedCounter<'info>>,
+    proof: CompressedProof,
+    counter: CompressedCounter,
+    meta: CompressedCounterMeta,
+) -> Result<()> {
+    let incremented = CompressedCounter {
+        count: counter.count.checked_add(1).ok_or(CounterError::Overflow)?,
+        ..counter
+    };
+
+    let mut inputs = LightInvokeCpi::new(proof);
+    inputs.input_compressed_accounts_with_merkle_context.push(
+        PackedCompressedAccountWithMerkleContext {
+            compressed_account: counter.compressed_account(meta.address)?,
+            merkle_context: meta.merkle_context,
+            root_index: meta.root_index,
+            read_only: false,
+        },
+    );
+    inputs
+        .output_compressed_accounts
+        .push(OutputCompressedAccountWithPackedContext {
+            compressed_account: incremented.compressed_account(meta.address)?,
+            merkle_tree_index: meta.output_tree_index,
+        });
+    let light = &ctx.accounts.light;
+    light.invoke(
+        &inputs,
+        ctx.remaining_accounts,
+        ctx.bumps.light.cpi_authority,
+    )?;
+
+    emit!(CompressedCounterIncremented {
+        address: meta.address,
+        authority: incremented.authority,
+        count: incremented.count,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+/// The accounts the Light system program's invoke_cpi takes, checked as
+/// far as we can; it checks them again itself. The trees and queues go in
+/// "remaining_accounts".
+#[derive(Accounts)]
+pub struct LightSystem<'info> {
+    // Pays for the new leaves and addresses.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(seeds = [LIGHT_CPI_AUTHORITY_SEED], bump)]
+    /// CHECK: our CPI authority PDA. It holds no data; it only signs.
+    pub cpi_authority: UncheckedAccount<'info>,
+
+    // "seeds::program" derives these from Light's program ids instead of
+    // ours, as in PostCount (see wormhole.rs).
+    #[account(
+        seeds = [LIGHT_SYSTEM_PROGRAM_ID.as_ref()],
+        bump,
+        seeds::program = ACCOUNT_COMPRESSION_PROGRAM_ID
+    )]
+    /// CHECK: lets the Light system program write to the trees, checked by
+    /// the account compression program.
+    pub registered_program_pda: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [LIGHT_CPI_AUTHORITY_SEED],
+        bump,
+        seeds::program = LIGHT_SYSTEM_PROGRAM_ID
+    )]
+    /// CHECK: the Light system program's CPI authority, checked by the
+    /// account compression program.
+    pub account_compression_authority: UncheckedAccount<'info>,
+
+    #[account(address = LIGHT_SYSTEM_PROGRAM_ID)]
+    /// CHECK: the Light system program, checked by address.
+    pub light_system_program: UncheckedAccount<'info>,
+
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    /// CHECK: Light's account compression program, checked by address.
+    pub account_compression_program: UncheckedAccount<'info>,
+
+    #[account(address = NOOP_PROGRAM_ID)]
+    /// CHECK: the program Light logs new leaves through for indexers,
+    /// checked by address.
+    pub noop_program: UncheckedAccount<'info>,
+
+    // Light checks that the compressed accounts' owner is the program that
+    // called it.
+    pub program: Program<'info, CounterProgram>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> LightSystem<'info> {
+    // Calls invoke_cpi with "inputs", signing as our CPI authority.
+    fn invoke(
+        &self,
+        inputs: &LightInvokeCpi,
+        trees: &[AccountInfo<'info>],
+        cpi_authority_bump: u8,
+    ) -> Result<()> {
+        // invoke_cpi's accounts, in its order. Its optional accounts
+        // (sol_pool_pda, decompression_recipient, cpi_context_account) are
+        // all unused; Anchor takes the called program's own address as
+        // "None".
+        let none = AccountMeta::new_readonly(LIGHT_SYSTEM_PROGRAM_ID, false);
+        let mut accounts = vec![
+            AccountMeta::new(self.payer.key(), true),
+            AccountMeta::new_readonly(self.cpi_authority.key(), true),
+            AccountMeta::new_readonly(self.registered_program_pda.key(), false),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+            AccountMeta::new_readonly(self.account_compression_authority.key(), false),
+            AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(crate::ID, false),
+            none.clone(),
+            none.clone(),
+            AccountMeta::new_readonly(self.system_program.key(), false),
+            none,
+        ];
+        accounts.extend(trees.iter().map(|tree| AccountMeta::new(*tree.key, false)));
+        let instruction = Instruction {
+            program_id: LIGHT_SYSTEM_PROGRAM_ID,
+            accounts,
+            data: inputs.data()?,
+        };
+
+        let mut infos = vec![
+            self.payer.to_account_info(),
+            self.cpi_authority.to_account_info(),
+            self.registered_program_pda.to_account_info(),
+            self.noop_program.to_account_info(),
+            self.account_compression_authority.to_account_info(),
+            self.account_compression_program.to_account_info(),
+            self.program.to_account_info(),
+            self.light_system_program.to_account_info(),
+            self.system_program.to_account_info(),
+        ];
+        infos.extend_from_slice(trees);
+        invoke_signed(
+            &instruction,
+            &infos,
+            &[&[LIGHT_CPI_AUTHORITY_SEED, &[cpi_authority_bump]]],
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateCompressedCounter<'info> {
+    // Its payer becomes the counter's authority.
+    pub light: LightSystem<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementCompressedCounter<'info> {
+    pub light: LightSystem<'info>,
+}
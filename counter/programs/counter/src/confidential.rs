@@ -0,0 +1,250 @@
+// ============================================================================
+// CONFIDENTIAL COUNTER - a count nobody but the authority can read
+// ============================================================================
+
+// Everything stored on Solana is public. A ConfidentialCounter stores its
+// count encrypted instead, so it can hold a private tally (sealed votes,
+// say) on a public chain.
+//
+// It uses "twisted ElGamal" encryption, the same scheme SPL Token-2022 uses
+// for confidential balances. What makes it work here is that it's
+// "additively homomorphic": adding two ciphertexts gives a ciphertext of
+// the sum of their values. So:
+//
+//   - the authority picks an ElGamal keypair and stores the public key in
+//     the counter
+//   - whoever increments encrypts their delta with that public key and
+//     sends the ciphertext
+//   - the program adds it to the stored ciphertext without ever seeing
+//     either value
+//   - only the authority, with the secret key, can decrypt the total
+//     (counter-client's confidential module does this)
+//
+// The program can't see deltas, so it can't check them either: a delta
+// could be an encryption of 1,000. Use this where whoever sends deltas is
+// trusted for their value (e.g. a vote-collecting backend), or attach a
+// proof of the delta's range alongside.
+//
+// The authority can also prove the count is above a threshold without
+// revealing it (see prove_threshold at the bottom of this file).
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+use solana_zk_token_sdk::{
+    instruction::{CiphertextCommitmentEqualityProofContext, RangeProofContext, ZkProofData},
+    zk_token_elgamal::{ops, pod},
+    zk_token_proof_program,
+    zk_token_proof_state::ProofContextState,
+};
+
+use crate::{ConfidentialIncremented, CounterError, ThresholdProven, Version};
+
+pub const THRESHOLD_ATTESTATION_SEED: &[u8] = b"threshold";
+
+/// A counter whose count only its authority can read.
+#[account]
+#[derive(InitSpace)]
+pub struct ConfidentialCounter {
+    pub authority: Pubkey,
+    /// The authority's ElGamal public key. Deltas must be encrypted with it.
+    pub elgamal_pubkey: [u8; 32],
+    /// The encrypted count: a 64-byte ElGamal ciphertext.
+    pub encrypted_count: [u8; 64],
+    /// How many increments there have been. This one is public, like the
+    /// transactions themselves.
+    pub increments: u64,
+}
+
+impl ConfidentialCounter {
+    // Adds an encrypted delta to the encrypted count.
+    pub fn add_encrypted(&mut self, delta: [u8; 64]) -> Result<()> {
+        let sum = ops::add(
+            &pod::ElGamalCiphertext(self.encrypted_count),
+            &pod::ElGamalCiphertext(delta),
+        )
+        .ok_or(CounterError::InvalidCiphertext)?;
+        self.encrypted_count = sum.0;
+        self.increments = self
+            .increments
+            .checked_add(1)
+            .ok_or(CounterError::Overflow)?;
+        Ok(())
+    }
+}
+
+// All zeros is a valid encryption of 0 (with no randomness), so a new
+// counter starts at 0 without the program doing any encryption.
+pub fn process_initialize_confidential(
+    ctx: Context<InitializeConfidential>,
+    elgamal_pubkey: [u8; 32],
+) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.authority = ctx.accounts.user.key();
+    counter.elgamal_pubkey = elgamal_pubkey;
+    Ok(())
+}
+
+pub fn process_increment_confidential(
+    ctx: Context<IncrementConfidential>,
+    encrypted_delta: [u8; 64],
+) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.add_encrypted(encrypted_delta)?;
+    emit!(ConfidentialIncremented {
+        counter: counter.key(),
+        encrypted_count: counter.encrypted_count,
+        increments: counter.increments,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfidential<'info> {
+    // Created from a new keypair, like Counter in Initialize.
+    #[account(init, payer = user, space = 8 + ConfidentialCounter::INIT_SPACE)]
+    pub counter: Account<'info, ConfidentialCounter>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementConfidential<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, ConfidentialCounter>,
+}
+
+// ----------------------------------------------------------------------------
+// Threshold proofs: "count >= min" without revealing count
+// ----------------------------------------------------------------------------
+
+// The authority can prove the encrypted count is at least "min" without
+// revealing it, using zero-knowledge proofs checked by Solana's ZK Token
+// Proof program:
+//
+//   1. Subtracting "min" from the encrypted count gives an encryption of
+//      (count - min). The program can do that subtraction itself.
+//   2. An "equality proof" shows that ciphertext holds the same value as a
+//      fresh Pedersen commitment. Only the secret key holder can make it.
+//   3. A "range proof" shows the commitment's value is between 0 and
+//      2^64. If count were below min, (count - min) would wrap around to
+//      a huge number outside that range, so no such proof could exist.
+//
+// The proofs are too big to fit in one transaction with this instruction,
+// so the client first has the proof program verify each one into a
+// "context state" account (which records what was proven), then passes
+// those accounts here. counter-client's confidential module builds them.
+//
+// A successful check writes a ThresholdAttestation, which other programs
+// can read instead of repeating any of this.
+/// Proof, checked by prove_threshold, that a ConfidentialCounter's count
+/// was at least "min".
+#[account]
+#[derive(InitSpace)]
+pub struct ThresholdAttestation {
+    pub counter: Pubkey,
+    /// The count proven reached, not the count itself.
+    pub min: u64,
+    /// The counter's "increments" when the proof was made. The count may
+    /// have changed since; compare with the counter's current value to
+    /// know if the attestation is still current.
+    pub increments: u64,
+    /// The slot the proof was checked in.
+    pub slot: u64,
+    pub bump: u8,
+}
+
+// Checks that the two proofs together show the counter's count >= min.
+pub fn check_threshold_proofs(
+    counter: &ConfidentialCounter,
+    min: u64,
+    equality: &CiphertextCommitmentEqualityProofContext,
+    range: &RangeProofContext,
+) -> Result<()> {
+    let remainder = ops::subtract_from(&pod::ElGamalCiphertext(counter.encrypted_count), min)
+        .ok_or(CounterError::InvalidCiphertext)?;
+    require!(
+        equality.pubkey.0 == counter.elgamal_pubkey
+            && equality.ciphertext.0 == remainder.0
+            && equality.commitment.0 == range.commitment.0,
+        CounterError::InvalidThresholdProof
+    );
+    Ok(())
+}
+
+// Reads what a context state account says was proven. "T" is the proof's
+// data type; it tells us which kind of proof to expect.
+fn read_proof_context<T, U>(account: &UncheckedAccount) -> Result<U>
+where
+    T: ZkProofData<U>,
+    U: bytemuck::Pod,
+{
+    let data = account.try_borrow_data()?;
+    let state = ProofContextState::<U>::try_from_bytes(&data)
+        .map_err(|_| CounterError::InvalidThresholdProof)?;
+    require!(
+        state.proof_type == pod::PodProofType::from(T::PROOF_TYPE),
+        CounterError::InvalidThresholdProof
+    );
+    Ok(state.proof_context)
+}
+
+pub fn process_prove_threshold(ctx: Context<ProveThreshold>, min: u64) -> Result<()> {
+    use solana_zk_token_sdk::instruction::{
+        CiphertextCommitmentEqualityProofData, RangeProofU64Data,
+    };
+
+    let equality = read_proof_context::<CiphertextCommitmentEqualityProofData, _>(
+        &ctx.accounts.equality_proof,
+    )?;
+    let range = read_proof_context::<RangeProofU64Data, _>(&ctx.accounts.range_proof)?;
+    let counter = &ctx.accounts.counter;
+    check_threshold_proofs(counter, min, &equality, &range)?;
+
+    let attestation = &mut ctx.accounts.attestation;
+    attestation.counter = counter.key();
+    attestation.min = min;
+    attestation.increments = counter.increments;
+    attestation.slot = clock::slot()?;
+    attestation.bump = ctx.bumps.attestation;
+
+    emit!(ThresholdProven {
+        counter: counter.key(),
+        min,
+        increments: counter.increments,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(min: u64)]
+pub struct ProveThreshold<'info> {
+    #[account(has_one = authority @ CounterError::Unauthorized)]
+    pub counter: Account<'info, ConfidentialCounter>,
+
+    // Only the proof program can write context state accounts, and only
+    // after verifying a proof, so the owner check is what makes them
+    // trustworthy.
+    #[account(owner = zk_token_proof_program::ID)]
+    /// CHECK: parsed by read_proof_context.
+    pub equality_proof: UncheckedAccount<'info>,
+    #[account(owner = zk_token_proof_program::ID)]
+    /// CHECK: parsed by read_proof_context.
+    pub range_proof: UncheckedAccount<'info>,
+
+    // One attestation per (counter, min); proving again refreshes it.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ThresholdAttestation::INIT_SPACE,
+        seeds = [THRESHOLD_ATTESTATION_SEED, counter.key().as_ref(), min.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, ThresholdAttestation>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
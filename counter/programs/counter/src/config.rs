@@ -0,0 +1,64 @@
+// ============================================================================
+// CONFIG - program-wide settings, owned by the deployer
+// ============================================================================
+
+// Counters each have their own authority, but some settings belong to the
+// program as a whole. They live in one Config PDA, whose "admin" manages
+// them.
+//
+// Who gets to create the Config matters: whoever does picks the admin. A
+// field like "admin" can't vouch for itself, so initialize_config checks
+// the signer against the program's upgrade authority instead, read from
+// the BPF loader's ProgramData account, the same way set_program_info does
+// (see program_info.rs). Only the deployer can call it, and only once.
+use anchor_lang::prelude::*;
+
+use crate::program::Counter as CounterProgram;
+use crate::CounterError;
+
+pub use nautilus_common::seeds::CONFIG_SEED;
+
+/// The program-wide settings.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    /// Manages the program-wide settings. Chosen by the deployer, so it can
+    /// be a multisig rather than the deploy key itself.
+    pub admin: Pubkey,
+    pub bump: u8,
+}
+
+pub fn process_initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = admin;
+    config.bump = ctx.bumps.config;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    // "init", not "init_if_needed": the admin is picked once.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // Same checks as in SetProgramInfo: "program_data" is really ours, and
+    // "authority" is its upgrade authority.
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CounterProgram>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CounterError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,172 @@
+// ============================================================================
+// CPI GUARD - control which callers a counter counts
+// ============================================================================
+
+// Increments are open to everyone, including other programs calling in
+// through CPI. For a counter meant to measure human engagement that's a
+// problem: an aggregator program can increment it many times per
+// transaction, or on behalf of users who never asked it to.
+//
+// With "deny_cpi" on (set_deny_cpi), a counter only accepts increments
+// that are instructions of the transaction itself, which a wallet signed.
+//
+// The runtime tracks a "stack height" for every instruction: 1 for the
+// transaction's own instructions, 2 for a CPI they make, and so on. The
+// check reads it with a syscall, so it needs no extra accounts.
+//
+// Partners can still be let in one by one. allow_program puts a program
+// on the counter's allowlist (an AllowedCaller PDA per program), and
+// increment_from_program accepts CPIs from exactly those programs, even
+// with deny_cpi on. Which program is calling isn't passed to us, so it's
+// worked out from the Instructions sysvar: at stack height 2 the caller
+// is the transaction instruction currently running. Deeper CPIs are
+// refused, since the program in between can't be identified.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+};
+
+use crate::access_log::AdminAction;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{AlarmTriggered, Counter, CounterError, CounterIncremented, Version};
+
+pub const ALLOWED_CALLER_SEED: &[u8] = b"allowed_caller";
+
+/// A program allowed to call increment_from_program on a counter.
+#[account]
+#[derive(InitSpace)]
+pub struct AllowedCaller {
+    pub counter: Pubkey,
+    pub program: Pubkey,
+    pub bump: u8,
+}
+
+// Fails if the current instruction was called through CPI.
+pub fn check_top_level() -> Result<()> {
+    require!(
+        get_stack_height() <= TRANSACTION_LEVEL_STACK_HEIGHT,
+        CounterError::CpiDenied
+    );
+    Ok(())
+}
+
+// The program that called us, if it's a transaction instruction calling
+// us directly.
+fn direct_caller(instructions: &AccountInfo) -> Result<Pubkey> {
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+        CounterError::CallerNotAllowed
+    );
+    let index = load_current_index_checked(instructions)?;
+    Ok(load_instruction_at_checked(usize::from(index), instructions)?.program_id)
+}
+
+pub fn process_allow_program(ctx: Context<AllowProgram>, program: Pubkey) -> Result<()> {
+    let allowed = &mut ctx.accounts.allowed_caller;
+    allowed.counter = ctx.accounts.guard.counter.key();
+    allowed.program = program;
+    allowed.bump = ctx.bumps.allowed_caller;
+
+    ctx.accounts.guard.log(AdminAction::AllowProgram)
+}
+
+// Closes the AllowedCaller, refunding its rent to the authority.
+pub fn process_disallow_program(ctx: Context<DisallowProgram>) -> Result<()> {
+    let guard = &ctx.accounts.guard;
+    guard.close_to_owner(&ctx.accounts.allowed_caller)?;
+    guard.log(AdminAction::DisallowProgram)
+}
+
+// Adds 1 to the counter, for an allowlisted program calling through CPI.
+pub fn process_increment_from_program(ctx: Context<IncrementFromProgram>) -> Result<()> {
+    require_keys_eq!(
+        direct_caller(&ctx.accounts.instructions)?,
+        ctx.accounts.allowed_caller.program,
+        CounterError::CallerNotAllowed
+    );
+
+    let counter = &mut ctx.accounts.counter;
+    counter.check_unrouted()?;
+    counter.check_pace()?;
+    require!(!counter.is_capped(), CounterError::SoftCapReached);
+    let before = counter.count;
+    counter.increment()?;
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct AllowProgram<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init,
+        payer = guard.authority,
+        space = 8 + AllowedCaller::INIT_SPACE,
+        seeds = [ALLOWED_CALLER_SEED, guard.counter.key().as_ref(), program.as_ref()],
+        bump
+    )]
+    pub allowed_caller: Account<'info, AllowedCaller>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisallowProgram<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    // Closed by the handler (see guards.rs).
+    #[account(
+        mut,
+        seeds = [
+            ALLOWED_CALLER_SEED,
+            guard.counter.key().as_ref(),
+            allowed_caller.program.as_ref()
+        ],
+        bump = allowed_caller.bump
+    )]
+    pub allowed_caller: Account<'info, AllowedCaller>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementFromProgram<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // The calling program's entry. Its address proves it's on this
+    // counter's allowlist; the handler checks it's really the caller.
+    #[account(
+        seeds = [
+            ALLOWED_CALLER_SEED,
+            counter.key().as_ref(),
+            allowed_caller.program.as_ref()
+        ],
+        bump = allowed_caller.bump
+    )]
+    pub allowed_caller: Account<'info, AllowedCaller>,
+
+    #[account(address = instructions_sysvar::ID)]
+    /// CHECK: the Instructions sysvar, checked by address.
+    pub instructions: UncheckedAccount<'info>,
+}
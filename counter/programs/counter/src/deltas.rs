@@ -0,0 +1,120 @@
+// ============================================================================
+// DELTAS - recent changes to the count, kept in the counter itself
+// ============================================================================
+
+// A dashboard that polls a counter every few seconds sees the count jump
+// from 40 to 43, but not whether that was three increments, or +5 and -2,
+// or when. The transaction logs say, but reading them takes an RPC node
+// that keeps them and a lot more requests. With set_delta_feed on, the
+// counter keeps its last RECENT_DELTAS changes instead, each with the
+// slot it happened in, in the account the poller reads anyway.
+//
+// Changes in the same slot are added together into one entry, so a busy
+// slot takes one entry, not many. "recorded" counts entries ever written:
+// a poller remembers it, and next time takes the entries written since
+// (see DeltaFeed::since). If more than RECENT_DELTAS were written in
+// between, the oldest of them are gone, and "since" says how many.
+//
+// Entries are small, to keep the account small: the slot is an offset
+// from "base_slot", the slot the feed was turned on in, and the change is
+// an i32. A bigger change (a reset of a large count, say) is recorded as
+// the largest i32 of its sign; the count itself is always exact.
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::access_log::AdminAction;
+use crate::UpdateCounter;
+
+pub const RECENT_DELTAS: usize = 16;
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default, Debug, PartialEq, Eq,
+)]
+pub struct DeltaEntry {
+    /// Slots after the feed's base_slot.
+    pub slot_offset: u32,
+    /// The change to the count in that slot.
+    pub delta: i32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct DeltaFeed {
+    /// The slot the feed was turned on in. 0 while it's off.
+    pub base_slot: u64,
+    /// The slot of the latest change, or the one being made (see
+    /// Counter::begin_change).
+    pub slot: u64,
+    /// Entries written ever. The next one goes in
+    /// recent_deltas[recorded % RECENT_DELTAS].
+    pub recorded: u64,
+    pub recent_deltas: [DeltaEntry; RECENT_DELTAS],
+}
+
+// What a poller gets from DeltaFeed::since.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeltasSince {
+    // (slot, change), oldest first.
+    pub deltas: Vec<(u64, i32)>,
+    // Entries written since but already overwritten.
+    pub missed: u64,
+}
+
+impl DeltaFeed {
+    pub fn is_on(&self) -> bool {
+        self.base_slot != 0
+    }
+
+    // Records a change of "delta" to the count, in "slot".
+    pub fn record(&mut self, delta: i128) {
+        if !self.is_on() || delta == 0 {
+            return;
+        }
+        let delta = delta.clamp(i32::MIN.into(), i32::MAX.into()) as i32;
+        let slot_offset = u32::try_from(self.slot - self.base_slot).unwrap_or(u32::MAX);
+        if self.recorded > 0 {
+            let newest = &mut self.recent_deltas[(self.recorded as usize - 1) % RECENT_DELTAS];
+            if newest.slot_offset == slot_offset {
+                newest.delta = newest.delta.saturating_add(delta);
+                return;
+            }
+        }
+        self.recent_deltas[self.recorded as usize % RECENT_DELTAS] =
+            DeltaEntry { slot_offset, delta };
+        self.recorded += 1;
+    }
+
+    // The entries written after the first "recorded" ones, as a poller
+    // that last saw "recorded" would want them.
+    //
+    // The newest entry can still grow while its slot lasts, so a poller
+    // that saw it then will see it again, bigger, if it was added to. Only
+    // polling once the slot is over avoids that.
+    pub fn since(&self, recorded: u64) -> DeltasSince {
+        let first = recorded.max(self.recorded.saturating_sub(RECENT_DELTAS as u64));
+        let deltas = (first..self.recorded)
+            .map(|i| self.recent_deltas[i as usize % RECENT_DELTAS])
+            .map(|entry| (self.base_slot + u64::from(entry.slot_offset), entry.delta))
+            .collect();
+        DeltasSince {
+            deltas,
+            missed: first.saturating_sub(recorded),
+        }
+    }
+}
+
+// Turns the feed on, starting empty at the current slot, or off, which
+// clears it.
+pub fn process_set_delta_feed(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+    ctx.accounts.log(AdminAction::SetDeltaFeed)?;
+    let feed = &mut ctx.accounts.counter.delta_feed;
+    if enabled == feed.is_on() {
+        return Ok(());
+    }
+    *feed = DeltaFeed::default();
+    if enabled {
+        let slot = clock::slot()?;
+        feed.base_slot = slot;
+        feed.slot = slot;
+    }
+    Ok(())
+}
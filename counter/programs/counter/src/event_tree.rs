@@ -0,0 +1,263 @@
+// ============================================================================
+// EVENT TREE - a verifiable history of every increment
+// ============================================================================
+
+// Transaction logs (and so emit!) are only kept by RPC nodes for a while,
+// and there's no way to prove a log line wasn't made up. Storing every
+// increment in an account would cost rent for each one.
+//
+// "State compression" sits in between. Each increment becomes a "leaf" in
+// a Merkle tree held by the SPL Account Compression program: the tree
+// account only stores a hash summarizing all the leaves (its "root") plus
+// a few recent changes, so it stays the same size however many leaves are
+// added. The leaf's full contents go into the transaction through the SPL
+// Noop program, where indexers (and DAS APIs) pick them up to rebuild the
+// tree - and anyone can check a rebuilt leaf against the on-chain root.
+//
+// Counters opt in with set_tree_logged. From then on their increments must
+// go through increment_logged, so the tree really has all of them.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    program::{invoke, invoke_signed},
+};
+use nautilus_common::clock;
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::program::Counter as CounterProgram;
+use crate::{AlarmTriggered, Counter, CounterError, CounterIncremented, Route, Version};
+
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey =
+    pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const SPL_NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+pub const EVENT_TREE_SEED: &[u8] = b"event_tree";
+// The PDA the compression program knows as the tree's authority. Only
+// this program can sign for it, so only this program can add leaves.
+pub const TREE_AUTHORITY_SEED: &[u8] = b"tree_authority";
+
+// The compression program is an Anchor program too; these are its
+// instruction discriminators (sha256("global:<name>")[..8]). We build its
+// instructions by hand because its crate is built for an older Anchor.
+pub const INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+pub const APPEND_DISCRIMINATOR: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+/// Where the program's one event tree lives.
+#[account]
+#[derive(InitSpace)]
+pub struct EventTree {
+    pub merkle_tree: Pubkey,
+    /// Leaves appended so far, which is also the index of the next one.
+    pub leaves: u64,
+    pub bump: u8,
+    pub authority_bump: u8,
+}
+
+// The contents of one leaf. The tree stores keccak(Borsh bytes); the bytes
+// themselves are logged through the Noop program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CounterLeaf {
+    pub counter: Pubkey,
+    pub count: u64,
+    pub slot: u64,
+    // The leaf's position in the tree.
+    pub index: u64,
+}
+
+impl CounterLeaf {
+    pub fn hash(&self) -> [u8; 32] {
+        keccak::hash(&self.try_to_vec().unwrap()).to_bytes()
+    }
+}
+
+// Sets up the event tree. The client first creates the tree account,
+// owned by the compression program and sized for max_depth and
+// max_buffer_size (see the SPL docs), then calls this to initialize it.
+pub fn process_initialize_event_tree(
+    ctx: Context<InitializeEventTree>,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<()> {
+    let merkle_tree = ctx.accounts.merkle_tree.key();
+    let event_tree = &mut ctx.accounts.event_tree;
+    event_tree.merkle_tree = merkle_tree;
+    event_tree.bump = ctx.bumps.event_tree;
+    event_tree.authority_bump = ctx.bumps.tree_authority;
+
+    let accounts = &ctx.accounts;
+    let mut data = INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+    compression_cpi(
+        data,
+        &accounts.compression_program,
+        &accounts.merkle_tree,
+        &accounts.tree_authority,
+        &accounts.noop_program,
+        ctx.bumps.tree_authority,
+    )
+}
+
+// Adds 1 to a counter and appends the increment to the event tree.
+pub fn process_increment_logged(ctx: Context<IncrementLogged>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.check_route(Route::TreeLogged)?;
+    let before = counter.count;
+    counter.increment()?;
+    debug_assert_invariants!(counter);
+
+    let event_tree = &mut ctx.accounts.event_tree;
+    let leaf = CounterLeaf {
+        counter: counter.key(),
+        count: counter.count,
+        slot: clock::slot()?,
+        index: event_tree.leaves,
+    };
+    event_tree.leaves += 1;
+
+    // Publish the leaf's contents for indexers...
+    invoke(
+        &Instruction {
+            program_id: SPL_NOOP_ID,
+            accounts: vec![],
+            data: leaf.try_to_vec()?,
+        },
+        &[ctx.accounts.noop_program.to_account_info()],
+    )?;
+
+    // ...and add its hash to the tree.
+    let mut data = APPEND_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&leaf.hash());
+    compression_cpi(
+        data,
+        &ctx.accounts.compression_program,
+        &ctx.accounts.merkle_tree,
+        &ctx.accounts.tree_authority,
+        &ctx.accounts.noop_program,
+        event_tree.authority_bump,
+    )?;
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+// Both compression instructions we use take the same three accounts: the
+// tree, its authority (our PDA, signing), and the Noop program.
+fn compression_cpi<'info>(
+    data: Vec<u8>,
+    compression_program: &UncheckedAccount<'info>,
+    merkle_tree: &UncheckedAccount<'info>,
+    tree_authority: &UncheckedAccount<'info>,
+    noop_program: &UncheckedAccount<'info>,
+    authority_bump: u8,
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts: vec![
+            AccountMeta::new(merkle_tree.key(), false),
+            AccountMeta::new_readonly(tree_authority.key(), true),
+            AccountMeta::new_readonly(noop_program.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            merkle_tree.to_account_info(),
+            tree_authority.to_account_info(),
+            noop_program.to_account_info(),
+            compression_program.to_account_info(),
+        ],
+        &[&[TREE_AUTHORITY_SEED, &[authority_bump]]],
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeEventTree<'info> {
+    // There's one event tree for the whole program, so like ProgramInfo
+    // only the upgrade authority can set it up.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EventTree::INIT_SPACE,
+        seeds = [EVENT_TREE_SEED],
+        bump
+    )]
+    pub event_tree: Account<'info, EventTree>,
+
+    #[account(mut, owner = SPL_ACCOUNT_COMPRESSION_ID)]
+    /// CHECK: the new, empty tree account, initialized by the compression program.
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(seeds = [TREE_AUTHORITY_SEED], bump)]
+    /// CHECK: our PDA; holds no data, only signs.
+    pub tree_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // See SetProgramInfo in program_info.rs.
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CounterProgram>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CounterError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    /// CHECK: checked by address.
+    pub compression_program: UncheckedAccount<'info>,
+    #[account(address = SPL_NOOP_ID)]
+    /// CHECK: checked by address.
+    pub noop_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementLogged<'info> {
+    // Otherwise this would add to counters whose mode wants some other
+    // instruction, without what that one does (see check_route).
+    #[account(
+        mut,
+        constraint = counter.has_flag(Counter::TREE_LOGGED) @ CounterError::NotTreeLogged
+    )]
+    pub counter: Account<'info, Counter>,
+
+    #[account(mut, seeds = [EVENT_TREE_SEED], bump = event_tree.bump, has_one = merkle_tree)]
+    pub event_tree: Account<'info, EventTree>,
+
+    #[account(mut)]
+    /// CHECK: checked against event_tree by "has_one".
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(seeds = [TREE_AUTHORITY_SEED], bump = event_tree.authority_bump)]
+    /// CHECK: our PDA; holds no data, only signs.
+    pub tree_authority: UncheckedAccount<'info>,
+
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    /// CHECK: checked by address.
+    pub compression_program: UncheckedAccount<'info>,
+    #[account(address = SPL_NOOP_ID)]
+    /// CHECK: checked by address.
+    pub noop_program: UncheckedAccount<'info>,
+}
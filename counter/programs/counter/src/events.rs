@@ -0,0 +1,360 @@
+// ============================================================================
+// EVENTS
+// Structured records of what happened, for off-chain apps to listen for
+// ============================================================================
+
+// "emit!(SomeEvent { ... })" serializes the event and writes it to the
+// transaction logs. Unlike a text log, an event has a fixed layout listed
+// in the IDL, so clients can decode it reliably, e.g. with
+// program.addEventListener("counterIncremented", ...).
+//
+// Every event carries the program version that emitted it, so consumers
+// can tell which layout (and which rules) produced it.
+//
+// Events about a Counter also carry "event_seq": the counter's own event
+// number, going up by exactly 1 per event (see Counter::next_event_seq).
+// A consumer that sees 5 then 7 knows it missed one and can fetch that
+// range of transactions again. Events about other accounts can use their
+// own running totals for the same (a replica's "count", a confidential
+// counter's "increments").
+//
+// The "///" comments on events and their fields go into the IDL too (with
+// the idl-build feature), so decoders get each field's meaning with its
+// layout. Say what unit a number is in: slots or unix seconds, lamports
+// or SOL, a count or a change to one.
+use anchor_lang::prelude::*;
+
+use crate::Version;
+
+/// A counter was created, at 0 unless it's a gift (see gift.rs).
+#[event]
+pub struct CounterInitialized {
+    pub counter: Pubkey,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A counter was incremented.
+#[event]
+pub struct CounterIncremented {
+    pub counter: Pubkey,
+    /// The count after the increment.
+    pub count: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// One of a bank's counters was incremented (see bank.rs).
+#[event]
+pub struct BankIncremented {
+    pub bank: Pubkey,
+    /// Which of the bank's counters, from 0.
+    pub index: u16,
+    /// That counter's count after the increment.
+    pub count: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// The counter reached its alarm threshold (see set_alarm_threshold).
+#[event]
+pub struct AlarmTriggered {
+    pub counter: Pubkey,
+    /// The count that reached the threshold.
+    pub count: u64,
+    /// The alarm threshold, a count.
+    pub threshold: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A replica (see replica.rs) was incremented.
+#[event]
+pub struct ReplicaIncremented {
+    pub replica: Pubkey,
+    /// The counter the replica is merged into.
+    pub root: Pubkey,
+    /// The replica's own total, not the root counter's.
+    pub count: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A counter went down by one (see token_mirror.rs).
+#[event]
+pub struct CounterDecremented {
+    pub counter: Pubkey,
+    /// The count after the decrement.
+    pub count: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// archive or unarchive was called.
+#[event]
+pub struct CounterArchived {
+    pub counter: Pubkey,
+    /// True for archive, false for unarchive.
+    pub archived: bool,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// reset_count set the count back to 0.
+#[event]
+pub struct CounterReset {
+    pub counter: Pubkey,
+    /// The count before the reset.
+    pub previous: u64,
+    /// Everything ever added to the count, which a reset doesn't change.
+    pub lifetime_count: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// lease_authority made "renter" the counter's effective authority for a
+/// while (see lease.rs).
+#[event]
+pub struct AuthorityLeased {
+    pub counter: Pubkey,
+    pub renter: Pubkey,
+    /// When the lease ends, in unix seconds.
+    pub expires_at: i64,
+    /// What the renter paid into the counter's vault, in lamports.
+    pub price: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// increment_paid charged "payer" a fee (see fees.rs).
+#[event]
+pub struct FeePaid {
+    pub counter: Pubkey,
+    pub payer: Pubkey,
+    /// The whole fee, in lamports, referrer's share included.
+    pub fee: u64,
+    pub referrer: Option<Pubkey>,
+    /// The part of "fee" that went to the referrer, in lamports. 0 without
+    /// one.
+    pub referrer_share: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// finalize fixed the counter's count for good (see finalize.rs).
+#[event]
+pub struct CounterFinalized {
+    pub counter: Pubkey,
+    /// The final count.
+    pub count: u64,
+    /// The slot it was finalized in.
+    pub slot: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// merge_replicas added its replicas' counts to the counter (see
+/// replica.rs).
+#[event]
+pub struct ReplicasMerged {
+    pub counter: Pubkey,
+    /// The count after the merge.
+    pub count: u64,
+    /// How much the merge added to the count.
+    pub added: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// receive_remote_count updated a RemoteCounter (see wormhole.rs).
+#[event]
+pub struct RemoteCountReceived {
+    pub remote_counter: Pubkey,
+    /// The Wormhole chain id the count came from.
+    pub emitter_chain: u16,
+    /// The count on that chain.
+    pub count: u64,
+    /// The Wormhole message's sequence number (not a slot).
+    pub sequence: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A ConfidentialCounter was incremented. The count stays encrypted.
+#[event]
+pub struct ConfidentialIncremented {
+    pub counter: Pubkey,
+    /// The count as an ElGamal ciphertext, so the authority can decrypt it
+    /// from the event alone.
+    pub encrypted_count: [u8; 64],
+    /// Increments so far, in the clear. Goes up by 1 per event.
+    pub increments: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// prove_threshold showed a ConfidentialCounter's count was at least
+/// "min" (see confidential.rs).
+#[event]
+pub struct ThresholdProven {
+    pub counter: Pubkey,
+    /// The count proven reached, not the count itself.
+    pub min: u64,
+    /// The counter's "increments" when the proof was made.
+    pub increments: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A contributor claimed the badge for a contribution tier (see
+/// badges.rs).
+#[event]
+pub struct BadgeEarned {
+    pub counter: Pubkey,
+    pub owner: Pubkey,
+    /// The index of the tier in TIERS, from 0.
+    pub tier: u8,
+    /// When it was claimed, in unix seconds.
+    pub earned_at: i64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// An abandoned counter was closed by reap (see reap.rs).
+#[event]
+pub struct CounterReaped {
+    pub counter: Pubkey,
+    /// Got the counter's rent.
+    pub beneficiary: Pubkey,
+    /// The counter's event number: 1 more than its last event's. Its last.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A gift (see gift.rs) was claimed.
+#[event]
+pub struct GiftClaimed {
+    pub counter: Pubkey,
+    /// The counter's authority from now on.
+    pub recipient: Pubkey,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// An unclaimed gift expired and was closed (see gift.rs).
+#[event]
+pub struct GiftReclaimed {
+    pub counter: Pubkey,
+    /// Got the rent back.
+    pub creator: Pubkey,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// An increment of a counter at its soft cap (see spill.rs) went to
+/// "overflow" instead, which emits its own CounterIncremented.
+#[event]
+pub struct CounterSpilled {
+    pub counter: Pubkey,
+    pub overflow: Pubkey,
+    /// The overflow counter's count after the increment.
+    pub overflow_count: u64,
+    /// The capped counter's event number, not the overflow's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// Contributors' votes (see freeze.rs) froze the counter, or unfreeze
+/// thawed it.
+#[event]
+pub struct CounterFrozen {
+    pub counter: Pubkey,
+    /// True when votes froze it, false for unfreeze.
+    pub frozen: bool,
+    /// Votes that froze it, or 0 for unfreeze.
+    pub votes: u16,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// sweep_fees emptied the counter's vault into the treasury (see
+/// sweep.rs).
+#[event]
+pub struct FeesSwept {
+    pub counter: Pubkey,
+    /// What the vault held, in lamports.
+    pub amount: u64,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// update_group changed a group's settings, for every member at once (see
+/// group.rs).
+#[event]
+pub struct GroupUpdated {
+    pub group: Pubkey,
+    /// Whether the group's members are paused.
+    pub paused: bool,
+    /// What each increment of a member costs, in lamports.
+    pub fee_lamports: u64,
+    /// Whether members only take increments made at the top level.
+    pub deny_cpi: bool,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// The counter joined a group, or left one (see group.rs).
+#[event]
+pub struct CounterGroupChanged {
+    pub counter: Pubkey,
+    /// The group it's in now, or the default (all zeros) address if it
+    /// left.
+    pub group: Pubkey,
+    /// The counter's event number: 1 more than its last event's.
+    pub event_seq: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
+
+/// A compressed counter was incremented (see compressed.rs).
+#[event]
+pub struct CompressedCounterIncremented {
+    /// The counter's compressed account address.
+    pub address: [u8; 32],
+    pub authority: Pubkey,
+    /// The count after the increment. It goes up by exactly 1 per event,
+    /// so it numbers them, as a replica's count does.
+    pub count: u64,
+    /// The program version that emitted the event.
+    pub version: Version,
+}
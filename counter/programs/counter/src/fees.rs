@@ -0,0 +1,557 @@
+// ============================================================================
+// FEES AND REFERRALS - charge for increments, share with whoever sent them
+// ============================================================================
+
+// The owner can put a price on a counter's increments with
+// set_increment_fee. From then on increments go through increment_paid,
+// which takes the fee from the caller and puts it in the counter's vault
+// (see lease.rs).
+//
+// increment_paid takes an optional "referrer": a partner who brought the
+// caller in. The referrer gets "referral_bps" of the fee (in basis points:
+// 100 = 1%) in their own vault, and a ReferrerStats PDA per (counter,
+// referrer) keeps score of what they brought in, so growth teams can
+// reward their partners from on-chain data. Payers can't refer
+// themselves, or every fee would come with a discount.
+//
+// The owner can exempt wallets (team wallets, keeper bots) with
+// exempt_from_fees, which creates a FeeExemption PDA per (counter,
+// wallet). increment_paid charges nothing when its payer has one.
+//
+// Instead of one flat fee, the owner can set a schedule by daily volume
+// with set_fee_schedule: "the first 100 increments a day are free, then
+// 1,000 lamports, from the 1,000th on 5,000". The day's volume is counted
+// in the FeeSchedule PDA, which increment_paid takes as an optional last
+// account. set_fee_schedule also sets the flat fee to the schedule's
+// highest, so leaving the schedule out never costs less than passing it.
+// A later set_increment_fee changes the flat fee, which retires the
+// schedule: it only applies while the flat fee is still the one it set.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use nautilus_common::{clock, math};
+
+use crate::access_log::AdminAction;
+use crate::args::check_args_version;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::stats::{self, current_hour_seed, StatsBucket, STATS_SEED};
+use crate::{
+    AlarmTriggered, Counter, CounterError, CounterIncremented, FeePaid, Route, Version,
+    FEES_ENABLED,
+};
+
+pub const FEES_SEED: &[u8] = b"fees";
+pub const REFERRER_STATS_SEED: &[u8] = b"referrer_stats";
+pub const REFERRAL_VAULT_SEED: &[u8] = b"referral_vault";
+pub const FEE_EXEMPTION_SEED: &[u8] = b"fee_exemption";
+pub const FEE_SCHEDULE_SEED: &[u8] = b"fee_schedule";
+
+pub const MAX_FEE_TIERS: usize = 4;
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+// 100% in basis points.
+pub const MAX_BPS: u16 = 10_000;
+
+/// What increment_paid charges for a counter.
+#[account]
+#[derive(InitSpace)]
+pub struct CounterFees {
+    pub counter: Pubkey,
+    /// Charged per increment_paid call, in lamports.
+    pub fee_lamports: u64,
+    /// The referrer's share of each fee, in basis points.
+    pub referral_bps: u16,
+    pub bump: u8,
+}
+
+impl CounterFees {
+    // Splits a fee into (counter's part, referrer's part). The referrer's
+    // part is rounded down, so the two always add up to the fee.
+    pub fn split(&self, referred: bool) -> (u64, u64) {
+        self.split_fee(self.fee_lamports, referred)
+    }
+
+    // split, for a fee other than the flat one (see FeeSchedule).
+    pub fn split_fee(&self, fee: u64, referred: bool) -> (u64, u64) {
+        if !referred {
+            return (fee, 0);
+        }
+        let share = math::portion(fee, u64::from(self.referral_bps), u64::from(MAX_BPS));
+        (fee - share, share)
+    }
+}
+
+/// One step of a FeeSchedule.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeTier {
+    /// The day's increment, counting from 0, this step starts at.
+    pub from: u64,
+    /// What each increment from then on costs, in lamports.
+    pub fee_lamports: u64,
+}
+
+/// Fees for a counter that depend on how many increments it's had that
+/// day (see increment_paid).
+#[account]
+#[derive(InitSpace)]
+pub struct FeeSchedule {
+    pub counter: Pubkey,
+    /// Ordered by "from", the first from 0.
+    #[max_len(MAX_FEE_TIERS)]
+    pub tiers: Vec<FeeTier>,
+    /// The day (unix seconds / SECONDS_PER_DAY) "day_volume" is for.
+    pub day: i64,
+    /// Increments charged through the schedule that day, a count.
+    pub day_volume: u64,
+    pub bump: u8,
+}
+
+impl FeeSchedule {
+    // The highest fee of any tier, which set_fee_schedule makes the flat
+    // fee.
+    pub fn top_fee(&self) -> u64 {
+        self.tiers.iter().map(|t| t.fee_lamports).max().unwrap_or(0)
+    }
+
+    // The fee for one more increment at "now", counting it in the day's
+    // volume.
+    pub fn charge(&mut self, now: i64) -> u64 {
+        let day = now.div_euclid(SECONDS_PER_DAY);
+        if day != self.day {
+            self.day = day;
+            self.day_volume = 0;
+        }
+        let fee = self
+            .tiers
+            .iter()
+            .take_while(|t| t.from <= self.day_volume)
+            .last()
+            .map_or(0, |t| t.fee_lamports);
+        self.day_volume = self.day_volume.saturating_add(1);
+        fee
+    }
+}
+
+// set_increment_fee's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct IncrementFeeArgs {
+    pub version: u8,
+    // 0 turns charging off.
+    pub fee_lamports: u64,
+    pub referral_bps: u16,
+}
+
+impl IncrementFeeArgs {
+    pub const VERSION: u8 = 1;
+}
+
+// set_fee_schedule's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FeeScheduleArgs {
+    pub version: u8,
+    // Up to MAX_FEE_TIERS, ordered by "from", the first from 0.
+    pub tiers: Vec<FeeTier>,
+    pub referral_bps: u16,
+}
+
+impl FeeScheduleArgs {
+    pub const VERSION: u8 = 1;
+}
+
+/// What one referrer brought a counter's paid increments.
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerStats {
+    pub counter: Pubkey,
+    pub referrer: Pubkey,
+    /// Paid increments this referrer brought in.
+    pub increments: u64,
+    /// Lamports credited to the referrer's vault for them.
+    pub earned: u64,
+    pub bump: u8,
+}
+
+/// A wallet that increment_paid doesn't charge.
+#[account]
+#[derive(InitSpace)]
+pub struct FeeExemption {
+    pub counter: Pubkey,
+    pub wallet: Pubkey,
+    pub bump: u8,
+}
+
+// Sets the fee. A fee of 0 makes increments free again.
+pub fn process_set_increment_fee(
+    ctx: Context<SetIncrementFee>,
+    args: IncrementFeeArgs,
+) -> Result<()> {
+    check_args_version(args.version, IncrementFeeArgs::VERSION)?;
+    let IncrementFeeArgs {
+        fee_lamports,
+        referral_bps,
+        ..
+    } = args;
+    set_fees(
+        &mut ctx.accounts.guard.counter,
+        &mut ctx.accounts.fees,
+        ctx.bumps.fees,
+        fee_lamports,
+        referral_bps,
+    )?;
+    ctx.accounts.guard.log(AdminAction::SetIncrementFee)
+}
+
+// Sets a schedule by daily volume, with its highest fee as the flat one.
+pub fn process_set_fee_schedule(ctx: Context<SetFeeSchedule>, args: FeeScheduleArgs) -> Result<()> {
+    check_args_version(args.version, FeeScheduleArgs::VERSION)?;
+    let FeeScheduleArgs {
+        tiers,
+        referral_bps,
+        ..
+    } = args;
+    require!(
+        !tiers.is_empty()
+            && tiers.len() <= MAX_FEE_TIERS
+            && tiers[0].from == 0
+            && tiers.windows(2).all(|w| w[0].from < w[1].from),
+        CounterError::InvalidFeeSchedule
+    );
+
+    let schedule = &mut ctx.accounts.fee_schedule;
+    schedule.counter = ctx.accounts.guard.counter.key();
+    schedule.tiers = tiers;
+    schedule.bump = ctx.bumps.fee_schedule;
+    set_fees(
+        &mut ctx.accounts.guard.counter,
+        &mut ctx.accounts.fees,
+        ctx.bumps.fees,
+        schedule.top_fee(),
+        referral_bps,
+    )?;
+    ctx.accounts.guard.log(AdminAction::SetFeeSchedule)
+}
+
+// Sets the flat fee, turning FEE_REQUIRED on for a fee above 0 and off
+// for 0.
+fn set_fees(
+    counter: &mut Account<Counter>,
+    fees: &mut Account<CounterFees>,
+    bump: u8,
+    fee_lamports: u64,
+    referral_bps: u16,
+) -> Result<()> {
+    require!(referral_bps <= MAX_BPS, CounterError::InvalidFee);
+    let charging = fee_lamports > 0;
+    if charging {
+        counter.check_route_free(Counter::FEE_REQUIRED)?;
+    }
+    counter.set_flag(Counter::FEE_REQUIRED, charging);
+    debug_assert_invariants!(counter);
+
+    fees.counter = counter.key();
+    fees.fee_lamports = fee_lamports;
+    fees.referral_bps = referral_bps;
+    fees.bump = bump;
+    Ok(())
+}
+
+// Adds 1 to the counter and charges the payer its fee.
+pub fn process_increment_paid(ctx: Context<IncrementPaid>, referrer: Option<Pubkey>) -> Result<()> {
+    let payment = &mut ctx.accounts.payment;
+    // A counter that stopped charging keeps its CounterFees; without
+    // this, that would make increment_paid a free way around its other
+    // modes.
+    payment.counter.check_route(Route::FeeRequired)?;
+    let before = payment.counter.count;
+    payment.counter.increment()?;
+    debug_assert_invariants!(payment.counter);
+
+    // Anchor checked the exemption's address against the payer; if it's
+    // passed in at all, the payer is exempt.
+    let (to_counter, to_referrer) = increment_fee(
+        &ctx.accounts.fees,
+        ctx.accounts.fee_schedule.as_deref_mut(),
+        ctx.accounts.fee_exemption.is_some(),
+        referrer.is_some(),
+    )?;
+    payment.pay_vault(to_counter)?;
+
+    if let Some(referrer) = referrer {
+        require_keys_neq!(referrer, payment.payer.key(), CounterError::SelfReferral);
+        // Anchor checked their addresses against "referrer", but they're
+        // optional, so they might have been left out.
+        let (Some(stats), Some(referral_vault)) = (
+            ctx.accounts.referrer_stats.as_mut(),
+            ctx.accounts.referral_vault.as_ref(),
+        ) else {
+            return err!(CounterError::ReferrerAccountsMissing);
+        };
+        payment.pay(referral_vault.to_account_info(), to_referrer)?;
+        stats.counter = payment.counter.key();
+        stats.referrer = referrer;
+        stats.increments = stats
+            .increments
+            .checked_add(1)
+            .ok_or(CounterError::Overflow)?;
+        stats.earned = stats
+            .earned
+            .checked_add(to_referrer)
+            .ok_or(CounterError::Overflow)?;
+        stats.bump = ctx.bumps.referrer_stats.unwrap_or_default();
+    }
+
+    let payer = payment.payer.key();
+    let counter = &mut payment.counter;
+    stats::record_increment(
+        &mut ctx.accounts.stats_bucket,
+        ctx.bumps.stats_bucket,
+        counter.key(),
+        &payer,
+        to_counter + to_referrer,
+    )?;
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    emit!(FeePaid {
+        counter: counter.key(),
+        payer,
+        fee: to_counter + to_referrer,
+        referrer,
+        referrer_share: to_referrer,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+// What one increment_paid charges, as (counter's part, referrer's part),
+// counting it in the schedule's day if there is one. Exempt payers pay
+// nothing and aren't counted, so they don't push others into a dearer
+// tier. preview_increment (see preview.rs) passes a copy of the schedule.
+// A localnet build (see FEES_ENABLED in lib.rs) charges nothing.
+pub fn increment_fee(
+    fees: &CounterFees,
+    schedule: Option<&mut FeeSchedule>,
+    exempt: bool,
+    referred: bool,
+) -> Result<(u64, u64)> {
+    if exempt || !FEES_ENABLED {
+        return Ok((0, 0));
+    }
+    let fee = match schedule {
+        // Only while the flat fee is still the one the schedule set.
+        Some(schedule) if schedule.top_fee() == fees.fee_lamports => schedule.charge(clock::now()?),
+        _ => fees.fee_lamports,
+    };
+    Ok(fees.split_fee(fee, referred))
+}
+
+pub fn process_exempt_from_fees(ctx: Context<ExemptFromFees>, wallet: Pubkey) -> Result<()> {
+    let exemption = &mut ctx.accounts.fee_exemption;
+    exemption.counter = ctx.accounts.guard.counter.key();
+    exemption.wallet = wallet;
+    exemption.bump = ctx.bumps.fee_exemption;
+
+    ctx.accounts.guard.log(AdminAction::ExemptFromFees)
+}
+
+// Closes the exemption, refunding its rent to the authority.
+pub fn process_revoke_fee_exemption(ctx: Context<RevokeFeeExemption>) -> Result<()> {
+    let guard = &ctx.accounts.guard;
+    guard.close_to_owner(&ctx.accounts.fee_exemption)?;
+    guard.log(AdminAction::RevokeFeeExemption)
+}
+
+// Sends "amount" lamports from a referrer's vault to the referrer.
+pub fn process_withdraw_referral(ctx: Context<WithdrawReferral>, amount: u64) -> Result<()> {
+    let referrer = ctx.accounts.referrer.key();
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.referral_vault.to_account_info(),
+                to: ctx.accounts.referrer.to_account_info(),
+            },
+            &[&[
+                REFERRAL_VAULT_SEED,
+                referrer.as_ref(),
+                &[ctx.bumps.referral_vault],
+            ]],
+        ),
+        amount,
+    )
+}
+
+#[derive(Accounts)]
+pub struct SetIncrementFee<'info> {
+    // Fees are money, so like the vault they stay with the owner during a
+    // lease (see lease.rs).
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + CounterFees::INIT_SPACE,
+        seeds = [FEES_SEED, guard.counter.key().as_ref()],
+        bump
+    )]
+    pub fees: Account<'info, CounterFees>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(referrer: Option<Pubkey>)]
+pub struct IncrementPaid<'info> {
+    pub payment: FeePayment<'info>,
+
+    #[account(
+        seeds = [FEES_SEED, payment.counter.key().as_ref()],
+        bump = fees.bump
+    )]
+    pub fees: Account<'info, CounterFees>,
+
+    // Only needed with a referrer. "unwrap_or_default" just keeps the
+    // seeds well-formed when there isn't one.
+    #[account(
+        init_if_needed,
+        payer = payment.payer,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [
+            REFERRER_STATS_SEED,
+            payment.counter.key().as_ref(),
+            referrer.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+    #[account(
+        mut,
+        seeds = [REFERRAL_VAULT_SEED, referrer.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<SystemAccount<'info>>,
+
+    // Only for exempt payers.
+    #[account(
+        seeds = [
+            FEE_EXEMPTION_SEED,
+            payment.counter.key().as_ref(),
+            payment.payer.key().as_ref()
+        ],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    // This hour's stats (see stats.rs).
+    #[account(
+        init_if_needed,
+        payer = payment.payer,
+        space = 8 + StatsBucket::INIT_SPACE,
+        seeds = [
+            STATS_SEED,
+            payment.counter.key().as_ref(),
+            &current_hour_seed()
+        ],
+        bump
+    )]
+    pub stats_bucket: Account<'info, StatsBucket>,
+
+    pub system_program: Program<'info, System>,
+
+    // Only for counters with a fee schedule. Last, so clients from before
+    // schedules can leave it off.
+    #[account(
+        mut,
+        seeds = [FEE_SCHEDULE_SEED, payment.counter.key().as_ref()],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Option<Account<'info, FeeSchedule>>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeSchedule<'info> {
+    // Like SetIncrementFee, owner only.
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + CounterFees::INIT_SPACE,
+        seeds = [FEES_SEED, guard.counter.key().as_ref()],
+        bump
+    )]
+    pub fees: Account<'info, CounterFees>,
+
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + FeeSchedule::INIT_SPACE,
+        seeds = [FEE_SCHEDULE_SEED, guard.counter.key().as_ref()],
+        bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct ExemptFromFees<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init,
+        payer = guard.authority,
+        space = 8 + FeeExemption::INIT_SPACE,
+        seeds = [FEE_EXEMPTION_SEED, guard.counter.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeFeeExemption<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    // Closed by the handler (see guards.rs).
+    #[account(
+        mut,
+        seeds = [
+            FEE_EXEMPTION_SEED,
+            guard.counter.key().as_ref(),
+            fee_exemption.wallet.as_ref()
+        ],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawReferral<'info> {
+    // One vault per referrer, shared by every counter they refer to.
+    #[account(mut, seeds = [REFERRAL_VAULT_SEED, referrer.key().as_ref()], bump)]
+    pub referral_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,126 @@
+// ============================================================================
+// FINALIZATION - a counter's last word, once its time is up
+// ============================================================================
+
+// Some counters count toward a deadline: a vote, a campaign, a season.
+// Whatever settles afterwards (payouts, rankings) needs a result that
+// can't move under it.
+//
+// The authority sets an end time with set_end_time. After it, anyone can
+// call finalize, which freezes the counter for good (unlike archive, this
+// can't be undone) and writes the final count and slot into a FinalResult
+// PDA. The PDA is created with "init", so it can only be written once.
+use anchor_lang::prelude::*;
+
+use crate::access_log::AdminAction;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::{Counter, CounterError, CounterFinalized, Version};
+
+pub const DEADLINE_SEED: &[u8] = b"deadline";
+pub const RESULT_SEED: &[u8] = b"result";
+
+/// When a counter can be finalized, set by set_end_time.
+#[account]
+#[derive(InitSpace)]
+pub struct Deadline {
+    pub counter: Pubkey,
+    /// finalize can be called from then on, in unix seconds.
+    pub end_time: i64,
+    pub bump: u8,
+}
+
+/// A finalized counter's result. Never changes after finalize.
+#[account]
+#[derive(InitSpace)]
+pub struct FinalResult {
+    pub counter: Pubkey,
+    /// The final count.
+    pub count: u64,
+    /// The slot finalize ran in.
+    pub slot: u64,
+    /// When finalize ran, in unix seconds.
+    pub finalized_at: i64,
+    pub bump: u8,
+}
+
+// Sets (or moves) the end time. Only possible until finalize.
+pub fn process_set_end_time(ctx: Context<SetEndTime>, end_time: i64) -> Result<()> {
+    let counter = &ctx.accounts.guard.counter;
+    counter.check_active()?;
+
+    let deadline = &mut ctx.accounts.deadline;
+    deadline.counter = counter.key();
+    deadline.end_time = end_time;
+    deadline.bump = ctx.bumps.deadline;
+
+    ctx.accounts.guard.log(AdminAction::SetEndTime)
+}
+
+pub fn process_finalize(ctx: Context<Finalize>) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(
+        clock.unix_timestamp >= ctx.accounts.deadline.end_time,
+        CounterError::TooEarlyToFinalize
+    );
+
+    let counter = &mut ctx.accounts.counter;
+    counter.finalized = true;
+    debug_assert_invariants!(counter);
+
+    let result = &mut ctx.accounts.result;
+    result.counter = counter.key();
+    result.count = counter.count;
+    result.slot = clock.slot;
+    result.finalized_at = clock.unix_timestamp;
+    result.bump = ctx.bumps.result;
+
+    emit!(CounterFinalized {
+        counter: counter.key(),
+        count: counter.count,
+        slot: clock.slot,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetEndTime<'info> {
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + Deadline::INIT_SPACE,
+        seeds = [DEADLINE_SEED, guard.counter.key().as_ref()],
+        bump
+    )]
+    pub deadline: Account<'info, Deadline>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Finalize<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(seeds = [DEADLINE_SEED, counter.key().as_ref()], bump = deadline.bump)]
+    pub deadline: Account<'info, Deadline>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FinalResult::INIT_SPACE,
+        seeds = [RESULT_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, FinalResult>,
+
+    // Anyone can finalize, and pays for the result account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,229 @@
+// ============================================================================
+// FREEZE - contributors voting to pause a counter
+// ============================================================================
+
+// A counter whose authority is gaming it (or whose increments are being
+// farmed) can't be stopped by anyone but that authority. With a freeze
+// policy in place, the people who built the count up can: a contributor
+// holding one of the counter's badges (see badges.rs) casts a freeze
+// vote, and once "quorum" of them have, the counter is frozen. Nothing
+// changes its count until the authority reviews it and calls unfreeze.
+// That's the owner's call even during a lease (see lease.rs): a freeze is
+// often about the renter, who shouldn't be able to lift it.
+//
+// Votes are counted in rounds, in a FreezeTally PDA per counter. A round
+// opens with its first vote and lasts the policy's "vote_secs"; a vote
+// after that opens a new round, starting from 0, so scattered complaints
+// months apart don't add up to a freeze. A freeze also closes the round,
+// so the votes that caused it can't freeze the counter again after
+// unfreeze.
+//
+// Each voter gets one FreezeVote PDA per counter, remembering the last
+// round they voted in, so one vote per round however many badges they
+// hold. The Config's admin sets the policy, in the FreezePolicy PDA; a
+// quorum of 0, or no FreezePolicy at all, turns voting off.
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::access_log::AdminAction;
+use crate::badges::{Badge, BADGE_SEED};
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::{Config, Counter, CounterError, CounterFrozen, Version, CONFIG_SEED};
+
+pub const FREEZE_POLICY_SEED: &[u8] = b"freeze_policy";
+pub const FREEZE_TALLY_SEED: &[u8] = b"freeze_tally";
+pub const FREEZE_VOTE_SEED: &[u8] = b"freeze_vote";
+
+/// How contributors' freeze votes work, program-wide.
+#[account]
+#[derive(InitSpace)]
+pub struct FreezePolicy {
+    /// Votes in one round that freeze a counter. 0 turns voting off.
+    pub quorum: u16,
+    /// How long a round stays open after its first vote, in seconds.
+    pub vote_secs: i64,
+    pub bump: u8,
+}
+
+/// A counter's freeze votes, counted in rounds.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct FreezeTally {
+    pub counter: Pubkey,
+    /// Rounds ever opened. Votes are for the latest one.
+    pub round: u64,
+    /// When the latest round opened (unix seconds), or 0 if it was closed
+    /// by a freeze and the next vote opens a new one.
+    pub opened_at: i64,
+    /// Votes in the latest round.
+    pub votes: u16,
+    pub bump: u8,
+}
+
+/// One voter's freeze votes on one counter.
+#[account]
+#[derive(InitSpace)]
+pub struct FreezeVote {
+    pub counter: Pubkey,
+    pub voter: Pubkey,
+    /// The round this voter last voted in.
+    pub round: u64,
+    pub bump: u8,
+}
+
+impl FreezeTally {
+    // Opens a new round if the latest one is closed or over at "now".
+    pub fn refresh(&mut self, now: i64, vote_secs: i64) {
+        if self.opened_at == 0 || now >= self.opened_at.saturating_add(vote_secs) {
+            self.round += 1;
+            self.opened_at = now;
+            self.votes = 0;
+        }
+    }
+
+    // Closes the round, after a freeze.
+    pub fn end_round(&mut self) {
+        self.opened_at = 0;
+        self.votes = 0;
+    }
+}
+
+// Sets how many votes freeze a counter, and how long a round lasts.
+pub fn process_set_freeze_policy(
+    ctx: Context<SetFreezePolicy>,
+    quorum: u16,
+    vote_secs: i64,
+) -> Result<()> {
+    require!(vote_secs > 0, CounterError::InvalidFreezePolicy);
+    let policy = &mut ctx.accounts.freeze_policy;
+    policy.quorum = quorum;
+    policy.vote_secs = vote_secs;
+    policy.bump = ctx.bumps.freeze_policy;
+    Ok(())
+}
+
+pub fn process_vote_freeze(ctx: Context<VoteFreeze>) -> Result<()> {
+    let policy = &ctx.accounts.freeze_policy;
+    require!(policy.quorum != 0, CounterError::FreezeVotingOff);
+    let counter = &mut ctx.accounts.counter;
+    // Frozen (or finalized, or archived) counters can't change anyway.
+    counter.check_active()?;
+
+    let tally = &mut ctx.accounts.freeze_tally;
+    tally.counter = counter.key();
+    tally.bump = ctx.bumps.freeze_tally;
+    tally.refresh(clock::now()?, policy.vote_secs);
+
+    let vote = &mut ctx.accounts.freeze_vote;
+    require!(vote.round != tally.round, CounterError::AlreadyVoted);
+    vote.counter = counter.key();
+    vote.voter = ctx.accounts.voter.key();
+    vote.round = tally.round;
+    vote.bump = ctx.bumps.freeze_vote;
+
+    tally.votes += 1;
+    if tally.votes < policy.quorum {
+        return Ok(());
+    }
+    let votes = tally.votes;
+    tally.end_round();
+    counter.frozen = true;
+    debug_assert_invariants!(counter);
+    emit!(CounterFrozen {
+        counter: counter.key(),
+        frozen: true,
+        votes,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// The authority's review: lets a frozen counter change again.
+pub fn process_unfreeze(ctx: Context<Unfreeze>) -> Result<()> {
+    let guard = &mut ctx.accounts.guard;
+    guard.log(AdminAction::Unfreeze)?;
+    let counter = &mut guard.counter;
+    require!(counter.frozen, CounterError::NotFrozen);
+    counter.frozen = false;
+    debug_assert_invariants!(counter);
+    emit!(CounterFrozen {
+        counter: counter.key(),
+        frozen: false,
+        votes: 0,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Unfreeze<'info> {
+    // The owner, not lease::effective_authority.
+    pub guard: AuthorityGuard<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFreezePolicy<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump, has_one = admin @ CounterError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + FreezePolicy::INIT_SPACE,
+        seeds = [FREEZE_POLICY_SEED],
+        bump
+    )]
+    pub freeze_policy: Account<'info, FreezePolicy>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteFreeze<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(seeds = [FREEZE_POLICY_SEED], bump = freeze_policy.bump)]
+    pub freeze_policy: Account<'info, FreezePolicy>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + FreezeTally::INIT_SPACE,
+        seeds = [FREEZE_TALLY_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub freeze_tally: Account<'info, FreezeTally>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + FreezeVote::INIT_SPACE,
+        seeds = [FREEZE_VOTE_SEED, counter.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub freeze_vote: Account<'info, FreezeVote>,
+
+    // Any of the voter's badges for this counter: the seeds tie it to both.
+    #[account(
+        seeds = [
+            BADGE_SEED,
+            counter.key().as_ref(),
+            voter.key().as_ref(),
+            &[badge.tier]
+        ],
+        bump = badge.bump
+    )]
+    pub badge: Account<'info, Badge>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
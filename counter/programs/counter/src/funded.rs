@@ -0,0 +1,71 @@
+// ============================================================================
+// FUNDED - counters paid for by someone other than their owner
+// ============================================================================
+
+// initialize makes whoever pays the rent the counter's authority. A
+// custodial service or a front-end that funds its users' counters would
+// then own them all, and have to hand each one over. initialize_funded
+// splits the two: "payer" signs and pays the rent, and "authority" gets
+// the counter, without signing or spending anything.
+//
+// Nothing is asked of the authority, so a counter can be made for any
+// address, including one that never uses it. That costs only the payer,
+// who gets nothing back for it: the counter is wholly the authority's.
+//
+// The authority can also be another program's PDA, so that program
+// decides what happens to the counter (the multisig's vault, in
+// programs/multisig, is one). Instructions that check the authority take
+// it as a Signer, and a PDA signs by its program calling ours with
+// invoke_signed and the PDA's seeds; nothing else about it is checked.
+// Two things to get right:
+//
+//   - the first settings change creates the counter's access log (see
+//     access_log.rs) with the authority paying, through the System
+//     Program. So the PDA has to be a plain system account holding enough
+//     lamports, not an account with data of its program's own
+//   - something has to call us as the PDA. A PDA of a program that never
+//     does leaves the counter with nobody to change its settings
+//
+// What initialize_funded can check, it does: a program, or an account of
+// this program, can never sign as the authority, so neither is taken.
+use anchor_lang::prelude::*;
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{Counter, CounterError, CounterInitialized, Version};
+
+// "initialize", with the rent from "payer".
+pub fn process_initialize_funded(ctx: Context<InitializeFunded>) -> Result<()> {
+    let authority = &ctx.accounts.authority;
+    require!(
+        !authority.executable && *authority.owner != crate::ID,
+        CounterError::InvalidAuthority
+    );
+    let counter = &mut ctx.accounts.counter;
+    counter.authority = ctx.accounts.authority.key();
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Initialized, counter.count);
+    emit!(CounterInitialized {
+        counter: counter.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeFunded<'info> {
+    // As in Initialize, but paid for by "payer".
+    #[account(init, payer = payer, space = 8 + Counter::INIT_SPACE)]
+    pub counter: Account<'info, Counter>,
+
+    /// CHECK: only its address is used, as the counter's authority, after
+    /// process_initialize_funded checks it could sign.
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
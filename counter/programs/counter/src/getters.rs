@@ -0,0 +1,69 @@
+// ============================================================================
+// GETTERS - a counter's state, handed back as return data
+// ============================================================================
+
+// Another program that wants a counter's count can read the account
+// itself, but only by depending on its layout, which grows over time (see
+// migrate.rs). get_count and get_state return it instead, the way
+// get_version does (see version.rs): through return data, which a caller
+// reads right after the CPI, e.g. with counter::cpi::get_state(...)?.get().
+//
+// get_state hands back everything a consumer usually wants in one call.
+// CounterState only ever gains fields at the end, so a caller built
+// against an older version can still decode the fields it knows.
+use anchor_lang::prelude::*;
+
+use crate::{Counter, Version};
+
+// A summary of a counter, as get_state returns it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CounterState {
+    pub count: u64,
+    pub lifetime_count: u64,
+    // The owner. During a lease (see lease.rs) the renter holds the
+    // settings, but the owner doesn't change.
+    pub authority: Pubkey,
+    pub flags: u8,
+    // The slot of the last increment, kept while ONCE_PER_SLOT is on.
+    pub last_increment_slot: u64,
+    pub alarm_threshold: u64,
+    pub event_seq: u64,
+    pub finalized: bool,
+    pub deny_cpi: bool,
+    pub role_weighted: bool,
+    // The program version that answered, so a caller knows which fields
+    // to expect.
+    pub version: Version,
+}
+
+impl From<&Counter> for CounterState {
+    fn from(counter: &Counter) -> Self {
+        CounterState {
+            count: counter.count,
+            lifetime_count: counter.lifetime_count,
+            authority: counter.authority,
+            flags: counter.flags,
+            last_increment_slot: counter.last_increment_slot,
+            alarm_threshold: counter.alarm_threshold,
+            event_seq: counter.event_seq,
+            finalized: counter.finalized,
+            deny_cpi: counter.deny_cpi,
+            role_weighted: counter.role_weighted,
+            version: Version::CURRENT,
+        }
+    }
+}
+
+pub fn process_get_count(ctx: Context<ReadCounter>) -> Result<u64> {
+    Ok(ctx.accounts.counter.count)
+}
+
+pub fn process_get_state(ctx: Context<ReadCounter>) -> Result<CounterState> {
+    Ok(CounterState::from(&*ctx.accounts.counter))
+}
+
+// Read-only: nothing needs to sign.
+#[derive(Accounts)]
+pub struct ReadCounter<'info> {
+    pub counter: Account<'info, Counter>,
+}
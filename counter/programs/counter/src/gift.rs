@@ -0,0 +1,226 @@
+// ============================================================================
+// GIFTS - counters created for someone else to claim
+// ============================================================================
+
+// create_gift makes a counter that already holds a count ("here's a
+// counter with your score") and sets it aside for one recipient, who takes
+// it over with claim_gift. Until then the counter's authority is its Gift
+// PDA, which no wallet can sign for, so nobody controls its settings in
+// the meantime; increments work as on any counter.
+//
+// A gift the recipient never claims shouldn't hold the creator's rent
+// forever. After "duration" seconds it can't be claimed anymore, and
+// reclaim_gift closes the counter and the Gift, sending both rents back to
+// the creator. Anyone can send it, since the lamports only ever go to the
+// creator. Claiming closes the Gift too, also refunding the creator.
+//
+// The gifted count goes into "lifetime_count" as well, as if it had been
+// added by increments, so the counter's invariants hold from the start.
+// Anyone can gift any count, though, so it's also kept in "gifted_count":
+// rankings leave gifted counters out (see leaderboard.rs), or a wallet
+// could gift itself u64::MAX and top the board.
+//
+// reclaim_gift closes the counter, but not its vault (see lease.rs): it
+// refuses while the vault holds anything, as reap does, or the lamports
+// would be stranded, or end up with whoever created a counter at that
+// address again.
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::access_log::{self, AccessLog, AdminAction, ACCESS_LOG_SEED};
+use crate::args::check_args_version;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    Counter, CounterError, CounterInitialized, GiftClaimed, GiftReclaimed, Version, VAULT_SEED,
+};
+
+pub const GIFT_SEED: &[u8] = b"gift";
+
+/// A gifted counter waiting for its recipient to claim it.
+#[account]
+#[derive(InitSpace)]
+pub struct Gift {
+    pub counter: Pubkey,
+    /// Paid the rent, and gets it back.
+    pub creator: Pubkey,
+    pub recipient: Pubkey,
+    /// The gift can't be claimed from this moment on, in unix seconds.
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Gift {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+// create_gift's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GiftArgs {
+    pub version: u8,
+    // What the counter starts at.
+    pub count: u64,
+    pub recipient: Pubkey,
+    // In seconds.
+    pub duration: i64,
+}
+
+impl GiftArgs {
+    pub const VERSION: u8 = 1;
+}
+
+pub fn process_create_gift(ctx: Context<CreateGift>, args: GiftArgs) -> Result<()> {
+    check_args_version(args.version, GiftArgs::VERSION)?;
+    require!(args.duration > 0, CounterError::InvalidGift);
+    let expires_at = clock::now()?
+        .checked_add(args.duration)
+        .ok_or(CounterError::InvalidGift)?;
+
+    let gift = &mut ctx.accounts.gift;
+    gift.counter = ctx.accounts.counter.key();
+    gift.creator = ctx.accounts.creator.key();
+    gift.recipient = args.recipient;
+    gift.expires_at = expires_at;
+    gift.bump = ctx.bumps.gift;
+
+    let counter = &mut ctx.accounts.counter;
+    counter.authority = gift.key();
+    counter.add(args.count)?;
+    counter.gifted_count = args.count;
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Initialized, counter.count);
+    emit!(CounterInitialized {
+        counter: counter.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// Hands the counter to the recipient, who is the first entry in its access
+// log.
+pub fn process_claim_gift(ctx: Context<ClaimGift>) -> Result<()> {
+    require!(
+        !ctx.accounts.gift.is_expired(clock::now()?),
+        CounterError::GiftExpired
+    );
+    let counter = &mut ctx.accounts.counter;
+    counter.authority = ctx.accounts.recipient.key();
+    emit!(GiftClaimed {
+        counter: counter.key(),
+        recipient: counter.authority,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    access_log::record(
+        &ctx.accounts.access_log,
+        counter.key(),
+        counter.authority,
+        AdminAction::ClaimGift,
+    )
+}
+
+// Closes an expired, unclaimed gift, refunding the creator.
+pub fn process_reclaim_gift(ctx: Context<ReclaimGift>) -> Result<()> {
+    require!(
+        ctx.accounts.gift.is_expired(clock::now()?),
+        CounterError::GiftNotExpired
+    );
+    require!(
+        ctx.accounts.vault.lamports() == 0,
+        CounterError::VaultNotEmpty
+    );
+    let counter = &mut ctx.accounts.counter;
+    emit!(GiftReclaimed {
+        counter: counter.key(),
+        creator: ctx.accounts.creator.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateGift<'info> {
+    // As in Initialize.
+    #[account(init, payer = creator, space = 8 + Counter::INIT_SPACE)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Gift::INIT_SPACE,
+        seeds = [GIFT_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub gift: Account<'info, Gift>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimGift<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [GIFT_SEED, counter.key().as_ref()],
+        bump = gift.bump,
+        has_one = recipient @ CounterError::Unauthorized,
+        has_one = creator @ CounterError::Unauthorized
+    )]
+    pub gift: Account<'info, Gift>,
+
+    // See UpdateCounter in lib.rs. The recipient pays for it, as they
+    // would for their first settings change.
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = AccessLog::SPACE,
+        seeds = [ACCESS_LOG_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub access_log: AccountLoader<'info, AccessLog>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// CHECK: only receives the Gift's rent; has_one checks it's the
+    /// creator.
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimGift<'info> {
+    #[account(mut, close = creator)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [GIFT_SEED, counter.key().as_ref()],
+        bump = gift.bump,
+        has_one = creator @ CounterError::Unauthorized
+    )]
+    pub gift: Account<'info, Gift>,
+
+    // An unclaimed gift has no authority that could empty it, so anything
+    // sent here keeps the gift from being reclaimed.
+    #[account(seeds = [VAULT_SEED, counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: only receives the rent; has_one checks it's the creator.
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+}
@@ -0,0 +1,232 @@
+// ============================================================================
+// GROUPS - one set of rules for many counters
+// ============================================================================
+
+// An operator running hundreds of counters sets each one's rules one
+// counter at a time: a fee change, or pausing everything during an
+// incident, is hundreds of transactions. A Group holds those rules once:
+//
+//   - paused: no member can be incremented
+//   - fee_lamports: what each increment of a member costs, paid into that
+//     member's vault (see lease.rs), as increment_paid does
+//   - deny_cpi: members only take increments made at the top level of a
+//     transaction, as a counter's own deny_cpi does (see cpi_guard.rs)
+//
+// update_group changes them, for every member at once.
+//
+// A counter joins with join_group, signed by its owner, who must also be
+// the group's authority, and from then on only increment_grouped may
+// increment it: that's the instruction that takes the group, so it's the
+// only one that can apply the rules. That makes being in a group another
+// of the ROUTES modes (see Counter in lib.rs), like role_weighted: a field,
+// since "flags" is full. The counter's own settings (ONCE_PER_SLOT, its
+// deny_cpi, the alarm) still apply on top. leave_group takes it out again,
+// signed by its owner alone.
+use anchor_lang::prelude::*;
+
+use crate::access_log::AdminAction;
+use crate::args::check_args_version;
+use crate::cpi_guard::check_top_level;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    AlarmTriggered, CounterError, CounterGroupChanged, CounterIncremented, FeePaid, GroupUpdated,
+    Route, Version, FEES_ENABLED,
+};
+
+/// Rules shared by every counter that joins it (see group.rs).
+#[account]
+#[derive(InitSpace)]
+pub struct Group {
+    /// Changes the rules, and owns every counter that joins.
+    pub authority: Pubkey,
+    /// While set, no member can be incremented.
+    pub paused: bool,
+    /// Charged per increment of a member, in lamports, into that member's
+    /// vault. 0 for free.
+    pub fee_lamports: u64,
+    /// Members only take increments made at the top level of a
+    /// transaction.
+    pub deny_cpi: bool,
+    /// Counters in the group, a count.
+    pub members: u64,
+}
+
+// update_group's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GroupArgs {
+    pub version: u8,
+    pub paused: bool,
+    pub fee_lamports: u64,
+    pub deny_cpi: bool,
+}
+
+impl GroupArgs {
+    pub const VERSION: u8 = 1;
+}
+
+pub fn process_initialize_group(ctx: Context<InitializeGroup>) -> Result<()> {
+    ctx.accounts.group.authority = ctx.accounts.authority.key();
+    Ok(())
+}
+
+pub fn process_update_group(ctx: Context<UpdateGroup>, args: GroupArgs) -> Result<()> {
+    check_args_version(args.version, GroupArgs::VERSION)?;
+    let group = &mut ctx.accounts.group;
+    group.paused = args.paused;
+    group.fee_lamports = args.fee_lamports;
+    group.deny_cpi = args.deny_cpi;
+
+    emit!(GroupUpdated {
+        group: group.key(),
+        paused: group.paused,
+        fee_lamports: group.fee_lamports,
+        deny_cpi: group.deny_cpi,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+pub fn process_join_group(ctx: Context<JoinGroup>) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    let counter = &mut ctx.accounts.guard.counter;
+    // Not in another group, and no other route, role_weighted included.
+    counter.check_route_free(0)?;
+    require!(!counter.role_weighted, CounterError::ConflictingModes);
+    counter.group = group.key();
+    debug_assert_invariants!(counter);
+    group.members += 1;
+
+    emit!(CounterGroupChanged {
+        counter: counter.key(),
+        group: group.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    ctx.accounts.guard.log(AdminAction::JoinGroup)
+}
+
+pub fn process_leave_group(ctx: Context<LeaveGroup>) -> Result<()> {
+    let counter = &mut ctx.accounts.guard.counter;
+    require_keys_eq!(
+        counter.group,
+        ctx.accounts.group.key(),
+        CounterError::WrongGroup
+    );
+    counter.group = Pubkey::default();
+    ctx.accounts.group.members -= 1;
+
+    emit!(CounterGroupChanged {
+        counter: counter.key(),
+        group: Pubkey::default(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    ctx.accounts.guard.log(AdminAction::LeaveGroup)
+}
+
+// increment, under the group's rules.
+pub fn process_increment_grouped(ctx: Context<IncrementGrouped>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    require!(!group.paused, CounterError::GroupPaused);
+    if group.deny_cpi {
+        check_top_level()?;
+    }
+    let payment = &mut ctx.accounts.payment;
+    payment.counter.check_route(Route::Grouped)?;
+    let before = payment.counter.count;
+    payment.counter.increment()?;
+    debug_assert_invariants!(payment.counter);
+
+    // Nothing on localnet (see FEES_ENABLED in lib.rs).
+    let fee = if FEES_ENABLED { group.fee_lamports } else { 0 };
+    payment.pay_vault(fee)?;
+
+    let payer = payment.payer.key();
+    let counter = &mut payment.counter;
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if fee > 0 {
+        emit!(FeePaid {
+            counter: counter.key(),
+            payer,
+            fee,
+            referrer: None,
+            referrer_share: 0,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeGroup<'info> {
+    // A new keypair, like a counter's.
+    #[account(init, payer = authority, space = 8 + Group::INIT_SPACE)]
+    pub group: Account<'info, Group>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGroup<'info> {
+    #[account(mut, has_one = authority @ CounterError::Unauthorized)]
+    pub group: Account<'info, Group>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGroup<'info> {
+    // The counter's owner; a renter (see lease.rs) can't move it.
+    pub guard: AuthorityGuard<'info>,
+
+    // The same signer has to run the group too, or anyone could put
+    // their counters under someone else's fees and pauses, or anyone's
+    // counters under theirs.
+    #[account(
+        mut,
+        constraint = group.authority == guard.authority.key() @ CounterError::Unauthorized
+    )]
+    pub group: Account<'info, Group>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveGroup<'info> {
+    // The owner alone: whoever runs the group now, the counter stays its
+    // owner's.
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(mut)]
+    pub group: Account<'info, Group>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementGrouped<'info> {
+    // The payer signs even when the fee is 0: update_group can change it
+    // between signing and landing.
+    pub payment: FeePayment<'info>,
+
+    // "has_one = group" on the counter, which is the guard's.
+    #[account(constraint = payment.counter.group == group.key() @ CounterError::WrongGroup)]
+    pub group: Account<'info, Group>,
+}
@@ -0,0 +1,123 @@
+// ============================================================================
+// GUARDS - account bundles shared by many instructions
+// ============================================================================
+
+// An accounts struct can include another one as a field ("composite"
+// accounts): the inner struct's accounts are passed in its place, in its
+// order, and its constraints are checked like the outer struct's own.
+// These bundle the groups of accounts that kept being copied from one
+// instruction to the next:
+//
+//   - AuthorityGuard: a counter, its owner signing, and the access log the
+//     change is recorded in. For owner-only settings. (UpdateCounter in
+//     lib.rs is the lease-aware version, for settings a renter can change
+//     too.)
+//   - FeePayment: a counter, its vault, and whoever pays into it.
+//
+// The outer struct refers to their accounts through the field, e.g.
+// "payer = guard.authority" and "seeds = [.., guard.counter.key().as_ref()]".
+// Anchor's "close" only takes a field of the outer struct, so instructions
+// that refund an account to the owner close it in their handler instead
+// (see close_to_owner). And Anchor wants a system_program of the outer
+// struct's own before it creates accounts there, so those list it again.
+// A transaction carries each address once, so that costs nothing.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+use crate::access_log::{self, AccessLog, AdminAction, ACCESS_LOG_SEED};
+use crate::{Counter, CounterError, VAULT_SEED};
+
+#[derive(Accounts)]
+pub struct AuthorityGuard<'info> {
+    // "mut" because some settings change the counter's flags. For the
+    // ones that don't, it only costs the write lock.
+    #[account(mut, has_one = authority @ CounterError::Unauthorized)]
+    pub counter: Account<'info, Counter>,
+
+    // See UpdateCounter in lib.rs.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AccessLog::SPACE,
+        seeds = [ACCESS_LOG_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub access_log: AccountLoader<'info, AccessLog>,
+
+    // Owners keep these settings during a lease (see lease.rs), so this
+    // is the owner, not lease::effective_authority.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AuthorityGuard<'info> {
+    pub fn log(&self, action: AdminAction) -> Result<()> {
+        access_log::record(
+            &self.access_log,
+            self.counter.key(),
+            self.authority.key(),
+            action,
+        )
+    }
+
+    // What "close = authority" would do: sends "account"'s rent to the
+    // owner and closes it.
+    pub fn close_to_owner<T: AccountSerialize + AccountDeserialize + Owner + Clone>(
+        &self,
+        account: &Account<'info, T>,
+    ) -> Result<()> {
+        account.close(self.authority.to_account_info())
+    }
+}
+
+#[derive(Accounts)]
+pub struct FeePayment<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // Where the counter's fees go (see lease.rs).
+    #[account(mut, seeds = [VAULT_SEED, counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FeePayment<'info> {
+    // Moves "lamports" from the payer to "to". Does nothing for 0, so
+    // callers don't have to check for free or exempt payments.
+    //
+    // The vaults are system accounts at PDAs, which only exist once they
+    // hold lamports, and the runtime refuses a transfer that leaves one
+    // with less than the rent-exempt minimum. So a payment into an empty
+    // vault creates it: the payer tops it up to the minimum, as they'd
+    // pay the rent of an "init_if_needed" account.
+    pub fn pay(&self, to: AccountInfo<'info>, lamports: u64) -> Result<()> {
+        if lamports == 0 {
+            return Ok(());
+        }
+        let lamports = if to.lamports() == 0 {
+            lamports.max(Rent::get()?.minimum_balance(0))
+        } else {
+            lamports
+        };
+        system_program::transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.payer.to_account_info(),
+                    to,
+                },
+            ),
+            lamports,
+        )
+    }
+
+    pub fn pay_vault(&self, lamports: u64) -> Result<()> {
+        self.pay(self.vault.to_account_info(), lamports)
+    }
+}
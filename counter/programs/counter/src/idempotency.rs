@@ -0,0 +1,103 @@
+// ============================================================================
+// IDEMPOTENT INCREMENTS - retry safely without counting twice
+// ============================================================================
+
+// A client sends an increment, the RPC times out, and the client can't tell
+// whether the transaction landed. If it retries and both land, the counter
+// goes up by 2.
+//
+// "increment_with_key" fixes that: the client picks a random 16-byte key
+// once per logical increment and reuses it for every retry. The first
+// transaction to land creates a small "receipt" account for that key; any
+// later one finds the receipt and fails with DuplicateIdempotencyKey, which
+// the client can treat as "already done".
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{AlarmTriggered, Counter, CounterError, CounterIncremented, Version};
+
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+/// Proof that the increment for one key has happened.
+#[account]
+#[derive(InitSpace)]
+pub struct Receipt {
+    /// The counter that was incremented. Pubkey::default() (all zeros)
+    /// means the receipt was only just created.
+    pub counter: Pubkey,
+    /// The counter's value right after this increment.
+    pub count: u64,
+    /// The slot the increment landed in.
+    pub slot: u64,
+    pub bump: u8,
+}
+
+pub fn process_increment_with_key(
+    ctx: Context<IncrementWithKey>,
+    _idempotency_key: [u8; 16],
+) -> Result<()> {
+    let receipt = &mut ctx.accounts.receipt;
+
+    // "init_if_needed" hands us the existing receipt if this key was used
+    // before. "require!" returns the given error if the condition is false.
+    require!(
+        receipt.counter == Pubkey::default(),
+        CounterError::DuplicateIdempotencyKey
+    );
+
+    let counter = &mut ctx.accounts.counter;
+    counter.check_modes()?;
+    let before = counter.count;
+    counter.increment()?;
+    debug_assert_invariants!(counter);
+
+    receipt.counter = counter.key();
+    receipt.count = counter.count;
+    receipt.slot = clock::slot()?;
+    receipt.bump = ctx.bumps.receipt;
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+// "#[instruction(...)]" lets the account constraints below use the
+// instruction's arguments - here, to put the key in the receipt's seeds.
+#[instruction(idempotency_key: [u8; 16])]
+pub struct IncrementWithKey<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // One receipt per (counter, key) pair, at an address derived from both.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Receipt::INIT_SPACE,
+        seeds = [RECEIPT_SEED, counter.key().as_ref(), idempotency_key.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, Receipt>,
+
+    // Pays the rent for the receipt.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,126 @@
+// ============================================================================
+// INVARIANTS - checks that should always pass, runnable on demand
+// ============================================================================
+
+// The instructions are written so these always hold. assert_invariants
+// checks them anyway, so a keeper can run it on a schedule (or simulate
+// it, for free) and find out the moment one breaks, instead of when
+// something downstream misbehaves:
+//
+//   - at most one of the Counter::ROUTES modes (role_weighted and being
+//     in a group included) is on
+//   - the count is at most the lifetime count, which includes it
+//   - every replica passed in belongs to the counter and hasn't merged
+//     more than it counted (see replica.rs)
+//   - a mirrored counter's mint supply equals its count (see
+//     token_mirror.rs); burning tokens outside decrement_minted breaks this
+//   - the vault is either empty or holds at least its rent-exempt minimum
+//     (see lease.rs)
+//
+// Each check fails with its own error, so the failure says which one.
+// It's a read-only instruction: nothing is written, and it can be called
+// by anyone.
+//
+// Handlers also run the checks that only need the accounts they already
+// have, right after they change them (see debug_assert_invariants below).
+// A mistake that breaks an invariant then fails the very instruction that
+// made it, in the tests, instead of waiting for the next keeper run.
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{Counter, CounterError, Replica, COUNTER_MINT_SEED, VAULT_SEED};
+
+// Re-checks a counter's invariants (and a replica's, with "replica:") after
+// a handler has changed it, failing the instruction with the broken
+// invariant's error. Like "debug_assert!", it's compiled into debug builds
+// only, which is what "cargo test" makes; "anchor build" makes release
+// builds, so deployed programs don't pay for it. To run it on a deployed
+// program anyway, say a devnet canary, turn on the "invariant-checks"
+// feature:
+//
+//     anchor build -- --features devnet,invariant-checks
+macro_rules! debug_assert_invariants {
+    ($counter:expr) => {
+        #[cfg(any(debug_assertions, feature = "invariant-checks"))]
+        $crate::invariants::check_counter(&$counter)?;
+    };
+    (replica: $replica:expr, $root:expr) => {
+        #[cfg(any(debug_assertions, feature = "invariant-checks"))]
+        $crate::invariants::check_replica(&$replica, $root)?;
+    };
+}
+pub(crate) use debug_assert_invariants;
+
+pub fn check_counter(counter: &Counter) -> Result<()> {
+    require!(
+        (counter.flags & Counter::ROUTES).count_ones()
+            + u32::from(counter.role_weighted)
+            + u32::from(counter.in_group())
+            <= 1,
+        CounterError::ConflictingModes
+    );
+    require!(
+        counter.count <= counter.lifetime_count,
+        CounterError::LifetimeBelowCount
+    );
+    Ok(())
+}
+
+pub fn check_replica(replica: &Replica, root: Pubkey) -> Result<()> {
+    require_keys_eq!(replica.root, root, CounterError::ReplicaRootMismatch);
+    require!(
+        replica.merged <= replica.count,
+        CounterError::ReplicaOvermerged
+    );
+    Ok(())
+}
+
+pub fn check_mirror_supply(counter: &Counter, supply: u64) -> Result<()> {
+    require!(supply == counter.count, CounterError::MirrorSupplyMismatch);
+    Ok(())
+}
+
+pub fn check_vault(lamports: u64, rent_exempt_minimum: u64) -> Result<()> {
+    require!(
+        lamports == 0 || lamports >= rent_exempt_minimum,
+        CounterError::VaultBelowRent
+    );
+    Ok(())
+}
+
+// Replicas to check go in "remaining_accounts".
+pub fn process_assert_invariants<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AssertInvariants<'info>>,
+) -> Result<()> {
+    let counter = &ctx.accounts.counter;
+    check_counter(counter)?;
+
+    for info in ctx.remaining_accounts {
+        let replica = Account::<Replica>::try_from(info)?;
+        check_replica(&replica, counter.key())?;
+    }
+
+    if counter.has_flag(Counter::TOKEN_MIRRORED) {
+        let Some(mint) = &ctx.accounts.mirror_mint else {
+            return err!(CounterError::MirrorMintMissing);
+        };
+        check_mirror_supply(counter, mint.supply)?;
+    }
+
+    check_vault(
+        ctx.accounts.vault.lamports(),
+        Rent::get()?.minimum_balance(0),
+    )
+}
+
+#[derive(Accounts)]
+pub struct AssertInvariants<'info> {
+    pub counter: Account<'info, Counter>,
+
+    #[account(seeds = [VAULT_SEED, counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    // Only needed for a mirrored counter.
+    #[account(seeds = [COUNTER_MINT_SEED, counter.key().as_ref()], bump)]
+    pub mirror_mint: Option<InterfaceAccount<'info, Mint>>,
+}
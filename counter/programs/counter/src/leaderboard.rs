@@ -0,0 +1,217 @@
+// ============================================================================
+// LEADERBOARD - the highest counters, kept in order on-chain
+// ============================================================================
+
+// Ranking counters off-chain means fetching every one of them
+// (getProgramAccounts) and sorting. The Leaderboard PDA keeps the top
+// LEADERBOARD_SIZE instead, highest count first, so a front-end reads one
+// account.
+//
+// A counter gets onto the board, or moves on it, when its count is
+// submitted:
+//
+//   - by "increment", when the Leaderboard is passed as an extra
+//     ("remaining") account after the counter
+//   - by "update_leaderboard", which anyone can send for any counter, for
+//     the other ways a count changes (increment_paid, merge_replicas, ...)
+//
+// Submitting is cheap when it changes nothing: a counter that isn't on a
+// full board and doesn't beat its lowest entry is left off after a scan of
+// LEADERBOARD_SIZE entries. Otherwise the entry moves up (or down) to its
+// place, one swap at a time, like a step of insertion sort.
+//
+// The board shows each counter's count as of its last submission, so a
+// counter that was decremented or reset since stays where it was until
+// it's submitted again or pushed off by a higher one. A counter that's
+// gone (closed, reaped, or reassigned to another program) can't be
+// submitted again, so anyone can take it off with
+// "evict_leaderboard_entry" instead.
+//
+// Gifted counters (see gift.rs) are refused: their count was set by
+// create_gift, not earned, and any count a gift can be made with would
+// otherwise buy a place at the top. They're evictable too, for entries
+// from before gifts were recorded.
+//
+// Like CounterBank (see bank.rs) it's zero-copy: only the entries that
+// move are touched.
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{Counter, CounterError};
+
+pub const LEADERBOARD_SEED: &[u8] = b"leaderboard";
+
+pub const LEADERBOARD_SIZE: usize = 25;
+
+/// The LEADERBOARD_SIZE counters with the highest counts, program-wide.
+#[account(zero_copy)]
+pub struct Leaderboard {
+    /// Entries in use, at the start of "entries".
+    pub len: u64,
+    /// Highest count first. Between equal counts, whoever got there first
+    /// stays ahead.
+    pub entries: [LeaderboardEntry; LEADERBOARD_SIZE],
+    pub bump: u8,
+    // Spelled out, as in AccessLogEntry (see access_log.rs).
+    pub padding: [u8; 7],
+}
+
+#[zero_copy]
+pub struct LeaderboardEntry {
+    pub counter: Pubkey,
+    pub count: u64,
+}
+
+impl Leaderboard {
+    pub const SPACE: usize = 8 + std::mem::size_of::<Leaderboard>();
+
+    // The entries in use, highest first.
+    pub fn ranked(&self) -> &[LeaderboardEntry] {
+        &self.entries[..self.len as usize]
+    }
+
+    // Records "count" for "counter". Returns whether the board changed.
+    pub fn submit(&mut self, counter: Pubkey, count: u64) -> bool {
+        let len = self.len as usize;
+        let mut index = match self.ranked().iter().position(|e| e.counter == counter) {
+            Some(index) if self.entries[index].count == count => return false,
+            Some(index) => index,
+            None if len < LEADERBOARD_SIZE => {
+                self.len += 1;
+                len
+            }
+            // Full, and not higher than the lowest entry, which it replaces
+            // otherwise.
+            None if count <= self.entries[len - 1].count => return false,
+            None => len - 1,
+        };
+        self.entries[index] = LeaderboardEntry { counter, count };
+
+        while index > 0 && self.entries[index - 1].count < count {
+            self.entries.swap(index - 1, index);
+            index -= 1;
+        }
+        while index + 1 < self.len as usize && self.entries[index + 1].count > count {
+            self.entries.swap(index, index + 1);
+            index += 1;
+        }
+        true
+    }
+
+    // Takes "counter" off the board, the entries below it moving up.
+    // Returns whether it was on it.
+    pub fn remove(&mut self, counter: Pubkey) -> bool {
+        let len = self.len as usize;
+        let Some(index) = self.ranked().iter().position(|e| e.counter == counter) else {
+            return false;
+        };
+        self.entries.copy_within(index + 1..len, index);
+        self.entries[len - 1] = LeaderboardEntry {
+            counter: Pubkey::default(),
+            count: 0,
+        };
+        self.len -= 1;
+        true
+    }
+}
+
+// Only earned counts go on the board.
+fn check_not_gifted(counter: &Counter) -> Result<()> {
+    require!(counter.gifted_count == 0, CounterError::GiftedCounter);
+    Ok(())
+}
+
+// Whether the account at "info" is still a Counter that could be
+// submitted: owned by us and with a Counter's discriminator. One in an
+// older layout counts, since migrate_counter can bring it back; a gifted
+// one doesn't.
+fn is_live_counter(info: &AccountInfo) -> Result<bool> {
+    if info.lamports() == 0 || info.owner != &crate::ID {
+        return Ok(false);
+    }
+    let data = info.try_borrow_data()?;
+    if data.len() < 8 || data[..8] != Counter::DISCRIMINATOR {
+        return Ok(false);
+    }
+    Ok(Counter::try_deserialize(&mut &data[..]).map_or(true, |c| c.gifted_count == 0))
+}
+
+// Submits "counter"'s count to the Leaderboard at "info", an extra account
+// passed to increment. There's only ever one Leaderboard, created at its
+// PDA by initialize_leaderboard, so the owner and discriminator checks of
+// AccountLoader are enough to know it's the one.
+pub fn submit<'info>(
+    info: &'info AccountInfo<'info>,
+    key: Pubkey,
+    counter: &Counter,
+) -> Result<()> {
+    check_not_gifted(counter)?;
+    let leaderboard = AccountLoader::<Leaderboard>::try_from(info)?;
+    // "load_mut" fails if the account wasn't passed as writable.
+    leaderboard.load_mut()?.submit(key, counter.count);
+    Ok(())
+}
+
+pub fn process_initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+    ctx.accounts.leaderboard.load_init()?.bump = ctx.bumps.leaderboard;
+    Ok(())
+}
+
+pub fn process_update_leaderboard(ctx: Context<UpdateLeaderboard>) -> Result<()> {
+    let counter = &ctx.accounts.counter;
+    check_not_gifted(counter)?;
+    ctx.accounts
+        .leaderboard
+        .load_mut()?
+        .submit(counter.key(), counter.count);
+    Ok(())
+}
+
+pub fn process_evict_leaderboard_entry(ctx: Context<EvictLeaderboardEntry>) -> Result<()> {
+    let counter = &ctx.accounts.counter;
+    require!(
+        !is_live_counter(counter)?,
+        CounterError::LeaderboardEntryLive
+    );
+    require!(
+        ctx.accounts.leaderboard.load_mut()?.remove(counter.key()),
+        CounterError::NotOnLeaderboard
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeLeaderboard<'info> {
+    // Anyone can create it; it starts empty.
+    #[account(
+        init,
+        payer = payer,
+        space = Leaderboard::SPACE,
+        seeds = [LEADERBOARD_SEED],
+        bump
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLeaderboard<'info> {
+    #[account(mut, seeds = [LEADERBOARD_SEED], bump = leaderboard.load()?.bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    pub counter: Account<'info, Counter>,
+}
+
+#[derive(Accounts)]
+pub struct EvictLeaderboardEntry<'info> {
+    #[account(mut, seeds = [LEADERBOARD_SEED], bump = leaderboard.load()?.bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+
+    /// CHECK: whatever is at the entry's address now; evicting only goes
+    /// ahead if it isn't a live, ungifted Counter.
+    pub counter: UncheckedAccount<'info>,
+}
@@ -0,0 +1,207 @@
+// ============================================================================
+// LEASES - rent out control of a counter for a while
+// ============================================================================
+
+// lease_authority hands a counter's settings to a "renter" until a
+// deadline, in exchange for lamports paid into the counter's vault. While
+// the lease runs, the renter is the counter's effective authority for
+// everything that uses UpdateCounter (modes, alarms, archiving) and the
+// owner isn't. When it expires, control goes back to the owner on its own:
+// nothing has to be called, because every check compares against the
+// clock.
+//
+// The owner keeps "authority" the whole time, and with it the things a
+// lease doesn't cover: the vault, fees, new leases, mints, replicas and
+// unfreezing (see freeze.rs). Those take AuthorityGuard (see guards.rs)
+// rather than UpdateCounter.
+//
+// The vault is a plain system account at a PDA of the counter. Anyone can
+// send lamports to it; only the owner can take them out (withdraw_vault).
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use nautilus_common::clock;
+
+use crate::access_log::{self, AccessLog, AdminAction, ACCESS_LOG_SEED};
+use crate::args::check_args_version;
+use crate::guards::*;
+use crate::{AuthorityLeased, Counter, CounterError, Version};
+
+pub const LEASE_SEED: &[u8] = b"lease";
+pub use nautilus_common::seeds::VAULT_SEED;
+
+/// A counter's settings, rented to "renter" for a while.
+#[account]
+#[derive(InitSpace)]
+pub struct Lease {
+    pub counter: Pubkey,
+    pub renter: Pubkey,
+    /// The lease is over from this moment on, in unix seconds.
+    pub expires_at: i64,
+    /// What the renter paid, in lamports.
+    pub price: u64,
+    pub bump: u8,
+}
+
+impl Lease {
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.expires_at
+    }
+}
+
+// lease_authority's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LeaseArgs {
+    pub version: u8,
+    // The renter.
+    pub to: Pubkey,
+    // In seconds.
+    pub duration: i64,
+    // In lamports, paid into the counter's vault.
+    pub price: u64,
+}
+
+impl LeaseArgs {
+    pub const VERSION: u8 = 1;
+}
+
+// Who controls the counter's settings right now: the renter during a
+// lease, the owner otherwise. "lease" is the counter's lease PDA, which
+// doesn't exist until the first lease.
+pub fn effective_authority(counter: &Account<Counter>, lease: &AccountInfo) -> Result<Pubkey> {
+    if let Some(lease) = load_lease(lease)? {
+        if lease.is_active(clock::now()?) {
+            return Ok(lease.renter);
+        }
+    }
+    Ok(counter.authority)
+}
+
+// The Lease at "info", or None if none was ever created there.
+fn load_lease(info: &AccountInfo) -> Result<Option<Lease>> {
+    if info.owner != &crate::ID {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(Lease::try_deserialize(&mut &data[..])?))
+}
+
+pub fn process_lease_authority(ctx: Context<LeaseAuthority>, args: LeaseArgs) -> Result<()> {
+    check_args_version(args.version, LeaseArgs::VERSION)?;
+    let LeaseArgs {
+        to,
+        duration,
+        price,
+        ..
+    } = args;
+    let now = clock::now()?;
+    require!(duration > 0, CounterError::InvalidLease);
+    // A fresh lease account is all zeros, so it reads as long expired.
+    require!(
+        !ctx.accounts.lease.is_active(now),
+        CounterError::LeaseActive
+    );
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.renter.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        ),
+        price,
+    )?;
+
+    let counter = &mut ctx.accounts.counter;
+    let lease = &mut ctx.accounts.lease;
+    lease.counter = counter.key();
+    lease.renter = to;
+    lease.expires_at = now
+        .checked_add(duration)
+        .ok_or(CounterError::InvalidLease)?;
+    lease.price = price;
+    lease.bump = ctx.bumps.lease;
+
+    access_log::record(
+        &ctx.accounts.access_log,
+        counter.key(),
+        ctx.accounts.authority.key(),
+        AdminAction::LeaseAuthority,
+    )?;
+    emit!(AuthorityLeased {
+        counter: counter.key(),
+        renter: to,
+        expires_at: lease.expires_at,
+        price,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// Sends "amount" lamports from the counter's vault to its owner.
+pub fn process_withdraw_vault(ctx: Context<WithdrawVault>, amount: u64) -> Result<()> {
+    let guard = &ctx.accounts.guard;
+    let counter = guard.counter.key();
+    // The vault is owned by the System Program, so moving lamports out of
+    // it takes a system transfer that the vault PDA signs.
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            guard.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: guard.authority.to_account_info(),
+            },
+            &[&[VAULT_SEED, counter.as_ref(), &[ctx.bumps.vault]]],
+        ),
+        amount,
+    )?;
+    guard.log(AdminAction::WithdrawVault)
+}
+
+#[derive(Accounts)]
+#[instruction(args: LeaseArgs)]
+pub struct LeaseAuthority<'info> {
+    #[account(mut, has_one = authority @ CounterError::Unauthorized)]
+    pub counter: Account<'info, Counter>,
+
+    // One lease per counter, reused by each new lease once the last one
+    // has expired.
+    #[account(
+        init_if_needed,
+        payer = renter,
+        space = 8 + Lease::INIT_SPACE,
+        seeds = [LEASE_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub lease: Account<'info, Lease>,
+
+    #[account(mut, seeds = [VAULT_SEED, counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    // See UpdateCounter in lib.rs.
+    #[account(
+        init_if_needed,
+        payer = renter,
+        space = AccessLog::SPACE,
+        seeds = [ACCESS_LOG_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub access_log: AccountLoader<'info, AccessLog>,
+
+    // Both sides sign: the owner agrees to the terms, the renter pays.
+    pub authority: Signer<'info>,
+    #[account(mut, constraint = renter.key() == args.to @ CounterError::Unauthorized)]
+    pub renter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVault<'info> {
+    // The owner; a renter can't take what the lease paid.
+    pub guard: AuthorityGuard<'info>,
+
+    #[account(mut, seeds = [VAULT_SEED, guard.counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+}
@@ -11,6 +11,9 @@
 // "prelude" is a common Rust convention - it's a module containing the most
 // commonly used items so you can import them all at once.
 use anchor_lang::prelude::*;
+// Helpers shared with the workspace's other programs (see common/ at the
+// workspace root). "clock" reads the Clock sysvar; "seq" numbers events.
+use nautilus_common::{clock, seq};
 
 // This macro sets the program's unique address on the Solana blockchain.
 //
@@ -21,114 +24,1401 @@ use anchor_lang::prelude::*;
 // generated when you run "anchor build" for the first time.
 // Every program deployed to Solana has a unique address, just like every
 // website has a unique URL.
+//
+// The program lives at a different address on each "cluster" (localnet,
+// devnet, mainnet), so we pick one at compile time with a cargo feature:
+//
+//     anchor build                          -> localnet (the default)
+//     anchor build -- --features localnet   -> localnet, fees off
+//     anchor build -- --features devnet     -> devnet
+//     anchor build -- --features mainnet    -> mainnet
+//
+// "#[cfg(feature = "...")]" keeps the line after it only when that feature
+// is turned on, so exactly one "declare_id!" survives.
+#[cfg(feature = "devnet")]
+declare_id!("DyjzWj2QdUAzMjdstmUgDZUDF6oZKoiv4Ym3bMX95iU5");
+
+#[cfg(feature = "mainnet")]
+declare_id!("EC2qL9qXo65QZ4HbNwrnDK3E2Y3GTLttwYJrwnP6i5cC");
+
+#[cfg(not(any(feature = "devnet", feature = "mainnet")))]
 declare_id!("2LUoJnKc5maGZYcyqMcGk2WwdKwTygxwRP7uqBUu6h6y");
 
-// "#[program]" is an "attribute macro". Attributes start with "#[" and end
-// with "]". They modify the thing that comes after them.
-//
-// This attribute tells Anchor: "the module below contains the program's
-// instruction handlers" (the functions users can call).
-#[program]
+// Building for two clusters at once is always a mistake, so refuse to
+// compile instead of silently picking one.
+#[cfg(any(
+    all(feature = "localnet", feature = "devnet"),
+    all(feature = "localnet", feature = "mainnet"),
+    all(feature = "devnet", feature = "mainnet"),
+))]
+compile_error!("Enable at most one of the \"localnet\", \"devnet\" and \"mainnet\" features");
+
+// Defaults that differ per cluster hang off the same features. An explicit
+// "localnet" build charges no increment fees (see increment_fee in fees.rs
+// and increment_grouped in group.rs), so wallets on a local validator don't
+// need funding to try out paid counters. The plain build charges like
+// devnet and mainnet do, so the tests see the fees.
+pub const FEES_ENABLED: bool = !cfg!(feature = "localnet");
+
+// "mod logging;" tells Rust to include the code in src/logging.rs as a
+// module named "logging". "use logging::*;" then imports its public items
+// so we can call "log_count" directly.
+pub mod logging;
+use logging::*;
+
+// Larger features live in their own files. "pub use bank::*;" re-exports
+// everything public from bank.rs at the top of the crate, which is where
+// the "#[program]" macro below expects to find the accounts structs.
+pub mod bank;
+pub use bank::*;
+pub mod program_info;
+pub use program_info::*;
+pub mod version;
+pub use version::*;
+pub mod events;
+pub use events::*;
+pub mod idempotency;
+pub use idempotency::*;
+pub mod replica;
+pub use replica::*;
+pub mod wormhole;
+pub use wormhole::*;
+pub mod event_tree;
+pub use event_tree::*;
+pub mod confidential;
+pub use confidential::*;
+pub mod token_mirror;
+pub use token_mirror::*;
+pub mod burn;
+pub use burn::*;
+pub mod access_log;
+pub use access_log::*;
+pub mod lease;
+pub use lease::*;
+pub mod fees;
+pub use fees::*;
+pub mod badges;
+pub use badges::*;
+pub mod vote_escrow;
+pub use vote_escrow::*;
+pub mod commit_reveal;
+pub use commit_reveal::*;
+pub mod finalize;
+pub use finalize::*;
+pub mod invariants;
+pub use invariants::*;
+pub mod config;
+pub use config::*;
+pub mod cpi_guard;
+pub use cpi_guard::*;
+pub mod stats;
+pub use stats::*;
+pub mod migrate;
+pub use migrate::*;
+pub mod guards;
+pub use guards::*;
+pub mod roles;
+pub use roles::*;
+pub mod templates;
+pub use templates::*;
+pub mod args;
+pub use args::*;
+pub mod getters;
+pub use getters::*;
+pub mod reap;
+pub use reap::*;
+pub mod sponsor;
+pub use sponsor::*;
+pub mod gift;
+pub use gift::*;
+pub mod leaderboard;
+pub use leaderboard::*;
+pub mod tags;
+pub use tags::*;
+pub mod spill;
+pub use spill::*;
+pub mod twac;
+pub use twac::*;
+pub mod deltas;
+pub use deltas::*;
+pub mod freeze;
+pub use freeze::*;
+pub mod sweep;
+pub use sweep::*;
+pub mod funded;
+pub use funded::*;
+pub mod checksum;
+pub use checksum::*;
+pub mod preview;
+pub use preview::*;
+pub mod group;
+pub use group::*;
+pub mod compressed;
+pub use compressed::*;
+
+// A "security.txt" for the program: contact details baked into the
+// deployed binary, so anyone who finds a bug can report it privately.
+// Explorers and tools like "query-security-txt" display it.
+//
+// It's left out when this crate is used as a library by other programs
+// ("no-entrypoint"), which would otherwise end up with two of them.
+#[cfg(not(feature = "no-entrypoint"))]
+solana_security_txt::security_txt! {
+    name: "Nautilus Counter",
+    project_url: "https://github.com/alexanderzliu/nautilus",
+    contacts: "link:https://github.com/alexanderzliu/nautilus/security/advisories/new",
+    policy: "https://github.com/alexanderzliu/nautilus/blob/main/SECURITY.md",
+    source_code: "https://github.com/alexanderzliu/nautilus"
+}
+
+// "#[program]" is an "attribute macro". Attributes start with "#[" and end
+// with "]". They modify the thing that comes after them.
+//
+// This attribute tells Anchor: "the module below contains the program's
+// instruction handlers" (the functions users can call).
+#[program]
+
+// "pub" means "public" - this can be accessed from outside this file.
+// "mod" declares a "module" - a way to organize code in Rust (like a folder).
+// "counter" is the name we're giving this module.
+// "{" starts the module's body (everything inside the curly braces belongs
+// to this module).
+pub mod counter {
+
+    // "use" imports items. "super" refers to the parent scope (the code
+    // outside this module). "::*" means "import everything from there".
+    // This lets us use the imports from line 14 inside this module.
+    use super::*;
+
+    // ========================================================================
+    // INSTRUCTION #1: initialize
+    // Creates a new counter account and sets its value to 0
+    // ========================================================================
+
+    // "pub" = public (can be called from outside).
+    // "fn" = "function" (declares a function).
+    // "initialize" = the name of this function/instruction.
+    //
+    // "(ctx: Context<Initialize>)" = this function takes one parameter:
+    //   - "ctx" is the parameter name (short for "context")
+    //   - ":" separates the name from its type
+    //   - "Context<Initialize>" is the type. "Context" is a generic type
+    //     from Anchor, and "<Initialize>" specifies which accounts struct
+    //     to use (defined below on line 97).
+    //
+    // "->" indicates what the function returns.
+    // "Result<()>" is the return type:
+    //   - "Result" is an enum that's either Ok (success) or Err (failure)
+    //   - "<()>" means on success, we return "()" which is the "unit type"
+    //     (Rust's version of void/nothing/null - no meaningful return value)
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+
+        // "let" declares a new variable.
+        // "counter" is the variable name.
+        // "=" assigns a value to it.
+        //
+        // "&mut" means "mutable reference". Let me break this down:
+        //   - "&" means "reference" (like a pointer - we're borrowing the
+        //     data, not copying it)
+        //   - "mut" means "mutable" (we're allowed to modify it)
+        //   - Without "mut", Rust variables are immutable (read-only) by
+        //     default!
+        //
+        // "ctx" is our context parameter from above.
+        // ".accounts" accesses the "accounts" field of the context (this
+        //   contains all the accounts passed to this instruction).
+        // ".counter" accesses the specific account named "counter" (this
+        //   name matches the field in the Initialize struct below).
+        let counter = &mut ctx.accounts.counter;
+
+        // "counter.count" accesses the "count" field of the counter account.
+        // "= 0" sets it to zero.
+        // ";" ends the statement (most lines in Rust end with semicolons).
+        counter.count = 0;
+
+        // Whoever creates the counter controls its settings.
+        // ".key()" gets an account's address (its public key).
+        counter.authority = ctx.accounts.user.key();
+        debug_assert_invariants!(counter);
+
+        // Log a message to Solana's transaction logs (similar to
+        // console.log() in JavaScript). See logging.rs for why this isn't
+        // a formatted "msg!" string.
+        log_count(LogEvent::Initialized, counter.count);
+
+        // "emit!" publishes an event (see events.rs) that off-chain apps
+        // can subscribe to. ".key()" is the account's address.
+        emit!(CounterInitialized {
+            counter: counter.key(),
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+
+        // "Ok(())" returns a successful result.
+        // "Ok" is one variant of the Result enum (the success case).
+        // "()" inside is the value we're returning (nothing/unit type).
+        //
+        // Note: no semicolon here! In Rust, the last expression in a function
+        // without a semicolon is the return value. Adding ";" would make this
+        // a statement instead of a return expression.
+        Ok(())
+
+    // "}" closes the function body.
+    }
+
+    // ========================================================================
+    // INSTRUCTION #2: increment
+    // Adds 1 to an existing counter
+    // ========================================================================
+
+    // Same structure as initialize, but uses "Context<Increment>" because
+    // this instruction needs different accounts (see Increment struct below).
+    //
+    // The "'info" lifetimes are there for the optional Leaderboard, which
+    // is loaded from "remaining_accounts" (see merge_replicas below).
+    pub fn increment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Increment<'info>>,
+    ) -> Result<()> {
+
+        // Get a mutable reference to the counter account.
+        // Same pattern as in initialize.
+        let counter = &mut ctx.accounts.counter;
+
+        // Enforce any optional rules the authority turned on for this
+        // counter (see "check_modes" below).
+        counter.check_modes()?;
+
+        // Remember the old count for the alarm check below.
+        let before = counter.count;
+
+        // Add 1 using the "increment" method defined on Counter below.
+        // The "?" at the end means "if this returned an error, stop here
+        // and return that error to the caller". It's a shortcut for
+        // checking the Result yourself.
+        counter.increment()?;
+        debug_assert_invariants!(counter);
+
+        // Log the new count.
+        log_count(LogEvent::Incremented, counter.count);
+        emit!(CounterIncremented {
+            counter: counter.key(),
+            count: counter.count,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+
+        // Raise the alarm if this increment reached the threshold (see
+        // "set_alarm_threshold" below).
+        if counter.check_alarm(before) {
+            emit!(AlarmTriggered {
+                counter: counter.key(),
+                count: counter.count,
+                threshold: counter.alarm_threshold,
+                event_seq: counter.next_event_seq(),
+                version: Version::CURRENT,
+            });
+        }
+
+        // If the client passed the Leaderboard as an extra account, the
+        // new count goes on it (see leaderboard.rs).
+        if let Some(info) = ctx.remaining_accounts.first() {
+            leaderboard::submit(info, counter.key(), counter)?;
+        }
+
+        // Return success.
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #3: initialize_bank
+    // Creates a CounterBank: 1,024 counters in one account (see bank.rs)
+    // ========================================================================
+
+    // Instructions for larger features just hand off to a function in that
+    // feature's file, so this module stays a readable list of everything
+    // the program can do.
+    pub fn initialize_bank(ctx: Context<InitializeBank>) -> Result<()> {
+        bank::process_initialize_bank(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #4: increment_index
+    // Adds 1 to one slot of a CounterBank
+    // ========================================================================
+
+    // Instructions can take arguments after "ctx". Clients pass them when
+    // they build the transaction, e.g. program.methods.incrementIndex(7).
+    pub fn increment_index(ctx: Context<IncrementIndex>, index: u16) -> Result<()> {
+        bank::process_increment_index(ctx, index)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #5: set_program_info
+    // Records the deployed version and git commit (see program_info.rs)
+    // ========================================================================
+
+    // Only the program's upgrade authority (the deployer) can call this.
+    pub fn set_program_info(ctx: Context<SetProgramInfo>, commit: [u8; 20]) -> Result<()> {
+        program_info::process_set_program_info(ctx, commit)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #6: get_version
+    // Returns the deployed instruction-set version (see version.rs)
+    // ========================================================================
+
+    // "Result<Version>" instead of "Result<()>": on success this returns a
+    // Version, which Anchor hands back to the caller as "return data".
+    pub fn get_version(ctx: Context<GetVersion>) -> Result<Version> {
+        version::process_get_version(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #7: increment_with_key
+    // Adds 1, at most once per idempotency key (see idempotency.rs)
+    // ========================================================================
+
+    // "[u8; 16]" = an array of exactly 16 bytes.
+    pub fn increment_with_key(
+        ctx: Context<IncrementWithKey>,
+        idempotency_key: [u8; 16],
+    ) -> Result<()> {
+        idempotency::process_increment_with_key(ctx, idempotency_key)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #8: set_once_per_slot
+    // Limits a counter to one increment per slot (a "heartbeat" counter)
+    // ========================================================================
+
+    // A "slot" is Solana's unit of time: one leader's turn to produce a
+    // block, roughly 400 milliseconds. With this mode on, the counter can
+    // go up at most once per slot, so its count measures how many slots
+    // something was alive for, not how many transactions it sent.
+    //
+    // Only the counter's authority can change this (see UpdateCounter).
+    pub fn set_once_per_slot(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        ctx.accounts.log(AdminAction::SetOncePerSlot)?;
+        let counter = &mut ctx.accounts.counter;
+        counter.set_flag(Counter::ONCE_PER_SLOT, enabled);
+        debug_assert_invariants!(counter);
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #9: set_alarm_threshold
+    // Sets the count at which the counter raises an alarm (0 turns it off)
+    // ========================================================================
+
+    // When an increment brings the count up to the threshold, the counter
+    // sets its ALARM_TRIGGERED flag and emits an AlarmTriggered event, which
+    // an off-chain watcher can turn into a page. The flag stays set
+    // ("sticky") until the authority calls acknowledge_alarm, so an alarm
+    // isn't lost if the watcher was down when it fired.
+    //
+    // The alarm fires when the count *reaches* the threshold, not on every
+    // increment above it, so setting a threshold the count has already
+    // passed doesn't fire anything.
+    pub fn set_alarm_threshold(ctx: Context<UpdateCounter>, threshold: u64) -> Result<()> {
+        ctx.accounts.log(AdminAction::SetAlarmThreshold)?;
+        let counter = &mut ctx.accounts.counter;
+        counter.alarm_threshold = threshold;
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #10: acknowledge_alarm
+    // Clears the ALARM_TRIGGERED flag
+    // ========================================================================
+
+    pub fn acknowledge_alarm(ctx: Context<UpdateCounter>) -> Result<()> {
+        ctx.accounts.log(AdminAction::AcknowledgeAlarm)?;
+        let counter = &mut ctx.accounts.counter;
+        counter.set_flag(Counter::ALARM_TRIGGERED, false);
+        debug_assert_invariants!(counter);
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #11: initialize_replica
+    // Creates a replica of a counter that can be incremented on its own
+    // (see replica.rs)
+    // ========================================================================
+
+    pub fn initialize_replica(ctx: Context<InitializeReplica>, replica_id: u16) -> Result<()> {
+        replica::process_initialize_replica(ctx, replica_id)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #12: increment_replica
+    // Adds 1 to a replica
+    // ========================================================================
+
+    pub fn increment_replica(ctx: Context<IncrementReplica>) -> Result<()> {
+        replica::process_increment_replica(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #13: merge_replicas
+    // Adds what the given replicas counted since their last merge to the
+    // counter
+    // ========================================================================
+
+    // The replicas are passed as extra ("remaining") accounts, so one
+    // instruction can merge as many as fit in a transaction.
+    //
+    // "'info" is written out here because remaining_accounts must live as
+    // long as the accounts struct for the replicas to be loaded from them.
+    pub fn merge_replicas<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MergeReplicas<'info>>,
+    ) -> Result<()> {
+        replica::process_merge_replicas(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #14: post_count
+    // Publishes a counter's value to other blockchains through Wormhole
+    // (see wormhole.rs)
+    // ========================================================================
+
+    pub fn post_count(ctx: Context<PostCount>, nonce: u32) -> Result<()> {
+        wormhole::process_post_count(ctx, nonce)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #15: initialize_remote_counter
+    // Creates a RemoteCounter that tracks a counter on another chain
+    // ========================================================================
+
+    pub fn initialize_remote_counter(
+        ctx: Context<InitializeRemoteCounter>,
+        emitter_chain: u16,
+        emitter_address: [u8; 32],
+        remote_counter_id: [u8; 32],
+    ) -> Result<()> {
+        wormhole::process_initialize_remote_counter(
+            ctx,
+            emitter_chain,
+            emitter_address,
+            remote_counter_id,
+        )
+    }
+
+    // ========================================================================
+    // INSTRUCTION #16: receive_remote_count
+    // Updates a RemoteCounter from a Wormhole message (VAA)
+    // ========================================================================
+
+    pub fn receive_remote_count(ctx: Context<ReceiveRemoteCount>, vaa_hash: [u8; 32]) -> Result<()> {
+        wormhole::process_receive_remote_count(ctx, vaa_hash)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #17: initialize_event_tree
+    // Sets up the Merkle tree that records increments (see event_tree.rs)
+    // ========================================================================
+
+    // Only the program's upgrade authority can call this, once.
+    pub fn initialize_event_tree(
+        ctx: Context<InitializeEventTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        event_tree::process_initialize_event_tree(ctx, max_depth, max_buffer_size)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #18: increment_logged
+    // Adds 1 and records the increment in the event tree
+    // ========================================================================
+
+    pub fn increment_logged(ctx: Context<IncrementLogged>) -> Result<()> {
+        event_tree::process_increment_logged(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #19: set_tree_logged
+    // Requires every increment of a counter to go through increment_logged
+    // ========================================================================
+
+    pub fn set_tree_logged(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        ctx.accounts.log(AdminAction::SetTreeLogged)?;
+        let counter = &mut ctx.accounts.counter;
+        if enabled {
+            counter.check_route_free(Counter::TREE_LOGGED)?;
+        }
+        counter.set_flag(Counter::TREE_LOGGED, enabled);
+        debug_assert_invariants!(counter);
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #20: initialize_confidential
+    // Creates a ConfidentialCounter, whose count is encrypted (see
+    // confidential.rs)
+    // ========================================================================
+
+    pub fn initialize_confidential(
+        ctx: Context<InitializeConfidential>,
+        elgamal_pubkey: [u8; 32],
+    ) -> Result<()> {
+        confidential::process_initialize_confidential(ctx, elgamal_pubkey)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #21: increment_confidential
+    // Adds an encrypted delta to a ConfidentialCounter
+    // ========================================================================
+
+    pub fn increment_confidential(
+        ctx: Context<IncrementConfidential>,
+        encrypted_delta: [u8; 64],
+    ) -> Result<()> {
+        confidential::process_increment_confidential(ctx, encrypted_delta)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #22: prove_threshold
+    // Proves a ConfidentialCounter's count is at least "min" without
+    // revealing it, and records that in a ThresholdAttestation
+    // ========================================================================
+
+    pub fn prove_threshold(ctx: Context<ProveThreshold>, min: u64) -> Result<()> {
+        confidential::process_prove_threshold(ctx, min)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #23: initialize_counter_mint
+    // Gives a counter a mint whose supply always equals the count (see
+    // token_mirror.rs)
+    // ========================================================================
+
+    pub fn initialize_counter_mint(ctx: Context<InitializeCounterMint>) -> Result<()> {
+        token_mirror::process_initialize_counter_mint(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #24: increment_minted
+    // Adds 1 to a mirrored counter and mints 1 token
+    // ========================================================================
+
+    pub fn increment_minted(ctx: Context<IncrementMinted>) -> Result<()> {
+        token_mirror::process_increment_minted(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #25: decrement_minted
+    // Burns 1 of the caller's tokens and subtracts 1 from a mirrored counter
+    // ========================================================================
+
+    pub fn decrement_minted(ctx: Context<DecrementMinted>) -> Result<()> {
+        token_mirror::process_decrement_minted(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #26: set_burn_mint
+    // Picks the token decrement_by_burn burns (see burn.rs)
+    // ========================================================================
+
+    pub fn set_burn_mint(ctx: Context<SetBurnMint>) -> Result<()> {
+        burn::process_set_burn_mint(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #27: decrement_by_burn
+    // Burns "amount" of the caller's tokens to subtract "amount" from a
+    // counter
+    // ========================================================================
+
+    pub fn decrement_by_burn(ctx: Context<DecrementByBurn>, amount: u64) -> Result<()> {
+        burn::process_decrement_by_burn(ctx, amount)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #28: archive
+    // Freezes a counter's count without deleting anything
+    // ========================================================================
+
+    // There's no "close" for counters: closing an account deletes its data
+    // for good, and some counters have to be kept for the record. An
+    // archived counter keeps every byte, but its count can't change and
+    // client listings skip it (see CounterClient::list_counters).
+    pub fn archive(ctx: Context<UpdateCounter>) -> Result<()> {
+        ctx.accounts.log(AdminAction::Archive)?;
+        let counter = &mut ctx.accounts.counter;
+        counter.set_flag(Counter::ARCHIVED, true);
+        debug_assert_invariants!(counter);
+        emit!(CounterArchived {
+            counter: counter.key(),
+            archived: true,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #29: unarchive
+    // Lets an archived counter change again
+    // ========================================================================
+
+    pub fn unarchive(ctx: Context<UpdateCounter>) -> Result<()> {
+        ctx.accounts.log(AdminAction::Unarchive)?;
+        let counter = &mut ctx.accounts.counter;
+        counter.set_flag(Counter::ARCHIVED, false);
+        debug_assert_invariants!(counter);
+        emit!(CounterArchived {
+            counter: counter.key(),
+            archived: false,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #30: lease_authority
+    // Hands a counter's settings to "to" for "duration" seconds, for
+    // "price" lamports paid into the counter's vault (see lease.rs)
+    // ========================================================================
+
+    pub fn lease_authority(ctx: Context<LeaseAuthority>, args: LeaseArgs) -> Result<()> {
+        lease::process_lease_authority(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #31: withdraw_vault
+    // Sends lamports from a counter's vault to its owner
+    // ========================================================================
+
+    pub fn withdraw_vault(ctx: Context<WithdrawVault>, amount: u64) -> Result<()> {
+        lease::process_withdraw_vault(ctx, amount)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #32: set_increment_fee
+    // Charges "fee_lamports" per increment, "referral_bps" of it shared
+    // with referrers (see fees.rs). A fee of 0 turns charging off
+    // ========================================================================
+
+    pub fn set_increment_fee(ctx: Context<SetIncrementFee>, args: IncrementFeeArgs) -> Result<()> {
+        fees::process_set_increment_fee(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #33: increment_paid
+    // Adds 1 to a counter that charges a fee, crediting "referrer" if given
+    // ========================================================================
+
+    pub fn increment_paid(ctx: Context<IncrementPaid>, referrer: Option<Pubkey>) -> Result<()> {
+        fees::process_increment_paid(ctx, referrer)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #34: withdraw_referral
+    // Sends lamports from a referrer's vault to the referrer
+    // ========================================================================
+
+    pub fn withdraw_referral(ctx: Context<WithdrawReferral>, amount: u64) -> Result<()> {
+        fees::process_withdraw_referral(ctx, amount)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #35: increment_contributed
+    // Adds 1 to the counter and to the signer's contribution count (see
+    // badges.rs)
+    // ========================================================================
+
+    pub fn increment_contributed(ctx: Context<IncrementContributed>) -> Result<()> {
+        badges::process_increment_contributed(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #36: claim_badge
+    // Creates the signer's Badge for a contribution tier they reached
+    // ========================================================================
+
+    pub fn claim_badge(ctx: Context<ClaimBadge>, tier: u8) -> Result<()> {
+        badges::process_claim_badge(ctx, tier)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #37: set_vote_weighted
+    // Makes increments count locked "mint" tokens (see vote_escrow.rs), or
+    // turns that off
+    // ========================================================================
+
+    pub fn set_vote_weighted(ctx: Context<SetVoteWeighted>, enabled: bool) -> Result<()> {
+        vote_escrow::process_set_vote_weighted(ctx, enabled)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #38: lock_tokens
+    // Locks "amount" tokens for "duration" seconds to get a vote weight
+    // ========================================================================
+
+    pub fn lock_tokens(ctx: Context<LockTokens>, amount: u64, duration: i64) -> Result<()> {
+        vote_escrow::process_lock_tokens(ctx, amount, duration)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #39: increment_weighted
+    // Adds the signer's lockup weight to a vote-weighted counter
+    // ========================================================================
+
+    pub fn increment_weighted(ctx: Context<IncrementWeighted>) -> Result<()> {
+        vote_escrow::process_increment_weighted(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #40: unlock
+    // Returns locked tokens once their lock has ended
+    // ========================================================================
+
+    pub fn unlock(ctx: Context<Unlock>) -> Result<()> {
+        vote_escrow::process_unlock(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #41: set_commit_reveal
+    // Requires every increment of a counter to go through commit-reveal
+    // rounds (see commit_reveal.rs)
+    // ========================================================================
+
+    pub fn set_commit_reveal(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        ctx.accounts.log(AdminAction::SetCommitReveal)?;
+        let counter = &mut ctx.accounts.counter;
+        if enabled {
+            counter.check_route_free(Counter::COMMIT_REVEAL)?;
+        }
+        counter.set_flag(Counter::COMMIT_REVEAL, enabled);
+        debug_assert_invariants!(counter);
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #42: open_commit_round
+    // Starts a round: commits until "commit_deadline", reveals until
+    // "reveal_deadline", each commit putting down "deposit" lamports and
+    // each reveal adding at most "max_value"
+    // ========================================================================
+
+    pub fn open_commit_round(ctx: Context<OpenCommitRound>, args: CommitRoundArgs) -> Result<()> {
+        commit_reveal::process_open_commit_round(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #43: commit
+    // Commits to a contribution without revealing it
+    // ========================================================================
+
+    pub fn commit(ctx: Context<Commit>, hash: [u8; 32]) -> Result<()> {
+        commit_reveal::process_commit(ctx, hash)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #44: reveal
+    // Adds a committed contribution to the count and refunds the deposit
+    // ========================================================================
+
+    pub fn reveal(ctx: Context<Reveal>, value: u64, salt: [u8; 32]) -> Result<()> {
+        commit_reveal::process_reveal(ctx, value, salt)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #45: forfeit_commit
+    // Sends the deposit of a commit that wasn't revealed in time to the vault
+    // ========================================================================
+
+    pub fn forfeit_commit(ctx: Context<ForfeitCommit>, round: u64) -> Result<()> {
+        commit_reveal::process_forfeit_commit(ctx, round)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #46: set_end_time
+    // Sets when a counter can be finalized (see finalize.rs)
+    // ========================================================================
+
+    pub fn set_end_time(ctx: Context<SetEndTime>, end_time: i64) -> Result<()> {
+        finalize::process_set_end_time(ctx, end_time)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #47: finalize
+    // Freezes a counter for good after its end time and records the result
+    // ========================================================================
+
+    pub fn finalize(ctx: Context<Finalize>) -> Result<()> {
+        finalize::process_finalize(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #48: assert_invariants
+    // Fails if any of a counter's invariants doesn't hold (see
+    // invariants.rs)
+    // ========================================================================
+
+    // Replicas to check are passed as remaining accounts, like in
+    // merge_replicas.
+    pub fn assert_invariants<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AssertInvariants<'info>>,
+    ) -> Result<()> {
+        invariants::process_assert_invariants(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #49: initialize_config
+    // Creates the program-wide Config and picks its admin (see config.rs)
+    // ========================================================================
+
+    // Like set_program_info, only the program's upgrade authority can call
+    // this.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        config::process_initialize_config(ctx, admin)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #50: set_deny_cpi
+    // Makes a counter refuse increments made through CPI (see
+    // cpi_guard.rs)
+    // ========================================================================
+
+    pub fn set_deny_cpi(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        ctx.accounts.log(AdminAction::SetDenyCpi)?;
+        ctx.accounts.counter.deny_cpi = enabled;
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #51: allow_program
+    // Lets "program" increment the counter through increment_from_program
+    // ========================================================================
+
+    pub fn allow_program(ctx: Context<AllowProgram>, program: Pubkey) -> Result<()> {
+        cpi_guard::process_allow_program(ctx, program)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #52: disallow_program
+    // Takes a program off the counter's allowlist
+    // ========================================================================
+
+    pub fn disallow_program(ctx: Context<DisallowProgram>) -> Result<()> {
+        cpi_guard::process_disallow_program(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #53: increment_from_program
+    // Adds 1 to the counter, called through CPI by an allowlisted program
+    // (even with deny_cpi on)
+    // ========================================================================
+
+    pub fn increment_from_program(ctx: Context<IncrementFromProgram>) -> Result<()> {
+        cpi_guard::process_increment_from_program(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #54: exempt_from_fees
+    // Lets "wallet" use increment_paid without paying (see fees.rs)
+    // ========================================================================
+
+    pub fn exempt_from_fees(ctx: Context<ExemptFromFees>, wallet: Pubkey) -> Result<()> {
+        fees::process_exempt_from_fees(ctx, wallet)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #55: revoke_fee_exemption
+    // Makes an exempt wallet pay again
+    // ========================================================================
+
+    pub fn revoke_fee_exemption(ctx: Context<RevokeFeeExemption>) -> Result<()> {
+        fees::process_revoke_fee_exemption(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #56: migrate_counter
+    // Grows a counter created before its newest fields to the current
+    // layout (see migrate.rs)
+    // ========================================================================
+
+    pub fn migrate_counter(ctx: Context<MigrateCounter>) -> Result<()> {
+        migrate::process_migrate_counter(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #57: reset_count
+    // Sets a counter's count back to 0, keeping its lifetime_count
+    // ========================================================================
+
+    // Owner only, like the vault: a renter (see lease.rs) shouldn't be
+    // able to wipe out the count they rented. A mirrored counter can't be
+    // reset, since its mint's supply has to match the count (see
+    // token_mirror.rs).
+    pub fn reset_count(ctx: Context<ResetCount>) -> Result<()> {
+        let guard = &mut ctx.accounts.guard;
+        guard.counter.begin_change()?;
+        require!(
+            !guard.counter.has_flag(Counter::TOKEN_MIRRORED),
+            CounterError::ResetMirrored
+        );
+        let previous = guard.counter.reset();
+        debug_assert_invariants!(guard.counter);
+        guard.log(AdminAction::ResetCount)?;
+
+        let counter = &mut guard.counter;
+        log_count(LogEvent::Reset, counter.count);
+        emit!(CounterReset {
+            counter: counter.key(),
+            previous,
+            lifetime_count: counter.lifetime_count,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #58: set_role_weighted
+    // Makes a counter's increments add the sender's role weight (see
+    // roles.rs)
+    // ========================================================================
+
+    pub fn set_role_weighted(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        ctx.accounts.log(AdminAction::SetRoleWeighted)?;
+        let counter = &mut ctx.accounts.counter;
+        if enabled {
+            // 0: role weighting isn't a flag (see role_weighted below).
+            counter.check_route_free(0)?;
+        }
+        counter.role_weighted = enabled;
+        debug_assert_invariants!(counter);
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #59: set_role
+    // Gives "member" a weight on the counter, or takes it away with 0
+    // ========================================================================
+
+    pub fn set_role(ctx: Context<SetRole>, member: Pubkey, weight: u64) -> Result<()> {
+        roles::process_set_role(ctx, member, weight)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #60: increment_by_role
+    // Adds the signing member's weight to a role-weighted counter
+    // ========================================================================
+
+    pub fn increment_by_role(ctx: Context<IncrementByRole>) -> Result<()> {
+        roles::process_increment_by_role(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #61: set_template
+    // Creates or replaces the counter preset "name" (see templates.rs).
+    // Admin only
+    // ========================================================================
+
+    pub fn set_template(ctx: Context<SetTemplate>, args: TemplateArgs) -> Result<()> {
+        templates::process_set_template(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #62: remove_template
+    // Closes a preset, refunding its rent to the admin. Counters already
+    // created from it keep their settings
+    // ========================================================================
+
+    pub fn remove_template(_ctx: Context<RemoveTemplate>) -> Result<()> {
+        Ok(())
+    }
+
+    // ========================================================================
+    // INSTRUCTION #63: initialize_from_template
+    // Creates a counter with a preset's settings
+    // ========================================================================
+
+    pub fn initialize_from_template(ctx: Context<InitializeFromTemplate>) -> Result<()> {
+        templates::process_initialize_from_template(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #64: get_count
+    // Returns the counter's count as return data (see getters.rs)
+    // ========================================================================
+
+    pub fn get_count(ctx: Context<ReadCounter>) -> Result<u64> {
+        getters::process_get_count(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #65: get_state
+    // Returns a summary of the counter as return data
+    // ========================================================================
+
+    pub fn get_state(ctx: Context<ReadCounter>) -> Result<CounterState> {
+        getters::process_get_state(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #66: initialize_reapable
+    // Creates a counter that anyone may close once it has gone unused for
+    // "idle_epochs" epochs, its rent going to "beneficiary" (see reap.rs)
+    // ========================================================================
+
+    pub fn initialize_reapable(
+        ctx: Context<InitializeReapable>,
+        idle_epochs: u64,
+        beneficiary: Pubkey,
+    ) -> Result<()> {
+        reap::process_initialize_reapable(ctx, idle_epochs, beneficiary)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #67: reap
+    // Closes a reapable counter that has gone unused long enough, or notes
+    // that it was used
+    // ========================================================================
+
+    pub fn reap(ctx: Context<Reap>) -> Result<()> {
+        reap::process_reap(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #68: set_sponsorship
+    // Admin-only: sets how many counters the treasury pays for per wallet
+    // (see sponsor.rs)
+    // ========================================================================
+
+    pub fn set_sponsorship(ctx: Context<SetSponsorship>, quota: u16) -> Result<()> {
+        sponsor::process_set_sponsorship(ctx, quota)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #69: withdraw_treasury
+    // Admin-only: takes lamports back out of the treasury
+    // ========================================================================
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        sponsor::process_withdraw_treasury(ctx, amount)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #70: initialize_sponsored
+    // Creates a counter with its rent paid by the treasury, within the
+    // user's quota
+    // ========================================================================
+
+    pub fn initialize_sponsored(ctx: Context<InitializeSponsored>) -> Result<()> {
+        sponsor::process_initialize_sponsored(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #71: create_gift
+    // Creates a counter starting at "count" for a recipient to claim before
+    // it expires (see gift.rs)
+    // ========================================================================
+
+    pub fn create_gift(ctx: Context<CreateGift>, args: GiftArgs) -> Result<()> {
+        gift::process_create_gift(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #72: claim_gift
+    // The recipient takes over a gifted counter
+    // ========================================================================
+
+    pub fn claim_gift(ctx: Context<ClaimGift>) -> Result<()> {
+        gift::process_claim_gift(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #73: reclaim_gift
+    // Closes an expired gift, refunding its rent to the creator
+    // ========================================================================
+
+    pub fn reclaim_gift(ctx: Context<ReclaimGift>) -> Result<()> {
+        gift::process_reclaim_gift(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #74: initialize_leaderboard
+    // Creates the (empty) Leaderboard of the highest counters (see
+    // leaderboard.rs)
+    // ========================================================================
+
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+        leaderboard::process_initialize_leaderboard(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #75: update_leaderboard
+    // Submits a counter's count to the Leaderboard
+    // ========================================================================
+
+    pub fn update_leaderboard(ctx: Context<UpdateLeaderboard>) -> Result<()> {
+        leaderboard::process_update_leaderboard(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #76: register_tag
+    // Admin-only: adds a tag counters can be created with (see tags.rs)
+    // ========================================================================
+
+    pub fn register_tag(ctx: Context<RegisterTag>, tag: [u8; TAG_LEN]) -> Result<()> {
+        tags::process_register_tag(ctx, tag)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #77: initialize_tagged
+    // Creates a counter with up to 4 registered tags
+    // ========================================================================
+
+    pub fn initialize_tagged(ctx: Context<InitializeTagged>, tags: Vec<[u8; TAG_LEN]>) -> Result<()> {
+        tags::process_initialize_tagged(ctx, tags)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #78: set_soft_cap
+    // Caps a counter's count, with increments past it spilling into
+    // another counter (see spill.rs)
+    // ========================================================================
+
+    pub fn set_soft_cap(ctx: Context<UpdateCounter>, args: SoftCapArgs) -> Result<()> {
+        spill::process_set_soft_cap(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #79: increment_spilling
+    // Adds 1 to the counter, or to its overflow once it's at its cap
+    // ========================================================================
+
+    pub fn increment_spilling(ctx: Context<IncrementSpilling>) -> Result<()> {
+        spill::process_increment_spilling(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #80: set_twac
+    // Makes a counter keep a time-weighted average of its count (see
+    // twac.rs)
+    // ========================================================================
+
+    pub fn set_twac(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        twac::process_set_twac(ctx, enabled)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #81: get_twac
+    // Returns the counter's TWAC accumulator as of the current slot
+    // ========================================================================
+
+    pub fn get_twac(ctx: Context<ReadCounter>) -> Result<TwacObservation> {
+        twac::process_get_twac(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #82: set_delta_feed
+    // Makes a counter keep its recent changes, for pollers (see deltas.rs)
+    // ========================================================================
+
+    pub fn set_delta_feed(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+        deltas::process_set_delta_feed(ctx, enabled)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #83: set_freeze_policy
+    // Admin-only: sets how many contributors' votes freeze a counter, and
+    // how long they have to reach it (see freeze.rs)
+    // ========================================================================
+
+    pub fn set_freeze_policy(
+        ctx: Context<SetFreezePolicy>,
+        quorum: u16,
+        vote_secs: i64,
+    ) -> Result<()> {
+        freeze::process_set_freeze_policy(ctx, quorum, vote_secs)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #84: vote_freeze
+    // A badge holder votes to freeze a counter they suspect is manipulated
+    // ========================================================================
+
+    pub fn vote_freeze(ctx: Context<VoteFreeze>) -> Result<()> {
+        freeze::process_vote_freeze(ctx)
+    }
 
-// "pub" means "public" - this can be accessed from outside this file.
-// "mod" declares a "module" - a way to organize code in Rust (like a folder).
-// "counter" is the name we're giving this module.
-// "{" starts the module's body (everything inside the curly braces belongs
-// to this module).
-pub mod counter {
+    // ========================================================================
+    // INSTRUCTION #85: unfreeze
+    // The owner lets a frozen counter change again, after reviewing it
+    // ========================================================================
 
-    // "use" imports items. "super" refers to the parent scope (the code
-    // outside this module). "::*" means "import everything from there".
-    // This lets us use the imports from line 14 inside this module.
-    use super::*;
+    pub fn unfreeze(ctx: Context<Unfreeze>) -> Result<()> {
+        freeze::process_unfreeze(ctx)
+    }
 
     // ========================================================================
-    // INSTRUCTION #1: initialize
-    // Creates a new counter account and sets its value to 0
+    // INSTRUCTION #86: set_fee_schedule
+    // Charges by the day's volume instead of one flat fee (see fees.rs)
     // ========================================================================
 
-    // "pub" = public (can be called from outside).
-    // "fn" = "function" (declares a function).
-    // "initialize" = the name of this function/instruction.
-    //
-    // "(ctx: Context<Initialize>)" = this function takes one parameter:
-    //   - "ctx" is the parameter name (short for "context")
-    //   - ":" separates the name from its type
-    //   - "Context<Initialize>" is the type. "Context" is a generic type
-    //     from Anchor, and "<Initialize>" specifies which accounts struct
-    //     to use (defined below on line 97).
-    //
-    // "->" indicates what the function returns.
-    // "Result<()>" is the return type:
-    //   - "Result" is an enum that's either Ok (success) or Err (failure)
-    //   - "<()>" means on success, we return "()" which is the "unit type"
-    //     (Rust's version of void/nothing/null - no meaningful return value)
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    pub fn set_fee_schedule(ctx: Context<SetFeeSchedule>, args: FeeScheduleArgs) -> Result<()> {
+        fees::process_set_fee_schedule(ctx, args)
+    }
 
-        // "let" declares a new variable.
-        // "counter" is the variable name.
-        // "=" assigns a value to it.
-        //
-        // "&mut" means "mutable reference". Let me break this down:
-        //   - "&" means "reference" (like a pointer - we're borrowing the
-        //     data, not copying it)
-        //   - "mut" means "mutable" (we're allowed to modify it)
-        //   - Without "mut", Rust variables are immutable (read-only) by
-        //     default!
-        //
-        // "ctx" is our context parameter from above.
-        // ".accounts" accesses the "accounts" field of the context (this
-        //   contains all the accounts passed to this instruction).
-        // ".counter" accesses the specific account named "counter" (this
-        //   name matches the field in the Initialize struct below).
-        let counter = &mut ctx.accounts.counter;
+    // ========================================================================
+    // INSTRUCTION #87: sweep_fees
+    // Empties the vaults of many of the signer's counters into the
+    // treasury (see sweep.rs)
+    // ========================================================================
 
-        // "counter.count" accesses the "count" field of the counter account.
-        // "= 0" sets it to zero.
-        // ";" ends the statement (most lines in Rust end with semicolons).
-        counter.count = 0;
+    // The counters and vaults are "remaining" accounts, as in
+    // merge_replicas.
+    pub fn sweep_fees<'info>(ctx: Context<'_, '_, 'info, 'info, SweepFees<'info>>) -> Result<()> {
+        sweep::process_sweep_fees(ctx)
+    }
 
-        // "msg!" is a macro that logs a message to Solana's transaction logs.
-        // Similar to console.log() in JavaScript.
-        //
-        // The string uses "{}" as a placeholder (like %s in other languages).
-        // "counter.count" is the value that replaces "{}".
-        msg!("Counter initialized! Current count: {}", counter.count);
+    // ========================================================================
+    // INSTRUCTION #88: initialize_funded
+    // Creates a counter for "authority", with "payer" paying its rent (see
+    // funded.rs)
+    // ========================================================================
 
-        // "Ok(())" returns a successful result.
-        // "Ok" is one variant of the Result enum (the success case).
-        // "()" inside is the value we're returning (nothing/unit type).
-        //
-        // Note: no semicolon here! In Rust, the last expression in a function
-        // without a semicolon is the return value. Adding ";" would make this
-        // a statement instead of a return expression.
-        Ok(())
+    pub fn initialize_funded(ctx: Context<InitializeFunded>) -> Result<()> {
+        funded::process_initialize_funded(ctx)
+    }
 
-    // "}" closes the function body.
+    // ========================================================================
+    // INSTRUCTION #89: verify_checksum
+    // Fails if the counter was changed other than by this program (see
+    // checksum.rs)
+    // ========================================================================
+
+    pub fn verify_checksum(ctx: Context<ReadCounter>) -> Result<()> {
+        checksum::process_verify_checksum(ctx)
     }
 
     // ========================================================================
-    // INSTRUCTION #2: increment
-    // Adds 1 to an existing counter
+    // INSTRUCTION #90: preview_increment
+    // Returns what an increment would do, changing nothing (see
+    // preview.rs)
     // ========================================================================
 
-    // Same structure as initialize, but uses "Context<Increment>" because
-    // this instruction needs different accounts (see Increment struct below).
-    pub fn increment(ctx: Context<Increment>) -> Result<()> {
+    // "referrer" as increment_paid would get it.
+    pub fn preview_increment(
+        ctx: Context<PreviewIncrement>,
+        referrer: Option<Pubkey>,
+    ) -> Result<IncrementPreview> {
+        preview::process_preview_increment(ctx, referrer)
+    }
 
-        // Get a mutable reference to the counter account.
-        // Same pattern as in initialize.
-        let counter = &mut ctx.accounts.counter;
+    // ========================================================================
+    // INSTRUCTION #91: initialize_group
+    // Creates a Group, rules shared by many counters (see group.rs)
+    // ========================================================================
 
-        // "+= 1" adds 1 to the current value (same as "counter.count =
-        // counter.count + 1").
-        counter.count += 1;
+    pub fn initialize_group(ctx: Context<InitializeGroup>) -> Result<()> {
+        group::process_initialize_group(ctx)
+    }
 
-        // Log the new count.
-        msg!("Counter incremented! Current count: {}", counter.count);
+    // ========================================================================
+    // INSTRUCTION #92: update_group
+    // Changes a group's rules, for every member at once
+    // ========================================================================
 
-        // Return success.
-        Ok(())
+    pub fn update_group(ctx: Context<UpdateGroup>, args: GroupArgs) -> Result<()> {
+        group::process_update_group(ctx, args)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #93: join_group
+    // Puts a counter under a group's rules
+    // ========================================================================
+
+    pub fn join_group(ctx: Context<JoinGroup>) -> Result<()> {
+        group::process_join_group(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #94: leave_group
+    // Takes a counter out of its group
+    // ========================================================================
+
+    pub fn leave_group(ctx: Context<LeaveGroup>) -> Result<()> {
+        group::process_leave_group(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #95: increment_grouped
+    // Adds 1 to a counter in a group, under the group's rules
+    // ========================================================================
+
+    pub fn increment_grouped(ctx: Context<IncrementGrouped>) -> Result<()> {
+        group::process_increment_grouped(ctx)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #96: migrate_ownerless_counter
+    // migrate_counter for the first, count-only counters, giving each an
+    // owner (see migrate.rs)
+    // ========================================================================
+
+    pub fn migrate_ownerless_counter(
+        ctx: Context<MigrateOwnerlessCounter>,
+        owner: Pubkey,
+    ) -> Result<()> {
+        migrate::process_migrate_ownerless_counter(ctx, owner)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #97: create_compressed_counter
+    // Creates a counter as Light compressed state, which pays no rent (see
+    // compressed.rs)
+    // ========================================================================
+
+    // The trees and queues go in "remaining_accounts", in the order the
+    // indexes in "address_tree" and "output_tree_index" refer to.
+    pub fn create_compressed_counter<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateCompressedCounter<'info>>,
+        proof: CompressedProof,
+        address_tree: PackedAddressTreeInfo,
+        output_tree_index: u8,
+        id: u64,
+    ) -> Result<()> {
+        compressed::process_create_compressed_counter(
+            ctx,
+            proof,
+            address_tree,
+            output_tree_index,
+            id,
+        )
+    }
+
+    // ========================================================================
+    // INSTRUCTION #98: increment_compressed_counter
+    // Adds 1 to a compressed counter
+    // ========================================================================
+
+    // "counter" is its current state and "meta" where it is, both as a
+    // Light RPC node returns them; the trees and queues go in
+    // "remaining_accounts".
+    pub fn increment_compressed_counter<'info>(
+        ctx: Context<'_, '_, 'info, 'info, IncrementCompressedCounter<'info>>,
+        proof: CompressedProof,
+        counter: CompressedCounter,
+        meta: CompressedCounterMeta,
+    ) -> Result<()> {
+        compressed::process_increment_compressed_counter(ctx, proof, counter, meta)
+    }
+
+    // ========================================================================
+    // INSTRUCTION #99: evict_leaderboard_entry
+    // Takes a counter that's gone or gifted off the Leaderboard (see
+    // leaderboard.rs)
+    // ========================================================================
+
+    pub fn evict_leaderboard_entry(ctx: Context<EvictLeaderboardEntry>) -> Result<()> {
+        leaderboard::process_evict_leaderboard_entry(ctx)
     }
 
 // "}" closes the "counter" module.
@@ -139,16 +1429,28 @@ pub mod counter {
 // Defines what data is stored in a counter account
 // ============================================================================
 
-// "#[account]" is an attribute macro from Anchor that:
+// Account structs usually get "#[account]", an attribute macro from Anchor
+// that:
 //   1. Implements serialization (converting struct to bytes for storage)
 //   2. Implements deserialization (converting bytes back to struct)
 //   3. Adds an 8-byte "discriminator" (a unique ID for this account type)
 //   4. Implements other traits Anchor needs
-#[account]
+// Counter writes 1-4 out by hand instead, in checksum.rs, so that storing
+// a counter also updates its checksum. It keeps the derives "#[account]"
+// would add: "AnchorSerialize" and "AnchorDeserialize" convert the fields
+// to and from bytes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+
+// "#[derive(InitSpace)]" makes Anchor add up the size of every field for
+// us, available as "Counter::INIT_SPACE" (see Initialize below).
+// "Default" lets us create a Counter with every field zeroed, which is
+// handy in tests.
+#[derive(InitSpace, Default)]
 
 // "pub" = public.
 // "struct" = defines a structure (like a class with only data, no methods).
 // "Counter" = the name of this struct (capitalize by convention).
+/// A counter: its count, who controls it, and its settings.
 pub struct Counter {
 
     // "pub" = this field is public (accessible from outside the struct).
@@ -157,11 +1459,612 @@ pub struct Counter {
     // "u64" = the type. "u" means unsigned (no negative numbers), "64" means
     //   64 bits. Can hold values from 0 to 18,446,744,073,709,551,615.
     //   Other options: u8, u16, u32, u128, i8, i16, i32, i64, i128 (i = signed)
+    //
+    // Keep "count" as the first field: programs/counter-native reads it
+    // straight from bytes 8..16 of the account.
+    /// The current count.
     pub count: u64,
 
+    // "Pubkey" = a 32-byte public key (an address).
+    /// The wallet allowed to change this counter's settings. Incrementing
+    /// is still open to everyone.
+    pub authority: Pubkey,
+
+    // Each mode is one "bit" of the number (see the FLAG constants below),
+    // so 8 modes fit in a u8.
+    /// Optional modes, one bit each (see Counter::ONCE_PER_SLOT and the
+    /// constants after it).
+    pub flags: u8,
+
+    /// The slot of the most recent increment, recorded while the
+    /// ONCE_PER_SLOT mode is on.
+    pub last_increment_slot: u64,
+
+    /// The count that raises an alarm, or 0 for no alarm.
+    pub alarm_threshold: u64,
+
+    /// The number of events emitted about this counter so far. Every event
+    /// carries its own number, so listeners can spot ones they missed.
+    pub event_seq: u64,
+
+    // Unlike ARCHIVED it's never cleared, so it gets its own field rather
+    // than a flag.
+    /// Set by finalize (see finalize.rs), for good.
+    pub finalized: bool,
+
+    // "flags" is full, so it's a field.
+    /// Only increments sent directly by a transaction count, not ones from
+    /// other programs (see cpi_guard.rs).
+    pub deny_cpi: bool,
+
+    /// Everything ever added to the count. It goes up with "count" but
+    /// never comes down: not on decrements, and not when reset_count sets
+    /// "count" back to 0. Analytics read this for a series that only grows.
+    pub lifetime_count: u64,
+
+    // It's one of the ROUTES modes in all but name: "flags" is full, so
+    // it's a field.
+    /// Increments add the sender's role weight (see roles.rs), so only
+    /// increment_by_role may increment it.
+    pub role_weighted: bool,
+
+    /// Up to MAX_COUNTER_TAGS categories, set by initialize_tagged (see
+    /// tags.rs), each padded with zeros to TAG_LEN bytes. Unused slots are
+    /// all zeros.
+    pub tags: [[u8; TAG_LEN]; MAX_COUNTER_TAGS],
+
+    /// A soft cap (see spill.rs): plain increments stop at this count, and
+    /// increment_spilling adds to "overflow" from then on. 0 means no cap.
+    pub max_count: u64,
+    /// Where increments go once "max_count" is reached. Unset without a
+    /// cap.
+    pub overflow: Pubkey,
+
+    /// The time-weighted average count's accumulator (see twac.rs): the
+    /// sum of "count * slots held" up to "twac_slot", in count-slots.
+    pub cumulative_count: u128,
+    /// The slot of the last change, or 0 while set_twac is off.
+    pub twac_slot: u64,
+
+    /// The last few changes to the count, for pollers (see deltas.rs). Off
+    /// until set_delta_feed.
+    pub delta_feed: DeltaFeed,
+
+    // Like ARCHIVED, but the authority didn't choose it; "flags" is full,
+    // so it's a field.
+    /// Set when enough badge holders vote to freeze the counter (see
+    /// freeze.rs), until its authority calls unfreeze.
+    pub frozen: bool,
+
+    // Written by try_serialize, never by instructions: whatever a handler
+    // sets here is replaced on the way out. It stays where it is; new
+    // fields go after it (see CHECKSUM).
+    /// The first 8 bytes of the SHA-256 hash of every other field, as
+    /// stored (see checksum.rs).
+    pub checksum: u64,
+
+    // Another ROUTES mode in all but name, like role_weighted.
+    /// The Group whose settings the counter follows (see group.rs), or
+    /// the default (all zeros) address for none. Only increment_grouped
+    /// may increment a counter in a group.
+    pub group: Pubkey,
+
+    /// How much of the count create_gift set rather than increments (see
+    /// gift.rs). 0 for counters that weren't gifts; gifted ones stay off
+    /// the leaderboard.
+    pub gifted_count: u64,
+
 // "}" closes the struct definition.
 }
 
+// "impl Counter" starts an "implementation block": functions defined in here
+// are "methods" that belong to Counter (like methods on a class).
+//
+// Keeping the math here instead of inside the instruction means it can be
+// tested on its own, without a blockchain (see tests/fuzz.rs).
+impl Counter {
+
+    // "const" = a fixed value known at compile time.
+    // "1 << 0" means "the number 1, shifted left 0 bits" = binary 00000001.
+    // The next flag would be "1 << 1" = binary 00000010, and so on, so
+    // every flag has its own bit.
+    pub const ONCE_PER_SLOT: u8 = 1 << 0;
+
+    // Set when the count reaches "alarm_threshold", cleared by
+    // acknowledge_alarm. Unlike ONCE_PER_SLOT this is a status, not a mode:
+    // the program sets it, not the authority.
+    pub const ALARM_TRIGGERED: u8 = 1 << 1;
+
+    // Increments must be recorded in the event tree (see event_tree.rs), so
+    // only increment_logged may increment this counter.
+    pub const TREE_LOGGED: u8 = 1 << 2;
+
+    // The counter has a mint whose supply must match the count (see
+    // token_mirror.rs), so only increment_minted may increment it.
+    pub const TOKEN_MIRRORED: u8 = 1 << 3;
+
+    // Set by archive: the count is frozen, but the account is kept.
+    pub const ARCHIVED: u8 = 1 << 4;
+
+    // The counter charges a fee per increment (see fees.rs), so only
+    // increment_paid may increment it.
+    pub const FEE_REQUIRED: u8 = 1 << 5;
+
+    // Increments add locked token weight (see vote_escrow.rs), so only
+    // increment_weighted may increment it.
+    pub const VOTE_WEIGHTED: u8 = 1 << 6;
+
+    // Increments are committed and revealed in rounds (see
+    // commit_reveal.rs), so only reveal may increment it. This is the last
+    // free bit: another flag will need a bigger "flags".
+    pub const COMMIT_REVEAL: u8 = 1 << 7;
+
+    // Modes that each reserve increments for one instruction. Only one of
+    // them can be on at a time, or no instruction could increment at all.
+    pub const ROUTES: u8 = Counter::TREE_LOGGED
+        | Counter::TOKEN_MIRRORED
+        | Counter::FEE_REQUIRED
+        | Counter::VOTE_WEIGHTED
+        | Counter::COMMIT_REVEAL;
+
+    // "&self" (no "mut") borrows the counter read-only.
+    // "&" on two numbers is "bitwise AND": it keeps only the bits set in
+    // both, so the result is non-zero exactly when "flag" is set.
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    // "|=" sets the flag's bit. "&= !flag" clears it ("!" flips every bit,
+    // so this keeps every bit except the flag's).
+    pub fn set_flag(&mut self, flag: u8, enabled: bool) {
+        if enabled {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    // Runs the checks for every optional mode that's turned on. Every
+    // instruction that increments calls this first.
+    pub fn check_modes(&mut self) -> Result<()> {
+        self.check_unrouted()?;
+        self.check_limits()?;
+        // Only the plain paths are capped (see spill.rs).
+        require!(!self.is_capped(), CounterError::SoftCapReached);
+        Ok(())
+    }
+
+    // Fails if one of the ROUTES modes is on, since then only that mode's
+    // instruction may increment.
+    pub fn check_unrouted(&self) -> Result<()> {
+        self.check_other_routes(None)
+    }
+
+    // check_modes for the instruction "route" reserves increments for
+    // (increment_logged, increment_minted, ...). The counter has to be in
+    // that mode, and so in no other, and is held to the same limits and
+    // soft cap as a plain increment. Without the first two, each of these
+    // instructions would be a way around every other mode's.
+    pub fn check_route(&mut self, route: Route) -> Result<()> {
+        require!(self.is_routed(route), route.missing());
+        self.check_other_routes(Some(route))?;
+        self.check_limits()?;
+        require!(!self.is_capped(), CounterError::SoftCapReached);
+        Ok(())
+    }
+
+    // Fails if the counter is in any mode of Route::ALL but "route".
+    fn check_other_routes(&self, route: Option<Route>) -> Result<()> {
+        for other in Route::ALL {
+            require!(
+                Some(other) == route || !self.is_routed(other),
+                other.required()
+            );
+        }
+        Ok(())
+    }
+
+    // Whether the counter is in "route"'s mode.
+    pub fn is_routed(&self, route: Route) -> bool {
+        match route {
+            Route::TreeLogged => self.has_flag(Counter::TREE_LOGGED),
+            Route::TokenMirrored => self.has_flag(Counter::TOKEN_MIRRORED),
+            Route::FeeRequired => self.has_flag(Counter::FEE_REQUIRED),
+            Route::VoteWeighted => self.has_flag(Counter::VOTE_WEIGHTED),
+            Route::CommitReveal => self.has_flag(Counter::COMMIT_REVEAL),
+            Route::RoleWeighted => self.role_weighted,
+            Route::Grouped => self.in_group(),
+        }
+    }
+
+    // Call before turning on one of the ROUTES modes. Fails if another
+    // one is already on. role_weighted and being in a group count as
+    // routes too; pass 0 before turning either on.
+    pub fn check_route_free(&self, route: u8) -> Result<()> {
+        require!(
+            self.flags & Counter::ROUTES & !route == 0
+                && (route == 0 || !self.role_weighted)
+                && !self.in_group(),
+            CounterError::ConflictingModes
+        );
+        Ok(())
+    }
+
+    // Whether the counter is in a Group (see group.rs).
+    pub fn in_group(&self) -> bool {
+        self.group != Pubkey::default()
+    }
+
+    // The modes that limit how often (and from where) a counter can go up.
+    // Every increment runs these, through check_modes or check_route.
+    pub fn check_limits(&mut self) -> Result<()> {
+        if self.deny_cpi {
+            check_top_level()?;
+        }
+        self.check_pace()
+    }
+
+    // check_limits without deny_cpi, for increment_from_program, which
+    // checks its caller itself (see cpi_guard.rs).
+    pub fn check_pace(&mut self) -> Result<()> {
+        self.begin_change()?;
+        if self.has_flag(Counter::ONCE_PER_SLOT) {
+            // Only read the clock when the mode needs it, so plain
+            // counters don't pay for it.
+            self.record_slot(clock::slot()?)?;
+        }
+        Ok(())
+    }
+
+    // Fails if the counter is finalized, archived or frozen. Everything
+    // that changes the count checks this, through begin_change: increments
+    // through check_pace, the rest directly.
+    pub fn check_active(&self) -> Result<()> {
+        require!(!self.finalized, CounterError::Finalized);
+        require!(!self.has_flag(Counter::ARCHIVED), CounterError::Archived);
+        require!(!self.frozen, CounterError::Frozen);
+        Ok(())
+    }
+
+    // check_active, for an instruction about to change the count. It also
+    // brings the TWAC accumulator up to now (see twac.rs), while the count
+    // it's been at since the last change is still there, and tells the
+    // delta feed (see deltas.rs) which slot the change is in.
+    pub fn begin_change(&mut self) -> Result<()> {
+        self.check_active()?;
+        if self.is_twac() || self.delta_feed.is_on() {
+            // As in check_pace, only counters that need the clock read it.
+            let slot = clock::slot()?;
+            self.accumulate(slot);
+            self.delta_feed.slot = slot;
+        }
+        Ok(())
+    }
+
+    // ONCE_PER_SLOT: fails if the counter was already incremented in this
+    // slot, otherwise remembers the slot.
+    pub fn record_slot(&mut self, slot: u64) -> Result<()> {
+        // "require!(condition, error)" returns the error if the condition
+        // is false.
+        require!(
+            slot != self.last_increment_slot,
+            CounterError::AlreadyCountedThisSlot
+        );
+        self.last_increment_slot = slot;
+        Ok(())
+    }
+
+    // Call after every change to the count, with the count from before
+    // it. Returns true (and sets ALARM_TRIGGERED) only for the change that
+    // took the count from below the threshold to at or above it. An alarm
+    // that's already set isn't raised again.
+    pub fn check_alarm(&mut self, before: u64) -> bool {
+        let reached = self.alarm_threshold != 0
+            && before < self.alarm_threshold
+            && self.count >= self.alarm_threshold;
+        if !reached || self.has_flag(Counter::ALARM_TRIGGERED) {
+            return false;
+        }
+        self.set_flag(Counter::ALARM_TRIGGERED, true);
+        true
+    }
+
+    // "&mut self" means this method borrows the counter mutably, so it can
+    // change "self.count".
+    pub fn increment(&mut self) -> Result<()> {
+        self.add(1)
+    }
+
+    // Like increment, but adds "amount" instead of 1 (see replica.rs).
+    pub fn add(&mut self, amount: u64) -> Result<()> {
+
+        // "checked_add" returns "Some(new_value)", or "None" if the result
+        // wouldn't fit in a u64 (an "overflow").
+        // "ok_or(...)" turns that None into our own error.
+        //
+        // A plain "+=" would instead crash the program on overflow (or
+        // silently wrap around to 0 if overflow checks were turned off).
+        //
+        // Both sums are checked before either is written, so an overflow
+        // leaves the counter as it was.
+        let count = self.count.checked_add(amount).ok_or(CounterError::Overflow)?;
+        let lifetime_count = self
+            .lifetime_count
+            .checked_add(amount)
+            .ok_or(CounterError::Overflow)?;
+        self.count = count;
+        self.lifetime_count = lifetime_count;
+        self.delta_feed.record(amount.into());
+        Ok(())
+    }
+
+    // Numbers the next event about this counter: 1 for the first, then
+    // 2, 3, ... with no gaps.
+    pub fn next_event_seq(&mut self) -> u64 {
+        seq::next(&mut self.event_seq)
+    }
+
+    // The opposite of add, except that "lifetime_count" stays where it
+    // is. "checked_sub" returns None below 0.
+    pub fn subtract(&mut self, amount: u64) -> Result<()> {
+        self.count = self.count.checked_sub(amount).ok_or(CounterError::Underflow)?;
+        self.delta_feed.record(-i128::from(amount));
+        Ok(())
+    }
+
+    // Sets the count back to 0 and returns what it was. "lifetime_count"
+    // keeps everything counted before the reset.
+    pub fn reset(&mut self) -> u64 {
+        let previous = std::mem::take(&mut self.count);
+        self.delta_feed.record(-i128::from(previous));
+        previous
+    }
+}
+
+// The ROUTES modes, role_weighted and group included, each of which
+// reserves a counter's increments for one instruction (see
+// Counter::check_route).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Route {
+    // increment_logged (see event_tree.rs)
+    TreeLogged,
+    // increment_minted (see token_mirror.rs)
+    TokenMirrored,
+    // increment_paid (see fees.rs)
+    FeeRequired,
+    // increment_weighted (see vote_escrow.rs)
+    VoteWeighted,
+    // reveal (see commit_reveal.rs)
+    CommitReveal,
+    // increment_by_role (see roles.rs)
+    RoleWeighted,
+    // increment_grouped (see group.rs)
+    Grouped,
+}
+
+impl Route {
+    pub const ALL: [Route; 7] = [
+        Route::TreeLogged,
+        Route::TokenMirrored,
+        Route::FeeRequired,
+        Route::VoteWeighted,
+        Route::CommitReveal,
+        Route::RoleWeighted,
+        Route::Grouped,
+    ];
+
+    // The error for incrementing a counter in this mode any other way.
+    pub fn required(self) -> CounterError {
+        match self {
+            Route::TreeLogged => CounterError::TreeLogRequired,
+            Route::TokenMirrored => CounterError::MintRequired,
+            Route::FeeRequired => CounterError::FeeRequired,
+            Route::VoteWeighted => CounterError::VoteWeightRequired,
+            Route::CommitReveal => CounterError::RevealRequired,
+            Route::RoleWeighted => CounterError::RoleRequired,
+            Route::Grouped => CounterError::GroupRequired,
+        }
+    }
+
+    // The error for incrementing a counter this way when it isn't in this
+    // mode.
+    pub fn missing(self) -> CounterError {
+        match self {
+            Route::TreeLogged => CounterError::NotTreeLogged,
+            Route::TokenMirrored => CounterError::NotTokenMirrored,
+            Route::FeeRequired => CounterError::NotFeeRequired,
+            Route::VoteWeighted => CounterError::NotVoteWeighted,
+            Route::CommitReveal => CounterError::NotCommitReveal,
+            Route::RoleWeighted => CounterError::NotRoleWeighted,
+            Route::Grouped => CounterError::WrongGroup,
+        }
+    }
+}
+
+// ============================================================================
+// ERRORS
+// Custom errors this program can return
+// ============================================================================
+
+// "#[error_code]" turns this enum into Anchor errors. Each variant gets a
+// number starting at 6000 (so "Overflow" is error 6000), and the "#[msg]"
+// text is shown in the transaction logs when the error happens.
+//
+// "enum" = a type that is exactly one of several named "variants".
+#[error_code]
+pub enum CounterError {
+    #[msg("Counter would overflow")]
+    Overflow,
+    #[msg("Slot index is past the end of the bank")]
+    IndexOutOfBounds,
+    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+    #[msg("This idempotency key was already used for this counter")]
+    DuplicateIdempotencyKey,
+    #[msg("Signer is not this counter's authority")]
+    Unauthorized,
+    #[msg("Counter was already incremented in this slot")]
+    AlreadyCountedThisSlot,
+    #[msg("Replica belongs to a different counter")]
+    ReplicaRootMismatch,
+    #[msg("Wormhole message is malformed or not a count")]
+    InvalidWormholeMessage,
+    #[msg("Wormhole message is from a different emitter")]
+    UnknownEmitter,
+    #[msg("Wormhole message is older than the last one received")]
+    StaleWormholeMessage,
+    #[msg("This counter's increments must go through increment_logged")]
+    TreeLogRequired,
+    #[msg("Not a valid ElGamal ciphertext")]
+    InvalidCiphertext,
+    #[msg("Proofs don't show this counter's count is at least the threshold")]
+    InvalidThresholdProof,
+    #[msg("Only one mode that restricts how a counter is incremented can be on")]
+    ConflictingModes,
+    #[msg("This counter's increments must go through increment_minted")]
+    MintRequired,
+    #[msg("Counter can't go below 0")]
+    Underflow,
+    #[msg("A mirrored counter can only burn its own mint")]
+    MirrorMintRequired,
+    #[msg("Counter is archived")]
+    Archived,
+    #[msg("Lease duration must be positive")]
+    InvalidLease,
+    #[msg("Counter is already leased")]
+    LeaseActive,
+    #[msg("This counter's increments must go through increment_paid")]
+    FeeRequired,
+    #[msg("Referral share can't be more than 10000 basis points")]
+    InvalidFee,
+    #[msg("A referrer needs its stats and vault accounts")]
+    ReferrerAccountsMissing,
+    #[msg("This contribution tier hasn't been reached")]
+    TierNotReached,
+    #[msg("This counter's increments must go through increment_weighted")]
+    VoteWeightRequired,
+    #[msg("Counter isn't vote-weighted")]
+    NotVoteWeighted,
+    #[msg("Lock needs tokens and a duration of at most a year")]
+    InvalidLockup,
+    #[msg("Tokens are still locked")]
+    LockupActive,
+    #[msg("Lockup has ended or already voted")]
+    NoVoteWeight,
+    #[msg("This counter's increments must go through commit and reveal")]
+    RevealRequired,
+    #[msg("Counter isn't in commit-reveal mode")]
+    NotCommitReveal,
+    #[msg("Round deadlines must be in the future, commits before reveals")]
+    InvalidRound,
+    #[msg("Not allowed in this phase of the commit round")]
+    WrongPhase,
+    #[msg("Revealed value and salt don't match the commit")]
+    CommitMismatch,
+    #[msg("Counter is finalized")]
+    Finalized,
+    #[msg("Counter's end time hasn't come yet")]
+    TooEarlyToFinalize,
+    #[msg("Replica has merged more than it counted")]
+    ReplicaOvermerged,
+    #[msg("Mirrored counter's mint supply doesn't match its count")]
+    MirrorSupplyMismatch,
+    #[msg("Mirrored counter's mint wasn't passed in")]
+    MirrorMintMissing,
+    #[msg("Vault holds less than its rent-exempt minimum")]
+    VaultBelowRent,
+    #[msg("This counter doesn't accept increments through CPI")]
+    CpiDenied,
+    #[msg("Calling program isn't on this counter's allowlist")]
+    CallerNotAllowed,
+    #[msg("Account isn't a counter in an older layout")]
+    NotMigratable,
+    #[msg("Mirrored counter's count can't be reset")]
+    ResetMirrored,
+    #[msg("Counter's lifetime count is below its count")]
+    LifetimeBelowCount,
+    #[msg("This counter's increments must go through increment_by_role")]
+    RoleRequired,
+    #[msg("Counter isn't role-weighted")]
+    NotRoleWeighted,
+    #[msg("Template name must be 1 to 32 bytes")]
+    InvalidTemplateName,
+    #[msg("A template with a fee needs the fees account")]
+    FeesAccountMissing,
+    #[msg("Instruction arguments are of a version this program doesn't know")]
+    UnsupportedArgsVersion,
+    #[msg("A reapable counter must stay idle for at least one epoch")]
+    InvalidReapPolicy,
+    #[msg("Counter hasn't been idle long enough to reap")]
+    CounterNotIdle,
+    #[msg("Counter's vault still holds lamports")]
+    VaultNotEmpty,
+    #[msg("This wallet has used up its sponsored counters")]
+    SponsorQuotaReached,
+    #[msg("Treasury can't cover the rent")]
+    TreasuryTooLow,
+    #[msg("A gift must be claimable for a positive duration")]
+    InvalidGift,
+    #[msg("Gift has expired")]
+    GiftExpired,
+    #[msg("Gift can still be claimed")]
+    GiftNotExpired,
+    #[msg("Tags must be non-empty, distinct, and at most 4")]
+    InvalidTag,
+    #[msg("Tag isn't in the tag registry")]
+    UnknownTag,
+    #[msg("Tag registry is full")]
+    TagRegistryFull,
+    #[msg("Counter is at its max_count; increments must go through increment_spilling")]
+    SoftCapReached,
+    #[msg("Overflow must be another counter, the one the soft cap names")]
+    InvalidSoftCap,
+    #[msg("Counter doesn't keep a time-weighted average; see set_twac")]
+    NotTwac,
+    #[msg("A freeze policy needs a voting period of at least one second")]
+    InvalidFreezePolicy,
+    #[msg("Freeze voting is turned off; see set_freeze_policy")]
+    FreezeVotingOff,
+    #[msg("Already voted in this freeze round")]
+    AlreadyVoted,
+    #[msg("Counter was frozen by its contributors and is awaiting review")]
+    Frozen,
+    #[msg("Counter isn't frozen")]
+    NotFrozen,
+    #[msg("A fee schedule needs 1 to 4 tiers, the first from 0, each starting after the last")]
+    InvalidFeeSchedule,
+    #[msg("sweep_fees takes counter and vault pairs, each vault the counter's")]
+    InvalidSweep,
+    #[msg("Authority could never sign for the counter")]
+    InvalidAuthority,
+    #[msg("Counter's checksum doesn't match its fields")]
+    ChecksumMismatch,
+    #[msg("Counter is in a group: increment it with increment_grouped")]
+    GroupRequired,
+    #[msg("Counter's group is paused")]
+    GroupPaused,
+    #[msg("Counter isn't in this group")]
+    WrongGroup,
+    #[msg("Counter isn't tree-logged")]
+    NotTreeLogged,
+    #[msg("Counter isn't mirrored to a mint")]
+    NotTokenMirrored,
+    #[msg("Counter doesn't charge a fee")]
+    NotFeeRequired,
+    #[msg("Revealed value is over the round's maximum")]
+    RevealTooLarge,
+    #[msg("Counter has no owner: migrate it with migrate_ownerless_counter")]
+    OwnerlessCounter,
+    #[msg("Gifted counters can't go on the leaderboard")]
+    GiftedCounter,
+    #[msg("Counter is still live: submit it with update_leaderboard instead")]
+    LeaderboardEntryLive,
+    #[msg("Counter isn't on the leaderboard")]
+    NotOnLeaderboard,
+    #[msg("Payers can't be their own referrer")]
+    SelfReferral,
+}
+
 // ============================================================================
 // ACCOUNTS STRUCT: Initialize
 // Defines which accounts the "initialize" instruction requires
@@ -197,12 +2100,13 @@ pub struct Initialize<'info> {
         // the SOL required for rent. Storing data on Solana costs money!
         payer = user,
 
-        // "space = 8 + 8" = allocate 16 bytes of space for this account.
+        // "space" = how many bytes to allocate for this account:
         //   - First 8: Anchor's "discriminator" (identifies the account type)
-        //   - Second 8: our "count" field (u64 = 8 bytes)
-        // You must calculate this yourself! Formula:
-        //   8 (discriminator) + size of all your fields
-        space = 8 + 8
+        //   - Then the fields: "Counter::INIT_SPACE" is their total size,
+        //     worked out by "#[derive(InitSpace)]" on Counter, so it
+        //     keeps up as fields are added
+        // Formula: 8 (discriminator) + size of all your fields
+        space = 8 + Counter::INIT_SPACE
     )]
 
     // "pub counter" = public field named "counter".
@@ -264,7 +2168,76 @@ pub struct Increment<'info> {
     pub counter: Account<'info, Counter>,
 
     // Notice: no Signer required! This means ANYONE can increment the
-    // counter, not just the person who created it. If you wanted to
-    // restrict this, you'd add an "authority" pubkey to the Counter struct
-    // and a Signer here that must match it.
+    // counter, not just the person who created it. The counter's
+    // "authority" only controls its settings (see UpdateCounter below).
+}
+
+// ============================================================================
+// ACCOUNTS STRUCT: UpdateCounter
+// Used by instructions that change a counter's settings
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct UpdateCounter<'info> {
+
+    // Usually "has_one = authority" would check that "counter.authority"
+    // equals the "authority" account below. Here the check is on
+    // "authority" instead, because a lease (see lease.rs) can hand
+    // control to someone else for a while.
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // The counter's lease, if it ever had one. It's always passed, so
+    // nobody can skip the lease check by leaving it out.
+    #[account(seeds = [LEASE_SEED, counter.key().as_ref()], bump)]
+    /// CHECK: read by lease::effective_authority.
+    pub lease: UncheckedAccount<'info>,
+
+    // Every settings change is recorded here (see access_log.rs). The
+    // first change creates it, which is why the authority pays ("mut")
+    // and the System Program is needed.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AccessLog::SPACE,
+        seeds = [ACCESS_LOG_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub access_log: AccountLoader<'info, AccessLog>,
+
+    // Together with "Signer", this proves whoever controls the counter
+    // right now approved this transaction. "@ CounterError::Unauthorized"
+    // picks the error returned otherwise.
+    #[account(
+        mut,
+        constraint = lease::effective_authority(&counter, &lease)? == authority.key()
+            @ CounterError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// "impl" blocks work on accounts structs too. This saves every settings
+// instruction from spelling out the same four arguments.
+impl<'info> UpdateCounter<'info> {
+    pub fn log(&self, action: AdminAction) -> Result<()> {
+        access_log::record(
+            &self.access_log,
+            self.counter.key(),
+            self.authority.key(),
+            action,
+        )
+    }
+}
+
+// ============================================================================
+// ACCOUNTS STRUCT: ResetCount
+// Used by reset_count
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct ResetCount<'info> {
+    // The counter, its owner signing, and the access log (see guards.rs).
+    pub guard: AuthorityGuard<'info>,
 }
@@ -0,0 +1,77 @@
+// ============================================================================
+// LOGGING
+// Cheap logs by default, human-readable logs behind the "verbose-logs" feature
+// ============================================================================
+
+// Every instruction has a limited compute budget (measured in "compute
+// units", or CUs). Formatting a string with msg!("... {}", value) is
+// surprisingly expensive on-chain: turning a u64 into text alone can cost
+// thousands of CUs.
+//
+// "sol_log_64" logs five raw numbers with no formatting at all, which costs
+// ~100 CUs. The log line looks like:
+//
+//     Program log: 0x2, 0x5, 0x0, 0x0, 0x0
+//
+// where the first number says which event happened (see LogEvent) and the
+// second is the current count. Off-chain tools can decode that easily.
+//
+// Build with "anchor build -- --features verbose-logs" to get the friendly
+// "Counter incremented! Current count: 5" logs back while debugging.
+#[cfg(not(feature = "verbose-logs"))]
+use anchor_lang::solana_program::log::sol_log_64;
+#[cfg(feature = "verbose-logs")]
+use anchor_lang::prelude::msg;
+
+// The events we log. "= 1" and "= 2" fix the number each variant is given
+// when converted with "as u64", so the numbers in compact logs never change
+// even if variants are added or reordered later.
+#[derive(Clone, Copy)]
+pub enum LogEvent {
+    Initialized = 1,
+    Incremented = 2,
+    BankIncremented = 3,
+    ReplicaIncremented = 4,
+    ReplicasMerged = 5,
+    Decremented = 6,
+    Reset = 7,
+}
+
+impl LogEvent {
+    // "&'static str" is a string that is baked into the program binary and
+    // lives forever, so no memory needs to be allocated for it.
+    #[cfg(feature = "verbose-logs")]
+    fn message(self) -> &'static str {
+        match self {
+            LogEvent::Initialized => "Counter initialized!",
+            LogEvent::Incremented => "Counter incremented!",
+            LogEvent::BankIncremented => "Bank slot incremented!",
+            LogEvent::ReplicaIncremented => "Replica incremented!",
+            LogEvent::ReplicasMerged => "Replicas merged!",
+            LogEvent::Decremented => "Counter decremented!",
+            LogEvent::Reset => "Counter reset!",
+        }
+    }
+}
+
+// Logs an event together with the counter's current value.
+//
+// "#[cfg(...)]" includes or removes code at compile time. Only one of the
+// two blocks below ends up in the compiled program.
+pub fn log_count(event: LogEvent, count: u64) {
+    #[cfg(feature = "verbose-logs")]
+    msg!("{} Current count: {}", event.message(), count);
+
+    #[cfg(not(feature = "verbose-logs"))]
+    sol_log_64(event as u64, count, 0, 0, 0);
+}
+
+// Same as log_count, for one slot of a CounterBank. The compact form is
+// (event, count, slot index).
+pub fn log_slot(event: LogEvent, index: u16, count: u64) {
+    #[cfg(feature = "verbose-logs")]
+    msg!("{} Slot: {} Current count: {}", event.message(), index, count);
+
+    #[cfg(not(feature = "verbose-logs"))]
+    sol_log_64(event as u64, count, u64::from(index), 0, 0);
+}
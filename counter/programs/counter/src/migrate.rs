@@ -0,0 +1,200 @@
+// ============================================================================
+// MIGRATION - bringing old counters up to the current layout
+// ============================================================================
+
+// New Counter fields are added at the end (authority, flags and
+// last_increment_slot, alarm_threshold, event_seq, finalized, deny_cpi,
+// lifetime_count, role_weighted, tags, max_count and overflow, the TWAC's,
+// delta_feed, frozen, checksum, group, then gifted_count), so the account
+// grows. Counters
+// created before a field existed are still their old size, and Anchor
+// can't load them as Account<Counter>: decoding runs out of bytes. Every
+// instruction that takes a counter fails on them.
+//
+// migrate_counter fixes one: it grows the account to the current size and
+// tops up its rent. The new bytes are zeroes, which decode as each new
+// field's "off" value (false, no tags, no cap, no TWAC, no delta feed, no
+// group, not a gift), so nothing about the counter changes. The exception is
+// lifetime_count: 0 would say the counter never counted, so it starts from
+// the count instead (earlier decrements are lost to history), and the
+// checksum, which is computed over the new layout (see checksum.rs).
+// Nothing the owner controls changes, so anyone may call it; whoever does
+// pays the extra rent.
+//
+// Except for the very first counters, which held only the count: they
+// have no owner to keep, and whoever migrated one would get to pick it.
+// migrate_counter refuses them, and migrate_ownerless_counter migrates
+// them instead, signed by the program's upgrade authority (checked the way
+// initialize_config does, see config.rs), which names the owner.
+//
+// COUNTER_LAYOUTS lists every size a Counter has had. The migrate tool in
+// client/src/bin uses it to find counters that need migrating.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+
+use crate::invariants::debug_assert_invariants;
+use crate::program::Counter as CounterProgram;
+use crate::{Counter, CounterError};
+
+// Every size a Counter account has had, discriminator included, oldest
+// first. The last one is the current layout; add a size here whenever a
+// field is added.
+pub const COUNTER_LAYOUTS: [usize; 16] = [
+    // count
+    8 + 8,
+    // + authority, flags, last_increment_slot
+    8 + 49,
+    // + alarm_threshold
+    8 + 57,
+    // + event_seq
+    8 + 65,
+    // + finalized
+    8 + 66,
+    // + deny_cpi
+    8 + 67,
+    // + lifetime_count
+    8 + 75,
+    // + role_weighted
+    8 + 76,
+    // + tags
+    8 + 140,
+    // + max_count, overflow
+    8 + 180,
+    // + cumulative_count, twac_slot
+    8 + 204,
+    // + delta_feed
+    8 + 356,
+    // + frozen
+    8 + 357,
+    // + checksum
+    8 + 365,
+    // + group
+    8 + 397,
+    // + gifted_count
+    8 + Counter::INIT_SPACE,
+];
+
+// The one of COUNTER_LAYOUTS without an authority.
+pub const OWNERLESS_LAYOUT: usize = 0;
+
+// The first of COUNTER_LAYOUTS with lifetime_count.
+pub const LIFETIME_COUNT_LAYOUT: usize = 6;
+
+// Which of COUNTER_LAYOUTS an account's data is in, or None if it isn't a
+// Counter of any known layout.
+pub fn counter_layout(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(&Counter::DISCRIMINATOR) {
+        return None;
+    }
+    COUNTER_LAYOUTS.iter().position(|&size| size == data.len())
+}
+
+pub fn process_migrate_counter(ctx: Context<MigrateCounter>) -> Result<()> {
+    let counter = ctx.accounts.counter.to_account_info();
+    let layout = counter_layout(&counter.try_borrow_data()?);
+    let Some(layout) = layout.filter(|&layout| layout + 1 < COUNTER_LAYOUTS.len()) else {
+        return err!(CounterError::NotMigratable);
+    };
+    require!(layout != OWNERLESS_LAYOUT, CounterError::OwnerlessCounter);
+    grow(&counter, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+
+    // Either way the counter is decoded and written back, which fills in
+    // its checksum.
+    let mut data = counter.try_borrow_mut_data()?;
+    if layout < LIFETIME_COUNT_LAYOUT {
+        backfill_lifetime_count(&mut data)
+    } else {
+        Counter::try_deserialize(&mut &data[..])?.try_serialize(&mut &mut data[..])
+    }
+}
+
+// migrate_counter, for a counter in OWNERLESS_LAYOUT: it comes out owned
+// by "owner".
+pub fn process_migrate_ownerless_counter(
+    ctx: Context<MigrateOwnerlessCounter>,
+    owner: Pubkey,
+) -> Result<()> {
+    let counter = ctx.accounts.counter.to_account_info();
+    require!(
+        counter_layout(&counter.try_borrow_data()?) == Some(OWNERLESS_LAYOUT),
+        CounterError::NotMigratable
+    );
+    grow(&counter, &ctx.accounts.authority, &ctx.accounts.system_program)?;
+
+    let mut data = counter.try_borrow_mut_data()?;
+    let mut migrated = Counter::try_deserialize(&mut &data[..])?;
+    migrated.authority = owner;
+    migrated.lifetime_count = migrated.count;
+    debug_assert_invariants!(migrated);
+    migrated.try_serialize(&mut &mut data[..])
+}
+
+// Grows "counter" to the current layout, with "payer" topping up its rent.
+fn grow<'info>(
+    counter: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let size = COUNTER_LAYOUTS[COUNTER_LAYOUTS.len() - 1];
+    let rent = Rent::get()?
+        .minimum_balance(size)
+        .saturating_sub(counter.lamports());
+    if rent > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: counter.clone(),
+                },
+            ),
+            rent,
+        )?;
+    }
+    counter.realloc(size, true)?;
+    Ok(())
+}
+
+// Sets lifetime_count to the count in a counter that was just grown to the
+// current layout.
+pub fn backfill_lifetime_count(data: &mut [u8]) -> Result<()> {
+    let mut counter = Counter::try_deserialize(&mut &data[..])?;
+    counter.lifetime_count = counter.count;
+    debug_assert_invariants!(counter);
+    counter.try_serialize(&mut &mut data[..])
+}
+
+#[derive(Accounts)]
+pub struct MigrateCounter<'info> {
+    #[account(mut, owner = crate::ID)]
+    /// CHECK: an old counter can't be loaded as Account<Counter>. "owner"
+    /// makes sure it's ours, and the handler checks its discriminator.
+    pub counter: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateOwnerlessCounter<'info> {
+    #[account(mut, owner = crate::ID)]
+    /// CHECK: as in MigrateCounter.
+    pub counter: UncheckedAccount<'info>,
+
+    // Pays the extra rent. Same checks as in InitializeConfig:
+    // "program_data" is really ours, and this is its upgrade authority.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CounterProgram>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CounterError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,102 @@
+// ============================================================================
+// PREVIEW - what an increment would do, without doing it
+// ============================================================================
+
+// A UI that wants to show "this will make the count 42, cost 5,000
+// lamports and set off the alarm" before the user signs can simulate
+// increment_paid, but then has to dig the fee out of the logs, and the
+// simulation fails for a payer with no lamports yet. preview_increment
+// answers directly, as return data (like get_state, see getters.rs): it
+// runs the same checks and sums as increment, or increment_paid for a
+// counter that charges, on a copy of the counter, and changes nothing.
+// Nothing signs, so it can be simulated for any payer.
+//
+// The answer holds for the state it was previewed against. Another
+// increment landing first changes the count, and maybe the tier of a fee
+// schedule (see fees.rs).
+use anchor_lang::prelude::*;
+
+use crate::{
+    increment_fee, Counter, CounterFees, FeeExemption, FeeSchedule, Route, Version, FEES_SEED,
+    FEE_EXEMPTION_SEED, FEE_SCHEDULE_SEED,
+};
+
+// What an increment would do, as preview_increment returns it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IncrementPreview {
+    // The count and lifetime count afterwards.
+    pub count: u64,
+    pub lifetime_count: u64,
+    // What the payer would pay in all, in lamports, and how much of that
+    // would go to the referrer. Both 0 for counters that don't charge.
+    pub fee: u64,
+    pub referrer_share: u64,
+    // Whether the increment would set off the alarm (AlarmTriggered).
+    pub alarm_triggered: bool,
+    pub version: Version,
+}
+
+pub fn process_preview_increment(
+    ctx: Context<PreviewIncrement>,
+    referrer: Option<Pubkey>,
+) -> Result<IncrementPreview> {
+    let accounts = &ctx.accounts;
+    let mut counter = Counter::clone(&accounts.counter);
+    let before = counter.count;
+
+    // The path increment_paid takes for a counter that charges, and
+    // increment's otherwise. Without "fees", a counter that charges fails
+    // here as it would in increment.
+    let (to_counter, to_referrer) = match &accounts.fees {
+        Some(fees) if counter.has_flag(Counter::FEE_REQUIRED) => {
+            counter.check_route(Route::FeeRequired)?;
+            let mut schedule = accounts.fee_schedule.as_deref().cloned();
+            increment_fee(
+                fees,
+                schedule.as_mut(),
+                accounts.fee_exemption.is_some(),
+                referrer.is_some(),
+            )?
+        }
+        _ => {
+            counter.check_modes()?;
+            (0, 0)
+        }
+    };
+    counter.increment()?;
+
+    Ok(IncrementPreview {
+        count: counter.count,
+        lifetime_count: counter.lifetime_count,
+        fee: to_counter + to_referrer,
+        referrer_share: to_referrer,
+        alarm_triggered: counter.check_alarm(before),
+        version: Version::CURRENT,
+    })
+}
+
+// increment_paid's accounts that decide the fee, all read-only. The
+// optional ones are the same as there: pass those that exist.
+#[derive(Accounts)]
+pub struct PreviewIncrement<'info> {
+    pub counter: Account<'info, Counter>,
+
+    /// CHECK: only its address is used, to find its fee exemption. It
+    /// doesn't sign: nothing is paid.
+    pub payer: UncheckedAccount<'info>,
+
+    #[account(seeds = [FEES_SEED, counter.key().as_ref()], bump = fees.bump)]
+    pub fees: Option<Account<'info, CounterFees>>,
+
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED, counter.key().as_ref()],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Option<Account<'info, FeeSchedule>>,
+
+    #[account(
+        seeds = [FEE_EXEMPTION_SEED, counter.key().as_ref(), payer.key().as_ref()],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+}
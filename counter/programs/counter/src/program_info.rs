@@ -0,0 +1,105 @@
+// ============================================================================
+// PROGRAM INFO - what exactly is deployed at this address?
+// ============================================================================
+
+// Anyone can read a deployed program's bytes, but not which source code
+// they came from. ProgramInfo is a small account, written by whoever
+// deployed the program, that records the version and the git commit it was
+// built from. Auditors can then rebuild that commit and compare.
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::program::Counter as CounterProgram;
+use crate::{CounterError, VERSION};
+
+// The "seed" for the ProgramInfo account's address (see below).
+pub const PROGRAM_INFO_SEED: &[u8] = b"program_info";
+
+// "#[derive(InitSpace)]" makes Anchor calculate the account's size for us
+// (available as ProgramInfo::INIT_SPACE), instead of adding up bytes by
+// hand like "space = 8 + 8" in Initialize.
+/// Which source the deployed program was built from (see
+/// set_program_info).
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramInfo {
+    // Strings can grow, so InitSpace needs to know the longest one we'll
+    // store: "#[max_len(16)]" reserves room for 16 bytes.
+    /// The program's version from Cargo.toml, e.g. "0.1.0".
+    #[max_len(16)]
+    pub version: String,
+
+    /// The 20-byte git commit hash the deployed binary was built from
+    /// (the 40-character hex string from "git rev-parse HEAD", as bytes).
+    pub commit: [u8; 20],
+
+    /// The slot when this record was last written.
+    pub updated_slot: u64,
+
+    // See "bump" on the account constraint below.
+    pub bump: u8,
+}
+
+// Records the deployed version and commit. Call it after every deploy.
+pub fn process_set_program_info(ctx: Context<SetProgramInfo>, commit: [u8; 20]) -> Result<()> {
+    let info = &mut ctx.accounts.program_info;
+
+    // The version this binary was built as (see version.rs).
+    info.version = VERSION.to_string();
+    info.commit = commit;
+
+    // "Clock" is a "sysvar": an account maintained by the runtime that
+    // programs can read. It holds the current slot, time, and epoch.
+    info.updated_slot = clock::slot()?;
+    info.bump = ctx.bumps.program_info;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetProgramInfo<'info> {
+    // This is a "PDA" (Program Derived Address). Instead of a keypair, its
+    // address is calculated from the program id plus some "seeds" - here
+    // just the text "program_info". That means:
+    //   - there's exactly one ProgramInfo account, at an address anyone can
+    //     work out, and
+    //   - nobody has a private key for it, so only this program can sign
+    //     for it.
+    //
+    // "bump" is an extra seed byte Anchor searches for to make sure the
+    // address is a valid PDA. We save it so we don't need to search again.
+    //
+    // "init_if_needed" creates the account the first time and reuses it
+    // after that, so the same instruction works for every deploy.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ProgramInfo::INIT_SPACE,
+        seeds = [PROGRAM_INFO_SEED],
+        bump
+    )]
+    pub program_info: Account<'info, ProgramInfo>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // Our own program account. Upgradeable programs keep their code and
+    // settings in a separate "ProgramData" account, and the program account
+    // just points at it. This check makes sure "program_data" below really
+    // is ours and not some other program's.
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CounterProgram>,
+
+    // ProgramData records the "upgrade authority": the key allowed to
+    // deploy new versions of this program. Only that key may write
+    // ProgramInfo, so nobody else can claim a different commit.
+    //
+    // "@ CounterError::NotUpgradeAuthority" picks which error to return
+    // when the constraint fails.
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CounterError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
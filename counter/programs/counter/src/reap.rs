@@ -0,0 +1,153 @@
+// ============================================================================
+// REAP - closing counters their owners have abandoned
+// ============================================================================
+
+// Counters that nobody uses anymore still hold their rent. An owner who
+// expects to walk away from a counter (a demo, a one-off event) can opt in
+// to having it cleaned up when they create it, with initialize_reapable:
+// once it goes "idle_epochs" epochs without changing, anyone may reap it,
+// which closes it and sends its rent to the "beneficiary" the owner chose.
+// Counters created with plain initialize can never be reaped.
+//
+// Counters don't record when they were last used, and every handler
+// would have to keep that up to date. So the ReapPolicy PDA watches from
+// outside instead: it keeps a hash of the counter's data and the epoch it
+// first saw that data. reap compares the hash with the counter as it is
+// now:
+//
+//   - changed: the counter was used. reap records the new hash and
+//     restarts the wait, and closes nothing
+//   - unchanged for "idle_epochs": reap closes the counter and the policy
+//
+// A keeper (see client/src/bin/keeper.rs) calls it when either is the
+// case. The wait only ever starts late, when the keeper first sees a
+// change, so a counter is never reaped early, only sometimes later.
+//
+// A counter's vault (see lease.rs) can only be emptied by its owner, so
+// reap refuses counters whose vault still holds anything.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use nautilus_common::clock;
+
+use crate::logging::{log_count, LogEvent};
+use crate::{Counter, CounterError, CounterInitialized, CounterReaped, Version, VAULT_SEED};
+
+pub const REAP_POLICY_SEED: &[u8] = b"reap_policy";
+
+/// When an abandoned counter can be closed, and who gets its rent.
+#[account]
+#[derive(InitSpace)]
+pub struct ReapPolicy {
+    pub counter: Pubkey,
+    /// Gets the counter's and the policy's rent when it's reaped.
+    pub beneficiary: Pubkey,
+    /// How long the counter has to stay unchanged, in epochs.
+    pub idle_epochs: u64,
+    /// Hash of the counter's data as reap last saw it, and the epoch it saw
+    /// it first. All zeros until reap's first look.
+    pub seen: [u8; 32],
+    pub seen_epoch: u64,
+    pub bump: u8,
+}
+
+// "initialize", for a counter that can be reaped.
+pub fn process_initialize_reapable(
+    ctx: Context<InitializeReapable>,
+    idle_epochs: u64,
+    beneficiary: Pubkey,
+) -> Result<()> {
+    require!(idle_epochs > 0, CounterError::InvalidReapPolicy);
+    let counter = &mut ctx.accounts.counter;
+    counter.authority = ctx.accounts.user.key();
+
+    let policy = &mut ctx.accounts.reap_policy;
+    policy.counter = counter.key();
+    policy.beneficiary = beneficiary;
+    policy.idle_epochs = idle_epochs;
+    policy.seen_epoch = clock::epoch()?;
+    policy.bump = ctx.bumps.reap_policy;
+
+    log_count(LogEvent::Initialized, counter.count);
+    emit!(CounterInitialized {
+        counter: counter.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// Notes that the counter changed, or closes it if it hasn't for long
+// enough. Anyone can call it.
+pub fn process_reap(ctx: Context<Reap>) -> Result<()> {
+    let epoch = clock::epoch()?;
+    let seen = hash(&ctx.accounts.counter.to_account_info().try_borrow_data()?).to_bytes();
+    let policy = &mut ctx.accounts.reap_policy;
+    if seen != policy.seen {
+        policy.seen = seen;
+        policy.seen_epoch = epoch;
+        return Ok(());
+    }
+    require!(
+        epoch >= policy.seen_epoch.saturating_add(policy.idle_epochs),
+        CounterError::CounterNotIdle
+    );
+    require!(
+        ctx.accounts.vault.lamports() == 0,
+        CounterError::VaultNotEmpty
+    );
+
+    let counter = &mut ctx.accounts.counter;
+    emit!(CounterReaped {
+        counter: counter.key(),
+        beneficiary: policy.beneficiary,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    let beneficiary = ctx.accounts.beneficiary.to_account_info();
+    ctx.accounts.counter.close(beneficiary.clone())?;
+    ctx.accounts.reap_policy.close(beneficiary)
+}
+
+#[derive(Accounts)]
+pub struct InitializeReapable<'info> {
+    // As in Initialize.
+    #[account(init, payer = user, space = 8 + Counter::INIT_SPACE)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ReapPolicy::INIT_SPACE,
+        seeds = [REAP_POLICY_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub reap_policy: Account<'info, ReapPolicy>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Reap<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // Only counters created reapable have one, so no policy, no reaping.
+    #[account(
+        mut,
+        seeds = [REAP_POLICY_SEED, counter.key().as_ref()],
+        bump = reap_policy.bump,
+        has_one = beneficiary @ CounterError::Unauthorized
+    )]
+    pub reap_policy: Account<'info, ReapPolicy>,
+
+    #[account(seeds = [VAULT_SEED, counter.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+
+    // Not a signer: the rent can only go where the owner said.
+    /// CHECK: checked against the policy by "has_one".
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+}
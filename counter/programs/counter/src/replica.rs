@@ -0,0 +1,182 @@
+// ============================================================================
+// REPLICAS - count in several places at once, add it all up later
+// ============================================================================
+
+// Every increment of a Counter writes the same account, so transactions
+// that increment it can't run in parallel, and a bot that can't reach it
+// (say, during an RPC outage in its region) can't count at all.
+//
+// Replicas split the work. Each one is a small account tied to a root
+// Counter that can be incremented on its own. "merge_replicas" later adds
+// whatever each replica counted since its last merge to the root.
+//
+// This is a "grow-only counter" (G-counter), a kind of CRDT: the root's
+// total is the sum of every replica's count, and each replica remembers
+// how much of its count the root already has ("merged"). So merges can
+// happen in any order, any number of times, with any subset of the
+// replicas - a replica left out just gets merged next time.
+use anchor_lang::prelude::*;
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{AlarmTriggered, Counter, CounterError, ReplicaIncremented, ReplicasMerged, Version};
+
+pub const REPLICA_SEED: &[u8] = b"replica";
+
+/// Increments of a counter kept apart, to be merged into it later.
+#[account]
+#[derive(InitSpace)]
+pub struct Replica {
+    /// The Counter this replica's counts are merged into.
+    pub root: Pubkey,
+    /// Chosen by the authority, so each bot can find its own replica's
+    /// address from (root, id).
+    pub id: u16,
+    /// Total increments on this replica, ever.
+    pub count: u64,
+    /// How much of "count" has already been added to the root.
+    pub merged: u64,
+    pub bump: u8,
+}
+
+impl Replica {
+    // Returns what was counted since the last merge and marks it merged.
+    // Calling it again right away returns 0, which is what makes merging
+    // the same replica twice harmless.
+    pub fn take_unmerged(&mut self) -> u64 {
+        // "count" only grows and "merged" is only ever set to "count", so
+        // this can't go below 0.
+        let delta = self.count - self.merged;
+        self.merged = self.count;
+        delta
+    }
+}
+
+// Creates replica "replica_id" of a counter. Only the counter's authority
+// decides which replicas exist.
+pub fn process_initialize_replica(ctx: Context<InitializeReplica>, replica_id: u16) -> Result<()> {
+    let replica = &mut ctx.accounts.replica;
+    replica.root = ctx.accounts.counter.key();
+    replica.id = replica_id;
+    replica.bump = ctx.bumps.replica;
+    Ok(())
+}
+
+// Adds 1 to a replica. Like Increment, anyone can call it.
+pub fn process_increment_replica(ctx: Context<IncrementReplica>) -> Result<()> {
+    let replica = &mut ctx.accounts.replica;
+    replica.count = replica.count.checked_add(1).ok_or(CounterError::Overflow)?;
+    debug_assert_invariants!(replica: replica, replica.root);
+
+    log_count(LogEvent::ReplicaIncremented, replica.count);
+    emit!(ReplicaIncremented {
+        replica: replica.key(),
+        root: replica.root,
+        count: replica.count,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// Adds every replica passed in "remaining_accounts" to the root counter.
+//
+// "remaining_accounts" are extra accounts the client appends after the
+// ones named in MergeReplicas. Anchor doesn't check them for us, so we do
+// it by hand below.
+//
+// Modes like ONCE_PER_SLOT don't apply here: they limit increments, and
+// these were already counted, one by one, on the replicas. The ROUTES
+// modes and the soft cap do: a merge adds to the count like any increment,
+// so it mustn't land in a counter only its mode's instruction may
+// increment (no mint, no fee, ...), or take the count past its cap.
+pub fn process_merge_replicas<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MergeReplicas<'info>>,
+) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.check_unrouted()?;
+    counter.begin_change()?;
+    let before = counter.count;
+
+    for info in ctx.remaining_accounts {
+        // "Account::try_from" does what "Account<'info, Replica>" does in
+        // an accounts struct: checks the owner and discriminator, then
+        // deserializes.
+        let mut replica = Account::<Replica>::try_from(info)?;
+        require_keys_eq!(
+            replica.root,
+            counter.key(),
+            CounterError::ReplicaRootMismatch
+        );
+        require!(info.is_writable, ErrorCode::ConstraintMut);
+
+        counter.add(replica.take_unmerged())?;
+        debug_assert_invariants!(counter);
+        debug_assert_invariants!(replica: replica, counter.key());
+
+        // Anchor only saves the accounts in the accounts struct. "exit"
+        // writes this one's changes back, right away, so if the same
+        // replica is passed twice the second copy sees it already merged.
+        replica.exit(&crate::ID)?;
+    }
+    // Failing undoes the whole merge, replicas included, so they keep what
+    // they counted until the cap is raised or taken off.
+    require!(
+        counter.max_count == 0 || counter.count <= counter.max_count,
+        CounterError::SoftCapReached
+    );
+
+    log_count(LogEvent::ReplicasMerged, counter.count);
+    emit!(ReplicasMerged {
+        counter: counter.key(),
+        count: counter.count,
+        added: counter.count - before,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(replica_id: u16)]
+pub struct InitializeReplica<'info> {
+    #[account(has_one = authority @ CounterError::Unauthorized)]
+    pub counter: Account<'info, Counter>,
+
+    // One replica per (counter, id) pair. "to_le_bytes" turns the u16 into
+    // its 2 bytes, since seeds have to be bytes.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Replica::INIT_SPACE,
+        seeds = [REPLICA_SEED, counter.key().as_ref(), replica_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub replica: Account<'info, Replica>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementReplica<'info> {
+    #[account(mut)]
+    pub replica: Account<'info, Replica>,
+}
+
+#[derive(Accounts)]
+pub struct MergeReplicas<'info> {
+    // Anyone can merge: it only moves counts that already happened.
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+}
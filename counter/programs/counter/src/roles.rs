@@ -0,0 +1,114 @@
+// ============================================================================
+// ROLES - increments weighted by who sends them
+// ============================================================================
+
+// Turns a counter into a participation score where some participants count
+// for more than others: an admin's increment might add 10, a member's 1.
+//
+// The owner gives wallets a weight with set_role, which stores it in a Role
+// PDA per (counter, member); weight 0 takes the role away again. With
+// set_role_weighted on, increments go through increment_by_role, which
+// must be signed by a member and adds their weight to the count. Anyone
+// without a role can't increment at all.
+use anchor_lang::prelude::*;
+
+use crate::access_log::AdminAction;
+use crate::guards::*;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{AlarmTriggered, Counter, CounterIncremented, Route, Version};
+
+pub const ROLE_SEED: &[u8] = b"role";
+
+/// One member's weight on one counter.
+#[account]
+#[derive(InitSpace)]
+pub struct Role {
+    pub counter: Pubkey,
+    pub member: Pubkey,
+    /// Added to the count by each of the member's increments. Never 0: a
+    /// role set to 0 is closed instead.
+    pub weight: u64,
+    pub bump: u8,
+}
+
+// Gives "member" a weight, changes it, or with 0 takes the role away and
+// refunds its rent to the owner.
+pub fn process_set_role(ctx: Context<SetRole>, member: Pubkey, weight: u64) -> Result<()> {
+    let guard = &ctx.accounts.guard;
+    if weight == 0 {
+        guard.close_to_owner(&ctx.accounts.role)?;
+    } else {
+        let role = &mut ctx.accounts.role;
+        role.counter = guard.counter.key();
+        role.member = member;
+        role.weight = weight;
+        role.bump = ctx.bumps.role;
+    }
+    guard.log(AdminAction::SetRole)
+}
+
+// Adds the signing member's weight to a role-weighted counter.
+pub fn process_increment_by_role(ctx: Context<IncrementByRole>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.check_route(Route::RoleWeighted)?;
+
+    let before = counter.count;
+    counter.add(ctx.accounts.role.weight)?;
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct SetRole<'info> {
+    // Who counts for how much is the owner's call, lease or not (see
+    // lease.rs).
+    pub guard: AuthorityGuard<'info>,
+
+    // Created the first time "member" gets a role. Closed by the handler
+    // when the weight is 0 (see guards.rs).
+    #[account(
+        init_if_needed,
+        payer = guard.authority,
+        space = 8 + Role::INIT_SPACE,
+        seeds = [ROLE_SEED, guard.counter.key().as_ref(), member.as_ref()],
+        bump
+    )]
+    pub role: Account<'info, Role>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementByRole<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // The seeds tie the role to this counter and the signer, so nobody can
+    // use someone else's weight.
+    #[account(
+        seeds = [ROLE_SEED, counter.key().as_ref(), member.key().as_ref()],
+        bump = role.bump
+    )]
+    pub role: Account<'info, Role>,
+
+    pub member: Signer<'info>,
+}
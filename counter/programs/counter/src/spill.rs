@@ -0,0 +1,132 @@
+// ============================================================================
+// SPILL - a soft cap, with the excess counted elsewhere
+// ============================================================================
+
+// A campaign with a fixed quota ("the first 1,000 sign-ups") wants its
+// counter to stop there, but also wants to know how many more tried. With
+// a soft cap the counter's "max_count" is its quota: once the count
+// reaches it, increment_spilling adds to the linked "overflow" counter
+// instead, and the primary stays at the cap.
+//
+// Every increment path refuses a counter at its cap with SoftCapReached
+// instead of going past it (see Counter::check_modes and check_route): a
+// client that meets that error on a plain counter sends
+// increment_spilling. That works below the cap too, so a client can send
+// it all along. Counters in one of the ROUTES modes can only be
+// incremented by their mode's instruction, so they stop at the cap.
+//
+// The overflow is an ordinary counter, with its own owner and modes, and
+// the spilled increment goes through the same checks as a plain increment
+// of it would. It can have a cap of its own, but doesn't spill further: a
+// full overflow refuses like any capped counter.
+use anchor_lang::prelude::*;
+
+use crate::access_log::AdminAction;
+use crate::args::check_args_version;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    AlarmTriggered, Counter, CounterError, CounterIncremented, CounterSpilled, UpdateCounter,
+    Version,
+};
+
+// set_soft_cap's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SoftCapArgs {
+    pub version: u8,
+    // 0 takes the cap off.
+    pub max_count: u64,
+    // Ignored without a cap.
+    pub overflow: Pubkey,
+}
+
+impl SoftCapArgs {
+    pub const VERSION: u8 = 1;
+}
+
+impl Counter {
+    // Whether the count has reached "max_count", so increments spill.
+    pub fn is_capped(&self) -> bool {
+        self.max_count != 0 && self.count >= self.max_count
+    }
+}
+
+pub fn process_set_soft_cap(ctx: Context<UpdateCounter>, args: SoftCapArgs) -> Result<()> {
+    check_args_version(args.version, SoftCapArgs::VERSION)?;
+    ctx.accounts.log(AdminAction::SetSoftCap)?;
+    let counter = &mut ctx.accounts.counter;
+    if args.max_count == 0 {
+        counter.max_count = 0;
+        counter.overflow = Pubkey::default();
+        return Ok(());
+    }
+    require_keys_neq!(args.overflow, counter.key(), CounterError::InvalidSoftCap);
+    require_keys_neq!(
+        args.overflow,
+        Pubkey::default(),
+        CounterError::InvalidSoftCap
+    );
+    counter.max_count = args.max_count;
+    counter.overflow = args.overflow;
+    Ok(())
+}
+
+// "increment", adding to the overflow instead once the counter is capped.
+pub fn process_increment_spilling(ctx: Context<IncrementSpilling>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    // The counter's own limits (once per slot, deny_cpi, ...) hold for
+    // every increment sent to it, spilled or not.
+    counter.check_unrouted()?;
+    counter.check_limits()?;
+    if !counter.is_capped() {
+        return count(counter);
+    }
+
+    let overflow = &mut ctx.accounts.overflow;
+    overflow.check_modes()?;
+    count(overflow)?;
+    emit!(CounterSpilled {
+        counter: counter.key(),
+        overflow: overflow.key(),
+        overflow_count: overflow.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+// The rest of "increment", once the checks have passed.
+fn count(counter: &mut Account<Counter>) -> Result<()> {
+    let before = counter.count;
+    counter.increment()?;
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct IncrementSpilling<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    // Passed even while the counter is under its cap, so a client doesn't
+    // need to know which side of it the count is on.
+    #[account(mut, address = counter.overflow @ CounterError::InvalidSoftCap)]
+    pub overflow: Account<'info, Counter>,
+}
@@ -0,0 +1,276 @@
+// ============================================================================
+// SPONSOR - counters whose rent the program pays
+// ============================================================================
+
+// Creating a counter costs its rent, so a new user needs SOL before they
+// can create their first one. With sponsorship on, initialize_sponsored
+// creates it with lamports from the program's treasury instead, up to a
+// quota of counters per wallet. The transaction fee is still someone's:
+// usually the front-end's, as the fee payer, with the user only signing.
+//
+// The treasury is a plain system account at a PDA, like a counter's vault
+// (see lease.rs): anyone can fund it with a transfer, and only the
+// Config's admin (see config.rs) can take lamports back out. The admin
+// also sets the quota, in the Sponsorship PDA; a quota of 0, or no
+// Sponsorship at all, turns sponsoring off.
+//
+// Anchor's "init" needs a payer that signs the transaction, which a PDA
+// can't, so initialize_sponsored creates its accounts itself: the counter,
+// and on a wallet's first sponsored counter its SponsoredWallet, which
+// counts them. Both are paid for by the treasury, signing for its PDA.
+//
+// A quota per wallet doesn't stop anyone from making new wallets, so the
+// treasury is best thought of as a budget: fund it with what onboarding
+// is worth, and it runs out rather than overdrawing anything.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{Config, Counter, CounterError, CounterInitialized, Version, CONFIG_SEED};
+
+pub const TREASURY_SEED: &[u8] = b"treasury";
+pub const SPONSORSHIP_SEED: &[u8] = b"sponsorship";
+pub const SPONSORED_WALLET_SEED: &[u8] = b"sponsored_wallet";
+
+/// How many counters the treasury pays for, program-wide.
+#[account]
+#[derive(InitSpace)]
+pub struct Sponsorship {
+    /// Counters the treasury pays for per wallet. 0 turns sponsoring off.
+    pub quota: u16,
+    pub bump: u8,
+}
+
+/// The counters the treasury has paid for one wallet.
+#[account]
+#[derive(InitSpace)]
+pub struct SponsoredWallet {
+    pub wallet: Pubkey,
+    /// Counters created for this wallet so far.
+    pub count: u16,
+    pub bump: u8,
+}
+
+// Sets how many counters the treasury pays for per wallet.
+pub fn process_set_sponsorship(ctx: Context<SetSponsorship>, quota: u16) -> Result<()> {
+    let sponsorship = &mut ctx.accounts.sponsorship;
+    sponsorship.quota = quota;
+    sponsorship.bump = ctx.bumps.sponsorship;
+    Ok(())
+}
+
+// Sends "amount" lamports from the treasury to the admin.
+pub fn process_withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.treasury.to_account_info(),
+                to: ctx.accounts.admin.to_account_info(),
+            },
+            &[&[TREASURY_SEED, &[ctx.bumps.treasury]]],
+        ),
+        amount,
+    )
+}
+
+// "initialize", with the rent paid by the treasury.
+pub fn process_initialize_sponsored(ctx: Context<InitializeSponsored>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let user = accounts.user.key();
+    let treasury_seeds: &[&[u8]] = &[TREASURY_SEED, &[ctx.bumps.treasury]];
+    let wallet_seeds: &[&[u8]] = &[
+        SPONSORED_WALLET_SEED,
+        user.as_ref(),
+        &[ctx.bumps.sponsored_wallet],
+    ];
+
+    let wallet_info = accounts.sponsored_wallet.to_account_info();
+    let mut wallet = if wallet_info.data_is_empty() {
+        SponsoredWallet {
+            wallet: user,
+            count: 0,
+            bump: ctx.bumps.sponsored_wallet,
+        }
+    } else {
+        SponsoredWallet::try_deserialize(&mut &wallet_info.try_borrow_data()?[..])?
+    };
+    require!(
+        wallet.count < accounts.sponsorship.quota,
+        CounterError::SponsorQuotaReached
+    );
+
+    // What the treasury is about to pay, on top of what keeps it
+    // rent-exempt itself.
+    let rent = Rent::get()?;
+    let wallet_space = 8 + SponsoredWallet::INIT_SPACE;
+    let counter_space = 8 + Counter::INIT_SPACE;
+    let mut needed = rent.minimum_balance(counter_space) + rent.minimum_balance(0);
+    if wallet_info.data_is_empty() {
+        needed += rent.minimum_balance(wallet_space);
+    }
+    require!(
+        accounts.treasury.lamports() >= needed,
+        CounterError::TreasuryTooLow
+    );
+
+    if wallet_info.data_is_empty() {
+        create(
+            accounts,
+            &wallet_info,
+            wallet_space,
+            &[treasury_seeds, wallet_seeds],
+        )?;
+    }
+    let counter_info = accounts.counter.to_account_info();
+    create(accounts, &counter_info, counter_space, &[treasury_seeds])?;
+
+    wallet.count += 1;
+    wallet.try_serialize(&mut &mut wallet_info.try_borrow_mut_data()?[..])?;
+
+    let mut counter = Counter {
+        authority: user,
+        ..Counter::default()
+    };
+    debug_assert_invariants!(counter);
+    log_count(LogEvent::Initialized, counter.count);
+    emit!(CounterInitialized {
+        counter: counter_info.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    counter.try_serialize(&mut &mut counter_info.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// Creates "account", owned by this program, with the treasury's lamports.
+//
+// The System Program's create_account fails on an address that already
+// holds lamports, and anyone can send a few to the SponsoredWallet's
+// address before its first counter, locking that wallet out. So, as
+// Anchor's "init" does, an account that's already funded is topped up to
+// rent-exempt, then allocated and assigned to us instead.
+fn create<'info>(
+    accounts: &InitializeSponsored<'info>,
+    account: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let system_program = accounts.system_program.to_account_info();
+    let rent = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program,
+                CreateAccount {
+                    from: accounts.treasury.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Transfer {
+                    from: accounts.treasury.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program,
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            signer_seeds,
+        ),
+        &crate::ID,
+    )
+}
+
+#[derive(Accounts)]
+pub struct SetSponsorship<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump, has_one = admin @ CounterError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Sponsorship::INIT_SPACE,
+        seeds = [SPONSORSHIP_SEED],
+        bump
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump, has_one = admin @ CounterError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [TREASURY_SEED], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSponsored<'info> {
+    #[account(seeds = [SPONSORSHIP_SEED], bump = sponsorship.bump)]
+    pub sponsorship: Account<'info, Sponsorship>,
+
+    #[account(mut, seeds = [TREASURY_SEED], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: the user's SponsoredWallet, or where it will be created; its
+    /// address is checked by the seeds, and its data in the handler.
+    #[account(
+        mut,
+        seeds = [SPONSORED_WALLET_SEED, user.key().as_ref()],
+        bump,
+        constraint = sponsored_wallet.data_is_empty()
+            || sponsored_wallet.owner == &crate::ID @ ErrorCode::AccountOwnedByWrongProgram
+    )]
+    pub sponsored_wallet: UncheckedAccount<'info>,
+
+    // A new keypair, as in Initialize. It signs so the System Program
+    // will create it; creating fails if it already exists.
+    #[account(mut)]
+    pub counter: Signer<'info>,
+
+    // Needn't hold any SOL.
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
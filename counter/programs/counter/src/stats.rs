@@ -0,0 +1,90 @@
+// ============================================================================
+// STATS - hourly rollups for charts
+// ============================================================================
+
+// Drawing a chart of a counter's activity from its events means replaying
+// every transaction. Instead, increments that know who made them
+// (increment_paid, increment_contributed) add themselves to a StatsBucket
+// PDA for the current hour, so a day of activity is 24 account reads.
+//
+// Buckets are seeded by (counter, unix hour), where the unix hour is the
+// unix time divided by 3600. "init_if_needed" creates each bucket with
+// the first increment of its hour, paid for by whoever makes it. Plain
+// increment has no signer to pay with, so it isn't bucketed.
+//
+// Counting unique actors exactly would take an account per actor per
+// hour. Each bucket keeps a 1024-bit "bitmap" instead: every actor sets
+// one bit, picked by hashing their key, and "unique_actors" counts the
+// bits set. Two actors can land on the same bit, so it's a slight
+// undercount once an hour sees hundreds of actors; clients that care can
+// correct for it from the bitmap ("linear counting").
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use nautilus_common::clock;
+
+pub const STATS_SEED: &[u8] = b"stats";
+pub const SECONDS_PER_HOUR: i64 = 3600;
+pub const ACTOR_BITS: usize = 1024;
+
+/// One hour of a counter's activity.
+#[account]
+#[derive(InitSpace)]
+pub struct StatsBucket {
+    pub counter: Pubkey,
+    /// The hour: unix seconds / 3600.
+    pub unix_hour: i64,
+    /// Increments that hour, a count.
+    pub increments: u64,
+    /// Bits set in "actors": about how many different signers incremented.
+    pub unique_actors: u32,
+    /// Lamports paid in fees through increment_paid, referral shares
+    /// included.
+    pub fee_revenue: u64,
+    /// One bit per signer, picked by hashing their key.
+    pub actors: [u8; ACTOR_BITS / 8],
+    pub bump: u8,
+}
+
+impl StatsBucket {
+    // Adds one increment by "actor" that paid "fee" lamports.
+    pub fn add(&mut self, actor: &Pubkey, fee: u64) {
+        self.increments = self.increments.saturating_add(1);
+        self.fee_revenue = self.fee_revenue.saturating_add(fee);
+
+        let digest = hash(actor.as_ref()).to_bytes();
+        let bit = usize::from(u16::from_le_bytes([digest[0], digest[1]])) % ACTOR_BITS;
+        let (byte, mask) = (bit / 8, 1u8 << (bit % 8));
+        if self.actors[byte] & mask == 0 {
+            self.actors[byte] |= mask;
+            self.unique_actors += 1;
+        }
+    }
+}
+
+// The current unix hour.
+pub fn current_hour() -> Result<i64> {
+    Ok(clock::now()?.div_euclid(SECONDS_PER_HOUR))
+}
+
+// The current unix hour as bucket seeds. Seeds can't use "?", so a clock
+// error gives hour 0 here; "record_increment" then fails on it properly.
+pub fn current_hour_seed() -> [u8; 8] {
+    current_hour().unwrap_or_default().to_le_bytes()
+}
+
+// Fills in a bucket's identity the first time it's used, then records.
+pub fn record_increment(
+    bucket: &mut Account<StatsBucket>,
+    bump: u8,
+    counter: Pubkey,
+    actor: &Pubkey,
+    fee: u64,
+) -> Result<()> {
+    if bucket.counter == Pubkey::default() {
+        bucket.counter = counter;
+        bucket.unix_hour = current_hour()?;
+        bucket.bump = bump;
+    }
+    bucket.add(actor, fee);
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// ============================================================================
+// SWEEP - emptying many vaults into the treasury at once
+// ============================================================================
+
+// A team that runs many paid counters collects fees in as many vaults
+// (see fees.rs and lease.rs), and withdraw_vault empties one at a time.
+// sweep_fees empties any number of them into the program's treasury (see
+// sponsor.rs) in one instruction, with a FeesSwept event per counter for
+// the books.
+//
+// The counters and their vaults are passed as pairs in
+// "remaining_accounts": counter, vault, counter, vault, ... Every counter
+// must be the signer's, since only a counter's owner may take lamports
+// out of its vault. Each vault is emptied completely; an empty vault is
+// skipped without an event.
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+use crate::{Counter, CounterError, FeesSwept, Version, TREASURY_SEED, VAULT_SEED};
+
+pub fn process_sweep_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SweepFees<'info>>,
+) -> Result<()> {
+    let pairs = ctx.remaining_accounts.chunks_exact(2);
+    require!(pairs.remainder().is_empty(), CounterError::InvalidSweep);
+
+    for pair in pairs {
+        let (counter_info, vault) = (&pair[0], &pair[1]);
+        // Checked by hand, as Anchor would for "Account<'info, Counter>"
+        // with "has_one = authority" (see replica.rs).
+        let mut counter = Account::<Counter>::try_from(counter_info)?;
+        require_keys_eq!(
+            counter.authority,
+            ctx.accounts.authority.key(),
+            CounterError::Unauthorized
+        );
+        require!(counter_info.is_writable, ErrorCode::ConstraintMut);
+        let (address, bump) =
+            Pubkey::find_program_address(&[VAULT_SEED, counter.key().as_ref()], &crate::ID);
+        require_keys_eq!(vault.key(), address, CounterError::InvalidSweep);
+
+        let amount = vault.lamports();
+        if amount == 0 {
+            continue;
+        }
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: vault.clone(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+                &[&[VAULT_SEED, counter.key().as_ref(), &[bump]]],
+            ),
+            amount,
+        )?;
+        emit!(FeesSwept {
+            counter: counter.key(),
+            amount,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+        // Saves the new event_seq (see merge_replicas).
+        counter.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepFees<'info> {
+    #[account(mut, seeds = [TREASURY_SEED], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    // Owns every counter swept.
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
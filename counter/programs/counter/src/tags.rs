@@ -0,0 +1,148 @@
+// ============================================================================
+// TAGS - sorting counters into categories
+// ============================================================================
+
+// Counters from different ecosystems (games, DAOs, events, ...) all live
+// in this one program, so browsing one kind means telling them apart.
+// initialize_tagged creates a counter with up to MAX_COUNTER_TAGS tags,
+// which it keeps for good: there's no instruction to change them.
+//
+// A tag is a short name, stored in a fixed TAG_LEN bytes (see encode_tag)
+// so each one sits at a known offset in the account. That lets a client
+// ask the RPC node for exactly the counters with a tag, with a memcmp
+// filter per slot (see fetch_by_tag in the client), instead of fetching
+// them all. Unused slots are all zeros.
+//
+// Only tags in the TagRegistry PDA can be used, so there's one spelling of
+// each category and a front-end can list them. The Config's admin (see
+// config.rs) adds them with register_tag.
+use anchor_lang::prelude::*;
+
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    Config, Counter, CounterError, CounterInitialized, Version, CONFIG_SEED, COUNTER_LAYOUTS,
+};
+
+pub const TAG_REGISTRY_SEED: &[u8] = b"tag_registry";
+
+pub const TAG_LEN: usize = 16;
+pub const MAX_COUNTER_TAGS: usize = 4;
+pub const MAX_REGISTERED_TAGS: usize = 64;
+
+// The first of COUNTER_LAYOUTS with tags.
+pub const TAGS_LAYOUT: usize = 8;
+
+// Where Counter's "tags" start in the account, discriminator included:
+// right after the last field before them (see migrate.rs).
+pub const TAGS_OFFSET: usize = COUNTER_LAYOUTS[TAGS_LAYOUT - 1];
+
+// "name" as a tag: its bytes, padded with zeros. None if it's empty, too
+// long, or ends in a zero byte (which padding couldn't tell apart).
+pub fn encode_tag(name: &str) -> Option<[u8; TAG_LEN]> {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() || bytes.len() > TAG_LEN || bytes.ends_with(&[0]) {
+        return None;
+    }
+    let mut tag = [0; TAG_LEN];
+    tag[..bytes.len()].copy_from_slice(bytes);
+    Some(tag)
+}
+
+// The name encode_tag made "tag" from.
+pub fn decode_tag(tag: &[u8; TAG_LEN]) -> String {
+    let len = tag.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&tag[..len]).into_owned()
+}
+
+impl Counter {
+    // The counter's tags, without the unused slots.
+    pub fn tags(&self) -> impl Iterator<Item = &[u8; TAG_LEN]> {
+        self.tags.iter().filter(|tag| **tag != [0; TAG_LEN])
+    }
+}
+
+/// The tags counters can use, program-wide.
+#[account]
+#[derive(InitSpace)]
+pub struct TagRegistry {
+    /// Each tag's name, padded with zeros to TAG_LEN bytes.
+    #[max_len(MAX_REGISTERED_TAGS)]
+    pub tags: Vec<[u8; TAG_LEN]>,
+    pub bump: u8,
+}
+
+// Adds "tag" to the registry, creating it the first time.
+pub fn process_register_tag(ctx: Context<RegisterTag>, tag: [u8; TAG_LEN]) -> Result<()> {
+    require!(tag != [0; TAG_LEN], CounterError::InvalidTag);
+    let registry = &mut ctx.accounts.tag_registry;
+    registry.bump = ctx.bumps.tag_registry;
+    if !registry.tags.contains(&tag) {
+        require!(
+            registry.tags.len() < MAX_REGISTERED_TAGS,
+            CounterError::TagRegistryFull
+        );
+        registry.tags.push(tag);
+    }
+    Ok(())
+}
+
+// "initialize", with tags.
+pub fn process_initialize_tagged(
+    ctx: Context<InitializeTagged>,
+    tags: Vec<[u8; TAG_LEN]>,
+) -> Result<()> {
+    require!(tags.len() <= MAX_COUNTER_TAGS, CounterError::InvalidTag);
+    let registered = &ctx.accounts.tag_registry.tags;
+    let counter = &mut ctx.accounts.counter;
+    for (i, tag) in tags.iter().enumerate() {
+        require!(!tags[..i].contains(tag), CounterError::InvalidTag);
+        require!(registered.contains(tag), CounterError::UnknownTag);
+        counter.tags[i] = *tag;
+    }
+    counter.authority = ctx.accounts.user.key();
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Initialized, counter.count);
+    emit!(CounterInitialized {
+        counter: counter.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RegisterTag<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump, has_one = admin @ CounterError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + TagRegistry::INIT_SPACE,
+        seeds = [TAG_REGISTRY_SEED],
+        bump
+    )]
+    pub tag_registry: Account<'info, TagRegistry>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTagged<'info> {
+    // As in Initialize.
+    #[account(init, payer = user, space = 8 + Counter::INIT_SPACE)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(seeds = [TAG_REGISTRY_SEED], bump = tag_registry.bump)]
+    pub tag_registry: Account<'info, TagRegistry>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
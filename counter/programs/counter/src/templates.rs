@@ -0,0 +1,180 @@
+// ============================================================================
+// TEMPLATES - curated counter presets, set up by the program's admin
+// ============================================================================
+
+// A counter with a fee, a pace limit and an alarm takes several
+// instructions to set up (initialize, set_increment_fee,
+// set_once_per_slot, ...), each with its own arguments. Templates let the
+// Config's admin (see config.rs) name a set of those settings once, so a
+// front-end can offer "tip jar" or "heartbeat" presets and create a
+// counter from one with initialize_from_template(name).
+//
+// Each template is its own PDA, seeded by its name, so the Config itself
+// never grows. A template only shapes counters when they're created:
+// changing or removing it later leaves existing counters alone, and their
+// owner can change any of the settings afterwards like on any counter.
+//
+// The settings are the ones a counter has: a fee (see fees.rs), the
+// once-per-slot pace limit (its cooldown), an alarm threshold and
+// deny_cpi (see cpi_guard.rs). Counters here always step by 1 and have no
+// cap on their count, so there's nothing to preset for those.
+use anchor_lang::prelude::*;
+
+use crate::args::check_args_version;
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    Config, Counter, CounterError, CounterFees, CounterInitialized, Version, CONFIG_SEED,
+    FEES_SEED, MAX_BPS,
+};
+
+pub const TEMPLATE_SEED: &[u8] = b"template";
+
+// A seed is at most 32 bytes, and the name is one.
+pub const MAX_TEMPLATE_NAME: usize = 32;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TemplateSettings {
+    /// Charged per increment, in lamports; 0 for a free counter. See
+    /// CounterFees.
+    pub fee_lamports: u64,
+    /// The referrer's share of each fee, in basis points.
+    pub referral_bps: u16,
+    pub once_per_slot: bool,
+    /// The count that raises an alarm, or 0 for no alarm.
+    pub alarm_threshold: u64,
+    pub deny_cpi: bool,
+}
+
+// set_template's arguments (see args.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TemplateArgs {
+    pub version: u8,
+    pub name: String,
+    pub settings: TemplateSettings,
+}
+
+impl TemplateArgs {
+    pub const VERSION: u8 = 1;
+}
+
+/// Settings a front-end can create counters with, by name.
+#[account]
+#[derive(InitSpace)]
+pub struct Template {
+    #[max_len(MAX_TEMPLATE_NAME)]
+    pub name: String,
+    pub settings: TemplateSettings,
+    pub bump: u8,
+}
+
+// Creates the template "name", or replaces its settings.
+pub fn process_set_template(ctx: Context<SetTemplate>, args: TemplateArgs) -> Result<()> {
+    check_args_version(args.version, TemplateArgs::VERSION)?;
+    let TemplateArgs { name, settings, .. } = args;
+    require!(
+        !name.is_empty() && name.len() <= MAX_TEMPLATE_NAME,
+        CounterError::InvalidTemplateName
+    );
+    require!(settings.referral_bps <= MAX_BPS, CounterError::InvalidFee);
+
+    let template = &mut ctx.accounts.template;
+    template.name = name;
+    template.settings = settings;
+    template.bump = ctx.bumps.template;
+    Ok(())
+}
+
+// "initialize", with the template's settings applied.
+pub fn process_initialize_from_template(ctx: Context<InitializeFromTemplate>) -> Result<()> {
+    let settings = &ctx.accounts.template.settings;
+    let counter = &mut ctx.accounts.counter;
+    counter.authority = ctx.accounts.user.key();
+    counter.set_flag(Counter::ONCE_PER_SLOT, settings.once_per_slot);
+    counter.set_flag(Counter::FEE_REQUIRED, settings.fee_lamports > 0);
+    counter.alarm_threshold = settings.alarm_threshold;
+    counter.deny_cpi = settings.deny_cpi;
+    debug_assert_invariants!(counter);
+
+    // Only a template with a fee needs the fees account.
+    if settings.fee_lamports > 0 {
+        let Some(fees) = &mut ctx.accounts.fees else {
+            return err!(CounterError::FeesAccountMissing);
+        };
+        fees.counter = counter.key();
+        fees.fee_lamports = settings.fee_lamports;
+        fees.referral_bps = settings.referral_bps;
+        fees.bump = ctx.bumps.fees.unwrap_or_default();
+    }
+
+    log_count(LogEvent::Initialized, counter.count);
+    emit!(CounterInitialized {
+        counter: counter.key(),
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(args: TemplateArgs)]
+pub struct SetTemplate<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump, has_one = admin @ CounterError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Template::INIT_SPACE,
+        seeds = [TEMPLATE_SEED, args.name.as_bytes()],
+        bump
+    )]
+    pub template: Account<'info, Template>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveTemplate<'info> {
+    #[account(seeds = [CONFIG_SEED], bump = config.bump, has_one = admin @ CounterError::Unauthorized)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [TEMPLATE_SEED, template.name.as_bytes()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, Template>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFromTemplate<'info> {
+    // As in Initialize.
+    #[account(init, payer = user, space = 8 + Counter::INIT_SPACE)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(seeds = [TEMPLATE_SEED, template.name.as_bytes()], bump = template.bump)]
+    pub template: Account<'info, Template>,
+
+    // Only for a template with a fee (see set_increment_fee).
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CounterFees::INIT_SPACE,
+        seeds = [FEES_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub fees: Option<Account<'info, CounterFees>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
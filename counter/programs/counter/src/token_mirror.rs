@@ -0,0 +1,249 @@
+// ============================================================================
+// TOKEN MIRROR - a counter whose count is also a token supply
+// ============================================================================
+
+// Other programs and wallets can't read a Counter without knowing its
+// layout, but every one of them understands SPL tokens. A mirrored counter
+// has its own mint, and the mint's supply always equals the count:
+//
+//   - increment_minted adds 1 and mints 1 token to any account of the mint
+//   - decrement_minted subtracts 1 and burns 1 token from the caller
+//
+// So holdings of the token show who did the counting, and anything that
+// can read a token balance (wallets, explorers, DeFi programs) can use it.
+//
+// The mint is a PDA of the counter, with 0 decimals. Its mint authority is
+// another PDA of this program, so only these instructions can mint. It
+// works with either token program (Token or Token-2022).
+//
+// Once mirrored, a counter can't be incremented any other way (the
+// TOKEN_MIRRORED flag), or the supply would fall behind.
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Burn, Mint, MintTo, TokenAccount, TokenInterface};
+
+use crate::access_log::{self, AccessLog, AdminAction, ACCESS_LOG_SEED};
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{
+    AlarmTriggered, Counter, CounterDecremented, CounterError, CounterIncremented, Route, Version,
+};
+
+pub const COUNTER_MINT_SEED: &[u8] = b"counter_mint";
+// Signs for every mirrored counter's mint.
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+
+// Creates the counter's mint and mints its current count to the
+// authority, so the supply starts out equal to the count.
+pub fn process_initialize_counter_mint(ctx: Context<InitializeCounterMint>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    // increment_logged doesn't mint, so the modes can't be combined.
+    counter.check_route_free(Counter::TOKEN_MIRRORED)?;
+    counter.set_flag(Counter::TOKEN_MIRRORED, true);
+    debug_assert_invariants!(counter);
+    access_log::record(
+        &ctx.accounts.access_log,
+        counter.key(),
+        ctx.accounts.authority.key(),
+        AdminAction::InitializeCounterMint,
+    )?;
+
+    let count = counter.count;
+    if count > 0 {
+        mint_to(
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.authority_tokens,
+            &ctx.accounts.mint_authority,
+            ctx.bumps.mint_authority,
+            count,
+        )?;
+    }
+    Ok(())
+}
+
+// Adds 1 to a mirrored counter and mints 1 token to "recipient".
+pub fn process_increment_minted(ctx: Context<IncrementMinted>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.check_route(Route::TokenMirrored)?;
+    let before = counter.count;
+    counter.increment()?;
+    debug_assert_invariants!(counter);
+
+    mint_to(
+        &ctx.accounts.token_program,
+        &ctx.accounts.mint,
+        &ctx.accounts.recipient,
+        &ctx.accounts.mint_authority,
+        ctx.bumps.mint_authority,
+        1,
+    )?;
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+// Burns 1 of the caller's tokens and subtracts 1 from the counter.
+pub fn process_decrement_minted(ctx: Context<DecrementMinted>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.begin_change()?;
+    counter.subtract(1)?;
+    debug_assert_invariants!(counter);
+
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.holder_tokens.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+
+    log_count(LogEvent::Decremented, counter.count);
+    emit!(CounterDecremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+fn mint_to<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    mint_authority: &UncheckedAccount<'info>,
+    authority_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            MintTo {
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: mint_authority.to_account_info(),
+            },
+            &[&[MINT_AUTHORITY_SEED, &[authority_bump]]],
+        ),
+        amount,
+    )
+}
+
+#[derive(Accounts)]
+pub struct InitializeCounterMint<'info> {
+    #[account(mut, has_one = authority @ CounterError::Unauthorized)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [COUNTER_MINT_SEED, counter.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [MINT_AUTHORITY_SEED], bump)]
+    /// CHECK: our PDA; holds no data, only signs.
+    pub mint_authority: UncheckedAccount<'info>,
+
+    // See UpdateCounter in lib.rs.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AccessLog::SPACE,
+        seeds = [ACCESS_LOG_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub access_log: AccountLoader<'info, AccessLog>,
+
+    // Receives the tokens for the count so far.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub authority_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementMinted<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        seeds = [COUNTER_MINT_SEED, counter.key().as_ref()],
+        bump,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [MINT_AUTHORITY_SEED], bump)]
+    /// CHECK: our PDA; holds no data, only signs.
+    pub mint_authority: UncheckedAccount<'info>,
+
+    // Like increment, anyone can call this, and the token goes wherever
+    // they like.
+    #[account(mut, token::mint = mint, token::token_program = token_program)]
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DecrementMinted<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        seeds = [COUNTER_MINT_SEED, counter.key().as_ref()],
+        bump,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub holder_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
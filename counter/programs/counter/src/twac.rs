@@ -0,0 +1,93 @@
+// ============================================================================
+// TWAC - the time-weighted average count
+// ============================================================================
+
+// A counter used as a live metric (players online, open positions, ...)
+// goes up and down, and its count at any one moment says little about
+// how it's been doing. With set_twac on, the counter keeps the trick AMM
+// price oracles use: a running sum of "count * slots it was held for",
+// "cumulative_count". Anyone can read it twice and divide:
+//
+//     average = (cumulative now - cumulative then) / (slots in between)
+//
+// which is the count averaged over that window, weighted by how long it
+// sat at each value. No history is stored, and a consumer picks its own
+// window.
+//
+// The sum is brought up to date just before every change to the count
+// (see Counter::begin_change), with the count from before it, so the
+// stored value is the sum as of "twac_slot". get_twac adds the time since
+// then, handing back the sum as of the current slot.
+//
+// The sum is a u128 that wraps around rather than overflowing. Averages
+// come from differences, which wrapping_sub gets right as long as the
+// window is shorter than a full wrap: 2^64 slots at the largest count.
+use anchor_lang::prelude::*;
+use nautilus_common::clock;
+
+use crate::access_log::AdminAction;
+use crate::{Counter, CounterError, ReadCounter, UpdateCounter};
+
+// The TWAC accumulator at one slot, as get_twac returns it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwacObservation {
+    pub cumulative_count: u128,
+    pub slot: u64,
+}
+
+impl TwacObservation {
+    // The average count between "earlier" and this observation, rounded
+    // down. None unless "earlier" is from an earlier slot.
+    pub fn average_since(&self, earlier: &TwacObservation) -> Option<u64> {
+        let slots = self.slot.checked_sub(earlier.slot).filter(|&s| s > 0)?;
+        let sum = self.cumulative_count.wrapping_sub(earlier.cumulative_count);
+        u64::try_from(sum / u128::from(slots)).ok()
+    }
+}
+
+impl Counter {
+    // Whether the counter keeps its TWAC accumulator. "twac_slot" is never
+    // 0 while it does.
+    pub fn is_twac(&self) -> bool {
+        self.twac_slot != 0
+    }
+
+    // Adds the count, held from "twac_slot" up to "slot", to the sum.
+    pub fn accumulate(&mut self, slot: u64) {
+        if !self.is_twac() || slot <= self.twac_slot {
+            return;
+        }
+        self.cumulative_count = self.observe(slot).cumulative_count;
+        self.twac_slot = slot;
+    }
+
+    // The accumulator as of "slot", without changing anything.
+    pub fn observe(&self, slot: u64) -> TwacObservation {
+        let held = u128::from(slot.saturating_sub(self.twac_slot));
+        TwacObservation {
+            cumulative_count: self
+                .cumulative_count
+                .wrapping_add(u128::from(self.count) * held),
+            slot,
+        }
+    }
+}
+
+// Turns the accumulator on, starting from 0 at the current slot, or off.
+// Turning it on again starts over: the time it was off isn't counted.
+pub fn process_set_twac(ctx: Context<UpdateCounter>, enabled: bool) -> Result<()> {
+    ctx.accounts.log(AdminAction::SetTwac)?;
+    let counter = &mut ctx.accounts.counter;
+    if enabled == counter.is_twac() {
+        return Ok(());
+    }
+    counter.cumulative_count = 0;
+    counter.twac_slot = if enabled { clock::slot()? } else { 0 };
+    Ok(())
+}
+
+pub fn process_get_twac(ctx: Context<ReadCounter>) -> Result<TwacObservation> {
+    let counter = &ctx.accounts.counter;
+    require!(counter.is_twac(), CounterError::NotTwac);
+    Ok(counter.observe(clock::slot()?))
+}
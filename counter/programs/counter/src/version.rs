@@ -0,0 +1,65 @@
+// ============================================================================
+// VERSION - which version of the instruction set is deployed?
+// ============================================================================
+
+// The version follows "semantic versioning" (MAJOR.MINOR.PATCH):
+//   - MAJOR changes when existing instructions or accounts change in a way
+//     that breaks old clients
+//   - MINOR changes when something is added (old clients keep working)
+//   - PATCH changes for fixes that don't change the interface
+//
+// It comes from "version" in Cargo.toml, so there's one place to bump it.
+use anchor_lang::prelude::*;
+
+// "#[constant]" also publishes this value in the IDL, so a client built
+// from the IDL knows which version it was generated for and can compare it
+// with what get_version returns (see app/version.ts).
+#[constant]
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// The same version as three numbers, which is cheaper to store and send
+// than text. "AnchorSerialize"/"AnchorDeserialize" let it be returned from
+// an instruction and included in events.
+/// A program version, MAJOR.MINOR.PATCH.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl Version {
+    // Cargo also provides each part of the version separately (as text),
+    // so we convert those to numbers while compiling.
+    pub const CURRENT: Version = Version {
+        major: parse_u8(env!("CARGO_PKG_VERSION_MAJOR")),
+        minor: parse_u8(env!("CARGO_PKG_VERSION_MINOR")),
+        patch: parse_u8(env!("CARGO_PKG_VERSION_PATCH")),
+    };
+}
+
+// A "const fn" can run at compile time. Converts text like "12" to 12.
+const fn parse_u8(text: &str) -> u8 {
+    let bytes = text.as_bytes();
+    let mut value: u8 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0');
+        i += 1;
+    }
+    value
+}
+
+// Returns the deployed version to the caller.
+//
+// Instructions can't return values the way normal functions do; instead
+// Anchor stores the Ok(...) value as the transaction's "return data".
+// Clients read it by simulating the transaction (no fee, nothing changes),
+// and other programs read it right after calling us.
+pub fn process_get_version(_ctx: Context<GetVersion>) -> Result<Version> {
+    Ok(Version::CURRENT)
+}
+
+// get_version doesn't need any accounts.
+#[derive(Accounts)]
+pub struct GetVersion {}
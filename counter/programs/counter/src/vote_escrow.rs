@@ -0,0 +1,340 @@
+// ============================================================================
+// VOTE ESCROW - increments weighted by locked tokens
+// ============================================================================
+
+// Turns a counter into a tally of token-weighted votes, in the style of
+// "vote-escrowed" (ve) tokens.
+//
+// The authority picks a mint with set_vote_weighted. Holders lock some of
+// it with lock_tokens for up to MAX_LOCK seconds; the tokens sit in an
+// escrow account owned by their Lockup PDA until unlock, after the lock
+// ends. While locked, one increment_weighted adds the lockup's weight to
+// the count:
+//
+//   weight = amount * time left / MAX_LOCK
+//
+// so longer commitments count for more, and a lock counts for less the
+// closer it gets to its end. Each lock votes once; to vote again, unlock
+// and lock again.
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use nautilus_common::{clock, math};
+
+use crate::access_log::{self, AccessLog, AdminAction, ACCESS_LOG_SEED};
+use crate::invariants::debug_assert_invariants;
+use crate::logging::{log_count, LogEvent};
+use crate::{AlarmTriggered, Counter, CounterError, CounterIncremented, Route, Version};
+
+pub const VOTE_MINT_SEED: &[u8] = b"vote_mint";
+pub const LOCKUP_SEED: &[u8] = b"lockup";
+
+// The longest lock, in seconds (a year), which gets the full weight.
+pub const MAX_LOCK: i64 = 365 * 24 * 60 * 60;
+
+/// Which mint a vote-weighted counter counts.
+#[account]
+#[derive(InitSpace)]
+pub struct VoteMint {
+    pub counter: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+/// One holder's locked tokens for one counter and mint.
+#[account]
+#[derive(InitSpace)]
+pub struct Lockup {
+    pub counter: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Tokens in escrow, in the mint's base units. 0 between an unlock
+    /// and the next lock.
+    pub amount: u64,
+    /// The tokens can be unlocked from then on, in unix seconds.
+    pub unlock_at: i64,
+    /// Whether this lock was already used by increment_weighted.
+    pub voted: bool,
+    pub bump: u8,
+}
+
+impl Lockup {
+    // The lock's vote weight at "now": 0 once it has ended.
+    pub fn weight(&self, now: i64) -> u64 {
+        let left = self.unlock_at.saturating_sub(now).clamp(0, MAX_LOCK);
+        math::portion(self.amount, left as u64, MAX_LOCK as u64)
+    }
+}
+
+// Turns vote weighting on with "mint", or off.
+pub fn process_set_vote_weighted(ctx: Context<SetVoteWeighted>, enabled: bool) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    if enabled {
+        counter.check_route_free(Counter::VOTE_WEIGHTED)?;
+    }
+    counter.set_flag(Counter::VOTE_WEIGHTED, enabled);
+    debug_assert_invariants!(counter);
+
+    let vote_mint = &mut ctx.accounts.vote_mint;
+    vote_mint.counter = counter.key();
+    vote_mint.mint = ctx.accounts.mint.key();
+    vote_mint.bump = ctx.bumps.vote_mint;
+
+    access_log::record(
+        &ctx.accounts.access_log,
+        counter.key(),
+        ctx.accounts.authority.key(),
+        AdminAction::SetVoteWeighted,
+    )
+}
+
+// Moves "amount" of the owner's tokens into escrow for "duration" seconds.
+pub fn process_lock_tokens(ctx: Context<LockTokens>, amount: u64, duration: i64) -> Result<()> {
+    require!(
+        amount > 0 && duration > 0 && duration <= MAX_LOCK,
+        CounterError::InvalidLockup
+    );
+    let lockup = &mut ctx.accounts.lockup;
+    require!(lockup.amount == 0, CounterError::LockupActive);
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.owner_tokens.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    lockup.counter = ctx.accounts.counter.key();
+    lockup.owner = ctx.accounts.owner.key();
+    lockup.mint = ctx.accounts.mint.key();
+    lockup.amount = amount;
+    lockup.unlock_at = clock::now()?
+        .checked_add(duration)
+        .ok_or(CounterError::InvalidLockup)?;
+    lockup.voted = false;
+    lockup.bump = ctx.bumps.lockup;
+    Ok(())
+}
+
+// Adds the lockup's weight to a vote-weighted counter.
+pub fn process_increment_weighted(ctx: Context<IncrementWeighted>) -> Result<()> {
+    let counter = &mut ctx.accounts.counter;
+    counter.check_route(Route::VoteWeighted)?;
+
+    let lockup = &mut ctx.accounts.lockup;
+    let weight = lockup.weight(clock::now()?);
+    require!(weight > 0 && !lockup.voted, CounterError::NoVoteWeight);
+    lockup.voted = true;
+
+    let before = counter.count;
+    counter.add(weight)?;
+    debug_assert_invariants!(counter);
+
+    log_count(LogEvent::Incremented, counter.count);
+    emit!(CounterIncremented {
+        counter: counter.key(),
+        count: counter.count,
+        event_seq: counter.next_event_seq(),
+        version: Version::CURRENT,
+    });
+    if counter.check_alarm(before) {
+        emit!(AlarmTriggered {
+            counter: counter.key(),
+            count: counter.count,
+            threshold: counter.alarm_threshold,
+            event_seq: counter.next_event_seq(),
+            version: Version::CURRENT,
+        });
+    }
+    Ok(())
+}
+
+// Gives the owner their tokens back once the lock has ended.
+pub fn process_unlock(ctx: Context<Unlock>) -> Result<()> {
+    let lockup = &ctx.accounts.lockup;
+    require!(
+        clock::now()? >= lockup.unlock_at,
+        CounterError::LockupActive
+    );
+
+    // The escrow belongs to the lockup PDA, so the PDA signs.
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.owner_tokens.to_account_info(),
+                authority: lockup.to_account_info(),
+            },
+            &[&[
+                LOCKUP_SEED,
+                lockup.counter.as_ref(),
+                lockup.mint.as_ref(),
+                lockup.owner.as_ref(),
+                &[lockup.bump],
+            ]],
+        ),
+        lockup.amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    let lockup = &mut ctx.accounts.lockup;
+    lockup.amount = 0;
+    lockup.voted = false;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetVoteWeighted<'info> {
+    #[account(mut, has_one = authority @ CounterError::Unauthorized)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VoteMint::INIT_SPACE,
+        seeds = [VOTE_MINT_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub vote_mint: Account<'info, VoteMint>,
+
+    // See UpdateCounter in lib.rs.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AccessLog::SPACE,
+        seeds = [ACCESS_LOG_SEED, counter.key().as_ref()],
+        bump
+    )]
+    pub access_log: AccountLoader<'info, AccessLog>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LockTokens<'info> {
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        seeds = [VOTE_MINT_SEED, counter.key().as_ref()],
+        bump = vote_mint.bump,
+        has_one = mint
+    )]
+    pub vote_mint: Account<'info, VoteMint>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // The mint is part of the seeds, so lockups of a mint the counter no
+    // longer counts can't vote (see IncrementWeighted).
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + Lockup::INIT_SPACE,
+        seeds = [
+            LOCKUP_SEED,
+            counter.key().as_ref(),
+            mint.key().as_ref(),
+            owner.key().as_ref()
+        ],
+        bump
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = lockup,
+        associated_token::token_program = token_program
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementWeighted<'info> {
+    #[account(mut)]
+    pub counter: Account<'info, Counter>,
+
+    #[account(seeds = [VOTE_MINT_SEED, counter.key().as_ref()], bump = vote_mint.bump)]
+    pub vote_mint: Account<'info, VoteMint>,
+
+    #[account(
+        mut,
+        seeds = [
+            LOCKUP_SEED,
+            counter.key().as_ref(),
+            vote_mint.mint.as_ref(),
+            owner.key().as_ref()
+        ],
+        bump = lockup.bump
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Unlock<'info> {
+    #[account(
+        mut,
+        seeds = [
+            LOCKUP_SEED,
+            lockup.counter.as_ref(),
+            mint.key().as_ref(),
+            owner.key().as_ref()
+        ],
+        bump = lockup.bump
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = lockup,
+        associated_token::token_program = token_program
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub owner_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
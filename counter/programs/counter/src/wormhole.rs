@@ -0,0 +1,396 @@
+// ============================================================================
+// WORMHOLE - sharing counts with other blockchains
+// ============================================================================
+
+// Wormhole is a messaging bridge. A program on Solana "posts" a message to
+// the Wormhole core bridge program; a set of independent validators
+// ("guardians") watch for it and sign it. The signed message, a "VAA"
+// (Verified Action Approval), can then be checked by a Wormhole contract on
+// any other chain - so an EVM dapp can trust a count it never read from
+// Solana itself.
+//
+// It works the other way too: a VAA from another chain is verified on
+// Solana by the core bridge, which stores it in a "PostedVAA" account. We
+// only have to read that account and check who sent the message.
+//
+//   - post_count: publishes a counter's current value as a Wormhole message
+//   - initialize_remote_counter / receive_remote_count: keep a
+//     RemoteCounter in sync with a counter on another chain
+//
+// We talk to the core bridge directly (by building its instructions and
+// reading its accounts by hand) instead of through an SDK crate; the
+// layouts below are the core bridge's and must not change.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+use anchor_lang::system_program;
+
+use crate::{Counter, CounterError, RemoteCountReceived, Version};
+
+// The core bridge's address on each cluster, picked the same way as our
+// own declare_id! in lib.rs. Localnet uses Wormhole's local test setup.
+#[cfg(feature = "devnet")]
+pub const WORMHOLE_PROGRAM_ID: Pubkey = pubkey!("3u8hJUVTA4jH1wYAyUur7FFZVQ8H635K3tSHHF4ssjQ5");
+#[cfg(feature = "mainnet")]
+pub const WORMHOLE_PROGRAM_ID: Pubkey = pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+#[cfg(not(any(feature = "devnet", feature = "mainnet")))]
+pub const WORMHOLE_PROGRAM_ID: Pubkey = pubkey!("Bridge1p5gheXUvJ6jGWGeCsgPKgnE3YgdGKRVCMY9o");
+
+// Seeds of the core bridge's own accounts (derived from its program id).
+pub const WORMHOLE_BRIDGE_SEED: &[u8] = b"Bridge";
+pub const WORMHOLE_FEE_COLLECTOR_SEED: &[u8] = b"fee_collector";
+pub const WORMHOLE_SEQUENCE_SEED: &[u8] = b"Sequence";
+pub const WORMHOLE_POSTED_VAA_SEED: &[u8] = b"PostedVAA";
+
+// Our "emitter": the PDA that signs our messages. Other chains identify
+// messages from this program by (SOLANA_CHAIN_ID, emitter address).
+pub const EMITTER_SEED: &[u8] = b"emitter";
+pub const REMOTE_COUNTER_SEED: &[u8] = b"remote_counter";
+
+// Wormhole's number for Solana.
+pub const SOLANA_CHAIN_ID: u16 = 1;
+
+// The core bridge's "post_message" instruction number, and the
+// "finalized" consistency level: guardians wait for the slot to be
+// finalized before signing, so the message can't be rolled back.
+const POST_MESSAGE_INSTRUCTION: u8 = 1;
+const CONSISTENCY_FINALIZED: u8 = 1;
+
+// ----------------------------------------------------------------------------
+// Message format
+// ----------------------------------------------------------------------------
+
+// What we post, and what we accept from other chains. Big endian, fixed
+// size, so Solidity can decode it with plain slicing:
+//
+//   0       payload id (always 1)
+//   1..33   counter id (on Solana, the counter's address)
+//   33..41  count
+//   41..49  slot or block number when the count was read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CountPayload {
+    pub counter: [u8; 32],
+    pub count: u64,
+    pub slot: u64,
+}
+
+impl CountPayload {
+    pub const ID: u8 = 1;
+    pub const LEN: usize = 49;
+
+    pub fn encode(&self) -> [u8; CountPayload::LEN] {
+        let mut bytes = [0; CountPayload::LEN];
+        bytes[0] = CountPayload::ID;
+        bytes[1..33].copy_from_slice(&self.counter);
+        bytes[33..41].copy_from_slice(&self.count.to_be_bytes());
+        bytes[41..49].copy_from_slice(&self.slot.to_be_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<CountPayload> {
+        require!(
+            bytes.len() == CountPayload::LEN && bytes[0] == CountPayload::ID,
+            CounterError::InvalidWormholeMessage
+        );
+        Ok(CountPayload {
+            counter: bytes[1..33].try_into().unwrap(),
+            count: u64::from_be_bytes(bytes[33..41].try_into().unwrap()),
+            slot: u64::from_be_bytes(bytes[41..49].try_into().unwrap()),
+        })
+    }
+}
+
+// The parts of a core bridge PostedVAA account we use. Its data is the
+// bytes "vaa" followed by (Borsh, little endian):
+//
+//   3       version (u8)
+//   4       consistency level (u8)
+//   5..9    timestamp (u32)
+//   9..41   signature set (Pubkey)
+//   41..45  posted timestamp (u32)
+//   45..49  nonce (u32)
+//   49..57  sequence (u64)
+//   57..59  emitter chain (u16)
+//   59..91  emitter address ([u8; 32])
+//   91..95  payload length (u32), then the payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PostedVaa {
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+impl PostedVaa {
+    pub fn parse(data: &[u8]) -> Result<PostedVaa> {
+        require!(
+            data.len() >= 95 && &data[..3] == b"vaa",
+            CounterError::InvalidWormholeMessage
+        );
+        let payload_len = u32::from_le_bytes(data[91..95].try_into().unwrap()) as usize;
+        let payload = data
+            .get(95..95 + payload_len)
+            .ok_or(CounterError::InvalidWormholeMessage)?;
+        Ok(PostedVaa {
+            sequence: u64::from_le_bytes(data[49..57].try_into().unwrap()),
+            emitter_chain: u16::from_le_bytes(data[57..59].try_into().unwrap()),
+            emitter_address: data[59..91].try_into().unwrap(),
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Posting our counts
+// ----------------------------------------------------------------------------
+
+// Posts the counter's current value. "nonce" is passed through to the VAA
+// untouched; clients can use it to group messages.
+pub fn process_post_count(ctx: Context<PostCount>, nonce: u32) -> Result<()> {
+    let accounts = &ctx.accounts;
+
+    // The core bridge may charge a fee per message, paid into its fee
+    // collector before posting. The fee is a u64 at bytes 16..24 of its
+    // config ("Bridge") account.
+    let bridge_data = accounts.wormhole_bridge.try_borrow_data()?;
+    let fee = bridge_data
+        .get(16..24)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(CounterError::InvalidWormholeMessage)?;
+    drop(bridge_data);
+    if fee > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.payer.to_account_info(),
+                    to: accounts.wormhole_fee_collector.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    let payload = CountPayload {
+        counter: accounts.counter.key().to_bytes(),
+        count: accounts.counter.count,
+        slot: accounts.clock.slot,
+    }
+    .encode();
+
+    // The instruction data is the core bridge's PostMessage: instruction
+    // number, nonce, payload (length first), consistency level.
+    let mut data = Vec::with_capacity(10 + CountPayload::LEN);
+    data.push(POST_MESSAGE_INSTRUCTION);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&(CountPayload::LEN as u32).to_le_bytes());
+    data.extend_from_slice(&payload);
+    data.push(CONSISTENCY_FINALIZED);
+
+    let instruction = Instruction {
+        program_id: WORMHOLE_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(accounts.wormhole_bridge.key(), false),
+            AccountMeta::new(accounts.wormhole_message.key(), true),
+            AccountMeta::new_readonly(accounts.wormhole_emitter.key(), true),
+            AccountMeta::new(accounts.wormhole_sequence.key(), false),
+            AccountMeta::new(accounts.payer.key(), true),
+            AccountMeta::new(accounts.wormhole_fee_collector.key(), false),
+            AccountMeta::new_readonly(accounts.clock.key(), false),
+            AccountMeta::new_readonly(accounts.rent.key(), false),
+            AccountMeta::new_readonly(accounts.system_program.key(), false),
+        ],
+        data,
+    };
+
+    // "invoke_signed" calls another program, signing for our emitter PDA
+    // with its seeds (nobody has a private key for it).
+    invoke_signed(
+        &instruction,
+        &[
+            accounts.wormhole_bridge.to_account_info(),
+            accounts.wormhole_message.to_account_info(),
+            accounts.wormhole_emitter.to_account_info(),
+            accounts.wormhole_sequence.to_account_info(),
+            accounts.payer.to_account_info(),
+            accounts.wormhole_fee_collector.to_account_info(),
+            accounts.clock.to_account_info(),
+            accounts.rent.to_account_info(),
+            accounts.system_program.to_account_info(),
+        ],
+        &[&[EMITTER_SEED, &[ctx.bumps.wormhole_emitter]]],
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PostCount<'info> {
+    // Anyone can publish a counter's value; it's public anyway.
+    pub counter: Account<'info, Counter>,
+
+    // The core bridge checks these itself; "seeds::program" derives their
+    // addresses from the core bridge's id instead of ours, so a wrong
+    // account fails here with a clearer error.
+    #[account(mut, seeds = [WORMHOLE_BRIDGE_SEED], bump, seeds::program = WORMHOLE_PROGRAM_ID)]
+    /// CHECK: the core bridge's config account, checked by the core bridge.
+    pub wormhole_bridge: UncheckedAccount<'info>,
+
+    // A new account the core bridge creates to hold the message. The
+    // client generates a fresh keypair for it and signs with it.
+    #[account(mut)]
+    pub wormhole_message: Signer<'info>,
+
+    #[account(seeds = [EMITTER_SEED], bump)]
+    /// CHECK: our emitter PDA. It holds no data; it only signs.
+    pub wormhole_emitter: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [WORMHOLE_SEQUENCE_SEED, wormhole_emitter.key().as_ref()],
+        bump,
+        seeds::program = WORMHOLE_PROGRAM_ID
+    )]
+    /// CHECK: counts our emitter's messages, managed by the core bridge.
+    pub wormhole_sequence: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [WORMHOLE_FEE_COLLECTOR_SEED],
+        bump,
+        seeds::program = WORMHOLE_PROGRAM_ID
+    )]
+    /// CHECK: receives the message fee, managed by the core bridge.
+    pub wormhole_fee_collector: UncheckedAccount<'info>,
+
+    // Pays the message fee and the rent for the message account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = WORMHOLE_PROGRAM_ID)]
+    /// CHECK: the core bridge program, checked by address.
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+// ----------------------------------------------------------------------------
+// Receiving counts from other chains
+// ----------------------------------------------------------------------------
+
+/// The latest count of a counter on another chain, as reported by one
+/// Wormhole emitter (the contract that owns that counter).
+#[account]
+#[derive(InitSpace)]
+pub struct RemoteCounter {
+    /// The Wormhole chain id of the other chain.
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    /// The counter's id on the other chain (see CountPayload).
+    pub remote_counter: [u8; 32],
+    /// The count there, as of the latest message.
+    pub count: u64,
+    /// The Wormhole sequence number (not a slot) the next message needs.
+    /// Messages with a lower sequence number are old and ignored, so they
+    /// can't be replayed to roll the count back.
+    pub next_sequence: u64,
+    pub bump: u8,
+}
+
+// Creates the RemoteCounter for one emitter's counter. Anyone can do this:
+// the emitter is part of the address, and only VAAs from that emitter can
+// update it.
+pub fn process_initialize_remote_counter(
+    ctx: Context<InitializeRemoteCounter>,
+    emitter_chain: u16,
+    emitter_address: [u8; 32],
+    remote_counter_id: [u8; 32],
+) -> Result<()> {
+    let remote = &mut ctx.accounts.remote_counter;
+    remote.emitter_chain = emitter_chain;
+    remote.emitter_address = emitter_address;
+    remote.remote_counter = remote_counter_id;
+    remote.bump = ctx.bumps.remote_counter;
+    Ok(())
+}
+
+// Updates a RemoteCounter from a VAA the core bridge has already verified.
+// "vaa_hash" is the VAA's hash, which is how the core bridge addresses it.
+pub fn process_receive_remote_count(
+    ctx: Context<ReceiveRemoteCount>,
+    _vaa_hash: [u8; 32],
+) -> Result<()> {
+    let vaa = PostedVaa::parse(&ctx.accounts.posted_vaa.try_borrow_data()?)?;
+    let remote = &mut ctx.accounts.remote_counter;
+
+    require!(
+        vaa.emitter_chain == remote.emitter_chain && vaa.emitter_address == remote.emitter_address,
+        CounterError::UnknownEmitter
+    );
+    require!(
+        vaa.sequence >= remote.next_sequence,
+        CounterError::StaleWormholeMessage
+    );
+    let payload = CountPayload::decode(&vaa.payload)?;
+    require!(
+        payload.counter == remote.remote_counter,
+        CounterError::InvalidWormholeMessage
+    );
+
+    remote.count = payload.count;
+    remote.next_sequence = vaa.sequence + 1;
+
+    emit!(RemoteCountReceived {
+        remote_counter: remote.key(),
+        emitter_chain: remote.emitter_chain,
+        count: remote.count,
+        sequence: vaa.sequence,
+        version: Version::CURRENT,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(emitter_chain: u16, emitter_address: [u8; 32], remote_counter_id: [u8; 32])]
+pub struct InitializeRemoteCounter<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RemoteCounter::INIT_SPACE,
+        seeds = [
+            REMOTE_COUNTER_SEED,
+            emitter_chain.to_le_bytes().as_ref(),
+            emitter_address.as_ref(),
+            remote_counter_id.as_ref(),
+        ],
+        bump
+    )]
+    pub remote_counter: Account<'info, RemoteCounter>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct ReceiveRemoteCount<'info> {
+    #[account(mut)]
+    pub remote_counter: Account<'info, RemoteCounter>,
+
+    // Only the core bridge can create accounts at its PostedVAA addresses,
+    // and it only does so after checking the guardians' signatures. The
+    // owner check makes sure it's a real one.
+    #[account(
+        seeds = [WORMHOLE_POSTED_VAA_SEED, vaa_hash.as_ref()],
+        bump,
+        seeds::program = WORMHOLE_PROGRAM_ID,
+        owner = WORMHOLE_PROGRAM_ID
+    )]
+    /// CHECK: parsed by PostedVaa::parse.
+    pub posted_vaa: UncheckedAccount<'info>,
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::Pubkey;
+use bytemuck::Zeroable;
+use counter::{AccessLog, AdminAction, ACCESS_LOG_ENTRIES};
+
+#[test]
+fn access_log_keeps_the_newest_entries_in_order() {
+    let mut log = AccessLog::zeroed();
+    let actor = Pubkey::new_unique();
+    assert_eq!(log.entries().count(), 0);
+
+    let written = ACCESS_LOG_ENTRIES as i64 + 10;
+    for timestamp in 0..written {
+        log.append(actor, AdminAction::SetAlarmThreshold, timestamp);
+    }
+
+    // The 10 oldest were overwritten; the rest read back oldest first.
+    let timestamps: Vec<i64> = log.entries().map(|entry| entry.timestamp).collect();
+    assert_eq!(timestamps, (10..written).collect::<Vec<_>>());
+    assert_eq!(log.total, written as u64);
+    assert!(log
+        .entries()
+        .all(|entry| entry.actor == actor && entry.action == AdminAction::SetAlarmThreshold as u8));
+}
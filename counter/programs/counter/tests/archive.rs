@@ -0,0 +1,15 @@
+use anchor_lang::error::Error;
+use counter::{Counter, CounterError};
+
+#[test]
+fn archived_counters_are_frozen() {
+    let mut counter = Counter::default();
+    counter.set_flag(Counter::ARCHIVED, true);
+    let archived = Some(Error::from(CounterError::Archived));
+    assert_eq!(counter.check_modes().err(), archived);
+    assert_eq!(counter.check_limits().err(), archived);
+    assert_eq!(counter.check_active().err(), archived);
+
+    counter.set_flag(Counter::ARCHIVED, false);
+    assert!(counter.check_modes().is_ok());
+}
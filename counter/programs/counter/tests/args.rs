@@ -0,0 +1,92 @@
+// Versioned instruction arguments (see args.rs), run through the program
+// with set_increment_fee: only the versions the program knows get through.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, CounterError, CounterFees, IncrementFeeArgs, ACCESS_LOG_SEED, FEES_SEED,
+};
+use harness::fixtures;
+use harness::{ix, Svm};
+
+fn error(error: CounterError) -> ProgramError {
+    Error::from(error).into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn set_increment_fee(counter: Pubkey, authority: Pubkey, version: u8) -> Instruction {
+    ix(
+        accounts::SetIncrementFee {
+            guard: accounts::AuthorityGuard {
+                counter,
+                access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+                authority,
+                system_program: system_program::ID,
+            },
+            fees: pda(&[FEES_SEED, counter.as_ref()]),
+            system_program: system_program::ID,
+        },
+        instruction::SetIncrementFee {
+            args: IncrementFeeArgs {
+                version,
+                fee_lamports: 5_000,
+                referral_bps: 100,
+            },
+        },
+    )
+}
+
+fn owned_counter(svm: &mut Svm) -> (Pubkey, Pubkey) {
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_anchor(counter, &fixtures::counter(authority));
+    (authority, counter)
+}
+
+#[test]
+fn only_known_versions_are_accepted() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+
+    // 0 is what zeroed data would read as, never a real version.
+    for version in [0, IncrementFeeArgs::VERSION + 1, u8::MAX] {
+        assert_eq!(
+            svm.process(&set_increment_fee(counter, authority, version)),
+            Err(error(CounterError::UnsupportedArgsVersion))
+        );
+    }
+    assert_eq!(
+        svm.process(&set_increment_fee(
+            counter,
+            authority,
+            IncrementFeeArgs::VERSION
+        )),
+        Ok(())
+    );
+    let fees = svm
+        .load::<CounterFees>(&pda(&[FEES_SEED, counter.as_ref()]))
+        .unwrap();
+    assert_eq!(fees.fee_lamports, 5_000);
+}
+
+#[test]
+fn fields_from_a_newer_client_are_refused_not_dropped() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+
+    // What a client built against a later version might send: a field
+    // this program doesn't know, after the ones it does. Anchor reads
+    // what it expects and ignores the rest, so only the version check
+    // stops the new field from being silently lost.
+    let mut newer = set_increment_fee(counter, authority, IncrementFeeArgs::VERSION + 1);
+    newer.data.extend_from_slice(&1_000u64.to_le_bytes());
+    assert_eq!(
+        svm.process(&newer),
+        Err(error(CounterError::UnsupportedArgsVersion))
+    );
+}
@@ -0,0 +1,17 @@
+use counter::{Contribution, TIERS};
+
+#[test]
+fn tiers_are_stamped_when_reached() {
+    let mut contribution = Contribution {
+        counter: Default::default(),
+        contributor: Default::default(),
+        count: 0,
+        reached_at: [0; TIERS.len()],
+        bump: 0,
+    };
+    for now in 1..=100 {
+        contribution.add(now).unwrap();
+    }
+    assert_eq!(contribution.count, 100);
+    assert_eq!(contribution.reached_at, [10, 100, 0]);
+}
@@ -0,0 +1,68 @@
+// Counter checksums (see checksum.rs), run through the program: every
+// write keeps them matching, and verify_checksum catches a change that
+// didn't go through an instruction.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{hash::hash, instruction::Instruction};
+use anchor_lang::Discriminator;
+use counter::{accounts, checksum_matches, instruction, Counter, CounterError};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn verify_checksum(counter: Pubkey) -> Instruction {
+    ix(
+        accounts::ReadCounter { counter },
+        instruction::VerifyChecksum {},
+    )
+}
+
+#[test]
+fn the_discriminator_is_the_one_account_would_pick() {
+    assert_eq!(
+        Counter::DISCRIMINATOR,
+        hash(b"account:Counter").to_bytes()[..8]
+    );
+}
+
+#[test]
+fn catches_changes_made_outside_the_program() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter_at(authority, 7), CURRENT);
+    assert_eq!(svm.process(&verify_checksum(counter)), Ok(()));
+
+    // Written by an instruction: still matches.
+    let increment = ix(accounts::Increment { counter }, instruction::Increment {});
+    assert_eq!(svm.process(&increment), Ok(()));
+    assert!(checksum_matches(&svm.get(&counter).unwrap().data));
+    assert_eq!(svm.process(&verify_checksum(counter)), Ok(()));
+
+    // A tag written behind the program's back.
+    svm.get_mut(&counter).unwrap().data[84] = b'x';
+    assert_eq!(
+        svm.process(&verify_checksum(counter)),
+        Err(Error::from(CounterError::ChecksumMismatch).into())
+    );
+    // It still loads; the next write stores a matching checksum again.
+    assert_eq!(svm.process(&increment), Ok(()));
+    assert_eq!(svm.process(&verify_checksum(counter)), Ok(()));
+    let loaded = svm.load::<Counter>(&counter).unwrap();
+    assert_eq!((loaded.count, loaded.tags[0][0]), (9, b'x'));
+}
+
+#[test]
+fn only_counters_in_the_current_layout_match() {
+    let counter = fixtures::counter(fixtures::address("authority"));
+    let data = fixtures::counter_data(&counter, CURRENT);
+    assert!(checksum_matches(&data));
+    assert!(!checksum_matches(&data[..data.len() - 1]));
+    let older = fixtures::counter_data(&counter, CURRENT - 1);
+    assert!(!checksum_matches(&older));
+
+    let mut other = data.clone();
+    other[0] ^= 1;
+    assert!(!checksum_matches(&other));
+}
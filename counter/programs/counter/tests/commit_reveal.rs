@@ -0,0 +1,67 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::{AnchorDeserialize, Pubkey};
+use counter::{commitment_hash, CommitRound, CommitRoundArgs, CounterError};
+
+fn round(commit_deadline: i64, reveal_deadline: i64) -> CommitRound {
+    CommitRound {
+        counter: Pubkey::default(),
+        round: 1,
+        commit_deadline,
+        reveal_deadline,
+        deposit: 0,
+        max_value: 10,
+        commits: 0,
+        reveals: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn phases_follow_the_deadlines() {
+    let round = round(100, 200);
+    let wrong_phase = Some(Error::from(CounterError::WrongPhase));
+
+    assert!(round.check_commit_phase(99).is_ok());
+    assert_eq!(round.check_commit_phase(100).err(), wrong_phase);
+
+    assert_eq!(round.check_reveal_phase(99).err(), wrong_phase);
+    assert!(round.check_reveal_phase(100).is_ok());
+    assert!(round.check_reveal_phase(199).is_ok());
+    assert_eq!(round.check_reveal_phase(200).err(), wrong_phase);
+
+    assert!(!round.is_over(199));
+    assert!(round.is_over(200));
+}
+
+#[test]
+fn hashes_bind_the_participant_value_and_salt() {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let salt = [7; 32];
+    let hash = commitment_hash(&alice, 5, &salt);
+
+    assert_eq!(hash, commitment_hash(&alice, 5, &salt));
+    assert_ne!(hash, commitment_hash(&alice, 6, &salt));
+    assert_ne!(hash, commitment_hash(&alice, 5, &[8; 32]));
+    assert_ne!(hash, commitment_hash(&bob, 5, &salt));
+}
+
+#[test]
+fn reveals_stop_at_the_round_maximum() {
+    let round = round(100, 200);
+    assert!(round.check_value(10).is_ok());
+    assert_eq!(
+        round.check_value(11).err(),
+        Some(Error::from(CounterError::RevealTooLarge))
+    );
+}
+
+#[test]
+fn version_1_rounds_take_reveals_of_one() {
+    let mut data = vec![1];
+    data.extend(100i64.to_le_bytes());
+    data.extend(200i64.to_le_bytes());
+    data.extend(0u64.to_le_bytes());
+    let args = CommitRoundArgs::try_from_slice(&data).unwrap();
+    assert_eq!(args.max_value, 1);
+}
@@ -0,0 +1,330 @@
+// Compressed counters (see compressed.rs). There's no Light build here, so
+// the Light system program is stubbed out: these check the invoke_cpi the
+// program sends it, byte for byte where the layout is Light's. Against
+// Light's real programs, see tests/litesvm/compressed.ts.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, derive_address, instruction, CompressedAccount, CompressedAccountData,
+    CompressedCounter, CompressedCounterMeta, CompressedProof, CounterError, LightInvokeCpi,
+    NewAddressParamsPacked, OutputCompressedAccountWithPackedContext, PackedAddressTreeInfo,
+    PackedMerkleContext, ACCOUNT_COMPRESSION_PROGRAM_ID, LIGHT_CPI_AUTHORITY_SEED,
+    LIGHT_SYSTEM_PROGRAM_ID, NOOP_PROGRAM_ID,
+};
+use harness::fixtures;
+use harness::{ix, Svm};
+
+const PROOF: CompressedProof = CompressedProof {
+    a: [1; 32],
+    b: [2; 64],
+    c: [3; 32],
+};
+
+#[test]
+fn invoke_cpi_data_is_lights_layout() {
+    let inputs = LightInvokeCpi {
+        proof: Some(PROOF),
+        new_address_params: vec![NewAddressParamsPacked {
+            seed: [4; 32],
+            address_queue_account_index: 1,
+            address_merkle_tree_account_index: 0,
+            address_merkle_tree_root_index: 9,
+        }],
+        input_compressed_accounts_with_merkle_context: Vec::new(),
+        output_compressed_accounts: vec![OutputCompressedAccountWithPackedContext {
+            compressed_account: CompressedAccount {
+                owner: counter::ID,
+                lamports: 0,
+                address: Some([5; 32]),
+                data: Some(CompressedAccountData {
+                    discriminator: [6; 8],
+                    data: vec![7, 7],
+                    data_hash: [8; 32],
+                }),
+            },
+            merkle_tree_index: 2,
+        }],
+        relay_fee: None,
+        compress_or_decompress_lamports: None,
+        is_compress: false,
+        cpi_context: None,
+    };
+
+    let mut expected = vec![1]; // proof: Some
+    expected.extend_from_slice(&[1; 32]);
+    expected.extend_from_slice(&[2; 64]);
+    expected.extend_from_slice(&[3; 32]);
+    expected.extend_from_slice(&1u32.to_le_bytes()); // new_address_params
+    expected.extend_from_slice(&[4; 32]);
+    expected.extend_from_slice(&[1, 0]);
+    expected.extend_from_slice(&9u16.to_le_bytes());
+    expected.extend_from_slice(&0u32.to_le_bytes()); // input accounts
+    expected.extend_from_slice(&1u32.to_le_bytes()); // output accounts
+    expected.extend_from_slice(counter::ID.as_ref());
+    expected.extend_from_slice(&0u64.to_le_bytes());
+    expected.push(1); // address: Some
+    expected.extend_from_slice(&[5; 32]);
+    expected.push(1); // data: Some
+    expected.extend_from_slice(&[6; 8]);
+    expected.extend_from_slice(&2u32.to_le_bytes());
+    expected.extend_from_slice(&[7, 7]);
+    expected.extend_from_slice(&[8; 32]);
+    expected.push(2); // merkle_tree_index
+    expected.extend_from_slice(&[0, 0, 0, 0]); // relay_fee, lamports, is_compress, cpi_context
+
+    let data = inputs.data().unwrap();
+    // sha256("global:invoke_cpi"), then the inputs as a Vec<u8>.
+    assert_eq!(data[..8], [49, 212, 191, 129, 39, 194, 43, 196]);
+    assert_eq!(data[8..12], (expected.len() as u32).to_le_bytes());
+    assert_eq!(data[12..], expected);
+}
+
+#[test]
+fn addresses_are_per_authority_id_and_tree() {
+    let (alice, bob) = (fixtures::address("alice"), fixtures::address("bob"));
+    let (tree, other_tree) = (fixtures::address("tree"), fixtures::address("other tree"));
+    let seed = CompressedCounter::address_seed(&alice, 0);
+
+    assert_eq!(seed, CompressedCounter::address_seed(&alice, 0));
+    assert_ne!(seed, CompressedCounter::address_seed(&alice, 1));
+    assert_ne!(seed, CompressedCounter::address_seed(&bob, 0));
+    assert_ne!(
+        derive_address(&tree, &seed),
+        derive_address(&other_tree, &seed)
+    );
+    // Under the BN254 field's modulus, as Light's trees need.
+    assert_eq!(seed[0], 0);
+    assert_eq!(derive_address(&tree, &seed)[0], 0);
+}
+
+#[test]
+fn the_discriminator_is_anchors() {
+    let hash = anchor_lang::solana_program::hash::hash(b"account:CompressedCounter");
+    assert_eq!(CompressedCounter::DISCRIMINATOR, hash.to_bytes()[..8]);
+}
+
+fn pda(seeds: &[&[u8]], program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program).0
+}
+
+fn light(payer: Pubkey) -> accounts::LightSystem {
+    accounts::LightSystem {
+        payer,
+        cpi_authority: pda(&[LIGHT_CPI_AUTHORITY_SEED], &counter::ID),
+        registered_program_pda: pda(
+            &[LIGHT_SYSTEM_PROGRAM_ID.as_ref()],
+            &ACCOUNT_COMPRESSION_PROGRAM_ID,
+        ),
+        account_compression_authority: pda(&[LIGHT_CPI_AUTHORITY_SEED], &LIGHT_SYSTEM_PROGRAM_ID),
+        light_system_program: LIGHT_SYSTEM_PROGRAM_ID,
+        account_compression_program: ACCOUNT_COMPRESSION_PROGRAM_ID,
+        noop_program: NOOP_PROGRAM_ID,
+        program: counter::ID,
+        system_program: system_program::ID,
+    }
+}
+
+// The trees and queues, as remaining accounts: address tree, address
+// queue, state tree, nullifier queue.
+fn trees() -> [Pubkey; 4] {
+    [
+        fixtures::address("address tree"),
+        fixtures::address("address queue"),
+        fixtures::address("state tree"),
+        fixtures::address("nullifier queue"),
+    ]
+}
+
+fn with_trees(mut ix: Instruction) -> Instruction {
+    ix.accounts
+        .extend(trees().map(|tree| AccountMeta::new(tree, false)));
+    ix
+}
+
+fn svm() -> Svm {
+    let mut svm = Svm::new();
+    svm.stub_program(LIGHT_SYSTEM_PROGRAM_ID);
+    svm.set_program(ACCOUNT_COMPRESSION_PROGRAM_ID);
+    svm.set_program(NOOP_PROGRAM_ID);
+    svm
+}
+
+fn create(payer: Pubkey, address_tree_index: u8, id: u64) -> Instruction {
+    with_trees(ix(
+        accounts::CreateCompressedCounter {
+            light: light(payer),
+        },
+        instruction::CreateCompressedCounter {
+            proof: PROOF,
+            address_tree: PackedAddressTreeInfo {
+                address_merkle_tree_account_index: address_tree_index,
+                address_queue_account_index: 1,
+                root_index: 3,
+            },
+            output_tree_index: 2,
+            id,
+        },
+    ))
+}
+
+fn increment(payer: Pubkey, counter: CompressedCounter, address: [u8; 32]) -> Instruction {
+    with_trees(ix(
+        accounts::IncrementCompressedCounter {
+            light: light(payer),
+        },
+        instruction::IncrementCompressedCounter {
+            proof: PROOF,
+            counter,
+            meta: CompressedCounterMeta {
+                address,
+                merkle_context: PackedMerkleContext {
+                    merkle_tree_pubkey_index: 2,
+                    nullifier_queue_pubkey_index: 3,
+                    leaf_index: 17,
+                    queue_index: None,
+                },
+                root_index: 5,
+                output_tree_index: 2,
+            },
+        },
+    ))
+}
+
+// The one CPI the last instruction made, checked against what invoke_cpi
+// takes, and its inputs.
+fn invoke_cpi(svm: &Svm, payer: Pubkey) -> LightInvokeCpi {
+    let cpis = svm.cpis();
+    assert_eq!(cpis.len(), 1);
+    let cpi = &cpis[0];
+    assert_eq!(cpi.program_id, LIGHT_SYSTEM_PROGRAM_ID);
+
+    let light = light(payer);
+    let accounts: Vec<Pubkey> = cpi.accounts.iter().map(|meta| meta.pubkey).collect();
+    let none = LIGHT_SYSTEM_PROGRAM_ID;
+    let mut expected = vec![
+        payer,
+        light.cpi_authority,
+        light.registered_program_pda,
+        NOOP_PROGRAM_ID,
+        light.account_compression_authority,
+        ACCOUNT_COMPRESSION_PROGRAM_ID,
+        counter::ID,
+        none,
+        none,
+        system_program::ID,
+        none,
+    ];
+    expected.extend(trees());
+    assert_eq!(accounts, expected);
+    // Our CPI authority signed, through its seeds.
+    assert!(cpi.accounts[1].is_signer);
+
+    LightInvokeCpi::deserialize(&mut &cpi.data[12..]).unwrap()
+}
+
+fn counter_in(account: &CompressedAccount) -> CompressedCounter {
+    let data = account.data.as_ref().unwrap();
+    assert_eq!(data.discriminator, CompressedCounter::DISCRIMINATOR);
+    let counter = CompressedCounter::deserialize(&mut &data.data[..]).unwrap();
+    assert_eq!(data.data_hash, counter.data_hash().unwrap());
+    counter
+}
+
+#[test]
+fn creates_counters_at_their_derived_address() {
+    let mut svm = svm();
+    let payer = svm.wallet();
+    assert_eq!(svm.process(&create(payer, 0, 7)), Ok(()));
+
+    let inputs = invoke_cpi(&svm, payer);
+    assert_eq!(inputs.proof, Some(PROOF));
+    assert!(inputs
+        .input_compressed_accounts_with_merkle_context
+        .is_empty());
+    let seed = CompressedCounter::address_seed(&payer, 7);
+    assert_eq!(
+        inputs.new_address_params,
+        [NewAddressParamsPacked {
+            seed,
+            address_queue_account_index: 1,
+            address_merkle_tree_account_index: 0,
+            address_merkle_tree_root_index: 3,
+        }]
+    );
+
+    let [output] = &inputs.output_compressed_accounts[..] else {
+        panic!("one output");
+    };
+    assert_eq!(output.merkle_tree_index, 2);
+    let account = &output.compressed_account;
+    assert_eq!(account.owner, counter::ID);
+    assert_eq!(account.address, Some(derive_address(&trees()[0], &seed)));
+    assert_eq!(
+        counter_in(account),
+        CompressedCounter {
+            authority: payer,
+            count: 0,
+        }
+    );
+}
+
+#[test]
+fn creating_needs_the_address_tree() {
+    let mut svm = svm();
+    let payer = svm.wallet();
+    assert_eq!(
+        svm.process(&create(payer, 4, 0)),
+        Err(Error::from(ErrorCode::AccountNotEnoughKeys).into())
+    );
+}
+
+#[test]
+fn increments_spend_the_old_state_for_the_new() {
+    let mut svm = svm();
+    let payer = svm.wallet();
+    let counter = CompressedCounter {
+        authority: fixtures::address("authority"),
+        count: 5,
+    };
+    let address = [9; 32];
+    assert_eq!(svm.process(&increment(payer, counter, address)), Ok(()));
+
+    let inputs = invoke_cpi(&svm, payer);
+    assert!(inputs.new_address_params.is_empty());
+    let [input] = &inputs.input_compressed_accounts_with_merkle_context[..] else {
+        panic!("one input");
+    };
+    assert_eq!(input.root_index, 5);
+    assert_eq!(input.merkle_context.leaf_index, 17);
+    assert_eq!(input.compressed_account.address, Some(address));
+    assert_eq!(counter_in(&input.compressed_account), counter);
+
+    let [output] = &inputs.output_compressed_accounts[..] else {
+        panic!("one output");
+    };
+    assert_eq!(output.compressed_account.address, Some(address));
+    assert_eq!(
+        counter_in(&output.compressed_account),
+        CompressedCounter {
+            count: 6,
+            ..counter
+        }
+    );
+}
+
+#[test]
+fn increments_stop_at_the_maximum() {
+    let mut svm = svm();
+    let payer = svm.wallet();
+    let full = CompressedCounter {
+        authority: payer,
+        count: u64::MAX,
+    };
+    assert_eq!(
+        svm.process(&increment(payer, full, [9; 32])),
+        Err(Error::from(CounterError::Overflow).into())
+    );
+}
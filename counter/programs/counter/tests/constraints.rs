@@ -0,0 +1,3196 @@
+// Adversarial tests for the account checks: for each instruction, a valid
+// set of accounts that passes, then the same set with one thing wrong,
+// which must fail with exactly the error that check is there to give.
+//
+// The attacks (see Attack) are the classic ways to smuggle a bad account
+// past a program: an account of the right shape owned by another program,
+// an account of another type, a missing signature, a look-alike at the
+// wrong PDA, and a fake System or Token Program. Each instruction lists the
+// ones that apply to its accounts.
+//
+// Only the account checks run (Anchor's try_accounts), not the handlers,
+// so this covers instructions whose handlers need programs the harness
+// doesn't run, like the Token Program (see harness/mod.rs).
+mod harness;
+
+use std::collections::BTreeSet;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction, program_option::COption, program_pack::Pack, system_program, sysvar,
+    sysvar::instructions as instructions_sysvar,
+};
+use anchor_lang::{Accounts, Discriminator, InstructionData};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token_2022::spl_token_2022::state::{
+    Account as TokenAccountState, AccountState, Mint as MintState,
+};
+use anchor_spl::{token, token_2022};
+use counter::{
+    accounts, encode_tag, instruction, AllowedCaller, Badge, BurnMint, CommitRound,
+    CommitRoundArgs, Commitment, ConfidentialCounter, Contribution, Counter, CounterBank,
+    CounterError, CounterFees, Deadline, EventTree, FeeExemption, FeeSchedule, FeeScheduleArgs,
+    FeeTier, FreezePolicy, Gift, GiftArgs, Group, GroupArgs, IncrementFeeArgs, Leaderboard,
+    LeaseArgs, Lockup, ReapPolicy, RemoteCounter, Replica, Role, SoftCapArgs, SponsoredWallet,
+    Sponsorship, TagRegistry, Template, TemplateArgs, TemplateSettings, VoteMint, ACCESS_LOG_SEED,
+    ALLOWED_CALLER_SEED, BADGE_SEED, BURN_MINT_SEED, COMMITMENT_SEED, COMMIT_ROUND_SEED,
+    CONFIG_SEED, CONTRIBUTION_SEED, COUNTER_MINT_SEED, DEADLINE_SEED, EMITTER_SEED,
+    EVENT_TREE_SEED, FEES_SEED, FEE_EXEMPTION_SEED, FEE_SCHEDULE_SEED, FREEZE_POLICY_SEED,
+    FREEZE_TALLY_SEED, FREEZE_VOTE_SEED, GIFT_SEED, LEADERBOARD_SEED, LEASE_SEED, LOCKUP_SEED,
+    MINT_AUTHORITY_SEED, PROGRAM_INFO_SEED, REAP_POLICY_SEED, RECEIPT_SEED, REFERRAL_VAULT_SEED,
+    REFERRER_STATS_SEED, REMOTE_COUNTER_SEED, REPLICA_SEED, RESULT_SEED, ROLE_SEED,
+    SECONDS_PER_HOUR, SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID, SPONSORED_WALLET_SEED,
+    SPONSORSHIP_SEED, STATS_SEED, TAG_REGISTRY_SEED, TEMPLATE_SEED, THRESHOLD_ATTESTATION_SEED,
+    TIERS, TREASURY_SEED, TREE_AUTHORITY_SEED, VAULT_SEED, VOTE_MINT_SEED, WORMHOLE_BRIDGE_SEED,
+    WORMHOLE_FEE_COLLECTOR_SEED, WORMHOLE_POSTED_VAA_SEED, WORMHOLE_PROGRAM_ID,
+    WORMHOLE_SEQUENCE_SEED,
+};
+use harness::{fixtures, ix, program_data_address, Svm};
+
+// Something wrong with one account of a valid instruction.
+#[derive(Clone, Copy, Debug)]
+enum Attack {
+    // The account is owned by another program.
+    Owner(Pubkey),
+    // Its first byte is changed, so it's some other type of account.
+    Discriminator(Pubkey),
+    // It's passed without its signature.
+    Signer(Pubkey),
+    // A copy of it is passed from an address its seeds don't lead to.
+    Seeds(Pubkey),
+    // Another executable is passed in the program's place.
+    Program(Pubkey),
+    // A real account, but not the one expected, is passed in its place.
+    Swap(Pubkey, Pubkey),
+}
+
+impl Attack {
+    fn apply(self, svm: &mut Svm, ix: &mut Instruction) {
+        match self {
+            Attack::Owner(address) => svm.get_mut(&address).unwrap().owner = Pubkey::new_unique(),
+            Attack::Discriminator(address) => svm.get_mut(&address).unwrap().data[0] ^= 0xff,
+            Attack::Signer(address) => {
+                for meta in ix.accounts.iter_mut().filter(|m| m.pubkey == address) {
+                    meta.is_signer = false;
+                }
+            }
+            Attack::Seeds(address) => {
+                let copy = Pubkey::new_unique();
+                if let Some(account) = svm.get(&address).cloned() {
+                    svm.set(copy, account);
+                }
+                replace(ix, address, copy);
+            }
+            Attack::Program(address) => {
+                let fake = Pubkey::new_unique();
+                svm.set_program(fake);
+                replace(ix, address, fake);
+            }
+            Attack::Swap(address, other) => replace(ix, address, other),
+        }
+    }
+}
+
+fn replace(ix: &mut Instruction, from: Pubkey, to: Pubkey) {
+    for meta in ix.accounts.iter_mut().filter(|m| m.pubkey == from) {
+        meta.pubkey = to;
+    }
+}
+
+// The error each attack gets from Anchor's own checks.
+fn owner(address: Pubkey) -> (Attack, Error) {
+    (
+        Attack::Owner(address),
+        ErrorCode::AccountOwnedByWrongProgram.into(),
+    )
+}
+
+fn discriminator(address: Pubkey) -> (Attack, Error) {
+    (
+        Attack::Discriminator(address),
+        ErrorCode::AccountDiscriminatorMismatch.into(),
+    )
+}
+
+fn signer(address: Pubkey) -> (Attack, Error) {
+    (Attack::Signer(address), ErrorCode::AccountNotSigner.into())
+}
+
+fn seeds(address: Pubkey) -> (Attack, Error) {
+    (Attack::Seeds(address), ErrorCode::ConstraintSeeds.into())
+}
+
+fn program(address: Pubkey) -> (Attack, Error) {
+    (Attack::Program(address), ErrorCode::InvalidProgramId.into())
+}
+
+// Both ways to pass off another account as a typed one.
+fn typed(address: Pubkey) -> Vec<(Attack, Error)> {
+    vec![owner(address), discriminator(address)]
+}
+
+type Check = fn(&mut Svm, &Instruction) -> Result<()>;
+
+// A Check that runs the account checks of accounts struct "$accounts".
+macro_rules! check {
+    ($accounts:ident) => {
+        |svm: &mut Svm, ix: &Instruction| {
+            svm.with_accounts(ix, |infos| {
+                counter::$accounts::try_accounts(
+                    &counter::ID,
+                    &mut &infos[..],
+                    &ix.data[8..],
+                    &mut Default::default(),
+                    &mut BTreeSet::new(),
+                )
+                .map(|_| ())
+            })
+        }
+    };
+}
+
+// Checks that "ix" passes, and that each attack on it fails as expected.
+fn run(svm: Svm, ix: Instruction, check: Check, attacks: &[(Attack, Error)]) {
+    assert_eq!(
+        check(&mut svm.clone(), &ix),
+        Ok(()),
+        "valid accounts failed"
+    );
+    for (attack, expected) in attacks {
+        let (mut svm, mut ix) = (svm.clone(), ix.clone());
+        attack.apply(&mut svm, &mut ix);
+        assert_eq!(
+            check(&mut svm, &ix).err().as_ref(),
+            Some(expected),
+            "{attack:?}"
+        );
+    }
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    bumped(seeds).0
+}
+
+// A PDA and its bump, for accounts that store their bump.
+fn bumped(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &counter::ID)
+}
+
+// A counter owned by "authority".
+fn counter(svm: &mut Svm, authority: Pubkey) -> Pubkey {
+    let counter = Pubkey::new_unique();
+    svm.set_anchor(counter, &fixtures::counter(authority));
+    counter
+}
+
+// An owner and their counter.
+fn owned_counter(svm: &mut Svm) -> (Pubkey, Pubkey) {
+    let authority = svm.wallet();
+    (authority, counter(svm, authority))
+}
+
+fn guard(counter: Pubkey, authority: Pubkey) -> accounts::AuthorityGuard {
+    accounts::AuthorityGuard {
+        counter,
+        access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+        authority,
+        system_program: system_program::ID,
+    }
+}
+
+// The attacks that apply to every AuthorityGuard.
+fn guard_attacks(counter: Pubkey, authority: Pubkey) -> Vec<(Attack, Error)> {
+    let access_log = pda(&[ACCESS_LOG_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        seeds(access_log),
+        signer(authority),
+        program(system_program::ID),
+    ]);
+    attacks
+}
+
+// This hour's stats bucket (see stats.rs).
+fn stats_bucket(svm: &Svm, counter: Pubkey) -> Pubkey {
+    let hour = svm.clock().unix_timestamp.div_euclid(SECONDS_PER_HOUR);
+    pda(&[STATS_SEED, counter.as_ref(), &hour.to_le_bytes()])
+}
+
+// A Token-2022 mint at "address".
+fn mint(svm: &mut Svm, address: Pubkey) {
+    let mut data = vec![0; MintState::LEN];
+    MintState {
+        mint_authority: COption::None,
+        supply: 0,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    svm.set_data(address, token_2022::ID, data);
+}
+
+// "owner"'s Token-2022 account for "mint", at "address".
+fn token_account(svm: &mut Svm, address: Pubkey, mint: Pubkey, owner: Pubkey) {
+    let mut data = vec![0; TokenAccountState::LEN];
+    TokenAccountState {
+        mint,
+        owner,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    svm.set_data(address, token_2022::ID, data);
+}
+
+// "owner"'s associated token account for "mint".
+fn associated(svm: &mut Svm, mint: Pubkey, owner: Pubkey) -> Pubkey {
+    let address = get_associated_token_address_with_program_id(&owner, &mint, &token_2022::ID);
+    token_account(svm, address, mint, owner);
+    address
+}
+
+// A token account at the wrong address: not an associated token account.
+fn not_associated(address: Pubkey) -> (Attack, Error) {
+    (
+        Attack::Seeds(address),
+        ErrorCode::ConstraintAssociated.into(),
+    )
+}
+
+// The legacy Token Program in place of Token-2022, whose mint it doesn't
+// own.
+fn legacy_token_program(error: ErrorCode) -> (Attack, Error) {
+    (Attack::Swap(token_2022::ID, token::ID), error.into())
+}
+
+// Another account in place of one with a fixed address.
+fn address(address: Pubkey) -> (Attack, Error) {
+    (Attack::Seeds(address), ErrorCode::ConstraintAddress.into())
+}
+
+// An UncheckedAccount with an "owner =" constraint, owned by someone else.
+fn owner_constraint(address: Pubkey) -> (Attack, Error) {
+    (Attack::Owner(address), ErrorCode::ConstraintOwner.into())
+}
+
+// ----------------------------------------------------------------------------
+// lib.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize() {
+    let mut svm = Svm::new();
+    let user = svm.wallet();
+    let counter = Pubkey::new_unique();
+    run(
+        svm,
+        ix(
+            accounts::Initialize {
+                counter,
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::Initialize {},
+        ),
+        check!(Initialize),
+        &[signer(user), program(system_program::ID)],
+    );
+}
+
+#[test]
+fn increment() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    run(
+        svm,
+        ix(accounts::Increment { counter }, instruction::Increment {}),
+        check!(Increment),
+        &typed(counter),
+    );
+}
+
+// Every instruction that takes an UpdateCounter.
+#[test]
+fn update_counter() {
+    let data: [Vec<u8>; 12] = [
+        instruction::SetOncePerSlot { enabled: true }.data(),
+        instruction::SetAlarmThreshold { threshold: 5 }.data(),
+        instruction::AcknowledgeAlarm {}.data(),
+        instruction::SetTreeLogged { enabled: true }.data(),
+        instruction::Archive {}.data(),
+        instruction::Unarchive {}.data(),
+        instruction::SetCommitReveal { enabled: true }.data(),
+        instruction::SetDenyCpi { enabled: true }.data(),
+        instruction::SetRoleWeighted { enabled: true }.data(),
+        instruction::SetSoftCap {
+            args: SoftCapArgs {
+                version: SoftCapArgs::VERSION,
+                max_count: 5,
+                overflow: Pubkey::new_unique(),
+            },
+        }
+        .data(),
+        instruction::SetTwac { enabled: true }.data(),
+        instruction::SetDeltaFeed { enabled: true }.data(),
+    ];
+    for data in data {
+        let mut svm = Svm::new();
+        let (authority, counter) = owned_counter(&mut svm);
+        let lease = pda(&[LEASE_SEED, counter.as_ref()]);
+        let access_log = pda(&[ACCESS_LOG_SEED, counter.as_ref()]);
+        let mut attacks = typed(counter);
+        attacks.extend([
+            seeds(lease),
+            seeds(access_log),
+            signer(authority),
+            program(system_program::ID),
+        ]);
+        run(
+            svm,
+            Instruction {
+                program_id: counter::ID,
+                accounts: accounts::UpdateCounter {
+                    counter,
+                    lease,
+                    access_log,
+                    authority,
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data,
+            },
+            check!(UpdateCounter),
+            &attacks,
+        );
+    }
+}
+
+#[test]
+fn reset_count() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    run(
+        svm,
+        ix(
+            accounts::ResetCount {
+                guard: guard(counter, authority),
+            },
+            instruction::ResetCount {},
+        ),
+        check!(ResetCount),
+        &guard_attacks(counter, authority),
+    );
+}
+
+// ----------------------------------------------------------------------------
+// bank.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_bank() {
+    let mut svm = Svm::new();
+    let user = svm.wallet();
+    let bank = Pubkey::new_unique();
+    run(
+        svm,
+        ix(
+            accounts::InitializeBank {
+                bank,
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeBank {},
+        ),
+        check!(InitializeBank),
+        &[signer(user), program(system_program::ID)],
+    );
+}
+
+#[test]
+fn increment_index() {
+    let mut svm = Svm::new();
+    let bank = Pubkey::new_unique();
+    svm.set_zeroed::<CounterBank>(bank, CounterBank::SPACE);
+    run(
+        svm,
+        ix(
+            accounts::IncrementIndex { bank },
+            instruction::IncrementIndex { index: 0 },
+        ),
+        check!(IncrementIndex),
+        &typed(bank),
+    );
+}
+
+// ----------------------------------------------------------------------------
+// program_info.rs, config.rs
+// ----------------------------------------------------------------------------
+
+// The attacks on an upgrade authority's instruction: the program and its
+// ProgramData are checked like any other accounts.
+fn upgrade_authority_attacks(authority: Pubkey) -> Vec<(Attack, Error)> {
+    vec![
+        signer(authority),
+        program(counter::ID),
+        owner(program_data_address(&counter::ID)),
+        program(system_program::ID),
+    ]
+}
+
+#[test]
+fn set_program_info() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    svm.deploy_counter(Some(authority));
+    let program_info = pda(&[PROGRAM_INFO_SEED]);
+    let mut attacks = upgrade_authority_attacks(authority);
+    attacks.push(seeds(program_info));
+    run(
+        svm,
+        ix(
+            accounts::SetProgramInfo {
+                program_info,
+                authority,
+                program: counter::ID,
+                program_data: program_data_address(&counter::ID),
+                system_program: system_program::ID,
+            },
+            instruction::SetProgramInfo { commit: [0; 20] },
+        ),
+        check!(SetProgramInfo),
+        &attacks,
+    );
+}
+
+#[test]
+fn initialize_config() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    svm.deploy_counter(Some(authority));
+    let config = pda(&[CONFIG_SEED]);
+    let mut attacks = upgrade_authority_attacks(authority);
+    attacks.push(seeds(config));
+    run(
+        svm,
+        ix(
+            accounts::InitializeConfig {
+                config,
+                authority,
+                program: counter::ID,
+                program_data: program_data_address(&counter::ID),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeConfig { admin: authority },
+        ),
+        check!(InitializeConfig),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// idempotency.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn increment_with_key() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let (_, counter) = owned_counter(&mut svm);
+    let key = [7; 16];
+    let receipt = pda(&[RECEIPT_SEED, counter.as_ref(), &key]);
+    let mut attacks = typed(counter);
+    attacks.extend([seeds(receipt), signer(payer), program(system_program::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::IncrementWithKey {
+                counter,
+                receipt,
+                payer,
+                system_program: system_program::ID,
+            },
+            instruction::IncrementWithKey {
+                idempotency_key: key,
+            },
+        ),
+        check!(IncrementWithKey),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// replica.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_replica() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let replica = pda(&[REPLICA_SEED, counter.as_ref(), &3u16.to_le_bytes()]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        seeds(replica),
+        signer(authority),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeReplica {
+                counter,
+                replica,
+                authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeReplica { replica_id: 3 },
+        ),
+        check!(InitializeReplica),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_replica() {
+    let mut svm = Svm::new();
+    let replica = Pubkey::new_unique();
+    svm.set_anchor(
+        replica,
+        &Replica {
+            root: Pubkey::new_unique(),
+            id: 0,
+            count: 0,
+            merged: 0,
+            bump: 0,
+        },
+    );
+    run(
+        svm,
+        ix(
+            accounts::IncrementReplica { replica },
+            instruction::IncrementReplica {},
+        ),
+        check!(IncrementReplica),
+        &typed(replica),
+    );
+}
+
+#[test]
+fn merge_replicas() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    run(
+        svm,
+        ix(
+            accounts::MergeReplicas { counter },
+            instruction::MergeReplicas {},
+        ),
+        check!(MergeReplicas),
+        &typed(counter),
+    );
+}
+
+// ----------------------------------------------------------------------------
+// wormhole.rs
+// ----------------------------------------------------------------------------
+
+fn wormhole_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &WORMHOLE_PROGRAM_ID).0
+}
+
+#[test]
+fn post_count() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let message = Pubkey::new_unique();
+    let (_, counter) = owned_counter(&mut svm);
+    let bridge = wormhole_pda(&[WORMHOLE_BRIDGE_SEED]);
+    let emitter = pda(&[EMITTER_SEED]);
+    let sequence = wormhole_pda(&[WORMHOLE_SEQUENCE_SEED, emitter.as_ref()]);
+    let fee_collector = wormhole_pda(&[WORMHOLE_FEE_COLLECTOR_SEED]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        seeds(bridge),
+        signer(message),
+        seeds(emitter),
+        seeds(sequence),
+        seeds(fee_collector),
+        signer(payer),
+        address(WORMHOLE_PROGRAM_ID),
+        (
+            Attack::Seeds(sysvar::clock::ID),
+            ErrorCode::AccountSysvarMismatch.into(),
+        ),
+        (
+            Attack::Seeds(sysvar::rent::ID),
+            ErrorCode::AccountSysvarMismatch.into(),
+        ),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::PostCount {
+                counter,
+                wormhole_bridge: bridge,
+                wormhole_message: message,
+                wormhole_emitter: emitter,
+                wormhole_sequence: sequence,
+                wormhole_fee_collector: fee_collector,
+                payer,
+                wormhole_program: WORMHOLE_PROGRAM_ID,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+                system_program: system_program::ID,
+            },
+            instruction::PostCount { nonce: 0 },
+        ),
+        check!(PostCount),
+        &attacks,
+    );
+}
+
+#[test]
+fn initialize_remote_counter() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let (chain, emitter, id) = (2u16, [1; 32], [2; 32]);
+    let remote_counter = pda(&[REMOTE_COUNTER_SEED, &chain.to_le_bytes(), &emitter, &id]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeRemoteCounter {
+                remote_counter,
+                payer,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeRemoteCounter {
+                emitter_chain: chain,
+                emitter_address: emitter,
+                remote_counter_id: id,
+            },
+        ),
+        check!(InitializeRemoteCounter),
+        &[
+            seeds(remote_counter),
+            signer(payer),
+            program(system_program::ID),
+        ],
+    );
+}
+
+#[test]
+fn receive_remote_count() {
+    let mut svm = Svm::new();
+    let remote_counter = Pubkey::new_unique();
+    svm.set_anchor(
+        remote_counter,
+        &RemoteCounter {
+            emitter_chain: 2,
+            emitter_address: [1; 32],
+            remote_counter: [2; 32],
+            count: 0,
+            next_sequence: 0,
+            bump: 0,
+        },
+    );
+    let vaa_hash = [3; 32];
+    let posted_vaa = wormhole_pda(&[WORMHOLE_POSTED_VAA_SEED, &vaa_hash]);
+    svm.set_data(posted_vaa, WORMHOLE_PROGRAM_ID, vec![0; 8]);
+    let mut attacks = typed(remote_counter);
+    attacks.extend([seeds(posted_vaa), owner_constraint(posted_vaa)]);
+    run(
+        svm,
+        ix(
+            accounts::ReceiveRemoteCount {
+                remote_counter,
+                posted_vaa,
+            },
+            instruction::ReceiveRemoteCount { vaa_hash },
+        ),
+        check!(ReceiveRemoteCount),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// event_tree.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_event_tree() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    svm.deploy_counter(Some(authority));
+    let merkle_tree = Pubkey::new_unique();
+    svm.set_data(merkle_tree, SPL_ACCOUNT_COMPRESSION_ID, vec![0; 8]);
+    let event_tree = pda(&[EVENT_TREE_SEED]);
+    let tree_authority = pda(&[TREE_AUTHORITY_SEED]);
+    let mut attacks = upgrade_authority_attacks(authority);
+    attacks.extend([
+        seeds(event_tree),
+        owner_constraint(merkle_tree),
+        seeds(tree_authority),
+        address(SPL_ACCOUNT_COMPRESSION_ID),
+        address(SPL_NOOP_ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeEventTree {
+                event_tree,
+                merkle_tree,
+                tree_authority,
+                authority,
+                program: counter::ID,
+                program_data: program_data_address(&counter::ID),
+                compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                noop_program: SPL_NOOP_ID,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeEventTree {
+                max_depth: 14,
+                max_buffer_size: 64,
+            },
+        ),
+        check!(InitializeEventTree),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_logged() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let mut logged = fixtures::counter(authority);
+    logged.set_flag(Counter::TREE_LOGGED, true);
+    svm.set_anchor(counter, &logged);
+    let merkle_tree = Pubkey::new_unique();
+    let (event_tree, bump) = bumped(&[EVENT_TREE_SEED]);
+    let (tree_authority, authority_bump) = bumped(&[TREE_AUTHORITY_SEED]);
+    svm.set_anchor(
+        event_tree,
+        &EventTree {
+            merkle_tree,
+            leaves: 0,
+            bump,
+            authority_bump,
+        },
+    );
+    let mut attacks = typed(counter);
+    attacks.extend(typed(event_tree));
+    attacks.extend([
+        seeds(event_tree),
+        (
+            Attack::Seeds(merkle_tree),
+            ErrorCode::ConstraintHasOne.into(),
+        ),
+        seeds(tree_authority),
+        address(SPL_ACCOUNT_COMPRESSION_ID),
+        address(SPL_NOOP_ID),
+    ]);
+    let ix = ix(
+        accounts::IncrementLogged {
+            counter,
+            event_tree,
+            merkle_tree,
+            tree_authority,
+            compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+            noop_program: SPL_NOOP_ID,
+        },
+        instruction::IncrementLogged {},
+    );
+
+    // Not one of the attacks: a real counter, but not a tree-logged one.
+    let mut plain = svm.clone();
+    plain.set_anchor(counter, &fixtures::counter(authority));
+    let check: Check = check!(IncrementLogged);
+    assert_eq!(
+        check(&mut plain, &ix).err(),
+        Some(CounterError::NotTreeLogged.into())
+    );
+    run(svm, ix, check, &attacks);
+}
+
+// ----------------------------------------------------------------------------
+// confidential.rs
+// ----------------------------------------------------------------------------
+
+fn confidential_counter(svm: &mut Svm, authority: Pubkey) -> Pubkey {
+    let counter = Pubkey::new_unique();
+    svm.set_anchor(
+        counter,
+        &ConfidentialCounter {
+            authority,
+            elgamal_pubkey: [0; 32],
+            encrypted_count: [0; 64],
+            increments: 0,
+        },
+    );
+    counter
+}
+
+#[test]
+fn initialize_confidential() {
+    let mut svm = Svm::new();
+    let user = svm.wallet();
+    run(
+        svm,
+        ix(
+            accounts::InitializeConfidential {
+                counter: Pubkey::new_unique(),
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeConfidential {
+                elgamal_pubkey: [0; 32],
+            },
+        ),
+        check!(InitializeConfidential),
+        &[signer(user), program(system_program::ID)],
+    );
+}
+
+#[test]
+fn increment_confidential() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = confidential_counter(&mut svm, authority);
+    run(
+        svm,
+        ix(
+            accounts::IncrementConfidential { counter },
+            instruction::IncrementConfidential {
+                encrypted_delta: [0; 64],
+            },
+        ),
+        check!(IncrementConfidential),
+        &typed(counter),
+    );
+}
+
+#[test]
+fn prove_threshold() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = confidential_counter(&mut svm, authority);
+    let (equality_proof, range_proof) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let proof_program = solana_zk_token_sdk::zk_token_proof_program::ID;
+    svm.set_data(equality_proof, proof_program, vec![0; 8]);
+    svm.set_data(range_proof, proof_program, vec![0; 8]);
+    let attestation = pda(&[
+        THRESHOLD_ATTESTATION_SEED,
+        counter.as_ref(),
+        &5u64.to_le_bytes(),
+    ]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        owner_constraint(equality_proof),
+        owner_constraint(range_proof),
+        seeds(attestation),
+        signer(authority),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::ProveThreshold {
+                counter,
+                equality_proof,
+                range_proof,
+                attestation,
+                authority,
+                system_program: system_program::ID,
+            },
+            instruction::ProveThreshold { min: 5 },
+        ),
+        check!(ProveThreshold),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// token_mirror.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_counter_mint() {
+    // Creating the mint takes a CPI to the Token Program, which the harness
+    // doesn't run (see harness/mod.rs), so the valid accounts stop there.
+    // Every attack below fails earlier, on the accounts themselves.
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let mint = pda(&[COUNTER_MINT_SEED, counter.as_ref()]);
+    let ix = ix(
+        accounts::InitializeCounterMint {
+            counter,
+            mint,
+            mint_authority: pda(&[MINT_AUTHORITY_SEED]),
+            access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+            authority_tokens: get_associated_token_address_with_program_id(
+                &authority,
+                &mint,
+                &token_2022::ID,
+            ),
+            authority,
+            token_program: token_2022::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeCounterMint {},
+    );
+    let check: Check = check!(InitializeCounterMint);
+    assert_eq!(
+        check(&mut svm.clone(), &ix).err(),
+        Some(ProgramError::IncorrectProgramId.into())
+    );
+
+    let mut attacks = typed(counter);
+    attacks.extend([
+        signer(authority),
+        program(token_2022::ID),
+        program(associated_token::ID),
+        program(system_program::ID),
+        seeds(mint),
+    ]);
+    for (attack, expected) in attacks {
+        let (mut svm, mut ix) = (svm.clone(), ix.clone());
+        attack.apply(&mut svm, &mut ix);
+        assert_eq!(check(&mut svm, &ix).err(), Some(expected), "{attack:?}");
+    }
+}
+
+// A counter with its mirror mint and a holder's tokens.
+fn mirrored(svm: &mut Svm) -> (Pubkey, Pubkey, Pubkey, Pubkey) {
+    let (_, counter) = owned_counter(svm);
+    let mint_address = pda(&[COUNTER_MINT_SEED, counter.as_ref()]);
+    mint(svm, mint_address);
+    let holder = svm.wallet();
+    let tokens = associated(svm, mint_address, holder);
+    (counter, mint_address, holder, tokens)
+}
+
+#[test]
+fn increment_minted() {
+    let mut svm = Svm::new();
+    let (counter, mint, _, recipient) = mirrored(&mut svm);
+    let mint_authority = pda(&[MINT_AUTHORITY_SEED]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        owner(mint),
+        seeds(mint),
+        seeds(mint_authority),
+        owner(recipient),
+        program(token_2022::ID),
+        legacy_token_program(ErrorCode::ConstraintMintTokenProgram),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::IncrementMinted {
+                counter,
+                mint,
+                mint_authority,
+                recipient,
+                token_program: token_2022::ID,
+            },
+            instruction::IncrementMinted {},
+        ),
+        check!(IncrementMinted),
+        &attacks,
+    );
+}
+
+#[test]
+fn decrement_minted() {
+    let mut svm = Svm::new();
+    let (counter, mint, holder, holder_tokens) = mirrored(&mut svm);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        owner(mint),
+        seeds(mint),
+        owner(holder_tokens),
+        signer(holder),
+        program(token_2022::ID),
+        legacy_token_program(ErrorCode::ConstraintMintTokenProgram),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::DecrementMinted {
+                counter,
+                mint,
+                holder_tokens,
+                holder,
+                token_program: token_2022::ID,
+            },
+            instruction::DecrementMinted {},
+        ),
+        check!(DecrementMinted),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// burn.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn set_burn_mint() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let mint_address = Pubkey::new_unique();
+    mint(&mut svm, mint_address);
+    let burn_mint = pda(&[BURN_MINT_SEED, counter.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.extend([seeds(burn_mint), owner(mint_address)]);
+    run(
+        svm,
+        ix(
+            accounts::SetBurnMint {
+                guard: guard(counter, authority),
+                burn_mint,
+                mint: mint_address,
+                system_program: system_program::ID,
+            },
+            instruction::SetBurnMint {},
+        ),
+        check!(SetBurnMint),
+        &attacks,
+    );
+}
+
+#[test]
+fn decrement_by_burn() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let mint_address = Pubkey::new_unique();
+    mint(&mut svm, mint_address);
+    let (burn_mint, bump) = bumped(&[BURN_MINT_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        burn_mint,
+        &BurnMint {
+            counter,
+            mint: mint_address,
+            bump,
+        },
+    );
+    let holder = svm.wallet();
+    let holder_tokens = associated(&mut svm, mint_address, holder);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(burn_mint));
+    attacks.extend([
+        seeds(burn_mint),
+        owner(mint_address),
+        not_associated(holder_tokens),
+        signer(holder),
+        program(token_2022::ID),
+        legacy_token_program(ErrorCode::ConstraintMintTokenProgram),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::DecrementByBurn {
+                counter,
+                burn_mint,
+                mint: mint_address,
+                holder_tokens,
+                holder,
+                token_program: token_2022::ID,
+            },
+            instruction::DecrementByBurn { amount: 1 },
+        ),
+        check!(DecrementByBurn),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// lease.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn lease_authority() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let renter = svm.wallet();
+    let lease = pda(&[LEASE_SEED, counter.as_ref()]);
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let access_log = pda(&[ACCESS_LOG_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        seeds(lease),
+        seeds(vault),
+        seeds(access_log),
+        signer(authority),
+        signer(renter),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::LeaseAuthority {
+                counter,
+                lease,
+                vault,
+                access_log,
+                authority,
+                renter,
+                system_program: system_program::ID,
+            },
+            instruction::LeaseAuthority {
+                args: LeaseArgs {
+                    version: LeaseArgs::VERSION,
+                    to: renter,
+                    duration: 60,
+                    price: 0,
+                },
+            },
+        ),
+        check!(LeaseAuthority),
+        &attacks,
+    );
+}
+
+#[test]
+fn withdraw_vault() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(vault));
+    run(
+        svm,
+        ix(
+            accounts::WithdrawVault {
+                guard: guard(counter, authority),
+                vault,
+            },
+            instruction::WithdrawVault { amount: 1 },
+        ),
+        check!(WithdrawVault),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// fees.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn set_increment_fee() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let fees = pda(&[FEES_SEED, counter.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(fees));
+    run(
+        svm,
+        ix(
+            accounts::SetIncrementFee {
+                guard: guard(counter, authority),
+                fees,
+                system_program: system_program::ID,
+            },
+            instruction::SetIncrementFee {
+                args: IncrementFeeArgs {
+                    version: IncrementFeeArgs::VERSION,
+                    fee_lamports: 10,
+                    referral_bps: 100,
+                },
+            },
+        ),
+        check!(SetIncrementFee),
+        &attacks,
+    );
+}
+
+#[test]
+fn set_fee_schedule() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let fees = pda(&[FEES_SEED, counter.as_ref()]);
+    let fee_schedule = pda(&[FEE_SCHEDULE_SEED, counter.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.extend([
+        seeds(fees),
+        seeds(fee_schedule),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::SetFeeSchedule {
+                guard: guard(counter, authority),
+                fees,
+                fee_schedule,
+                system_program: system_program::ID,
+            },
+            instruction::SetFeeSchedule {
+                args: FeeScheduleArgs {
+                    version: FeeScheduleArgs::VERSION,
+                    tiers: vec![FeeTier {
+                        from: 0,
+                        fee_lamports: 10,
+                    }],
+                    referral_bps: 100,
+                },
+            },
+        ),
+        check!(SetFeeSchedule),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_paid() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let payer = svm.wallet();
+    let referrer = Pubkey::new_unique();
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let (fees, bump) = bumped(&[FEES_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        fees,
+        &CounterFees {
+            counter,
+            fee_lamports: 10,
+            referral_bps: 100,
+            bump,
+        },
+    );
+    let (fee_exemption, bump) = bumped(&[FEE_EXEMPTION_SEED, counter.as_ref(), payer.as_ref()]);
+    svm.set_anchor(
+        fee_exemption,
+        &FeeExemption {
+            counter,
+            wallet: payer,
+            bump,
+        },
+    );
+    let referrer_stats = pda(&[REFERRER_STATS_SEED, counter.as_ref(), referrer.as_ref()]);
+    let referral_vault = pda(&[REFERRAL_VAULT_SEED, referrer.as_ref()]);
+    let stats_bucket = stats_bucket(&svm, counter);
+    let (fee_schedule, bump) = bumped(&[FEE_SCHEDULE_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        fee_schedule,
+        &FeeSchedule {
+            counter,
+            tiers: vec![FeeTier {
+                from: 0,
+                fee_lamports: 10,
+            }],
+            day: 0,
+            day_volume: 0,
+            bump,
+        },
+    );
+
+    let mut attacks = typed(counter);
+    attacks.extend([seeds(vault), signer(payer), program(system_program::ID)]);
+    attacks.extend(typed(fees));
+    attacks.push(seeds(fees));
+    attacks.extend([seeds(referrer_stats), seeds(referral_vault)]);
+    attacks.extend(typed(fee_exemption));
+    attacks.extend([seeds(fee_exemption), seeds(stats_bucket)]);
+    attacks.extend(typed(fee_schedule));
+    attacks.push(seeds(fee_schedule));
+    run(
+        svm,
+        ix(
+            accounts::IncrementPaid {
+                payment: accounts::FeePayment {
+                    counter,
+                    vault,
+                    payer,
+                    system_program: system_program::ID,
+                },
+                fees,
+                referrer_stats: Some(referrer_stats),
+                referral_vault: Some(referral_vault),
+                fee_exemption: Some(fee_exemption),
+                stats_bucket,
+                system_program: system_program::ID,
+                fee_schedule: Some(fee_schedule),
+            },
+            instruction::IncrementPaid {
+                referrer: Some(referrer),
+            },
+        ),
+        check!(IncrementPaid),
+        &attacks,
+    );
+}
+
+#[test]
+fn exempt_from_fees() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let wallet = Pubkey::new_unique();
+    let fee_exemption = pda(&[FEE_EXEMPTION_SEED, counter.as_ref(), wallet.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(fee_exemption));
+    run(
+        svm,
+        ix(
+            accounts::ExemptFromFees {
+                guard: guard(counter, authority),
+                fee_exemption,
+                system_program: system_program::ID,
+            },
+            instruction::ExemptFromFees { wallet },
+        ),
+        check!(ExemptFromFees),
+        &attacks,
+    );
+}
+
+#[test]
+fn revoke_fee_exemption() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let wallet = Pubkey::new_unique();
+    let (fee_exemption, bump) = bumped(&[FEE_EXEMPTION_SEED, counter.as_ref(), wallet.as_ref()]);
+    svm.set_anchor(
+        fee_exemption,
+        &FeeExemption {
+            counter,
+            wallet,
+            bump,
+        },
+    );
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.extend(typed(fee_exemption));
+    attacks.push(seeds(fee_exemption));
+    run(
+        svm,
+        ix(
+            accounts::RevokeFeeExemption {
+                guard: guard(counter, authority),
+                fee_exemption,
+            },
+            instruction::RevokeFeeExemption {},
+        ),
+        check!(RevokeFeeExemption),
+        &attacks,
+    );
+}
+
+#[test]
+fn withdraw_referral() {
+    let mut svm = Svm::new();
+    let referrer = svm.wallet();
+    let referral_vault = pda(&[REFERRAL_VAULT_SEED, referrer.as_ref()]);
+    run(
+        svm,
+        ix(
+            accounts::WithdrawReferral {
+                referral_vault,
+                referrer,
+                system_program: system_program::ID,
+            },
+            instruction::WithdrawReferral { amount: 1 },
+        ),
+        check!(WithdrawReferral),
+        &[
+            seeds(referral_vault),
+            signer(referrer),
+            program(system_program::ID),
+        ],
+    );
+}
+
+// ----------------------------------------------------------------------------
+// badges.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn increment_contributed() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let contributor = svm.wallet();
+    let contribution = pda(&[CONTRIBUTION_SEED, counter.as_ref(), contributor.as_ref()]);
+    let stats_bucket = stats_bucket(&svm, counter);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        seeds(contribution),
+        seeds(stats_bucket),
+        signer(contributor),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::IncrementContributed {
+                counter,
+                contribution,
+                stats_bucket,
+                contributor,
+                system_program: system_program::ID,
+            },
+            instruction::IncrementContributed {},
+        ),
+        check!(IncrementContributed),
+        &attacks,
+    );
+}
+
+#[test]
+fn claim_badge() {
+    let mut svm = Svm::new();
+    let counter = Pubkey::new_unique();
+    let contributor = svm.wallet();
+    let (contribution, bump) = bumped(&[CONTRIBUTION_SEED, counter.as_ref(), contributor.as_ref()]);
+    svm.set_anchor(
+        contribution,
+        &Contribution {
+            counter,
+            contributor,
+            count: TIERS[0],
+            reached_at: [0; TIERS.len()],
+            bump,
+        },
+    );
+    let badge = pda(&[BADGE_SEED, counter.as_ref(), contributor.as_ref(), &[0]]);
+    let mut attacks = typed(contribution);
+    attacks.extend([
+        seeds(contribution),
+        seeds(badge),
+        signer(contributor),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::ClaimBadge {
+                contribution,
+                badge,
+                contributor,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimBadge { tier: 0 },
+        ),
+        check!(ClaimBadge),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// vote_escrow.rs
+// ----------------------------------------------------------------------------
+
+// A vote-weighted counter, its mint, and a holder's lockup with its escrow.
+struct Weighted {
+    counter: Pubkey,
+    vote_mint: Pubkey,
+    mint: Pubkey,
+    owner: Pubkey,
+    owner_tokens: Pubkey,
+    lockup: Pubkey,
+    escrow: Pubkey,
+}
+
+fn weighted(svm: &mut Svm) -> Weighted {
+    let (_, counter) = owned_counter(svm);
+    let mint_address = Pubkey::new_unique();
+    mint(svm, mint_address);
+    let (vote_mint, bump) = bumped(&[VOTE_MINT_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        vote_mint,
+        &VoteMint {
+            counter,
+            mint: mint_address,
+            bump,
+        },
+    );
+    let owner = svm.wallet();
+    let owner_tokens = associated(svm, mint_address, owner);
+    let (lockup, bump) = bumped(&[
+        LOCKUP_SEED,
+        counter.as_ref(),
+        mint_address.as_ref(),
+        owner.as_ref(),
+    ]);
+    svm.set_anchor(
+        lockup,
+        &Lockup {
+            counter,
+            owner,
+            mint: mint_address,
+            amount: 0,
+            unlock_at: 0,
+            voted: false,
+            bump,
+        },
+    );
+    let escrow = associated(svm, mint_address, lockup);
+    Weighted {
+        counter,
+        vote_mint,
+        mint: mint_address,
+        owner,
+        owner_tokens,
+        lockup,
+        escrow,
+    }
+}
+
+#[test]
+fn set_vote_weighted() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let mint_address = Pubkey::new_unique();
+    mint(&mut svm, mint_address);
+    let vote_mint = pda(&[VOTE_MINT_SEED, counter.as_ref()]);
+    let access_log = pda(&[ACCESS_LOG_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend([
+        seeds(vote_mint),
+        seeds(access_log),
+        owner(mint_address),
+        signer(authority),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::SetVoteWeighted {
+                counter,
+                vote_mint,
+                access_log,
+                mint: mint_address,
+                authority,
+                system_program: system_program::ID,
+            },
+            instruction::SetVoteWeighted { enabled: true },
+        ),
+        check!(SetVoteWeighted),
+        &attacks,
+    );
+}
+
+#[test]
+fn lock_tokens() {
+    let mut svm = Svm::new();
+    let w = weighted(&mut svm);
+    let mut attacks = typed(w.counter);
+    attacks.extend(typed(w.vote_mint));
+    attacks.extend([
+        seeds(w.vote_mint),
+        owner(w.mint),
+        seeds(w.lockup),
+        // "init_if_needed" checks an existing account's address itself,
+        // with its own error.
+        (
+            Attack::Seeds(w.escrow),
+            ErrorCode::AccountNotAssociatedTokenAccount.into(),
+        ),
+        not_associated(w.owner_tokens),
+        signer(w.owner),
+        program(token_2022::ID),
+        // The escrow's "init_if_needed" is checked before the mint.
+        legacy_token_program(ErrorCode::ConstraintAssociatedTokenTokenProgram),
+        program(associated_token::ID),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::LockTokens {
+                counter: w.counter,
+                vote_mint: w.vote_mint,
+                mint: w.mint,
+                lockup: w.lockup,
+                escrow: w.escrow,
+                owner_tokens: w.owner_tokens,
+                owner: w.owner,
+                token_program: token_2022::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::LockTokens {
+                amount: 1,
+                duration: 60,
+            },
+        ),
+        check!(LockTokens),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_weighted() {
+    let mut svm = Svm::new();
+    let w = weighted(&mut svm);
+    let mut attacks = typed(w.counter);
+    attacks.extend(typed(w.vote_mint));
+    attacks.push(seeds(w.vote_mint));
+    attacks.extend(typed(w.lockup));
+    attacks.extend([seeds(w.lockup), signer(w.owner)]);
+    run(
+        svm,
+        ix(
+            accounts::IncrementWeighted {
+                counter: w.counter,
+                vote_mint: w.vote_mint,
+                lockup: w.lockup,
+                owner: w.owner,
+            },
+            instruction::IncrementWeighted {},
+        ),
+        check!(IncrementWeighted),
+        &attacks,
+    );
+}
+
+#[test]
+fn unlock() {
+    let mut svm = Svm::new();
+    let w = weighted(&mut svm);
+    let mut attacks = typed(w.lockup);
+    attacks.extend([
+        seeds(w.lockup),
+        owner(w.mint),
+        not_associated(w.escrow),
+        owner(w.owner_tokens),
+        signer(w.owner),
+        program(token_2022::ID),
+        legacy_token_program(ErrorCode::ConstraintMintTokenProgram),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::Unlock {
+                lockup: w.lockup,
+                mint: w.mint,
+                escrow: w.escrow,
+                owner_tokens: w.owner_tokens,
+                owner: w.owner,
+                token_program: token_2022::ID,
+            },
+            instruction::Unlock {},
+        ),
+        check!(Unlock),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// commit_reveal.rs
+// ----------------------------------------------------------------------------
+
+// A commit-reveal counter with round 1 open, and a participant's
+// commitment in it.
+fn committed(svm: &mut Svm) -> (Pubkey, Pubkey, Pubkey, Pubkey) {
+    let (authority, counter) = owned_counter(svm);
+    let mut commit_reveal = fixtures::counter(authority);
+    commit_reveal.set_flag(Counter::COMMIT_REVEAL, true);
+    svm.set_anchor(counter, &commit_reveal);
+    let (commit_round, bump) = bumped(&[COMMIT_ROUND_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        commit_round,
+        &CommitRound {
+            counter,
+            round: 1,
+            commit_deadline: 0,
+            reveal_deadline: 0,
+            deposit: 0,
+            max_value: 10,
+            commits: 1,
+            reveals: 0,
+            bump,
+        },
+    );
+    let participant = svm.wallet();
+    let (commitment, bump) = bumped(&[
+        COMMITMENT_SEED,
+        counter.as_ref(),
+        &1u64.to_le_bytes(),
+        participant.as_ref(),
+    ]);
+    svm.set_anchor(
+        commitment,
+        &Commitment {
+            participant,
+            hash: [0; 32],
+            bump,
+        },
+    );
+    (counter, commit_round, participant, commitment)
+}
+
+#[test]
+fn open_commit_round() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let commit_round = pda(&[COMMIT_ROUND_SEED, counter.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(commit_round));
+    run(
+        svm,
+        ix(
+            accounts::OpenCommitRound {
+                guard: guard(counter, authority),
+                commit_round,
+                system_program: system_program::ID,
+            },
+            instruction::OpenCommitRound {
+                args: CommitRoundArgs {
+                    version: CommitRoundArgs::VERSION,
+                    commit_deadline: 10,
+                    reveal_deadline: 20,
+                    deposit: 0,
+                    max_value: 10,
+                },
+            },
+        ),
+        check!(OpenCommitRound),
+        &attacks,
+    );
+}
+
+#[test]
+fn commit() {
+    let mut svm = Svm::new();
+    let (counter, commit_round, _, _) = committed(&mut svm);
+    // A participant who hasn't committed yet.
+    let participant = svm.wallet();
+    let commitment = pda(&[
+        COMMITMENT_SEED,
+        counter.as_ref(),
+        &1u64.to_le_bytes(),
+        participant.as_ref(),
+    ]);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(commit_round));
+    attacks.extend([
+        seeds(commit_round),
+        seeds(commitment),
+        signer(participant),
+        program(system_program::ID),
+    ]);
+    let ix = ix(
+        accounts::Commit {
+            counter,
+            commit_round,
+            commitment,
+            participant,
+            system_program: system_program::ID,
+        },
+        instruction::Commit { hash: [0; 32] },
+    );
+
+    // Not one of the attacks: a real counter, but not a commit-reveal one.
+    let mut plain = svm.clone();
+    let authority = svm.load::<Counter>(&counter).unwrap().authority;
+    plain.set_anchor(counter, &fixtures::counter(authority));
+    let check: Check = check!(Commit);
+    assert_eq!(
+        check(&mut plain, &ix).err(),
+        Some(CounterError::NotCommitReveal.into())
+    );
+    run(svm, ix, check, &attacks);
+}
+
+#[test]
+fn reveal() {
+    let mut svm = Svm::new();
+    let (counter, commit_round, participant, commitment) = committed(&mut svm);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(commit_round));
+    attacks.push(seeds(commit_round));
+    attacks.extend(typed(commitment));
+    attacks.extend([seeds(commitment), signer(participant)]);
+    run(
+        svm,
+        ix(
+            accounts::Reveal {
+                counter,
+                commit_round,
+                commitment,
+                participant,
+            },
+            instruction::Reveal {
+                value: 1,
+                salt: [0; 32],
+            },
+        ),
+        check!(Reveal),
+        &attacks,
+    );
+}
+
+#[test]
+fn forfeit_commit() {
+    let mut svm = Svm::new();
+    let (counter, commit_round, _, commitment) = committed(&mut svm);
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(commit_round));
+    attacks.push(seeds(commit_round));
+    attacks.extend(typed(commitment));
+    attacks.extend([seeds(commitment), seeds(vault)]);
+    run(
+        svm,
+        ix(
+            accounts::ForfeitCommit {
+                counter,
+                commit_round,
+                commitment,
+                vault,
+            },
+            instruction::ForfeitCommit { round: 1 },
+        ),
+        check!(ForfeitCommit),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// finalize.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn set_end_time() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let deadline = pda(&[DEADLINE_SEED, counter.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(deadline));
+    run(
+        svm,
+        ix(
+            accounts::SetEndTime {
+                guard: guard(counter, authority),
+                deadline,
+                system_program: system_program::ID,
+            },
+            instruction::SetEndTime { end_time: 10 },
+        ),
+        check!(SetEndTime),
+        &attacks,
+    );
+}
+
+#[test]
+fn finalize() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let payer = svm.wallet();
+    let (deadline, bump) = bumped(&[DEADLINE_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        deadline,
+        &Deadline {
+            counter,
+            end_time: 0,
+            bump,
+        },
+    );
+    let result = pda(&[RESULT_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(deadline));
+    attacks.extend([
+        seeds(deadline),
+        seeds(result),
+        signer(payer),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::Finalize {
+                counter,
+                deadline,
+                result,
+                payer,
+                system_program: system_program::ID,
+            },
+            instruction::Finalize {},
+        ),
+        check!(Finalize),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// invariants.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn assert_invariants() {
+    let mut svm = Svm::new();
+    let (counter, mirror_mint, _, _) = mirrored(&mut svm);
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend([seeds(vault), owner(mirror_mint), seeds(mirror_mint)]);
+    run(
+        svm,
+        ix(
+            accounts::AssertInvariants {
+                counter,
+                vault,
+                mirror_mint: Some(mirror_mint),
+            },
+            instruction::AssertInvariants {},
+        ),
+        check!(AssertInvariants),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// cpi_guard.rs
+// ----------------------------------------------------------------------------
+
+fn allowed_caller(svm: &mut Svm, counter: Pubkey) -> Pubkey {
+    let program = Pubkey::new_unique();
+    let (allowed_caller, bump) = bumped(&[ALLOWED_CALLER_SEED, counter.as_ref(), program.as_ref()]);
+    svm.set_anchor(
+        allowed_caller,
+        &AllowedCaller {
+            counter,
+            program,
+            bump,
+        },
+    );
+    allowed_caller
+}
+
+#[test]
+fn allow_program() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let program = Pubkey::new_unique();
+    let allowed_caller = pda(&[ALLOWED_CALLER_SEED, counter.as_ref(), program.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(allowed_caller));
+    run(
+        svm,
+        ix(
+            accounts::AllowProgram {
+                guard: guard(counter, authority),
+                allowed_caller,
+                system_program: system_program::ID,
+            },
+            instruction::AllowProgram { program },
+        ),
+        check!(AllowProgram),
+        &attacks,
+    );
+}
+
+#[test]
+fn disallow_program() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let allowed_caller = allowed_caller(&mut svm, counter);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.extend(typed(allowed_caller));
+    attacks.push(seeds(allowed_caller));
+    run(
+        svm,
+        ix(
+            accounts::DisallowProgram {
+                guard: guard(counter, authority),
+                allowed_caller,
+            },
+            instruction::DisallowProgram {},
+        ),
+        check!(DisallowProgram),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_from_program() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let allowed_caller = allowed_caller(&mut svm, counter);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(allowed_caller));
+    attacks.extend([seeds(allowed_caller), address(instructions_sysvar::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::IncrementFromProgram {
+                counter,
+                allowed_caller,
+                instructions: instructions_sysvar::ID,
+            },
+            instruction::IncrementFromProgram {},
+        ),
+        check!(IncrementFromProgram),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// migrate.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn migrate_counter() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let (_, counter) = owned_counter(&mut svm);
+    run(
+        svm,
+        ix(
+            accounts::MigrateCounter {
+                counter,
+                payer,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateCounter {},
+        ),
+        check!(MigrateCounter),
+        &[
+            // An UncheckedAccount, so "owner = crate::ID" does the check,
+            // and the handler checks the discriminator.
+            (Attack::Owner(counter), ErrorCode::ConstraintOwner.into()),
+            signer(payer),
+            program(system_program::ID),
+        ],
+    );
+}
+
+#[test]
+fn migrate_ownerless_counter() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    svm.deploy_counter(Some(authority));
+    let (owner, counter) = owned_counter(&mut svm);
+    let mut attacks = upgrade_authority_attacks(authority);
+    attacks.push((Attack::Owner(counter), ErrorCode::ConstraintOwner.into()));
+    run(
+        svm,
+        ix(
+            accounts::MigrateOwnerlessCounter {
+                counter,
+                authority,
+                program: counter::ID,
+                program_data: program_data_address(&counter::ID),
+                system_program: system_program::ID,
+            },
+            instruction::MigrateOwnerlessCounter { owner },
+        ),
+        check!(MigrateOwnerlessCounter),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// roles.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn set_role() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let member = Pubkey::new_unique();
+    let role = pda(&[ROLE_SEED, counter.as_ref(), member.as_ref()]);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.push(seeds(role));
+    run(
+        svm,
+        ix(
+            accounts::SetRole {
+                guard: guard(counter, authority),
+                role,
+                system_program: system_program::ID,
+            },
+            instruction::SetRole { member, weight: 10 },
+        ),
+        check!(SetRole),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_by_role() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let member = svm.wallet();
+    let (role, bump) = bumped(&[ROLE_SEED, counter.as_ref(), member.as_ref()]);
+    svm.set_anchor(
+        role,
+        &Role {
+            counter,
+            member,
+            weight: 10,
+            bump,
+        },
+    );
+    let mut attacks = typed(counter);
+    attacks.extend(typed(role));
+    attacks.extend([seeds(role), signer(member)]);
+    run(
+        svm,
+        ix(
+            accounts::IncrementByRole {
+                counter,
+                role,
+                member,
+            },
+            instruction::IncrementByRole {},
+        ),
+        check!(IncrementByRole),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// templates.rs
+// ----------------------------------------------------------------------------
+
+fn settings() -> TemplateSettings {
+    TemplateSettings {
+        fee_lamports: 1_000,
+        referral_bps: 500,
+        once_per_slot: true,
+        alarm_threshold: 100,
+        deny_cpi: false,
+    }
+}
+
+// The template "tip jar".
+fn template(svm: &mut Svm) -> Pubkey {
+    let (template, bump) = bumped(&[TEMPLATE_SEED, b"tip jar"]);
+    svm.set_anchor(
+        template,
+        &Template {
+            name: "tip jar".to_string(),
+            settings: settings(),
+            bump,
+        },
+    );
+    template
+}
+
+fn admin_attacks(svm: &mut Svm, config: Pubkey, admin: Pubkey) -> Vec<(Attack, Error)> {
+    let mut attacks = typed(config);
+    attacks.extend([
+        seeds(config),
+        signer(admin),
+        (
+            Attack::Swap(admin, svm.wallet()),
+            CounterError::Unauthorized.into(),
+        ),
+    ]);
+    attacks
+}
+
+#[test]
+fn set_template() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let template = pda(&[TEMPLATE_SEED, b"tip jar"]);
+    let mut attacks = admin_attacks(&mut svm, config, admin);
+    attacks.extend([seeds(template), program(system_program::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::SetTemplate {
+                config,
+                template,
+                admin,
+                system_program: system_program::ID,
+            },
+            instruction::SetTemplate {
+                args: TemplateArgs {
+                    version: TemplateArgs::VERSION,
+                    name: "tip jar".to_string(),
+                    settings: settings(),
+                },
+            },
+        ),
+        check!(SetTemplate),
+        &attacks,
+    );
+}
+
+#[test]
+fn remove_template() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let template = template(&mut svm);
+    let mut attacks = admin_attacks(&mut svm, config, admin);
+    attacks.extend(typed(template));
+    attacks.push(seeds(template));
+    run(
+        svm,
+        ix(
+            accounts::RemoveTemplate {
+                config,
+                template,
+                admin,
+            },
+            instruction::RemoveTemplate {},
+        ),
+        check!(RemoveTemplate),
+        &attacks,
+    );
+}
+
+#[test]
+fn initialize_from_template() {
+    let mut svm = Svm::new();
+    let user = svm.wallet();
+    let template = template(&mut svm);
+    let counter = Pubkey::new_unique();
+    let fees = pda(&[FEES_SEED, counter.as_ref()]);
+    let mut attacks = typed(template);
+    attacks.extend([
+        seeds(template),
+        seeds(fees),
+        signer(user),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeFromTemplate {
+                counter,
+                template,
+                fees: Some(fees),
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeFromTemplate {},
+        ),
+        check!(InitializeFromTemplate),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// getters.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn read_counter() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    run(
+        svm,
+        ix(accounts::ReadCounter { counter }, instruction::GetState {}),
+        check!(ReadCounter),
+        &typed(counter),
+    );
+}
+
+// ----------------------------------------------------------------------------
+// reap.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_reapable() {
+    let mut svm = Svm::new();
+    let user = svm.wallet();
+    let counter = Pubkey::new_unique();
+    let reap_policy = pda(&[REAP_POLICY_SEED, counter.as_ref()]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeReapable {
+                counter,
+                reap_policy,
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeReapable {
+                idle_epochs: 2,
+                beneficiary: user,
+            },
+        ),
+        check!(InitializeReapable),
+        &[
+            seeds(reap_policy),
+            signer(user),
+            program(system_program::ID),
+        ],
+    );
+}
+
+#[test]
+fn reap() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let (reap_policy, bump) = bumped(&[REAP_POLICY_SEED, counter.as_ref()]);
+    let beneficiary = svm.wallet();
+    svm.set_anchor(
+        reap_policy,
+        &ReapPolicy {
+            counter,
+            beneficiary,
+            idle_epochs: 2,
+            seen: [0; 32],
+            seen_epoch: 0,
+            bump,
+        },
+    );
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(reap_policy));
+    attacks.extend([
+        seeds(reap_policy),
+        seeds(vault),
+        (
+            Attack::Swap(beneficiary, svm.wallet()),
+            CounterError::Unauthorized.into(),
+        ),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::Reap {
+                counter,
+                reap_policy,
+                vault,
+                beneficiary,
+            },
+            instruction::Reap {},
+        ),
+        check!(Reap),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// sponsor.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn set_sponsorship() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let sponsorship = pda(&[SPONSORSHIP_SEED]);
+    let mut attacks = admin_attacks(&mut svm, config, admin);
+    attacks.extend([seeds(sponsorship), program(system_program::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::SetSponsorship {
+                config,
+                sponsorship,
+                admin,
+                system_program: system_program::ID,
+            },
+            instruction::SetSponsorship { quota: 3 },
+        ),
+        check!(SetSponsorship),
+        &attacks,
+    );
+}
+
+#[test]
+fn withdraw_treasury() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, 1_000_000_000);
+    let mut attacks = admin_attacks(&mut svm, config, admin);
+    attacks.extend([seeds(treasury), program(system_program::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::WithdrawTreasury {
+                config,
+                treasury,
+                admin,
+                system_program: system_program::ID,
+            },
+            instruction::WithdrawTreasury { amount: 1_000 },
+        ),
+        check!(WithdrawTreasury),
+        &attacks,
+    );
+}
+
+#[test]
+fn initialize_sponsored() {
+    let mut svm = Svm::new();
+    let (sponsorship, bump) = bumped(&[SPONSORSHIP_SEED]);
+    svm.set_anchor(sponsorship, &Sponsorship { quota: 3, bump });
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, 1_000_000_000);
+    let user = svm.wallet();
+    let (sponsored_wallet, bump) = bumped(&[SPONSORED_WALLET_SEED, user.as_ref()]);
+    svm.set_anchor(
+        sponsored_wallet,
+        &SponsoredWallet {
+            wallet: user,
+            count: 1,
+            bump,
+        },
+    );
+    let counter = Pubkey::new_unique();
+    let mut attacks = typed(sponsorship);
+    attacks.extend([
+        seeds(sponsorship),
+        seeds(treasury),
+        seeds(sponsored_wallet),
+        owner(sponsored_wallet),
+        signer(counter),
+        signer(user),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeSponsored {
+                sponsorship,
+                treasury,
+                sponsored_wallet,
+                counter,
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeSponsored {},
+        ),
+        check!(InitializeSponsored),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// gift.rs
+// ----------------------------------------------------------------------------
+
+// An unclaimed gift of "counter", from "creator" to "recipient".
+fn gift(svm: &mut Svm, counter: Pubkey, creator: Pubkey, recipient: Pubkey) -> Pubkey {
+    let (gift, bump) = bumped(&[GIFT_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        gift,
+        &Gift {
+            counter,
+            creator,
+            recipient,
+            expires_at: i64::MAX,
+            bump,
+        },
+    );
+    gift
+}
+
+#[test]
+fn create_gift() {
+    let mut svm = Svm::new();
+    let creator = svm.wallet();
+    let counter = Pubkey::new_unique();
+    let gift = pda(&[GIFT_SEED, counter.as_ref()]);
+    run(
+        svm,
+        ix(
+            accounts::CreateGift {
+                counter,
+                gift,
+                creator,
+                system_program: system_program::ID,
+            },
+            instruction::CreateGift {
+                args: GiftArgs {
+                    version: GiftArgs::VERSION,
+                    count: 42,
+                    recipient: creator,
+                    duration: 3_600,
+                },
+            },
+        ),
+        check!(CreateGift),
+        &[seeds(gift), signer(creator), program(system_program::ID)],
+    );
+}
+
+#[test]
+fn claim_gift() {
+    let mut svm = Svm::new();
+    let (creator, recipient) = (svm.wallet(), svm.wallet());
+    let counter = counter(&mut svm, creator);
+    let gift = gift(&mut svm, counter, creator, recipient);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(gift));
+    let access_log = pda(&[ACCESS_LOG_SEED, counter.as_ref()]);
+    attacks.extend([
+        seeds(gift),
+        seeds(access_log),
+        signer(recipient),
+        (
+            Attack::Swap(recipient, svm.wallet()),
+            CounterError::Unauthorized.into(),
+        ),
+        (
+            Attack::Swap(creator, svm.wallet()),
+            CounterError::Unauthorized.into(),
+        ),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::ClaimGift {
+                counter,
+                gift,
+                access_log,
+                recipient,
+                creator,
+                system_program: system_program::ID,
+            },
+            instruction::ClaimGift {},
+        ),
+        check!(ClaimGift),
+        &attacks,
+    );
+}
+
+#[test]
+fn reclaim_gift() {
+    let mut svm = Svm::new();
+    let (creator, recipient) = (svm.wallet(), svm.wallet());
+    let counter = counter(&mut svm, creator);
+    let gift = gift(&mut svm, counter, creator, recipient);
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(gift));
+    attacks.extend([
+        seeds(gift),
+        seeds(vault),
+        (
+            Attack::Swap(creator, recipient),
+            CounterError::Unauthorized.into(),
+        ),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::ReclaimGift {
+                counter,
+                gift,
+                vault,
+                creator,
+            },
+            instruction::ReclaimGift {},
+        ),
+        check!(ReclaimGift),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// leaderboard.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_leaderboard() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let leaderboard = pda(&[LEADERBOARD_SEED]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeLeaderboard {
+                leaderboard,
+                payer,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeLeaderboard {},
+        ),
+        check!(InitializeLeaderboard),
+        &[
+            seeds(leaderboard),
+            signer(payer),
+            program(system_program::ID),
+        ],
+    );
+}
+
+#[test]
+fn update_leaderboard() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let (leaderboard, bump) = bumped(&[LEADERBOARD_SEED]);
+    let board = Leaderboard {
+        bump,
+        ..bytemuck::Zeroable::zeroed()
+    };
+    svm.set_data(
+        leaderboard,
+        counter::ID,
+        [&Leaderboard::DISCRIMINATOR[..], bytemuck::bytes_of(&board)].concat(),
+    );
+    let mut attacks = typed(leaderboard);
+    attacks.push(seeds(leaderboard));
+    attacks.extend(typed(counter));
+    run(
+        svm,
+        ix(
+            accounts::UpdateLeaderboard {
+                leaderboard,
+                counter,
+            },
+            instruction::UpdateLeaderboard {},
+        ),
+        check!(UpdateLeaderboard),
+        &attacks,
+    );
+}
+
+#[test]
+fn evict_leaderboard_entry() {
+    let mut svm = Svm::new();
+    let (leaderboard, bump) = bumped(&[LEADERBOARD_SEED]);
+    let board = Leaderboard {
+        bump,
+        ..bytemuck::Zeroable::zeroed()
+    };
+    svm.set_data(
+        leaderboard,
+        counter::ID,
+        [&Leaderboard::DISCRIMINATOR[..], bytemuck::bytes_of(&board)].concat(),
+    );
+    let mut attacks = typed(leaderboard);
+    attacks.push(seeds(leaderboard));
+    run(
+        svm,
+        ix(
+            accounts::EvictLeaderboardEntry {
+                leaderboard,
+                counter: fixtures::address("closed"),
+            },
+            instruction::EvictLeaderboardEntry {},
+        ),
+        check!(EvictLeaderboardEntry),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// tags.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn register_tag() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let tag_registry = pda(&[TAG_REGISTRY_SEED]);
+    let mut attacks = admin_attacks(&mut svm, config, admin);
+    attacks.extend([seeds(tag_registry), program(system_program::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::RegisterTag {
+                config,
+                tag_registry,
+                admin,
+                system_program: system_program::ID,
+            },
+            instruction::RegisterTag {
+                tag: encode_tag("games").unwrap(),
+            },
+        ),
+        check!(RegisterTag),
+        &attacks,
+    );
+}
+
+#[test]
+fn initialize_tagged() {
+    let mut svm = Svm::new();
+    let tag = encode_tag("games").unwrap();
+    let (tag_registry, bump) = bumped(&[TAG_REGISTRY_SEED]);
+    svm.set_anchor(
+        tag_registry,
+        &TagRegistry {
+            tags: vec![tag],
+            bump,
+        },
+    );
+    let user = svm.wallet();
+    let mut attacks = typed(tag_registry);
+    attacks.extend([
+        seeds(tag_registry),
+        signer(user),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::InitializeTagged {
+                counter: Pubkey::new_unique(),
+                tag_registry,
+                user,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeTagged { tags: vec![tag] },
+        ),
+        check!(InitializeTagged),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// spill.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn increment_spilling() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let overflow = counter(&mut svm, authority);
+    let counter = Pubkey::new_unique();
+    svm.set_anchor(
+        counter,
+        &Counter {
+            max_count: 1,
+            overflow,
+            ..fixtures::counter_at(authority, 1)
+        },
+    );
+    let other = self::counter(&mut svm, authority);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(overflow));
+    attacks.push((
+        Attack::Swap(overflow, other),
+        CounterError::InvalidSoftCap.into(),
+    ));
+    run(
+        svm,
+        ix(
+            accounts::IncrementSpilling { counter, overflow },
+            instruction::IncrementSpilling {},
+        ),
+        check!(IncrementSpilling),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// twac.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn get_twac() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = Pubkey::new_unique();
+    svm.set_anchor(
+        counter,
+        &Counter {
+            twac_slot: 1,
+            ..fixtures::counter(authority)
+        },
+    );
+    run(
+        svm,
+        ix(accounts::ReadCounter { counter }, instruction::GetTwac {}),
+        check!(ReadCounter),
+        &typed(counter),
+    );
+}
+
+// ----------------------------------------------------------------------------
+// freeze.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn set_freeze_policy() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let freeze_policy = pda(&[FREEZE_POLICY_SEED]);
+    let mut attacks = admin_attacks(&mut svm, config, admin);
+    attacks.extend([seeds(freeze_policy), program(system_program::ID)]);
+    run(
+        svm,
+        ix(
+            accounts::SetFreezePolicy {
+                config,
+                freeze_policy,
+                admin,
+                system_program: system_program::ID,
+            },
+            instruction::SetFreezePolicy {
+                quorum: 3,
+                vote_secs: 60,
+            },
+        ),
+        check!(SetFreezePolicy),
+        &attacks,
+    );
+}
+
+// A badge "holder" earned on "counter".
+fn set_badge(svm: &mut Svm, counter: Pubkey, holder: Pubkey) -> Pubkey {
+    let (badge, bump) = bumped(&[BADGE_SEED, counter.as_ref(), holder.as_ref(), &[0]]);
+    svm.set_anchor(
+        badge,
+        &Badge {
+            counter,
+            owner: holder,
+            tier: 0,
+            earned_at: 1,
+            bump,
+        },
+    );
+    badge
+}
+
+#[test]
+fn vote_freeze() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let (freeze_policy, bump) = bumped(&[FREEZE_POLICY_SEED]);
+    svm.set_anchor(
+        freeze_policy,
+        &FreezePolicy {
+            quorum: 3,
+            vote_secs: 60,
+            bump,
+        },
+    );
+    let voter = svm.wallet();
+    let badge = set_badge(&mut svm, counter, voter);
+    let other = svm.wallet();
+    let other_badge = set_badge(&mut svm, counter, other);
+    let freeze_tally = pda(&[FREEZE_TALLY_SEED, counter.as_ref()]);
+    let freeze_vote = pda(&[FREEZE_VOTE_SEED, counter.as_ref(), voter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend(typed(freeze_policy));
+    attacks.extend(typed(badge));
+    attacks.extend([
+        seeds(freeze_policy),
+        seeds(freeze_tally),
+        seeds(freeze_vote),
+        seeds(badge),
+        // Someone else's badge doesn't let this voter vote.
+        (
+            Attack::Swap(badge, other_badge),
+            ErrorCode::ConstraintSeeds.into(),
+        ),
+        signer(voter),
+        program(system_program::ID),
+    ]);
+    run(
+        svm,
+        ix(
+            accounts::VoteFreeze {
+                counter,
+                freeze_policy,
+                freeze_tally,
+                freeze_vote,
+                badge,
+                voter,
+                system_program: system_program::ID,
+            },
+            instruction::VoteFreeze {},
+        ),
+        check!(VoteFreeze),
+        &attacks,
+    );
+}
+
+#[test]
+fn unfreeze() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    run(
+        svm,
+        ix(
+            accounts::Unfreeze {
+                guard: guard(counter, authority),
+            },
+            instruction::Unfreeze {},
+        ),
+        check!(Unfreeze),
+        &guard_attacks(counter, authority),
+    );
+}
+
+// ----------------------------------------------------------------------------
+// sweep.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn sweep_fees() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let treasury = pda(&[TREASURY_SEED]);
+    run(
+        svm,
+        ix(
+            accounts::SweepFees {
+                treasury,
+                authority,
+                system_program: system_program::ID,
+            },
+            instruction::SweepFees {},
+        ),
+        check!(SweepFees),
+        &[
+            seeds(treasury),
+            signer(authority),
+            program(system_program::ID),
+        ],
+    );
+}
+
+// ----------------------------------------------------------------------------
+// funded.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn initialize_funded() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let counter = Pubkey::new_unique();
+    run(
+        svm,
+        ix(
+            accounts::InitializeFunded {
+                counter,
+                authority: Pubkey::new_unique(),
+                payer,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeFunded {},
+        ),
+        check!(InitializeFunded),
+        &[signer(payer), program(system_program::ID)],
+    );
+}
+
+// ----------------------------------------------------------------------------
+// preview.rs
+// ----------------------------------------------------------------------------
+
+#[test]
+fn preview_increment() {
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let payer = svm.wallet();
+    let (fees, bump) = bumped(&[FEES_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        fees,
+        &CounterFees {
+            counter,
+            fee_lamports: 10,
+            referral_bps: 100,
+            bump,
+        },
+    );
+    let (fee_schedule, bump) = bumped(&[FEE_SCHEDULE_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        fee_schedule,
+        &FeeSchedule {
+            counter,
+            tiers: vec![FeeTier {
+                from: 0,
+                fee_lamports: 10,
+            }],
+            day: 0,
+            day_volume: 0,
+            bump,
+        },
+    );
+    let (fee_exemption, bump) = bumped(&[FEE_EXEMPTION_SEED, counter.as_ref(), payer.as_ref()]);
+    svm.set_anchor(
+        fee_exemption,
+        &FeeExemption {
+            counter,
+            wallet: payer,
+            bump,
+        },
+    );
+
+    let mut attacks = typed(counter);
+    attacks.extend(typed(fees));
+    attacks.push(seeds(fees));
+    attacks.extend(typed(fee_schedule));
+    attacks.push(seeds(fee_schedule));
+    attacks.extend(typed(fee_exemption));
+    attacks.push(seeds(fee_exemption));
+    run(
+        svm,
+        ix(
+            accounts::PreviewIncrement {
+                counter,
+                payer,
+                fees: Some(fees),
+                fee_schedule: Some(fee_schedule),
+                fee_exemption: Some(fee_exemption),
+            },
+            instruction::PreviewIncrement { referrer: None },
+        ),
+        check!(PreviewIncrement),
+        &attacks,
+    );
+}
+
+// ----------------------------------------------------------------------------
+// group.rs
+// ----------------------------------------------------------------------------
+
+// A group run by "authority", and a counter of theirs in it.
+fn grouped(svm: &mut Svm, authority: Pubkey) -> (Pubkey, Pubkey) {
+    let group = Pubkey::new_unique();
+    svm.set_anchor(
+        group,
+        &Group {
+            authority,
+            paused: false,
+            fee_lamports: 10,
+            deny_cpi: false,
+            members: 1,
+        },
+    );
+    let counter = Pubkey::new_unique();
+    svm.set_anchor(
+        counter,
+        &Counter {
+            group,
+            ..fixtures::counter(authority)
+        },
+    );
+    (group, counter)
+}
+
+#[test]
+fn initialize_group() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    run(
+        svm,
+        ix(
+            accounts::InitializeGroup {
+                group: Pubkey::new_unique(),
+                authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeGroup {},
+        ),
+        check!(InitializeGroup),
+        &[signer(authority), program(system_program::ID)],
+    );
+}
+
+#[test]
+fn update_group() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let (group, _) = grouped(&mut svm, authority);
+    let mut attacks = typed(group);
+    attacks.push(signer(authority));
+    run(
+        svm,
+        ix(
+            accounts::UpdateGroup { group, authority },
+            instruction::UpdateGroup {
+                args: GroupArgs {
+                    version: GroupArgs::VERSION,
+                    paused: true,
+                    fee_lamports: 0,
+                    deny_cpi: false,
+                },
+            },
+        ),
+        check!(UpdateGroup),
+        &attacks,
+    );
+}
+
+#[test]
+fn join_group() {
+    let mut svm = Svm::new();
+    let (authority, counter) = owned_counter(&mut svm);
+    let (group, _) = grouped(&mut svm, authority);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.extend(typed(group));
+    run(
+        svm,
+        ix(
+            accounts::JoinGroup {
+                guard: guard(counter, authority),
+                group,
+            },
+            instruction::JoinGroup {},
+        ),
+        check!(JoinGroup),
+        &attacks,
+    );
+}
+
+#[test]
+fn leave_group() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let (group, counter) = grouped(&mut svm, authority);
+    let mut attacks = guard_attacks(counter, authority);
+    attacks.extend(typed(group));
+    run(
+        svm,
+        ix(
+            accounts::LeaveGroup {
+                guard: guard(counter, authority),
+                group,
+            },
+            instruction::LeaveGroup {},
+        ),
+        check!(LeaveGroup),
+        &attacks,
+    );
+}
+
+#[test]
+fn increment_grouped() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let (group, counter) = grouped(&mut svm, authority);
+    let payer = svm.wallet();
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let mut attacks = typed(counter);
+    attacks.extend([seeds(vault), signer(payer), program(system_program::ID)]);
+    attacks.extend(typed(group));
+    run(
+        svm,
+        ix(
+            accounts::IncrementGrouped {
+                payment: accounts::FeePayment {
+                    counter,
+                    vault,
+                    payer,
+                    system_program: system_program::ID,
+                },
+                group,
+            },
+            instruction::IncrementGrouped {},
+        ),
+        check!(IncrementGrouped),
+        &attacks,
+    );
+}
+
+#[test]
+fn unauthorized_signers_are_still_refused() {
+    // Not one of the five attacks, but the reason most of them matter: a
+    // valid signature from the wrong wallet.
+    let mut svm = Svm::new();
+    let (_, counter) = owned_counter(&mut svm);
+    let stranger = svm.wallet();
+    let ix = ix(
+        accounts::ResetCount {
+            guard: guard(counter, stranger),
+        },
+        instruction::ResetCount {},
+    );
+    let check: Check = check!(ResetCount);
+    assert_eq!(
+        check(&mut svm, &ix).err(),
+        Some(CounterError::Unauthorized.into())
+    );
+}
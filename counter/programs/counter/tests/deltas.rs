@@ -0,0 +1,127 @@
+// The delta feed (see deltas.rs): the ring by itself, then run through the
+// program with the clock moved by TimeMachine.
+mod harness;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, DeltaFeed, DeltasSince, ACCESS_LOG_SEED, LEASE_SEED,
+    RECENT_DELTAS,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::time::TimeMachine;
+use harness::{ix, Svm};
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn set_delta_feed(counter: Pubkey, authority: Pubkey, enabled: bool) -> Instruction {
+    ix(
+        accounts::UpdateCounter {
+            counter,
+            lease: pda(&[LEASE_SEED, counter.as_ref()]),
+            access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetDeltaFeed { enabled },
+    )
+}
+
+fn increment(counter: Pubkey) -> Instruction {
+    ix(accounts::Increment { counter }, instruction::Increment {})
+}
+
+// A counter at 10 whose feed was turned on in slot 100.
+fn feeding() -> Counter {
+    Counter {
+        delta_feed: DeltaFeed {
+            base_slot: 100,
+            slot: 100,
+            ..DeltaFeed::default()
+        },
+        ..fixtures::counter_at(Pubkey::default(), 10)
+    }
+}
+
+#[test]
+fn keeps_the_latest_changes() {
+    let mut counter = feeding();
+    counter.add(3).unwrap();
+    counter.delta_feed.slot = 101;
+    counter.subtract(1).unwrap();
+    // Same slot: merged into the entry before.
+    counter.add(4).unwrap();
+    counter.delta_feed.slot = 105;
+    assert_eq!(counter.reset(), 16);
+    assert_eq!(
+        counter.delta_feed.since(0),
+        DeltasSince {
+            deltas: vec![(100, 3), (101, 3), (105, -16)],
+            missed: 0,
+        }
+    );
+    assert_eq!(counter.delta_feed.since(2).deltas, vec![(105, -16)]);
+    assert_eq!(counter.delta_feed.since(3).deltas, vec![]);
+
+    // Older entries are overwritten, and a poller that fell behind is told
+    // how many it missed.
+    for slot in 106..106 + RECENT_DELTAS as u64 {
+        counter.delta_feed.slot = slot;
+        counter.add(1).unwrap();
+    }
+    let since = counter.delta_feed.since(1);
+    assert_eq!(since.missed, 2);
+    assert_eq!(since.deltas.len(), RECENT_DELTAS);
+    assert_eq!(since.deltas[0], (106, 1));
+
+    // Changes too big for an entry are recorded as the biggest one.
+    counter.delta_feed.slot = 200;
+    counter.add(u64::from(u32::MAX)).unwrap();
+    assert_eq!(counter.delta_feed.since(19).deltas, vec![(200, i32::MAX)]);
+}
+
+#[test]
+fn counters_without_a_feed_dont_record() {
+    let mut counter = fixtures::counter_at(Pubkey::default(), 4);
+    counter.add(2).unwrap();
+    counter.reset();
+    assert_eq!(counter.delta_feed.recorded, 0);
+    // And don't read the clock, which isn't there outside a program.
+    assert!(counter.begin_change().is_ok());
+}
+
+#[test]
+fn increments_are_fed_with_their_slot() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter_at(authority, 2), CURRENT);
+
+    assert_eq!(
+        svm.process(&set_delta_feed(counter, authority, true)),
+        Ok(())
+    );
+    let base_slot = svm.load::<Counter>(&counter).unwrap().delta_feed.base_slot;
+    assert_ne!(base_slot, 0);
+
+    assert_eq!(svm.process(&increment(counter)), Ok(()));
+    assert_eq!(svm.process(&increment(counter)), Ok(()));
+    svm.advance_slots(5);
+    assert_eq!(svm.process(&increment(counter)), Ok(()));
+    let feed = svm.load::<Counter>(&counter).unwrap().delta_feed;
+    assert_eq!(
+        feed.since(0).deltas,
+        vec![(base_slot, 2), (base_slot + 5, 1)]
+    );
+
+    // Off clears it.
+    assert_eq!(
+        svm.process(&set_delta_feed(counter, authority, false)),
+        Ok(())
+    );
+    let feed = svm.load::<Counter>(&counter).unwrap().delta_feed;
+    assert!(!feed.is_on());
+    assert_eq!(feed.recorded, 0);
+}
@@ -0,0 +1,34 @@
+// The compression program's instructions are built by hand, so check the
+// discriminators we hardcoded against Anchor's formula.
+use anchor_lang::solana_program::hash::hash;
+use counter::{APPEND_DISCRIMINATOR, INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR};
+
+fn anchor_discriminator(name: &str) -> [u8; 8] {
+    hash(format!("global:{name}").as_bytes()).to_bytes()[..8]
+        .try_into()
+        .unwrap()
+}
+
+#[test]
+fn compression_discriminators_match_anchor() {
+    assert_eq!(
+        INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR,
+        anchor_discriminator("init_empty_merkle_tree")
+    );
+    assert_eq!(APPEND_DISCRIMINATOR, anchor_discriminator("append"));
+}
+
+#[test]
+fn tree_logged_counters_only_allow_logged_increments() {
+    use anchor_lang::error::Error;
+    use counter::{Counter, CounterError, Route};
+
+    let mut counter = Counter::default();
+    counter.set_flag(Counter::TREE_LOGGED, true);
+    assert_eq!(
+        counter.check_modes().err(),
+        Some(Error::from(CounterError::TreeLogRequired))
+    );
+    // increment_logged's check.
+    assert!(counter.check_route(Route::TreeLogged).is_ok());
+}
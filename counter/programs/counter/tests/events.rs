@@ -0,0 +1,9 @@
+use counter::Counter;
+
+#[test]
+fn event_numbers_start_at_one_without_gaps() {
+    let mut counter = Counter::default();
+    let numbers: Vec<u64> = (0..4).map(|_| counter.next_event_seq()).collect();
+    assert_eq!(numbers, [1, 2, 3, 4]);
+    assert_eq!(counter.event_seq, 4);
+}
@@ -0,0 +1,296 @@
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, increment_fee, instruction, Counter, CounterError, CounterFees, FeeSchedule,
+    FeeScheduleArgs, FeeTier, ReferrerStats, ACCESS_LOG_SEED, FEES_ENABLED, FEES_SEED,
+    FEE_SCHEDULE_SEED, REFERRAL_VAULT_SEED, REFERRER_STATS_SEED, SECONDS_PER_DAY, SECONDS_PER_HOUR,
+    STATS_SEED, VAULT_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::time::TimeMachine;
+use harness::{ix, Svm};
+
+fn fees(fee_lamports: u64, referral_bps: u16) -> CounterFees {
+    CounterFees {
+        counter: Default::default(),
+        fee_lamports,
+        referral_bps,
+        bump: 0,
+    }
+}
+
+#[test]
+fn referrers_get_their_share_rounded_down() {
+    assert_eq!(fees(1_000, 2_500).split(true), (750, 250));
+    assert_eq!(fees(1_000, 2_500).split(false), (1_000, 0));
+    assert_eq!(fees(999, 1).split(true), (999, 0));
+    assert_eq!(fees(u64::MAX, 10_000).split(true), (0, u64::MAX));
+}
+
+#[test]
+fn fee_counters_only_increment_through_increment_paid() {
+    let mut counter = Counter::default();
+    counter.set_flag(Counter::FEE_REQUIRED, true);
+    assert_eq!(
+        counter.check_modes().err(),
+        Some(Error::from(CounterError::FeeRequired))
+    );
+    assert!(counter.check_limits().is_ok());
+}
+
+#[test]
+fn only_one_route_at_a_time() {
+    let mut counter = Counter::default();
+    counter.set_flag(Counter::TOKEN_MIRRORED, true);
+    let conflict = Some(Error::from(CounterError::ConflictingModes));
+    assert_eq!(
+        counter.check_route_free(Counter::FEE_REQUIRED).err(),
+        conflict
+    );
+    assert_eq!(
+        counter.check_route_free(Counter::TREE_LOGGED).err(),
+        conflict
+    );
+    assert!(counter.check_route_free(Counter::TOKEN_MIRRORED).is_ok());
+}
+
+fn tier(from: u64, fee_lamports: u64) -> FeeTier {
+    FeeTier { from, fee_lamports }
+}
+
+#[test]
+fn schedules_charge_by_the_days_volume() {
+    let mut schedule = FeeSchedule {
+        counter: Default::default(),
+        tiers: vec![tier(0, 0), tier(2, 100), tier(3, 500)],
+        day: 0,
+        day_volume: 0,
+        bump: 0,
+    };
+    let day = 20_000 * SECONDS_PER_DAY;
+    let fees: Vec<u64> = (0..5).map(|_| schedule.charge(day)).collect();
+    assert_eq!(fees, [0, 0, 100, 500, 500]);
+    assert_eq!(schedule.top_fee(), 500);
+
+    // A new day starts from the first tier again.
+    assert_eq!(schedule.charge(day + SECONDS_PER_DAY), 0);
+    assert_eq!(schedule.day_volume, 1);
+}
+
+#[test]
+fn exempt_payers_arent_counted_in_the_days_volume() {
+    let mut schedule = FeeSchedule {
+        counter: Default::default(),
+        tiers: vec![tier(0, 0), tier(1, 100)],
+        day: 0,
+        day_volume: 0,
+        bump: 0,
+    };
+    let exempt = increment_fee(&fees(100, 0), Some(&mut schedule), true, false);
+    assert_eq!(exempt.unwrap(), (0, 0));
+    assert_eq!(schedule.day_volume, 0);
+}
+
+// The plain build charges; a "--features localnet" one doesn't.
+#[test]
+fn localnet_builds_charge_nothing() {
+    let charged = increment_fee(&fees(1_000, 2_500), None, false, true).unwrap();
+    let expected = if FEES_ENABLED { (750, 250) } else { (0, 0) };
+    assert_eq!(charged, expected);
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn set_fee_schedule(counter: Pubkey, authority: Pubkey, tiers: Vec<FeeTier>) -> Instruction {
+    ix(
+        accounts::SetFeeSchedule {
+            guard: accounts::AuthorityGuard {
+                counter,
+                access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+                authority,
+                system_program: system_program::ID,
+            },
+            fees: pda(&[FEES_SEED, counter.as_ref()]),
+            fee_schedule: pda(&[FEE_SCHEDULE_SEED, counter.as_ref()]),
+            system_program: system_program::ID,
+        },
+        instruction::SetFeeSchedule {
+            args: FeeScheduleArgs {
+                version: FeeScheduleArgs::VERSION,
+                tiers,
+                referral_bps: 0,
+            },
+        },
+    )
+}
+
+fn increment_paid(svm: &Svm, counter: Pubkey, payer: Pubkey, scheduled: bool) -> Instruction {
+    referred(svm, counter, payer, scheduled, None)
+}
+
+fn referred(
+    svm: &Svm,
+    counter: Pubkey,
+    payer: Pubkey,
+    scheduled: bool,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let hour = svm
+        .clock()
+        .unix_timestamp
+        .div_euclid(SECONDS_PER_HOUR)
+        .to_le_bytes();
+    ix(
+        accounts::IncrementPaid {
+            payment: accounts::FeePayment {
+                counter,
+                vault: pda(&[VAULT_SEED, counter.as_ref()]),
+                payer,
+                system_program: system_program::ID,
+            },
+            fees: pda(&[FEES_SEED, counter.as_ref()]),
+            referrer_stats: referrer
+                .map(|referrer| pda(&[REFERRER_STATS_SEED, counter.as_ref(), referrer.as_ref()])),
+            referral_vault: referrer.map(|referrer| pda(&[REFERRAL_VAULT_SEED, referrer.as_ref()])),
+            fee_exemption: None,
+            stats_bucket: pda(&[STATS_SEED, counter.as_ref(), &hour]),
+            system_program: system_program::ID,
+            fee_schedule: scheduled.then(|| pda(&[FEE_SCHEDULE_SEED, counter.as_ref()])),
+        },
+        instruction::IncrementPaid { referrer },
+    )
+}
+
+#[test]
+fn increment_paid_follows_the_schedule() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+    let bad = vec![tier(1, 0)];
+    assert_eq!(
+        svm.process(&set_fee_schedule(counter, authority, bad)),
+        Err(Error::from(CounterError::InvalidFeeSchedule).into())
+    );
+    let tiers = vec![tier(0, 0), tier(1, 1_000)];
+    assert_eq!(
+        svm.process(&set_fee_schedule(counter, authority, tiers)),
+        Ok(())
+    );
+    let fees = svm.load::<CounterFees>(&pda(&[FEES_SEED, counter.as_ref()]));
+    assert_eq!(fees.unwrap().fee_lamports, 1_000);
+
+    let payer = svm.wallet();
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let paid = |svm: &Svm| svm.get(&vault).map_or(0, |v| v.lamports);
+    let ix = increment_paid(&svm, counter, payer, true);
+    assert_eq!(svm.process(&ix), Ok(()));
+    assert_eq!(paid(&svm), 0);
+    // The first fee creates the vault, topped up to rent-exempt.
+    let rent = Rent::default().minimum_balance(0);
+    assert_eq!(svm.process(&ix), Ok(()));
+    assert_eq!(paid(&svm), rent);
+
+    // Leaving the schedule out costs the top fee.
+    svm.advance_time(SECONDS_PER_DAY);
+    let ix = increment_paid(&svm, counter, payer, false);
+    assert_eq!(svm.process(&ix), Ok(()));
+    assert_eq!(paid(&svm), rent + 1_000);
+    let ix = increment_paid(&svm, counter, payer, true);
+    assert_eq!(svm.process(&ix), Ok(()));
+    assert_eq!(paid(&svm), rent + 1_000);
+}
+
+// A counter that charges "fees" through increment_paid.
+fn charging_counter(svm: &mut Svm, fees: CounterFees) -> Pubkey {
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    let (fees_address, bump) =
+        Pubkey::find_program_address(&[FEES_SEED, counter.as_ref()], &counter::ID);
+    svm.set_anchor(
+        fees_address,
+        &CounterFees {
+            counter,
+            bump,
+            ..fees
+        },
+    );
+    let mut charging = fixtures::counter(authority);
+    charging.set_flag(Counter::FEE_REQUIRED, true);
+    svm.set_counter(counter, &charging, CURRENT);
+    counter
+}
+
+// The harness doesn't hold accounts to rent, so this checks by hand that
+// the vaults a small fee lands in come out rent-exempt, as a cluster would
+// require.
+#[test]
+fn small_fees_leave_both_vaults_rent_exempt() {
+    let mut svm = Svm::new();
+    let counter = charging_counter(&mut svm, fees(1_000, 2_500));
+
+    let (payer, referrer) = (svm.wallet(), svm.wallet());
+    let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+    let referral_vault = pda(&[REFERRAL_VAULT_SEED, referrer.as_ref()]);
+    let rent = Rent::default().minimum_balance(0);
+    let ix = referred(&svm, counter, payer, false, Some(referrer));
+    assert_eq!(svm.process(&ix), Ok(()));
+    for address in [vault, referral_vault] {
+        assert_eq!(svm.get(&address).unwrap().lamports, rent);
+    }
+
+    // Once they exist, fees go in as they are.
+    assert_eq!(svm.process(&ix), Ok(()));
+    assert_eq!(svm.get(&vault).unwrap().lamports, rent + 750);
+    assert_eq!(svm.get(&referral_vault).unwrap().lamports, rent + 250);
+    let stats = pda(&[REFERRER_STATS_SEED, counter.as_ref(), referrer.as_ref()]);
+    let stats = svm.load::<ReferrerStats>(&stats).unwrap();
+    assert_eq!((stats.increments, stats.earned), (2, 500));
+}
+
+#[test]
+fn payers_cant_refer_themselves() {
+    let mut svm = Svm::new();
+    let counter = charging_counter(&mut svm, fees(1_000, 2_500));
+
+    let payer = svm.wallet();
+    assert_eq!(
+        svm.process(&referred(&svm, counter, payer, false, Some(payer))),
+        Err(Error::from(CounterError::SelfReferral).into())
+    );
+}
+
+#[test]
+fn increment_paid_needs_a_counter_that_charges() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    let (fees_address, bump) =
+        Pubkey::find_program_address(&[FEES_SEED, counter.as_ref()], &counter::ID);
+    svm.set_anchor(
+        fees_address,
+        &CounterFees {
+            counter,
+            bump,
+            ..fees(1_000, 0)
+        },
+    );
+    // set_increment_fee(0) leaves the fees account behind, with the
+    // flag off.
+    svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+    let ix = increment_paid(&svm, counter, authority, false);
+    assert_eq!(
+        svm.process(&ix),
+        Err(Error::from(CounterError::NotFeeRequired).into())
+    );
+
+    let mut charging = fixtures::counter(authority);
+    charging.set_flag(Counter::FEE_REQUIRED, true);
+    svm.set_counter(counter, &charging, CURRENT);
+    assert_eq!(svm.process(&ix), Ok(()));
+}
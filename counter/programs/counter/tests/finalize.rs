@@ -0,0 +1,14 @@
+use anchor_lang::error::Error;
+use counter::{Counter, CounterError};
+
+#[test]
+fn finalized_counters_stay_frozen() {
+    let mut counter = Counter {
+        finalized: true,
+        ..Default::default()
+    };
+    let finalized = Some(Error::from(CounterError::Finalized));
+    assert_eq!(counter.check_modes().err(), finalized);
+    assert_eq!(counter.check_active().err(), finalized);
+    assert_eq!(counter.check_limits().err(), finalized);
+}
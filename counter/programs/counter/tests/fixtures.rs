@@ -0,0 +1,62 @@
+// The fixtures in harness/fixtures.rs have to be what the program itself
+// would have written, or tests built on them prove nothing.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use counter::{counter_layout, Config, Counter, CounterError, COUNTER_LAYOUTS};
+use harness::fixtures::{self, CURRENT};
+use harness::Svm;
+
+#[test]
+fn the_same_fixture_is_the_same_bytes_at_the_same_address() {
+    let authority = fixtures::address("authority");
+    assert_eq!(authority, fixtures::address("authority"));
+    assert_ne!(authority, fixtures::address("someone else"));
+
+    let (mut first, mut second) = (Svm::new(), Svm::new());
+    let counter = fixtures::address("counter");
+    first.set_counter(counter, &fixtures::counter_at(authority, 5), CURRENT);
+    second.set_counter(counter, &fixtures::counter_at(authority, 5), CURRENT);
+    assert_eq!(first.get(&counter), second.get(&counter));
+}
+
+#[test]
+fn older_layouts_are_recognized_by_the_program() {
+    let counter = fixtures::counter_at(fixtures::address("authority"), 5);
+    for layout in 0..COUNTER_LAYOUTS.len() {
+        let data = fixtures::counter_data(&counter, layout);
+        assert_eq!(counter_layout(&data), Some(layout));
+    }
+
+    // Only the current one loads as a Counter; the rest need migrating.
+    let mut svm = Svm::new();
+    let (old, new) = (fixtures::address("old"), fixtures::address("new"));
+    svm.set_counter(old, &counter, CURRENT - 1);
+    svm.set_counter(new, &counter, CURRENT);
+    assert!(svm.load::<Counter>(&old).is_none());
+    assert_eq!(svm.load::<Counter>(&new).unwrap().count, 5);
+}
+
+#[test]
+fn edge_states_load_as_what_they_claim_to_be() {
+    let mut svm = Svm::new();
+    let admin = fixtures::address("admin");
+    let config = svm.set_config(admin);
+    let loaded: Config = svm.load(&config).unwrap();
+    assert_eq!(loaded.admin, admin);
+    assert_eq!(
+        Pubkey::create_program_address(&[counter::CONFIG_SEED, &[loaded.bump]], &counter::ID),
+        Ok(config)
+    );
+
+    let address = fixtures::address("counter");
+    svm.set_counter(address, &fixtures::near_overflow(admin), CURRENT);
+    let mut counter: Counter = svm.load(&address).unwrap();
+    counter.increment().unwrap();
+    assert_eq!(counter.count, u64::MAX);
+    assert_eq!(
+        counter.increment().err(),
+        Some(Error::from(CounterError::Overflow))
+    );
+}
@@ -0,0 +1,228 @@
+// Freeze votes (see freeze.rs), run through the program: badge holders
+// freeze a counter once enough of them vote within a round, and its
+// owner unfreezes it.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Badge, Counter, CounterError, FreezeTally, Lease, ACCESS_LOG_SEED,
+    BADGE_SEED, FREEZE_POLICY_SEED, FREEZE_TALLY_SEED, FREEZE_VOTE_SEED, LEASE_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::time::TimeMachine;
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn set_freeze_policy(admin: Pubkey, quorum: u16) -> Instruction {
+    ix(
+        accounts::SetFreezePolicy {
+            config: pda(&[counter::CONFIG_SEED]),
+            freeze_policy: pda(&[FREEZE_POLICY_SEED]),
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetFreezePolicy {
+            quorum,
+            vote_secs: 60,
+        },
+    )
+}
+
+fn vote_freeze(counter: Pubkey, voter: Pubkey) -> Instruction {
+    ix(
+        accounts::VoteFreeze {
+            counter,
+            freeze_policy: pda(&[FREEZE_POLICY_SEED]),
+            freeze_tally: pda(&[FREEZE_TALLY_SEED, counter.as_ref()]),
+            freeze_vote: pda(&[FREEZE_VOTE_SEED, counter.as_ref(), voter.as_ref()]),
+            badge: pda(&[BADGE_SEED, counter.as_ref(), voter.as_ref(), &[0]]),
+            voter,
+            system_program: system_program::ID,
+        },
+        instruction::VoteFreeze {},
+    )
+}
+
+fn unfreeze(counter: Pubkey, authority: Pubkey) -> Instruction {
+    ix(
+        accounts::Unfreeze {
+            guard: accounts::AuthorityGuard {
+                counter,
+                access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+                authority,
+                system_program: system_program::ID,
+            },
+        },
+        instruction::Unfreeze {},
+    )
+}
+
+fn increment(counter: Pubkey) -> Instruction {
+    ix(accounts::Increment { counter }, instruction::Increment {})
+}
+
+// A counter, its authority, and three contributors holding its tier-0
+// badge, with a freeze policy of "quorum" votes in 60 seconds.
+fn setup(svm: &mut Svm, quorum: u16) -> (Pubkey, Pubkey, [Pubkey; 3]) {
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    assert_eq!(svm.process(&set_freeze_policy(admin, quorum)), Ok(()));
+
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+    let voters = [svm.wallet(), svm.wallet(), svm.wallet()];
+    for voter in voters {
+        let (badge, bump) = Pubkey::find_program_address(
+            &[BADGE_SEED, counter.as_ref(), voter.as_ref(), &[0]],
+            &counter::ID,
+        );
+        svm.set_anchor(
+            badge,
+            &Badge {
+                counter,
+                owner: voter,
+                tier: 0,
+                earned_at: 1,
+                bump,
+            },
+        );
+    }
+    (counter, authority, voters)
+}
+
+fn frozen(svm: &Svm, counter: Pubkey) -> bool {
+    svm.load::<Counter>(&counter).unwrap().frozen
+}
+
+#[test]
+fn rounds_expire_or_end() {
+    let mut tally = FreezeTally::default();
+    tally.refresh(100, 60);
+    tally.votes = 2;
+    assert_eq!((tally.round, tally.opened_at), (1, 100));
+
+    // Still open: nothing changes.
+    tally.refresh(159, 60);
+    assert_eq!((tally.round, tally.votes), (1, 2));
+
+    // Over: a new round, from 0.
+    tally.refresh(160, 60);
+    assert_eq!((tally.round, tally.opened_at, tally.votes), (2, 160, 0));
+
+    // Closed by a freeze: the next vote opens a new one, however soon.
+    tally.end_round();
+    tally.refresh(161, 60);
+    assert_eq!((tally.round, tally.opened_at), (3, 161));
+}
+
+#[test]
+fn a_quorum_of_badge_holders_freezes_the_counter() {
+    let mut svm = Svm::new();
+    let (counter, authority, [first, second, third]) = setup(&mut svm, 2);
+
+    assert_eq!(svm.process(&vote_freeze(counter, first)), Ok(()));
+    assert_eq!(
+        svm.process(&vote_freeze(counter, first)),
+        Err(error(CounterError::AlreadyVoted))
+    );
+    assert!(!frozen(&svm, counter));
+    assert_eq!(svm.process(&vote_freeze(counter, second)), Ok(()));
+    assert!(frozen(&svm, counter));
+
+    // Nothing changes the count until the authority has reviewed it.
+    assert_eq!(
+        svm.process(&increment(counter)),
+        Err(error(CounterError::Frozen))
+    );
+    assert_eq!(
+        svm.process(&vote_freeze(counter, third)),
+        Err(error(CounterError::Frozen))
+    );
+    let stranger = svm.wallet();
+    assert_eq!(
+        svm.process(&unfreeze(counter, stranger)),
+        Err(error(CounterError::Unauthorized))
+    );
+    assert_eq!(svm.process(&unfreeze(counter, authority)), Ok(()));
+    assert_eq!(svm.process(&increment(counter)), Ok(()));
+    assert_eq!(
+        svm.process(&unfreeze(counter, authority)),
+        Err(error(CounterError::NotFrozen))
+    );
+
+    // The votes that froze it were used up: it takes a new quorum.
+    assert_eq!(svm.process(&vote_freeze(counter, third)), Ok(()));
+    assert!(!frozen(&svm, counter));
+}
+
+#[test]
+fn renters_cant_unfreeze() {
+    let mut svm = Svm::new();
+    let (counter, authority, [first, second, _]) = setup(&mut svm, 2);
+    let renter = svm.wallet();
+    let (lease, bump) = Pubkey::find_program_address(&[LEASE_SEED, counter.as_ref()], &counter::ID);
+    svm.set_anchor(
+        lease,
+        &Lease {
+            counter,
+            renter,
+            expires_at: svm.clock().unix_timestamp + 3_600,
+            price: 0,
+            bump,
+        },
+    );
+    assert_eq!(svm.process(&vote_freeze(counter, first)), Ok(()));
+    assert_eq!(svm.process(&vote_freeze(counter, second)), Ok(()));
+
+    // A lease hands over the counter's settings, but lifting a freeze
+    // stays with the owner.
+    assert_eq!(
+        svm.process(&unfreeze(counter, renter)),
+        Err(error(CounterError::Unauthorized))
+    );
+    assert!(frozen(&svm, counter));
+    assert_eq!(svm.process(&unfreeze(counter, authority)), Ok(()));
+}
+
+#[test]
+fn votes_expire_with_their_round() {
+    let mut svm = Svm::new();
+    let (counter, _, [first, second, _]) = setup(&mut svm, 2);
+
+    assert_eq!(svm.process(&vote_freeze(counter, first)), Ok(()));
+    svm.advance_time(61);
+    assert_eq!(svm.process(&vote_freeze(counter, second)), Ok(()));
+    assert!(!frozen(&svm, counter));
+
+    // A new round, so the first voter can vote again.
+    assert_eq!(svm.process(&vote_freeze(counter, first)), Ok(()));
+    assert!(frozen(&svm, counter));
+}
+
+#[test]
+fn only_badge_holders_vote_and_only_with_a_quorum() {
+    let mut svm = Svm::new();
+    let (counter, _, [first, ..]) = setup(&mut svm, 0);
+    assert_eq!(
+        svm.process(&vote_freeze(counter, first)),
+        Err(error(CounterError::FreezeVotingOff))
+    );
+
+    let mut svm = Svm::new();
+    let (counter, _, _) = setup(&mut svm, 2);
+    let stranger = svm.wallet();
+    assert_eq!(
+        svm.process(&vote_freeze(counter, stranger)),
+        Err(error(ErrorCode::AccountNotInitialized))
+    );
+}
@@ -0,0 +1,93 @@
+// Funded counters (see funded.rs), run through the program: the payer
+// pays the rent and the authority, who never signed, owns the counter.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{accounts, instruction, Counter, CounterError, ACCESS_LOG_SEED, LEASE_SEED};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn initialize_funded(counter: Pubkey, authority: Pubkey, payer: Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeFunded {
+            counter,
+            authority,
+            payer,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeFunded {},
+    )
+}
+
+fn set_delta_feed(counter: Pubkey, authority: Pubkey) -> Instruction {
+    ix(
+        accounts::UpdateCounter {
+            counter,
+            lease: pda(&[LEASE_SEED, counter.as_ref()]),
+            access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetDeltaFeed { enabled: true },
+    )
+}
+
+fn lamports(svm: &Svm, address: &Pubkey) -> u64 {
+    svm.get(address).map_or(0, |account| account.lamports)
+}
+
+#[test]
+fn the_payer_pays_and_the_authority_owns() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let authority = svm.wallet();
+    let counter = Pubkey::new_unique();
+    let (paid, owned) = (lamports(&svm, &payer), lamports(&svm, &authority));
+
+    assert_eq!(
+        svm.process(&initialize_funded(counter, authority, payer)),
+        Ok(())
+    );
+    let rent = lamports(&svm, &counter);
+    assert!(rent > 0);
+    assert_eq!(lamports(&svm, &payer), paid - rent);
+    assert_eq!(lamports(&svm, &authority), owned);
+
+    let created = svm.load::<Counter>(&counter).unwrap();
+    assert_eq!((created.authority, created.count), (authority, 0));
+
+    // Paying for it gives the payer no say over it.
+    assert_eq!(
+        svm.process(&set_delta_feed(counter, payer)),
+        Err(Error::from(CounterError::Unauthorized).into())
+    );
+    assert_eq!(svm.process(&set_delta_feed(counter, authority)), Ok(()));
+}
+
+#[test]
+fn only_authorities_that_could_sign() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    let other = fixtures::address("other");
+    svm.set_counter(other, &fixtures::counter(payer), CURRENT);
+
+    // Programs, and an account of this program.
+    for authority in [counter::ID, system_program::ID, other] {
+        assert_eq!(
+            svm.process(&initialize_funded(Pubkey::new_unique(), authority, payer)),
+            Err(Error::from(CounterError::InvalidAuthority).into()),
+        );
+    }
+    // Any other address will do, even one nobody holds the key for.
+    let pda = pda(&[b"anything"]);
+    assert_eq!(
+        svm.process(&initialize_funded(Pubkey::new_unique(), pda, payer)),
+        Ok(())
+    );
+}
@@ -0,0 +1,267 @@
+// Property-based tests for the counter's state transitions.
+//
+// proptest generates hundreds of random inputs for each test below and, if
+// one fails, shrinks it down to the smallest input that still fails.
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use counter::{
+    check_counter, check_replica, Counter, CounterBank, CounterError, Replica, BANK_SLOTS,
+};
+use proptest::prelude::*;
+
+// Starting values biased towards the top of the u64 range, where overflow
+// bugs live.
+fn start_count() -> impl Strategy<Value = u64> {
+    prop_oneof![any::<u64>(), (u64::MAX - 64)..=u64::MAX]
+}
+
+proptest! {
+    #[test]
+    fn increment_sequences_never_panic_or_wrap(
+        start in start_count(),
+        increments in 0usize..256,
+    ) {
+        let mut counter = Counter { count: start, ..Default::default() };
+        // Track the "true" value in a wider type so it can't overflow.
+        let mut expected = u128::from(start);
+
+        for _ in 0..increments {
+            match counter.increment() {
+                Ok(()) => expected += 1,
+                Err(err) => {
+                    prop_assert_eq!(err, Error::from(CounterError::Overflow));
+                    prop_assert_eq!(counter.count, u64::MAX);
+                }
+            }
+            prop_assert_eq!(u128::from(counter.count), expected);
+        }
+    }
+
+    #[test]
+    fn increment_is_monotonic(start in start_count()) {
+        let mut counter = Counter { count: start, ..Default::default() };
+        let before = counter.count;
+        let result = counter.increment();
+
+        prop_assert!(counter.count >= before);
+        prop_assert_eq!(result.is_ok(), before < u64::MAX);
+    }
+}
+
+proptest! {
+    #[test]
+    fn once_per_slot_allows_one_increment_per_slot(
+        steps in prop::collection::vec(0u64..3, 0..64),
+    ) {
+        let mut counter = Counter::default();
+        counter.set_flag(Counter::ONCE_PER_SLOT, true);
+        let mut expected = 0u64;
+
+        // Slots only move forward, possibly repeating (several
+        // transactions in the same slot).
+        let mut slot = 0;
+        let mut last_counted = counter.last_increment_slot;
+        for step in steps {
+            slot += step;
+            let result = counter.record_slot(slot).and_then(|()| counter.increment());
+            if slot == last_counted {
+                prop_assert_eq!(
+                    result.err(),
+                    Some(Error::from(CounterError::AlreadyCountedThisSlot))
+                );
+            } else {
+                prop_assert!(result.is_ok());
+                last_counted = slot;
+                expected += 1;
+            }
+            prop_assert_eq!(counter.count, expected);
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn alarm_fires_once_when_the_threshold_is_reached(
+        start in 0u64..64,
+        threshold in 0u64..128,
+        increments in 0usize..128,
+    ) {
+        let mut counter = Counter { count: start, alarm_threshold: threshold, ..Default::default() };
+        let mut fired = 0;
+
+        for _ in 0..increments {
+            counter.increment().unwrap();
+            if counter.check_alarm(counter.count - 1) {
+                fired += 1;
+            }
+        }
+
+        let end = start + increments as u64;
+        let crossed = threshold != 0 && start < threshold && threshold <= end;
+        prop_assert_eq!(fired, u32::from(crossed));
+        prop_assert_eq!(counter.has_flag(Counter::ALARM_TRIGGERED), crossed);
+    }
+}
+
+// One step of the replica test: increment replica i, or merge a subset
+// of the replicas (bit i set = replica i included).
+#[derive(Clone, Debug)]
+enum ReplicaOp {
+    Increment(usize),
+    Merge(u8),
+}
+
+proptest! {
+    #[test]
+    fn merged_replicas_add_up_to_their_total(
+        ops in prop::collection::vec(
+            prop_oneof![
+                (0usize..4).prop_map(ReplicaOp::Increment),
+                any::<u8>().prop_map(ReplicaOp::Merge),
+            ],
+            0..128,
+        ),
+    ) {
+        let mut root = Counter::default();
+        let mut replicas: Vec<Replica> = (0..4)
+            .map(|id| Replica { root: Default::default(), id, count: 0, merged: 0, bump: 0 })
+            .collect();
+
+        for op in ops {
+            match op {
+                ReplicaOp::Increment(i) => replicas[i].count += 1,
+                ReplicaOp::Merge(subset) => {
+                    for (i, replica) in replicas.iter_mut().enumerate() {
+                        if subset & (1 << i) != 0 {
+                            root.add(replica.take_unmerged()).unwrap();
+                        }
+                    }
+                }
+            }
+            // The root never has more than the replicas counted, and
+            // exactly what they've had merged.
+            let merged: u64 = replicas.iter().map(|r| r.merged).sum();
+            prop_assert_eq!(root.count, merged);
+        }
+
+        // A final merge of everything catches the root up completely.
+        for replica in &mut replicas {
+            root.add(replica.take_unmerged()).unwrap();
+        }
+        let total: u64 = replicas.iter().map(|r| r.count).sum();
+        prop_assert_eq!(root.count, total);
+    }
+}
+
+proptest! {
+    #[test]
+    fn bank_increments_only_touch_their_slot(
+        indices in prop::collection::vec(0u16..(BANK_SLOTS as u16 + 8), 0..256),
+    ) {
+        let mut bank = CounterBank { slots: [0; BANK_SLOTS] };
+        let mut expected = vec![0u64; BANK_SLOTS];
+
+        for index in indices {
+            let result = bank.increment(index);
+            match expected.get_mut(usize::from(index)) {
+                Some(slot) => {
+                    *slot += 1;
+                    prop_assert_eq!(result.ok(), Some(*slot));
+                }
+                None => prop_assert_eq!(
+                    result.err(),
+                    Some(Error::from(CounterError::IndexOutOfBounds))
+                ),
+            }
+        }
+
+        prop_assert_eq!(&bank.slots[..], &expected[..]);
+    }
+}
+
+// One step of the mixed-operations test. Each of ACTORS actors increments
+// the root directly or through their own replica; the owner decrements,
+// resets and merges.
+#[derive(Clone, Debug)]
+enum Op {
+    Increment(usize),
+    IncrementReplica(usize),
+    Decrement(u64),
+    Reset,
+    Merge(u8),
+}
+
+const ACTORS: usize = 4;
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (0..ACTORS).prop_map(Op::Increment),
+        4 => (0..ACTORS).prop_map(Op::IncrementReplica),
+        2 => (0u64..8).prop_map(Op::Decrement),
+        1 => Just(Op::Reset),
+        2 => any::<u8>().prop_map(Op::Merge),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn mixed_operations_keep_the_invariants(
+        start in 0u64..1_000,
+        ops in prop::collection::vec(op(), 0..256),
+    ) {
+        let root_key = Pubkey::new_unique();
+        let mut root = Counter { count: start, lifetime_count: start, ..Default::default() };
+        let mut replicas: Vec<Replica> = (0..ACTORS as u16)
+            .map(|id| Replica { root: root_key, id, count: 0, merged: 0, bump: 0 })
+            .collect();
+        // What each actor incremented the root by directly, and what
+        // decrements and resets took back off.
+        let mut direct = [0u64; ACTORS];
+        let mut removed = 0u64;
+
+        for op in ops {
+            match op {
+                Op::Increment(actor) => {
+                    root.increment().unwrap();
+                    direct[actor] += 1;
+                }
+                Op::IncrementReplica(actor) => replicas[actor].count += 1,
+                Op::Decrement(amount) => match root.subtract(amount) {
+                    Ok(()) => removed += amount,
+                    Err(err) => {
+                        prop_assert_eq!(err, Error::from(CounterError::Underflow));
+                        prop_assert!(root.count < amount);
+                    }
+                },
+                Op::Reset => removed += root.reset(),
+                Op::Merge(subset) => {
+                    for (actor, replica) in replicas.iter_mut().enumerate() {
+                        if subset & (1 << actor) != 0 {
+                            root.add(replica.take_unmerged()).unwrap();
+                        }
+                    }
+                }
+            }
+
+            // What the program checks on-chain after each of these.
+            prop_assert!(check_counter(&root).is_ok());
+            for replica in &replicas {
+                prop_assert!(check_replica(replica, root_key).is_ok());
+            }
+            // Nothing is counted twice or lost: the lifetime count is every
+            // actor's direct increments plus what their replicas have had
+            // merged, and the count is that less what was taken off.
+            let merged: u64 = replicas.iter().map(|r| r.merged).sum();
+            prop_assert_eq!(root.lifetime_count, start + direct.iter().sum::<u64>() + merged);
+            prop_assert_eq!(root.count + removed, root.lifetime_count);
+        }
+
+        // A final merge of everything brings in every replica increment.
+        for replica in &mut replicas {
+            root.add(replica.take_unmerged()).unwrap();
+        }
+        let counted: u64 = replicas.iter().map(|r| r.count).sum();
+        prop_assert_eq!(root.lifetime_count, start + direct.iter().sum::<u64>() + counted);
+        prop_assert!(check_counter(&root).is_ok());
+    }
+}
@@ -0,0 +1,64 @@
+// The getters (see getters.rs), run through the program: what they hand
+// back as return data is what another program would get from a CPI.
+mod harness;
+
+use anchor_lang::prelude::*;
+use counter::{accounts, instruction, Counter, CounterState, Version};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+#[test]
+fn get_state_sums_up_the_counter() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(
+        counter,
+        &Counter {
+            flags: Counter::ONCE_PER_SLOT,
+            alarm_threshold: 10,
+            role_weighted: true,
+            ..fixtures::counter_at(authority, 7)
+        },
+        CURRENT,
+    );
+
+    assert_eq!(
+        svm.process(&ix(
+            accounts::ReadCounter { counter },
+            instruction::GetState {}
+        )),
+        Ok(())
+    );
+    let state = CounterState::try_from_slice(&svm.return_data()).unwrap();
+    assert_eq!(state.count, 7);
+    assert_eq!(state.authority, authority);
+    assert_eq!(state.flags, Counter::ONCE_PER_SLOT);
+    assert_eq!(state.alarm_threshold, 10);
+    assert!(state.role_weighted);
+    assert_eq!(state.version, Version::CURRENT);
+
+    assert_eq!(
+        svm.process(&ix(
+            accounts::ReadCounter { counter },
+            instruction::GetCount {}
+        )),
+        Ok(())
+    );
+    assert_eq!(u64::try_from_slice(&svm.return_data()).unwrap(), 7);
+}
+
+#[test]
+fn getters_change_nothing() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+    let before = svm.get(&counter).cloned();
+
+    let get_state = ix(accounts::ReadCounter { counter }, instruction::GetState {});
+    assert_eq!(svm.process(&get_state), Ok(()));
+    assert_eq!(svm.get(&counter).cloned(), before);
+    // Nothing signs, so anyone (or any program) can ask.
+    assert!(get_state.accounts.iter().all(|meta| !meta.is_signer));
+}
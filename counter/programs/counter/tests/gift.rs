@@ -0,0 +1,198 @@
+// Gifted counters (see gift.rs), run through the program: the recipient
+// claims them before they expire, or the creator gets the rent back.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, AccessLog, AdminAction, Counter, CounterError, Gift, GiftArgs,
+    ACCESS_LOG_SEED, GIFT_SEED, VAULT_SEED,
+};
+use harness::time::TimeMachine;
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn gift_address(counter: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[GIFT_SEED, counter.as_ref()], &counter::ID).0
+}
+
+fn vault_address(counter: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[VAULT_SEED, counter.as_ref()], &counter::ID).0
+}
+
+fn access_log_address(counter: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ACCESS_LOG_SEED, counter.as_ref()], &counter::ID).0
+}
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn create_gift(
+    counter: Pubkey,
+    creator: Pubkey,
+    count: u64,
+    recipient: Pubkey,
+    duration: i64,
+) -> Instruction {
+    ix(
+        accounts::CreateGift {
+            counter,
+            gift: gift_address(counter),
+            creator,
+            system_program: system_program::ID,
+        },
+        instruction::CreateGift {
+            args: GiftArgs {
+                version: GiftArgs::VERSION,
+                count,
+                recipient,
+                duration,
+            },
+        },
+    )
+}
+
+fn claim_gift(counter: Pubkey, recipient: Pubkey, creator: Pubkey) -> Instruction {
+    ix(
+        accounts::ClaimGift {
+            counter,
+            gift: gift_address(counter),
+            access_log: access_log_address(counter),
+            recipient,
+            creator,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimGift {},
+    )
+}
+
+fn reclaim_gift(counter: Pubkey, creator: Pubkey) -> Instruction {
+    ix(
+        accounts::ReclaimGift {
+            counter,
+            gift: gift_address(counter),
+            vault: vault_address(counter),
+            creator,
+        },
+        instruction::ReclaimGift {},
+    )
+}
+
+fn lamports(svm: &Svm, address: &Pubkey) -> u64 {
+    svm.get(address).map_or(0, |account| account.lamports)
+}
+
+#[test]
+fn the_recipient_claims_a_preloaded_counter() {
+    let mut svm = Svm::new();
+    let (creator, recipient, stranger) = (svm.wallet(), svm.wallet(), svm.wallet());
+    let counter = Pubkey::new_unique();
+    assert_eq!(
+        svm.process(&create_gift(counter, creator, 42, recipient, DAY)),
+        Ok(())
+    );
+    let gift = gift_address(counter);
+    let loaded = svm.load::<Counter>(&counter).unwrap();
+    assert_eq!((loaded.count, loaded.lifetime_count), (42, 42));
+    // Marked as gifted, for the leaderboard to leave out.
+    assert_eq!(loaded.gifted_count, 42);
+    assert_eq!(loaded.authority, gift);
+
+    assert_eq!(
+        svm.process(&claim_gift(counter, stranger, creator)),
+        Err(error(CounterError::Unauthorized))
+    );
+    assert_eq!(
+        svm.process(&claim_gift(counter, recipient, stranger)),
+        Err(error(CounterError::Unauthorized))
+    );
+
+    let refund = lamports(&svm, &creator) + lamports(&svm, &gift);
+    assert_eq!(
+        svm.process(&claim_gift(counter, recipient, creator)),
+        Ok(())
+    );
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().authority, recipient);
+    assert!(svm.load::<Gift>(&gift).is_none());
+    assert_eq!(lamports(&svm, &creator), refund);
+
+    // The handover is on record, like any change of hands.
+    let data = &svm.get(&access_log_address(counter)).unwrap().data;
+    let log: &AccessLog = bytemuck::from_bytes(&data[8..]);
+    let entries: Vec<_> = log
+        .entries()
+        .map(|entry| (entry.actor, entry.action))
+        .collect();
+    assert_eq!(entries, [(recipient, AdminAction::ClaimGift as u8)]);
+}
+
+#[test]
+fn expired_gifts_go_back_to_the_creator() {
+    let mut svm = Svm::new();
+    let (creator, recipient) = (svm.wallet(), svm.wallet());
+    let before = lamports(&svm, &creator);
+    let counter = Pubkey::new_unique();
+    assert_eq!(
+        svm.process(&create_gift(counter, creator, 7, recipient, DAY)),
+        Ok(())
+    );
+    assert_eq!(
+        svm.process(&reclaim_gift(counter, creator)),
+        Err(error(CounterError::GiftNotExpired))
+    );
+
+    svm.advance_time(DAY);
+    assert_eq!(
+        svm.process(&claim_gift(counter, recipient, creator)),
+        Err(error(CounterError::GiftExpired))
+    );
+    assert_eq!(
+        svm.process(&reclaim_gift(counter, recipient)),
+        Err(error(CounterError::Unauthorized))
+    );
+    assert_eq!(svm.process(&reclaim_gift(counter, creator)), Ok(()));
+    assert_eq!(lamports(&svm, &counter), 0);
+    assert_eq!(lamports(&svm, &gift_address(counter)), 0);
+    assert_eq!(lamports(&svm, &creator), before);
+}
+
+#[test]
+fn gifts_with_lamports_in_their_vault_stay() {
+    let mut svm = Svm::new();
+    let (creator, recipient) = (svm.wallet(), svm.wallet());
+    let counter = Pubkey::new_unique();
+    assert_eq!(
+        svm.process(&create_gift(counter, creator, 7, recipient, DAY)),
+        Ok(())
+    );
+    svm.airdrop(vault_address(counter), 5_000);
+    svm.advance_time(DAY);
+
+    // Closing the counter would strand them.
+    assert_eq!(
+        svm.process(&reclaim_gift(counter, creator)),
+        Err(error(CounterError::VaultNotEmpty))
+    );
+    assert!(svm.load::<Counter>(&counter).is_some());
+}
+
+#[test]
+fn gifts_must_be_claimable_for_a_while() {
+    let mut svm = Svm::new();
+    let (creator, recipient) = (svm.wallet(), svm.wallet());
+    for duration in [0, -DAY, i64::MAX] {
+        assert_eq!(
+            svm.process(&create_gift(
+                Pubkey::new_unique(),
+                creator,
+                1,
+                recipient,
+                duration
+            )),
+            Err(error(CounterError::InvalidGift))
+        );
+    }
+}
@@ -0,0 +1,1012 @@
+// Golden tests: every instruction's data and every account type, encoded
+// at fixed values and compared with the bytes checked in under golden/.
+//
+// Clients encode instructions and decode accounts on their own, from the
+// IDL or by hand (see client/ and programs/counter-native), so bytes that
+// change under them break them. A renamed instruction changes its
+// discriminator; a field moved or resized shifts everything after it.
+// Neither shows up anywhere else until it's deployed.
+//
+// When a change is meant to change the bytes, regenerate the files with
+//
+//     UPDATE_GOLDEN=1 cargo test -p counter --test golden
+//
+// and review the diff like any other: every changed line is a break for
+// whoever reads those bytes.
+//
+// To rename an instruction without that break, keep the old name too: a
+// handler under the old name, with the same accounts and arguments, that
+// calls the new one. Both then stay listed here, each with its own
+// discriminator.
+mod harness;
+
+use std::path::PathBuf;
+
+use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator, InstructionData};
+use counter::{
+    encode_tag, instruction, AccessLog, AdminAction, AllowedCaller, Badge, BurnMint, CommitRound,
+    CommitRoundArgs, Commitment, CompressedCounter, CompressedCounterMeta, CompressedProof,
+    ConfidentialCounter, Config, Contribution, Counter, CounterBank, CounterFees, CounterState,
+    Deadline, DeltaEntry, DeltaFeed, EventTree, FeeExemption, FeeSchedule, FeeScheduleArgs,
+    FeeTier, FinalResult, FreezePolicy, FreezeTally, FreezeVote, Gift, GiftArgs, Group, GroupArgs,
+    IncrementFeeArgs, IncrementPreview, Leaderboard, Lease, LeaseArgs, Lockup,
+    PackedAddressTreeInfo, PackedMerkleContext, ProgramInfo, QueueIndex, ReapPolicy, Receipt,
+    ReferrerStats, RemoteCounter, Replica, Role, SoftCapArgs, SponsoredWallet, Sponsorship,
+    StatsBucket, TagRegistry, Template, TemplateArgs, TemplateSettings, ThresholdAttestation,
+    TwacObservation, Version, VoteMint, ACTOR_BITS, MAX_COUNTER_TAGS, RECENT_DELTAS, TAG_LEN,
+};
+use harness::fixtures::address;
+
+// One line per value: its name, its length in bytes, then its bytes in
+// hex, with the trailing zeroes left out (the length says how many there
+// are), which keeps the big zero-copy accounts readable.
+fn render(values: &[(&str, Vec<u8>)]) -> String {
+    let mut out = String::from("# name, length in bytes, bytes in hex without trailing zeroes\n");
+    for (name, bytes) in values {
+        let end = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        let hex: String = bytes[..end].iter().map(|b| format!("{b:02x}")).collect();
+        out += &format!("{name} {} {hex}\n", bytes.len());
+    }
+    out
+}
+
+// Compares "values" with golden/"file", or rewrites it with UPDATE_GOLDEN
+// set.
+fn check(file: &str, values: &[(&str, Vec<u8>)]) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", file]
+        .iter()
+        .collect();
+    let actual = render(values);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_default();
+    let changed: Vec<&str> = actual
+        .lines()
+        .filter(|line| !expected.lines().any(|e| e == *line))
+        .map(|line| line.split(' ').next().unwrap())
+        .collect();
+    assert!(
+        actual == expected,
+        "{file} doesn't match: {changed:?} changed (or were removed or reordered). \
+         If that's intended, rerun with UPDATE_GOLDEN=1"
+    );
+}
+
+fn anchor<T: AccountSerialize>(account: T) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn zero_copy<T: Discriminator + bytemuck::Pod>(account: &T) -> Vec<u8> {
+    [&T::DISCRIMINATOR[..], bytemuck::bytes_of(account)].concat()
+}
+
+fn proof() -> CompressedProof {
+    CompressedProof {
+        a: [1; 32],
+        b: [2; 64],
+        c: [3; 32],
+    }
+}
+
+fn settings() -> TemplateSettings {
+    TemplateSettings {
+        fee_lamports: 46,
+        referral_bps: 47,
+        once_per_slot: true,
+        alarm_threshold: 48,
+        deny_cpi: true,
+    }
+}
+
+#[test]
+fn instruction_data() {
+    let (a, b) = (address("a"), address("b"));
+    check(
+        "instructions.txt",
+        &[
+            ("initialize", instruction::Initialize {}.data()),
+            ("increment", instruction::Increment {}.data()),
+            ("initialize_bank", instruction::InitializeBank {}.data()),
+            (
+                "increment_index",
+                instruction::IncrementIndex { index: 0x0102 }.data(),
+            ),
+            (
+                "set_program_info",
+                instruction::SetProgramInfo { commit: [0xab; 20] }.data(),
+            ),
+            ("get_version", instruction::GetVersion {}.data()),
+            (
+                "increment_with_key",
+                instruction::IncrementWithKey {
+                    idempotency_key: [0x11; 16],
+                }
+                .data(),
+            ),
+            (
+                "set_once_per_slot",
+                instruction::SetOncePerSlot { enabled: true }.data(),
+            ),
+            (
+                "set_alarm_threshold",
+                instruction::SetAlarmThreshold { threshold: 5 }.data(),
+            ),
+            ("acknowledge_alarm", instruction::AcknowledgeAlarm {}.data()),
+            (
+                "initialize_replica",
+                instruction::InitializeReplica { replica_id: 3 }.data(),
+            ),
+            ("increment_replica", instruction::IncrementReplica {}.data()),
+            ("merge_replicas", instruction::MergeReplicas {}.data()),
+            ("post_count", instruction::PostCount { nonce: 7 }.data()),
+            (
+                "initialize_remote_counter",
+                instruction::InitializeRemoteCounter {
+                    emitter_chain: 2,
+                    emitter_address: [0x22; 32],
+                    remote_counter_id: [0x33; 32],
+                }
+                .data(),
+            ),
+            (
+                "receive_remote_count",
+                instruction::ReceiveRemoteCount {
+                    vaa_hash: [0x44; 32],
+                }
+                .data(),
+            ),
+            (
+                "initialize_event_tree",
+                instruction::InitializeEventTree {
+                    max_depth: 14,
+                    max_buffer_size: 64,
+                }
+                .data(),
+            ),
+            ("increment_logged", instruction::IncrementLogged {}.data()),
+            (
+                "set_tree_logged",
+                instruction::SetTreeLogged { enabled: true }.data(),
+            ),
+            (
+                "initialize_confidential",
+                instruction::InitializeConfidential {
+                    elgamal_pubkey: [0x55; 32],
+                }
+                .data(),
+            ),
+            (
+                "increment_confidential",
+                instruction::IncrementConfidential {
+                    encrypted_delta: [0x66; 64],
+                }
+                .data(),
+            ),
+            (
+                "prove_threshold",
+                instruction::ProveThreshold { min: 10 }.data(),
+            ),
+            (
+                "initialize_counter_mint",
+                instruction::InitializeCounterMint {}.data(),
+            ),
+            ("increment_minted", instruction::IncrementMinted {}.data()),
+            ("decrement_minted", instruction::DecrementMinted {}.data()),
+            ("set_burn_mint", instruction::SetBurnMint {}.data()),
+            (
+                "decrement_by_burn",
+                instruction::DecrementByBurn { amount: 4 }.data(),
+            ),
+            ("archive", instruction::Archive {}.data()),
+            ("unarchive", instruction::Unarchive {}.data()),
+            (
+                "lease_authority",
+                instruction::LeaseAuthority {
+                    args: LeaseArgs {
+                        version: LeaseArgs::VERSION,
+                        to: a,
+                        duration: 60,
+                        price: 1_000,
+                    },
+                }
+                .data(),
+            ),
+            (
+                "withdraw_vault",
+                instruction::WithdrawVault { amount: 500 }.data(),
+            ),
+            (
+                "set_increment_fee",
+                instruction::SetIncrementFee {
+                    args: IncrementFeeArgs {
+                        version: IncrementFeeArgs::VERSION,
+                        fee_lamports: 5_000,
+                        referral_bps: 250,
+                    },
+                }
+                .data(),
+            ),
+            (
+                "increment_paid",
+                instruction::IncrementPaid { referrer: Some(b) }.data(),
+            ),
+            (
+                "increment_paid_unreferred",
+                instruction::IncrementPaid { referrer: None }.data(),
+            ),
+            (
+                "withdraw_referral",
+                instruction::WithdrawReferral { amount: 200 }.data(),
+            ),
+            (
+                "increment_contributed",
+                instruction::IncrementContributed {}.data(),
+            ),
+            ("claim_badge", instruction::ClaimBadge { tier: 2 }.data()),
+            (
+                "set_vote_weighted",
+                instruction::SetVoteWeighted { enabled: true }.data(),
+            ),
+            (
+                "lock_tokens",
+                instruction::LockTokens {
+                    amount: 100,
+                    duration: 3_600,
+                }
+                .data(),
+            ),
+            (
+                "increment_weighted",
+                instruction::IncrementWeighted {}.data(),
+            ),
+            ("unlock", instruction::Unlock {}.data()),
+            (
+                "set_commit_reveal",
+                instruction::SetCommitReveal { enabled: true }.data(),
+            ),
+            (
+                "open_commit_round",
+                instruction::OpenCommitRound {
+                    args: CommitRoundArgs {
+                        version: CommitRoundArgs::VERSION,
+                        commit_deadline: 1_000,
+                        reveal_deadline: 2_000,
+                        deposit: 300,
+                        max_value: 50,
+                    },
+                }
+                .data(),
+            ),
+            ("commit", instruction::Commit { hash: [0x77; 32] }.data()),
+            (
+                "reveal",
+                instruction::Reveal {
+                    value: 9,
+                    salt: [0x88; 32],
+                }
+                .data(),
+            ),
+            (
+                "forfeit_commit",
+                instruction::ForfeitCommit { round: 1 }.data(),
+            ),
+            (
+                "set_end_time",
+                instruction::SetEndTime { end_time: 5_000 }.data(),
+            ),
+            ("finalize", instruction::Finalize {}.data()),
+            ("assert_invariants", instruction::AssertInvariants {}.data()),
+            (
+                "initialize_config",
+                instruction::InitializeConfig { admin: a }.data(),
+            ),
+            (
+                "set_deny_cpi",
+                instruction::SetDenyCpi { enabled: true }.data(),
+            ),
+            (
+                "allow_program",
+                instruction::AllowProgram { program: b }.data(),
+            ),
+            ("disallow_program", instruction::DisallowProgram {}.data()),
+            (
+                "increment_from_program",
+                instruction::IncrementFromProgram {}.data(),
+            ),
+            (
+                "exempt_from_fees",
+                instruction::ExemptFromFees { wallet: b }.data(),
+            ),
+            (
+                "revoke_fee_exemption",
+                instruction::RevokeFeeExemption {}.data(),
+            ),
+            ("migrate_counter", instruction::MigrateCounter {}.data()),
+            ("reset_count", instruction::ResetCount {}.data()),
+            (
+                "set_role_weighted",
+                instruction::SetRoleWeighted { enabled: true }.data(),
+            ),
+            (
+                "set_role",
+                instruction::SetRole {
+                    member: b,
+                    weight: 10,
+                }
+                .data(),
+            ),
+            ("increment_by_role", instruction::IncrementByRole {}.data()),
+            (
+                "set_template",
+                instruction::SetTemplate {
+                    args: TemplateArgs {
+                        version: TemplateArgs::VERSION,
+                        name: "tip jar".to_string(),
+                        settings: settings(),
+                    },
+                }
+                .data(),
+            ),
+            ("remove_template", instruction::RemoveTemplate {}.data()),
+            (
+                "initialize_from_template",
+                instruction::InitializeFromTemplate {}.data(),
+            ),
+            ("get_count", instruction::GetCount {}.data()),
+            ("get_state", instruction::GetState {}.data()),
+            (
+                "initialize_reapable",
+                instruction::InitializeReapable {
+                    idle_epochs: 4,
+                    beneficiary: address("a"),
+                }
+                .data(),
+            ),
+            ("reap", instruction::Reap {}.data()),
+            (
+                "set_sponsorship",
+                instruction::SetSponsorship { quota: 3 }.data(),
+            ),
+            (
+                "withdraw_treasury",
+                instruction::WithdrawTreasury { amount: 500_000 }.data(),
+            ),
+            (
+                "initialize_sponsored",
+                instruction::InitializeSponsored {}.data(),
+            ),
+            (
+                "create_gift",
+                instruction::CreateGift {
+                    args: GiftArgs {
+                        version: GiftArgs::VERSION,
+                        count: 42,
+                        recipient: address("a"),
+                        duration: 86_400,
+                    },
+                }
+                .data(),
+            ),
+            ("claim_gift", instruction::ClaimGift {}.data()),
+            ("reclaim_gift", instruction::ReclaimGift {}.data()),
+            (
+                "initialize_leaderboard",
+                instruction::InitializeLeaderboard {}.data(),
+            ),
+            (
+                "update_leaderboard",
+                instruction::UpdateLeaderboard {}.data(),
+            ),
+            (
+                "register_tag",
+                instruction::RegisterTag {
+                    tag: encode_tag("games").unwrap(),
+                }
+                .data(),
+            ),
+            (
+                "initialize_tagged",
+                instruction::InitializeTagged {
+                    tags: vec![encode_tag("games").unwrap(), encode_tag("dao").unwrap()],
+                }
+                .data(),
+            ),
+            (
+                "set_soft_cap",
+                instruction::SetSoftCap {
+                    args: SoftCapArgs {
+                        version: SoftCapArgs::VERSION,
+                        max_count: 1_000,
+                        overflow: b,
+                    },
+                }
+                .data(),
+            ),
+            (
+                "increment_spilling",
+                instruction::IncrementSpilling {}.data(),
+            ),
+            ("set_twac", instruction::SetTwac { enabled: true }.data()),
+            ("get_twac", instruction::GetTwac {}.data()),
+            (
+                "set_delta_feed",
+                instruction::SetDeltaFeed { enabled: true }.data(),
+            ),
+            (
+                "set_freeze_policy",
+                instruction::SetFreezePolicy {
+                    quorum: 3,
+                    vote_secs: 60,
+                }
+                .data(),
+            ),
+            ("vote_freeze", instruction::VoteFreeze {}.data()),
+            ("unfreeze", instruction::Unfreeze {}.data()),
+            (
+                "set_fee_schedule",
+                instruction::SetFeeSchedule {
+                    args: FeeScheduleArgs {
+                        version: FeeScheduleArgs::VERSION,
+                        tiers: vec![
+                            FeeTier {
+                                from: 0,
+                                fee_lamports: 0,
+                            },
+                            FeeTier {
+                                from: 100,
+                                fee_lamports: 5_000,
+                            },
+                        ],
+                        referral_bps: 250,
+                    },
+                }
+                .data(),
+            ),
+            ("sweep_fees", instruction::SweepFees {}.data()),
+            ("initialize_funded", instruction::InitializeFunded {}.data()),
+            ("verify_checksum", instruction::VerifyChecksum {}.data()),
+            (
+                "preview_increment",
+                instruction::PreviewIncrement { referrer: Some(b) }.data(),
+            ),
+            ("initialize_group", instruction::InitializeGroup {}.data()),
+            (
+                "update_group",
+                instruction::UpdateGroup {
+                    args: GroupArgs {
+                        version: GroupArgs::VERSION,
+                        paused: true,
+                        fee_lamports: 5_000,
+                        deny_cpi: true,
+                    },
+                }
+                .data(),
+            ),
+            ("join_group", instruction::JoinGroup {}.data()),
+            ("leave_group", instruction::LeaveGroup {}.data()),
+            ("increment_grouped", instruction::IncrementGrouped {}.data()),
+            (
+                "migrate_ownerless_counter",
+                instruction::MigrateOwnerlessCounter {
+                    owner: address("owner"),
+                }
+                .data(),
+            ),
+            (
+                "create_compressed_counter",
+                instruction::CreateCompressedCounter {
+                    proof: proof(),
+                    address_tree: PackedAddressTreeInfo {
+                        address_merkle_tree_account_index: 0,
+                        address_queue_account_index: 1,
+                        root_index: 7,
+                    },
+                    output_tree_index: 2,
+                    id: 3,
+                }
+                .data(),
+            ),
+            (
+                "increment_compressed_counter",
+                instruction::IncrementCompressedCounter {
+                    proof: proof(),
+                    counter: CompressedCounter {
+                        authority: address("authority"),
+                        count: 42,
+                    },
+                    meta: CompressedCounterMeta {
+                        address: [9; 32],
+                        merkle_context: PackedMerkleContext {
+                            merkle_tree_pubkey_index: 2,
+                            nullifier_queue_pubkey_index: 3,
+                            leaf_index: 17,
+                            queue_index: Some(QueueIndex {
+                                queue_id: 1,
+                                index: 4,
+                            }),
+                        },
+                        root_index: 5,
+                        output_tree_index: 2,
+                    },
+                }
+                .data(),
+            ),
+            (
+                "evict_leaderboard_entry",
+                instruction::EvictLeaderboardEntry {}.data(),
+            ),
+        ],
+    );
+}
+
+#[test]
+fn account_layouts() {
+    let (a, b, c) = (address("a"), address("b"), address("c"));
+
+    let mut access_log: AccessLog = bytemuck::Zeroable::zeroed();
+    access_log.counter = a;
+    access_log.append(b, AdminAction::ResetCount, 1_700_000_000);
+    let mut bank: CounterBank = bytemuck::Zeroable::zeroed();
+    bank.slots[0] = 1;
+    bank.slots[1] = 2;
+    let mut actors = [0; ACTOR_BITS / 8];
+    actors[0] = 0b101;
+    let mut leaderboard: Leaderboard = bytemuck::Zeroable::zeroed();
+    leaderboard.submit(a, 7);
+    leaderboard.submit(b, 9);
+    leaderboard.bump = 226;
+
+    check(
+        "accounts.txt",
+        &[
+            (
+                "Counter",
+                anchor(Counter {
+                    count: 1,
+                    authority: a,
+                    flags: Counter::ONCE_PER_SLOT | Counter::ARCHIVED,
+                    last_increment_slot: 2,
+                    alarm_threshold: 3,
+                    event_seq: 4,
+                    finalized: true,
+                    deny_cpi: true,
+                    lifetime_count: 5,
+                    role_weighted: true,
+                    tags: [[6; TAG_LEN]; MAX_COUNTER_TAGS],
+                    max_count: 7,
+                    overflow: b,
+                    cumulative_count: 8,
+                    twac_slot: 9,
+                    delta_feed: DeltaFeed {
+                        base_slot: 10,
+                        slot: 11,
+                        recorded: 12,
+                        recent_deltas: [DeltaEntry {
+                            slot_offset: 13,
+                            delta: -14,
+                        }; RECENT_DELTAS],
+                    },
+                    frozen: true,
+                    // Replaced by the real one on the way out.
+                    checksum: 0,
+                    group: c,
+                    gifted_count: 15,
+                }),
+            ),
+            ("AccessLog", zero_copy(&access_log)),
+            ("CounterBank", zero_copy(&bank)),
+            (
+                "Config",
+                anchor(Config {
+                    admin: a,
+                    bump: 255,
+                }),
+            ),
+            (
+                "ProgramInfo",
+                anchor(ProgramInfo {
+                    version: "0.1.0".to_string(),
+                    commit: [0xab; 20],
+                    updated_slot: 6,
+                    bump: 254,
+                }),
+            ),
+            (
+                "Receipt",
+                anchor(Receipt {
+                    counter: a,
+                    count: 7,
+                    slot: 8,
+                    bump: 253,
+                }),
+            ),
+            (
+                "Replica",
+                anchor(Replica {
+                    root: a,
+                    id: 9,
+                    count: 10,
+                    merged: 11,
+                    bump: 252,
+                }),
+            ),
+            (
+                "RemoteCounter",
+                anchor(RemoteCounter {
+                    emitter_chain: 2,
+                    emitter_address: [0x22; 32],
+                    remote_counter: [0x33; 32],
+                    count: 12,
+                    next_sequence: 13,
+                    bump: 251,
+                }),
+            ),
+            (
+                "EventTree",
+                anchor(EventTree {
+                    merkle_tree: a,
+                    leaves: 14,
+                    bump: 250,
+                    authority_bump: 249,
+                }),
+            ),
+            (
+                "ConfidentialCounter",
+                anchor(ConfidentialCounter {
+                    authority: a,
+                    elgamal_pubkey: [0x55; 32],
+                    encrypted_count: [0x66; 64],
+                    increments: 15,
+                }),
+            ),
+            (
+                "ThresholdAttestation",
+                anchor(ThresholdAttestation {
+                    counter: a,
+                    min: 16,
+                    increments: 17,
+                    slot: 18,
+                    bump: 248,
+                }),
+            ),
+            (
+                "BurnMint",
+                anchor(BurnMint {
+                    counter: a,
+                    mint: b,
+                    bump: 247,
+                }),
+            ),
+            (
+                "Lease",
+                anchor(Lease {
+                    counter: a,
+                    renter: b,
+                    expires_at: 19,
+                    price: 20,
+                    bump: 246,
+                }),
+            ),
+            (
+                "CounterFees",
+                anchor(CounterFees {
+                    counter: a,
+                    fee_lamports: 21,
+                    referral_bps: 22,
+                    bump: 245,
+                }),
+            ),
+            (
+                "ReferrerStats",
+                anchor(ReferrerStats {
+                    counter: a,
+                    referrer: b,
+                    increments: 23,
+                    earned: 24,
+                    bump: 244,
+                }),
+            ),
+            (
+                "FeeExemption",
+                anchor(FeeExemption {
+                    counter: a,
+                    wallet: b,
+                    bump: 243,
+                }),
+            ),
+            (
+                "Contribution",
+                anchor(Contribution {
+                    counter: a,
+                    contributor: b,
+                    count: 25,
+                    reached_at: [26, 27, 28],
+                    bump: 242,
+                }),
+            ),
+            (
+                "Badge",
+                anchor(Badge {
+                    counter: a,
+                    owner: b,
+                    tier: 2,
+                    earned_at: 29,
+                    bump: 241,
+                }),
+            ),
+            (
+                "VoteMint",
+                anchor(VoteMint {
+                    counter: a,
+                    mint: b,
+                    bump: 240,
+                }),
+            ),
+            (
+                "Lockup",
+                anchor(Lockup {
+                    counter: a,
+                    owner: b,
+                    mint: c,
+                    amount: 30,
+                    unlock_at: 31,
+                    voted: true,
+                    bump: 239,
+                }),
+            ),
+            (
+                "CommitRound",
+                anchor(CommitRound {
+                    counter: a,
+                    round: 32,
+                    commit_deadline: 33,
+                    reveal_deadline: 34,
+                    deposit: 35,
+                    max_value: 50,
+                    commits: 36,
+                    reveals: 37,
+                    bump: 238,
+                }),
+            ),
+            (
+                "Commitment",
+                anchor(Commitment {
+                    participant: a,
+                    hash: [0x77; 32],
+                    bump: 237,
+                }),
+            ),
+            (
+                "Deadline",
+                anchor(Deadline {
+                    counter: a,
+                    end_time: 38,
+                    bump: 236,
+                }),
+            ),
+            (
+                "FinalResult",
+                anchor(FinalResult {
+                    counter: a,
+                    count: 39,
+                    slot: 40,
+                    finalized_at: 41,
+                    bump: 235,
+                }),
+            ),
+            (
+                "AllowedCaller",
+                anchor(AllowedCaller {
+                    counter: a,
+                    program: b,
+                    bump: 234,
+                }),
+            ),
+            (
+                "StatsBucket",
+                anchor(StatsBucket {
+                    counter: a,
+                    unix_hour: 42,
+                    increments: 43,
+                    unique_actors: 2,
+                    fee_revenue: 44,
+                    actors,
+                    bump: 233,
+                }),
+            ),
+            (
+                "Role",
+                anchor(Role {
+                    counter: a,
+                    member: b,
+                    weight: 45,
+                    bump: 232,
+                }),
+            ),
+            (
+                "Template",
+                anchor(Template {
+                    name: "tip jar".to_string(),
+                    settings: settings(),
+                    bump: 231,
+                }),
+            ),
+            (
+                "ReapPolicy",
+                anchor(ReapPolicy {
+                    counter: a,
+                    beneficiary: b,
+                    idle_epochs: 4,
+                    seen: [7; 32],
+                    seen_epoch: 512,
+                    bump: 230,
+                }),
+            ),
+            (
+                "Sponsorship",
+                anchor(Sponsorship {
+                    quota: 3,
+                    bump: 229,
+                }),
+            ),
+            (
+                "SponsoredWallet",
+                anchor(SponsoredWallet {
+                    wallet: a,
+                    count: 2,
+                    bump: 228,
+                }),
+            ),
+            (
+                "Gift",
+                anchor(Gift {
+                    counter: a,
+                    creator: b,
+                    recipient: c,
+                    expires_at: 1_700_086_400,
+                    bump: 227,
+                }),
+            ),
+            ("Leaderboard", zero_copy(&leaderboard)),
+            (
+                "TagRegistry",
+                anchor(TagRegistry {
+                    tags: vec![encode_tag("games").unwrap(), encode_tag("dao").unwrap()],
+                    bump: 225,
+                }),
+            ),
+            (
+                "FreezePolicy",
+                anchor(FreezePolicy {
+                    quorum: 3,
+                    vote_secs: 86_400,
+                    bump: 224,
+                }),
+            ),
+            (
+                "FreezeTally",
+                anchor(FreezeTally {
+                    counter: a,
+                    round: 2,
+                    opened_at: 1_700_000_000,
+                    votes: 1,
+                    bump: 223,
+                }),
+            ),
+            (
+                "FreezeVote",
+                anchor(FreezeVote {
+                    counter: a,
+                    voter: b,
+                    round: 2,
+                    bump: 222,
+                }),
+            ),
+            (
+                "FeeSchedule",
+                anchor(FeeSchedule {
+                    counter: a,
+                    tiers: vec![
+                        FeeTier {
+                            from: 0,
+                            fee_lamports: 0,
+                        },
+                        FeeTier {
+                            from: 100,
+                            fee_lamports: 5_000,
+                        },
+                    ],
+                    day: 19_700,
+                    day_volume: 101,
+                    bump: 221,
+                }),
+            ),
+            (
+                "Group",
+                anchor(Group {
+                    authority: a,
+                    paused: true,
+                    fee_lamports: 5_000,
+                    deny_cpi: true,
+                    members: 2,
+                }),
+            ),
+            // Compressed: not an account, but the data of one (see
+            // compressed.rs), behind its discriminator.
+            (
+                "CompressedCounter",
+                [
+                    CompressedCounter::DISCRIMINATOR.to_vec(),
+                    CompressedCounter {
+                        authority: a,
+                        count: 42,
+                    }
+                    .try_to_vec()
+                    .unwrap(),
+                ]
+                .concat(),
+            ),
+        ],
+    );
+}
+
+// What the getters hand back as return data, which other programs decode
+// after a CPI.
+#[test]
+fn return_data() {
+    let version = Version {
+        major: 1,
+        minor: 2,
+        patch: 3,
+    };
+    check(
+        "return_data.txt",
+        &[
+            ("Version", version.try_to_vec().unwrap()),
+            (
+                "CounterState",
+                CounterState {
+                    count: 1,
+                    lifetime_count: 5,
+                    authority: address("a"),
+                    flags: Counter::ONCE_PER_SLOT | Counter::ARCHIVED,
+                    last_increment_slot: 2,
+                    alarm_threshold: 3,
+                    event_seq: 4,
+                    finalized: true,
+                    deny_cpi: true,
+                    role_weighted: true,
+                    version,
+                }
+                .try_to_vec()
+                .unwrap(),
+            ),
+            (
+                "TwacObservation",
+                TwacObservation {
+                    cumulative_count: u128::from(u64::MAX) + 1,
+                    slot: 6,
+                }
+                .try_to_vec()
+                .unwrap(),
+            ),
+            (
+                "IncrementPreview",
+                IncrementPreview {
+                    count: 7,
+                    lifetime_count: 8,
+                    fee: 1_000,
+                    referrer_share: 250,
+                    alarm_triggered: true,
+                    version,
+                }
+                .try_to_vec()
+                .unwrap(),
+            ),
+        ],
+    );
+}
@@ -0,0 +1,204 @@
+// Counter groups (see group.rs), run through the program: one update
+// changes the rules for every member, and members only increment under
+// them.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, Group, GroupArgs, ACCESS_LOG_SEED, VAULT_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn guard(counter: Pubkey, authority: Pubkey) -> accounts::AuthorityGuard {
+    accounts::AuthorityGuard {
+        counter,
+        access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+        authority,
+        system_program: system_program::ID,
+    }
+}
+
+fn initialize_group(group: Pubkey, authority: Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeGroup {
+            group,
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeGroup {},
+    )
+}
+
+fn update_group(group: Pubkey, authority: Pubkey, paused: bool, fee_lamports: u64) -> Instruction {
+    ix(
+        accounts::UpdateGroup { group, authority },
+        instruction::UpdateGroup {
+            args: GroupArgs {
+                version: GroupArgs::VERSION,
+                paused,
+                fee_lamports,
+                deny_cpi: false,
+            },
+        },
+    )
+}
+
+fn join_group(counter: Pubkey, group: Pubkey, authority: Pubkey) -> Instruction {
+    ix(
+        accounts::JoinGroup {
+            guard: guard(counter, authority),
+            group,
+        },
+        instruction::JoinGroup {},
+    )
+}
+
+fn leave_group(counter: Pubkey, group: Pubkey, authority: Pubkey) -> Instruction {
+    ix(
+        accounts::LeaveGroup {
+            guard: guard(counter, authority),
+            group,
+        },
+        instruction::LeaveGroup {},
+    )
+}
+
+fn increment_grouped(counter: Pubkey, group: Pubkey, payer: Pubkey) -> Instruction {
+    ix(
+        accounts::IncrementGrouped {
+            payment: accounts::FeePayment {
+                counter,
+                vault: pda(&[VAULT_SEED, counter.as_ref()]),
+                payer,
+                system_program: system_program::ID,
+            },
+            group,
+        },
+        instruction::IncrementGrouped {},
+    )
+}
+
+fn increment(counter: Pubkey) -> Instruction {
+    ix(accounts::Increment { counter }, instruction::Increment {})
+}
+
+// A group run by "authority", with "count" of their counters in it.
+fn group_of(svm: &mut Svm, authority: Pubkey, count: usize) -> (Pubkey, Vec<Pubkey>) {
+    let group = Pubkey::new_unique();
+    assert_eq!(svm.process(&initialize_group(group, authority)), Ok(()));
+    let members = (0..count)
+        .map(|_| {
+            let counter = Pubkey::new_unique();
+            svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+            assert_eq!(svm.process(&join_group(counter, group, authority)), Ok(()));
+            counter
+        })
+        .collect();
+    (group, members)
+}
+
+#[test]
+fn one_update_changes_every_member() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let payer = svm.wallet();
+    let (group, members) = group_of(&mut svm, authority, 3);
+    assert_eq!(svm.load::<Group>(&group).unwrap().members, 3);
+
+    // Only the group's authority changes its rules.
+    assert_eq!(
+        svm.process(&update_group(group, payer, false, 1_000)),
+        Err(Error::from(CounterError::Unauthorized).into())
+    );
+    assert_eq!(
+        svm.process(&update_group(group, authority, false, 1_000)),
+        Ok(())
+    );
+    for counter in &members {
+        assert_eq!(
+            svm.process(&increment_grouped(*counter, group, payer)),
+            Ok(())
+        );
+        // The fee created the vault, topped up to rent-exempt (see
+        // FeePayment::pay).
+        let vault = pda(&[VAULT_SEED, counter.as_ref()]);
+        assert_eq!(
+            svm.get(&vault).unwrap().lamports,
+            Rent::default().minimum_balance(0)
+        );
+        assert_eq!(svm.load::<Counter>(counter).unwrap().count, 1);
+    }
+
+    assert_eq!(
+        svm.process(&update_group(group, authority, true, 1_000)),
+        Ok(())
+    );
+    for counter in &members {
+        assert_eq!(
+            svm.process(&increment_grouped(*counter, group, payer)),
+            Err(Error::from(CounterError::GroupPaused).into())
+        );
+    }
+}
+
+#[test]
+fn members_only_increment_through_their_group() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let (group, members) = group_of(&mut svm, authority, 1);
+    let (other, _) = group_of(&mut svm, authority, 0);
+    let counter = members[0];
+
+    assert_eq!(
+        svm.process(&increment(counter)),
+        Err(Error::from(CounterError::GroupRequired).into())
+    );
+    assert_eq!(
+        svm.process(&increment_grouped(counter, other, authority)),
+        Err(Error::from(CounterError::WrongGroup).into())
+    );
+    // One group at a time.
+    assert_eq!(
+        svm.process(&join_group(counter, other, authority)),
+        Err(Error::from(CounterError::ConflictingModes).into())
+    );
+    assert_eq!(
+        svm.process(&leave_group(counter, other, authority)),
+        Err(Error::from(CounterError::WrongGroup).into())
+    );
+
+    assert_eq!(svm.process(&leave_group(counter, group, authority)), Ok(()));
+    assert_eq!(svm.load::<Group>(&group).unwrap().members, 0);
+    assert_eq!(svm.process(&increment(counter)), Ok(()));
+    assert_eq!(
+        svm.process(&increment_grouped(counter, group, authority)),
+        Err(Error::from(CounterError::WrongGroup).into())
+    );
+}
+
+#[test]
+fn counters_only_join_groups_their_owner_runs() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let stranger = svm.wallet();
+    let (theirs, _) = group_of(&mut svm, stranger, 0);
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+
+    // Into someone else's group, or by someone else.
+    assert_eq!(
+        svm.process(&join_group(counter, theirs, authority)),
+        Err(Error::from(CounterError::Unauthorized).into())
+    );
+    assert_eq!(
+        svm.process(&join_group(counter, theirs, stranger)),
+        Err(Error::from(CounterError::Unauthorized).into())
+    );
+}
@@ -0,0 +1,79 @@
+// Accounts in the states tests need, built directly instead of by replaying
+// the instructions that would lead there. A counter one increment away from
+// overflowing would take u64::MAX increments to reach; here it's a struct
+// literal.
+//
+// Everything is deterministic: addresses come from labels (see address),
+// not Pubkey::new_unique, so the same fixture is the same bytes at the same
+// address in every run and every test binary.
+//
+// Older account versions are the current one cut short: each layout in
+// COUNTER_LAYOUTS only added fields at the end (see migrate.rs), so an old
+// counter is a new one's first COUNTER_LAYOUTS[layout] bytes.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::AccountSerialize;
+use counter::{Config, Counter, CONFIG_SEED, COUNTER_LAYOUTS};
+
+use super::Svm;
+
+// The index of the current layout in COUNTER_LAYOUTS.
+pub const CURRENT: usize = COUNTER_LAYOUTS.len() - 1;
+
+// A fixed address for "label": the same label always gives the same one.
+pub fn address(label: &str) -> Pubkey {
+    Pubkey::new_from_array(hash(label.as_bytes()).to_bytes())
+}
+
+// A counter as initialize leaves it.
+pub fn counter(authority: Pubkey) -> Counter {
+    Counter {
+        authority,
+        ..Default::default()
+    }
+}
+
+// A counter that has been incremented "count" times.
+pub fn counter_at(authority: Pubkey, count: u64) -> Counter {
+    Counter {
+        count,
+        lifetime_count: count,
+        ..counter(authority)
+    }
+}
+
+// A counter one increment away from the largest count there is: the next
+// increment works, the one after overflows.
+pub fn near_overflow(authority: Pubkey) -> Counter {
+    counter_at(authority, u64::MAX - 1)
+}
+
+// "counter"'s account data in one of COUNTER_LAYOUTS. Fields that layout
+// didn't have yet are dropped.
+pub fn counter_data(counter: &Counter, layout: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    counter.try_serialize(&mut data).unwrap();
+    data.truncate(COUNTER_LAYOUTS[layout]);
+    data
+}
+
+// The program's Config PDA with "admin" as its admin.
+pub fn config(admin: Pubkey) -> (Pubkey, Config) {
+    let (address, bump) = Pubkey::find_program_address(&[CONFIG_SEED], &counter::ID);
+    (address, Config { admin, bump })
+}
+
+impl Svm {
+    // Stores "counter" at "address", in one of COUNTER_LAYOUTS.
+    pub fn set_counter(&mut self, address: Pubkey, counter: &Counter, layout: usize) {
+        self.set_data(address, counter::ID, counter_data(counter, layout));
+    }
+
+    // Stores the Config PDA with "admin" as its admin, and returns its
+    // address.
+    pub fn set_config(&mut self, admin: Pubkey) -> Pubkey {
+        let (address, config) = config(admin);
+        self.set_anchor(address, &config);
+        address
+    }
+}
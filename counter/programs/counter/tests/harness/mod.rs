@@ -0,0 +1,605 @@
+// An in-process stand-in for the Solana runtime, for Rust tests that need
+// real AccountInfos: Anchor's account checks, and anything else that can't
+// run on plain structs.
+//
+// The program is compiled for the host, so there's no BPF VM here. What the
+// runtime would provide is filled in instead:
+//
+//   - accounts live in a Svm (address -> Stored), and each call lays them
+//     out in memory the way the runtime does, so AccountInfo::realloc and
+//     AccountInfo::assign work on them
+//   - the Rent and Clock sysvars come from syscall stubs (see Stubs); the
+//     clock is whatever the test set, usually through TimeMachine (see
+//     time.rs)
+//   - return data (see Svm::return_data) is kept by the stubs too
+//   - the counter program is the current build, unless a test upgrades it
+//     to another one, such as an old release's (see Svm::upgrade_counter
+//     and tests/builds)
+//   - CPIs to the System Program (create_account, transfer, allocate,
+//     assign) are carried out by the stubs. CPIs to a program a test
+//     stubbed out (see Svm::stub_program) are only recorded, for the test
+//     to check. CPIs to any other program fail, so tests that need those
+//     stay in tests/litesvm.
+//
+// Each test binary that uses this declares "mod harness;". Not every
+// binary uses every helper, hence the allow.
+#![allow(dead_code)]
+
+pub mod fixtures;
+pub mod time;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_utils::limited_deserialize,
+    system_instruction::{SystemError, SystemInstruction},
+    system_program, sysvar,
+};
+use anchor_lang::{AccountSerialize, Discriminator, InstructionData};
+
+// An account at rest: what the runtime keeps between transactions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stored {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+// A build of the counter program: its entrypoint, compiled for the host.
+pub type Build = for<'a> fn(&Pubkey, &'a [AccountInfo<'a>], &[u8]) -> ProgramResult;
+
+#[derive(Clone)]
+pub struct Svm {
+    accounts: HashMap<Pubkey, Stored>,
+    clock: Clock,
+    // The build deployed at counter::ID: the current one, unless a test
+    // upgraded to another (see upgrade_counter).
+    build: Build,
+    // Programs whose CPIs are recorded instead of run (see stub_program).
+    stubbed: Vec<Pubkey>,
+}
+
+impl Default for Svm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Svm {
+    // Starts with the counter program, the System Program and the token
+    // programs deployed, and the clock at slot 1, 2024-01-01 00:00 UTC.
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut svm = Svm {
+            accounts: HashMap::new(),
+            clock: time::clock_at(1),
+            build: counter::entry,
+            stubbed: Vec::new(),
+        };
+        for program in [
+            system_program::ID,
+            anchor_spl::token::ID,
+            anchor_spl::token_2022::ID,
+            anchor_spl::associated_token::ID,
+        ] {
+            svm.set_program(program);
+        }
+        svm.deploy_counter(None);
+        svm
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<&Stored> {
+        self.accounts.get(address)
+    }
+
+    pub fn get_mut(&mut self, address: &Pubkey) -> Option<&mut Stored> {
+        self.accounts.get_mut(address)
+    }
+
+    pub fn set(&mut self, address: Pubkey, account: Stored) {
+        self.accounts.insert(address, account);
+    }
+
+    // Deserializes the Anchor account at "address".
+    pub fn load<T: AccountDeserialize>(&self, address: &Pubkey) -> Option<T> {
+        let account = self.get(address)?;
+        T::try_deserialize(&mut &account.data[..]).ok()
+    }
+
+    // Stores "account" at "address", owned by "owner" and rent-exempt.
+    pub fn set_data(&mut self, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set(
+            address,
+            Stored {
+                lamports,
+                data,
+                owner,
+                executable: false,
+            },
+        );
+    }
+
+    // Stores an Anchor account of this program at "address".
+    pub fn set_anchor<T: AccountSerialize + Owner>(&mut self, address: Pubkey, account: &T) {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        self.set_data(address, T::owner(), data);
+    }
+
+    // Stores a zero-copy account: its discriminator, then "space - 8"
+    // zeroes.
+    pub fn set_zeroed<T: Discriminator + Owner>(&mut self, address: Pubkey, space: usize) {
+        let mut data = vec![0; space];
+        data[..8].copy_from_slice(&T::DISCRIMINATOR);
+        self.set_data(address, T::owner(), data);
+    }
+
+    // Gives "address" lamports, creating it as a system account if needed.
+    pub fn airdrop(&mut self, address: Pubkey, lamports: u64) {
+        let account = self.accounts.entry(address).or_insert_with(|| Stored {
+            owner: system_program::ID,
+            ..Stored::default()
+        });
+        account.lamports += lamports;
+    }
+
+    // A funded system account, for payers and signers.
+    pub fn wallet(&mut self) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.airdrop(wallet, 10_000_000_000);
+        wallet
+    }
+
+    pub fn set_program(&mut self, program: Pubkey) {
+        self.set(
+            program,
+            Stored {
+                lamports: 1,
+                data: Vec::new(),
+                owner: bpf_loader_upgradeable::ID,
+                executable: true,
+            },
+        );
+    }
+
+    // Deploys "program" as a stand-in: CPIs to it succeed without running
+    // anything, once their signatures check out, and cpis() returns them.
+    // For programs like Light's, whose builds these tests don't have.
+    pub fn stub_program(&mut self, program: Pubkey) {
+        self.set_program(program);
+        self.stubbed.push(program);
+    }
+
+    // The CPIs the last instruction on this thread made to stubbed
+    // programs, in order.
+    pub fn cpis(&self) -> Vec<Instruction> {
+        CPIS.with(|cpis| cpis.borrow().clone())
+    }
+
+    // Deploys the counter program the way the upgradeable loader does: a
+    // program account pointing at its ProgramData, which names the upgrade
+    // authority (none if "upgrade_authority" is None).
+    pub fn deploy_counter(&mut self, upgrade_authority: Option<Pubkey>) {
+        let program_data = program_data_address(&counter::ID);
+
+        // bincode for UpgradeableLoaderState::Program: a u32 variant, then
+        // the fields.
+        let mut program = 2u32.to_le_bytes().to_vec();
+        program.extend_from_slice(program_data.as_ref());
+        self.set(
+            counter::ID,
+            Stored {
+                lamports: 1,
+                data: program,
+                owner: bpf_loader_upgradeable::ID,
+                executable: true,
+            },
+        );
+
+        // UpgradeableLoaderState::ProgramData { slot, upgrade_authority }.
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        match upgrade_authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.push(0),
+        }
+        self.set_data(program_data, bpf_loader_upgradeable::ID, data);
+    }
+
+    // Replaces the counter program's code with "build", as an upgrade
+    // through the loader does: the program keeps its address, its accounts
+    // and its upgrade authority, and its ProgramData records the slot.
+    pub fn upgrade_counter(&mut self, build: Build) {
+        self.build = build;
+        let slot = self.clock.slot.to_le_bytes();
+        let program_data = program_data_address(&counter::ID);
+        self.get_mut(&program_data).unwrap().data[4..12].copy_from_slice(&slot);
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    // Runs "instruction" through the counter program's entrypoint. Like a
+    // failed transaction, a failed instruction changes nothing.
+    pub fn process(&mut self, instruction: &Instruction) -> ProgramResult {
+        let before = self.accounts.clone();
+        let build = self.build;
+        let result = self.with_accounts(instruction, |infos| {
+            build(&instruction.program_id, infos, &instruction.data)
+        });
+        if result.is_err() {
+            self.accounts = before;
+        }
+        result
+    }
+
+    // The return data the last instruction on this thread set, empty if
+    // it set none.
+    pub fn return_data(&self) -> Vec<u8> {
+        RETURN_DATA.with(|return_data| return_data.borrow().clone())
+    }
+
+    // Lays out the accounts "instruction" names, calls "f" with them in
+    // the instruction's order, and stores whatever "f" changed. Accounts
+    // that don't exist yet are passed as empty system accounts, like the
+    // runtime does, and sysvars are filled in from the stubs.
+    pub fn with_accounts<R>(
+        &mut self,
+        instruction: &Instruction,
+        f: impl for<'a> FnOnce(&'a [AccountInfo<'a>]) -> R,
+    ) -> R {
+        // One entry per distinct address, flagged as a signer (or writable)
+        // if any of its metas is.
+        let mut order: Vec<Pubkey> = Vec::new();
+        let mut flags: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+        for meta in &instruction.accounts {
+            let entry = flags.entry(meta.pubkey).or_insert_with(|| {
+                order.push(meta.pubkey);
+                (false, false)
+            });
+            entry.0 |= meta.is_signer;
+            entry.1 |= meta.is_writable;
+        }
+
+        let mut memory: Vec<Memory> = order
+            .iter()
+            .map(|address| Memory::new(*address, &self.stored_or_default(address)))
+            .collect();
+
+        let result = {
+            let infos: Vec<AccountInfo> = memory
+                .iter_mut()
+                .map(|memory| {
+                    let (is_signer, is_writable) = flags[&memory.header.key];
+                    memory.info(is_signer, is_writable)
+                })
+                .collect();
+            // Duplicates share one AccountInfo (its lamports and data are
+            // behind an Rc), as they do on-chain.
+            let ordered: Vec<AccountInfo> = instruction
+                .accounts
+                .iter()
+                .map(|meta| {
+                    let index = order.iter().position(|a| *a == meta.pubkey).unwrap();
+                    infos[index].clone()
+                })
+                .collect();
+
+            CLOCK.with(|clock| *clock.borrow_mut() = self.clock.clone());
+            CALLER.with(|caller| *caller.borrow_mut() = instruction.program_id);
+            RETURN_DATA.with(|return_data| return_data.borrow_mut().clear());
+            STUBBED.with(|stubbed| *stubbed.borrow_mut() = self.stubbed.clone());
+            CPIS.with(|cpis| cpis.borrow_mut().clear());
+            f(&ordered)
+        };
+
+        for memory in memory {
+            let (address, stored) = memory.into_stored();
+            if stored != Stored::default() || self.accounts.contains_key(&address) {
+                self.accounts.insert(address, stored);
+            }
+        }
+        result
+    }
+
+    fn stored_or_default(&self, address: &Pubkey) -> Stored {
+        if let Some(stored) = self.accounts.get(address) {
+            return stored.clone();
+        }
+        if *address == sysvar::clock::ID {
+            return sysvar_account(clock_bytes(&self.clock));
+        }
+        if *address == sysvar::rent::ID {
+            return sysvar_account(rent_bytes(&Rent::default()));
+        }
+        if *address == sysvar::instructions::ID {
+            return sysvar_account(Vec::new());
+        }
+        Stored {
+            owner: system_program::ID,
+            ..Stored::default()
+        }
+    }
+}
+
+// An instruction to the counter program.
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: counter::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn program_data_address(program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+fn sysvar_account(data: Vec<u8>) -> Stored {
+    Stored {
+        lamports: 1,
+        data,
+        owner: sysvar::ID,
+        executable: false,
+    }
+}
+
+// bincode, which the sysvar accounts are encoded with, lays these out
+// field by field, little-endian.
+fn clock_bytes(clock: &Clock) -> Vec<u8> {
+    [
+        clock.slot.to_le_bytes(),
+        clock.epoch_start_timestamp.to_le_bytes(),
+        clock.epoch.to_le_bytes(),
+        clock.leader_schedule_epoch.to_le_bytes(),
+        clock.unix_timestamp.to_le_bytes(),
+    ]
+    .concat()
+}
+
+fn rent_bytes(rent: &Rent) -> Vec<u8> {
+    let mut data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+    data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+    data.push(rent.burn_percent);
+    data
+}
+
+// ----------------------------------------------------------------------------
+// Account memory
+// ----------------------------------------------------------------------------
+
+// The runtime hands programs their accounts in one buffer, and a few
+// AccountInfo methods rely on its layout: realloc writes the new length
+// into the 8 bytes before the data, and reads the length the data had when
+// the instruction started from the 4 bytes before the key. Each account
+// gets that layout here: a Header for the key, and a data buffer with its
+// length in front and room to grow behind.
+#[repr(C)]
+struct Header {
+    _padding: u32,
+    original_data_len: u32,
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    executable: bool,
+}
+
+struct Memory {
+    // Boxed so their addresses don't change while AccountInfos point at
+    // them. "data" is u64s so the length in front is aligned.
+    header: Box<Header>,
+    data: Box<[u64]>,
+}
+
+impl Memory {
+    fn new(address: Pubkey, stored: &Stored) -> Self {
+        let len = stored.data.len();
+        let words = 1 + (len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+        let mut memory = Memory {
+            header: Box::new(Header {
+                _padding: 0,
+                original_data_len: len as u32,
+                key: address,
+                owner: stored.owner,
+                lamports: stored.lamports,
+                executable: stored.executable,
+            }),
+            data: vec![0u64; words].into_boxed_slice(),
+        };
+        memory.data[0] = len as u64;
+        memory.bytes_mut()[8..8 + len].copy_from_slice(&stored.data);
+        memory
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        bytemuck::cast_slice_mut(&mut self.data)
+    }
+
+    fn info(&mut self, is_signer: bool, is_writable: bool) -> AccountInfo<'_> {
+        let len = self.data[0] as usize;
+        let data: &mut [u8] = bytemuck::cast_slice_mut(&mut self.data);
+        let header = &mut *self.header;
+        AccountInfo::new(
+            &header.key,
+            is_signer,
+            is_writable,
+            &mut header.lamports,
+            &mut data[8..8 + len],
+            &header.owner,
+            header.executable,
+            0,
+        )
+    }
+
+    fn into_stored(mut self) -> (Pubkey, Stored) {
+        // realloc may have changed the length in front of the data.
+        let len = self.data[0] as usize;
+        let data = self.bytes_mut()[8..8 + len].to_vec();
+        let header = &self.header;
+        (
+            header.key,
+            Stored {
+                lamports: header.lamports,
+                data,
+                owner: header.owner,
+                executable: header.executable,
+            },
+        )
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Syscall stubs
+// ----------------------------------------------------------------------------
+
+// The stubs are installed once for the whole test binary, but tests run on
+// several threads, so what they return comes from thread-locals that
+// Svm::with_accounts sets before each call.
+thread_local! {
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+    // The program being called, which signs for its PDAs in CPIs.
+    static CALLER: RefCell<Pubkey> = RefCell::new(Pubkey::default());
+    // What the last call set as its return data.
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    // The stubbed programs, and the CPIs made to them.
+    static STUBBED: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+    static CPIS: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    // Keeps test output readable; failures are reported through errors.
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = data.to_vec());
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = CLOCK.with(|clock| clock.borrow().clone());
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    // Every call is a top-level instruction.
+    fn sol_get_stack_height(&self) -> u64 {
+        1
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = CALLER.with(|caller| *caller.borrow());
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let info = account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?;
+                // A CPI can only sign with the caller's own signatures and
+                // its PDAs.
+                let signed = info.is_signer
+                    || signers_seeds.iter().any(|seeds| {
+                        Pubkey::create_program_address(seeds, &caller) == Ok(meta.pubkey)
+                    });
+                if meta.is_signer && !signed {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                Ok(info)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        if STUBBED.with(|stubbed| stubbed.borrow().contains(&instruction.program_id)) {
+            CPIS.with(|cpis| cpis.borrow_mut().push(instruction.clone()));
+            return Ok(());
+        }
+        if instruction.program_id != system_program::ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        system(&instruction.data, &accounts)
+    }
+}
+
+// The System Program instructions Anchor's "init" and transfers use.
+fn system(data: &[u8], accounts: &[&AccountInfo]) -> ProgramResult {
+    let instruction =
+        limited_deserialize(data, 1232).map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let (from, to) = (accounts[0], accounts[1]);
+            if to.lamports() != 0 || !to.data_is_empty() || *to.owner != system_program::ID {
+                return Err(ProgramError::Custom(
+                    SystemError::AccountAlreadyInUse as u32,
+                ));
+            }
+            move_lamports(from, to, lamports)?;
+            to.realloc(space as usize, true)?;
+            to.assign(&owner);
+            Ok(())
+        }
+        SystemInstruction::Transfer { lamports } => {
+            let (from, to) = (accounts[0], accounts[1]);
+            if *from.owner != system_program::ID || !from.data_is_empty() {
+                return Err(ProgramError::InvalidArgument);
+            }
+            move_lamports(from, to, lamports)
+        }
+        SystemInstruction::Allocate { space } => accounts[0].realloc(space as usize, true),
+        SystemInstruction::Assign { owner } => {
+            accounts[0].assign(&owner);
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let remaining = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::Custom(
+            SystemError::ResultWithNegativeLamports as u32,
+        ))?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// Moves the clock the program sees, so tests of anything time-based
+// (once-per-slot, lease expiry, commit and reveal deadlines, lockups, the
+// hourly stats buckets) can get to the moment they need instead of waiting
+// for it.
+//
+// Slots and unix time move together, as on a cluster running at the target
+// 400ms per slot: the time is a function of the slot (see clock_at), so any
+// slot always has the same time and tests stay deterministic. For a clock a
+// cluster would never show (a time that goes backwards, say), set it
+// directly with Svm::set_clock.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::{DEFAULT_MS_PER_SLOT, DEFAULT_SLOTS_PER_EPOCH};
+
+use super::Svm;
+
+// Unix time at slot 0: 2024-01-01 00:00 UTC.
+pub const GENESIS_TIMESTAMP: i64 = 1_704_067_200;
+
+// The clock at "slot".
+pub fn clock_at(slot: u64) -> Clock {
+    let epoch = slot / DEFAULT_SLOTS_PER_EPOCH;
+    Clock {
+        slot,
+        epoch_start_timestamp: timestamp_at(epoch * DEFAULT_SLOTS_PER_EPOCH),
+        epoch,
+        leader_schedule_epoch: epoch + 1,
+        unix_timestamp: timestamp_at(slot),
+    }
+}
+
+fn timestamp_at(slot: u64) -> i64 {
+    GENESIS_TIMESTAMP + (slot * DEFAULT_MS_PER_SLOT / 1000) as i64
+}
+
+// The first slot whose time is "unix_timestamp" or later.
+fn slot_at(unix_timestamp: i64) -> u64 {
+    let ms = (unix_timestamp - GENESIS_TIMESTAMP).max(0) as u64 * 1000;
+    ms.div_ceil(DEFAULT_MS_PER_SLOT)
+}
+
+pub trait TimeMachine {
+    // Jumps to "slot". Time only moves forward, so it can't be a slot
+    // that's already gone by.
+    fn warp_to_slot(&mut self, slot: u64);
+
+    fn advance_slots(&mut self, slots: u64);
+
+    // Jumps to the first slot at "unix_timestamp" or later.
+    fn warp_to_time(&mut self, unix_timestamp: i64);
+
+    fn advance_time(&mut self, seconds: i64);
+}
+
+impl TimeMachine for Svm {
+    fn warp_to_slot(&mut self, slot: u64) {
+        assert!(
+            slot >= self.clock().slot,
+            "can't warp back from slot {} to {slot}",
+            self.clock().slot
+        );
+        self.set_clock(clock_at(slot));
+    }
+
+    fn advance_slots(&mut self, slots: u64) {
+        self.warp_to_slot(self.clock().slot + slots);
+    }
+
+    fn warp_to_time(&mut self, unix_timestamp: i64) {
+        // Later than the current time, but maybe within the current slot.
+        let slot = slot_at(unix_timestamp).max(self.clock().slot);
+        self.warp_to_slot(slot);
+    }
+
+    fn advance_time(&mut self, seconds: i64) {
+        self.warp_to_time(self.clock().unix_timestamp + seconds);
+    }
+}
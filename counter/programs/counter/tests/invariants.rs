@@ -0,0 +1,95 @@
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use counter::{
+    accounts, check_counter, check_mirror_supply, check_replica, check_vault, instruction, Counter,
+    CounterError, Replica,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+#[test]
+fn each_broken_invariant_has_its_own_error() {
+    let mut counter = Counter {
+        count: 3,
+        lifetime_count: 3,
+        ..Default::default()
+    };
+    assert!(check_counter(&counter).is_ok());
+    counter.lifetime_count = 2;
+    assert_eq!(
+        check_counter(&counter).err(),
+        Some(Error::from(CounterError::LifetimeBelowCount))
+    );
+    counter.lifetime_count = 3;
+    counter.flags = Counter::TREE_LOGGED | Counter::FEE_REQUIRED;
+    assert_eq!(
+        check_counter(&counter).err(),
+        Some(Error::from(CounterError::ConflictingModes))
+    );
+
+    assert!(check_mirror_supply(&counter, 3).is_ok());
+    assert_eq!(
+        check_mirror_supply(&counter, 2).err(),
+        Some(Error::from(CounterError::MirrorSupplyMismatch))
+    );
+
+    assert!(check_vault(0, 890_880).is_ok());
+    assert!(check_vault(890_880, 890_880).is_ok());
+    assert_eq!(
+        check_vault(1, 890_880).err(),
+        Some(Error::from(CounterError::VaultBelowRent))
+    );
+}
+
+#[test]
+fn replicas_must_belong_and_not_overmerge() {
+    let root = Pubkey::new_unique();
+    let mut replica = Replica {
+        root,
+        id: 0,
+        count: 5,
+        merged: 5,
+        bump: 0,
+    };
+    assert!(check_replica(&replica, root).is_ok());
+    assert_eq!(
+        check_replica(&replica, Pubkey::new_unique()).err(),
+        Some(Error::from(CounterError::ReplicaRootMismatch))
+    );
+    replica.merged = 6;
+    assert_eq!(
+        check_replica(&replica, root).err(),
+        Some(Error::from(CounterError::ReplicaOvermerged))
+    );
+}
+
+// Test builds re-check invariants after every handler that changes a
+// counter (see debug_assert_invariants), so a counter that's already
+// broken fails the next increment instead of carrying on.
+#[test]
+fn handlers_recheck_invariants_in_test_builds() {
+    let mut svm = Svm::new();
+    let counter = fixtures::address("counter");
+    let increment = ix(accounts::Increment { counter }, instruction::Increment {});
+
+    svm.set_counter(
+        counter,
+        &fixtures::counter_at(Pubkey::default(), 3),
+        CURRENT,
+    );
+    assert_eq!(svm.process(&increment), Ok(()));
+
+    let broken = Counter {
+        lifetime_count: 2,
+        ..fixtures::counter_at(Pubkey::default(), 3)
+    };
+    svm.set_counter(counter, &broken, CURRENT);
+    assert_eq!(
+        svm.process(&increment),
+        Err(ProgramError::from(Error::from(
+            CounterError::LifetimeBelowCount
+        )))
+    );
+}
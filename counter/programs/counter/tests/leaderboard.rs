@@ -0,0 +1,230 @@
+// The Leaderboard (see leaderboard.rs): its ordering on its own, then fed
+// by increment and update_leaderboard, and cleared by
+// evict_leaderboard_entry, through the program.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use bytemuck::Zeroable;
+use counter::{
+    accounts, instruction, Counter, CounterError, Leaderboard, LEADERBOARD_SEED, LEADERBOARD_SIZE,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn ranked(leaderboard: &Leaderboard) -> Vec<(Pubkey, u64)> {
+    leaderboard
+        .ranked()
+        .iter()
+        .map(|entry| (entry.counter, entry.count))
+        .collect()
+}
+
+#[test]
+fn keeps_the_highest_counters_in_order() {
+    let mut board = Leaderboard::zeroed();
+    let (a, b, c) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    assert!(board.submit(a, 5));
+    assert!(board.submit(b, 9));
+    assert!(board.submit(c, 5));
+    // Ties keep whoever got there first ahead.
+    assert_eq!(ranked(&board), [(b, 9), (a, 5), (c, 5)]);
+
+    // Moving up, moving down, and not moving at all.
+    assert!(board.submit(c, 10));
+    assert!(board.submit(b, 1));
+    assert!(!board.submit(a, 5));
+    assert_eq!(ranked(&board), [(c, 10), (a, 5), (b, 1)]);
+
+    // A full board only takes counters above its lowest entry, which drop
+    // off.
+    let mut board = Leaderboard::zeroed();
+    for count in 1..=LEADERBOARD_SIZE as u64 {
+        board.submit(Pubkey::new_unique(), count);
+    }
+    let lowest = board.ranked()[LEADERBOARD_SIZE - 1];
+    assert!(!board.submit(Pubkey::new_unique(), lowest.count));
+    assert!(board.submit(a, 3));
+    assert_eq!(board.ranked().len(), LEADERBOARD_SIZE);
+    assert!(board
+        .ranked()
+        .iter()
+        .all(|entry| entry.counter != lowest.counter));
+    // Behind the counter already at 3.
+    assert_eq!(board.ranked()[LEADERBOARD_SIZE - 2].counter, a);
+    assert!(board.ranked().windows(2).all(|w| w[0].count >= w[1].count));
+}
+
+#[test]
+fn removing_closes_the_gap() {
+    let mut board = Leaderboard::zeroed();
+    let (a, b, c) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    board.submit(a, 3);
+    board.submit(b, 2);
+    board.submit(c, 1);
+
+    assert!(board.remove(a));
+    assert!(!board.remove(a));
+    assert_eq!(ranked(&board), [(b, 2), (c, 1)]);
+    // The freed slot takes the next counter in.
+    assert!(board.submit(a, 1));
+    assert_eq!(ranked(&board), [(b, 2), (c, 1), (a, 1)]);
+}
+
+fn leaderboard_address() -> Pubkey {
+    Pubkey::find_program_address(&[LEADERBOARD_SEED], &counter::ID).0
+}
+
+fn load_leaderboard(svm: &Svm) -> Leaderboard {
+    bytemuck::pod_read_unaligned(&svm.get(&leaderboard_address()).unwrap().data[8..])
+}
+
+fn increment(counter: Pubkey, leaderboard: Option<Pubkey>) -> Instruction {
+    let mut ix = ix(accounts::Increment { counter }, instruction::Increment {});
+    if let Some(leaderboard) = leaderboard {
+        ix.accounts.push(AccountMeta::new(leaderboard, false));
+    }
+    ix
+}
+
+fn initialize(svm: &mut Svm, payer: Pubkey) {
+    let initialize = ix(
+        accounts::InitializeLeaderboard {
+            leaderboard: leaderboard_address(),
+            payer,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeLeaderboard {},
+    );
+    assert_eq!(svm.process(&initialize), Ok(()));
+}
+
+fn update(counter: Pubkey) -> Instruction {
+    ix(
+        accounts::UpdateLeaderboard {
+            leaderboard: leaderboard_address(),
+            counter,
+        },
+        instruction::UpdateLeaderboard {},
+    )
+}
+
+fn evict(counter: Pubkey) -> Instruction {
+    ix(
+        accounts::EvictLeaderboardEntry {
+            leaderboard: leaderboard_address(),
+            counter,
+        },
+        instruction::EvictLeaderboardEntry {},
+    )
+}
+
+#[test]
+fn increments_and_updates_feed_the_board() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    initialize(&mut svm, payer);
+
+    let (low, high) = (fixtures::address("low"), fixtures::address("high"));
+    svm.set_counter(low, &fixtures::counter_at(payer, 3), CURRENT);
+    svm.set_counter(
+        high,
+        &Counter {
+            count: 40,
+            lifetime_count: 40,
+            ..fixtures::counter(payer)
+        },
+        CURRENT,
+    );
+
+    // Without the board, increment leaves it alone.
+    assert_eq!(svm.process(&increment(low, None)), Ok(()));
+    assert!(ranked(&load_leaderboard(&svm)).is_empty());
+
+    assert_eq!(
+        svm.process(&increment(low, Some(leaderboard_address()))),
+        Ok(())
+    );
+    assert_eq!(svm.process(&update(high)), Ok(()));
+    assert_eq!(ranked(&load_leaderboard(&svm)), [(high, 40), (low, 5)]);
+
+    // Anything else passed in its place is refused.
+    assert!(svm.process(&increment(low, Some(high))).is_err());
+}
+
+#[test]
+fn gifted_counters_stay_off() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    initialize(&mut svm, payer);
+    let gifted = fixtures::address("gifted");
+    svm.set_counter(
+        gifted,
+        &Counter {
+            count: u64::MAX - 1,
+            lifetime_count: u64::MAX - 1,
+            gifted_count: u64::MAX - 1,
+            ..fixtures::counter(payer)
+        },
+        CURRENT,
+    );
+
+    let refused = Err(Error::from(CounterError::GiftedCounter).into());
+    assert_eq!(svm.process(&update(gifted)), refused);
+    assert_eq!(
+        svm.process(&increment(gifted, Some(leaderboard_address()))),
+        refused
+    );
+    assert!(ranked(&load_leaderboard(&svm)).is_empty());
+}
+
+#[test]
+fn counters_that_are_gone_can_be_evicted() {
+    let mut svm = Svm::new();
+    let payer = svm.wallet();
+    initialize(&mut svm, payer);
+    let (live, gone, gifted) = (
+        fixtures::address("live"),
+        fixtures::address("gone"),
+        fixtures::address("gifted"),
+    );
+    for counter in [live, gone, gifted] {
+        svm.set_counter(counter, &fixtures::counter_at(payer, 7), CURRENT);
+        assert_eq!(svm.process(&update(counter)), Ok(()));
+    }
+
+    // A live counter can only be resubmitted.
+    assert_eq!(
+        svm.process(&evict(live)),
+        Err(Error::from(CounterError::LeaderboardEntryLive).into())
+    );
+
+    // One closed, handed back to the system program, and one a gift that
+    // got on before gifts were refused.
+    svm.set_data(gone, system_program::ID, Vec::new());
+    svm.set_counter(
+        gifted,
+        &Counter {
+            gifted_count: 7,
+            ..fixtures::counter_at(payer, 7)
+        },
+        CURRENT,
+    );
+    assert_eq!(svm.process(&evict(gone)), Ok(()));
+    assert_eq!(svm.process(&evict(gifted)), Ok(()));
+    assert_eq!(ranked(&load_leaderboard(&svm)), [(live, 7)]);
+
+    assert_eq!(
+        svm.process(&evict(gone)),
+        Err(Error::from(CounterError::NotOnLeaderboard).into())
+    );
+}
@@ -0,0 +1,46 @@
+// lifetime_count only ever grows: every change that adds to the count adds
+// to it too, and nothing that takes the count down touches it.
+use anchor_lang::error::Error;
+use counter::{Counter, CounterError};
+
+#[test]
+fn additions_count_towards_the_lifetime_count() {
+    let mut counter = Counter::default();
+    counter.increment().unwrap();
+    counter.add(4).unwrap();
+    assert_eq!((counter.count, counter.lifetime_count), (5, 5));
+
+    counter.subtract(2).unwrap();
+    assert_eq!((counter.count, counter.lifetime_count), (3, 5));
+    counter.increment().unwrap();
+    assert_eq!((counter.count, counter.lifetime_count), (4, 6));
+}
+
+#[test]
+fn reset_keeps_the_lifetime_count() {
+    let mut counter = Counter {
+        count: 7,
+        lifetime_count: 9,
+        ..Default::default()
+    };
+    assert_eq!(counter.reset(), 7);
+    assert_eq!((counter.count, counter.lifetime_count), (0, 9));
+
+    counter.add(2).unwrap();
+    assert_eq!((counter.count, counter.lifetime_count), (2, 11));
+}
+
+#[test]
+fn a_lifetime_overflow_changes_nothing() {
+    // A reset count can still grow long after the lifetime count is full.
+    let mut counter = Counter {
+        count: 1,
+        lifetime_count: u64::MAX,
+        ..Default::default()
+    };
+    assert_eq!(
+        counter.increment().err(),
+        Some(Error::from(CounterError::Overflow))
+    );
+    assert_eq!((counter.count, counter.lifetime_count), (1, u64::MAX));
+}
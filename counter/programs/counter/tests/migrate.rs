@@ -0,0 +1,59 @@
+use anchor_lang::{AccountSerialize, Discriminator};
+use counter::{
+    backfill_lifetime_count, counter_layout, Counter, COUNTER_LAYOUTS, LIFETIME_COUNT_LAYOUT,
+};
+
+fn current() -> Vec<u8> {
+    let mut data = Vec::new();
+    Counter::default().try_serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn the_last_layout_is_the_current_one() {
+    let data = current();
+    assert_eq!(data.len(), *COUNTER_LAYOUTS.last().unwrap());
+    assert_eq!(counter_layout(&data), Some(COUNTER_LAYOUTS.len() - 1));
+}
+
+#[test]
+fn finds_older_layouts_by_size() {
+    let data = current();
+    for (layout, &size) in COUNTER_LAYOUTS.iter().enumerate() {
+        assert_eq!(counter_layout(&data[..size]), Some(layout));
+    }
+    assert_eq!(counter_layout(&data[..COUNTER_LAYOUTS[0] - 1]), None);
+}
+
+#[test]
+fn needs_the_counter_discriminator() {
+    let mut data = current();
+    assert!(data.starts_with(&Counter::DISCRIMINATOR));
+    data[0] ^= 1;
+    assert_eq!(counter_layout(&data), None);
+}
+
+#[test]
+fn backfills_the_lifetime_count_from_the_count() {
+    let serialize = |counter: Counter| {
+        let mut data = Vec::new();
+        counter.try_serialize(&mut data).unwrap();
+        data
+    };
+    // What realloc leaves: the old layout, then zeroes.
+    let mut data = serialize(Counter {
+        count: 42,
+        ..Default::default()
+    });
+    data[COUNTER_LAYOUTS[LIFETIME_COUNT_LAYOUT - 1]..].fill(0);
+
+    backfill_lifetime_count(&mut data).unwrap();
+    assert_eq!(
+        data,
+        serialize(Counter {
+            count: 42,
+            lifetime_count: 42,
+            ..Default::default()
+        })
+    );
+}
@@ -0,0 +1,171 @@
+// preview_increment (see preview.rs), run through the program: it answers
+// what increment or increment_paid would, and leaves everything as it was.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use counter::{
+    accounts, instruction, Counter, CounterError, CounterFees, FeeExemption, FeeSchedule, FeeTier,
+    IncrementPreview, Version, FEES_SEED, FEE_EXEMPTION_SEED, FEE_SCHEDULE_SEED, SECONDS_PER_DAY,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &counter::ID)
+}
+
+// Which of the optional accounts to pass.
+#[derive(Default)]
+struct Optional {
+    fees: bool,
+    schedule: bool,
+    exemption: bool,
+}
+
+fn preview_increment(
+    counter: Pubkey,
+    payer: Pubkey,
+    optional: Optional,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    ix(
+        accounts::PreviewIncrement {
+            counter,
+            payer,
+            fees: optional.fees.then(|| pda(&[FEES_SEED, counter.as_ref()]).0),
+            fee_schedule: optional
+                .schedule
+                .then(|| pda(&[FEE_SCHEDULE_SEED, counter.as_ref()]).0),
+            fee_exemption: optional
+                .exemption
+                .then(|| pda(&[FEE_EXEMPTION_SEED, counter.as_ref(), payer.as_ref()]).0),
+        },
+        instruction::PreviewIncrement { referrer },
+    )
+}
+
+fn preview(svm: &mut Svm, instruction: &Instruction) -> IncrementPreview {
+    assert_eq!(svm.process(instruction), Ok(()));
+    IncrementPreview::try_from_slice(&svm.return_data()).unwrap()
+}
+
+#[test]
+fn previews_an_increment_without_making_it() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(
+        counter,
+        &Counter {
+            alarm_threshold: 10,
+            ..fixtures::counter_at(authority, 9)
+        },
+        CURRENT,
+    );
+    let before = svm.get(&counter).cloned();
+
+    let ix = preview_increment(counter, authority, Optional::default(), None);
+    assert_eq!(
+        preview(&mut svm, &ix),
+        IncrementPreview {
+            count: 10,
+            lifetime_count: 10,
+            fee: 0,
+            referrer_share: 0,
+            alarm_triggered: true,
+            version: Version::CURRENT,
+        }
+    );
+    assert_eq!(svm.get(&counter).cloned(), before);
+    assert!(ix.accounts.iter().all(|m| !m.is_signer && !m.is_writable));
+
+    // What increment would refuse, the preview refuses too.
+    svm.set_counter(
+        counter,
+        &Counter {
+            finalized: true,
+            ..fixtures::counter_at(authority, 9)
+        },
+        CURRENT,
+    );
+    assert_eq!(
+        svm.process(&ix),
+        Err(Error::from(CounterError::Finalized).into())
+    );
+}
+
+#[test]
+fn previews_what_increment_paid_would_charge() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let payer = fixtures::address("payer");
+    let counter = fixtures::address("counter");
+    let mut charging = fixtures::counter_at(authority, 4);
+    charging.set_flag(Counter::FEE_REQUIRED, true);
+    svm.set_counter(counter, &charging, CURRENT);
+    let (fees, bump) = pda(&[FEES_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        fees,
+        &CounterFees {
+            counter,
+            fee_lamports: 1_000,
+            referral_bps: 2_500,
+            bump,
+        },
+    );
+    let paid = |optional, referrer| preview_increment(counter, payer, optional, referrer);
+    let with = |schedule, exemption| Optional {
+        fees: true,
+        schedule,
+        exemption,
+    };
+
+    // Like increment, it needs the fees to preview a counter that charges.
+    assert_eq!(
+        svm.process(&paid(Optional::default(), None)),
+        Err(Error::from(CounterError::FeeRequired).into())
+    );
+    let flat = preview(&mut svm, &paid(with(false, false), None));
+    assert_eq!((flat.count, flat.fee, flat.referrer_share), (5, 1_000, 0));
+    let referred = preview(&mut svm, &paid(with(false, false), Some(authority)));
+    assert_eq!((referred.fee, referred.referrer_share), (1_000, 250));
+
+    // The day's first increment is free on this schedule; the preview
+    // doesn't count toward the day.
+    let (schedule, bump) = pda(&[FEE_SCHEDULE_SEED, counter.as_ref()]);
+    svm.set_anchor(
+        schedule,
+        &FeeSchedule {
+            counter,
+            tiers: vec![
+                FeeTier {
+                    from: 0,
+                    fee_lamports: 0,
+                },
+                FeeTier {
+                    from: 1,
+                    fee_lamports: 1_000,
+                },
+            ],
+            day: svm.clock().unix_timestamp.div_euclid(SECONDS_PER_DAY),
+            day_volume: 0,
+            bump,
+        },
+    );
+    for _ in 0..2 {
+        assert_eq!(preview(&mut svm, &paid(with(true, false), None)).fee, 0);
+    }
+
+    let (exemption, bump) = pda(&[FEE_EXEMPTION_SEED, counter.as_ref(), payer.as_ref()]);
+    svm.set_anchor(
+        exemption,
+        &FeeExemption {
+            counter,
+            wallet: payer,
+            bump,
+        },
+    );
+    assert_eq!(preview(&mut svm, &paid(with(false, true), None)).fee, 0);
+}
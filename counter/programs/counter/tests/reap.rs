@@ -0,0 +1,154 @@
+// Reaping abandoned counters (see reap.rs), run through the program with
+// the clock moved an epoch at a time.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::DEFAULT_SLOTS_PER_EPOCH;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{accounts, instruction, Counter, CounterError, REAP_POLICY_SEED, VAULT_SEED};
+use harness::fixtures::{self, CURRENT};
+use harness::time::TimeMachine;
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn advance_epochs(svm: &mut Svm, epochs: u64) {
+    svm.advance_slots(epochs * DEFAULT_SLOTS_PER_EPOCH);
+}
+
+fn initialize_reapable(
+    counter: Pubkey,
+    user: Pubkey,
+    idle_epochs: u64,
+    beneficiary: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::InitializeReapable {
+            counter,
+            reap_policy: pda(&[REAP_POLICY_SEED, counter.as_ref()]),
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeReapable {
+            idle_epochs,
+            beneficiary,
+        },
+    )
+}
+
+fn reap(counter: Pubkey, beneficiary: Pubkey) -> Instruction {
+    ix(
+        accounts::Reap {
+            counter,
+            reap_policy: pda(&[REAP_POLICY_SEED, counter.as_ref()]),
+            vault: pda(&[VAULT_SEED, counter.as_ref()]),
+            beneficiary,
+        },
+        instruction::Reap {},
+    )
+}
+
+// A counter that can be reaped after two idle epochs, and its beneficiary.
+fn reapable(svm: &mut Svm) -> (Pubkey, Pubkey) {
+    let (user, beneficiary) = (svm.wallet(), Pubkey::new_unique());
+    let counter = Pubkey::new_unique();
+    assert_eq!(
+        svm.process(&initialize_reapable(counter, user, 2, beneficiary)),
+        Ok(())
+    );
+    (counter, beneficiary)
+}
+
+fn lamports(svm: &Svm, address: &Pubkey) -> u64 {
+    svm.get(address).map_or(0, |account| account.lamports)
+}
+
+#[test]
+fn idle_counters_are_reaped_to_the_beneficiary() {
+    let mut svm = Svm::new();
+    let (counter, beneficiary) = reapable(&mut svm);
+    let policy = pda(&[REAP_POLICY_SEED, counter.as_ref()]);
+    let rent = lamports(&svm, &counter) + lamports(&svm, &policy);
+
+    // The first reap only takes a look, which starts the wait.
+    assert_eq!(svm.process(&reap(counter, beneficiary)), Ok(()));
+    assert!(svm.load::<Counter>(&counter).is_some());
+    advance_epochs(&mut svm, 1);
+    assert_eq!(
+        svm.process(&reap(counter, beneficiary)),
+        Err(error(CounterError::CounterNotIdle))
+    );
+
+    advance_epochs(&mut svm, 1);
+    assert_eq!(svm.process(&reap(counter, beneficiary)), Ok(()));
+    assert_eq!(lamports(&svm, &counter), 0);
+    assert_eq!(lamports(&svm, &policy), 0);
+    assert_eq!(lamports(&svm, &beneficiary), rent);
+}
+
+#[test]
+fn using_a_counter_restarts_the_wait() {
+    let mut svm = Svm::new();
+    let (counter, beneficiary) = reapable(&mut svm);
+    assert_eq!(svm.process(&reap(counter, beneficiary)), Ok(()));
+
+    advance_epochs(&mut svm, 1);
+    let increment = ix(accounts::Increment { counter }, instruction::Increment {});
+    assert_eq!(svm.process(&increment), Ok(()));
+    advance_epochs(&mut svm, 1);
+
+    // Two epochs since the first look, but it changed in between: reap
+    // notes the change instead of closing it.
+    assert_eq!(svm.process(&reap(counter, beneficiary)), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 1);
+    assert_eq!(
+        svm.process(&reap(counter, beneficiary)),
+        Err(error(CounterError::CounterNotIdle))
+    );
+    advance_epochs(&mut svm, 2);
+    assert_eq!(svm.process(&reap(counter, beneficiary)), Ok(()));
+    assert_eq!(lamports(&svm, &counter), 0);
+}
+
+#[test]
+fn only_opted_in_counters_with_empty_vaults_are_reaped() {
+    let mut svm = Svm::new();
+    let user = svm.wallet();
+    assert_eq!(
+        svm.process(&initialize_reapable(
+            Pubkey::new_unique(),
+            user,
+            0,
+            Pubkey::new_unique()
+        )),
+        Err(error(CounterError::InvalidReapPolicy))
+    );
+
+    // No policy: created with plain initialize.
+    let plain = fixtures::address("counter");
+    svm.set_counter(plain, &fixtures::counter(user), CURRENT);
+    assert_eq!(
+        svm.process(&reap(plain, user)),
+        Err(error(ErrorCode::AccountNotInitialized))
+    );
+
+    let (counter, beneficiary) = reapable(&mut svm);
+    assert_eq!(svm.process(&reap(counter, beneficiary)), Ok(()));
+    advance_epochs(&mut svm, 2);
+    assert_eq!(
+        svm.process(&reap(counter, user)),
+        Err(error(CounterError::Unauthorized))
+    );
+    svm.airdrop(pda(&[VAULT_SEED, counter.as_ref()]), 1_000_000);
+    assert_eq!(
+        svm.process(&reap(counter, beneficiary)),
+        Err(error(CounterError::VaultNotEmpty))
+    );
+}
@@ -0,0 +1,83 @@
+// Merging replicas (see replica.rs) into counters whose increments are
+// restricted: a merge counts like any increment, so it's held to the same
+// modes and cap. The merge itself is covered by tests/litesvm/replica.ts.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use counter::{accounts, instruction, Counter, CounterError, Replica, REPLICA_SEED};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+// A replica of "counter" that has counted "count" and merged none of it.
+fn replica(svm: &mut Svm, counter: Pubkey, count: u64) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(
+        &[REPLICA_SEED, counter.as_ref(), &0u16.to_le_bytes()],
+        &counter::ID,
+    );
+    svm.set_anchor(
+        address,
+        &Replica {
+            root: counter,
+            id: 0,
+            count,
+            merged: 0,
+            bump,
+        },
+    );
+    address
+}
+
+fn merge(counter: Pubkey, replica: Pubkey) -> Instruction {
+    let mut ix = ix(
+        accounts::MergeReplicas { counter },
+        instruction::MergeReplicas {},
+    );
+    ix.accounts.push(AccountMeta::new(replica, false));
+    ix
+}
+
+#[test]
+fn merges_only_land_in_unrouted_counters() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    let mut mirrored = fixtures::counter(authority);
+    mirrored.set_flag(Counter::TOKEN_MIRRORED, true);
+    svm.set_counter(counter, &mirrored, CURRENT);
+    let replica = replica(&mut svm, counter, 3);
+
+    // It would count without minting.
+    assert_eq!(
+        svm.process(&merge(counter, replica)),
+        Err(Error::from(CounterError::MintRequired).into())
+    );
+    svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+    assert_eq!(svm.process(&merge(counter, replica)), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 3);
+}
+
+#[test]
+fn merges_stop_at_the_cap() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    let capped = |max_count| Counter {
+        max_count,
+        overflow: fixtures::address("overflow"),
+        ..fixtures::counter_at(authority, 1)
+    };
+    svm.set_counter(counter, &capped(3), CURRENT);
+    let replica = replica(&mut svm, counter, 3);
+
+    assert_eq!(
+        svm.process(&merge(counter, replica)),
+        Err(Error::from(CounterError::SoftCapReached).into())
+    );
+    // Nothing was merged, so it all counts once there's room.
+    assert_eq!(svm.load::<Replica>(&replica).unwrap().merged, 0);
+    svm.set_counter(counter, &capped(4), CURRENT);
+    assert_eq!(svm.process(&merge(counter, replica)), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 4);
+}
@@ -0,0 +1,170 @@
+// Role-weighted counters (see roles.rs), run through the program: each
+// member's increments add their own weight, and nobody else can increment.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, ACCESS_LOG_SEED, LEASE_SEED, ROLE_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+// A counter owned by a fresh wallet, with role weighting turned on.
+fn role_weighted(svm: &mut Svm) -> (Pubkey, Pubkey) {
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(
+        counter,
+        &Counter {
+            role_weighted: true,
+            ..fixtures::counter(authority)
+        },
+        CURRENT,
+    );
+    (authority, counter)
+}
+
+fn set_role(counter: Pubkey, authority: Pubkey, member: Pubkey, weight: u64) -> Instruction {
+    ix(
+        accounts::SetRole {
+            guard: accounts::AuthorityGuard {
+                counter,
+                access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+                authority,
+                system_program: system_program::ID,
+            },
+            role: pda(&[ROLE_SEED, counter.as_ref(), member.as_ref()]),
+            system_program: system_program::ID,
+        },
+        instruction::SetRole { member, weight },
+    )
+}
+
+fn increment_by_role(counter: Pubkey, member: Pubkey) -> Instruction {
+    ix(
+        accounts::IncrementByRole {
+            counter,
+            role: pda(&[ROLE_SEED, counter.as_ref(), member.as_ref()]),
+            member,
+        },
+        instruction::IncrementByRole {},
+    )
+}
+
+fn update_counter(counter: Pubkey, authority: Pubkey) -> accounts::UpdateCounter {
+    accounts::UpdateCounter {
+        counter,
+        lease: pda(&[LEASE_SEED, counter.as_ref()]),
+        access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+        authority,
+        system_program: system_program::ID,
+    }
+}
+
+#[test]
+fn members_add_their_own_weight() {
+    let mut svm = Svm::new();
+    let (authority, counter) = role_weighted(&mut svm);
+    let (admin, member, stranger) = (svm.wallet(), svm.wallet(), svm.wallet());
+    assert_eq!(
+        svm.process(&set_role(counter, authority, admin, 10)),
+        Ok(())
+    );
+    assert_eq!(
+        svm.process(&set_role(counter, authority, member, 1)),
+        Ok(())
+    );
+
+    assert_eq!(svm.process(&increment_by_role(counter, admin)), Ok(()));
+    assert_eq!(svm.process(&increment_by_role(counter, member)), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 11);
+
+    // No role, no increment: not through increment_by_role, and not
+    // through plain increment either.
+    assert_eq!(
+        svm.process(&increment_by_role(counter, stranger)),
+        Err(error(ErrorCode::AccountNotInitialized))
+    );
+    assert_eq!(
+        svm.process(&ix(
+            accounts::Increment { counter },
+            instruction::Increment {}
+        )),
+        Err(error(CounterError::RoleRequired))
+    );
+}
+
+#[test]
+fn weight_zero_takes_the_role_away() {
+    let mut svm = Svm::new();
+    let (authority, counter) = role_weighted(&mut svm);
+    let member = svm.wallet();
+    let role = pda(&[ROLE_SEED, counter.as_ref(), member.as_ref()]);
+    assert_eq!(
+        svm.process(&set_role(counter, authority, member, 3)),
+        Ok(())
+    );
+    assert_eq!(
+        svm.process(&set_role(counter, authority, member, 5)),
+        Ok(())
+    );
+    assert_eq!(svm.process(&increment_by_role(counter, member)), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 5);
+
+    assert_eq!(
+        svm.process(&set_role(counter, authority, member, 0)),
+        Ok(())
+    );
+    assert_eq!(svm.get(&role).map_or(0, |role| role.lamports), 0);
+    assert_eq!(
+        svm.process(&increment_by_role(counter, member)),
+        Err(error(ErrorCode::AccountNotInitialized))
+    );
+}
+
+#[test]
+fn role_weighting_is_one_of_the_routes() {
+    let mut svm = Svm::new();
+    let (authority, counter) = role_weighted(&mut svm);
+    let set_tree_logged = ix(
+        update_counter(counter, authority),
+        instruction::SetTreeLogged { enabled: true },
+    );
+    assert_eq!(
+        svm.process(&set_tree_logged),
+        Err(error(CounterError::ConflictingModes))
+    );
+
+    let set_role_weighted = |enabled| {
+        ix(
+            update_counter(counter, authority),
+            instruction::SetRoleWeighted { enabled },
+        )
+    };
+    assert_eq!(svm.process(&set_role_weighted(false)), Ok(()));
+    assert_eq!(svm.process(&set_tree_logged), Ok(()));
+    assert_eq!(
+        svm.process(&set_role_weighted(true)),
+        Err(error(CounterError::ConflictingModes))
+    );
+
+    let member = svm.wallet();
+    assert_eq!(
+        svm.process(&set_role(counter, authority, member, 1)),
+        Ok(())
+    );
+    assert_eq!(
+        svm.process(&increment_by_role(counter, member)),
+        Err(error(CounterError::NotRoleWeighted))
+    );
+}
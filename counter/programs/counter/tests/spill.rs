@@ -0,0 +1,199 @@
+// Soft caps (see spill.rs), run through the program: a counter stops at
+// its max_count, and increment_spilling counts the rest on its overflow.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, Route, SoftCapArgs, ACCESS_LOG_SEED, LEASE_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn set_soft_cap(
+    counter: Pubkey,
+    authority: Pubkey,
+    max_count: u64,
+    overflow: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::UpdateCounter {
+            counter,
+            lease: pda(&[LEASE_SEED, counter.as_ref()]),
+            access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetSoftCap {
+            args: SoftCapArgs {
+                version: SoftCapArgs::VERSION,
+                max_count,
+                overflow,
+            },
+        },
+    )
+}
+
+fn increment_spilling(counter: Pubkey, overflow: Pubkey) -> Instruction {
+    ix(
+        accounts::IncrementSpilling { counter, overflow },
+        instruction::IncrementSpilling {},
+    )
+}
+
+fn increment(counter: Pubkey) -> Instruction {
+    ix(accounts::Increment { counter }, instruction::Increment {})
+}
+
+// A counter at 1 and an empty overflow, both owned by a fresh wallet.
+fn counters(svm: &mut Svm) -> (Pubkey, Pubkey, Pubkey) {
+    let authority = svm.wallet();
+    let (primary, overflow) = (fixtures::address("primary"), fixtures::address("overflow"));
+    svm.set_counter(primary, &fixtures::counter_at(authority, 1), CURRENT);
+    svm.set_counter(overflow, &fixtures::counter(authority), CURRENT);
+    (authority, primary, overflow)
+}
+
+fn count(svm: &Svm, counter: Pubkey) -> u64 {
+    svm.load::<Counter>(&counter).unwrap().count
+}
+
+#[test]
+fn increments_past_the_cap_spill() {
+    let mut svm = Svm::new();
+    let (authority, primary, overflow) = counters(&mut svm);
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, authority, 3, overflow)),
+        Ok(())
+    );
+
+    // Below the cap, both ways count on the primary.
+    assert_eq!(svm.process(&increment(primary)), Ok(()));
+    assert_eq!(svm.process(&increment_spilling(primary, overflow)), Ok(()));
+    assert_eq!((count(&svm, primary), count(&svm, overflow)), (3, 0));
+
+    // At it, plain increments are refused and the rest spill.
+    assert_eq!(
+        svm.process(&increment(primary)),
+        Err(error(CounterError::SoftCapReached))
+    );
+    for _ in 0..2 {
+        assert_eq!(svm.process(&increment_spilling(primary, overflow)), Ok(()));
+    }
+    assert_eq!((count(&svm, primary), count(&svm, overflow)), (3, 2));
+
+    // Raising the cap makes room again; 0 takes it off.
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, authority, 4, overflow)),
+        Ok(())
+    );
+    assert_eq!(svm.process(&increment_spilling(primary, overflow)), Ok(()));
+    assert_eq!((count(&svm, primary), count(&svm, overflow)), (4, 2));
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, authority, 0, overflow)),
+        Ok(())
+    );
+    assert_eq!(svm.process(&increment(primary)), Ok(()));
+    let state = svm.load::<Counter>(&primary).unwrap();
+    assert_eq!((state.count, state.overflow), (5, Pubkey::default()));
+}
+
+#[test]
+fn the_overflow_keeps_its_own_rules() {
+    let mut svm = Svm::new();
+    let (authority, primary, overflow) = counters(&mut svm);
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, authority, 1, overflow)),
+        Ok(())
+    );
+    svm.set_counter(
+        overflow,
+        &Counter {
+            flags: Counter::ARCHIVED,
+            ..fixtures::counter(authority)
+        },
+        CURRENT,
+    );
+    assert_eq!(
+        svm.process(&increment_spilling(primary, overflow)),
+        Err(error(CounterError::Archived))
+    );
+
+    // A full overflow refuses instead of spilling further.
+    svm.set_counter(
+        overflow,
+        &Counter {
+            max_count: 1,
+            overflow: primary,
+            ..fixtures::counter_at(authority, 1)
+        },
+        CURRENT,
+    );
+    assert_eq!(
+        svm.process(&increment_spilling(primary, overflow)),
+        Err(error(CounterError::SoftCapReached))
+    );
+}
+
+#[test]
+fn routed_counters_stop_at_the_cap() {
+    // Only increment_by_role can increment it, and that doesn't spill.
+    let mut counter = Counter {
+        role_weighted: true,
+        max_count: 2,
+        ..fixtures::counter_at(fixtures::address("authority"), 1)
+    };
+    assert!(counter.check_route(Route::RoleWeighted).is_ok());
+    counter.count = 2;
+    assert_eq!(
+        counter.check_route(Route::RoleWeighted).err(),
+        Some(CounterError::SoftCapReached.into())
+    );
+}
+
+#[test]
+fn refuses_bad_overflows() {
+    let mut svm = Svm::new();
+    let (authority, primary, overflow) = counters(&mut svm);
+    for bad in [primary, Pubkey::default()] {
+        assert_eq!(
+            svm.process(&set_soft_cap(primary, authority, 5, bad)),
+            Err(error(CounterError::InvalidSoftCap))
+        );
+    }
+    let stranger = svm.wallet();
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, stranger, 5, overflow)),
+        Err(error(CounterError::Unauthorized))
+    );
+
+    // The overflow has to be the one the cap names, and a counter.
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, authority, 5, overflow)),
+        Ok(())
+    );
+    let other = fixtures::address("other");
+    svm.set_counter(other, &fixtures::counter(authority), CURRENT);
+    assert_eq!(
+        svm.process(&increment_spilling(primary, other)),
+        Err(error(CounterError::InvalidSoftCap))
+    );
+    let not_a_counter = svm.wallet();
+    assert_eq!(
+        svm.process(&set_soft_cap(primary, authority, 5, not_a_counter)),
+        Ok(())
+    );
+    assert_eq!(
+        svm.process(&increment_spilling(primary, not_a_counter)),
+        Err(error(ErrorCode::AccountOwnedByWrongProgram))
+    );
+}
@@ -0,0 +1,187 @@
+// Sponsored counters (see sponsor.rs), run through the program: the
+// treasury pays their rent, up to each wallet's quota.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, SponsoredWallet, SPONSORED_WALLET_SEED,
+    SPONSORSHIP_SEED, TREASURY_SEED,
+};
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+const SOL: u64 = 1_000_000_000;
+
+fn set_sponsorship(admin: Pubkey, quota: u16) -> Instruction {
+    ix(
+        accounts::SetSponsorship {
+            config: pda(&[counter::CONFIG_SEED]),
+            sponsorship: pda(&[SPONSORSHIP_SEED]),
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetSponsorship { quota },
+    )
+}
+
+fn initialize_sponsored(counter: Pubkey, user: Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeSponsored {
+            sponsorship: pda(&[SPONSORSHIP_SEED]),
+            treasury: pda(&[TREASURY_SEED]),
+            sponsored_wallet: pda(&[SPONSORED_WALLET_SEED, user.as_ref()]),
+            counter,
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeSponsored {},
+    )
+}
+
+fn withdraw_treasury(admin: Pubkey, amount: u64) -> Instruction {
+    ix(
+        accounts::WithdrawTreasury {
+            config: pda(&[counter::CONFIG_SEED]),
+            treasury: pda(&[TREASURY_SEED]),
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawTreasury { amount },
+    )
+}
+
+fn lamports(svm: &Svm, address: &Pubkey) -> u64 {
+    svm.get(address).map_or(0, |account| account.lamports)
+}
+
+// A wallet without any SOL.
+fn broke(svm: &mut Svm) -> Pubkey {
+    let user = Pubkey::new_unique();
+    svm.airdrop(user, 0);
+    user
+}
+
+#[test]
+fn the_treasury_pays_up_to_the_quota() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    assert_eq!(svm.process(&set_sponsorship(admin, 2)), Ok(()));
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, SOL);
+
+    let user = broke(&mut svm);
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert_eq!(svm.process(&initialize_sponsored(first, user)), Ok(()));
+    assert_eq!(svm.process(&initialize_sponsored(second, user)), Ok(()));
+    assert_eq!(
+        svm.process(&initialize_sponsored(Pubkey::new_unique(), user)),
+        Err(error(CounterError::SponsorQuotaReached))
+    );
+
+    let counter = svm.load::<Counter>(&second).unwrap();
+    assert_eq!((counter.authority, counter.count), (user, 0));
+    let wallet = pda(&[SPONSORED_WALLET_SEED, user.as_ref()]);
+    assert_eq!(svm.load::<SponsoredWallet>(&wallet).unwrap().count, 2);
+    assert_eq!(lamports(&svm, &user), 0);
+    assert_eq!(
+        lamports(&svm, &treasury),
+        SOL - lamports(&svm, &first) - lamports(&svm, &second) - lamports(&svm, &wallet)
+    );
+
+    // Quotas are per wallet.
+    let other = broke(&mut svm);
+    assert_eq!(
+        svm.process(&initialize_sponsored(Pubkey::new_unique(), other)),
+        Ok(())
+    );
+}
+
+#[test]
+fn a_prefunded_wallet_address_still_gets_sponsored() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    assert_eq!(svm.process(&set_sponsorship(admin, 1)), Ok(()));
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, SOL);
+
+    // Anyone can send a lamport to where the user's SponsoredWallet goes,
+    // which create_account would refuse.
+    let user = broke(&mut svm);
+    let wallet = pda(&[SPONSORED_WALLET_SEED, user.as_ref()]);
+    svm.airdrop(wallet, 1);
+    let counter = Pubkey::new_unique();
+    assert_eq!(svm.process(&initialize_sponsored(counter, user)), Ok(()));
+
+    let stored = svm.get(&wallet).unwrap();
+    assert_eq!(stored.owner, counter::ID);
+    assert_eq!(
+        stored.lamports,
+        Rent::default().minimum_balance(stored.data.len())
+    );
+    assert_eq!(svm.load::<SponsoredWallet>(&wallet).unwrap().count, 1);
+    assert_eq!(
+        lamports(&svm, &treasury),
+        SOL - lamports(&svm, &counter) - (lamports(&svm, &wallet) - 1)
+    );
+}
+
+#[test]
+fn sponsoring_stops_when_turned_off_or_out_of_funds() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    let user = broke(&mut svm);
+    assert_eq!(
+        svm.process(&initialize_sponsored(Pubkey::new_unique(), user)),
+        Err(error(ErrorCode::AccountNotInitialized))
+    );
+
+    assert_eq!(svm.process(&set_sponsorship(admin, 1)), Ok(()));
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, 2_000_000);
+    assert_eq!(
+        svm.process(&initialize_sponsored(Pubkey::new_unique(), user)),
+        Err(error(CounterError::TreasuryTooLow))
+    );
+
+    assert_eq!(svm.process(&set_sponsorship(admin, 0)), Ok(()));
+    svm.airdrop(treasury, SOL);
+    assert_eq!(
+        svm.process(&initialize_sponsored(Pubkey::new_unique(), user)),
+        Err(error(CounterError::SponsorQuotaReached))
+    );
+}
+
+#[test]
+fn only_the_admin_manages_the_treasury() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    let stranger = svm.wallet();
+    assert_eq!(
+        svm.process(&set_sponsorship(stranger, 5)),
+        Err(error(CounterError::Unauthorized))
+    );
+
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, SOL);
+    assert_eq!(
+        svm.process(&withdraw_treasury(stranger, SOL / 2)),
+        Err(error(CounterError::Unauthorized))
+    );
+    let before = lamports(&svm, &admin);
+    assert_eq!(svm.process(&withdraw_treasury(admin, SOL / 2)), Ok(()));
+    assert_eq!(lamports(&svm, &admin), before + SOL / 2);
+    assert_eq!(lamports(&svm, &treasury), SOL / 2);
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::Pubkey;
+use counter::{StatsBucket, ACTOR_BITS};
+
+#[test]
+fn buckets_count_increments_actors_and_fees() {
+    let mut bucket = StatsBucket {
+        counter: Pubkey::default(),
+        unix_hour: 0,
+        increments: 0,
+        unique_actors: 0,
+        fee_revenue: 0,
+        actors: [0; ACTOR_BITS / 8],
+        bump: 0,
+    };
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    bucket.add(&alice, 10);
+    bucket.add(&alice, 10);
+    bucket.add(&bob, 5);
+
+    assert_eq!(bucket.increments, 3);
+    assert_eq!(bucket.fee_revenue, 25);
+    // Alice only counts once. Bob could share her bit, but that's a 1 in
+    // 1024 chance and these keys are fixed.
+    assert_eq!(bucket.unique_actors, 2);
+    let bits: u32 = bucket.actors.iter().map(|b| b.count_ones()).sum();
+    assert_eq!(bits, bucket.unique_actors);
+}
@@ -0,0 +1,103 @@
+// sweep_fees (see sweep.rs), run through the program: many vaults emptied
+// into the treasury at once, and only by their counters' owner.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    system_program,
+};
+use counter::{accounts, instruction, CounterError, TREASURY_SEED, VAULT_SEED};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, Svm};
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn vault(counter: Pubkey) -> Pubkey {
+    pda(&[VAULT_SEED, counter.as_ref()])
+}
+
+fn sweep_fees(authority: Pubkey, pairs: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut ix = ix(
+        accounts::SweepFees {
+            treasury: pda(&[TREASURY_SEED]),
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::SweepFees {},
+    );
+    for &(counter, vault) in pairs {
+        ix.accounts.push(AccountMeta::new(counter, false));
+        ix.accounts.push(AccountMeta::new(vault, false));
+    }
+    ix
+}
+
+fn lamports(svm: &Svm, address: Pubkey) -> u64 {
+    svm.get(&address).map_or(0, |account| account.lamports)
+}
+
+// "authority"'s counters, with "fees" lamports in each one's vault.
+fn setup(svm: &mut Svm, authority: Pubkey, fees: &[u64]) -> Vec<Pubkey> {
+    fees.iter()
+        .enumerate()
+        .map(|(i, &fee)| {
+            let counter = fixtures::address(&format!("counter{i}"));
+            svm.set_counter(counter, &fixtures::counter(authority), CURRENT);
+            if fee != 0 {
+                svm.airdrop(vault(counter), fee);
+            }
+            counter
+        })
+        .collect()
+}
+
+#[test]
+fn empties_every_vault_into_the_treasury() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let treasury = pda(&[TREASURY_SEED]);
+    svm.airdrop(treasury, 1_000_000_000);
+    let counters = setup(&mut svm, authority, &[3_000, 0, 5_000]);
+    let pairs: Vec<_> = counters.iter().map(|&c| (c, vault(c))).collect();
+
+    assert_eq!(svm.process(&sweep_fees(authority, &pairs)), Ok(()));
+    assert_eq!(lamports(&svm, treasury), 1_000_008_000);
+    for counter in counters {
+        assert_eq!(lamports(&svm, vault(counter)), 0);
+    }
+
+    // Nothing left: a second sweep moves nothing, and doesn't fail.
+    assert_eq!(svm.process(&sweep_fees(authority, &pairs)), Ok(()));
+    assert_eq!(lamports(&svm, treasury), 1_000_008_000);
+}
+
+#[test]
+fn only_the_owner_sweeps_and_only_vaults() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    svm.airdrop(pda(&[TREASURY_SEED]), 1_000_000_000);
+    let counters = setup(&mut svm, authority, &[3_000, 5_000]);
+    let (first, second) = (counters[0], counters[1]);
+
+    let stranger = svm.wallet();
+    assert_eq!(
+        svm.process(&sweep_fees(stranger, &[(first, vault(first))])),
+        Err(Error::from(CounterError::Unauthorized).into())
+    );
+    // Another counter's vault, or a counter without its vault.
+    assert_eq!(
+        svm.process(&sweep_fees(authority, &[(first, vault(second))])),
+        Err(Error::from(CounterError::InvalidSweep).into())
+    );
+    let mut ix = sweep_fees(authority, &[(first, vault(first))]);
+    ix.accounts.push(AccountMeta::new(second, false));
+    assert_eq!(
+        svm.process(&ix),
+        Err(Error::from(CounterError::InvalidSweep).into())
+    );
+    assert_eq!(lamports(&svm, vault(first)), 3_000);
+}
@@ -0,0 +1,158 @@
+// Tagged counters (see tags.rs), run through the program: only registered
+// tags can be used, and they land at TAGS_OFFSET, where the client's
+// memcmp filters look for them.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, decode_tag, encode_tag, instruction, Counter, CounterError, TagRegistry, TAGS_OFFSET,
+    TAG_LEN, TAG_REGISTRY_SEED,
+};
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn tag(name: &str) -> [u8; TAG_LEN] {
+    encode_tag(name).unwrap()
+}
+
+fn register_tag(admin: Pubkey, tag: [u8; TAG_LEN]) -> Instruction {
+    ix(
+        accounts::RegisterTag {
+            config: pda(&[counter::CONFIG_SEED]),
+            tag_registry: pda(&[TAG_REGISTRY_SEED]),
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterTag { tag },
+    )
+}
+
+fn initialize_tagged(counter: Pubkey, user: Pubkey, tags: Vec<[u8; TAG_LEN]>) -> Instruction {
+    ix(
+        accounts::InitializeTagged {
+            counter,
+            tag_registry: pda(&[TAG_REGISTRY_SEED]),
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeTagged { tags },
+    )
+}
+
+#[test]
+fn tags_round_trip() {
+    assert_eq!(decode_tag(&tag("games")), "games");
+    assert_eq!(decode_tag(&tag("sixteen-bytes-ok")), "sixteen-bytes-ok");
+    assert_eq!(encode_tag(""), None);
+    assert_eq!(encode_tag("seventeen-bytes!!"), None);
+    assert_eq!(encode_tag("nul\0"), None);
+}
+
+#[test]
+fn counters_take_registered_tags() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    for name in ["games", "dao", "events"] {
+        assert_eq!(svm.process(&register_tag(admin, tag(name))), Ok(()));
+    }
+    // Registering twice changes nothing.
+    assert_eq!(svm.process(&register_tag(admin, tag("dao"))), Ok(()));
+    let registry = svm.load::<TagRegistry>(&pda(&[TAG_REGISTRY_SEED])).unwrap();
+    assert_eq!(registry.tags, [tag("games"), tag("dao"), tag("events")]);
+
+    let user = svm.wallet();
+    let counter = Pubkey::new_unique();
+    assert_eq!(
+        svm.process(&initialize_tagged(
+            counter,
+            user,
+            vec![tag("dao"), tag("games")]
+        )),
+        Ok(())
+    );
+    let state = svm.load::<Counter>(&counter).unwrap();
+    assert_eq!(state.authority, user);
+    assert_eq!(
+        state.tags().map(decode_tag).collect::<Vec<_>>(),
+        ["dao", "games"]
+    );
+    // Where tag_filters in the client looks for them.
+    let data = &svm.get(&counter).unwrap().data;
+    assert_eq!(data[TAGS_OFFSET..][..TAG_LEN], tag("dao"));
+    assert_eq!(data[TAGS_OFFSET + TAG_LEN..][..TAG_LEN], tag("games"));
+    assert_eq!(
+        data[TAGS_OFFSET + 2 * TAG_LEN..][..2 * TAG_LEN],
+        [0; 2 * TAG_LEN]
+    );
+
+    // No tags at all is a plain counter.
+    let plain = Pubkey::new_unique();
+    assert_eq!(svm.process(&initialize_tagged(plain, user, vec![])), Ok(()));
+    assert_eq!(svm.load::<Counter>(&plain).unwrap().tags().count(), 0);
+}
+
+#[test]
+fn refuses_bad_tags() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    let user = svm.wallet();
+    // No registry yet.
+    assert_eq!(
+        svm.process(&initialize_tagged(Pubkey::new_unique(), user, vec![])),
+        Err(error(ErrorCode::AccountNotInitialized))
+    );
+    assert_eq!(
+        svm.process(&register_tag(admin, [0; TAG_LEN])),
+        Err(error(CounterError::InvalidTag))
+    );
+    assert_eq!(
+        svm.process(&register_tag(user, tag("games"))),
+        Err(error(CounterError::Unauthorized))
+    );
+    for name in ["a", "b", "c", "d", "e"] {
+        assert_eq!(svm.process(&register_tag(admin, tag(name))), Ok(()));
+    }
+
+    let refused = [
+        (vec![tag("zzz")], CounterError::UnknownTag),
+        (vec![tag("a"), tag("a")], CounterError::InvalidTag),
+        (
+            ["a", "b", "c", "d", "e"].map(tag).to_vec(),
+            CounterError::InvalidTag,
+        ),
+    ];
+    for (tags, expected) in refused {
+        assert_eq!(
+            svm.process(&initialize_tagged(Pubkey::new_unique(), user, tags)),
+            Err(error(expected))
+        );
+    }
+}
+
+#[test]
+fn the_registry_fills_up() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    for i in 0..counter::MAX_REGISTERED_TAGS {
+        let name = format!("tag-{i}");
+        assert_eq!(svm.process(&register_tag(admin, tag(&name))), Ok(()));
+    }
+    assert_eq!(
+        svm.process(&register_tag(admin, tag("one-too-many"))),
+        Err(error(CounterError::TagRegistryFull))
+    );
+    // Tags already in it are still fine.
+    assert_eq!(svm.process(&register_tag(admin, tag("tag-0"))), Ok(()));
+}
@@ -0,0 +1,170 @@
+// Counter templates (see templates.rs), run through the program: the admin
+// names a preset once, and anyone can create counters from it.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, CounterFees, Template, TemplateArgs,
+    TemplateSettings, FEES_SEED, TEMPLATE_SEED,
+};
+use harness::{ix, Svm};
+
+fn error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn tip_jar() -> TemplateSettings {
+    TemplateSettings {
+        fee_lamports: 5_000,
+        referral_bps: 1_000,
+        once_per_slot: true,
+        alarm_threshold: 100,
+        deny_cpi: true,
+    }
+}
+
+fn set_template(admin: Pubkey, name: &str, settings: TemplateSettings) -> Instruction {
+    ix(
+        accounts::SetTemplate {
+            config: pda(&[counter::CONFIG_SEED]),
+            template: pda(&[TEMPLATE_SEED, name.as_bytes()]),
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetTemplate {
+            args: TemplateArgs {
+                version: TemplateArgs::VERSION,
+                name: name.to_string(),
+                settings,
+            },
+        },
+    )
+}
+
+// "fees": whether to pass the fees account, which only a template with a
+// fee needs.
+fn initialize_from_template(counter: Pubkey, user: Pubkey, name: &str, fees: bool) -> Instruction {
+    ix(
+        accounts::InitializeFromTemplate {
+            counter,
+            template: pda(&[TEMPLATE_SEED, name.as_bytes()]),
+            fees: fees.then(|| pda(&[FEES_SEED, counter.as_ref()])),
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeFromTemplate {},
+    )
+}
+
+#[test]
+fn counters_start_with_the_templates_settings() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    assert_eq!(
+        svm.process(&set_template(admin, "tip jar", tip_jar())),
+        Ok(())
+    );
+
+    let (user, counter) = (svm.wallet(), Pubkey::new_unique());
+    assert_eq!(
+        svm.process(&initialize_from_template(counter, user, "tip jar", true)),
+        Ok(())
+    );
+    let created = svm.load::<Counter>(&counter).unwrap();
+    assert_eq!(created.authority, user);
+    assert!(created.has_flag(Counter::ONCE_PER_SLOT));
+    assert!(created.has_flag(Counter::FEE_REQUIRED));
+    assert_eq!(created.alarm_threshold, 100);
+    assert!(created.deny_cpi);
+    let other = Pubkey::new_unique();
+    assert_eq!(
+        svm.process(&initialize_from_template(other, user, "tip jar", false)),
+        Err(error(CounterError::FeesAccountMissing))
+    );
+    let fees = svm
+        .load::<CounterFees>(&pda(&[FEES_SEED, counter.as_ref()]))
+        .unwrap();
+    assert_eq!((fees.fee_lamports, fees.referral_bps), (5_000, 1_000));
+}
+
+#[test]
+fn only_the_admin_sets_templates() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    svm.set_config(admin);
+    let stranger = svm.wallet();
+    assert_eq!(
+        svm.process(&set_template(stranger, "tip jar", tip_jar())),
+        Err(error(CounterError::Unauthorized))
+    );
+
+    assert_eq!(
+        svm.process(&set_template(admin, "", tip_jar())),
+        Err(error(CounterError::InvalidTemplateName))
+    );
+    let too_generous = TemplateSettings {
+        referral_bps: 10_001,
+        ..tip_jar()
+    };
+    assert_eq!(
+        svm.process(&set_template(admin, "tip jar", too_generous)),
+        Err(error(CounterError::InvalidFee))
+    );
+
+    // Setting a template again replaces it.
+    let free = TemplateSettings {
+        fee_lamports: 0,
+        ..tip_jar()
+    };
+    assert_eq!(
+        svm.process(&set_template(admin, "tip jar", tip_jar())),
+        Ok(())
+    );
+    assert_eq!(svm.process(&set_template(admin, "tip jar", free)), Ok(()));
+    let template = svm
+        .load::<Template>(&pda(&[TEMPLATE_SEED, b"tip jar"]))
+        .unwrap();
+    assert_eq!(template.settings.fee_lamports, 0);
+}
+
+#[test]
+fn removed_templates_leave_their_counters_alone() {
+    let mut svm = Svm::new();
+    let admin = svm.wallet();
+    let config = svm.set_config(admin);
+    let free = TemplateSettings {
+        fee_lamports: 0,
+        ..tip_jar()
+    };
+    assert_eq!(svm.process(&set_template(admin, "free", free)), Ok(()));
+
+    let (user, counter) = (svm.wallet(), Pubkey::new_unique());
+    assert_eq!(
+        svm.process(&initialize_from_template(counter, user, "free", false)),
+        Ok(())
+    );
+
+    let template = pda(&[TEMPLATE_SEED, b"free"]);
+    assert_eq!(
+        svm.process(&ix(
+            accounts::RemoveTemplate {
+                config,
+                template,
+                admin,
+            },
+            instruction::RemoveTemplate {},
+        )),
+        Ok(())
+    );
+    assert_eq!(svm.get(&template).map_or(0, |t| t.lamports), 0);
+    let created = svm.load::<Counter>(&counter).unwrap();
+    assert!(created.has_flag(Counter::ONCE_PER_SLOT));
+    assert!(!created.has_flag(Counter::FEE_REQUIRED));
+}
@@ -0,0 +1,225 @@
+// Time-based rules, run through the program with the clock moved by
+// TimeMachine (see harness/time.rs) rather than by waiting.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::DEFAULT_SLOTS_PER_EPOCH;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, commitment_hash, instruction, CommitRoundArgs, Counter, CounterError, LeaseArgs,
+    ACCESS_LOG_SEED, COMMITMENT_SEED, COMMIT_ROUND_SEED, LEASE_SEED, VAULT_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::time::{TimeMachine, GENESIS_TIMESTAMP};
+use harness::{ix, Svm};
+
+fn error(error: CounterError) -> ProgramError {
+    Error::from(error).into()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+// A counter owned by a fresh wallet, with "flags" set.
+fn counter(svm: &mut Svm, flags: u8) -> (Pubkey, Pubkey) {
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(
+        counter,
+        &Counter {
+            flags,
+            ..fixtures::counter(authority)
+        },
+        CURRENT,
+    );
+    (authority, counter)
+}
+
+fn update_counter(counter: Pubkey, authority: Pubkey) -> accounts::UpdateCounter {
+    accounts::UpdateCounter {
+        counter,
+        lease: pda(&[LEASE_SEED, counter.as_ref()]),
+        access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+        authority,
+        system_program: system_program::ID,
+    }
+}
+
+#[test]
+fn slots_and_time_move_together() {
+    let mut svm = Svm::new();
+    svm.advance_slots(9);
+    assert_eq!(svm.clock().slot, 10);
+    assert_eq!(svm.clock().unix_timestamp, GENESIS_TIMESTAMP + 4);
+
+    svm.advance_time(60);
+    assert_eq!(svm.clock().unix_timestamp, GENESIS_TIMESTAMP + 64);
+    assert_eq!(svm.clock().slot, 160);
+
+    svm.warp_to_slot(DEFAULT_SLOTS_PER_EPOCH);
+    assert_eq!(svm.clock().epoch, 1);
+    assert_eq!(
+        svm.clock().epoch_start_timestamp,
+        svm.clock().unix_timestamp
+    );
+}
+
+#[test]
+fn once_per_slot_counters_take_one_increment_per_slot() {
+    let mut svm = Svm::new();
+    let (_, counter) = counter(&mut svm, Counter::ONCE_PER_SLOT);
+    let increment = ix(accounts::Increment { counter }, instruction::Increment {});
+
+    assert_eq!(svm.process(&increment), Ok(()));
+    assert_eq!(
+        svm.process(&increment),
+        Err(error(CounterError::AlreadyCountedThisSlot))
+    );
+    svm.advance_slots(1);
+    assert_eq!(svm.process(&increment), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 2);
+}
+
+#[test]
+fn a_lease_ends_at_its_expiry() {
+    let mut svm = Svm::new();
+    let (authority, counter) = counter(&mut svm, 0);
+    let renter = svm.wallet();
+    let lease = ix(
+        accounts::LeaseAuthority {
+            counter,
+            lease: pda(&[LEASE_SEED, counter.as_ref()]),
+            vault: pda(&[VAULT_SEED, counter.as_ref()]),
+            access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+            authority,
+            renter,
+            system_program: system_program::ID,
+        },
+        instruction::LeaseAuthority {
+            args: LeaseArgs {
+                version: LeaseArgs::VERSION,
+                to: renter,
+                duration: 60,
+                price: 0,
+            },
+        },
+    );
+    assert_eq!(svm.process(&lease), Ok(()));
+
+    let set_threshold = |by| {
+        ix(
+            update_counter(counter, by),
+            instruction::SetAlarmThreshold { threshold: 5 },
+        )
+    };
+    assert_eq!(svm.process(&set_threshold(renter)), Ok(()));
+    svm.advance_time(59);
+    assert_eq!(svm.process(&set_threshold(renter)), Ok(()));
+    svm.advance_time(1);
+    assert_eq!(
+        svm.process(&set_threshold(renter)),
+        Err(error(CounterError::Unauthorized))
+    );
+    assert_eq!(svm.process(&set_threshold(authority)), Ok(()));
+}
+
+// Opens round 1 on "counter", taking commits for 60 seconds and reveals of
+// up to "max_value" for 60 more. Returns the round and its commit deadline.
+fn open_round(svm: &mut Svm, counter: Pubkey, authority: Pubkey, max_value: u64) -> (Pubkey, i64) {
+    let commit_round = pda(&[COMMIT_ROUND_SEED, counter.as_ref()]);
+    let commit_deadline = svm.clock().unix_timestamp + 60;
+    let open = ix(
+        accounts::OpenCommitRound {
+            guard: accounts::AuthorityGuard {
+                counter,
+                access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+                authority,
+                system_program: system_program::ID,
+            },
+            commit_round,
+            system_program: system_program::ID,
+        },
+        instruction::OpenCommitRound {
+            args: CommitRoundArgs {
+                version: CommitRoundArgs::VERSION,
+                commit_deadline,
+                reveal_deadline: commit_deadline + 60,
+                deposit: 0,
+                max_value,
+            },
+        },
+    );
+    assert_eq!(svm.process(&open), Ok(()));
+    (commit_round, commit_deadline)
+}
+
+// A fresh participant's commit of "value" in round 1, and its reveal.
+fn commit_and_reveal(
+    svm: &mut Svm,
+    counter: Pubkey,
+    commit_round: Pubkey,
+    value: u64,
+) -> (Instruction, Instruction) {
+    let participant = svm.wallet();
+    let commitment = pda(&[
+        COMMITMENT_SEED,
+        counter.as_ref(),
+        &1u64.to_le_bytes(),
+        participant.as_ref(),
+    ]);
+    let salt = [9; 32];
+    let commit = ix(
+        accounts::Commit {
+            counter,
+            commit_round,
+            commitment,
+            participant,
+            system_program: system_program::ID,
+        },
+        instruction::Commit {
+            hash: commitment_hash(&participant, value, &salt),
+        },
+    );
+    let reveal = ix(
+        accounts::Reveal {
+            counter,
+            commit_round,
+            commitment,
+            participant,
+        },
+        instruction::Reveal { value, salt },
+    );
+    (commit, reveal)
+}
+
+#[test]
+fn reveals_open_at_the_commit_deadline() {
+    let mut svm = Svm::new();
+    let (authority, counter) = counter(&mut svm, Counter::COMMIT_REVEAL);
+    let (commit_round, commit_deadline) = open_round(&mut svm, counter, authority, 10);
+    let (commit, reveal) = commit_and_reveal(&mut svm, counter, commit_round, 3);
+    assert_eq!(svm.process(&commit), Ok(()));
+
+    assert_eq!(svm.process(&reveal), Err(error(CounterError::WrongPhase)));
+    svm.warp_to_time(commit_deadline);
+    assert_eq!(svm.process(&reveal), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 3);
+}
+
+#[test]
+fn reveals_over_the_round_maximum_fail() {
+    let mut svm = Svm::new();
+    let (authority, counter) = counter(&mut svm, Counter::COMMIT_REVEAL);
+    let (commit_round, commit_deadline) = open_round(&mut svm, counter, authority, 10);
+    let (commit, over) = commit_and_reveal(&mut svm, counter, commit_round, 11);
+    assert_eq!(svm.process(&commit), Ok(()));
+    let (commit, at) = commit_and_reveal(&mut svm, counter, commit_round, 10);
+    assert_eq!(svm.process(&commit), Ok(()));
+
+    svm.warp_to_time(commit_deadline);
+    assert_eq!(svm.process(&over), Err(error(CounterError::RevealTooLarge)));
+    assert_eq!(svm.process(&at), Ok(()));
+    assert_eq!(svm.load::<Counter>(&counter).unwrap().count, 10);
+}
@@ -0,0 +1,38 @@
+use anchor_lang::error::Error;
+use counter::{Counter, CounterError, Route};
+
+#[test]
+fn mirrored_counters_only_allow_minted_increments() {
+    let mut counter = Counter::default();
+    counter.set_flag(Counter::TOKEN_MIRRORED, true);
+    assert_eq!(
+        counter.check_modes().err(),
+        Some(Error::from(CounterError::MintRequired))
+    );
+    // increment_minted's check, and no other route's: each of those would
+    // count without minting.
+    assert!(counter.check_route(Route::TokenMirrored).is_ok());
+    for route in Route::ALL {
+        if route != Route::TokenMirrored {
+            assert_eq!(
+                counter.check_route(route).err(),
+                Some(Error::from(route.missing()))
+            );
+        }
+    }
+}
+
+#[test]
+fn subtract_stops_at_zero() {
+    let mut counter = Counter {
+        count: 1,
+        ..Default::default()
+    };
+    counter.subtract(1).unwrap();
+    assert_eq!(counter.count, 0);
+    assert_eq!(
+        counter.subtract(1).err(),
+        Some(Error::from(CounterError::Underflow))
+    );
+    assert_eq!(counter.count, 0);
+}
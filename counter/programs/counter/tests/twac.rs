@@ -0,0 +1,120 @@
+// The time-weighted average count (see twac.rs): the accumulator by
+// itself, then run through the program with the clock moved by
+// TimeMachine.
+mod harness;
+
+use anchor_lang::error::Error;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, TwacObservation, ACCESS_LOG_SEED, LEASE_SEED,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::time::TimeMachine;
+use harness::{ix, Svm};
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &counter::ID).0
+}
+
+fn set_twac(counter: Pubkey, authority: Pubkey, enabled: bool) -> Instruction {
+    ix(
+        accounts::UpdateCounter {
+            counter,
+            lease: pda(&[LEASE_SEED, counter.as_ref()]),
+            access_log: pda(&[ACCESS_LOG_SEED, counter.as_ref()]),
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetTwac { enabled },
+    )
+}
+
+fn get_twac(svm: &mut Svm, counter: Pubkey) -> TwacObservation {
+    let get = ix(accounts::ReadCounter { counter }, instruction::GetTwac {});
+    assert_eq!(svm.process(&get), Ok(()));
+    TwacObservation::try_from_slice(&svm.return_data()).unwrap()
+}
+
+fn increment(counter: Pubkey) -> Instruction {
+    ix(accounts::Increment { counter }, instruction::Increment {})
+}
+
+#[test]
+fn accumulates_the_count_held_over_time() {
+    let mut counter = Counter {
+        twac_slot: 10,
+        ..fixtures::counter_at(Pubkey::default(), 4)
+    };
+    let start = counter.observe(10);
+
+    // 4 for 10 slots, then 0 for 30, then 8 for 10.
+    counter.accumulate(20);
+    counter.reset();
+    counter.accumulate(50);
+    counter.add(8).unwrap();
+    let end = counter.observe(60);
+    assert_eq!(end.cumulative_count, 4 * 10 + 8 * 10);
+    assert_eq!(end.average_since(&start), Some(120 / 50));
+    assert_eq!(end.average_since(&counter.observe(50)), Some(8));
+
+    // Nothing to average over.
+    assert_eq!(end.average_since(&end), None);
+    assert_eq!(start.average_since(&end), None);
+
+    // Differences survive the sum wrapping around.
+    let before = TwacObservation {
+        cumulative_count: u128::MAX - 5,
+        slot: 1,
+    };
+    let after = TwacObservation {
+        cumulative_count: 14,
+        slot: 5,
+    };
+    assert_eq!(after.average_since(&before), Some(5));
+}
+
+#[test]
+fn counters_without_twac_dont_accumulate() {
+    let mut counter = fixtures::counter_at(Pubkey::default(), 4);
+    counter.accumulate(100);
+    assert_eq!((counter.cumulative_count, counter.twac_slot), (0, 0));
+    // And don't read the clock, which isn't there outside a program.
+    assert!(counter.begin_change().is_ok());
+}
+
+#[test]
+fn increments_update_the_accumulator() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let counter = fixtures::address("counter");
+    svm.set_counter(counter, &fixtures::counter_at(authority, 2), CURRENT);
+
+    let get = ix(accounts::ReadCounter { counter }, instruction::GetTwac {});
+    assert_eq!(
+        svm.process(&get),
+        Err(Error::from(CounterError::NotTwac).into())
+    );
+
+    assert_eq!(svm.process(&set_twac(counter, authority, true)), Ok(()));
+    let start = get_twac(&mut svm, counter);
+    assert_eq!(start.cumulative_count, 0);
+
+    // 2 for 10 slots, then 3 for 30.
+    svm.advance_slots(10);
+    assert_eq!(svm.process(&increment(counter)), Ok(()));
+    let state = svm.load::<Counter>(&counter).unwrap();
+    assert_eq!(
+        (state.cumulative_count, state.twac_slot),
+        (20, start.slot + 10)
+    );
+    svm.advance_slots(30);
+    let end = get_twac(&mut svm, counter);
+    assert_eq!(end.cumulative_count, 20 + 90);
+    assert_eq!(end.average_since(&start), Some(110 / 40));
+
+    // Off, then on again, starts over.
+    assert_eq!(svm.process(&set_twac(counter, authority, false)), Ok(()));
+    assert_eq!(svm.process(&set_twac(counter, authority, true)), Ok(()));
+    assert_eq!(get_twac(&mut svm, counter).cumulative_count, 0);
+}
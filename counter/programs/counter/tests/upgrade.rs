@@ -0,0 +1,341 @@
+// Upgrading the program with counters from older builds on chain: each
+// old layout goes through migrate_counter on the current build, or
+// migrate_ownerless_counter for the first, count-only one, and comes out
+// with everything it held.
+//
+// Most old builds don't run here, only what they left behind, byte for
+// byte: layouts only ever added fields at the end, so an old counter is the
+// current encoding cut short (see harness/fixtures.rs). The first release
+// does run (see tests/builds/baseline): counters are created and counted
+// by its code, then the program is upgraded in place to the current build
+// and they're migrated. Both are built for the host, since the harness has
+// no BPF VM (see harness/mod.rs), but the accounts are the ones a deployed
+// first release would have written.
+mod harness;
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction::SystemError;
+use anchor_lang::solana_program::{instruction::Instruction, system_program};
+use counter::{
+    accounts, instruction, Counter, CounterError, DeltaEntry, DeltaFeed, ACCESS_LOG_SEED,
+    COUNTER_LAYOUTS, LIFETIME_COUNT_LAYOUT, MAX_COUNTER_TAGS, OWNERLESS_LAYOUT, RECENT_DELTAS,
+    TAGS_LAYOUT, TAG_LEN,
+};
+use harness::fixtures::{self, CURRENT};
+use harness::{ix, program_data_address, Svm};
+
+// A counter with every field set, so a field lost in migration shows.
+fn full(authority: Pubkey) -> Counter {
+    Counter {
+        count: 41,
+        authority,
+        flags: Counter::ONCE_PER_SLOT,
+        last_increment_slot: 3,
+        alarm_threshold: 50,
+        event_seq: 12,
+        finalized: true,
+        deny_cpi: true,
+        lifetime_count: 45,
+        role_weighted: true,
+        tags: [[1; TAG_LEN]; MAX_COUNTER_TAGS],
+        max_count: 40,
+        overflow: Pubkey::new_unique(),
+        cumulative_count: 1_000,
+        twac_slot: 2,
+        delta_feed: DeltaFeed {
+            base_slot: 2,
+            slot: 3,
+            recorded: 1,
+            recent_deltas: [DeltaEntry {
+                slot_offset: 1,
+                delta: -4,
+            }; RECENT_DELTAS],
+        },
+        frozen: true,
+        // Not kept by anything: every write computes it.
+        checksum: 0,
+        group: Pubkey::new_unique(),
+        gifted_count: 3,
+    }
+}
+
+// What "counter" is after migrate_counter from "layout": fields the layout
+// didn't have start at their defaults, except lifetime_count, which starts
+// at the count. Every layout it takes has the authority.
+fn migrated(counter: &Counter, layout: usize) -> Counter {
+    Counter {
+        alarm_threshold: if layout >= 2 {
+            counter.alarm_threshold
+        } else {
+            0
+        },
+        event_seq: if layout >= 3 { counter.event_seq } else { 0 },
+        finalized: layout >= 4 && counter.finalized,
+        deny_cpi: layout >= 5 && counter.deny_cpi,
+        role_weighted: layout >= 7 && counter.role_weighted,
+        tags: if layout >= TAGS_LAYOUT {
+            counter.tags
+        } else {
+            Default::default()
+        },
+        max_count: if layout >= 9 { counter.max_count } else { 0 },
+        overflow: if layout >= 9 {
+            counter.overflow
+        } else {
+            Pubkey::default()
+        },
+        cumulative_count: if layout >= 10 {
+            counter.cumulative_count
+        } else {
+            0
+        },
+        twac_slot: if layout >= 10 { counter.twac_slot } else { 0 },
+        delta_feed: if layout >= 11 {
+            counter.delta_feed
+        } else {
+            DeltaFeed::default()
+        },
+        frozen: layout >= 12 && counter.frozen,
+        group: if layout >= 14 {
+            counter.group
+        } else {
+            Pubkey::default()
+        },
+        gifted_count: if layout >= 15 {
+            counter.gifted_count
+        } else {
+            0
+        },
+        lifetime_count: if layout < LIFETIME_COUNT_LAYOUT {
+            counter.count
+        } else {
+            counter.lifetime_count
+        },
+        ..counter.clone()
+    }
+}
+
+fn migrate(counter: Pubkey, payer: Pubkey) -> Instruction {
+    ix(
+        accounts::MigrateCounter {
+            counter,
+            payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateCounter {},
+    )
+}
+
+#[test]
+fn every_old_layout_migrates_with_its_data_intact() {
+    for (layout, &size) in COUNTER_LAYOUTS[..CURRENT]
+        .iter()
+        .enumerate()
+        .skip(OWNERLESS_LAYOUT + 1)
+    {
+        let mut svm = Svm::new();
+        let payer = svm.wallet();
+        let old = full(fixtures::address("authority"));
+        let address = fixtures::address("counter");
+        svm.set_counter(address, &old, layout);
+        let lamports_before = svm.get(&payer).unwrap().lamports;
+
+        assert_eq!(svm.process(&migrate(address, payer)), Ok(()), "{layout}");
+
+        let account = svm.get(&address).unwrap();
+        let rent = Rent::default().minimum_balance(COUNTER_LAYOUTS[CURRENT]);
+        assert_eq!(account.lamports, rent);
+        assert_eq!(
+            lamports_before - svm.get(&payer).unwrap().lamports,
+            rent - Rent::default().minimum_balance(size)
+        );
+        assert_eq!(
+            svm.get(&address).unwrap().data,
+            fixtures::counter_data(&migrated(&old, layout), CURRENT)
+        );
+    }
+}
+
+#[test]
+fn migrated_counters_work_on_the_new_build() {
+    let mut svm = Svm::new();
+    let authority = svm.wallet();
+    let address = fixtures::address("counter");
+    svm.set_counter(
+        address,
+        &fixtures::counter_at(authority, 41),
+        OWNERLESS_LAYOUT + 1,
+    );
+    let increment = ix(
+        accounts::Increment { counter: address },
+        instruction::Increment {},
+    );
+    assert_eq!(
+        svm.process(&increment),
+        Err(Error::from(ErrorCode::AccountDidNotDeserialize).into())
+    );
+
+    assert_eq!(svm.process(&migrate(address, authority)), Ok(()));
+    assert_eq!(svm.process(&increment), Ok(()));
+    let reset = ix(
+        accounts::ResetCount {
+            guard: accounts::AuthorityGuard {
+                counter: address,
+                access_log: Pubkey::find_program_address(
+                    &[ACCESS_LOG_SEED, address.as_ref()],
+                    &counter::ID,
+                )
+                .0,
+                authority,
+                system_program: system_program::ID,
+            },
+        },
+        instruction::ResetCount {},
+    );
+    assert_eq!(svm.process(&reset), Ok(()));
+    let counter: Counter = svm.load(&address).unwrap();
+    assert_eq!((counter.count, counter.lifetime_count), (0, 42));
+
+    // Once is enough.
+    assert_eq!(
+        svm.process(&migrate(address, authority)),
+        Err(Error::from(CounterError::NotMigratable).into())
+    );
+}
+
+#[test]
+fn a_failed_migration_leaves_the_old_counter_as_it_was() {
+    let mut svm = Svm::new();
+    let address = fixtures::address("counter");
+    svm.set_counter(
+        address,
+        &full(fixtures::address("authority")),
+        OWNERLESS_LAYOUT + 1,
+    );
+    let before = svm.get(&address).cloned();
+
+    // Can't pay the extra rent.
+    let broke = fixtures::address("broke");
+    assert_eq!(
+        svm.process(&migrate(address, broke)),
+        Err(ProgramError::Custom(
+            SystemError::ResultWithNegativeLamports as u32
+        ))
+    );
+    assert_eq!(svm.get(&address).cloned(), before);
+}
+
+fn migrate_ownerless(counter: Pubkey, authority: Pubkey, owner: Pubkey) -> Instruction {
+    ix(
+        accounts::MigrateOwnerlessCounter {
+            counter,
+            authority,
+            program: counter::ID,
+            program_data: program_data_address(&counter::ID),
+            system_program: system_program::ID,
+        },
+        instruction::MigrateOwnerlessCounter { owner },
+    )
+}
+
+#[test]
+fn count_only_counters_get_their_owner_from_the_upgrade_authority() {
+    let mut svm = Svm::new();
+    let upgrade_authority = svm.wallet();
+    svm.deploy_counter(Some(upgrade_authority));
+    let stranger = svm.wallet();
+    let owner = fixtures::address("owner");
+    let address = fixtures::address("counter");
+    svm.set_counter(address, &full(owner), OWNERLESS_LAYOUT);
+
+    // Whoever migrated it would pick its owner.
+    assert_eq!(
+        svm.process(&migrate(address, stranger)),
+        Err(Error::from(CounterError::OwnerlessCounter).into())
+    );
+    assert_eq!(
+        svm.process(&migrate_ownerless(address, stranger, stranger)),
+        Err(Error::from(CounterError::NotUpgradeAuthority).into())
+    );
+
+    assert_eq!(
+        svm.process(&migrate_ownerless(address, upgrade_authority, owner)),
+        Ok(())
+    );
+    assert_eq!(
+        svm.get(&address).unwrap().data,
+        fixtures::counter_data(
+            &Counter {
+                count: 41,
+                authority: owner,
+                lifetime_count: 41,
+                ..Default::default()
+            },
+            CURRENT
+        )
+    );
+    // Only count-only counters.
+    assert_eq!(
+        svm.process(&migrate_ownerless(address, upgrade_authority, owner)),
+        Err(Error::from(CounterError::NotMigratable).into())
+    );
+}
+
+#[test]
+fn counters_from_the_first_release_survive_the_upgrade() {
+    let mut svm = Svm::new();
+    let upgrade_authority = svm.wallet();
+    svm.deploy_counter(Some(upgrade_authority));
+    svm.upgrade_counter(counter_baseline::entry);
+
+    // Used the way it was: counters created and counted by the first
+    // release.
+    let user = svm.wallet();
+    let counters = [Pubkey::new_unique(), Pubkey::new_unique()];
+    for (i, &address) in counters.iter().enumerate() {
+        let initialize = ix(
+            counter_baseline::accounts::Initialize {
+                counter: address,
+                user,
+                system_program: system_program::ID,
+            },
+            counter_baseline::instruction::Initialize {},
+        );
+        assert_eq!(svm.process(&initialize), Ok(()));
+        for _ in 0..=i {
+            let increment = ix(
+                counter_baseline::accounts::Increment { counter: address },
+                counter_baseline::instruction::Increment {},
+            );
+            assert_eq!(svm.process(&increment), Ok(()));
+        }
+        assert_eq!(svm.get(&address).unwrap().data.len(), COUNTER_LAYOUTS[0]);
+    }
+
+    svm.upgrade_counter(counter::entry);
+    let increment = |counter| ix(accounts::Increment { counter }, instruction::Increment {});
+    for (i, &address) in counters.iter().enumerate() {
+        assert_eq!(
+            svm.process(&increment(address)),
+            Err(Error::from(ErrorCode::AccountDidNotDeserialize).into())
+        );
+        assert_eq!(
+            svm.process(&migrate_ownerless(address, upgrade_authority, user)),
+            Ok(())
+        );
+        assert_eq!(svm.process(&increment(address)), Ok(()));
+
+        let counter: Counter = svm.load(&address).unwrap();
+        let count = i as u64 + 2;
+        assert_eq!(
+            (counter.count, counter.lifetime_count, counter.authority),
+            (count, count, user)
+        );
+        let verify = ix(
+            accounts::ReadCounter { counter: address },
+            instruction::VerifyChecksum {},
+        );
+        assert_eq!(svm.process(&verify), Ok(()));
+    }
+}
@@ -0,0 +1,38 @@
+use anchor_lang::error::Error;
+use counter::{Counter, CounterError, Lockup, MAX_LOCK};
+
+fn lockup(amount: u64, unlock_at: i64) -> Lockup {
+    Lockup {
+        counter: Default::default(),
+        owner: Default::default(),
+        mint: Default::default(),
+        amount,
+        unlock_at,
+        voted: false,
+        bump: 0,
+    }
+}
+
+#[test]
+fn weight_decays_with_the_time_left() {
+    let lock = lockup(1_000, MAX_LOCK);
+    assert_eq!(lock.weight(0), 1_000);
+    assert_eq!(lock.weight(MAX_LOCK / 2), 500);
+    assert_eq!(lock.weight(MAX_LOCK), 0);
+    assert_eq!(lock.weight(MAX_LOCK + 1), 0);
+    assert_eq!(lockup(u64::MAX, MAX_LOCK).weight(0), u64::MAX);
+}
+
+#[test]
+fn weighted_counters_only_increment_through_increment_weighted() {
+    let mut counter = Counter::default();
+    counter.set_flag(Counter::VOTE_WEIGHTED, true);
+    assert_eq!(
+        counter.check_modes().err(),
+        Some(Error::from(CounterError::VoteWeightRequired))
+    );
+    assert_eq!(
+        counter.check_route_free(Counter::FEE_REQUIRED).err(),
+        Some(Error::from(CounterError::ConflictingModes))
+    );
+}
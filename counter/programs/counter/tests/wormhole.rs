@@ -0,0 +1,73 @@
+// The Wormhole message formats are shared with contracts on other chains,
+// so these pin them down byte for byte.
+use anchor_lang::error::Error;
+use counter::{CountPayload, CounterError, PostedVaa};
+
+#[test]
+fn count_payload_is_big_endian_and_round_trips() {
+    let payload = CountPayload {
+        counter: [7; 32],
+        count: 0x0102_0304_0506_0708,
+        slot: 42,
+    };
+    let bytes = payload.encode();
+
+    assert_eq!(bytes[0], 1);
+    assert_eq!(bytes[1..33], [7; 32]);
+    assert_eq!(bytes[33..41], [1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(bytes[41..49], 42u64.to_be_bytes());
+    assert_eq!(CountPayload::decode(&bytes).unwrap(), payload);
+}
+
+#[test]
+fn count_payload_rejects_other_messages() {
+    let mut bytes = CountPayload {
+        counter: [0; 32],
+        count: 1,
+        slot: 1,
+    }
+    .encode();
+    bytes[0] = 2;
+    assert_eq!(
+        CountPayload::decode(&bytes).err(),
+        Some(Error::from(CounterError::InvalidWormholeMessage))
+    );
+    assert!(CountPayload::decode(&bytes[..48]).is_err());
+}
+
+// Builds the data of a core bridge PostedVAA account.
+fn posted_vaa(sequence: u64, emitter_chain: u16, emitter: [u8; 32], payload: &[u8]) -> Vec<u8> {
+    let mut data = b"vaa".to_vec();
+    data.push(1); // version
+    data.push(1); // consistency level
+    data.extend_from_slice(&0u32.to_le_bytes()); // timestamp
+    data.extend_from_slice(&[0; 32]); // signature set
+    data.extend_from_slice(&0u32.to_le_bytes()); // posted timestamp
+    data.extend_from_slice(&0u32.to_le_bytes()); // nonce
+    data.extend_from_slice(&sequence.to_le_bytes());
+    data.extend_from_slice(&emitter_chain.to_le_bytes());
+    data.extend_from_slice(&emitter);
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+#[test]
+fn parses_posted_vaas() {
+    let data = posted_vaa(9, 2, [3; 32], b"hello");
+    assert_eq!(
+        PostedVaa::parse(&data).unwrap(),
+        PostedVaa {
+            sequence: 9,
+            emitter_chain: 2,
+            emitter_address: [3; 32],
+            payload: b"hello".to_vec(),
+        }
+    );
+
+    // Truncated payload, or not a VAA at all.
+    assert!(PostedVaa::parse(&data[..data.len() - 1]).is_err());
+    let mut wrong_magic = data.clone();
+    wrong_magic[0] = b'x';
+    assert!(PostedVaa::parse(&wrong_magic).is_err());
+}
@@ -0,0 +1,311 @@
+// ============================================================================
+// CROWDFUND - all-or-nothing campaigns with a contributors counter
+// ============================================================================
+
+// A creator sets a goal in lamports and a deadline. Backers pledge SOL
+// into the campaign until the deadline. Then it's all or nothing:
+//
+//   - goal met: the creator claims everything pledged;
+//   - goal missed: every backer takes their own pledge back with refund.
+//
+// Each campaign has a "contributors" counter in the counter program,
+// created by create_campaign at a PDA of this program. A backer's first
+// pledge increments it through CPI, so it counts wallets rather than
+// pledges. A refund doesn't take the count back down: the counter is the
+// campaign's history, and the counter program has no plain decrement
+// anyway.
+//
+// Pledges are tracked per backer in a Pledge PDA, which is what refund
+// pays out from.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction,
+    program::{invoke, invoke_signed},
+};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::InstructionData;
+use counter::program::Counter as CounterProgram;
+use counter::Counter;
+use nautilus_common::clock;
+
+declare_id!("AGSdGQWCMwqjJosiy8GMFM4UBTJ7hgaHnLyZt3zA3KMV");
+
+pub const CAMPAIGN_SEED: &[u8] = b"campaign";
+pub const CONTRIBUTORS_SEED: &[u8] = b"contributors";
+pub const PLEDGE_SEED: &[u8] = b"pledge";
+
+#[program]
+pub mod crowdfund {
+    use super::*;
+
+    // Creates a campaign and its contributors counter. "id" lets a
+    // creator run several.
+    pub fn create_campaign(
+        ctx: Context<CreateCampaign>,
+        id: u64,
+        goal: u64,
+        deadline: i64,
+    ) -> Result<()> {
+        require!(
+            goal > 0 && deadline > clock::now()?,
+            CrowdfundError::InvalidCampaign
+        );
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.creator = ctx.accounts.creator.key();
+        campaign.contributors = ctx.accounts.contributors.key();
+        campaign.id = id;
+        campaign.goal = goal;
+        campaign.deadline = deadline;
+        campaign.pledged = 0;
+        campaign.claimed = false;
+        campaign.bump = ctx.bumps.campaign;
+
+        // Built from the counter crate's generated types, like in
+        // counter-hook. The counter's address is our PDA, so we sign for it.
+        let initialize = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Initialize {
+                counter: ctx.accounts.contributors.key(),
+                user: ctx.accounts.creator.key(),
+                system_program: ctx.accounts.system_program.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Initialize {}.data(),
+        };
+        let campaign_key = campaign.key();
+        invoke_signed(
+            &initialize,
+            &[
+                ctx.accounts.contributors.to_account_info(),
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+            &[&[
+                CONTRIBUTORS_SEED,
+                campaign_key.as_ref(),
+                &[ctx.bumps.contributors],
+            ]],
+        )?;
+        Ok(())
+    }
+
+    // Pledges "lamports". Can be called again to pledge more.
+    pub fn pledge(ctx: Context<PledgeToCampaign>, lamports: u64) -> Result<()> {
+        let pledge = &mut ctx.accounts.pledge;
+        let first = pledge.amount == 0;
+        ctx.accounts
+            .campaign
+            .record_pledge(lamports, clock::now()?)?;
+        pledge.campaign = ctx.accounts.campaign.key();
+        pledge.backer = ctx.accounts.backer.key();
+        pledge.amount = pledge
+            .amount
+            .checked_add(lamports)
+            .ok_or(CrowdfundError::Overflow)?;
+        pledge.bump = ctx.bumps.pledge;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.backer.to_account_info(),
+                    to: ctx.accounts.campaign.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        if first {
+            let increment = Instruction {
+                program_id: counter::ID,
+                accounts: counter::accounts::Increment {
+                    counter: ctx.accounts.contributors.key(),
+                }
+                .to_account_metas(None),
+                data: counter::instruction::Increment {}.data(),
+            };
+            invoke(
+                &increment,
+                &[
+                    ctx.accounts.contributors.to_account_info(),
+                    ctx.accounts.counter_program.to_account_info(),
+                ],
+            )?;
+            // See counter-hook for why our copy has to be reloaded.
+            ctx.accounts.contributors.reload()?;
+        }
+        Ok(())
+    }
+
+    // Sends everything pledged to the creator, once the deadline has
+    // passed with the goal met.
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.check_claim(clock::now()?)?;
+        campaign.claimed = true;
+        let pledged = campaign.pledged;
+        campaign.sub_lamports(pledged)?;
+        ctx.accounts.creator.add_lamports(pledged)?;
+        Ok(())
+    }
+
+    // Gives a backer their pledge back, once the deadline has passed with
+    // the goal missed. "close = backer" on the pledge returns its rent too.
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        ctx.accounts.campaign.check_refund(clock::now()?)?;
+        let amount = ctx.accounts.pledge.amount;
+        ctx.accounts.campaign.sub_lamports(amount)?;
+        ctx.accounts.backer.add_lamports(amount)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Campaign {
+    pub creator: Pubkey,
+    pub contributors: Pubkey,
+    pub id: u64,
+    // Lamports.
+    pub goal: u64,
+    // Unix time, in seconds. Pledges are accepted until then.
+    pub deadline: i64,
+    // Lamports pledged so far. Refunds don't lower it, so it keeps
+    // recording whether the goal was met.
+    pub pledged: u64,
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+impl Campaign {
+    pub fn record_pledge(&mut self, lamports: u64, now: i64) -> Result<()> {
+        require!(lamports > 0, CrowdfundError::EmptyPledge);
+        require!(now <= self.deadline, CrowdfundError::CampaignEnded);
+        self.pledged = self
+            .pledged
+            .checked_add(lamports)
+            .ok_or(CrowdfundError::Overflow)?;
+        Ok(())
+    }
+
+    pub fn check_claim(&self, now: i64) -> Result<()> {
+        require!(now > self.deadline, CrowdfundError::CampaignRunning);
+        require!(self.pledged >= self.goal, CrowdfundError::GoalMissed);
+        require!(!self.claimed, CrowdfundError::AlreadyClaimed);
+        Ok(())
+    }
+
+    pub fn check_refund(&self, now: i64) -> Result<()> {
+        require!(now > self.deadline, CrowdfundError::CampaignRunning);
+        require!(self.pledged < self.goal, CrowdfundError::GoalMet);
+        Ok(())
+    }
+}
+
+// One backer's total pledge to one campaign.
+#[account]
+#[derive(InitSpace)]
+pub struct Pledge {
+    pub campaign: Pubkey,
+    pub backer: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateCampaign<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Campaign::INIT_SPACE,
+        seeds = [CAMPAIGN_SEED, creator.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(mut, seeds = [CONTRIBUTORS_SEED, campaign.key().as_ref()], bump)]
+    /// CHECK: a new account the counter program initializes.
+    pub contributors: UncheckedAccount<'info>,
+
+    // Pays for both, and becomes the counter's authority.
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+// Not "Pledge", which is the account type.
+#[derive(Accounts)]
+pub struct PledgeToCampaign<'info> {
+    #[account(mut, has_one = contributors)]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(mut)]
+    pub contributors: Account<'info, Counter>,
+
+    #[account(
+        init_if_needed,
+        payer = backer,
+        space = 8 + Pledge::INIT_SPACE,
+        seeds = [PLEDGE_SEED, campaign.key().as_ref(), backer.key().as_ref()],
+        bump
+    )]
+    pub pledge: Account<'info, Pledge>,
+
+    #[account(mut)]
+    pub backer: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut, has_one = creator @ CrowdfundError::NotCreator)]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    #[account(mut)]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        seeds = [PLEDGE_SEED, campaign.key().as_ref(), backer.key().as_ref()],
+        bump = pledge.bump,
+        close = backer
+    )]
+    pub pledge: Account<'info, Pledge>,
+
+    #[account(mut)]
+    pub backer: Signer<'info>,
+}
+
+#[error_code]
+pub enum CrowdfundError {
+    #[msg("Campaign needs a goal and a deadline in the future")]
+    InvalidCampaign,
+    #[msg("Pledge must be more than nothing")]
+    EmptyPledge,
+    #[msg("Campaign's totals would overflow")]
+    Overflow,
+    #[msg("Campaign's deadline has passed")]
+    CampaignEnded,
+    #[msg("Campaign's deadline hasn't passed yet")]
+    CampaignRunning,
+    #[msg("Campaign missed its goal")]
+    GoalMissed,
+    #[msg("Campaign met its goal, so pledges aren't refunded")]
+    GoalMet,
+    #[msg("Campaign's funds were already claimed")]
+    AlreadyClaimed,
+    #[msg("Only the campaign's creator can claim")]
+    NotCreator,
+}
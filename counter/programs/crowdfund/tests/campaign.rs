@@ -0,0 +1,68 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use crowdfund::{Campaign, CrowdfundError};
+
+fn campaign(goal: u64) -> Campaign {
+    Campaign {
+        creator: Pubkey::new_unique(),
+        contributors: Pubkey::new_unique(),
+        id: 0,
+        goal,
+        deadline: 1_000,
+        pledged: 0,
+        claimed: false,
+        bump: 255,
+    }
+}
+
+#[test]
+fn pledges_only_until_the_deadline() {
+    let mut campaign = campaign(100);
+    campaign.record_pledge(40, 1_000).unwrap();
+    campaign.record_pledge(60, 500).unwrap();
+    assert_eq!(campaign.pledged, 100);
+    assert_eq!(
+        campaign.record_pledge(0, 500).err(),
+        Some(Error::from(CrowdfundError::EmptyPledge))
+    );
+    assert_eq!(
+        campaign.record_pledge(1, 1_001).err(),
+        Some(Error::from(CrowdfundError::CampaignEnded))
+    );
+}
+
+#[test]
+fn claims_once_after_the_deadline_if_the_goal_was_met() {
+    let mut campaign = campaign(100);
+    campaign.record_pledge(100, 0).unwrap();
+    assert_eq!(
+        campaign.check_claim(1_000).err(),
+        Some(Error::from(CrowdfundError::CampaignRunning))
+    );
+    campaign.check_claim(1_001).unwrap();
+    assert_eq!(
+        campaign.check_refund(1_001).err(),
+        Some(Error::from(CrowdfundError::GoalMet))
+    );
+
+    campaign.claimed = true;
+    assert_eq!(
+        campaign.check_claim(1_001).err(),
+        Some(Error::from(CrowdfundError::AlreadyClaimed))
+    );
+}
+
+#[test]
+fn refunds_after_the_deadline_if_the_goal_was_missed() {
+    let mut campaign = campaign(100);
+    campaign.record_pledge(99, 0).unwrap();
+    assert_eq!(
+        campaign.check_refund(1_000).err(),
+        Some(Error::from(CrowdfundError::CampaignRunning))
+    );
+    campaign.check_refund(1_001).unwrap();
+    assert_eq!(
+        campaign.check_claim(1_001).err(),
+        Some(Error::from(CrowdfundError::GoalMissed))
+    );
+}
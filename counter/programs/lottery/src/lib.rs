@@ -0,0 +1,384 @@
+// ============================================================================
+// LOTTERY - ticket sales counted by a counter, one winner takes the pot
+// ============================================================================
+
+// A creator opens a lottery with a ticket price and a close time. Until
+// then anyone can buy tickets; each one moves the price into the lottery
+// and increments its "entries" counter in the counter program through CPI.
+// After the close, the creator draws a winning ticket, and its holder
+// claims the whole pot.
+//
+// There's no VRF in this workspace yet, so the randomness is commit-reveal
+// by the creator: open_lottery takes sha256(seed), and draw takes the seed
+// itself and checks it. The creator can't pick the winner after seeing
+// the tickets, since the seed is pinned down before any are sold. What it
+// can't stop is a creator who knows the seed buying tickets to land on the
+// winning number, so a creator is trusted not to play their own lottery.
+// Swapping in a VRF only changes draw; winning_ticket already takes the
+// random bytes from the outside.
+//
+// If the creator doesn't draw by "draw_deadline", nobody wins: every
+// ticket can be refunded instead.
+//
+// Ticket numbers come from "tickets" on the Lottery, not from the counter:
+// the counter is open to anyone's increments like any other, so it's a
+// public tally of entries rather than something a payout can rely on.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    hash::hashv,
+    instruction::Instruction,
+    program::{invoke, invoke_signed},
+};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::InstructionData;
+use counter::program::Counter as CounterProgram;
+use counter::Counter;
+use nautilus_common::clock;
+
+declare_id!("EZVjqJ8nLubtnyv4mStyi6tcJi2pHtvahQNqH9RqL7Kb");
+
+pub const LOTTERY_SEED: &[u8] = b"lottery";
+pub const ENTRIES_SEED: &[u8] = b"entries";
+pub const TICKET_SEED: &[u8] = b"ticket";
+
+#[program]
+pub mod lottery {
+    use super::*;
+
+    // Opens a lottery and creates its entries counter. "seed_hash" is
+    // sha256 of the seed the creator will reveal in draw.
+    pub fn open_lottery(
+        ctx: Context<OpenLottery>,
+        id: u64,
+        terms: Terms,
+        seed_hash: [u8; 32],
+    ) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.creator = ctx.accounts.creator.key();
+        lottery.entries = ctx.accounts.entries.key();
+        lottery.id = id;
+        lottery.seed_hash = seed_hash;
+        lottery.tickets = 0;
+        lottery.winner = None;
+        lottery.paid = false;
+        lottery.bump = ctx.bumps.lottery;
+        lottery.set_terms(terms, clock::now()?)?;
+
+        // Built from the counter crate's generated types, like in
+        // counter-hook. The counter's address is our PDA, so we sign for it.
+        let initialize = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Initialize {
+                counter: ctx.accounts.entries.key(),
+                user: ctx.accounts.creator.key(),
+                system_program: ctx.accounts.system_program.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Initialize {}.data(),
+        };
+        let lottery_key = lottery.key();
+        invoke_signed(
+            &initialize,
+            &[
+                ctx.accounts.entries.to_account_info(),
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+            &[&[ENTRIES_SEED, lottery_key.as_ref(), &[ctx.bumps.entries]]],
+        )?;
+        Ok(())
+    }
+
+    // Buys the next ticket.
+    pub fn buy_ticket(ctx: Context<BuyTicket>) -> Result<()> {
+        let number = ctx.accounts.lottery.record_ticket(clock::now()?)?;
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.lottery = ctx.accounts.lottery.key();
+        ticket.holder = ctx.accounts.buyer.key();
+        ticket.number = number;
+        ticket.bump = ctx.bumps.ticket;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.lottery.to_account_info(),
+                },
+            ),
+            ctx.accounts.lottery.ticket_price,
+        )?;
+
+        let increment = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Increment {
+                counter: ctx.accounts.entries.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Increment {}.data(),
+        };
+        invoke(
+            &increment,
+            &[
+                ctx.accounts.entries.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+        )?;
+        // See counter-hook for why our copy has to be reloaded.
+        ctx.accounts.entries.reload()
+    }
+
+    // Reveals the seed and picks the winning ticket.
+    pub fn draw(ctx: Context<Draw>, seed: [u8; 32]) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.draw(seed, clock::now()?)?;
+        Ok(())
+    }
+
+    // Pays the pot to the winning ticket's holder.
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.check_prize(ctx.accounts.ticket.number)?;
+        lottery.paid = true;
+        let pot = lottery.pot();
+        lottery.sub_lamports(pot)?;
+        ctx.accounts.holder.add_lamports(pot)?;
+        Ok(())
+    }
+
+    // Gives a ticket's price back when the lottery was never drawn.
+    // "close = holder" on the ticket returns its rent too.
+    pub fn refund_ticket(ctx: Context<RefundTicket>) -> Result<()> {
+        let lottery = &ctx.accounts.lottery;
+        lottery.check_refund(clock::now()?)?;
+        lottery.sub_lamports(lottery.ticket_price)?;
+        ctx.accounts.holder.add_lamports(lottery.ticket_price)?;
+        Ok(())
+    }
+}
+
+// What the creator sets up.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Terms {
+    // Lamports.
+    pub ticket_price: u64,
+    // Unix times, in seconds. Tickets are sold until "close_time"; the
+    // creator can draw from then until "draw_deadline", and refunds open
+    // after it if they didn't.
+    pub close_time: i64,
+    pub draw_deadline: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Lottery {
+    pub creator: Pubkey,
+    pub entries: Pubkey,
+    pub id: u64,
+    pub ticket_price: u64,
+    pub close_time: i64,
+    pub draw_deadline: i64,
+    pub seed_hash: [u8; 32],
+    // Tickets sold. Ticket numbers run from 0 to tickets - 1.
+    pub tickets: u64,
+    // The winning ticket's number, once drawn.
+    pub winner: Option<u64>,
+    pub paid: bool,
+    pub bump: u8,
+}
+
+impl Lottery {
+    pub fn set_terms(&mut self, terms: Terms, now: i64) -> Result<()> {
+        require!(
+            terms.ticket_price > 0
+                && now < terms.close_time
+                && terms.close_time < terms.draw_deadline,
+            LotteryError::InvalidTerms
+        );
+        self.ticket_price = terms.ticket_price;
+        self.close_time = terms.close_time;
+        self.draw_deadline = terms.draw_deadline;
+        Ok(())
+    }
+
+    // Counts a ticket sold at "now", and returns its number.
+    pub fn record_ticket(&mut self, now: i64) -> Result<u64> {
+        require!(now < self.close_time, LotteryError::SalesClosed);
+        let number = self.tickets;
+        self.tickets = number.checked_add(1).ok_or(LotteryError::Overflow)?;
+        // So pot can't overflow later.
+        self.ticket_price
+            .checked_mul(self.tickets)
+            .ok_or(LotteryError::Overflow)?;
+        Ok(number)
+    }
+
+    pub fn draw(&mut self, seed: [u8; 32], now: i64) -> Result<()> {
+        require!(now >= self.close_time, LotteryError::SalesOpen);
+        require!(now <= self.draw_deadline, LotteryError::DrawDeadlinePassed);
+        require!(self.winner.is_none(), LotteryError::AlreadyDrawn);
+        require!(self.tickets > 0, LotteryError::NoTickets);
+        require!(
+            hashv(&[&seed]).to_bytes() == self.seed_hash,
+            LotteryError::WrongSeed
+        );
+        self.winner = Some(winning_ticket(&seed, self.tickets));
+        Ok(())
+    }
+
+    pub fn check_prize(&self, number: u64) -> Result<()> {
+        require!(self.winner == Some(number), LotteryError::NotTheWinner);
+        require!(!self.paid, LotteryError::AlreadyPaid);
+        Ok(())
+    }
+
+    pub fn check_refund(&self, now: i64) -> Result<()> {
+        require!(
+            self.winner.is_none() && now > self.draw_deadline,
+            LotteryError::NoRefund
+        );
+        Ok(())
+    }
+
+    // Everything the tickets paid in.
+    pub fn pot(&self) -> u64 {
+        self.ticket_price * self.tickets
+    }
+}
+
+// The winning number out of "tickets", from 32 random bytes. Reduces a
+// u128 rather than a u64, which keeps the bias towards low numbers
+// negligible for any number of tickets.
+pub fn winning_ticket(random: &[u8; 32], tickets: u64) -> u64 {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&random[..16]);
+    (u128::from_le_bytes(bytes) % u128::from(tickets)) as u64
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Ticket {
+    pub lottery: Pubkey,
+    pub holder: Pubkey,
+    pub number: u64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct OpenLottery<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Lottery::INIT_SPACE,
+        seeds = [LOTTERY_SEED, creator.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut, seeds = [ENTRIES_SEED, lottery.key().as_ref()], bump)]
+    /// CHECK: a new account the counter program initializes.
+    pub entries: UncheckedAccount<'info>,
+
+    // Pays for both, and becomes the counter's authority.
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTicket<'info> {
+    #[account(mut, has_one = entries)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub entries: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [TICKET_SEED, lottery.key().as_ref(), &lottery.tickets.to_le_bytes()],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Draw<'info> {
+    #[account(mut, has_one = creator @ LotteryError::NotCreator)]
+    pub lottery: Account<'info, Lottery>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        seeds = [TICKET_SEED, lottery.key().as_ref(), &ticket.number.to_le_bytes()],
+        bump = ticket.bump,
+        has_one = holder
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundTicket<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        seeds = [TICKET_SEED, lottery.key().as_ref(), &ticket.number.to_le_bytes()],
+        bump = ticket.bump,
+        has_one = holder,
+        close = holder
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+}
+
+#[error_code]
+pub enum LotteryError {
+    #[msg("Lottery needs a ticket price and now < close time < draw deadline")]
+    InvalidTerms,
+    #[msg("Lottery's totals would overflow")]
+    Overflow,
+    #[msg("Ticket sales have closed")]
+    SalesClosed,
+    #[msg("Ticket sales haven't closed yet")]
+    SalesOpen,
+    #[msg("Draw deadline has passed")]
+    DrawDeadlinePassed,
+    #[msg("Lottery was already drawn")]
+    AlreadyDrawn,
+    #[msg("Lottery sold no tickets")]
+    NoTickets,
+    #[msg("Seed doesn't match the lottery's seed hash")]
+    WrongSeed,
+    #[msg("Only the lottery's creator can draw")]
+    NotCreator,
+    #[msg("Ticket isn't the winner")]
+    NotTheWinner,
+    #[msg("Prize was already paid")]
+    AlreadyPaid,
+    #[msg("Refunds only open when the draw deadline passes without a draw")]
+    NoRefund,
+}
@@ -0,0 +1,82 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hashv;
+use lottery::{winning_ticket, Lottery, LotteryError};
+
+const SEED: [u8; 32] = [7; 32];
+
+fn lottery() -> Lottery {
+    Lottery {
+        creator: Pubkey::new_unique(),
+        entries: Pubkey::new_unique(),
+        id: 0,
+        ticket_price: 10,
+        close_time: 1_000,
+        draw_deadline: 2_000,
+        seed_hash: hashv(&[&SEED]).to_bytes(),
+        tickets: 0,
+        winner: None,
+        paid: false,
+        bump: 255,
+    }
+}
+
+#[test]
+fn sells_numbered_tickets_until_the_close() {
+    let mut lottery = lottery();
+    assert_eq!(lottery.record_ticket(0).unwrap(), 0);
+    assert_eq!(lottery.record_ticket(999).unwrap(), 1);
+    assert_eq!(lottery.pot(), 20);
+    assert_eq!(
+        lottery.record_ticket(1_000).err(),
+        Some(Error::from(LotteryError::SalesClosed))
+    );
+}
+
+#[test]
+fn draws_once_with_the_committed_seed() {
+    let mut lottery = lottery();
+    for _ in 0..5 {
+        lottery.record_ticket(0).unwrap();
+    }
+    assert_eq!(
+        lottery.draw(SEED, 999).err(),
+        Some(Error::from(LotteryError::SalesOpen))
+    );
+    assert_eq!(
+        lottery.draw([8; 32], 1_000).err(),
+        Some(Error::from(LotteryError::WrongSeed))
+    );
+    lottery.draw(SEED, 1_000).unwrap();
+    let winner = winning_ticket(&SEED, 5);
+    assert_eq!(lottery.winner, Some(winner));
+    assert_eq!(
+        lottery.draw(SEED, 1_000).err(),
+        Some(Error::from(LotteryError::AlreadyDrawn))
+    );
+
+    assert_eq!(
+        lottery.check_prize((winner + 1) % 5).err(),
+        Some(Error::from(LotteryError::NotTheWinner))
+    );
+    lottery.check_prize(winner).unwrap();
+    assert_eq!(
+        lottery.check_refund(2_001).err(),
+        Some(Error::from(LotteryError::NoRefund))
+    );
+}
+
+#[test]
+fn refunds_only_when_the_draw_deadline_is_missed() {
+    let mut lottery = lottery();
+    lottery.record_ticket(0).unwrap();
+    assert_eq!(
+        lottery.check_refund(2_000).err(),
+        Some(Error::from(LotteryError::NoRefund))
+    );
+    lottery.check_refund(2_001).unwrap();
+    assert_eq!(
+        lottery.draw(SEED, 2_001).err(),
+        Some(Error::from(LotteryError::DrawDeadlinePassed))
+    );
+}
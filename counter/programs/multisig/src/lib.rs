@@ -0,0 +1,306 @@
+// ============================================================================
+// MULTISIG - M-of-N wallets that sign with a PDA
+// ============================================================================
+
+// A multisig is a list of members and a threshold. Any member can propose
+// an instruction, for any program; once "threshold" members have approved
+// it, anyone can execute it, and it runs with the multisig's vault PDA as
+// a signer.
+//
+// The vault is what the multisig controls: make it the authority of a
+// counter (or the owner of a token account, the upgrade authority of a
+// program, ...) and only proposals the members approve can use it. It
+// holds no data, so it's a plain system account that can also hold SOL
+// and pay for things the proposals create.
+//
+// Members and the threshold are fixed when the multisig is created. Each
+// proposal runs at most once.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+
+declare_id!("Ax3ijL8193oNhCCrw9b1MyHtEkcyuSa2cJGKysB9RoBo");
+
+pub const MULTISIG_SEED: &[u8] = b"multisig";
+pub use nautilus_common::seeds::VAULT_SEED;
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+
+// Approvals are a bitmask over member indexes, so 16 at most.
+pub const MAX_MEMBERS: usize = 16;
+
+#[program]
+pub mod multisig {
+    use super::*;
+
+    // "id" lets one creator set up several multisigs.
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        _id: u64,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.set_members(members, threshold)?;
+        multisig.proposals = 0;
+        multisig.bump = ctx.bumps.multisig;
+        multisig.vault_bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    // Proposes "instruction", counting the proposer's approval.
+    pub fn propose(ctx: Context<Propose>, instruction: StoredInstruction) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let member = multisig.member_index(&ctx.accounts.proposer.key())?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.multisig = multisig.key();
+        proposal.index = multisig.proposals;
+        proposal.approvals = 0;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+        proposal.instruction = instruction;
+        proposal.approve(member)?;
+        multisig.proposals += 1;
+        Ok(())
+    }
+
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        let member = ctx
+            .accounts
+            .multisig
+            .member_index(&ctx.accounts.member.key())?;
+        ctx.accounts.proposal.approve(member)
+    }
+
+    // Runs an approved proposal. The instruction's accounts are passed as
+    // remaining accounts, in any order, along with the program it calls.
+    pub fn execute<'info>(ctx: Context<'_, '_, '_, 'info, Execute<'info>>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.check_execute(multisig.threshold)?;
+        proposal.executed = true;
+
+        let multisig_key = multisig.key();
+        invoke_signed(
+            &proposal.instruction.to_instruction(),
+            ctx.remaining_accounts,
+            &[&[VAULT_SEED, multisig_key.as_ref(), &[multisig.vault_bump]]],
+        )?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    #[max_len(MAX_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    // Approvals a proposal needs.
+    pub threshold: u8,
+    // Proposals made so far; the next one's index.
+    pub proposals: u64,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl Multisig {
+    pub fn set_members(&mut self, members: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_MEMBERS,
+            MultisigError::InvalidMembers
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(
+                !members[..i].contains(member),
+                MultisigError::InvalidMembers
+            );
+        }
+        require!(
+            threshold > 0 && usize::from(threshold) <= members.len(),
+            MultisigError::InvalidThreshold
+        );
+        self.members = members;
+        self.threshold = threshold;
+        Ok(())
+    }
+
+    pub fn member_index(&self, key: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|member| member == key)
+            .ok_or_else(|| error!(MultisigError::NotAMember))
+    }
+}
+
+// An instruction as a proposal stores it. Instruction and AccountMeta
+// aren't Borsh types, so these mirror them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StoredInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<StoredAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StoredAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl StoredInstruction {
+    // Bytes it takes up in a Proposal.
+    pub fn size(&self) -> usize {
+        32 + 4 + self.accounts.len() * (32 + 1 + 1) + 4 + self.data.len()
+    }
+
+    pub fn to_instruction(&self) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl From<Instruction> for StoredInstruction {
+    fn from(instruction: Instruction) -> Self {
+        StoredInstruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .into_iter()
+                .map(|meta| StoredAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data,
+        }
+    }
+}
+
+// No InitSpace: the size depends on the instruction (see size).
+#[account]
+#[derive(Debug)]
+pub struct Proposal {
+    pub multisig: Pubkey,
+    pub index: u64,
+    // Bit i is set once member i has approved.
+    pub approvals: u16,
+    pub executed: bool,
+    pub bump: u8,
+    pub instruction: StoredInstruction,
+}
+
+impl Proposal {
+    // Bytes a proposal of "instruction" takes up, not counting the
+    // discriminator.
+    pub fn size(instruction: &StoredInstruction) -> usize {
+        32 + 8 + 2 + 1 + 1 + instruction.size()
+    }
+
+    pub fn approve(&mut self, member: usize) -> Result<()> {
+        require!(!self.executed, MultisigError::AlreadyExecuted);
+        let bit = 1u16 << member;
+        require!(self.approvals & bit == 0, MultisigError::AlreadyApproved);
+        self.approvals |= bit;
+        Ok(())
+    }
+
+    pub fn check_execute(&self, threshold: u8) -> Result<()> {
+        require!(!self.executed, MultisigError::AlreadyExecuted);
+        require!(
+            self.approvals.count_ones() >= u32::from(threshold),
+            MultisigError::NotEnoughApprovals
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateMultisig<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Multisig::INIT_SPACE,
+        seeds = [MULTISIG_SEED, creator.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(seeds = [VAULT_SEED, multisig.key().as_ref()], bump)]
+    /// CHECK: never read; only its bump is stored.
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(instruction: StoredInstruction)]
+pub struct Propose<'info> {
+    #[account(mut)]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::size(&instruction),
+        seeds = [PROPOSAL_SEED, multisig.key().as_ref(), &multisig.proposals.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(mut, has_one = multisig)]
+    pub proposal: Account<'info, Proposal>,
+
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(mut, has_one = multisig)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[error_code]
+pub enum MultisigError {
+    #[msg("Multisig needs 1 to 16 distinct members")]
+    InvalidMembers,
+    #[msg("Threshold must be between 1 and the number of members")]
+    InvalidThreshold,
+    #[msg("Signer isn't a member of this multisig")]
+    NotAMember,
+    #[msg("Member already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Proposal doesn't have enough approvals yet")]
+    NotEnoughApprovals,
+    #[msg("Proposal was already executed")]
+    AlreadyExecuted,
+}
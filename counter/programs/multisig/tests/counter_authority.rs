@@ -0,0 +1,155 @@
+// The multisig's vault as a counter's authority. Without a runtime in
+// these tests, check the pieces execute puts together: a counter settings
+// instruction for the vault asks for the vault's signature, it survives
+// being stored in a proposal, and the seeds execute signs with are the
+// vault's. tests/litesvm/multisig.ts runs the whole thing.
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use counter::{ACCESS_LOG_SEED, LEASE_SEED};
+use multisig::{Multisig, MultisigError, Proposal, StoredInstruction, VAULT_SEED};
+
+fn vault(multisig: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, multisig.as_ref()], &multisig::ID)
+}
+
+fn set_alarm_threshold(counter: Pubkey, authority: Pubkey) -> Instruction {
+    let pda = |seed: &[u8]| Pubkey::find_program_address(&[seed, counter.as_ref()], &counter::ID).0;
+    Instruction {
+        program_id: counter::ID,
+        accounts: counter::accounts::UpdateCounter {
+            counter,
+            lease: pda(LEASE_SEED),
+            access_log: pda(ACCESS_LOG_SEED),
+            authority,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: counter::instruction::SetAlarmThreshold { threshold: 10 }.data(),
+    }
+}
+
+fn initialize_funded(counter: Pubkey, authority: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: counter::ID,
+        accounts: counter::accounts::InitializeFunded {
+            counter,
+            authority,
+            payer,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: counter::instruction::InitializeFunded {}.data(),
+    }
+}
+
+fn members(n: usize) -> Vec<Pubkey> {
+    (0..n).map(|_| Pubkey::new_unique()).collect()
+}
+
+#[test]
+fn vault_signs_counter_settings_through_a_proposal() {
+    let multisig = Pubkey::new_unique();
+    let (vault, bump) = vault(&multisig);
+    let instruction = set_alarm_threshold(Pubkey::new_unique(), vault);
+
+    let stored = StoredInstruction::from(instruction.clone());
+    assert_eq!(stored.to_instruction(), instruction);
+    assert!(stored
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == vault && meta.is_signer));
+
+    // The seeds execute passes to invoke_signed.
+    let signer =
+        Pubkey::create_program_address(&[VAULT_SEED, multisig.as_ref(), &[bump]], &multisig::ID)
+            .unwrap();
+    assert_eq!(signer, vault);
+}
+
+#[test]
+fn counters_are_created_for_the_vault_without_a_proposal() {
+    let (vault, _) = vault(&Pubkey::new_unique());
+    let instruction = initialize_funded(Pubkey::new_unique(), vault, Pubkey::new_unique());
+
+    // Anyone can make the vault a counter's authority: it doesn't sign.
+    let meta = instruction
+        .accounts
+        .iter()
+        .find(|meta| meta.pubkey == vault)
+        .unwrap();
+    assert!(!meta.is_signer && !meta.is_writable);
+
+    // It pays for the counter's access log on the first settings change,
+    // so it's asked to be writable as well as to sign.
+    let settings = set_alarm_threshold(Pubkey::new_unique(), vault);
+    assert!(settings
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == vault && meta.is_signer && meta.is_writable));
+}
+
+#[test]
+fn executes_once_with_enough_distinct_approvals() {
+    let stored = StoredInstruction::from(set_alarm_threshold(
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ));
+    let mut proposal = Proposal {
+        multisig: Pubkey::new_unique(),
+        index: 0,
+        approvals: 0,
+        executed: false,
+        bump: 255,
+        instruction: stored,
+    };
+    proposal.approve(0).unwrap();
+    assert_eq!(
+        proposal.approve(0).err(),
+        Some(Error::from(MultisigError::AlreadyApproved))
+    );
+    assert_eq!(
+        proposal.check_execute(2).err(),
+        Some(Error::from(MultisigError::NotEnoughApprovals))
+    );
+    proposal.approve(15).unwrap();
+    proposal.check_execute(2).unwrap();
+
+    proposal.executed = true;
+    assert_eq!(
+        proposal.check_execute(2).err(),
+        Some(Error::from(MultisigError::AlreadyExecuted))
+    );
+}
+
+#[test]
+fn members_are_distinct_and_cover_the_threshold() {
+    let mut multisig = Multisig {
+        members: Vec::new(),
+        threshold: 0,
+        proposals: 0,
+        bump: 255,
+        vault_bump: 255,
+    };
+    let three = members(3);
+    multisig.set_members(three.clone(), 2).unwrap();
+    assert_eq!(multisig.member_index(&three[2]).unwrap(), 2);
+    assert_eq!(
+        multisig.member_index(&Pubkey::new_unique()).err(),
+        Some(Error::from(MultisigError::NotAMember))
+    );
+
+    assert_eq!(
+        multisig.set_members(three.clone(), 4).err(),
+        Some(Error::from(MultisigError::InvalidThreshold))
+    );
+    assert_eq!(
+        multisig.set_members(vec![three[0], three[0]], 1).err(),
+        Some(Error::from(MultisigError::InvalidMembers))
+    );
+    assert_eq!(
+        multisig.set_members(members(17), 1).err(),
+        Some(Error::from(MultisigError::InvalidMembers))
+    );
+}
@@ -0,0 +1,319 @@
+// ============================================================================
+// TIPPING - tip jars with a supporters counter
+// ============================================================================
+
+// A creator opens a tip jar; fans tip it SOL or tokens. Every tip, in one
+// transaction, moves the money and increments the creator's "supporters"
+// counter in the counter program through CPI, so no tip goes uncounted.
+// (The counter itself is open to everyone's increments like any other;
+// "tips" on the Jar is the count only tips can move.)
+//
+// The jar is a PDA of the creator. SOL tips sit in the Jar account
+// itself; token tips sit in the jar's associated token account for that
+// mint, which the first tip in a mint creates. Only the creator can
+// withdraw.
+//
+// The supporters counter is created by open_jar at a PDA of this program,
+// with the creator as its authority, so the creator can use every counter
+// setting on it (an alarm at 1,000 tips, say). Turning on a mode that
+// routes increments elsewhere (fees, token mirroring, ...) or deny_cpi
+// stops tips until it's turned off again.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction,
+    program::{invoke, invoke_signed},
+};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::InstructionData;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use counter::program::Counter as CounterProgram;
+use counter::Counter;
+
+declare_id!("2LG6etGsuvNY2ebiv9jT7JHhU9C4dys8TsCDzG9eKaDN");
+
+pub const JAR_SEED: &[u8] = b"jar";
+pub const SUPPORTERS_SEED: &[u8] = b"supporters";
+
+#[program]
+pub mod tipping {
+    use super::*;
+
+    // Creates the creator's jar and supporters counter.
+    pub fn open_jar(ctx: Context<OpenJar>) -> Result<()> {
+        let jar = &mut ctx.accounts.jar;
+        jar.creator = ctx.accounts.creator.key();
+        jar.supporters = ctx.accounts.supporters.key();
+        jar.tips = 0;
+        jar.lamports_tipped = 0;
+        jar.bump = ctx.bumps.jar;
+
+        // Built from the counter crate's generated types, like in
+        // counter-hook. The counter's address is our PDA, so we sign for it.
+        let initialize = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Initialize {
+                counter: ctx.accounts.supporters.key(),
+                user: ctx.accounts.creator.key(),
+                system_program: ctx.accounts.system_program.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Initialize {}.data(),
+        };
+        let creator = ctx.accounts.creator.key();
+        invoke_signed(
+            &initialize,
+            &[
+                ctx.accounts.supporters.to_account_info(),
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+            &[&[SUPPORTERS_SEED, creator.as_ref(), &[ctx.bumps.supporters]]],
+        )?;
+        Ok(())
+    }
+
+    // Tips "lamports" of SOL.
+    pub fn tip(ctx: Context<Tip>, lamports: u64) -> Result<()> {
+        require!(lamports > 0, TippingError::EmptyTip);
+        ctx.accounts.jar.record_tip(lamports)?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.tipper.to_account_info(),
+                    to: ctx.accounts.jar.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+        count_supporter(&mut ctx.accounts.supporters, &ctx.accounts.counter_program)
+    }
+
+    // Tips "amount" of "mint".
+    pub fn tip_tokens(ctx: Context<TipTokens>, amount: u64) -> Result<()> {
+        require!(amount > 0, TippingError::EmptyTip);
+        // Token tips are counted, but not added to "lamports_tipped".
+        ctx.accounts.jar.record_tip(0)?;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.tipper_tokens.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.jar_tokens.to_account_info(),
+                    authority: ctx.accounts.tipper.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        count_supporter(&mut ctx.accounts.supporters, &ctx.accounts.counter_program)
+    }
+
+    // Sends "lamports" of the jar's SOL to the creator. The jar keeps its
+    // rent-exempt minimum.
+    pub fn withdraw(ctx: Context<Withdraw>, lamports: u64) -> Result<()> {
+        let jar = ctx.accounts.jar.to_account_info();
+        let available = withdrawable(jar.lamports(), Rent::get()?.minimum_balance(jar.data_len()));
+        require!(lamports <= available, TippingError::InsufficientFunds);
+        jar.sub_lamports(lamports)?;
+        ctx.accounts.creator.add_lamports(lamports)?;
+        Ok(())
+    }
+
+    // Sends "amount" of the jar's "mint" tokens to the creator.
+    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
+        let jar = &ctx.accounts.jar;
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.jar_tokens.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.creator_tokens.to_account_info(),
+                    authority: jar.to_account_info(),
+                },
+                &[&[JAR_SEED, jar.creator.as_ref(), &[jar.bump]]],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )
+    }
+}
+
+// Increments the supporters counter through CPI.
+fn count_supporter<'info>(
+    supporters: &mut Account<'info, Counter>,
+    counter_program: &Program<'info, CounterProgram>,
+) -> Result<()> {
+    let increment = Instruction {
+        program_id: counter::ID,
+        accounts: counter::accounts::Increment {
+            counter: supporters.key(),
+        }
+        .to_account_metas(None),
+        data: counter::instruction::Increment {}.data(),
+    };
+    invoke(
+        &increment,
+        &[
+            supporters.to_account_info(),
+            counter_program.to_account_info(),
+        ],
+    )?;
+    // See counter-hook for why our copy has to be reloaded.
+    supporters.reload()
+}
+
+// How much of a balance can be withdrawn without going below "rent".
+pub fn withdrawable(balance: u64, rent: u64) -> u64 {
+    balance.saturating_sub(rent)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Jar {
+    pub creator: Pubkey,
+    pub supporters: Pubkey,
+    // Tips of any kind so far.
+    pub tips: u64,
+    pub lamports_tipped: u64,
+    pub bump: u8,
+}
+
+impl Jar {
+    pub fn record_tip(&mut self, lamports: u64) -> Result<()> {
+        self.tips = self.tips.checked_add(1).ok_or(TippingError::Overflow)?;
+        self.lamports_tipped = self
+            .lamports_tipped
+            .checked_add(lamports)
+            .ok_or(TippingError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct OpenJar<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Jar::INIT_SPACE,
+        seeds = [JAR_SEED, creator.key().as_ref()],
+        bump
+    )]
+    pub jar: Account<'info, Jar>,
+
+    #[account(mut, seeds = [SUPPORTERS_SEED, creator.key().as_ref()], bump)]
+    /// CHECK: a new account the counter program initializes.
+    pub supporters: UncheckedAccount<'info>,
+
+    // Pays for both, and becomes the counter's authority.
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Tip<'info> {
+    #[account(mut, has_one = supporters)]
+    pub jar: Account<'info, Jar>,
+
+    #[account(mut)]
+    pub supporters: Account<'info, Counter>,
+
+    #[account(mut)]
+    pub tipper: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TipTokens<'info> {
+    #[account(mut, has_one = supporters)]
+    pub jar: Account<'info, Jar>,
+
+    #[account(mut)]
+    pub supporters: Account<'info, Counter>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = tipper,
+        associated_token::mint = mint,
+        associated_token::authority = jar,
+        associated_token::token_program = token_program
+    )]
+    pub jar_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = tipper,
+        token::token_program = token_program
+    )]
+    pub tipper_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub tipper: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, has_one = creator @ TippingError::NotCreator)]
+    pub jar: Account<'info, Jar>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokens<'info> {
+    #[account(has_one = creator @ TippingError::NotCreator)]
+    pub jar: Account<'info, Jar>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = jar,
+        associated_token::token_program = token_program
+    )]
+    pub jar_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub creator_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    pub creator: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum TippingError {
+    #[msg("Tip must be more than nothing")]
+    EmptyTip,
+    #[msg("Jar's totals would overflow")]
+    Overflow,
+    #[msg("Only the jar's creator can withdraw")]
+    NotCreator,
+    #[msg("Jar doesn't hold that much above its rent")]
+    InsufficientFunds,
+}
@@ -0,0 +1,29 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use tipping::{withdrawable, Jar, TippingError};
+
+#[test]
+fn counts_every_tip_and_sums_sol() {
+    let mut jar = Jar {
+        creator: Pubkey::new_unique(),
+        supporters: Pubkey::new_unique(),
+        tips: 0,
+        lamports_tipped: 0,
+        bump: 255,
+    };
+    jar.record_tip(500).unwrap();
+    jar.record_tip(0).unwrap();
+    assert_eq!((jar.tips, jar.lamports_tipped), (2, 500));
+
+    jar.lamports_tipped = u64::MAX;
+    assert_eq!(
+        jar.record_tip(1).err(),
+        Some(Error::from(TippingError::Overflow))
+    );
+}
+
+#[test]
+fn withdrawals_leave_the_rent() {
+    assert_eq!(withdrawable(5_000, 1_000), 4_000);
+    assert_eq!(withdrawable(900, 1_000), 0);
+}
@@ -0,0 +1,82 @@
+// ============================================================================
+// CONFIG - program-wide limits, owned by the deployer
+// ============================================================================
+
+// The same arrangement as the counter program's config.rs: one Config PDA,
+// created once by the program's upgrade authority, who picks an admin to
+// manage it. Here it holds the limit every list's size is checked
+// against. Lists grow with their items, and each item costs its adder
+// rent, so the cap is there to keep one list from outgrowing what clients
+// will load.
+use anchor_lang::prelude::*;
+
+use crate::program::Todo as TodoProgram;
+use crate::TodoError;
+
+pub use nautilus_common::seeds::CONFIG_SEED;
+
+// Until the admin says otherwise.
+pub const DEFAULT_MAX_ITEMS: u16 = 64;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    // The most items a list can hold.
+    pub max_items: u16,
+    pub bump: u8,
+}
+
+pub fn process_initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = admin;
+    config.max_items = DEFAULT_MAX_ITEMS;
+    config.bump = ctx.bumps.config;
+    Ok(())
+}
+
+// Lowering the limit doesn't touch lists already over it; they just can't
+// grow until they're back under.
+pub fn process_set_max_items(ctx: Context<SetMaxItems>, max_items: u16) -> Result<()> {
+    ctx.accounts.config.max_items = max_items;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // See InitializeConfig in the counter program.
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, TodoProgram>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ TodoError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxItems<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = admin @ TodoError::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+}
@@ -0,0 +1,36 @@
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+// Like the counter program's events, each one carries "event_seq", the
+// list's own event number, going up by exactly 1 per event (see
+// TodoList::next_event_seq), so a listener can tell when it missed one.
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct ListCreated {
+    pub list: Pubkey,
+    pub owner: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ItemAdded {
+    pub list: Pubkey,
+    pub item: u32,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ItemCompleted {
+    pub list: Pubkey,
+    pub item: u32,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ItemDeleted {
+    pub list: Pubkey,
+    pub item: u32,
+    pub event_seq: u64,
+}
@@ -0,0 +1,285 @@
+// ============================================================================
+// TODO - lists whose accounts grow and shrink with them
+// ============================================================================
+
+// Every account in the counter program has a size fixed when it's created.
+// A todo list can't: it's as long as its items, and each item's text is
+// as long as its author made it.
+//
+// Solana accounts can be resized after creation ("realloc"). Anchor's
+// "realloc" constraint does it before the instruction runs: it sets the
+// new size, and tops up the rent from "realloc::payer" when the account
+// grows or refunds it when it shrinks. So add_item grows the list by
+// exactly the item it adds, and delete_item shrinks it by exactly the
+// item it removes. Nobody pays rent for space they don't use.
+//
+// The layout follows the counter program: the instructions are listed
+// here, events in events.rs, and the program-wide Config (the largest a
+// list can get) in config.rs, created by the upgrade authority.
+use anchor_lang::prelude::*;
+use nautilus_common::seq;
+
+pub mod config;
+pub use config::*;
+pub mod events;
+pub use events::*;
+
+declare_id!("66gkJmdu64KsjS6sr8BmCcP7oR46DQZX1L8oT9LF5Wbh");
+
+pub const LIST_SEED: &[u8] = b"list";
+
+pub const MAX_NAME_LEN: usize = 32;
+pub const MAX_TEXT_LEN: usize = 280;
+
+#[program]
+pub mod todo {
+    use super::*;
+
+    // Creates the program-wide Config. Only the upgrade authority can,
+    // once.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        config::process_initialize_config(ctx, admin)
+    }
+
+    pub fn set_max_items(ctx: Context<SetMaxItems>, max_items: u16) -> Result<()> {
+        config::process_set_max_items(ctx, max_items)
+    }
+
+    // Creates an empty list. "id" lets an owner have several.
+    pub fn create_list(ctx: Context<CreateList>, _id: u64, name: String) -> Result<()> {
+        require!(name.len() <= MAX_NAME_LEN, TodoError::NameTooLong);
+        let list = &mut ctx.accounts.list;
+        list.owner = ctx.accounts.owner.key();
+        list.name = name;
+        list.next_id = 0;
+        list.event_seq = 0;
+        list.bump = ctx.bumps.list;
+        list.items = Vec::new();
+        emit!(ListCreated {
+            list: list.key(),
+            owner: list.owner,
+            event_seq: list.next_event_seq(),
+        });
+        Ok(())
+    }
+
+    pub fn add_item(ctx: Context<AddItem>, text: String) -> Result<()> {
+        let list = &mut ctx.accounts.list;
+        let item = list.add(text, ctx.accounts.config.max_items)?;
+        emit!(ItemAdded {
+            list: list.key(),
+            item,
+            event_seq: list.next_event_seq(),
+        });
+        Ok(())
+    }
+
+    pub fn complete_item(ctx: Context<CompleteItem>, item: u32) -> Result<()> {
+        let list = &mut ctx.accounts.list;
+        list.complete(item)?;
+        emit!(ItemCompleted {
+            list: list.key(),
+            item,
+            event_seq: list.next_event_seq(),
+        });
+        Ok(())
+    }
+
+    pub fn delete_item(ctx: Context<DeleteItem>, item: u32) -> Result<()> {
+        let list = &mut ctx.accounts.list;
+        list.delete(item)?;
+        emit!(ItemDeleted {
+            list: list.key(),
+            item,
+            event_seq: list.next_event_seq(),
+        });
+        Ok(())
+    }
+}
+
+// No InitSpace: the size depends on what's in the list (see size).
+#[account]
+#[derive(Debug)]
+pub struct TodoList {
+    pub owner: Pubkey,
+    pub name: String,
+    // The id the next item gets. Ids aren't reused after a delete, so an
+    // id in an old event always means the same item.
+    pub next_id: u32,
+    pub event_seq: u64,
+    pub bump: u8,
+    // Last on purpose, so everything before it stays at a fixed offset
+    // for a given name.
+    pub items: Vec<Item>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    pub id: u32,
+    pub done: bool,
+    pub text: String,
+}
+
+impl Item {
+    // Bytes an item with "text" takes up: the id, "done", and the text
+    // with its 4-byte length.
+    pub fn size(text: &str) -> usize {
+        4 + 1 + 4 + text.len()
+    }
+}
+
+impl TodoList {
+    // Bytes a list takes up, not counting the discriminator.
+    pub fn size(&self) -> usize {
+        TodoList::empty_size(&self.name)
+            + self
+                .items
+                .iter()
+                .map(|item| Item::size(&item.text))
+                .sum::<usize>()
+    }
+
+    // The size of a list named "name" with no items.
+    pub fn empty_size(name: &str) -> usize {
+        32 + 4 + name.len() + 4 + 8 + 1 + 4
+    }
+
+    // The size of item "id", or 0 if there's no such item (the
+    // instruction then fails, undoing the realloc).
+    pub fn item_size(&self, id: u32) -> usize {
+        self.items
+            .iter()
+            .find(|item| item.id == id)
+            .map_or(0, |item| Item::size(&item.text))
+    }
+
+    // Adds an item and returns its id.
+    pub fn add(&mut self, text: String, max_items: u16) -> Result<u32> {
+        require!(text.len() <= MAX_TEXT_LEN, TodoError::TextTooLong);
+        require!(
+            self.items.len() < usize::from(max_items),
+            TodoError::ListFull
+        );
+        let id = self.next_id;
+        self.next_id = id.checked_add(1).ok_or(TodoError::ListFull)?;
+        self.items.push(Item {
+            id,
+            done: false,
+            text,
+        });
+        Ok(id)
+    }
+
+    pub fn complete(&mut self, id: u32) -> Result<()> {
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or(TodoError::NoSuchItem)?;
+        require!(!item.done, TodoError::AlreadyDone);
+        item.done = true;
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: u32) -> Result<()> {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or(TodoError::NoSuchItem)?;
+        self.items.remove(index);
+        Ok(())
+    }
+
+    pub fn next_event_seq(&mut self) -> u64 {
+        seq::next(&mut self.event_seq)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64, name: String)]
+pub struct CreateList<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + TodoList::empty_size(&name),
+        seeds = [LIST_SEED, owner.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub list: Account<'info, TodoList>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(text: String)]
+pub struct AddItem<'info> {
+    // Grows by the new item before add_item runs; the owner pays the
+    // extra rent.
+    #[account(
+        mut,
+        has_one = owner @ TodoError::NotOwner,
+        realloc = 8 + list.size() + Item::size(&text),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub list: Account<'info, TodoList>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteItem<'info> {
+    #[account(mut, has_one = owner @ TodoError::NotOwner)]
+    pub list: Account<'info, TodoList>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(item: u32)]
+pub struct DeleteItem<'info> {
+    // Shrinks by the deleted item; the owner gets its rent back.
+    #[account(
+        mut,
+        has_one = owner @ TodoError::NotOwner,
+        realloc = 8 + list.size() - list.item_size(item),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub list: Account<'info, TodoList>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code]
+pub enum TodoError {
+    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+    #[msg("Signer is not the config's admin")]
+    NotAdmin,
+    #[msg("Signer doesn't own this list")]
+    NotOwner,
+    #[msg("List name is longer than 32 bytes")]
+    NameTooLong,
+    #[msg("Item text is longer than 280 bytes")]
+    TextTooLong,
+    #[msg("List has as many items as it can hold")]
+    ListFull,
+    #[msg("List has no item with that id")]
+    NoSuchItem,
+    #[msg("Item is already done")]
+    AlreadyDone,
+}
@@ -0,0 +1,68 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AnchorSerialize;
+use todo::{Item, TodoError, TodoList};
+
+fn list() -> TodoList {
+    TodoList {
+        owner: Pubkey::new_unique(),
+        name: "groceries".to_string(),
+        next_id: 0,
+        event_seq: 0,
+        bump: 255,
+        items: Vec::new(),
+    }
+}
+
+#[test]
+fn size_matches_the_serialized_list() {
+    let mut list = list();
+    assert_eq!(list.size(), list.try_to_vec().unwrap().len());
+    list.add("milk".to_string(), 10).unwrap();
+    list.add("a much longer item".to_string(), 10).unwrap();
+    assert_eq!(list.size(), list.try_to_vec().unwrap().len());
+
+    // What add_item and delete_item realloc to.
+    let before = list.size();
+    list.delete(0).unwrap();
+    assert_eq!(list.size(), before - Item::size("milk"));
+    assert_eq!(list.item_size(0), 0);
+}
+
+#[test]
+fn ids_are_never_reused() {
+    let mut list = list();
+    assert_eq!(list.add("a".to_string(), 10).unwrap(), 0);
+    list.delete(0).unwrap();
+    assert_eq!(list.add("b".to_string(), 10).unwrap(), 1);
+    assert_eq!(
+        list.delete(0).err(),
+        Some(Error::from(TodoError::NoSuchItem))
+    );
+}
+
+#[test]
+fn completes_each_item_once() {
+    let mut list = list();
+    let id = list.add("a".to_string(), 10).unwrap();
+    list.complete(id).unwrap();
+    assert!(list.items[0].done);
+    assert_eq!(
+        list.complete(id).err(),
+        Some(Error::from(TodoError::AlreadyDone))
+    );
+}
+
+#[test]
+fn enforces_the_limits() {
+    let mut list = list();
+    list.add("a".to_string(), 1).unwrap();
+    assert_eq!(
+        list.add("b".to_string(), 1).err(),
+        Some(Error::from(TodoError::ListFull))
+    );
+    assert_eq!(
+        list.add("x".repeat(281), 10).err(),
+        Some(Error::from(TodoError::TextTooLong))
+    );
+}
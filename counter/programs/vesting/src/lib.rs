@@ -0,0 +1,278 @@
+// ============================================================================
+// VESTING - token grants released over time
+// ============================================================================
+
+// A funder locks tokens up for a beneficiary, who can claim them as they
+// vest. The schedule is three times (Unix seconds):
+//
+//   start ......... cliff ............ end
+//   nothing vested  |  vests linearly  |  all vested
+//
+// Before the cliff nothing can be claimed. From the cliff on, the vested
+// amount is the share of start..end that has gone by, so the cliff
+// releases everything that accrued since start at once. "cliff == start"
+// is a plain linear schedule; "cliff == end" releases it all at end.
+//
+// Each grant is a PDA of the funder and an id the funder picks. Its tokens
+// sit in the grant's associated token account, which only the grant PDA
+// can sign for. The beneficiary claims whatever has vested and not been
+// claimed yet, as often as they like; once everything has been claimed,
+// close returns the rent of both accounts to the funder.
+//
+// There's no revoking: once funded, a grant vests on its schedule
+// regardless of what the funder does.
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use nautilus_common::{clock, math};
+
+declare_id!("FdjmG2McQwjpXfsWgmmcSWtjXc1o5dXXACPgTnxuzbVH");
+
+pub const GRANT_SEED: &[u8] = b"grant";
+
+#[program]
+pub mod vesting {
+    use super::*;
+
+    // Locks "schedule.total" of "mint" up for "beneficiary".
+    pub fn create_grant(
+        ctx: Context<CreateGrant>,
+        id: u64,
+        beneficiary: Pubkey,
+        schedule: Schedule,
+    ) -> Result<()> {
+        schedule.validate()?;
+        let grant = &mut ctx.accounts.grant;
+        grant.funder = ctx.accounts.funder.key();
+        grant.beneficiary = beneficiary;
+        grant.mint = ctx.accounts.mint.key();
+        grant.id = id;
+        grant.schedule = schedule;
+        grant.claimed = 0;
+        grant.bump = ctx.bumps.grant;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.funder_tokens.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            schedule.total,
+            ctx.accounts.mint.decimals,
+        )
+    }
+
+    // Sends the beneficiary everything vested so far that they haven't
+    // claimed.
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        let grant = &mut ctx.accounts.grant;
+        let amount = grant.claim(clock::now()?)?;
+        let id = grant.id.to_le_bytes();
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.beneficiary_tokens.to_account_info(),
+                    authority: grant.to_account_info(),
+                },
+                &[&[GRANT_SEED, grant.funder.as_ref(), &id, &[grant.bump]]],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )
+    }
+
+    // Closes a fully claimed grant and its token account. Anyone can call
+    // it, since the rent can only go back to the funder.
+    pub fn close(ctx: Context<Close>) -> Result<()> {
+        let grant = &ctx.accounts.grant;
+        require!(
+            grant.claimed == grant.schedule.total,
+            VestingError::NotFullyClaimed
+        );
+        let id = grant.id.to_le_bytes();
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.funder.to_account_info(),
+                authority: grant.to_account_info(),
+            },
+            &[&[GRANT_SEED, grant.funder.as_ref(), &id, &[grant.bump]]],
+        ))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct Schedule {
+    // In the token's smallest unit.
+    pub total: u64,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.total > 0
+                && self.start <= self.cliff
+                && self.cliff <= self.end
+                && self.start < self.end,
+            VestingError::InvalidSchedule
+        );
+        Ok(())
+    }
+
+    // How much has vested by "now".
+    pub fn vested(&self, now: i64) -> u64 {
+        if now < self.cliff {
+            0
+        } else if now >= self.end {
+            self.total
+        } else {
+            let elapsed = (now - self.start) as u64;
+            let duration = (self.end - self.start) as u64;
+            math::portion(self.total, elapsed, duration)
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Grant {
+    pub funder: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub id: u64,
+    pub schedule: Schedule,
+    // Claimed so far. Never more than schedule.vested(now).
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+impl Grant {
+    // Marks whatever is claimable at "now" as claimed, and returns it.
+    pub fn claim(&mut self, now: i64) -> Result<u64> {
+        let amount = self.schedule.vested(now) - self.claimed;
+        require!(amount > 0, VestingError::NothingToClaim);
+        self.claimed += amount;
+        Ok(amount)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreateGrant<'info> {
+    #[account(
+        init,
+        payer = funder,
+        space = 8 + Grant::INIT_SPACE,
+        seeds = [GRANT_SEED, funder.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub grant: Account<'info, Grant>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = funder,
+        associated_token::mint = mint,
+        associated_token::authority = grant,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = funder,
+        token::token_program = token_program
+    )]
+    pub funder_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(
+        mut,
+        has_one = beneficiary @ VestingError::NotBeneficiary,
+        has_one = mint
+    )]
+    pub grant: Account<'info, Grant>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = grant,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub beneficiary_tokens: InterfaceAccount<'info, TokenAccount>,
+
+    pub beneficiary: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Close<'info> {
+    #[account(mut, has_one = funder, has_one = mint, close = funder)]
+    pub grant: Account<'info, Grant>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = grant,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: only receives rent; has_one on the grant checks it's the
+    /// funder.
+    #[account(mut)]
+    pub funder: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[error_code]
+pub enum VestingError {
+    #[msg("Schedule needs a total and start <= cliff <= end, with start < end")]
+    InvalidSchedule,
+    #[msg("Nothing has vested since the last claim")]
+    NothingToClaim,
+    #[msg("Only the grant's beneficiary can claim")]
+    NotBeneficiary,
+    #[msg("Grant still has tokens to claim")]
+    NotFullyClaimed,
+}
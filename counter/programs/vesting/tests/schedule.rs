@@ -0,0 +1,90 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use vesting::{Grant, Schedule, VestingError};
+
+fn schedule(cliff: i64) -> Schedule {
+    Schedule {
+        total: 1_000,
+        start: 100,
+        cliff,
+        end: 200,
+    }
+}
+
+fn grant(schedule: Schedule) -> Grant {
+    Grant {
+        funder: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        id: 0,
+        schedule,
+        claimed: 0,
+        bump: 255,
+    }
+}
+
+#[test]
+fn vests_linearly_after_the_cliff() {
+    let linear = schedule(100);
+    assert_eq!(linear.vested(50), 0);
+    assert_eq!(linear.vested(125), 250);
+    assert_eq!(linear.vested(200), 1_000);
+    assert_eq!(linear.vested(i64::MAX), 1_000);
+
+    let cliff = schedule(150);
+    assert_eq!(cliff.vested(149), 0);
+    assert_eq!(cliff.vested(150), 500);
+    assert_eq!(cliff.vested(175), 750);
+
+    let all_at_end = schedule(200);
+    assert_eq!(all_at_end.vested(199), 0);
+    assert_eq!(all_at_end.vested(200), 1_000);
+
+    let huge = Schedule {
+        total: u64::MAX,
+        ..linear
+    };
+    assert_eq!(huge.vested(150), u64::MAX / 2);
+}
+
+#[test]
+fn claims_only_what_vested_since_the_last_claim() {
+    let mut grant = grant(schedule(150));
+    assert_eq!(
+        grant.claim(149).err(),
+        Some(Error::from(VestingError::NothingToClaim))
+    );
+    assert_eq!(grant.claim(150).unwrap(), 500);
+    assert_eq!(
+        grant.claim(150).err(),
+        Some(Error::from(VestingError::NothingToClaim))
+    );
+    assert_eq!(grant.claim(175).unwrap(), 250);
+    assert_eq!(grant.claim(300).unwrap(), 250);
+    assert_eq!(grant.claimed, 1_000);
+}
+
+#[test]
+fn rejects_out_of_order_schedules() {
+    schedule(100).validate().unwrap();
+    schedule(200).validate().unwrap();
+    for bad in [
+        Schedule {
+            total: 0,
+            ..schedule(100)
+        },
+        schedule(99),
+        schedule(201),
+        Schedule {
+            start: 200,
+            cliff: 200,
+            end: 200,
+            total: 1,
+        },
+    ] {
+        assert_eq!(
+            bad.validate().err(),
+            Some(Error::from(VestingError::InvalidSchedule))
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// ============================================================================
+// VOTING - polls tallied by counters
+// ============================================================================
+
+// A poll has up to MAX_OPTIONS options and a close time. Each option gets
+// its own counter in the counter program, at a PDA of this program, so its
+// tally shows up in every tool that reads counters (counter-cli, the
+// notifier, feeds) without knowing anything about polls.
+//
+// Setup:
+//   1. create_poll with the option labels and a close time.
+//   2. open_option once per option, which creates its counter through a
+//      CPI to the counter program's initialize. The counter's address is a
+//      PDA of this program, so this program signs for it.
+//
+// Each wallet votes once per poll: vote creates a VoteReceipt PDA of the
+// poll and the voter with "init", which fails the second time. After the
+// close time anyone can call finalize_poll, which records the winner.
+//
+// Counter increments are open to everyone, so an option's counter can be
+// pushed up without voting. The poll keeps its own tally next to it, and
+// that's what finalize_poll goes by; a counter ahead of its tally is
+// visible proof that someone tried. The poll's creator is the counters'
+// authority and could also archive them, which would stop the voting; a
+// poll is only as neutral as whoever created it.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::Instruction,
+    program::{invoke, invoke_signed},
+};
+use anchor_lang::InstructionData;
+use counter::program::Counter as CounterProgram;
+use counter::Counter;
+use nautilus_common::clock;
+
+declare_id!("5C8ybgEDdqaMPnBRfoqm3yK2iZrmLSK1KpthTRFKqs9U");
+
+pub const POLL_SEED: &[u8] = b"poll";
+pub const OPTION_SEED: &[u8] = b"option";
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+pub const MAX_OPTIONS: usize = 8;
+pub const MAX_LABEL_LEN: usize = 32;
+
+#[program]
+pub mod voting {
+    use super::*;
+
+    // Creates a poll. "id" lets a creator have several.
+    pub fn create_poll(
+        ctx: Context<CreatePoll>,
+        id: u64,
+        labels: Vec<String>,
+        close_time: i64,
+    ) -> Result<()> {
+        require!(
+            (2..=MAX_OPTIONS).contains(&labels.len())
+                && labels.iter().all(|label| label.len() <= MAX_LABEL_LEN),
+            VotingError::InvalidOptions
+        );
+        require!(close_time > clock::now()?, VotingError::InvalidCloseTime);
+
+        let poll = &mut ctx.accounts.poll;
+        poll.creator = ctx.accounts.creator.key();
+        poll.id = id;
+        poll.close_time = close_time;
+        poll.votes = vec![0; labels.len()];
+        poll.labels = labels;
+        poll.opened = 0;
+        poll.finalized = false;
+        poll.winner = None;
+        poll.bump = ctx.bumps.poll;
+        Ok(())
+    }
+
+    // Creates the counter for option "index". Options are opened in order,
+    // and voting needs all of them open.
+    pub fn open_option(ctx: Context<OpenOption>, index: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            usize::from(index) == usize::from(poll.opened)
+                && usize::from(index) < poll.labels.len(),
+            VotingError::InvalidOption
+        );
+        poll.opened += 1;
+
+        // Like counter-hook, the instruction is built from the counter
+        // crate's generated types, since its "cpi" feature doesn't compile.
+        let initialize = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Initialize {
+                counter: ctx.accounts.option_counter.key(),
+                user: ctx.accounts.creator.key(),
+                system_program: ctx.accounts.system_program.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Initialize {}.data(),
+        };
+        let poll_key = poll.key();
+        invoke_signed(
+            &initialize,
+            &[
+                ctx.accounts.option_counter.to_account_info(),
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+            &[&[
+                OPTION_SEED,
+                poll_key.as_ref(),
+                &[index],
+                &[ctx.bumps.option_counter],
+            ]],
+        )?;
+        Ok(())
+    }
+
+    // Casts the voter's one vote, for option "index".
+    pub fn vote(ctx: Context<Vote>, index: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        poll.record_vote(index, clock::now()?)?;
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.poll = poll.key();
+        receipt.voter = ctx.accounts.voter.key();
+        receipt.option = index;
+        receipt.bump = ctx.bumps.receipt;
+
+        let increment = Instruction {
+            program_id: counter::ID,
+            accounts: counter::accounts::Increment {
+                counter: ctx.accounts.option_counter.key(),
+            }
+            .to_account_metas(None),
+            data: counter::instruction::Increment {}.data(),
+        };
+        invoke(
+            &increment,
+            &[
+                ctx.accounts.option_counter.to_account_info(),
+                ctx.accounts.counter_program.to_account_info(),
+            ],
+        )?;
+        // Our copy predates the increment; see counter-hook for why it
+        // has to be reloaded before Anchor writes it back.
+        ctx.accounts.option_counter.reload()?;
+        Ok(())
+    }
+
+    // Records the winner once the poll has closed. Anyone can call it.
+    pub fn finalize_poll(ctx: Context<FinalizePoll>) -> Result<()> {
+        ctx.accounts.poll.finalize(clock::now()?)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Poll {
+    pub creator: Pubkey,
+    pub id: u64,
+    // Unix time, in seconds. Votes are accepted until then.
+    pub close_time: i64,
+    #[max_len(MAX_OPTIONS, MAX_LABEL_LEN)]
+    pub labels: Vec<String>,
+    // This poll's own tally, one entry per option.
+    #[max_len(MAX_OPTIONS)]
+    pub votes: Vec<u64>,
+    // Options whose counter has been created.
+    pub opened: u8,
+    pub finalized: bool,
+    // The option with the most votes. None if there's a tie for first,
+    // including the tie of nobody voting.
+    pub winner: Option<u8>,
+    pub bump: u8,
+}
+
+impl Poll {
+    pub fn record_vote(&mut self, index: u8, now: i64) -> Result<()> {
+        require!(now < self.close_time, VotingError::PollClosed);
+        require!(
+            usize::from(self.opened) == self.labels.len(),
+            VotingError::OptionsNotOpen
+        );
+        let votes = self
+            .votes
+            .get_mut(usize::from(index))
+            .ok_or(VotingError::InvalidOption)?;
+        *votes += 1;
+        Ok(())
+    }
+
+    pub fn finalize(&mut self, now: i64) -> Result<()> {
+        require!(now >= self.close_time, VotingError::PollOpen);
+        require!(!self.finalized, VotingError::AlreadyFinalized);
+        let most = self.votes.iter().copied().max().unwrap_or(0);
+        let mut leaders = (0u8..).zip(&self.votes).filter(|(_, &votes)| votes == most);
+        self.winner = match (leaders.next(), leaders.next()) {
+            (Some((index, _)), None) => Some(index),
+            _ => None,
+        };
+        self.finalized = true;
+        Ok(())
+    }
+}
+
+// Proof that "voter" voted in "poll". Its address is what makes votes
+// one per wallet.
+#[account]
+#[derive(InitSpace)]
+pub struct VoteReceipt {
+    pub poll: Pubkey,
+    pub voter: Pubkey,
+    pub option: u8,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct CreatePoll<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Poll::INIT_SPACE,
+        seeds = [POLL_SEED, creator.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct OpenOption<'info> {
+    #[account(mut, has_one = creator)]
+    pub poll: Account<'info, Poll>,
+
+    // Created by the counter program's initialize, not by Anchor here.
+    #[account(mut, seeds = [OPTION_SEED, poll.key().as_ref(), &[index]], bump)]
+    /// CHECK: a new account the counter program initializes.
+    pub option_counter: UncheckedAccount<'info>,
+
+    // Pays for the counter and becomes its authority.
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct Vote<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut, seeds = [OPTION_SEED, poll.key().as_ref(), &[index]], bump)]
+    pub option_counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoteReceipt::INIT_SPACE,
+        seeds = [RECEIPT_SEED, poll.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, VoteReceipt>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub counter_program: Program<'info, CounterProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizePoll<'info> {
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+}
+
+#[error_code]
+pub enum VotingError {
+    #[msg("A poll needs 2 to 8 options, with labels of at most 32 bytes")]
+    InvalidOptions,
+    #[msg("Poll's close time must be in the future")]
+    InvalidCloseTime,
+    #[msg("No such option, or not the next one to open")]
+    InvalidOption,
+    #[msg("Not all of the poll's options are open yet")]
+    OptionsNotOpen,
+    #[msg("Poll is closed")]
+    PollClosed,
+    #[msg("Poll hasn't closed yet")]
+    PollOpen,
+    #[msg("Poll is already finalized")]
+    AlreadyFinalized,
+}
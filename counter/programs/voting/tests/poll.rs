@@ -0,0 +1,66 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use voting::{Poll, VotingError};
+
+fn poll(options: usize) -> Poll {
+    Poll {
+        creator: Pubkey::new_unique(),
+        id: 0,
+        close_time: 1_000,
+        labels: vec!["option".to_string(); options],
+        votes: vec![0; options],
+        opened: options as u8,
+        finalized: false,
+        winner: None,
+        bump: 255,
+    }
+}
+
+#[test]
+fn votes_only_while_open_and_fully_set_up() {
+    let mut poll = poll(3);
+    poll.record_vote(2, 999).unwrap();
+    assert_eq!(poll.votes, [0, 0, 1]);
+    assert_eq!(
+        poll.record_vote(3, 999).err(),
+        Some(Error::from(VotingError::InvalidOption))
+    );
+    assert_eq!(
+        poll.record_vote(0, 1_000).err(),
+        Some(Error::from(VotingError::PollClosed))
+    );
+
+    poll.opened = 2;
+    assert_eq!(
+        poll.record_vote(0, 999).err(),
+        Some(Error::from(VotingError::OptionsNotOpen))
+    );
+}
+
+#[test]
+fn finalizes_once_after_close() {
+    let mut poll = poll(3);
+    poll.votes = vec![4, 7, 2];
+    assert_eq!(
+        poll.finalize(999).err(),
+        Some(Error::from(VotingError::PollOpen))
+    );
+    poll.finalize(1_000).unwrap();
+    assert_eq!(poll.winner, Some(1));
+    assert_eq!(
+        poll.finalize(1_001).err(),
+        Some(Error::from(VotingError::AlreadyFinalized))
+    );
+}
+
+#[test]
+fn ties_have_no_winner() {
+    let mut tied = poll(3);
+    tied.votes = vec![5, 1, 5];
+    tied.finalize(1_000).unwrap();
+    assert_eq!(tied.winner, None);
+
+    let mut empty = poll(2);
+    empty.finalize(1_000).unwrap();
+    assert_eq!(empty.winner, None);
+}
@@ -0,0 +1,74 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Counter } from "../target/types/counter";
+import { assert } from "chai";
+
+// Like program-info.ts, needs a real upgradeable deployment where the
+// provider wallet is the upgrade authority.
+describe("config", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Counter as Program<Counter>;
+
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+  const [config] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const admin = anchor.web3.Keypair.generate().publicKey;
+
+  // Runs first: the config can only be created once.
+  it("rejects anyone but the deployer", async () => {
+    const stranger = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        stranger.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    try {
+      await program.methods
+        .initializeConfig(admin)
+        .accounts({
+          authority: stranger.publicKey,
+          programData,
+        })
+        .signers([stranger])
+        .rpc();
+      assert.fail("expected NotUpgradeAuthority");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "NotUpgradeAuthority");
+    }
+  });
+
+  it("lets the deployer create it once", async () => {
+    await program.methods
+      .initializeConfig(admin)
+      .accounts({
+        authority: provider.wallet.publicKey,
+        programData,
+      })
+      .rpc();
+
+    const state = await program.account.config.fetch(config);
+    assert.ok(state.admin.equals(admin));
+
+    try {
+      await program.methods
+        .initializeConfig(provider.wallet.publicKey)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          programData,
+        })
+        .rpc();
+      assert.fail("expected the second initialize_config to fail");
+    } catch (err) {
+      assert.include(String(err), "already in use");
+    }
+  });
+});
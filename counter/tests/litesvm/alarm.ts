@@ -0,0 +1,76 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  expectError,
+  fundedKeypair,
+  Harness,
+  initializeCounter,
+  setup,
+} from "./harness";
+
+// Counter.ALARM_TRIGGERED in lib.rs.
+const ALARM_TRIGGERED = 1 << 1;
+
+describe("alarm threshold (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+  });
+
+  function increment() {
+    h.client.expireBlockhash();
+    return h.program.methods.increment().accounts({ counter }).rpc();
+  }
+
+  async function alarmTriggered(): Promise<boolean> {
+    const state = await h.program.account.counter.fetch(counter);
+    return (state.flags & ALARM_TRIGGERED) !== 0;
+  }
+
+  it("sets a sticky flag when the count reaches the threshold", async () => {
+    await h.program.methods
+      .setAlarmThreshold(new anchor.BN(2))
+      .accounts({ counter, authority: h.provider.wallet.publicKey })
+      .rpc();
+
+    await increment();
+    assert.isFalse(await alarmTriggered());
+    await increment();
+    assert.isTrue(await alarmTriggered());
+    await increment();
+    assert.isTrue(await alarmTriggered());
+
+    await h.program.methods
+      .acknowledgeAlarm()
+      .accounts({ counter, authority: h.provider.wallet.publicKey })
+      .rpc();
+    assert.isFalse(await alarmTriggered());
+
+    // Already past the threshold, so it doesn't fire again.
+    await increment();
+    assert.isFalse(await alarmTriggered());
+  });
+
+  it("only the authority can configure or acknowledge alarms", async () => {
+    const stranger = fundedKeypair(h.client);
+    await expectError(
+      h.program.methods
+        .setAlarmThreshold(new anchor.BN(1))
+        .accounts({ counter, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc(),
+      "Unauthorized"
+    );
+    await expectError(
+      h.program.methods
+        .acknowledgeAlarm()
+        .accounts({ counter, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+});
@@ -0,0 +1,50 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { expectError, Harness, setup } from "./harness";
+
+describe("counter bank (litesvm)", () => {
+  let h: Harness;
+  let bank: anchor.web3.Keypair;
+
+  beforeEach(async () => {
+    h = setup();
+    bank = anchor.web3.Keypair.generate();
+    await h.program.methods
+      .initializeBank()
+      .accounts({ bank: bank.publicKey, user: h.provider.wallet.publicKey })
+      .signers([bank])
+      .rpc();
+  });
+
+  async function incrementIndex(index: number) {
+    h.client.expireBlockhash();
+    await h.program.methods
+      .incrementIndex(index)
+      .accounts({ bank: bank.publicKey })
+      .rpc();
+  }
+
+  it("initialize_bank creates 1,024 zeroed slots", async () => {
+    const account = h.client.getAccount(bank.publicKey);
+    assert.equal(account.data.length, 8 + 1024 * 8);
+
+    const state = await h.program.account.counterBank.fetch(bank.publicKey);
+    assert.equal(state.slots.length, 1024);
+    assert.isTrue(state.slots.every((slot) => slot.isZero()));
+  });
+
+  it("increment_index only changes the addressed slot", async () => {
+    await incrementIndex(7);
+    await incrementIndex(7);
+    await incrementIndex(1023);
+
+    const state = await h.program.account.counterBank.fetch(bank.publicKey);
+    assert.equal(state.slots[7].toNumber(), 2);
+    assert.equal(state.slots[1023].toNumber(), 1);
+    assert.equal(state.slots[0].toNumber(), 0);
+  });
+
+  it("increment_index rejects an index past the end", async () => {
+    await expectError(incrementIndex(1024), "IndexOutOfBounds");
+  });
+});
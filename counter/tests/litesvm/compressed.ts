@@ -0,0 +1,146 @@
+import * as anchor from "@coral-xyz/anchor";
+import { FailedTransactionMetadata } from "litesvm";
+import { assert } from "chai";
+import * as fs from "fs";
+import { Harness, setup } from "./harness";
+
+// Sends create_compressed_counter to Light's real programs, dumped from
+// mainnet with "yarn dump:light", to check that the invoke_cpi we build by
+// hand (see compressed.rs) is one Light's system program accepts. The Rust
+// tests (tests/compressed.rs) only check it against a stub.
+//
+// Without a Light prover there's no valid proof to send, so the CPI can't
+// succeed. What this checks is how far it gets: past Light's decoding of
+// our instruction data and its checks of the accounts we pass, to the
+// proof itself.
+const LIGHT = "target/deploy/light";
+const LIGHT_SYSTEM_PROGRAM = new anchor.web3.PublicKey(
+  "SySTEM1eSU2p4BGQfQpimFEWWSC1XDFeun3Nqzz3rT7"
+);
+const ACCOUNT_COMPRESSION_PROGRAM = new anchor.web3.PublicKey(
+  "compr6CUsB5m2jS4Y3831ztGSTnDpnKJTKS95d64XVq"
+);
+const NOOP_PROGRAM = new anchor.web3.PublicKey(
+  "noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV"
+);
+// Light's public trees and queues, in the order the instruction's indexes
+// refer to them: address tree, address queue, state tree, nullifier queue.
+const TREES = [
+  "amt1Ayt45jfbdw5YSo7iz6WZxUmnZsQTYXy82hVwyC2",
+  "aq1S9z4reTSQAdgWHGD2zDaS39sjGrAxbR31vxJ2F4F",
+  "smt1NamzXdq4AMqS2fS2F1i5KTYPZRhoHgWx38d8WsT",
+  "nfq1NvQDJ2GEgnS8zt9prAe8rjjpAW1zFkrvZoBR148",
+].map((address) => new anchor.web3.PublicKey(address));
+
+describe("compressed counters against Light (litesvm)", () => {
+  let h: Harness;
+
+  before(function () {
+    if (!fs.existsSync(`${LIGHT}/${LIGHT_SYSTEM_PROGRAM}.so`)) {
+      this.skip();
+    }
+  });
+
+  function pda(seeds: Buffer[], programId: anchor.web3.PublicKey) {
+    return anchor.web3.PublicKey.findProgramAddressSync(seeds, programId)[0];
+  }
+
+  // An account as "solana account --output json" wrote it.
+  function loadAccount(address: anchor.web3.PublicKey) {
+    const { account } = JSON.parse(
+      fs.readFileSync(`${LIGHT}/${address}.json`, "utf8")
+    );
+    h.client.setAccount(address, {
+      lamports: account.lamports,
+      data: Buffer.from(account.data[0], "base64"),
+      owner: new anchor.web3.PublicKey(account.owner),
+      executable: account.executable,
+    });
+  }
+
+  beforeEach(() => {
+    h = setup();
+    for (const program of [
+      LIGHT_SYSTEM_PROGRAM,
+      ACCOUNT_COMPRESSION_PROGRAM,
+      NOOP_PROGRAM,
+    ]) {
+      h.client.addProgramFromFile(program, `${LIGHT}/${program}.so`);
+    }
+    loadAccount(
+      pda([LIGHT_SYSTEM_PROGRAM.toBuffer()], ACCOUNT_COMPRESSION_PROGRAM)
+    );
+    TREES.forEach(loadAccount);
+  });
+
+  it("Light decodes our invoke_cpi and gets as far as the proof", async () => {
+    const payer = h.provider.wallet.publicKey;
+    const ix = await h.program.methods
+      .createCompressedCounter(
+        { a: Array(32).fill(1), b: Array(64).fill(2), c: Array(32).fill(3) },
+        {
+          addressMerkleTreeAccountIndex: 0,
+          addressQueueAccountIndex: 1,
+          rootIndex: 0,
+        },
+        2,
+        new anchor.BN(0)
+      )
+      .accountsPartial({
+        light: {
+          payer,
+          cpiAuthority: pda([Buffer.from("cpi_authority")], h.program.programId),
+          registeredProgramPda: pda(
+            [LIGHT_SYSTEM_PROGRAM.toBuffer()],
+            ACCOUNT_COMPRESSION_PROGRAM
+          ),
+          accountCompressionAuthority: pda(
+            [Buffer.from("cpi_authority")],
+            LIGHT_SYSTEM_PROGRAM
+          ),
+          lightSystemProgram: LIGHT_SYSTEM_PROGRAM,
+          accountCompressionProgram: ACCOUNT_COMPRESSION_PROGRAM,
+          noopProgram: NOOP_PROGRAM,
+          program: h.program.programId,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      })
+      .remainingAccounts(
+        TREES.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+      )
+      .instruction();
+
+    const tx = new anchor.web3.Transaction().add(ix);
+    tx.recentBlockhash = h.client.latestBlockhash();
+    tx.feePayer = payer;
+    tx.sign(h.provider.wallet.payer);
+    const result = h.client.sendTransaction(tx);
+    assert.instanceOf(result, FailedTransactionMetadata);
+    const logs = (result as FailedTransactionMetadata).meta().logs();
+
+    // Light ran invoke_cpi, and it was Light, not us, that failed.
+    assert.isTrue(
+      logs.some((line) => line.includes("Instruction: InvokeCpi")),
+      logs.join("\n")
+    );
+    assert.isTrue(
+      logs.some((line) =>
+        line.startsWith(`Program ${LIGHT_SYSTEM_PROGRAM} failed`)
+      ),
+      logs.join("\n")
+    );
+    // Not on decoding the data, or on an account we passed.
+    for (const error of [
+      "InstructionDidNotDeserialize",
+      "AccountDiscriminatorMismatch",
+      "AccountNotEnoughKeys",
+      "ConstraintSeeds",
+      "ConstraintAddress",
+    ]) {
+      assert.isFalse(
+        logs.some((line) => line.includes(error)),
+        logs.join("\n")
+      );
+    }
+  });
+});
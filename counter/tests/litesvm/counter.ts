@@ -0,0 +1,130 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { expectError, Harness, setup } from "./harness";
+
+describe("counter (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.Keypair;
+
+  // Every test gets its own SVM, so tests never depend on each other's state.
+  beforeEach(async () => {
+    h = setup();
+    counter = anchor.web3.Keypair.generate();
+  });
+
+  async function initialize() {
+    await h.program.methods
+      .initialize()
+      .accounts({
+        counter: counter.publicKey,
+        user: h.provider.wallet.publicKey,
+      })
+      .signers([counter])
+      .rpc();
+  }
+
+  it("initialize creates a counter set to 0, owned by the user", async () => {
+    await initialize();
+
+    const account = h.client.getAccount(counter.publicKey);
+    assert.isNotNull(account);
+    assert.isTrue(account.owner.equals(h.program.programId));
+    assert.equal(account.data.length, h.program.account.counter.size);
+
+    const state = await h.program.account.counter.fetch(counter.publicKey);
+    assert.equal(state.count.toNumber(), 0);
+    assert.isTrue(state.authority.equals(h.provider.wallet.publicKey));
+    assert.equal(state.flags, 0);
+  });
+
+  it("initialize fails if the counter already exists", async () => {
+    await initialize();
+    h.client.expireBlockhash();
+
+    await expectError(initialize(), "already in use");
+  });
+
+  it("increment adds 1 each call", async () => {
+    await initialize();
+
+    for (let expected = 1; expected <= 3; expected++) {
+      h.client.expireBlockhash();
+      await h.program.methods
+        .increment()
+        .accounts({ counter: counter.publicKey })
+        .rpc();
+
+      const state = await h.program.account.counter.fetch(counter.publicKey);
+      assert.equal(state.count.toNumber(), expected);
+    }
+  });
+
+  it("increment fails with Overflow instead of wrapping at u64::MAX", async () => {
+    // A freshly initialized counter, with only the count changed.
+    await initialize();
+    const state = await h.program.account.counter.fetch(counter.publicKey);
+    const data = await h.program.coder.accounts.encode("counter", {
+      ...state,
+      count: new anchor.BN("18446744073709551615"),
+    });
+    h.client.setAccount(counter.publicKey, {
+      executable: false,
+      owner: h.program.programId,
+      lamports: anchor.web3.LAMPORTS_PER_SOL,
+      data,
+    });
+
+    await expectError(
+      h.program.methods
+        .increment()
+        .accounts({ counter: counter.publicKey })
+        .rpc(),
+      "Overflow"
+    );
+  });
+
+  it("increment rejects an account that was never initialized", async () => {
+    await expectError(
+      h.program.methods
+        .increment()
+        .accounts({ counter: counter.publicKey })
+        .rpc(),
+      "AccountNotInitialized"
+    );
+  });
+
+  it("increment rejects an account with the wrong discriminator", async () => {
+    // Program-owned, right size, but not written by `initialize`.
+    h.client.setAccount(counter.publicKey, {
+      executable: false,
+      owner: h.program.programId,
+      lamports: anchor.web3.LAMPORTS_PER_SOL,
+      data: new Uint8Array(h.program.account.counter.size),
+    });
+
+    await expectError(
+      h.program.methods
+        .increment()
+        .accounts({ counter: counter.publicKey })
+        .rpc(),
+      "AccountDiscriminatorMismatch"
+    );
+  });
+
+  it("increment rejects an account owned by another program", async () => {
+    h.client.setAccount(counter.publicKey, {
+      executable: false,
+      owner: anchor.web3.SystemProgram.programId,
+      lamports: anchor.web3.LAMPORTS_PER_SOL,
+      data: new Uint8Array(h.program.account.counter.size),
+    });
+
+    await expectError(
+      h.program.methods
+        .increment()
+        .accounts({ counter: counter.publicKey })
+        .rpc(),
+      "AccountOwnedByWrongProgram"
+    );
+  });
+});
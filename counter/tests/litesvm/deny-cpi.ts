@@ -0,0 +1,47 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { expectError, Harness, initializeCounter, setup } from "./harness";
+
+describe("deny_cpi and allowed callers (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+    await h.program.methods
+      .setDenyCpi(true)
+      .accounts({ counter, authority: h.provider.wallet.publicKey })
+      .rpc();
+  });
+
+  // Increments through CPI fail with CpiDenied. The only program that
+  // calls increment is the transfer hook, which needs a whole Token-2022
+  // transfer to run, so that side isn't tested here.
+  it("still accepts increments sent by a transaction", async () => {
+    await h.program.methods.increment().accounts({ counter }).rpc();
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.isTrue(state.denyCpi);
+    assert.equal(state.count.toNumber(), 1);
+  });
+
+  it("increment_from_program only accepts CPIs from allowed programs", async () => {
+    // Any program id will do: the point is that nothing calls through it.
+    const partner = anchor.web3.SystemProgram.programId;
+    await h.program.methods.allowProgram(partner).accounts({ guard: { counter } }).rpc();
+    const [allowedCaller] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("allowed_caller"), counter.toBuffer(), partner.toBuffer()],
+      h.program.programId
+    );
+
+    // Sent straight from the transaction, so there's no calling program.
+    await expectError(
+      h.program.methods
+        .incrementFromProgram()
+        .accountsPartial({ counter, allowedCaller })
+        .rpc(),
+      "CallerNotAllowed"
+    );
+  });
+});
@@ -0,0 +1,167 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  expectError,
+  fundedKeypair,
+  Harness,
+  initializeCounter,
+  setup,
+} from "./harness";
+
+describe("increment fees and referrals (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+  let vault: anchor.web3.PublicKey;
+
+  const FEE = 1_000_000;
+  const REFERRAL_BPS = 2_500;
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+    [vault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), counter.toBuffer()],
+      h.program.programId
+    );
+
+    await h.program.methods
+      .setIncrementFee({
+        version: 1,
+        feeLamports: new anchor.BN(FEE),
+        referralBps: REFERRAL_BPS,
+      })
+      .accounts({ guard: { counter } })
+      .rpc();
+  });
+
+  function pda(...seeds: Buffer[]) {
+    return anchor.web3.PublicKey.findProgramAddressSync(seeds, h.program.programId)[0];
+  }
+
+  // The stats bucket for the current hour (see stats.rs).
+  function statsBucket() {
+    const hour = Buffer.alloc(8);
+    hour.writeBigInt64LE(h.client.getClock().unixTimestamp / BigInt(3600));
+    return pda(Buffer.from("stats"), counter.toBuffer(), hour);
+  }
+
+  it("plain increments are refused once a fee is set", async () => {
+    await expectError(
+      h.program.methods.increment().accounts({ counter }).rpc(),
+      "FeeRequired"
+    );
+  });
+
+  it("without a referrer the vault gets the whole fee", async () => {
+    await h.program.methods
+      .incrementPaid(null)
+      .accounts({
+        payment: { counter },
+        referrerStats: null,
+        referralVault: null,
+        feeExemption: null,
+        statsBucket: statsBucket(),
+      })
+      .rpc();
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 1);
+    assert.equal(Number(h.client.getBalance(vault)), FEE);
+
+    const stats = await h.program.account.statsBucket.fetch(statsBucket());
+    assert.equal(stats.increments.toNumber(), 1);
+    assert.equal(stats.uniqueActors, 1);
+    assert.equal(stats.feeRevenue.toNumber(), FEE);
+  });
+
+  it("preview_increment shows the fee before anyone signs", async () => {
+    const referrer = anchor.web3.Keypair.generate().publicKey;
+    const preview = await h.program.methods
+      .previewIncrement(referrer)
+      .accounts({
+        counter,
+        payer: anchor.web3.Keypair.generate().publicKey,
+        feeSchedule: null,
+        feeExemption: null,
+      })
+      .view();
+
+    assert.equal(preview.count.toNumber(), 1);
+    assert.equal(preview.fee.toNumber(), FEE);
+    assert.equal(preview.referrerShare.toNumber(), (FEE * REFERRAL_BPS) / 10_000);
+    // Nothing changed.
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 0);
+  });
+
+  it("a referrer gets their share and their stats", async () => {
+    const referrer = fundedKeypair(h.client);
+    const referralVault = pda(Buffer.from("referral_vault"), referrer.publicKey.toBuffer());
+
+    await h.program.methods
+      .incrementPaid(referrer.publicKey)
+      .accounts({
+        payment: { counter },
+        feeExemption: null,
+        statsBucket: statsBucket(),
+      })
+      .rpc();
+
+    // The share alone is below the rent-exempt minimum, so the payer tops
+    // up the new referral vault to it (see FeePayment::pay).
+    const share = (FEE * REFERRAL_BPS) / 10_000;
+    const rent = Number(h.client.minimumBalanceForRentExemption(BigInt(0)));
+    assert.equal(Number(h.client.getBalance(vault)), FEE - share);
+    assert.equal(Number(h.client.getBalance(referralVault)), rent);
+
+    const stats = await h.program.account.referrerStats.fetch(
+      pda(Buffer.from("referrer_stats"), counter.toBuffer(), referrer.publicKey.toBuffer())
+    );
+    assert.equal(stats.increments.toNumber(), 1);
+    assert.equal(stats.earned.toNumber(), share);
+
+    await h.program.methods
+      .withdrawReferral(new anchor.BN(rent))
+      .accounts({ referrer: referrer.publicKey })
+      .signers([referrer])
+      .rpc();
+    assert.equal(Number(h.client.getBalance(referralVault) ?? 0), 0);
+  });
+
+  it("payers can't refer themselves", async () => {
+    await expectError(
+      h.program.methods
+        .incrementPaid(h.provider.wallet.publicKey)
+        .accounts({
+          payment: { counter },
+          feeExemption: null,
+          statsBucket: statsBucket(),
+        })
+        .rpc(),
+      "SelfReferral"
+    );
+  });
+
+  it("exempt wallets don't pay", async () => {
+    const bot = fundedKeypair(h.client);
+    await h.program.methods
+      .exemptFromFees(bot.publicKey)
+      .accounts({ guard: { counter } })
+      .rpc();
+
+    await h.program.methods
+      .incrementPaid(null)
+      .accounts({
+        payment: { counter, payer: bot.publicKey },
+        referrerStats: null,
+        referralVault: null,
+        statsBucket: statsBucket(),
+      })
+      .signers([bot])
+      .rpc();
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 1);
+    assert.equal(Number(h.client.getBalance(vault) ?? 0), 0);
+  });
+});
@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { LiteSVM } from "litesvm";
+import { fromWorkspace, LiteSVMProvider } from "anchor-litesvm";
+import { Counter } from "../../target/types/counter";
+import IDL from "../../target/idl/counter.json";
+import { assert } from "chai";
+
+// A fresh in-process SVM with the workspace programs loaded from
+// target/deploy. No validator is needed, so each test runs in milliseconds.
+export interface Harness {
+  client: LiteSVM;
+  provider: LiteSVMProvider;
+  program: Program<Counter>;
+}
+
+export function setup(): Harness {
+  const client = fromWorkspace(".");
+  const provider = new LiteSVMProvider(client);
+  anchor.setProvider(provider);
+  const program = new Program<Counter>(IDL as Counter, provider);
+  return { client, provider, program };
+}
+
+// Funds a new keypair so it can pay for transactions and rent.
+export function fundedKeypair(client: LiteSVM, sol = 10): anchor.web3.Keypair {
+  const keypair = anchor.web3.Keypair.generate();
+  client.airdrop(keypair.publicKey, BigInt(sol * anchor.web3.LAMPORTS_PER_SOL));
+  return keypair;
+}
+
+// Asserts that a transaction fails with the given error. `code` is either an
+// Anchor error name (e.g. "AccountNotInitialized") or a substring expected in
+// the program logs for errors raised outside Anchor (e.g. the System Program).
+export async function expectError(promise: Promise<unknown>, code: string) {
+  try {
+    await promise;
+  } catch (err) {
+    if (err instanceof anchor.AnchorError) {
+      assert.equal(err.error.errorCode.code, code);
+      return;
+    }
+    const logs: string[] = (err as any).logs ?? [];
+    assert.isTrue(
+      logs.some((line) => line.includes(code)) || String(err).includes(code),
+      `expected error "${code}", got: ${err}`
+    );
+    return;
+  }
+  assert.fail(`expected the transaction to fail with "${code}"`);
+}
+
+// Creates a counter (at 0) owned by the provider wallet and returns its
+// address.
+export async function initializeCounter(
+  h: Harness
+): Promise<anchor.web3.PublicKey> {
+  const counter = anchor.web3.Keypair.generate();
+  await h.program.methods
+    .initialize()
+    .accounts({
+      counter: counter.publicKey,
+      user: h.provider.wallet.publicKey,
+    })
+    .signers([counter])
+    .rpc();
+  return counter.publicKey;
+}
@@ -0,0 +1,51 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { expectError, Harness, initializeCounter, setup } from "./harness";
+
+describe("idempotent increments (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+  });
+
+  function incrementWithKey(key: number[]) {
+    h.client.expireBlockhash();
+    return h.program.methods
+      .incrementWithKey(key)
+      .accounts({ counter, payer: h.provider.wallet.publicKey })
+      .rpc();
+  }
+
+  const key = (byte: number) => new Array(16).fill(byte);
+
+  it("records a receipt for the key", async () => {
+    await incrementWithKey(key(1));
+
+    const [receipt] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt"), counter.toBuffer(), Buffer.from(key(1))],
+      h.program.programId
+    );
+    const state = await h.program.account.receipt.fetch(receipt);
+    assert.isTrue(state.counter.equals(counter));
+    assert.equal(state.count.toNumber(), 1);
+  });
+
+  it("rejects a retry with the same key without counting twice", async () => {
+    await incrementWithKey(key(1));
+    await expectError(incrementWithKey(key(1)), "DuplicateIdempotencyKey");
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 1);
+  });
+
+  it("counts different keys separately", async () => {
+    await incrementWithKey(key(1));
+    await incrementWithKey(key(2));
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 2);
+  });
+});
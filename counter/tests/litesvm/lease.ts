@@ -0,0 +1,113 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  expectError,
+  fundedKeypair,
+  Harness,
+  initializeCounter,
+  setup,
+} from "./harness";
+
+describe("authority leases (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+  let renter: anchor.web3.Keypair;
+  let vault: anchor.web3.PublicKey;
+
+  const PRICE = anchor.web3.LAMPORTS_PER_SOL;
+  const DURATION = 3600;
+
+  // lease_authority's arguments, at version 1 (see args.rs).
+  function leaseArgs(to: anchor.web3.PublicKey) {
+    return { version: 1, to, duration: new anchor.BN(DURATION), price: new anchor.BN(PRICE) };
+  }
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+    renter = fundedKeypair(h.client);
+    [vault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), counter.toBuffer()],
+      h.program.programId
+    );
+
+    await h.program.methods
+      .leaseAuthority(leaseArgs(renter.publicKey))
+      .accounts({ counter, renter: renter.publicKey })
+      .signers([renter])
+      .rpc();
+  });
+
+  function setThreshold(signer: anchor.web3.Keypair) {
+    h.client.expireBlockhash();
+    return h.program.methods
+      .setAlarmThreshold(new anchor.BN(5))
+      .accounts({ counter, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  // Moves the clock "seconds" forward.
+  function advanceClock(seconds: number) {
+    const clock = h.client.getClock();
+    clock.unixTimestamp += BigInt(seconds);
+    h.client.setClock(clock);
+  }
+
+  it("the renter pays into the vault and controls the settings", async () => {
+    assert.equal(Number(h.client.getBalance(vault)), PRICE);
+
+    await setThreshold(renter);
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.alarmThreshold.toNumber(), 5);
+
+    await expectError(setThreshold(h.provider.wallet.payer), "Unauthorized");
+  });
+
+  it("control goes back to the owner when the lease expires", async () => {
+    advanceClock(DURATION);
+
+    await setThreshold(h.provider.wallet.payer);
+    await expectError(setThreshold(renter), "Unauthorized");
+  });
+
+  it("a counter can't be leased twice at once", async () => {
+    const other = fundedKeypair(h.client);
+    h.client.expireBlockhash();
+    await expectError(
+      h.program.methods
+        .leaseAuthority(leaseArgs(other.publicKey))
+        .accounts({ counter, renter: other.publicKey })
+        .signers([other])
+        .rpc(),
+      "LeaseActive"
+    );
+  });
+
+  it("only the owner can withdraw from the vault", async () => {
+    await expectError(
+      h.program.methods
+        .withdrawVault(new anchor.BN(PRICE))
+        .accountsPartial({ guard: { counter, authority: renter.publicKey } })
+        .signers([renter])
+        .rpc(),
+      "Unauthorized"
+    );
+
+    await h.program.methods
+      .withdrawVault(new anchor.BN(PRICE))
+      .accounts({ guard: { counter } })
+      .rpc();
+    assert.equal(Number(h.client.getBalance(vault) ?? 0), 0);
+
+    // On record in the counter's access log (see access_log.rs).
+    const [accessLog] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("access_log"), counter.toBuffer()],
+      h.program.programId
+    );
+    const log = await h.program.account.accessLog.fetch(accessLog);
+    const last = log.entries[(log.total.toNumber() - 1) % log.entries.length];
+    assert.equal(last.action, 30); // AdminAction::WithdrawVault
+    assert.isTrue(last.actor.equals(h.provider.wallet.publicKey));
+  });
+});
@@ -0,0 +1,84 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { expectError, Harness, setup } from "./harness";
+
+describe("migrate_counter (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+
+  // A counter from before "finalized", "deny_cpi", "lifetime_count",
+  // "role_weighted", "tags", "maxCount", "overflow", the TWAC's fields,
+  // "deltaFeed", "frozen", "checksum", "group" and "giftedCount" were
+  // added: the current encoding, cut back to the size it had once
+  // "eventSeq" was.
+  beforeEach(async () => {
+    h = setup();
+    counter = anchor.web3.Keypair.generate().publicKey;
+    const data = await h.program.coder.accounts.encode("counter", {
+      count: new anchor.BN(41),
+      authority: h.provider.wallet.publicKey,
+      flags: 0,
+      lastIncrementSlot: new anchor.BN(0),
+      alarmThreshold: new anchor.BN(0),
+      eventSeq: new anchor.BN(0),
+      finalized: false,
+      denyCpi: false,
+      lifetimeCount: new anchor.BN(0),
+      roleWeighted: false,
+      tags: Array(4).fill(Array(16).fill(0)),
+      maxCount: new anchor.BN(0),
+      overflow: anchor.web3.PublicKey.default,
+      cumulativeCount: new anchor.BN(0),
+      twacSlot: new anchor.BN(0),
+      deltaFeed: {
+        baseSlot: new anchor.BN(0),
+        slot: new anchor.BN(0),
+        recorded: new anchor.BN(0),
+        recentDeltas: Array(16).fill({ slotOffset: 0, delta: 0 }),
+      },
+      frozen: false,
+      checksum: new anchor.BN(0),
+      group: anchor.web3.PublicKey.default,
+      giftedCount: new anchor.BN(0),
+    });
+    h.client.setAccount(counter, {
+      executable: false,
+      owner: h.program.programId,
+      lamports: Number(h.client.minimumBalanceForRentExemption(BigInt(8 + 65))),
+      data: data.subarray(0, 8 + 65),
+    });
+  });
+
+  it("old counters can't be used until they're migrated", async () => {
+    await expectError(
+      h.program.methods.increment().accounts({ counter }).rpc(),
+      "AccountDidNotDeserialize"
+    );
+
+    await h.program.methods.migrateCounter().accounts({ counter }).rpc();
+    await h.program.methods.increment().accounts({ counter }).rpc();
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 42);
+    // Backfilled from the count, then incremented with it.
+    assert.equal(state.lifetimeCount.toNumber(), 42);
+    assert.isFalse(state.finalized);
+    assert.isFalse(state.denyCpi);
+    assert.isFalse(state.roleWeighted);
+    assert.equal(
+      h.client.getAccount(counter)!.data.length,
+      h.program.account.counter.size
+    );
+    // Written by the program, so its checksum matches.
+    await h.program.methods.verifyChecksum().accounts({ counter }).rpc();
+  });
+
+  it("refuses counters that are already current", async () => {
+    await h.program.methods.migrateCounter().accounts({ counter }).rpc();
+    h.client.expireBlockhash();
+    await expectError(
+      h.program.methods.migrateCounter().accounts({ counter }).rpc(),
+      "NotMigratable"
+    );
+  });
+});
@@ -0,0 +1,121 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { Multisig } from "../../target/types/multisig";
+import MultisigIDL from "../../target/idl/multisig.json";
+import { expectError, fundedKeypair, Harness, setup } from "./harness";
+
+// A counter whose authority is another program's PDA: the multisig's vault
+// (see programs/multisig). initialize_funded makes the vault the authority,
+// and the multisig signs for it through invoke_signed once enough members
+// approve (see funded.rs for what the PDA needs).
+describe("a multisig's vault as a counter's authority (litesvm)", () => {
+  let h: Harness;
+  let multisigProgram: Program<Multisig>;
+  let member: anchor.web3.Keypair;
+  let multisig: anchor.web3.PublicKey;
+  let vault: anchor.web3.PublicKey;
+  let counter: anchor.web3.PublicKey;
+
+  function pda(seeds: Buffer[]): anchor.web3.PublicKey {
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      seeds,
+      multisigProgram.programId
+    )[0];
+  }
+
+  function proposal(index: number): anchor.web3.PublicKey {
+    return pda([
+      Buffer.from("proposal"),
+      multisig.toBuffer(),
+      new anchor.BN(index).toArrayLike(Buffer, "le", 8),
+    ]);
+  }
+
+  beforeEach(async () => {
+    h = setup();
+    multisigProgram = new Program<Multisig>(MultisigIDL as Multisig, h.provider);
+    member = fundedKeypair(h.client);
+    const creator = h.provider.wallet.publicKey;
+
+    const id = new anchor.BN(0);
+    multisig = pda([
+      Buffer.from("multisig"),
+      creator.toBuffer(),
+      id.toArrayLike(Buffer, "le", 8),
+    ]);
+    vault = pda([Buffer.from("vault"), multisig.toBuffer()]);
+    await multisigProgram.methods
+      .createMultisig(id, [creator, member.publicKey], 2)
+      .accountsPartial({ multisig, vault, creator })
+      .rpc();
+    // Pays for the counter's access log on its first settings change.
+    h.client.airdrop(vault, BigInt(anchor.web3.LAMPORTS_PER_SOL));
+
+    const keypair = anchor.web3.Keypair.generate();
+    await h.program.methods
+      .initializeFunded()
+      .accounts({ counter: keypair.publicKey, authority: vault, payer: creator })
+      .signers([keypair])
+      .rpc();
+    counter = keypair.publicKey;
+  });
+
+  it("changes settings only through an approved proposal", async () => {
+    const state = await h.program.account.counter.fetch(counter);
+    assert.isTrue(state.authority.equals(vault));
+
+    const ix = await h.program.methods
+      .setAlarmThreshold(new anchor.BN(10))
+      .accounts({ counter, authority: vault })
+      .instruction();
+    await multisigProgram.methods
+      .propose({
+        programId: ix.programId,
+        accounts: ix.keys,
+        data: ix.data,
+      })
+      .accountsPartial({
+        multisig,
+        proposal: proposal(0),
+        proposer: h.provider.wallet.publicKey,
+      })
+      .rpc();
+
+    // One approval of two: not yet.
+    const execute = () => {
+      h.client.expireBlockhash();
+      return multisigProgram.methods
+        .execute()
+        .accountsPartial({ multisig, proposal: proposal(0) })
+        .remainingAccounts([
+          // Only the multisig can make the vault a signer.
+          ...ix.keys.map((key) => ({ ...key, isSigner: false })),
+          { pubkey: ix.programId, isSigner: false, isWritable: false },
+        ])
+        .rpc();
+    };
+    await expectError(execute(), "NotEnoughApprovals");
+
+    await multisigProgram.methods
+      .approve()
+      .accountsPartial({ multisig, proposal: proposal(0), member: member.publicKey })
+      .signers([member])
+      .rpc();
+    await execute();
+
+    const after = await h.program.account.counter.fetch(counter);
+    assert.equal(after.alarmThreshold.toNumber(), 10);
+  });
+
+  it("members can't act as the authority themselves", async () => {
+    await expectError(
+      h.program.methods
+        .setAlarmThreshold(new anchor.BN(10))
+        .accounts({ counter, authority: member.publicKey })
+        .signers([member])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+});
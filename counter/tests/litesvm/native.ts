@@ -0,0 +1,92 @@
+import * as anchor from "@coral-xyz/anchor";
+import { FailedTransactionMetadata } from "litesvm";
+import { assert } from "chai";
+import * as fs from "fs";
+import { Harness, setup } from "./harness";
+
+// Runs the same instructions against the Anchor program and the hand-written
+// native one (programs/counter-native) and reports the compute units each
+// used. Build the native program first with "yarn build:native".
+const NATIVE_SO = "target/deploy/counter_native.so";
+const NATIVE_ID = new anchor.web3.PublicKey(
+  "3Toq9X9ME3pAGaAPdNFcspH4fnGXivgqpsyzztjgTr97"
+);
+
+describe("anchor vs native compute units (litesvm)", () => {
+  let h: Harness;
+  let payer: anchor.web3.Keypair;
+
+  before(function () {
+    if (!fs.existsSync(NATIVE_SO)) {
+      this.skip();
+    }
+  });
+
+  beforeEach(() => {
+    h = setup();
+    h.client.addProgramFromFile(NATIVE_ID, NATIVE_SO);
+    payer = h.provider.wallet.payer;
+  });
+
+  // Sends one instruction and returns the compute units it consumed.
+  function send(
+    ix: anchor.web3.TransactionInstruction,
+    signers: anchor.web3.Keypair[] = []
+  ): bigint {
+    h.client.expireBlockhash();
+    const tx = new anchor.web3.Transaction().add(ix);
+    tx.recentBlockhash = h.client.latestBlockhash();
+    tx.feePayer = payer.publicKey;
+    tx.sign(payer, ...signers);
+
+    const result = h.client.sendTransaction(tx);
+    if (result instanceof FailedTransactionMetadata) {
+      assert.fail(`${result.err()}\n${result.meta().logs().join("\n")}`);
+    }
+    return result.computeUnitsConsumed();
+  }
+
+  // Builds the instructions with the Anchor client, pointed at `programId`.
+  // This works for both programs because they share discriminators.
+  async function runCounter(programId: anchor.web3.PublicKey) {
+    const counter = anchor.web3.Keypair.generate();
+
+    const init = await h.program.methods
+      .initialize()
+      .accounts({ counter: counter.publicKey, user: payer.publicKey })
+      .instruction();
+    init.programId = programId;
+    const initializeCu = send(init, [counter]);
+
+    const inc = await h.program.methods
+      .increment()
+      .accounts({ counter: counter.publicKey })
+      .instruction();
+    inc.programId = programId;
+    send(inc);
+    const incrementCu = send(inc);
+
+    const data = h.client.getAccount(counter.publicKey).data;
+    return { initializeCu, incrementCu, data };
+  }
+
+  it("native program matches Anchor's behaviour with fewer compute units", async () => {
+    const anchorRun = await runCounter(h.program.programId);
+    const nativeRun = await runCounter(NATIVE_ID);
+
+    console.table({
+      initialize: {
+        anchor: Number(anchorRun.initializeCu),
+        native: Number(nativeRun.initializeCu),
+      },
+      increment: {
+        anchor: Number(anchorRun.incrementCu),
+        native: Number(nativeRun.incrementCu),
+      },
+    });
+
+    // Same bytes on-chain: discriminator + count of 2.
+    assert.deepEqual(nativeRun.data, anchorRun.data);
+    assert.isTrue(nativeRun.incrementCu < anchorRun.incrementCu);
+  });
+});
@@ -0,0 +1,67 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  expectError,
+  fundedKeypair,
+  Harness,
+  initializeCounter,
+  setup,
+} from "./harness";
+
+describe("once-per-slot mode (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+    await h.program.methods
+      .setOncePerSlot(true)
+      .accounts({ counter, authority: h.provider.wallet.publicKey })
+      .rpc();
+  });
+
+  function increment() {
+    h.client.expireBlockhash();
+    return h.program.methods.increment().accounts({ counter }).rpc();
+  }
+
+  it("allows one increment per slot", async () => {
+    h.client.warpToSlot(BigInt(100));
+    await increment();
+    await expectError(increment(), "AlreadyCountedThisSlot");
+
+    h.client.warpToSlot(BigInt(101));
+    await increment();
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 2);
+    assert.equal(state.lastIncrementSlot.toNumber(), 101);
+  });
+
+  it("can be turned off again", async () => {
+    await h.program.methods
+      .setOncePerSlot(false)
+      .accounts({ counter, authority: h.provider.wallet.publicKey })
+      .rpc();
+
+    h.client.warpToSlot(BigInt(100));
+    await increment();
+    await increment();
+
+    const state = await h.program.account.counter.fetch(counter);
+    assert.equal(state.count.toNumber(), 2);
+  });
+
+  it("only the authority can change the mode", async () => {
+    const stranger = fundedKeypair(h.client);
+    await expectError(
+      h.program.methods
+        .setOncePerSlot(false)
+        .accounts({ counter, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+});
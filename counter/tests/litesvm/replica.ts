@@ -0,0 +1,81 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { expectError, Harness, initializeCounter, setup } from "./harness";
+
+describe("replicas (litesvm)", () => {
+  let h: Harness;
+  let counter: anchor.web3.PublicKey;
+
+  beforeEach(async () => {
+    h = setup();
+    counter = await initializeCounter(h);
+  });
+
+  // Seeds match InitializeReplica in replica.rs.
+  function replicaAddress(root: anchor.web3.PublicKey, id: number) {
+    const idBytes = Buffer.alloc(2);
+    idBytes.writeUInt16LE(id);
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("replica"), root.toBuffer(), idBytes],
+      h.program.programId
+    )[0];
+  }
+
+  async function initializeReplica(root: anchor.web3.PublicKey, id: number) {
+    await h.program.methods
+      .initializeReplica(id)
+      .accounts({ counter: root })
+      .rpc();
+    return replicaAddress(root, id);
+  }
+
+  async function incrementReplica(replica: anchor.web3.PublicKey, times = 1) {
+    for (let i = 0; i < times; i++) {
+      h.client.expireBlockhash();
+      await h.program.methods.incrementReplica().accounts({ replica }).rpc();
+    }
+  }
+
+  function merge(replicas: anchor.web3.PublicKey[]) {
+    h.client.expireBlockhash();
+    return h.program.methods
+      .mergeReplicas()
+      .accounts({ counter })
+      .remainingAccounts(
+        replicas.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+      )
+      .rpc();
+  }
+
+  async function count() {
+    const state = await h.program.account.counter.fetch(counter);
+    return state.count.toNumber();
+  }
+
+  it("merges whichever replicas are available, each count once", async () => {
+    const a = await initializeReplica(counter, 0);
+    const b = await initializeReplica(counter, 1);
+    await incrementReplica(a, 3);
+    await incrementReplica(b, 2);
+
+    // Only "a" is reachable at first.
+    await merge([a]);
+    assert.equal(await count(), 3);
+
+    // Merging "a" again (even twice in one go) adds nothing new.
+    await incrementReplica(a);
+    await merge([a, b, a]);
+    assert.equal(await count(), 6);
+
+    await merge([a, b]);
+    assert.equal(await count(), 6);
+  });
+
+  it("rejects replicas of another counter", async () => {
+    const other = await initializeCounter(h);
+    const replica = await initializeReplica(other, 0);
+    await incrementReplica(replica);
+
+    await expectError(merge([replica]), "ReplicaRootMismatch");
+  });
+});
@@ -0,0 +1,39 @@
+import { assert } from "chai";
+import {
+  checkProgramVersion,
+  expectedVersion,
+  isCompatible,
+} from "../../app/version";
+import { Harness, setup } from "./harness";
+
+describe("version (litesvm)", () => {
+  let h: Harness;
+
+  beforeEach(() => {
+    h = setup();
+  });
+
+  it("get_version returns the version published in the IDL", async () => {
+    const deployed = await h.program.methods.getVersion().view();
+    assert.deepEqual(deployed, expectedVersion(h.program));
+  });
+
+  it("checkProgramVersion stays quiet when versions match", async () => {
+    const warnings: string[] = [];
+    await checkProgramVersion(h.program, (m) => warnings.push(m));
+    assert.isEmpty(warnings);
+  });
+
+  it("isCompatible follows semver", () => {
+    const v = (major: number, minor: number, patch = 0) => ({
+      major,
+      minor,
+      patch,
+    });
+    assert.isTrue(isCompatible(v(1, 3, 9), v(1, 2)));
+    assert.isFalse(isCompatible(v(1, 1), v(1, 2)));
+    assert.isFalse(isCompatible(v(2, 0), v(1, 2)));
+    assert.isTrue(isCompatible(v(0, 1, 4), v(0, 1)));
+    assert.isFalse(isCompatible(v(0, 2), v(0, 1)));
+  });
+});
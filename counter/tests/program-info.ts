@@ -0,0 +1,66 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Counter } from "../target/types/counter";
+import { assert } from "chai";
+
+// Needs a real upgradeable deployment (Anchor.toml: [test] upgradeable =
+// true), where the provider wallet is the upgrade authority.
+describe("program info", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Counter as Program<Counter>;
+
+  // Upgradeable programs keep their settings in a ProgramData account whose
+  // address is derived from the program id.
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+  const [programInfo] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("program_info")],
+    program.programId
+  );
+
+  const commit = Array.from(
+    Buffer.from("0123456789abcdef0123456789abcdef01234567", "hex")
+  );
+
+  it("lets the upgrade authority record the deployed commit", async () => {
+    await program.methods
+      .setProgramInfo(commit)
+      .accounts({
+        authority: provider.wallet.publicKey,
+        programData,
+      })
+      .rpc();
+
+    const info = await program.account.programInfo.fetch(programInfo);
+    assert.equal(info.version, "0.1.0");
+    assert.deepEqual(info.commit, commit);
+  });
+
+  it("rejects anyone else", async () => {
+    const stranger = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        stranger.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    try {
+      await program.methods
+        .setProgramInfo(commit)
+        .accounts({
+          authority: stranger.publicKey,
+          programData,
+        })
+        .signers([stranger])
+        .rpc();
+      assert.fail("expected NotUpgradeAuthority");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "NotUpgradeAuthority");
+    }
+  });
+});